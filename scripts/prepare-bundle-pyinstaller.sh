@@ -61,12 +61,14 @@ pyinstaller \
     --hidden-import=auto_browser \
     --hidden-import=auto_browser.web_ui \
     --hidden-import=auto_browser.config_manager \
+    --hidden-import=auto_browser.control_channel \
     --hidden-import=auto_browser.automation_server \
     --collect-all nova_act \
     --collect-all playwright \
     --collect-all flask \
     --collect-all flask_cors \
     --add-data "src:src" \
+    --add-data "src/auto_browser/control_protocol.json:auto_browser" \
     server.py
 
 # Move the dist/server directory to bundle-bin
@@ -106,6 +108,12 @@ else
     exit 1
 fi
 
+# Record the binary checksum so the app's self-test can verify the bundle
+echo ""
+echo "Writing server checksum..."
+(cd bundle-bin && shasum -a 256 server > server.sha256)
+cat bundle-bin/server.sha256
+
 # Clean up build artifacts
 echo ""
 echo "Cleaning up build artifacts..."
@@ -14,7 +14,8 @@ import logging
 # Add src to path
 sys.path.insert(0, os.path.join(os.path.dirname(__file__), 'src'))
 
-from auto_browser.web_ui import app, automation_server
+from auto_browser import SAFE_MODE, control_channel
+from auto_browser.web_ui import app, automation_server, CONTROL_HANDLERS
 from auto_browser.config_manager import ConfigManager
 
 
@@ -71,6 +72,16 @@ def main():
                        help='Enable Flask debug mode')
     args = parser.parse_args()
 
+    # Typed control channel to the desktop shell (lifecycle, config, job events)
+    control_socket = os.getenv('NOVA_CONTROL_SOCKET')
+    if os.getenv('NOVA_CONTROL_STDIO') == '1':
+        control_channel.serve_stdio(CONTROL_HANDLERS)
+    elif control_socket:
+        try:
+            control_channel.serve(control_socket, CONTROL_HANDLERS)
+        except OSError as e:
+            print(f"[CONTROL] Failed to open control channel: {e}")
+
     # Setup logging based on verbose flag
     if args.verbose:
         logging.basicConfig(
@@ -101,9 +112,12 @@ def main():
         print(f"[VERBOSE] API key present: ***{api_key[-4:]}")
         print(f"[VERBOSE] API key length: {len(api_key)} characters")
 
+    if SAFE_MODE:
+        print("[SAFE MODE] Ignoring saved configuration other than the API key")
+
     # Get agent ID
     agent_id = os.getenv('ELEVENLABS_AGENT_ID')
-    if not agent_id:
+    if not agent_id and not SAFE_MODE:
         agent_id = ConfigManager.get_agent_id()
         if agent_id:
             os.environ['ELEVENLABS_AGENT_ID'] = agent_id
@@ -167,6 +181,8 @@ def main():
         print("[VERBOSE] Flask threading mode: DISABLED (single-threaded for Selenium compatibility)")
     print("="*80 + "\n")
 
+    control_channel.notify('ready', {'protocol_version': control_channel.PROTOCOL_VERSION, 'port': args.port})
+
     app.run(
         host='127.0.0.1',
         port=args.port,
@@ -1,3 +1,28 @@
+use std::path::Path;
+
 fn main() {
+  generate_control_protocol();
   tauri_build::build()
 }
+
+/// Generates method name constants from the control channel schema shared with the
+/// Python server, so renaming a method there breaks this build instead of the channel.
+fn generate_control_protocol() {
+  let schema_path = Path::new("../src/auto_browser/control_protocol.json");
+  println!("cargo:rerun-if-changed={}", schema_path.display());
+
+  let schema: serde_json::Value = serde_json::from_str(
+    &std::fs::read_to_string(schema_path).expect("failed to read control_protocol.json"),
+  )
+  .expect("control_protocol.json is not valid JSON");
+
+  let mut code = String::from("// Generated by build.rs from src/auto_browser/control_protocol.json\n");
+  code += &format!("pub const VERSION: u64 = {};\n", schema["version"].as_u64().expect("schema version"));
+  for name in schema["methods"].as_object().expect("schema methods").keys() {
+    let ident = name.replace('.', "_").to_uppercase();
+    code += &format!("pub const {}: &str = {:?};\n", ident, name);
+  }
+
+  let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("control_protocol.rs");
+  std::fs::write(out, code).expect("failed to write control protocol constants");
+}
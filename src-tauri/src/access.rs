@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::history::{self, AccessAuditEntry, History};
+use crate::launch::is_viewer;
+use crate::security;
+use crate::settings::SettingsStore;
+
+/// Audit entries returned when no limit is given.
+const DEFAULT_LIMIT: usize = 100;
+
+/// Who is using the app, set per settings profile. Each role has the capabilities of the
+/// ones before it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Reviews runs and results.
+    Viewer,
+    /// Runs jobs and edits recipes.
+    Operator,
+    /// Everything, including settings, credentials and deleting data.
+    #[default]
+    Admin,
+}
+
+/// What a command needs the current role to allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Capability {
+    /// Reading runs, recipes, schedules and status; exporting and printing results;
+    /// window chrome.
+    Review,
+    /// Running and managing jobs, recipes, downloads and servers.
+    Operate,
+    /// Settings, credentials and secrets, licensing, installing recipes and deleting data.
+    Administer,
+}
+
+impl Capability {
+    /// The least role that has it.
+    pub fn role(self) -> Role {
+        match self {
+            Capability::Review => Role::Viewer,
+            Capability::Operate => Role::Operator,
+            Capability::Administer => Role::Admin,
+        }
+    }
+}
+
+/// The capability every command needs. Commands missing here need `Administer`, so a new
+/// command is closed to everyone else until it is listed.
+const COMMANDS: &[(&str, Capability)] = &[
+    ("list_alerts", Capability::Review),
+    ("test_assertion", Capability::Review),
+    ("get_recipe_analytics", Capability::Review),
+    ("archive_run", Capability::Operate),
+    ("create_backup", Capability::Administer),
+    ("restore_backup", Capability::Administer),
+    ("cancel_batch", Capability::Operate),
+    ("enqueue_batch", Capability::Operate),
+    ("get_batch_status", Capability::Review),
+    ("list_batch_rows", Capability::Review),
+    ("validate_blackout_window", Capability::Review),
+    ("gc_artifacts", Capability::Administer),
+    ("get_server_layout", Capability::Review),
+    ("export_schedules_ics", Capability::Review),
+    ("get_captcha_usage", Capability::Review),
+    ("list_captcha_solves", Capability::Review),
+    ("set_captcha_api_key", Capability::Administer),
+    ("list_monitor_changes", Capability::Review),
+    ("set_server_log_level", Capability::Operate),
+    ("set_server_verbose", Capability::Operate),
+    ("get_control_api_token", Capability::Administer),
+    ("reset_control_api_token", Capability::Administer),
+    ("apply_cookie_jar", Capability::Operate),
+    ("capture_cookie_jar", Capability::Administer),
+    ("delete_cookie_jar", Capability::Administer),
+    ("export_cookies", Capability::Administer),
+    ("import_cookies", Capability::Administer),
+    ("list_browser_profiles", Capability::Review),
+    ("list_cookie_jars", Capability::Review),
+    ("list_credential_access", Capability::Administer),
+    ("export_run_to_database", Capability::Operate),
+    ("set_database_credentials", Capability::Administer),
+    ("test_connection", Capability::Administer),
+    ("get_debug_targets", Capability::Review),
+    ("open_in_devtools", Capability::Administer),
+    ("list_breakpoints", Capability::Review),
+    ("pause_job", Capability::Operate),
+    ("resume_job", Capability::Operate),
+    ("set_breakpoint", Capability::Operate),
+    ("step_job", Capability::Operate),
+    ("get_server_environment", Capability::Review),
+    ("run_self_test", Capability::Operate),
+    ("preview_digest", Capability::Review),
+    ("send_digest", Capability::Operate),
+    ("get_disk_space", Capability::Review),
+    ("purge_artifacts", Capability::Administer),
+    ("capture_screenshot", Capability::Operate),
+    ("get_display_capabilities", Capability::Review),
+    ("list_displays", Capability::Review),
+    ("move_window_to_display", Capability::Operate),
+    ("cancel_download", Capability::Operate),
+    ("list_downloads", Capability::Review),
+    ("open_containing_folder", Capability::Operate),
+    ("pause_download", Capability::Operate),
+    ("resume_download", Capability::Operate),
+    ("start_download", Capability::Operate),
+    ("get_job_concurrency", Capability::Review),
+    ("set_job_concurrency", Capability::Administer),
+    ("get_sync_status", Capability::Review),
+    ("set_sync_token", Capability::Administer),
+    ("sync_now", Capability::Operate),
+    ("get_gpu_info", Capability::Review),
+    ("get_idle_state", Capability::Review),
+    ("get_dnd_state", Capability::Review),
+    ("send_report", Capability::Operate),
+    ("set_smtp_password", Capability::Administer),
+    ("report_job_event", Capability::Operate),
+    ("export_run", Capability::Review),
+    ("get_failure_context", Capability::Review),
+    ("export_run_html", Capability::Review),
+    ("export_har", Capability::Review),
+    ("get_feature_flags", Capability::Review),
+    ("is_enabled", Capability::Review),
+    ("list_runs", Capability::Review),
+    ("list_instances", Capability::Review),
+    ("spawn_instance", Capability::Operate),
+    ("stop_instance", Capability::Operate),
+    ("test_webhook", Capability::Operate),
+    ("list_interactions", Capability::Review),
+    ("respond_to_interaction", Capability::Operate),
+    ("set_kiosk_mode", Capability::Administer),
+    ("get_launch_options", Capability::Review),
+    ("activate", Capability::Administer),
+    ("deactivate", Capability::Administer),
+    ("get_license_status", Capability::Review),
+    ("open_live_view", Capability::Review),
+    ("send_live_view_input", Capability::Operate),
+    ("set_live_view_control", Capability::Operate),
+    ("start_live_view", Capability::Review),
+    ("toggle_monitor_window", Capability::Review),
+    ("preview_normalization", Capability::Review),
+    ("ocr_artifact", Capability::Review),
+    ("delete_pipeline", Capability::Operate),
+    ("get_pipeline_status", Capability::Review),
+    ("save_pipeline", Capability::Operate),
+    ("start_pipeline", Capability::Operate),
+    ("list_plugins", Capability::Review),
+    ("run_plugin_command", Capability::Operate),
+    ("get_domain_stats", Capability::Review),
+    ("get_power_state", Capability::Review),
+    ("export_page_pdf", Capability::Review),
+    ("print_current_page", Capability::Review),
+    ("export_profile", Capability::Administer),
+    ("import_profile", Capability::Administer),
+    // Requests that change anything need `Operate` as well; see `proxy::api_request`
+    ("api_request", Capability::Review),
+    ("purge_all_user_data", Capability::Administer),
+    ("request_purge_token", Capability::Administer),
+    ("cancel_queued_job", Capability::Operate),
+    ("enqueue_job", Capability::Operate),
+    ("list_queue", Capability::Review),
+    ("pause_queue", Capability::Operate),
+    ("reorder_queue", Capability::Operate),
+    ("resume_queue", Capability::Operate),
+    ("add_recent_task", Capability::Operate),
+    ("clear_recent_tasks", Capability::Operate),
+    ("list_recent_tasks", Capability::Review),
+    ("install_recipe_from_url", Capability::Administer),
+    ("get_recipe_diagnostics", Capability::Review),
+    ("validate_recipe", Capability::Review),
+    ("delete_recipe", Capability::Operate),
+    ("duplicate_recipe", Capability::Operate),
+    ("get_recipe", Capability::Review),
+    ("list_recipe_revisions", Capability::Review),
+    ("list_recipes", Capability::Review),
+    ("rename_recipe", Capability::Operate),
+    ("restore_recipe_revision", Capability::Operate),
+    ("save_recipe", Capability::Operate),
+    ("get_recording_status", Capability::Review),
+    ("pause_recording", Capability::Operate),
+    ("start_recording", Capability::Operate),
+    ("stop_recording", Capability::Operate),
+    ("delete_recordings_older_than", Capability::Administer),
+    ("list_recordings", Capability::Review),
+    ("list_har_captures", Capability::Review),
+    ("redact_regions", Capability::Operate),
+    ("generate_run_report", Capability::Operate),
+    ("diff_runs", Capability::Review),
+    ("get_upcoming_runs", Capability::Review),
+    ("list_schedule_audit", Capability::Review),
+    ("list_domain_overrides", Capability::Review),
+    ("search_runs", Capability::Review),
+    ("authenticate_user", Capability::Review),
+    ("close_selector_picker", Capability::Operate),
+    ("open_selector_picker", Capability::Operate),
+    ("restore_session", Capability::Review),
+    ("save_session", Capability::Review),
+    ("get_settings", Capability::Review),
+    ("update_settings", Capability::Administer),
+    ("append_run_to_sheet", Capability::Operate),
+    ("connect_google_account", Capability::Administer),
+    ("disconnect_google_account", Capability::Administer),
+    ("google_account_status", Capability::Review),
+    ("set_google_oauth_client", Capability::Administer),
+    ("export_run_to_sqlite", Capability::Operate),
+    ("preview_sqlite_export", Capability::Review),
+    ("get_server_status", Capability::Review),
+    ("request_server_restart", Capability::Operate),
+    ("set_badge", Capability::Review),
+    ("set_progress", Capability::Review),
+    ("run_task_file", Capability::Operate),
+    ("get_thumbnail", Capability::Review),
+    ("simulate_schedule", Capability::Review),
+    ("close_window", Capability::Review),
+    ("minimize_window", Capability::Review),
+    ("toggle_maximize_window", Capability::Review),
+    ("list_totp_seeds", Capability::Review),
+    ("set_totp_seed", Capability::Administer),
+    ("get_run_trace", Capability::Review),
+    ("preview_transform", Capability::Review),
+    ("set_upload_credentials", Capability::Administer),
+    ("upload_artifacts", Capability::Operate),
+    ("missing_variables", Capability::Review),
+    ("set_secret_variable", Capability::Administer),
+    ("template_variables", Capability::Review),
+    ("clear_cookies", Capability::Operate),
+    ("clear_webview_cache", Capability::Operate),
+    ("get_webview_storage_usage", Capability::Review),
+    ("reset_zoom", Capability::Review),
+    ("set_zoom", Capability::Review),
+    ("zoom_in", Capability::Review),
+    ("zoom_out", Capability::Review),
+    ("get_role", Capability::Review),
+    ("list_access_audit", Capability::Administer),
+    ("list_policy_audit", Capability::Administer),
+    ("override_domain_policy", Capability::Administer),
+    ("revoke_domain_override", Capability::Administer),
+];
+
+fn required(command: &str) -> Capability {
+    COMMANDS.iter().find(|(name, _)| *name == command).map_or(Capability::Administer, |(_, capability)| *capability)
+}
+
+/// The current role: the settings profile's, or viewer when launched with `--viewer`. The
+/// default settings of safe mode, or of a settings file that failed to load, don't say who
+/// is using the app, so they get viewer rather than the default admin.
+pub fn role(app: &AppHandle) -> Role {
+    if is_viewer(app) {
+        return Role::Viewer;
+    }
+    app.try_state::<SettingsStore>()
+        .filter(|store| !store.on_defaults())
+        .map_or(Role::Viewer, |store| store.get().role)
+}
+
+/// The error actions refused in viewer mode fail with.
+pub fn refusal(action: &str) -> String {
+    format!("Not available in viewer mode: {}", action)
+}
+
+/// The lowercase name a role or capability is serialized as.
+pub fn name(value: &impl Serialize) -> String {
+    serde_json::to_value(value).ok().and_then(|value| value.as_str().map(str::to_string)).unwrap_or_default()
+}
+
+fn audit(app: &AppHandle, command: &str, role: Role, required: Capability) {
+    log::warn!("Refused {} to the {:?} role, it needs {:?}", command, role, required);
+    let Some(history) = app.try_state::<History>() else {
+        return;
+    };
+    let entry = AccessAuditEntry {
+        at: history::now_ms(),
+        command: command.to_string(),
+        role: name(&role),
+        required: name(&required),
+    };
+    if let Err(e) = history.add_access_audit(&entry) {
+        log::warn!("Failed to record refused command {}: {}", command, e);
+    }
+}
+
+/// Fails, and audits the attempt, unless the current role has `capability`. `action`
+/// names what was attempted.
+pub fn check(app: &AppHandle, capability: Capability, action: &str) -> Result<(), String> {
+    let role = role(app);
+    if role >= capability.role() {
+        return Ok(());
+    }
+    audit(app, action, role, capability);
+    Err(match role {
+        Role::Viewer if is_viewer(app) => refusal(action),
+        Role::Viewer if app.try_state::<SettingsStore>().is_some_and(|store| store.on_defaults()) => {
+            format!("Not available while the saved settings are not loaded (e.g. in safe mode): {}", action)
+        }
+        _ => format!("Your role does not allow this: {}", action),
+    })
+}
+
+/// Whether the call names where to write a file, e.g. an export's `path`, instead of leaving
+/// it to the save dialog.
+fn names_path(invoke: &Invoke<Wry>) -> bool {
+    match invoke.message.payload() {
+        InvokeBody::Json(Value::Object(args)) => args.get("path").is_some_and(|path| !path.is_null()),
+        _ => false,
+    }
+}
+
+/// Wraps the app's command handler so every command is checked against the capability
+/// it declares in `COMMANDS`, whatever the frontend shows. Commands beyond `Review`, and any
+/// command given a file path to write, must also carry the session nonce, so a page the local
+/// server renders from automated content can't call them even though it shares the app's origin.
+pub fn guard<F>(handler: F) -> impl Fn(Invoke<Wry>) -> bool + Send + Sync + 'static
+where
+    F: Fn(Invoke<Wry>) -> bool + Send + Sync + 'static,
+{
+    move |invoke| {
+        let command = invoke.message.command();
+        let app = invoke.message.webview_ref().app_handle().clone();
+        let capability = required(command);
+        let result = check(&app, capability, command).and_then(|_| {
+            let trusted = capability == Capability::Review && !names_path(&invoke);
+            if trusted || security::has_session_nonce(invoke.message.headers()) {
+                return Ok(());
+            }
+            let url = invoke.message.webview_ref().url().map(|url| url.to_string()).unwrap_or_default();
+            log::warn!("Refused {} from {}: no session nonce", command, url);
+            Err(format!("{} may only be called from the app's own pages", command))
+        });
+        match result {
+            Ok(()) => handler(invoke),
+            Err(e) => {
+                invoke.resolver.reject(e);
+                true
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_role(app: AppHandle) -> Role {
+    role(&app)
+}
+
+/// Refused commands, newest first.
+#[tauri::command]
+pub fn list_access_audit(app: AppHandle, limit: Option<usize>) -> Result<Vec<AccessAuditEntry>, String> {
+    app.state::<History>().access_audit(limit.unwrap_or(DEFAULT_LIMIT))
+}
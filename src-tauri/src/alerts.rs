@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::events::{JobEvent, JobStatus};
+use crate::history::{self, Alert, History};
+use crate::notifications::{self, Category, Notification, Urgency};
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::settings::SettingsStore;
+use crate::{integrations, recipes};
+
+/// Alerts returned when no limit is given.
+const DEFAULT_LIMIT: usize = 100;
+/// Longer operators first, so `<=` isn't read as `<`.
+const OPERATORS: [(&str, Operator); 7] = [
+    ("<=", Operator::AtMost),
+    (">=", Operator::AtLeast),
+    ("==", Operator::Equal),
+    ("!=", Operator::NotEqual),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+    (" contains ", Operator::Contains),
+];
+
+/// A check on a recipe's output, e.g. `price < 100` or `row count > 0`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assertion {
+    pub expression: String,
+    /// Name of a configured webhook to call when the assertion breaks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Less,
+    AtMost,
+    Greater,
+    AtLeast,
+    Equal,
+    NotEqual,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Subject {
+    RowCount,
+    /// Dotted (`item.price`) or JSON pointer (`/item/price`) path within each row.
+    Field(String),
+}
+
+/// A parsed assertion: `<field or "row count"> <operator> <value>`.
+#[derive(Debug, Clone)]
+pub struct Check {
+    subject: Subject,
+    operator: Operator,
+    expected: Value,
+}
+
+/// Parses `expression`. Values are numbers, `true`/`false`/`null`, or text, quoted or not.
+pub fn parse(expression: &str) -> Result<Check, String> {
+    let (index, symbol, operator) = OPERATORS
+        .iter()
+        .filter_map(|(symbol, operator)| expression.find(symbol).map(|index| (index, *symbol, *operator)))
+        .min_by_key(|(index, symbol, _)| (*index, std::cmp::Reverse(symbol.len())))
+        .ok_or_else(|| format!("'{}' has no comparison (<, <=, >, >=, ==, != or contains)", expression))?;
+    let subject = expression[..index].trim();
+    let value = expression[index + symbol.len()..].trim();
+    if subject.is_empty() || value.is_empty() {
+        return Err(format!("'{}' needs a field and a value", expression));
+    }
+    let subject = if subject.eq_ignore_ascii_case("row count") {
+        Subject::RowCount
+    } else {
+        Subject::Field(subject.to_string())
+    };
+    let expected = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    if subject == Subject::RowCount && !expected.is_number() {
+        return Err(format!("'{}' compares the row count with something other than a number", expression));
+    }
+    Ok(Check { subject, operator, expected })
+}
+
+/// A number, also from text such as `$1,299.00` or `12 %`.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => {
+            let digits: String = s.chars().filter(|c| !matches!(c, ',' | '$' | '€' | '£' | '%' | ' ')).collect();
+            digits.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn field<'a>(row: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.starts_with('/') {
+        return row.pointer(path);
+    }
+    path.split('.').try_fold(row, |value, key| match value {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|index| items.get(index)),
+        _ => value.get(key),
+    })
+}
+
+impl Check {
+    fn holds(&self, actual: &Value) -> bool {
+        let numbers = number(actual).zip(number(&self.expected));
+        match (self.operator, numbers) {
+            (Operator::Contains, _) => match actual {
+                Value::Array(items) => items.iter().any(|item| text(item) == text(&self.expected)),
+                _ => text(actual).contains(&text(&self.expected)),
+            },
+            (Operator::Less, Some((a, b))) => a < b,
+            (Operator::AtMost, Some((a, b))) => a <= b,
+            (Operator::Greater, Some((a, b))) => a > b,
+            (Operator::AtLeast, Some((a, b))) => a >= b,
+            (Operator::Equal, Some((a, b))) => a == b,
+            (Operator::NotEqual, Some((a, b))) => a != b,
+            (Operator::Equal, None) => text(actual) == text(&self.expected),
+            (Operator::NotEqual, None) => text(actual) != text(&self.expected),
+            // Ordering something that isn't a number can't hold
+            _ => false,
+        }
+    }
+
+    /// Why `output` breaks the check, or `None` when it holds. An array output is a list
+    /// of rows and a field must hold in every one; anything else is a single row.
+    pub fn violation(&self, output: Option<&Value>) -> Option<String> {
+        let rows: Vec<&Value> = match output {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(rows)) => rows.iter().collect(),
+            Some(row) => vec![row],
+        };
+        match &self.subject {
+            Subject::RowCount => {
+                let count = Value::from(rows.len());
+                (!self.holds(&count)).then(|| format!("the output has {} rows", rows.len()))
+            }
+            Subject::Field(path) => {
+                if rows.is_empty() {
+                    return Some(format!("the output has no '{}'", path));
+                }
+                rows.iter().enumerate().find_map(|(index, row)| {
+                    let at = if rows.len() > 1 { format!("row {}: ", index + 1) } else { String::new() };
+                    match field(row, path) {
+                        None => Some(format!("{}no '{}'", at, path)),
+                        Some(value) if !self.holds(value) => Some(format!("{}{} is {}", at, path, value)),
+                        Some(_) => None,
+                    }
+                })
+            }
+        }
+    }
+}
+
+fn alert(app: &AppHandle, job: &JobEvent, recipe: &recipes::Recipe, assertion: &Assertion, detail: String) {
+    let name = recipe.task.name.clone().unwrap_or_else(|| recipe.id.clone());
+    log::warn!("Recipe '{}' broke '{}': {}", name, assertion.expression, detail);
+    let alert = Alert {
+        run_id: job.job_id.clone(),
+        recipe_id: recipe.id.clone(),
+        expression: assertion.expression.clone(),
+        detail: detail.clone(),
+        at: history::now_ms(),
+    };
+    if let Err(e) = app.state::<History>().add_alert(&alert) {
+        log::warn!("Failed to record alert for run {}: {}", job.job_id, e);
+    }
+    let _ = app.emit("recipe-alert", &alert);
+    notifications::notify(
+        app,
+        Notification {
+            title: format!("{}: {}", name, assertion.expression),
+            body: format!("Check failed, {}", detail),
+            urgency: Urgency::Critical,
+            category: Category::Alerts,
+        },
+    );
+
+    let Some(webhook) = assertion.webhook.as_deref() else {
+        return;
+    };
+    let webhooks = app.state::<SettingsStore>().get().webhooks;
+    let Some(config) = webhooks.iter().find(|w| w.name == webhook) else {
+        log::warn!("Recipe '{}' names a webhook '{}' that does not exist", name, webhook);
+        return;
+    };
+    // Reported like a failed job, so the webhook's template applies unchanged
+    let mut event = job.clone();
+    event.status = Some(JobStatus::Error);
+    event.message = Some(format!("Check '{}' failed: {}", assertion.expression, detail));
+    if let Err(e) = integrations::send(config, &event) {
+        log::warn!("Alert webhook '{}' failed: {}", webhook, e);
+    }
+}
+
+/// Built-in plugin that checks successful runs of a recipe against its assertions.
+struct AlertPlugin;
+
+impl ShellPlugin for AlertPlugin {
+    fn name(&self) -> &str {
+        "alerts"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        // Failed runs are reported already and have no output worth checking
+        if job.status != Some(JobStatus::Success) {
+            return;
+        }
+        let history = ctx.app.state::<History>();
+        let run = match history.get(&job.job_id) {
+            Ok(run) => run,
+            Err(e) => {
+                log::warn!("Failed to read run {}: {}", job.job_id, e);
+                return;
+            }
+        };
+        let Some(recipe_id) = job.recipe_id.clone().or_else(|| run.as_ref().and_then(|r| r.recipe_id.clone())) else {
+            return;
+        };
+        let recipe = match recipes::load(ctx.app, &recipe_id) {
+            Ok(recipe) => recipe,
+            Err(e) => {
+                log::warn!("Assertions of recipe '{}' not checked: {}", recipe_id, e);
+                return;
+            }
+        };
+        let output = job.output.as_ref().or_else(|| run.as_ref().and_then(|r| r.output.as_ref()));
+        for assertion in &recipe.task.assertions {
+            let detail = match parse(&assertion.expression) {
+                Ok(check) => check.violation(output),
+                Err(e) => Some(e),
+            };
+            if let Some(detail) = detail {
+                alert(ctx.app, job, &recipe, assertion, detail);
+            }
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(AlertPlugin), "Alerts when a run's output breaks its recipe's assertions");
+}
+
+/// Recorded alerts, newest first, optionally only those of one recipe.
+#[tauri::command]
+pub fn list_alerts(app: AppHandle, recipe_id: Option<String>, limit: Option<usize>) -> Result<Vec<Alert>, String> {
+    app.state::<History>().alerts(recipe_id.as_deref(), limit.unwrap_or(DEFAULT_LIMIT))
+}
+
+/// Checks an assertion against a past run's output without alerting. Returns why it
+/// breaks, or `None` when it holds.
+#[tauri::command]
+pub fn test_assertion(app: AppHandle, expression: String, run_id: String) -> Result<Option<String>, String> {
+    let check = parse(&expression)?;
+    let run = app.state::<History>().get(&run_id)?.ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    Ok(check.violation(run.output.as_ref()))
+}
@@ -0,0 +1,202 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+use crate::history::{self, History, RunRecord};
+use crate::{debugger, recipes};
+
+/// Runs older than this are left out of a recipe's analytics.
+const WINDOW_MS: i64 = 90 * 24 * 60 * 60 * 1000;
+const MAX_RUNS: u32 = 1000;
+/// Fewest runs on each side of a change for the two to be compared.
+const MIN_RUNS_TO_COMPARE: u32 = 5;
+/// A failure rate this much higher (absolute) counts as a regression.
+const FAILURE_RATE_REGRESSION: f64 = 0.1;
+/// A median duration this much longer (relative) counts as a regression.
+const DURATION_REGRESSION: f64 = 0.25;
+
+/// Duration percentiles of successful runs (ms).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Durations {
+    pub p50: Option<i64>,
+    pub p90: Option<i64>,
+    pub p95: Option<i64>,
+    pub max: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PeriodStats {
+    pub runs: u32,
+    pub failures: u32,
+    pub failure_rate: f64,
+    pub durations: Durations,
+}
+
+/// How one step of the recipe's current prompt has fared.
+#[derive(Debug, Clone, Serialize)]
+pub struct StepAnalytics {
+    /// From 1.
+    pub step: u32,
+    pub text: String,
+    /// Runs that got to this step.
+    pub attempts: u32,
+    pub failures: u32,
+    pub failure_rate: f64,
+    /// Has both failed and passed.
+    pub flaky: bool,
+}
+
+/// A significant difference between runs before and since the recipe last changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Trend {
+    /// `failure_rate` or `median_duration_ms`.
+    pub metric: &'static str,
+    pub before: f64,
+    pub since: f64,
+    /// Worse since the change; otherwise better.
+    pub regression: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecipeAnalytics {
+    pub recipe_id: String,
+    /// Start of the period analysed (Unix ms).
+    pub since: i64,
+    #[serde(flatten)]
+    pub overall: PeriodStats,
+    /// Steps are attributed failures only when the run went step by step and captured
+    /// its failure; other failures are counted here.
+    pub unattributed_failures: u32,
+    pub steps: Vec<StepAnalytics>,
+    /// When the recipe was last saved (Unix ms).
+    pub changed_at: i64,
+    pub before_change: PeriodStats,
+    pub since_change: PeriodStats,
+    pub trends: Vec<Trend>,
+}
+
+/// Nearest-rank percentile of `sorted`.
+fn percentile(sorted: &[i64], percent: usize) -> Option<i64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+fn period<'a>(runs: impl Iterator<Item = &'a RunRecord>) -> PeriodStats {
+    let mut stats = PeriodStats::default();
+    let mut durations = Vec::new();
+    for run in runs {
+        stats.runs += 1;
+        if run.status == "success" {
+            durations.extend(run.duration_ms);
+        } else {
+            stats.failures += 1;
+        }
+    }
+    if stats.runs > 0 {
+        stats.failure_rate = stats.failures as f64 / stats.runs as f64;
+    }
+    durations.sort_unstable();
+    stats.durations = Durations {
+        p50: percentile(&durations, 50),
+        p90: percentile(&durations, 90),
+        p95: percentile(&durations, 95),
+        max: durations.last().copied(),
+    };
+    stats
+}
+
+/// Steps are matched by their text rather than their number, so a step keeps its history
+/// when steps before it are added or removed.
+fn step_analytics(prompt: &str, runs: &[(RunRecord, Option<u32>)]) -> (Vec<StepAnalytics>, u32) {
+    let mut counts: HashMap<&str, (u32, u32)> = HashMap::new();
+    let mut unattributed = 0;
+    for (run, failed_step) in runs {
+        let steps = debugger::steps(&run.prompt);
+        let (reached, failed) = match (run.status.as_str(), failed_step) {
+            ("success", _) => (steps.len(), None),
+            (_, Some(step)) if (1..=steps.len()).contains(&(*step as usize)) => {
+                (*step as usize, Some(*step as usize))
+            }
+            _ => {
+                unattributed += 1;
+                continue;
+            }
+        };
+        for (index, text) in steps.iter().take(reached).enumerate() {
+            let entry = counts.entry(*text).or_default();
+            entry.0 += 1;
+            if failed == Some(index + 1) {
+                entry.1 += 1;
+            }
+        }
+    }
+    let steps = debugger::steps(prompt)
+        .into_iter()
+        .enumerate()
+        .map(|(index, text)| {
+            let (attempts, failures) = counts.get(text).copied().unwrap_or_default();
+            StepAnalytics {
+                step: index as u32 + 1,
+                text: text.to_string(),
+                attempts,
+                failures,
+                failure_rate: if attempts > 0 { failures as f64 / attempts as f64 } else { 0.0 },
+                flaky: failures > 0 && failures < attempts,
+            }
+        })
+        .collect();
+    (steps, unattributed)
+}
+
+fn trends(before: &PeriodStats, since: &PeriodStats) -> Vec<Trend> {
+    if before.runs < MIN_RUNS_TO_COMPARE || since.runs < MIN_RUNS_TO_COMPARE {
+        return Vec::new();
+    }
+    let mut trends = Vec::new();
+    if (since.failure_rate - before.failure_rate).abs() >= FAILURE_RATE_REGRESSION {
+        trends.push(Trend {
+            metric: "failure_rate",
+            before: before.failure_rate,
+            since: since.failure_rate,
+            regression: since.failure_rate > before.failure_rate,
+        });
+    }
+    if let (Some(before), Some(since)) = (before.durations.p50, since.durations.p50) {
+        let (before, since) = (before as f64, since as f64);
+        if before > 0.0 && (since - before).abs() / before >= DURATION_REGRESSION {
+            trends.push(Trend { metric: "median_duration_ms", before, since, regression: since > before });
+        }
+    }
+    trends
+}
+
+/// Failure rates and durations of a recipe's runs over the last 90 days, overall and per
+/// step, and how they changed since the recipe was last saved.
+#[tauri::command]
+pub async fn get_recipe_analytics(app: AppHandle, recipe_id: String) -> Result<RecipeAnalytics, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let recipe = recipes::load(&app, &recipe_id)?;
+        let since = history::now_ms() - WINDOW_MS;
+        let runs = app.state::<History>().recipe_runs(&recipe_id, since, MAX_RUNS)?;
+        let (steps, unattributed_failures) = step_analytics(&recipe.task.prompt, &runs);
+        let all = || runs.iter().map(|(run, _)| run);
+        let before_change = period(all().filter(|run| run.started_at < recipe.modified_at));
+        let since_change = period(all().filter(|run| run.started_at >= recipe.modified_at));
+        Ok(RecipeAnalytics {
+            overall: period(all()),
+            trends: trends(&before_change, &since_change),
+            recipe_id,
+            since,
+            unattributed_failures,
+            steps,
+            changed_at: recipe.modified_at,
+            before_change,
+            since_change,
+        })
+    })
+    .await
+    .map_err(|e| format!("Analysing the recipe failed: {}", e))?
+}
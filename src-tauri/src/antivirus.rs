@@ -0,0 +1,73 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+/// Spawns blocked by antivirus are retried this many times, since scanners often only
+/// hold the file while they look at it.
+pub const RETRIES: u32 = 3;
+pub const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Windows errors antivirus interference shows up as.
+#[cfg(windows)]
+const ERROR_ACCESS_DENIED: i32 = 5;
+#[cfg(windows)]
+const ERROR_VIRUS_INFECTED: i32 = 225;
+#[cfg(windows)]
+const ERROR_VIRUS_DELETED: i32 = 226;
+
+/// Sent as `server-spawn-blocked` so the UI can show remediation steps.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpawnBlocked {
+    pub binary: PathBuf,
+    pub error: String,
+    /// 1-based.
+    pub attempt: u32,
+    /// Seconds until the next try; `None` once retries are exhausted.
+    pub retry_in_secs: Option<u64>,
+}
+
+/// Whether a spawn failure looks like antivirus software locking or quarantining the binary.
+#[cfg(windows)]
+pub fn is_blocked(error: &std::io::Error) -> bool {
+    matches!(
+        error.raw_os_error(),
+        Some(ERROR_ACCESS_DENIED | ERROR_VIRUS_INFECTED | ERROR_VIRUS_DELETED)
+    )
+}
+
+#[cfg(not(windows))]
+pub fn is_blocked(_error: &std::io::Error) -> bool {
+    false
+}
+
+/// Tells the UI about a blocked spawn and, on the first attempt, explains to the user
+/// which file to allowlist.
+pub fn report(app: &AppHandle, binary: &Path, error: &std::io::Error, attempt: u32) {
+    let retry_in_secs = (attempt <= RETRIES).then_some(RETRY_DELAY.as_secs());
+    log::warn!(
+        "Server spawn blocked, likely by antivirus (attempt {}): {}",
+        attempt,
+        error
+    );
+    let _ = app.emit(
+        "server-spawn-blocked",
+        SpawnBlocked { binary: binary.to_path_buf(), error: error.to_string(), attempt, retry_in_secs },
+    );
+    if attempt != 1 {
+        return;
+    }
+    app.dialog()
+        .message(format!(
+            "Windows refused to start the automation server ({}).\n\n\
+             This is usually antivirus software, such as Microsoft Defender, scanning or \
+             quarantining the file. Add this file to your antivirus allowlist:\n\n{}\n\n\
+             The app will keep retrying for a little while.",
+            error,
+            binary.display()
+        ))
+        .title("Server blocked by antivirus")
+        .kind(MessageDialogKind::Warning)
+        .show(|_| {});
+}
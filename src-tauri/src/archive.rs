@@ -0,0 +1,160 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{BufWriter, Read};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::{downloads, history};
+
+/// Emit a progress event every this many bytes.
+const PROGRESS_EVERY: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+        }
+    }
+}
+
+/// Sent as `archive-progress` while an archive is written.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveProgress {
+    pub run_id: String,
+    /// Uncompressed bytes added so far.
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub done: bool,
+}
+
+struct Progress<'a> {
+    app: &'a AppHandle,
+    run_id: &'a str,
+    total_bytes: u64,
+    bytes_read: Cell<u64>,
+    last_emitted: Cell<u64>,
+}
+
+impl Progress<'_> {
+    fn report(&self, bytes: usize) {
+        let bytes_read = self.bytes_read.get() + bytes as u64;
+        self.bytes_read.set(bytes_read);
+        if bytes_read - self.last_emitted.get() >= PROGRESS_EVERY {
+            self.last_emitted.set(bytes_read);
+            self.emit(false);
+        }
+    }
+
+    fn emit(&self, done: bool) {
+        let progress = ArchiveProgress {
+            run_id: self.run_id.to_string(),
+            bytes_read: self.bytes_read.get(),
+            total_bytes: self.total_bytes,
+            done,
+        };
+        if let Err(e) = self.app.emit("archive-progress", progress) {
+            log::warn!("Failed to emit archive progress: {}", e);
+        }
+    }
+}
+
+/// A file being archived, counting what is read for progress.
+struct Counting<'a, R> {
+    inner: R,
+    progress: &'a Progress<'a>,
+}
+
+impl<R: Read> Read for Counting<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.report(read);
+        Ok(read)
+    }
+}
+
+fn write_zip(path: &Path, files: &[(PathBuf, String)], progress: &Progress) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default().large_file(true);
+    for (source, name) in files {
+        let file = File::open(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+        std::io::copy(&mut Counting { inner: file, progress }, &mut zip)
+            .map_err(|e| format!("Failed to archive {}: {}", source.display(), e))?;
+    }
+    zip.finish().map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+fn write_tar_gz(path: &Path, files: &[(PathBuf, String)], progress: &Progress) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(BufWriter::new(file), Compression::default()));
+    for (source, name) in files {
+        let file = File::open(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        let metadata = file.metadata().map_err(|e| e.to_string())?;
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&metadata);
+        tar.append_data(&mut header, name, Counting { inner: file, progress })
+            .map_err(|e| format!("Failed to archive {}: {}", source.display(), e))?;
+    }
+    tar.into_inner()
+        .and_then(|gz| gz.finish())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+fn archive(app: &AppHandle, run_id: &str, format: ArchiveFormat, path: Option<PathBuf>) -> Result<PathBuf, String> {
+    let dir = history::artifacts_dir(app, run_id).ok_or_else(|| format!("Invalid run id '{}'", run_id))?;
+    if !dir.is_dir() {
+        return Err(format!("Run '{}' has no artifacts", run_id));
+    }
+    let files = history::artifact_files(&dir)?;
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let folder = downloads::download_dir(app, None)?;
+            downloads::unique_path(&folder, &format!("run-{}.{}", run_id, format.extension()), &[])
+        }
+    };
+
+    log::info!("Archiving {} files of run {} as {:?} to {}", files.len(), run_id, format, path.display());
+    let total_bytes = files.iter().filter_map(|(source, _)| std::fs::metadata(source).ok()).map(|m| m.len()).sum();
+    let progress = Progress { app, run_id, total_bytes, bytes_read: Cell::new(0), last_emitted: Cell::new(0) };
+    let result = match format {
+        ArchiveFormat::Zip => write_zip(&path, &files, &progress),
+        ArchiveFormat::TarGz => write_tar_gz(&path, &files, &progress),
+    };
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+    progress.emit(true);
+    Ok(path)
+}
+
+/// Packs a run's artifacts into a zip or tar.gz for sharing. Without a `path` it is saved
+/// to the download folder; returns where it was written.
+#[tauri::command]
+pub async fn archive_run(
+    app: AppHandle,
+    run_id: String,
+    format: ArchiveFormat,
+    path: Option<PathBuf>,
+) -> Result<PathBuf, String> {
+    tauri::async_runtime::spawn_blocking(move || archive(&app, &run_id, format, path))
+        .await
+        .map_err(|e| format!("Archive failed: {}", e))?
+}
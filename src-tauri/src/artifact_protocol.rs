@@ -0,0 +1,143 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, UriSchemeContext, UriSchemeResponder, Wry};
+
+use crate::downloads::percent_decode;
+use crate::history;
+
+/// Serves run artifacts to the webviews as `nova-artifacts://localhost/<run_id>/<path>`
+/// (`http://nova-artifacts.localhost/<run_id>/<path>` on Windows), straight from disk.
+pub const SCHEME: &str = "nova-artifacts";
+
+/// Longest slice returned for an open-ended range, so seeking in a large video reads
+/// only what the player asked for next instead of the rest of the file.
+const MAX_RANGE: u64 = 4 * 1024 * 1024;
+
+/// The URL a webview loads `path` (relative, `/`-separated) of a run's artifacts from.
+pub fn url(run_id: &str, path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    // Windows webviews only allow custom schemes as http://<scheme>.localhost
+    if cfg!(windows) {
+        format!("http://{}.localhost/{}/{}", SCHEME, run_id, encoded)
+    } else {
+        format!("{}://localhost/{}/{}", SCHEME, run_id, encoded)
+    }
+}
+
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "txt" | "log" => "text/plain; charset=utf-8",
+        "json" => "application/json",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The artifact a request path points at. Anything that could step outside the run's
+/// artifacts dir is refused.
+fn resolve(app: &AppHandle, uri_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(uri_path.trim_start_matches('/'));
+    let (run_id, relative) = decoded.split_once('/')?;
+    let relative = Path::new(relative);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let dir = history::artifacts_dir(app, run_id)?;
+    Some(dir.join(relative)).filter(|path| path.is_file())
+}
+
+/// Parses a single `bytes=start-end`, `bytes=start-` or `bytes=-suffix` range into an
+/// inclusive (start, end). `Err` means it cannot be satisfied for a file of `len` bytes.
+fn parse_range(value: &str, len: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    // Multiple ranges are rare from media elements; serve the first
+    let spec = spec.split(',').next().unwrap_or_default().trim();
+    let (start, end) = spec.split_once('-').ok_or(())?;
+    let (start, end) = match (start.parse::<u64>().ok(), end.parse::<u64>().ok()) {
+        (Some(start), Some(end)) => (start, end.min(len.saturating_sub(1))),
+        (Some(start), None) => (start, (start + MAX_RANGE).min(len).saturating_sub(1)),
+        (None, Some(suffix)) if suffix > 0 => (len.saturating_sub(suffix), len.saturating_sub(1)),
+        _ => return Err(()),
+    };
+    if start >= len || start > end {
+        return Err(());
+    }
+    Ok(Some((start, end)))
+}
+
+fn error(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder().status(status).body(Vec::new()).unwrap()
+}
+
+fn serve(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Some(path) = resolve(app, request.uri().path()) else {
+        return error(StatusCode::NOT_FOUND);
+    };
+    let Ok(mut file) = File::open(&path) else {
+        return error(StatusCode::NOT_FOUND);
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let range = request.headers().get(header::RANGE).and_then(|v| v.to_str().ok()).map(|v| parse_range(v, len));
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type(&path))
+        .header(header::ACCEPT_RANGES, "bytes");
+    match range {
+        Some(Err(())) => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Vec::new())
+            .unwrap(),
+        Some(Ok(Some((start, end)))) => {
+            let mut body = vec![0; (end - start + 1) as usize];
+            if file.seek(SeekFrom::Start(start)).and_then(|_| file.read_exact(&mut body)).is_err() {
+                return error(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len))
+                .body(body)
+                .unwrap()
+        }
+        None | Some(Ok(None)) => {
+            let mut body = Vec::with_capacity(len as usize);
+            if file.read_to_end(&mut body).is_err() {
+                return error(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+            builder.status(StatusCode::OK).body(body).unwrap()
+        }
+    }
+}
+
+/// Handler for the `nova-artifacts` scheme. Files are read off the webview's thread.
+pub fn handle(ctx: UriSchemeContext<'_, Wry>, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let app = ctx.app_handle().clone();
+    std::thread::spawn(move || {
+        let response = if request.method() == tauri::http::Method::GET {
+            serve(&app, &request)
+        } else {
+            error(StatusCode::METHOD_NOT_ALLOWED)
+        };
+        responder.respond(response);
+    });
+}
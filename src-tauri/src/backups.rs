@@ -0,0 +1,495 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::history::{self, History};
+use crate::instances::ServerManager;
+use crate::settings::SettingsStore;
+use crate::{packaging, webview_data, AppState};
+
+/// Newest backup format this version restores.
+const FORMAT_VERSION: u32 = 1;
+pub const EXTENSION: &str = "babackup";
+const MANIFEST: &str = "manifest.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// History meta key holding when the last automatic backup was made (Unix milliseconds).
+const LAST_BACKUP_KEY: &str = "last_backup_at";
+/// Entries of the app data dir left out: the extracted server (re-extracted on start), logs,
+/// the feature flag cache, backups themselves and migration leftovers. The history DB is added
+/// from a snapshot instead.
+const EXCLUDED: &[&str] = &[
+    "server",
+    "logs",
+    "backups",
+    "flags.json",
+    ".migration-backup",
+    history::DB_FILE,
+    "history.db-journal",
+];
+/// Chromium caches inside the browser profile.
+const BROWSER_CACHES: &[&str] = &["Cache", "Code Cache", "GPUCache", "ShaderCache", "GrShaderCache", "DawnCache"];
+
+/// Automatic backups of the app data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    /// Where automatic backups go; `backups` in the app data dir when unset.
+    pub dir: Option<PathBuf>,
+    /// Automatic backups kept; older ones are deleted.
+    pub keep: usize,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings { enabled: false, interval_hours: 24, dir: None, keep: 7 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format: u32,
+    app_version: String,
+    /// Unix milliseconds.
+    created_at: i64,
+    /// Archive entry name to size and checksum, checked before anything is restored.
+    files: BTreeMap<String, FileEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub app_version: String,
+    pub created_at: i64,
+    pub files: usize,
+    /// Uncompressed.
+    pub bytes: u64,
+}
+
+/// What a backup holds, each under its own prefix in the archive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Root {
+    Data,
+    Config,
+    Browser,
+}
+
+/// The roots to back up. On macOS and Windows config lives in the app data dir, which
+/// is then backed up once, as data.
+fn roots(app: &AppHandle) -> Result<Vec<Root>, String> {
+    let shared = Root::Config.dir(app)? == Root::Data.dir(app)?;
+    Ok([Root::Data, Root::Config, Root::Browser]
+        .into_iter()
+        .filter(|root| !(shared && *root == Root::Config))
+        .collect())
+}
+
+impl Root {
+    fn prefix(self) -> &'static str {
+        match self {
+            Root::Data => "data",
+            Root::Config => "config",
+            Root::Browser => "browser",
+        }
+    }
+
+    fn dir(self, app: &AppHandle) -> Result<PathBuf, String> {
+        let path = app.path();
+        match self {
+            Root::Data => path.app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e)),
+            Root::Config => path.app_config_dir().map_err(|e| format!("Failed to get app config dir: {}", e)),
+            Root::Browser => packaging::browser_profile_dir().ok_or_else(|| "No home directory".to_string()),
+        }
+    }
+
+    /// Whether `name` (relative to the root) stays out of backups and survives restores.
+    fn excluded(self, app: &AppHandle, path: &Path, name: &str) -> bool {
+        match self {
+            Root::Data => {
+                let top = name.split('/').next().unwrap_or_default();
+                // On Linux the webview keeps its storage in the app data dir too
+                EXCLUDED.contains(&top) || webview_data::webview_data_paths(app).iter().any(|p| path.starts_with(p))
+            }
+            Root::Config => false,
+            Root::Browser => name.split('/').any(|part| BROWSER_CACHES.contains(&part)),
+        }
+    }
+}
+
+/// Reads through to a checksum, so files are hashed as they are archived or extracted.
+struct Hashing<R> {
+    inner: R,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<R: Read> Hashing<R> {
+    fn new(inner: R) -> Self {
+        Hashing { inner, hasher: Sha256::new(), size: 0 }
+    }
+
+    fn finish(self) -> FileEntry {
+        FileEntry { size: self.size, sha256: format!("{:x}", self.hasher.finalize()) }
+    }
+}
+
+impl<R: Read> Read for Hashing<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.size += n as u64;
+        Ok(n)
+    }
+}
+
+fn add_file(zip: &mut ZipWriter<BufWriter<File>>, name: &str, path: &Path) -> Result<FileEntry, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let large = file.metadata().map(|m| m.len() >= u32::MAX as u64).unwrap_or(false);
+    zip.start_file(name, SimpleFileOptions::default().large_file(large)).map_err(|e| e.to_string())?;
+    let mut reader = Hashing::new(file);
+    std::io::copy(&mut reader, zip).map_err(|e| format!("Failed to add {}: {}", path.display(), e))?;
+    Ok(reader.finish())
+}
+
+fn write_archive(app: &AppHandle, path: &Path, snapshot: &Path) -> Result<Manifest, String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let mut files = BTreeMap::new();
+    let db = format!("{}/{}", Root::Data.prefix(), history::DB_FILE);
+    files.insert(db.clone(), add_file(&mut zip, &db, snapshot)?);
+    for root in roots(app)? {
+        let dir = root.dir(app)?;
+        if !dir.is_dir() {
+            continue;
+        }
+        for (file, name) in history::artifact_files(&dir)? {
+            if root.excluded(app, &file, &name) {
+                continue;
+            }
+            let name = format!("{}/{}", root.prefix(), name);
+            match add_file(&mut zip, &name, &file) {
+                Ok(entry) => {
+                    files.insert(name, entry);
+                }
+                // Lock files and the like vanish while the app runs
+                Err(e) if !file.exists() => log::debug!("Skipped from backup: {}", e),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    let manifest = Manifest {
+        format: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: history::now_ms(),
+        files,
+    };
+    zip.start_file(MANIFEST, SimpleFileOptions::default()).map_err(|e| e.to_string())?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    zip.finish()
+        .and_then(|writer| writer.into_inner().map_err(|e| e.into_error().into()))
+        .and_then(|file| file.sync_all().map_err(Into::into))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(manifest)
+}
+
+fn info(path: &Path, manifest: &Manifest) -> BackupInfo {
+    BackupInfo {
+        path: path.to_path_buf(),
+        app_version: manifest.app_version.clone(),
+        created_at: manifest.created_at,
+        files: manifest.files.len(),
+        bytes: manifest.files.values().map(|file| file.size).sum(),
+    }
+}
+
+/// Writes a backup to `path`, by way of a partial file so a failed backup never
+/// leaves a truncated archive behind.
+fn create(app: &AppHandle, path: &Path) -> Result<BackupInfo, String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let partial = path.with_extension("partial");
+    let snapshot = std::env::temp_dir().join(format!("backup-{}.db", uuid::Uuid::new_v4()));
+    let result = app
+        .state::<History>()
+        .snapshot(&snapshot)
+        .and_then(|_| write_archive(app, &partial, &snapshot))
+        .and_then(|manifest| {
+            std::fs::rename(&partial, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            Ok(manifest)
+        });
+    let _ = std::fs::remove_file(&snapshot);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    let manifest = result?;
+    log::info!("Backed up {} files to {}", manifest.files.len(), path.display());
+    Ok(info(path, &manifest))
+}
+
+/// Extracts every file the manifest lists into `staging`, checking sizes and checksums.
+fn extract(archive: &mut ZipArchive<File>, manifest: &Manifest, staging: &Path) -> Result<(), String> {
+    for (name, expected) in &manifest.files {
+        let entry = archive.by_name(name).map_err(|e| format!("The backup is missing {}: {}", name, e))?;
+        let relative = entry.enclosed_name().ok_or_else(|| format!("The backup has an unsafe path '{}'", name))?;
+        let target = staging.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut reader = Hashing::new(entry);
+        let mut file = File::create(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        std::io::copy(&mut reader, &mut file).map_err(|e| format!("Failed to extract {}: {}", name, e))?;
+        let actual = reader.finish();
+        if actual.size != expected.size || actual.sha256 != expected.sha256 {
+            return Err(format!("The backup is damaged: {} does not match its checksum", name));
+        }
+    }
+    Ok(())
+}
+
+/// `dir` with `suffix` added to its name, next to it so renames stay on one filesystem.
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    dir.with_file_name(name)
+}
+
+/// One root's restored files, moved into place with what they replaced kept aside until
+/// every root has been swapped.
+struct Swapped {
+    dir: PathBuf,
+    aside: PathBuf,
+    staged: PathBuf,
+    moved_aside: Vec<OsString>,
+    moved_in: Vec<OsString>,
+}
+
+impl Swapped {
+    /// Puts the replaced files back where they were.
+    fn roll_back(self) {
+        for name in &self.moved_in {
+            let _ = std::fs::rename(self.dir.join(name), self.staged.join(name));
+        }
+        for name in &self.moved_aside {
+            let _ = std::fs::rename(self.aside.join(name), self.dir.join(name));
+        }
+        let _ = std::fs::remove_dir_all(&self.aside);
+    }
+
+    fn finish(self) {
+        let _ = std::fs::remove_dir_all(&self.aside);
+    }
+}
+
+/// Moves the restored files of one root into place. What they replace is moved aside
+/// first and moved back if anything fails.
+fn swap_in(app: &AppHandle, root: Root, staged: &Path) -> Result<Swapped, String> {
+    let dir = root.dir(app)?;
+    let aside = sibling(&dir, ".pre-restore");
+    let _ = std::fs::remove_dir_all(&aside);
+    std::fs::create_dir_all(&aside).map_err(|e| format!("Failed to create {}: {}", aside.display(), e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let mut moved_aside = Vec::new();
+    let mut moved_in = Vec::new();
+    let mut result = Ok(());
+    for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+        let name = entry.file_name();
+        if root.excluded(app, &entry.path(), &name.to_string_lossy()) {
+            continue;
+        }
+        result = std::fs::rename(entry.path(), aside.join(&name));
+        if result.is_err() {
+            break;
+        }
+        moved_aside.push(name);
+    }
+    if result.is_ok() && staged.is_dir() {
+        for entry in std::fs::read_dir(staged).into_iter().flatten().flatten() {
+            let name = entry.file_name();
+            result = std::fs::rename(entry.path(), dir.join(&name));
+            if result.is_err() {
+                break;
+            }
+            moved_in.push(name);
+        }
+    }
+    let swapped = Swapped { dir, aside, staged: staged.to_path_buf(), moved_aside, moved_in };
+    if let Err(e) = result {
+        let message = format!("Failed to restore {}: {}", swapped.dir.display(), e);
+        swapped.roll_back();
+        return Err(message);
+    }
+    Ok(swapped)
+}
+
+/// Checks the backup and extracts it next to the app data. Returns the staging dir, with
+/// one subdirectory per root.
+fn prepare(app: &AppHandle, path: &Path) -> Result<PathBuf, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("{} is not a backup: {}", path.display(), e))?;
+    let manifest: Manifest = {
+        let mut entry = archive.by_name(MANIFEST).map_err(|_| format!("{} is not a backup", path.display()))?;
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_slice(&contents).map_err(|e| format!("The backup's manifest is damaged: {}", e))?
+    };
+    if manifest.format > FORMAT_VERSION {
+        return Err(format!("{} was made by a newer version of the app, please update", path.display()));
+    }
+
+    let staging = sibling(&Root::Data.dir(app)?, ".restore");
+    let _ = std::fs::remove_dir_all(&staging);
+    let mut result = extract(&mut archive, &manifest, &staging);
+    // A backup from a machine that keeps config apart, restored where it lives with the data
+    let config = staging.join(Root::Config.prefix());
+    if result.is_ok() && config.is_dir() && !roots(app)?.contains(&Root::Config) {
+        let data = staging.join(Root::Data.prefix());
+        result = std::fs::read_dir(&config)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .try_for_each(|entry| std::fs::rename(entry.path(), data.join(entry.file_name())))
+            .map_err(|e| format!("Failed to extract the backup: {}", e));
+    }
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    log::info!("Restoring backup from {} (made by version {})", path.display(), manifest.app_version);
+    Ok(staging)
+}
+
+/// Replaces the data with the staged files, every root or none: if one fails, the roots
+/// already swapped are put back too. Nothing may write to the data meanwhile, so the
+/// servers are stopped and the history DB closed; the app has to restart afterwards.
+fn swap(app: &AppHandle, staging: &Path) -> Result<(), String> {
+    app.state::<ServerManager>().stop_all();
+    app.state::<AppState>().cleanup_server();
+    app.state::<History>().close();
+    let mut swapped = Vec::new();
+    let mut result = Ok(());
+    for root in roots(app)? {
+        match swap_in(app, root, &staging.join(root.prefix())) {
+            Ok(root) => swapped.push(root),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+    match result {
+        Ok(()) => swapped.into_iter().for_each(Swapped::finish),
+        Err(_) => swapped.into_iter().rev().for_each(Swapped::roll_back),
+    }
+    let _ = std::fs::remove_dir_all(staging);
+    result
+}
+
+fn backup_dir(app: &AppHandle, settings: &BackupSettings) -> Result<PathBuf, String> {
+    match &settings.dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Root::Data.dir(app).map(|dir| dir.join("backups")),
+    }
+}
+
+/// Deletes automatic backups beyond the newest `keep`.
+fn prune(dir: &Path, keep: usize) {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("auto-")))
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .collect();
+    // Names carry the time, so they sort oldest first
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for path in &backups[..excess] {
+        if let Err(e) = std::fs::remove_file(path) {
+            log::warn!("Failed to delete old backup {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn automatic_backup(app: &AppHandle) -> Result<(), String> {
+    let settings = app.state::<SettingsStore>().get().backups;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let history = app.state::<History>();
+    let last = history.meta(LAST_BACKUP_KEY)?.and_then(|at| at.parse::<i64>().ok()).unwrap_or(0);
+    if history::now_ms() - last < settings.interval_hours.max(1) as i64 * 60 * 60 * 1000 {
+        return Ok(());
+    }
+    let dir = backup_dir(app, &settings)?;
+    let name = format!("auto-{}.{}", history::compact_utc(SystemTime::now()), EXTENSION);
+    create(app, &dir.join(name))?;
+    history.set_meta(LAST_BACKUP_KEY, &history::now_ms().to_string())?;
+    prune(&dir, settings.keep);
+    Ok(())
+}
+
+/// Makes automatic backups when they are turned on and one is due.
+pub fn setup(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        if let Err(e) = automatic_backup(&app) {
+            log::error!("Automatic backup failed: {}", e);
+        }
+    });
+}
+
+/// Writes settings, run history and artifacts, recipes and the browser profile to a
+/// checksummed archive at `path`. Caches and the extracted server are left out; keychain
+/// entries stay in the keychain.
+#[tauri::command]
+pub async fn create_backup(app: AppHandle, path: PathBuf) -> Result<BackupInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || create(&app, &path))
+        .await
+        .map_err(|e| format!("Backup failed: {}", e))?
+}
+
+/// Replaces the app data with a backup's after checking every file against its checksum.
+/// The server is stopped while the files are swapped and the app restarts afterwards;
+/// older backups are upgraded on that start like any older data.
+#[tauri::command]
+pub async fn restore_backup(app: AppHandle, path: PathBuf) -> Result<(), String> {
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let staging = prepare(&handle, &path)?;
+        if let Err(e) = swap(&handle, &staging) {
+            // The server is down either way, so the app restarts on whatever is in place
+            log::error!("Restoring the backup failed: {}", e);
+            handle
+                .dialog()
+                .message(format!("The backup could not be restored; the data was left as it was.\n\n{}", e))
+                .title("Restore failed")
+                .kind(MessageDialogKind::Error)
+                .blocking_show();
+        }
+        Ok::<_, String>(())
+    })
+    .await
+    .map_err(|e| format!("Restore failed: {}", e))??;
+    log::info!("Backup restored, restarting");
+    app.restart()
+}
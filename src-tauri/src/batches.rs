@@ -0,0 +1,250 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::events::{JobEvent, JobStatus};
+use crate::history::{self, Batch, BatchRow, History};
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::{queue, recipes, variables};
+
+const MAX_ROWS: usize = 10_000;
+const DEFAULT_CHUNK_SIZE: usize = 20;
+
+/// Row counts of a batch. Sent as `batch-progress`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchStatus {
+    pub id: String,
+    pub recipe_id: String,
+    pub total: i64,
+    pub pending: i64,
+    pub queued: i64,
+    pub success: i64,
+    pub error: i64,
+    pub cancelled: i64,
+}
+
+/// Serializes topping up, so a finishing row can't queue the same pending row twice.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn status(history: &History, batch: &Batch) -> Result<BatchStatus, String> {
+    let counts = history.batch_counts(&batch.id)?;
+    let count = |status: &str| counts.get(status).copied().unwrap_or(0);
+    Ok(BatchStatus {
+        id: batch.id.clone(),
+        recipe_id: batch.recipe_id.clone(),
+        total: counts.values().sum(),
+        pending: count("pending"),
+        queued: count("queued"),
+        success: count("success"),
+        error: count("error"),
+        cancelled: count("cancelled"),
+    })
+}
+
+fn load(history: &History, id: &str) -> Result<Batch, String> {
+    history.batch(id)?.ok_or_else(|| format!("No batch '{}'", id))
+}
+
+fn progress(app: &AppHandle, batch: &Batch) {
+    match status(&app.state::<History>(), batch) {
+        Ok(status) => {
+            let _ = app.emit("batch-progress", status);
+        }
+        Err(e) => log::warn!("Failed to read batch {}: {}", batch.id, e),
+    }
+}
+
+/// Queues pending rows until `chunk_size` of the batch are in the queue, so a large batch
+/// doesn't crowd out everything else. Call with `LOCK` held.
+fn top_up(app: &AppHandle, batch: &Batch) -> Result<(), String> {
+    let history = app.state::<History>();
+    let queued = history.batch_counts(&batch.id)?.get("queued").copied().unwrap_or(0) as usize;
+    let free = batch.chunk_size.saturating_sub(queued);
+    for row in history.batch_rows(&batch.id, Some("pending"), free)? {
+        let prompt = variables::substitute(&batch.prompt, &row.inputs);
+        match queue::enqueue(app, prompt, 0, None, Some(batch.recipe_id.clone())) {
+            Ok(job) => history.update_batch_row(&batch.id, row.row_index, "queued", Some(&job.id), None)?,
+            Err(e) => history.update_batch_row(&batch.id, row.row_index, "error", None, Some(&e))?,
+        }
+    }
+    Ok(())
+}
+
+/// Reads the CSV into one set of variable values per row. `mapping` maps column names
+/// to the variables they fill.
+fn read_rows(path: &PathBuf, mapping: &BTreeMap<String, String>) -> Result<Vec<BTreeMap<String, String>>, String> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let headers = reader.headers().map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.clone();
+    let mut columns = Vec::new();
+    for (column, variable) in mapping {
+        let index = headers
+            .iter()
+            .position(|header| header.trim() == column.trim())
+            .ok_or_else(|| format!("{} has no column '{}'", path.display(), column))?;
+        columns.push((index, variable.clone()));
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        if rows.len() == MAX_ROWS {
+            return Err(format!("{} has more than {} rows", path.display(), MAX_ROWS));
+        }
+        let values =
+            columns.iter().map(|(index, variable)| (variable.clone(), record.get(*index).unwrap_or("").to_string()));
+        rows.push(values.collect());
+    }
+    if rows.is_empty() {
+        return Err(format!("{} has no rows", path.display()));
+    }
+    Ok(rows)
+}
+
+fn create(
+    app: &AppHandle,
+    recipe_id: &str,
+    csv_path: PathBuf,
+    mapping: BTreeMap<String, String>,
+    chunk_size: Option<usize>,
+) -> Result<BatchStatus, String> {
+    let prompt = recipes::load(app, recipe_id)?.task.prompt;
+    let rows = read_rows(&csv_path, &mapping)?;
+    // Every row maps the same variables, so checking the first finds what none will fill
+    let missing = variables::missing_variables(app.clone(), variables::substitute(&prompt, &rows[0]))?;
+    if !missing.is_empty() {
+        return Err(format!(
+            "No column is mapped to {} and the active profile has no value for it",
+            missing.iter().map(|name| format!("${{{}}}", name)).collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    let batch = Batch {
+        id: uuid::Uuid::new_v4().to_string(),
+        recipe_id: recipe_id.to_string(),
+        prompt,
+        chunk_size: chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).max(1),
+        created_at: history::now_ms(),
+    };
+    let history = app.state::<History>();
+    history.add_batch(&batch, &rows)?;
+    log::info!("Batch {} of recipe '{}' created with {} rows", batch.id, recipe_id, rows.len());
+    {
+        let _lock = LOCK.lock().unwrap();
+        top_up(app, &batch)?;
+    }
+    progress(app, &batch);
+    status(&history, &batch)
+}
+
+/// Built-in plugin that records how each row's job went and queues the next rows.
+struct BatchPlugin;
+
+impl ShellPlugin for BatchPlugin {
+    fn name(&self) -> &str {
+        "batches"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        let _lock = LOCK.lock().unwrap();
+        let history = ctx.app.state::<History>();
+        let row = match history.batch_row_for_job(&job.job_id) {
+            Ok(Some(row)) => row,
+            Ok(None) => return,
+            Err(e) => {
+                log::warn!("Failed to look up the batch of job {}: {}", job.job_id, e);
+                return;
+            }
+        };
+        let status = if job.status == Some(JobStatus::Success) { "success" } else { "error" };
+        let result = history
+            .update_batch_row(&row.batch_id, row.row_index, status, None, job.message.as_deref())
+            .and_then(|_| load(&history, &row.batch_id))
+            .and_then(|batch| top_up(ctx.app, &batch).map(|_| batch));
+        match result {
+            Ok(batch) => progress(ctx.app, &batch),
+            Err(e) => log::error!("Batch {} could not continue: {}", row.batch_id, e),
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(BatchPlugin), "Queues batch rows as earlier ones finish");
+}
+
+/// Resumes batches that still had rows to queue when the app last quit.
+pub fn setup(app: &AppHandle) {
+    let history = app.state::<History>();
+    let batches = match history.pending_batches() {
+        Ok(batches) => batches,
+        Err(e) => {
+            log::warn!("Batches unavailable: {}", e);
+            return;
+        }
+    };
+    let _lock = LOCK.lock().unwrap();
+    for id in batches {
+        if let Err(e) = load(&history, &id).and_then(|batch| top_up(app, &batch)) {
+            log::warn!("Failed to resume batch {}: {}", id, e);
+        }
+    }
+}
+
+/// Runs a recipe once per CSV row, filling its `${name}` placeholders from the columns
+/// `mapping` names (column -> variable). At most `chunk_size` rows (20 by default) wait
+/// in the queue at a time.
+#[tauri::command]
+pub async fn enqueue_batch(
+    app: AppHandle,
+    recipe_id: String,
+    csv_path: PathBuf,
+    mapping: BTreeMap<String, String>,
+    chunk_size: Option<usize>,
+) -> Result<BatchStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || create(&app, &recipe_id, csv_path, mapping, chunk_size))
+        .await
+        .map_err(|e| format!("Creating the batch failed: {}", e))?
+}
+
+#[tauri::command]
+pub fn get_batch_status(app: AppHandle, id: String) -> Result<BatchStatus, String> {
+    let history = app.state::<History>();
+    status(&history, &load(&history, &id)?)
+}
+
+/// A batch's rows in order, optionally only those with `status`.
+#[tauri::command]
+pub fn list_batch_rows(
+    app: AppHandle,
+    id: String,
+    status: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<BatchRow>, String> {
+    app.state::<History>().batch_rows(&id, status.as_deref(), limit.unwrap_or(MAX_ROWS))
+}
+
+/// Stops a batch: rows not queued yet are cancelled and queued ones leave the queue
+/// unless already running.
+#[tauri::command]
+pub fn cancel_batch(app: AppHandle, id: String) -> Result<BatchStatus, String> {
+    let _lock = LOCK.lock().unwrap();
+    let history = app.state::<History>();
+    let batch = load(&history, &id)?;
+    history.cancel_pending_batch_rows(&id)?;
+    for row in history.batch_rows(&id, Some("queued"), MAX_ROWS)? {
+        let Some(job_id) = row.job_id.as_deref() else {
+            continue;
+        };
+        if queue::cancel(&app, job_id)? {
+            history.update_batch_row(&id, row.row_index, "cancelled", None, None)?;
+        }
+    }
+    log::info!("Batch {} cancelled", id);
+    progress(&app, &batch);
+    status(&history, &batch)
+}
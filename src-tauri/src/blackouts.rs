@@ -0,0 +1,124 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SettingsStore;
+use crate::timetable;
+
+/// A period (e.g. holidays, a maintenance night) in which scheduled runs are suppressed.
+/// Every condition that is set must hold; a window that sets none never applies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlackoutWindow {
+    pub name: String,
+    pub enabled: bool,
+    /// Days (`2026-12-25`) or inclusive ranges (`2026-12-24..2027-01-01`).
+    pub dates: Vec<String>,
+    /// e.g. `sat`, `sun`.
+    pub weekdays: Vec<String>,
+    /// `HH:MM`, local to `time_zone`. A range ending before it starts crosses midnight.
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    /// IANA time zone the dates and times are in; unset follows the machine's zone.
+    pub time_zone: Option<String>,
+}
+
+impl Default for BlackoutWindow {
+    fn default() -> Self {
+        BlackoutWindow {
+            name: String::new(),
+            enabled: true,
+            dates: Vec::new(),
+            weekdays: Vec::new(),
+            start_time: None,
+            end_time: None,
+            time_zone: None,
+        }
+    }
+}
+
+/// A window with its conditions parsed.
+struct Conditions {
+    zone: Tz,
+    dates: Vec<(NaiveDate, NaiveDate)>,
+    weekdays: Vec<Weekday>,
+    times: Option<(NaiveTime, Option<NaiveTime>)>,
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| format!("Invalid date '{}'", date.trim()))
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| format!("Invalid time '{}'", time.trim()))
+}
+
+impl BlackoutWindow {
+    fn conditions(&self) -> Result<Conditions, String> {
+        let dates = self
+            .dates
+            .iter()
+            .map(|entry| match entry.split_once("..") {
+                Some((first, last)) => Ok((parse_date(first)?, parse_date(last)?)),
+                None => parse_date(entry).map(|date| (date, date)),
+            })
+            .collect::<Result<_, String>>()?;
+        let weekdays = self
+            .weekdays
+            .iter()
+            .map(|day| day.trim().parse::<Weekday>().map_err(|_| format!("Invalid weekday '{}'", day.trim())))
+            .collect::<Result<_, _>>()?;
+        let times = match (self.start_time.as_deref(), self.end_time.as_deref()) {
+            (None, None) => None,
+            (start, end) => Some((
+                start.map(parse_time).transpose()?.unwrap_or(NaiveTime::MIN),
+                end.map(parse_time).transpose()?,
+            )),
+        };
+        Ok(Conditions { zone: timetable::zone(self.time_zone.as_deref())?, dates, weekdays, times })
+    }
+
+    /// Whether the window covers `at`; a disabled window never does.
+    pub fn matches(&self, at: DateTime<Utc>) -> Result<bool, String> {
+        let conditions = self.conditions()?;
+        let unconditional = conditions.dates.is_empty() && conditions.weekdays.is_empty() && conditions.times.is_none();
+        if !self.enabled || unconditional {
+            return Ok(false);
+        }
+        let local = at.with_timezone(&conditions.zone).naive_local();
+        let on_date = conditions.dates.is_empty()
+            || conditions.dates.iter().any(|(first, last)| (*first..=*last).contains(&local.date()));
+        let on_weekday = conditions.weekdays.is_empty() || conditions.weekdays.contains(&local.weekday());
+        let at_time = match conditions.times {
+            None => true,
+            Some((start, None)) => local.time() >= start,
+            Some((start, Some(end))) if start <= end => local.time() >= start && local.time() < end,
+            Some((start, Some(end))) => local.time() >= start || local.time() < end,
+        };
+        Ok(on_date && on_weekday && at_time)
+    }
+}
+
+/// The window of `windows` covering `at`, if any. Windows that can't be parsed are
+/// logged and skipped rather than blocking every run.
+pub fn covering(windows: &[BlackoutWindow], at: DateTime<Utc>) -> Option<&BlackoutWindow> {
+    windows.iter().find(|window| match window.matches(at) {
+        Ok(matches) => matches,
+        Err(e) => {
+            log::warn!("Ignoring blackout window '{}': {}", window.name, e);
+            false
+        }
+    })
+}
+
+/// The configured window covering `at`, if any.
+pub fn active(app: &AppHandle, at: DateTime<Utc>) -> Option<BlackoutWindow> {
+    covering(&app.state::<SettingsStore>().get().blackouts, at).cloned()
+}
+
+/// Checks a window's dates, weekdays and times without saving it.
+#[tauri::command]
+pub fn validate_blackout_window(window: BlackoutWindow) -> Result<(), String> {
+    window.conditions().map(|_| ())
+}
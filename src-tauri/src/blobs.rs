@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::history::{self, History};
+use crate::server;
+
+/// Artifacts at least this big (screenshots, saved pages, downloads) are deduplicated.
+const MIN_BLOB_SIZE: u64 = 256 * 1024;
+
+/// Content-addressed store in the app data dir: `blobs/<first two hex digits>/<sha256>`.
+/// Run artifacts are hard links to their blob, so identical files take space once.
+fn blobs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("blobs"))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+fn blob_path(blobs: &Path, hash: &str) -> PathBuf {
+    blobs.join(&hash[..2]).join(hash)
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DedupeReport {
+    pub files: usize,
+    /// Bytes no longer stored twice.
+    pub bytes_saved: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GcReport {
+    pub blobs_removed: usize,
+    pub bytes_freed: u64,
+}
+
+/// Replaces `file` with a hard link to its blob, adding the blob first if it is new.
+/// Returns the hash and whether an existing blob was reused.
+fn store(blobs: &Path, file: &Path) -> Result<(String, bool), String> {
+    let hash = server::sha256_file(file).map_err(|e| format!("Failed to hash {}: {}", file.display(), e))?;
+    let blob = blob_path(blobs, &hash);
+    if blob.exists() {
+        // Link next to the file first so it is never missing if linking fails
+        let mut temp = file.as_os_str().to_owned();
+        temp.push(".dedupe");
+        let temp = PathBuf::from(temp);
+        std::fs::hard_link(&blob, &temp).map_err(|e| format!("Failed to link {}: {}", file.display(), e))?;
+        std::fs::rename(&temp, file).map_err(|e| {
+            let _ = std::fs::remove_file(&temp);
+            format!("Failed to replace {}: {}", file.display(), e)
+        })?;
+        return Ok((hash, true));
+    }
+    if let Some(dir) = blob.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::hard_link(file, &blob).map_err(|e| format!("Failed to store {}: {}", file.display(), e))?;
+    Ok((hash, false))
+}
+
+/// Moves a run's large artifacts into the blob store. Files the store cannot link to
+/// (e.g. on another volume) are left as they are.
+pub fn dedupe_run(app: &AppHandle, run_id: &str) -> Result<DedupeReport, String> {
+    let Some(dir) = history::artifacts_dir(app, run_id).filter(|dir| dir.is_dir()) else {
+        return Ok(DedupeReport::default());
+    };
+    let blobs = blobs_dir(app)?;
+    let history = app.state::<History>();
+    let mut report = DedupeReport::default();
+    for (file, name) in history::artifact_files(&dir)? {
+        let size = std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+        if size < MIN_BLOB_SIZE {
+            continue;
+        }
+        match store(&blobs, &file) {
+            Ok((hash, reused)) => {
+                history.add_blob_ref(run_id, &name, &hash)?;
+                report.files += 1;
+                if reused {
+                    report.bytes_saved += size;
+                }
+            }
+            Err(e) => log::warn!("Artifact {} of run {} not deduplicated: {}", name, run_id, e),
+        }
+    }
+    if report.bytes_saved > 0 {
+        log::info!("Deduplicated run {}: {} bytes saved", run_id, report.bytes_saved);
+    }
+    Ok(report)
+}
+
+/// Deduplicates a finished run's artifacts in the background.
+pub fn run_finished(app: &AppHandle, run_id: &str) {
+    let (app, run_id) = (app.clone(), run_id.to_string());
+    std::thread::spawn(move || {
+        if let Err(e) = dedupe_run(&app, &run_id) {
+            log::warn!("Failed to deduplicate artifacts of run {}: {}", run_id, e);
+        }
+    });
+}
+
+/// Drops references to artifacts that no longer exist (e.g. purged by retention), then
+/// deletes blobs nothing refers to.
+pub fn gc(app: &AppHandle) -> Result<GcReport, String> {
+    let blobs = blobs_dir(app)?;
+    let history = app.state::<History>();
+    let mut live = HashSet::new();
+    for (run_id, name, hash) in history.blob_refs()? {
+        let exists = history::artifacts_dir(app, &run_id).is_some_and(|dir| dir.join(&name).is_file());
+        if exists {
+            live.insert(hash);
+        } else {
+            history.remove_blob_ref(&run_id, &name)?;
+        }
+    }
+
+    let mut report = GcReport::default();
+    let Ok(shards) = std::fs::read_dir(&blobs) else {
+        return Ok(report);
+    };
+    for shard in shards.flatten().filter(|e| e.file_type().is_ok_and(|t| t.is_dir())) {
+        for blob in std::fs::read_dir(shard.path()).into_iter().flatten().flatten() {
+            if live.contains(&*blob.file_name().to_string_lossy()) {
+                continue;
+            }
+            let size = blob.metadata().map(|m| m.len()).unwrap_or(0);
+            match std::fs::remove_file(blob.path()) {
+                Ok(()) => {
+                    report.blobs_removed += 1;
+                    // Counts space that is only freed once no run links to the file either
+                    report.bytes_freed += size;
+                }
+                Err(e) => log::warn!("Failed to remove blob {}: {}", blob.path().display(), e),
+            }
+        }
+        let _ = std::fs::remove_dir(shard.path());
+    }
+    if report.blobs_removed > 0 {
+        log::info!("Collected {} unreferenced blobs ({} bytes)", report.blobs_removed, report.bytes_freed);
+    }
+    Ok(report)
+}
+
+/// Deletes stored blobs no run artifact refers to anymore.
+#[tauri::command]
+pub async fn gc_artifacts(app: AppHandle) -> Result<GcReport, String> {
+    tauri::async_runtime::spawn_blocking(move || gc(&app))
+        .await
+        .map_err(|e| format!("Artifact cleanup failed: {}", e))?
+}
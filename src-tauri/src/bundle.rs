@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::packaging;
+
+// Written into an extracted copy once it is complete, so an interrupted copy is never used.
+const EXTRACTED_STAMP: &str = ".extracted";
+
+/// How the server binary is laid out on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleLayout {
+    /// Onedir build copied into the app data dir. Preferred: starts without self-extraction
+    /// and does not depend on the (possibly read-only or translocated) app bundle.
+    Extracted,
+    /// Onedir build run straight from the app resources.
+    Onedir,
+    /// PyInstaller onefile binary that unpacks itself to a temp dir on every start.
+    Onefile,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerBundle {
+    pub layout: BundleLayout,
+    pub binary: PathBuf,
+}
+
+/// The `bundle-bin` directory shipped in the app resources.
+pub fn resource_bundle_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let resource_dir = app
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Failed to get resource dir: {}", e))?;
+
+    // Resources specified in tauri.conf.json with ../ paths are placed in _up_ subdirectory,
+    // unless a Linux repackaging moved them
+    let candidates = packaging::bundle_dir_candidates(&resource_dir);
+    let found = candidates.iter().find(|dir| dir.join("server").exists());
+    Ok(found.unwrap_or(&candidates[0]).clone())
+}
+
+/// Where the onedir bundle for this app version is copied to.
+fn extracted_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(data_dir.join("server").join(env!("CARGO_PKG_VERSION")))
+}
+
+fn is_onedir(dir: &Path) -> bool {
+    // PyInstaller >= 6 puts the onedir payload in _internal next to the executable
+    dir.join("_internal").is_dir()
+}
+
+/// Reports the layout that would be used right now, without extracting anything.
+pub fn detect<R: Runtime>(app: &AppHandle<R>) -> Result<ServerBundle, String> {
+    let extracted = extracted_dir(app)?;
+    if extracted.join(EXTRACTED_STAMP).exists() {
+        return Ok(ServerBundle { layout: BundleLayout::Extracted, binary: extracted.join("server") });
+    }
+
+    let source = resource_bundle_dir(app)?;
+    let layout = if is_onedir(&source) { BundleLayout::Onedir } else { BundleLayout::Onefile };
+    Ok(ServerBundle { layout, binary: source.join("server") })
+}
+
+/// Resolves the server binary to launch, copying a onedir bundle into the app data dir on
+/// first run. Falls back to running from the resources if the copy fails.
+pub fn prepare<R: Runtime>(app: &AppHandle<R>) -> Result<ServerBundle, String> {
+    let bundle = detect(app)?;
+    if bundle.layout != BundleLayout::Onedir {
+        return Ok(bundle);
+    }
+
+    let source = resource_bundle_dir(app)?;
+    let target = extracted_dir(app)?;
+    log::info!("Copying server bundle to {:?} (first run of this version)...", target);
+    match extract(&source, &target) {
+        Ok(()) => {
+            log::info!("Server bundle extracted");
+            remove_stale_versions(&target);
+            Ok(ServerBundle { layout: BundleLayout::Extracted, binary: target.join("server") })
+        }
+        Err(e) => {
+            log::warn!("Failed to extract server bundle, running from resources: {}", e);
+            Ok(bundle)
+        }
+    }
+}
+
+/// Deletes the extracted copy so the next `prepare` copies a fresh one from the resources.
+pub fn remove_extracted<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let dir = extracted_dir(app)?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove {}: {}", dir.display(), e))?;
+    }
+    Ok(())
+}
+
+fn extract(source: &Path, target: &Path) -> std::io::Result<()> {
+    // Copy into a sibling temp dir and rename, so a crash mid-copy leaves nothing behind
+    let staging = target.with_extension("partial");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    if target.exists() {
+        fs::remove_dir_all(target)?;
+    }
+
+    copy_dir_all(source, &staging)?;
+    fs::write(staging.join(EXTRACTED_STAMP), env!("CARGO_PKG_VERSION"))?;
+    fs::rename(&staging, target)
+}
+
+pub fn copy_dir_all(source: &Path, target: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let dest = target.join(entry.file_name());
+
+        if file_type.is_symlink() {
+            // macOS onedir builds link into Python.framework; keep the links relative
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &dest)?;
+            #[cfg(not(unix))]
+            fs::copy(entry.path(), &dest).map(|_| ())?;
+        } else if file_type.is_dir() {
+            copy_dir_all(&entry.path(), &dest)?;
+        } else {
+            // fs::copy keeps permission bits, so the executable stays executable
+            fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes copies extracted by previous app versions.
+fn remove_stale_versions(current: &Path) {
+    let Some(parent) = current.parent() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(parent) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path != current && path.is_dir() {
+            log::info!("Removing server bundle from previous version: {:?}", path);
+            let _ = fs::remove_dir_all(&path);
+        }
+    }
+}
+
+/// Reports which server layout is in use, for support and the settings page.
+#[tauri::command]
+pub fn get_server_layout(app: AppHandle) -> Result<ServerBundle, String> {
+    detect(&app)
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+
+use crate::history;
+use crate::schedules::Schedule;
+use crate::settings::SettingsStore;
+
+/// Occurrences listed per schedule when the caller doesn't say.
+const DEFAULT_OCCURRENCES: u32 = 10;
+const MAX_OCCURRENCES: u32 = 500;
+/// Runs have no fixed length; calendar apps need one to draw the event.
+const EVENT_DURATION: &str = "PT15M";
+
+/// Escapes a TEXT value (RFC 5545 3.3.11).
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace("\r\n", "\\n").replace('\n', "\\n")
+}
+
+/// Adds a content line, folded at 75 octets without splitting a character.
+fn push_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn push_events(ics: &mut String, schedule: &Schedule, occurrences: u32, stamp: &str) -> usize {
+    let timing = match schedule.cron.as_deref().filter(|cron| !cron.trim().is_empty()) {
+        Some(cron) => format!("Runs at '{}' ({})", cron, schedule.time_zone.as_deref().unwrap_or("local time")),
+        None => format!("Runs every {} minutes", schedule.every_minutes.max(1)),
+    };
+    let description = format!("{}.\n\n{}", timing, schedule.prompt);
+    let starts = schedule.upcoming_runs(occurrences as usize);
+    for &start in &starts {
+        let start_utc = history::compact_utc(start);
+        push_line(ics, "BEGIN:VEVENT");
+        push_line(ics, &format!("UID:{}-{}@schedules.browser-automation", schedule.id, start_utc));
+        push_line(ics, &format!("DTSTAMP:{}", stamp));
+        push_line(ics, &format!("DTSTART:{}", start_utc));
+        push_line(ics, &format!("DURATION:{}", EVENT_DURATION));
+        push_line(ics, &format!("SUMMARY:{}", escape(&schedule.name)));
+        push_line(ics, &format!("DESCRIPTION:{}", escape(&description)));
+        push_line(ics, "TRANSP:TRANSPARENT");
+        push_line(ics, "END:VEVENT");
+    }
+    starts.len()
+}
+
+/// The calendar and how many events it holds.
+fn to_ics(schedules: &[Schedule], occurrences: u32) -> (String, usize) {
+    let stamp = history::compact_utc(SystemTime::now());
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//Browser Automation//Schedules//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "X-WR-CALNAME:Browser Automation schedules");
+    let events = schedules.iter().map(|schedule| push_events(&mut ics, schedule, occurrences, &stamp)).sum();
+    push_line(&mut ics, "END:VCALENDAR");
+    (ics, events)
+}
+
+/// Writes the enabled schedules to an iCalendar file, each as its next `occurrences`
+/// runs (10 by default). Returns how many events were written.
+#[tauri::command]
+pub fn export_schedules_ics(app: AppHandle, path: PathBuf, occurrences: Option<u32>) -> Result<usize, String> {
+    let occurrences = occurrences.unwrap_or(DEFAULT_OCCURRENCES).clamp(1, MAX_OCCURRENCES);
+    let schedules: Vec<Schedule> =
+        app.state::<SettingsStore>().get().schedules.into_iter().filter(|s| s.enabled).collect();
+    let (ics, events) = to_ics(&schedules, occurrences);
+    std::fs::write(&path, ics).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    log::info!("Exported {} schedules to {}", schedules.len(), path.display());
+    Ok(events)
+}
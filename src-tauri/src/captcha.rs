@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::history::{self, CaptchaSolve, History};
+use crate::secrets;
+use crate::settings::SettingsStore;
+
+pub const API_KEY_SECRET: &str = "captcha-api-key";
+/// Longest a solve may take; providers typically answer in 10–60 seconds.
+const SOLVE_TIMEOUT: Duration = Duration::from_secs(150);
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// The spending limit covers this window.
+const LIMIT_WINDOW_MS: i64 = 24 * 60 * 60 * 1000;
+const DEFAULT_LIMIT: usize = 100;
+
+/// Captcha-solving services. All of them speak the `createTask`/`getTaskResult` JSON API,
+/// with their own task names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptchaProvider {
+    #[serde(rename = "2captcha")]
+    TwoCaptcha,
+    #[serde(rename = "anti-captcha")]
+    AntiCaptcha,
+    #[serde(rename = "capsolver")]
+    CapSolver,
+}
+
+impl CaptchaProvider {
+    fn name(self) -> &'static str {
+        match self {
+            CaptchaProvider::TwoCaptcha => "2captcha",
+            CaptchaProvider::AntiCaptcha => "anti-captcha",
+            CaptchaProvider::CapSolver => "capsolver",
+        }
+    }
+
+    fn endpoint(self) -> &'static str {
+        match self {
+            CaptchaProvider::TwoCaptcha => "https://api.2captcha.com",
+            CaptchaProvider::AntiCaptcha => "https://api.anti-captcha.com",
+            CaptchaProvider::CapSolver => "https://api.capsolver.com",
+        }
+    }
+
+    /// The provider's task for a captcha type, or `None` if it can't solve that type.
+    fn task_type(self, captcha_type: &str) -> Option<&'static str> {
+        match (self, captcha_type) {
+            (CaptchaProvider::CapSolver, "recaptcha_v2") => Some("ReCaptchaV2TaskProxyLess"),
+            (CaptchaProvider::CapSolver, "turnstile") => Some("AntiTurnstileTaskProxyLess"),
+            (CaptchaProvider::CapSolver, _) => None,
+            (_, "recaptcha_v2") => Some("RecaptchaV2TaskProxyless"),
+            (_, "hcaptcha") => Some("HCaptchaTaskProxyless"),
+            (_, "turnstile") => Some("TurnstileTaskProxyless"),
+            _ => None,
+        }
+    }
+}
+
+/// A third-party captcha solver the server may use through the shell. The API key is kept
+/// in the keychain; the shell makes every request, so the server never sees it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptchaSettings {
+    /// `None` turns solving off.
+    pub provider: Option<CaptchaProvider>,
+    /// Another address for the provider's API, e.g. a compatible service.
+    pub endpoint: Option<String>,
+    /// Most that solves may cost in any 24 hours, in USD.
+    pub daily_limit_usd: f64,
+    /// Most solves one job may ask for.
+    pub max_per_job: u32,
+    /// Assumed cost of a solve, for the limit check and for providers that don't report
+    /// what a solve cost.
+    pub estimated_cost_usd: f64,
+}
+
+impl Default for CaptchaSettings {
+    fn default() -> Self {
+        CaptchaSettings {
+            provider: None,
+            endpoint: None,
+            daily_limit_usd: 1.0,
+            max_per_job: 3,
+            estimated_cost_usd: 0.003,
+        }
+    }
+}
+
+/// What the server asks for with `captcha.solve`.
+#[derive(Debug, Clone, Deserialize)]
+struct SolveRequest {
+    job_id: String,
+    /// `recaptcha_v2`, `hcaptcha` or `turnstile`.
+    captcha_type: String,
+    website_url: String,
+    website_key: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptchaUsage {
+    pub provider: Option<CaptchaProvider>,
+    pub has_api_key: bool,
+    /// Solves and their cost over the last 24 hours.
+    pub solves: u32,
+    pub spent_usd: f64,
+    pub daily_limit_usd: f64,
+}
+
+/// The origin of a page, for the usage log; paths and queries may carry tokens.
+fn site(url: &str) -> String {
+    tauri::Url::parse(url).map(|url| url.origin().ascii_serialization()).unwrap_or_default()
+}
+
+fn api_call(endpoint: &str, method: &str, body: Value) -> Result<Value, String> {
+    let response: Value = ureq::post(&format!("{}/{}", endpoint.trim_end_matches('/'), method))
+        .timeout(REQUEST_TIMEOUT)
+        .send_json(body)
+        .map_err(|e| format!("Captcha solver request failed: {}", e))?
+        .into_json()
+        .map_err(|e| format!("Captcha solver sent an invalid reply: {}", e))?;
+    if response["errorId"].as_u64().unwrap_or(0) != 0 {
+        let code = response["errorCode"].as_str().unwrap_or("unknown error");
+        let description = response["errorDescription"].as_str().unwrap_or_default();
+        return Err(format!("Captcha solver refused: {} {}", code, description).trim_end().to_string());
+    }
+    Ok(response)
+}
+
+/// Creates the task and polls until it is solved; returns the token and the reported cost.
+fn run_task(endpoint: &str, key: &str, task: Value) -> Result<(String, Option<f64>), String> {
+    let created = api_call(endpoint, "createTask", serde_json::json!({ "clientKey": key, "task": task }))?;
+    let task_id = created["taskId"].clone();
+    if task_id.is_null() {
+        return Err("Captcha solver returned no task id".to_string());
+    }
+    let started = Instant::now();
+    while started.elapsed() < SOLVE_TIMEOUT {
+        std::thread::sleep(POLL_INTERVAL);
+        let result = api_call(endpoint, "getTaskResult", serde_json::json!({ "clientKey": key, "taskId": task_id }))?;
+        if result["status"] != "ready" {
+            continue;
+        }
+        let solution = &result["solution"];
+        let token = solution["gRecaptchaResponse"].as_str().or_else(|| solution["token"].as_str());
+        let token = token.ok_or("Captcha solver returned no token")?.to_string();
+        // Reported as a string by some providers, a number by others
+        let cost = result["cost"].as_f64().or_else(|| result["cost"].as_str().and_then(|cost| cost.parse().ok()));
+        return Ok((token, cost));
+    }
+    Err(format!("Captcha not solved within {} seconds", SOLVE_TIMEOUT.as_secs()))
+}
+
+fn record(app: &AppHandle, request: &SolveRequest, provider: &str, outcome: &str, cost_usd: f64) {
+    let solve = CaptchaSolve {
+        at: history::now_ms(),
+        job_id: request.job_id.clone(),
+        provider: provider.to_string(),
+        captcha_type: request.captcha_type.clone(),
+        site: site(&request.website_url),
+        outcome: outcome.to_string(),
+        cost_usd,
+    };
+    if let Err(e) = app.state::<History>().add_captcha_solve(&solve) {
+        log::warn!("Failed to record captcha solve for job {}: {}", request.job_id, e);
+    }
+}
+
+/// Serves `captcha.solve`: has the configured provider solve a captcha for a job, within
+/// the spending limits. Blocks until solved. Every request is recorded with its outcome.
+pub fn solve(app: &AppHandle, params: &Value) -> Result<Value, String> {
+    let request: SolveRequest =
+        serde_json::from_value(params.clone()).map_err(|e| format!("Invalid captcha request: {}", e))?;
+    let settings = app.state::<SettingsStore>().get().captcha;
+    let provider = settings.provider.ok_or("No captcha solver is configured")?;
+    let refuse = |outcome: &str, message: String| {
+        log::warn!("Captcha for job {} not solved: {}", request.job_id, message);
+        record(app, &request, provider.name(), outcome, 0.0);
+        Err(message)
+    };
+
+    let Some(task_type) = provider.task_type(&request.captcha_type) else {
+        let message = format!("{} can't solve {} captchas", provider.name(), request.captcha_type);
+        return refuse("unsupported", message);
+    };
+    let Some(key) = secrets::get(API_KEY_SECRET)? else {
+        return refuse("no_api_key", "No captcha solver API key is set".to_string());
+    };
+    let history = app.state::<History>();
+    if history.captcha_requests_for_job(&request.job_id)? >= settings.max_per_job {
+        let message = format!("The job already asked for {} captcha solves", settings.max_per_job);
+        return refuse("job_limit", message);
+    }
+    let (_, spent) = history.captcha_spend_since(history::now_ms() - LIMIT_WINDOW_MS)?;
+    if spent + settings.estimated_cost_usd > settings.daily_limit_usd {
+        let message = format!("Captcha spending reached the limit of ${:.2} a day", settings.daily_limit_usd);
+        return refuse("daily_limit", message);
+    }
+
+    let endpoint = settings.endpoint.as_deref().unwrap_or(provider.endpoint());
+    let task = serde_json::json!({
+        "type": task_type,
+        "websiteURL": request.website_url,
+        "websiteKey": request.website_key,
+    });
+    let origin = site(&request.website_url);
+    log::info!("Solving a {} captcha on {} for job {}", request.captcha_type, origin, request.job_id);
+    match run_task(endpoint, &key, task) {
+        Ok((token, cost)) => {
+            let cost = cost.unwrap_or(settings.estimated_cost_usd);
+            record(app, &request, provider.name(), "solved", cost);
+            Ok(serde_json::json!({ "token": token }))
+        }
+        Err(e) => refuse("failed", e),
+    }
+}
+
+/// Stores the solver's API key in the keychain; `None` removes it.
+#[tauri::command]
+pub fn set_captcha_api_key(key: Option<String>) -> Result<(), String> {
+    match key.filter(|key| !key.trim().is_empty()) {
+        Some(key) => secrets::set(API_KEY_SECRET, key.trim()),
+        None => secrets::delete(API_KEY_SECRET),
+    }
+}
+
+/// The solver's setup and what it cost over the last 24 hours.
+#[tauri::command]
+pub fn get_captcha_usage(app: AppHandle) -> Result<CaptchaUsage, String> {
+    let settings = app.state::<SettingsStore>().get().captcha;
+    let (solves, spent_usd) = app.state::<History>().captcha_spend_since(history::now_ms() - LIMIT_WINDOW_MS)?;
+    Ok(CaptchaUsage {
+        provider: settings.provider,
+        has_api_key: secrets::get(API_KEY_SECRET)?.is_some(),
+        solves,
+        spent_usd,
+        daily_limit_usd: settings.daily_limit_usd,
+    })
+}
+
+/// Solver requests with their outcome and cost, newest first.
+#[tauri::command]
+pub fn list_captcha_solves(app: AppHandle, limit: Option<usize>) -> Result<Vec<CaptchaSolve>, String> {
+    app.state::<History>().captcha_solves(limit.unwrap_or(DEFAULT_LIMIT))
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::events::{JobEvent, JobStatus};
+use crate::history::{self, History, MonitorChange};
+use crate::integrations;
+use crate::notifications::{self, Category, Notification, Urgency};
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::run_diff::{diff_values, ChangeKind, OutputChange};
+use crate::schedules::Schedule;
+use crate::settings::SettingsStore;
+
+/// Changes returned when no limit is given.
+const DEFAULT_LIMIT: usize = 100;
+/// Changes named in a notification; the rest are counted.
+const NOTIFIED_CHANGES: usize = 3;
+
+/// Turns a schedule into a page monitor: each run's output is compared with the previous
+/// successful run's, changes are kept, and meaningful ones alert.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorSettings {
+    pub enabled: bool,
+    /// Field names whose changes never count, wherever they appear (e.g. `updated_at`).
+    pub ignore_fields: Vec<String>,
+    /// Numbers that moved by less than this percentage don't count.
+    pub numeric_tolerance_percent: f64,
+    /// Text that differs only in case or whitespace doesn't count.
+    pub ignore_case_and_whitespace: bool,
+    /// Changes that must count before anyone is alerted; 0 is treated as 1.
+    pub min_changes: usize,
+    /// Name of a configured webhook to call on meaningful changes.
+    pub webhook: Option<String>,
+}
+
+/// The JSON pointer's keys, unescaped.
+fn segments(path: &str) -> impl Iterator<Item = String> + '_ {
+    path.split('/').skip(1).map(|key| key.replace("~1", "/").replace("~0", "~"))
+}
+
+fn normalized(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+impl MonitorSettings {
+    /// Whether a change is big enough to count under these settings.
+    fn counts(&self, change: &OutputChange) -> bool {
+        if segments(&change.path).any(|key| self.ignore_fields.contains(&key)) {
+            return false;
+        }
+        let (Some(before), Some(after)) = (&change.before, &change.after) else {
+            return true;
+        };
+        match (before, after) {
+            (Value::Number(before), Value::Number(after)) if self.numeric_tolerance_percent > 0.0 => {
+                let (before, after) = (before.as_f64().unwrap_or_default(), after.as_f64().unwrap_or_default());
+                let moved = if before == 0.0 { f64::INFINITY } else { ((after - before) / before).abs() * 100.0 };
+                moved >= self.numeric_tolerance_percent
+            }
+            (Value::String(before), Value::String(after)) if self.ignore_case_and_whitespace => {
+                normalized(before) != normalized(after)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// One line per change, e.g. `/0/price: 99 → 89`.
+fn describe(change: &OutputChange) -> String {
+    let value = |value: &Option<Value>| match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let path = if change.path.is_empty() { "output" } else { &change.path };
+    match change.kind {
+        ChangeKind::Added => format!("{}: added {}", path, value(&change.after)),
+        ChangeKind::Removed => format!("{}: removed {}", path, value(&change.before)),
+        ChangeKind::Changed => format!("{}: {} → {}", path, value(&change.before), value(&change.after)),
+    }
+}
+
+fn alert(app: &AppHandle, schedule: &Schedule, job: &JobEvent, change: &MonitorChange) {
+    let counted: Vec<&OutputChange> = change.changes.iter().filter(|c| schedule.monitor.counts(c)).collect();
+    let mut body: Vec<String> = counted.iter().take(NOTIFIED_CHANGES).map(|c| describe(c)).collect();
+    if counted.len() > NOTIFIED_CHANGES {
+        body.push(format!("and {} more", counted.len() - NOTIFIED_CHANGES));
+    }
+    log::info!("Monitor '{}' saw {} changes in run {}", schedule.name, counted.len(), job.job_id);
+    let _ = app.emit("monitor-change", change);
+    notifications::notify(
+        app,
+        Notification {
+            title: format!("{} changed", schedule.name),
+            body: body.join("\n"),
+            urgency: Urgency::Normal,
+            category: Category::Monitors,
+        },
+    );
+
+    let Some(webhook) = schedule.monitor.webhook.as_deref() else {
+        return;
+    };
+    let webhooks = app.state::<SettingsStore>().get().webhooks;
+    let Some(config) = webhooks.iter().find(|w| w.name == webhook) else {
+        log::warn!("Monitor '{}' names a webhook '{}' that does not exist", schedule.name, webhook);
+        return;
+    };
+    let mut event = job.clone();
+    event.message = Some(format!("{} changed: {}", schedule.name, body.join("; ")));
+    if let Err(e) = integrations::send(config, &event) {
+        log::warn!("Monitor webhook '{}' failed: {}", webhook, e);
+    }
+}
+
+/// Compares a monitored schedule's run with the one before it. Returns the recorded
+/// change, or `None` when nothing changed or there is nothing to compare with yet.
+fn compare(app: &AppHandle, schedule: &Schedule, run_id: &str) -> Result<Option<MonitorChange>, String> {
+    let history = app.state::<History>();
+    let run = history.get(run_id)?.ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let Some(previous) = history.previous_successful_run(&schedule.id, run_id)? else {
+        log::info!("Monitor '{}' has its first run {} to compare with", schedule.name, run_id);
+        return Ok(None);
+    };
+    let mut changes = Vec::new();
+    diff_values(String::new(), previous.output.as_ref(), run.output.as_ref(), &mut changes);
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    let counted = changes.iter().filter(|c| schedule.monitor.counts(c)).count();
+    let change = MonitorChange {
+        schedule_id: schedule.id.clone(),
+        run_id: run_id.to_string(),
+        previous_run_id: previous.id,
+        significant: counted >= schedule.monitor.min_changes.max(1),
+        changes,
+        at: history::now_ms(),
+    };
+    history.add_monitor_change(&change)?;
+    Ok(Some(change))
+}
+
+/// Built-in plugin that compares successful runs of monitored schedules with the run
+/// before them.
+struct ChangeMonitorPlugin;
+
+impl ShellPlugin for ChangeMonitorPlugin {
+    fn name(&self) -> &str {
+        "change-monitor"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        // A failed run says nothing about whether the page changed
+        if job.status != Some(JobStatus::Success) {
+            return;
+        }
+        let Some(schedule_id) = job.schedule_id.as_deref() else {
+            return;
+        };
+        let settings = ctx.app.state::<SettingsStore>().get();
+        let Some(schedule) = settings.schedules.iter().find(|s| s.id == schedule_id && s.monitor.enabled) else {
+            return;
+        };
+        match compare(ctx.app, schedule, &job.job_id) {
+            Ok(Some(change)) if change.significant => alert(ctx.app, schedule, job, &change),
+            Ok(_) => {}
+            Err(e) => log::warn!("Monitor '{}' could not compare run {}: {}", schedule.name, job.job_id, e),
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(ChangeMonitorPlugin), "Compares runs of monitored schedules and alerts on changes");
+}
+
+/// Changes seen by monitored schedules, newest first, optionally of one schedule and only
+/// those that alerted.
+#[tauri::command]
+pub fn list_monitor_changes(
+    app: AppHandle,
+    schedule_id: Option<String>,
+    significant_only: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<MonitorChange>, String> {
+    app.state::<History>().monitor_changes(
+        schedule_id.as_deref(),
+        significant_only.unwrap_or(false),
+        limit.unwrap_or(DEFAULT_LIMIT),
+    )
+}
@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::history::History;
+use crate::settings::SettingsStore;
+use crate::{jobs, launch, license, secrets, security, server, AppState};
+
+/// Keychain entry holding the bearer token clients must send.
+pub const TOKEN_KEY: &str = "control-api-token";
+
+/// Localhost API that lets scripts and other apps drive automations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// LAN mode: listen on every interface so other machines on the network can call
+    /// the API, not just this one.
+    pub lan: bool,
+}
+
+impl Default for ControlApiSettings {
+    fn default() -> Self {
+        ControlApiSettings { enabled: false, port: 5690, lan: false }
+    }
+}
+
+impl ControlApiSettings {
+    /// Whether other machines can reach the API with these settings.
+    pub fn exposed(&self) -> bool {
+        self.enabled && self.lan
+    }
+
+    /// Where to listen; every interface only in LAN mode and if the license allows it.
+    fn address(&self, lan_allowed: bool) -> Address {
+        (if self.lan && lan_allowed { "0.0.0.0" } else { "127.0.0.1" }, self.port)
+    }
+}
+
+/// Host and port the listener binds.
+type Address = (&'static str, u16);
+
+/// The running listener and the address it is bound to, if any, and the token it accepts.
+#[derive(Default)]
+pub struct ControlApi {
+    server: Mutex<Option<(Address, Arc<Server>)>>,
+    token: Mutex<String>,
+}
+
+/// Returns the API token, creating one the first time.
+fn token() -> Result<String, String> {
+    match secrets::get(TOKEN_KEY)? {
+        Some(token) => Ok(token),
+        None => reset_token(),
+    }
+}
+
+fn reset_token() -> Result<String, String> {
+    let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    secrets::set(TOKEN_KEY, &token)?;
+    Ok(token)
+}
+
+/// Compares without bailing out at the first differing byte.
+pub fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Starts, stops or moves the listener to match the current settings.
+pub fn apply_settings(app: &AppHandle) {
+    let settings = app.state::<SettingsStore>().get().control_api;
+    let api = app.state::<ControlApi>();
+    let mut current = api.server.lock().unwrap();
+
+    let lan_allowed = license::entitlements(app).lan_mode;
+    if settings.enabled && settings.lan && !lan_allowed {
+        log::warn!("The license does not include LAN mode; the control API only listens on this computer");
+    }
+    // Viewer mode must not take jobs from outside either
+    let wanted = (settings.enabled && !launch::is_viewer(app)).then(|| settings.address(lan_allowed));
+    if current.as_ref().map(|(address, _)| *address) == wanted {
+        return;
+    }
+    if let Some(((_, port), server)) = current.take() {
+        log::info!("Stopping control API on port {}", port);
+        server.unblock();
+    }
+    let Some(address) = wanted else {
+        return;
+    };
+
+    match token() {
+        Ok(token) => *api.token.lock().unwrap() = token,
+        Err(e) => {
+            log::error!("Control API not started: {}", e);
+            return;
+        }
+    }
+    let (host, port) = address;
+    match Server::http(address) {
+        Ok(server) => {
+            log::info!("Control API listening on {}:{}", host, port);
+            let server = Arc::new(server);
+            *current = Some((address, server.clone()));
+            let app = app.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(&app, request);
+                }
+            });
+        }
+        Err(e) => log::error!("Control API failed to listen on port {}: {}", port, e),
+    }
+}
+
+fn json_response(status: u16, body: serde_json::Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    Response::from_string(body.to_string()).with_status_code(status).with_header(header)
+}
+
+fn error(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    json_response(status, serde_json::json!({ "error": message }))
+}
+
+fn handle(app: &AppHandle, mut request: Request) {
+    let token = app.state::<ControlApi>().token.lock().unwrap().clone();
+    let authorized = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| !token.is_empty() && token_matches(given, &token));
+
+    let response = if authorized {
+        route(app, &mut request)
+    } else {
+        error(401, "Missing or invalid token")
+    };
+    if let Err(e) = request.respond(response) {
+        log::warn!("Control API failed to respond: {}", e);
+    }
+}
+
+fn route(app: &AppHandle, request: &mut Request) -> Response<std::io::Cursor<Vec<u8>>> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let history = app.state::<History>();
+
+    match (request.method(), segments.as_slice()) {
+        (&Method::Get, ["v1", "status"]) => {
+            let app_state = app.state::<AppState>();
+            let running = app_state.python_process.lock().unwrap().is_some();
+            let port = *app_state.server_port.lock().unwrap();
+            json_response(
+                200,
+                serde_json::json!({
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "server": { "running": running, "port": port, "ready": running && server::is_ready(port) },
+                }),
+            )
+        }
+        (&Method::Post, ["v1", "jobs"]) => {
+            let mut body = String::new();
+            let _ = request.as_reader().read_to_string(&mut body);
+            let prompt = serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|body| body["prompt"].as_str().map(str::to_string))
+                .filter(|prompt| !prompt.trim().is_empty());
+            let Some(prompt) = prompt else {
+                return error(400, "Body must be JSON with a non-empty \"prompt\"");
+            };
+            let port = *app.state::<AppState>().server_port.lock().unwrap();
+            if !server::is_ready(port) {
+                return error(503, "Server is not ready");
+            }
+            let job_id = jobs::new_job_id();
+            log::info!("Control API started job {}", job_id);
+            let app = app.clone();
+            let id = job_id.clone();
+            std::thread::spawn(move || jobs::run(&app, port, id, &prompt, None, None));
+            json_response(202, serde_json::json!({ "job_id": job_id }))
+        }
+        (&Method::Get, ["v1", "jobs"]) => {
+            let limit = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("limit="))
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or(50);
+            match history.list(limit) {
+                Ok(runs) => json_response(200, serde_json::json!(runs)),
+                Err(e) => error(500, &e),
+            }
+        }
+        (&Method::Get, ["v1", "jobs", id]) => match history.get(id) {
+            Ok(Some(run)) => json_response(200, serde_json::json!(run)),
+            Ok(None) => error(404, "No such job"),
+            Err(e) => error(500, &e),
+        },
+        (&Method::Get, ["v1", "jobs", id, "results"]) => match history.get(id) {
+            Ok(Some(run)) if run.status == "running" => error(409, "Job is still running"),
+            Ok(Some(run)) => json_response(200, run.output.unwrap_or_default()),
+            Ok(None) => error(404, "No such job"),
+            Err(e) => error(500, &e),
+        },
+        _ => error(404, "Not found"),
+    }
+}
+
+/// Token to give to scripts using the control API. The user confirms who they are first.
+#[tauri::command]
+pub async fn get_control_api_token() -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        security::authenticate("show the control API token")?;
+        token()
+    })
+    .await
+    .map_err(|e| format!("Failed to read the token: {}", e))?
+}
+
+/// Issues a new token, revoking the old one immediately. The user confirms who they are
+/// first, as the new token is shown.
+#[tauri::command]
+pub async fn reset_control_api_token(app: AppHandle) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        security::authenticate("create a new control API token")?;
+        let token = reset_token()?;
+        *app.state::<ControlApi>().token.lock().unwrap() = token.clone();
+        Ok(token)
+    })
+    .await
+    .map_err(|e| format!("Failed to reset the token: {}", e))?
+}
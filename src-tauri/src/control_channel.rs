@@ -0,0 +1,396 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::downloads::{self, DownloadRequest};
+use crate::events::{self, JobEvent};
+use crate::failures::{self, FailureCaptured};
+use crate::har::{self, HarReady};
+use crate::recordings::{self, RecordingReady};
+use crate::traces::{self, TraceReady};
+use crate::{captcha, credentials, interactions, live_view, packaging, recorder, AppState};
+
+/// Method names and version, generated from `src/auto_browser/control_protocol.json`.
+mod protocol {
+    include!(concat!(env!("OUT_DIR"), "/control_protocol.rs"));
+}
+
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Pending = Arc<Mutex<HashMap<u64, Sender<Result<Value, String>>>>>;
+
+struct Connection {
+    writer: Mutex<Box<dyn Write + Send>>,
+    pending: Pending,
+    next_id: AtomicU64,
+}
+
+impl Connection {
+    fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+
+        let request = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let result = match writeln!(self.writer.lock().unwrap(), "{}", request) {
+            Ok(()) => rx
+                .recv_timeout(CALL_TIMEOUT)
+                .unwrap_or_else(|_| Err(format!("No reply to '{}' from the server", method))),
+            Err(e) => Err(format!("Control channel write failed: {}", e)),
+        };
+        self.pending.lock().unwrap().remove(&id);
+        result
+    }
+
+    /// Answers a request the server made.
+    fn reply(&self, id: &Value, result: Result<Value, String>) {
+        let reply = match result {
+            Ok(result) => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(message) => {
+                serde_json::json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32000, "message": message } })
+            }
+        };
+        if let Err(e) = writeln!(self.writer.lock().unwrap(), "{}", reply) {
+            log::warn!("Control channel write failed: {}", e);
+        }
+    }
+}
+
+/// Handshake reply; only the fields the shell looks at.
+#[derive(Debug, Deserialize)]
+struct Health {
+    protocol_version: u64,
+    version: String,
+}
+
+/// Typed JSON-RPC channel to the main server for lifecycle and control messages. When it
+/// is not connected (e.g. a server without the channel) the shell falls back to plain
+/// HTTP and killing the process.
+#[derive(Default)]
+pub struct ControlChannel {
+    conn: Mutex<Option<Arc<Connection>>>,
+    /// The server sent `ready`: it is configured and about to open its HTTP port.
+    announced_ready: AtomicBool,
+}
+
+/// How the shell talks to the server it spawns.
+#[derive(Debug, Clone)]
+pub enum Transport {
+    /// Unix socket the server listens on; connected once HTTP is up.
+    Socket(PathBuf),
+    /// The child's stdin/stdout, usable from the moment it starts. Used where Unix
+    /// sockets are unavailable, or when `NOVA_CONTROL_TRANSPORT=stdio` is set.
+    Stdio,
+}
+
+impl Transport {
+    pub fn for_port(port: u16) -> Self {
+        let forced_stdio = std::env::var("NOVA_CONTROL_TRANSPORT").is_ok_and(|t| t == "stdio");
+        if cfg!(unix) && !forced_stdio {
+            let name = format!("nova-control-{}-{}.sock", std::process::id(), port);
+            Transport::Socket(packaging::runtime_dir().join(name))
+        } else {
+            Transport::Stdio
+        }
+    }
+
+    /// Tells the server which transport to serve.
+    pub fn configure(&self, cmd: &mut Command) {
+        match self {
+            Transport::Socket(path) => {
+                cmd.env("NOVA_CONTROL_SOCKET", path);
+            }
+            Transport::Stdio => {
+                cmd.env("NOVA_CONTROL_STDIO", "1").stdin(Stdio::piped()).stdout(Stdio::piped());
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn open(path: &Path) -> std::io::Result<(Box<dyn Read + Send>, Box<dyn Write + Send>)> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    Ok((Box::new(stream.try_clone()?), Box::new(stream)))
+}
+
+#[cfg(not(unix))]
+fn open(_path: &Path) -> std::io::Result<(Box<dyn Read + Send>, Box<dyn Write + Send>)> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "control channel requires Unix sockets"))
+}
+
+/// Reads replies and notifications until the server closes the connection.
+fn read_loop(app: AppHandle, reader: Box<dyn Read + Send>, conn: Arc<Connection>) {
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            log::warn!("Ignoring malformed control message: {}", line);
+            continue;
+        };
+
+        if message["method"] == protocol::READY {
+            let version = message["params"]["protocol_version"].as_u64();
+            let control = &app.state::<AppState>().control;
+            if version == Some(protocol::VERSION) {
+                log::info!("Server announced ready over the control channel");
+                control.announced_ready.store(true, Ordering::Relaxed);
+            } else {
+                log::warn!("Server speaks control protocol {:?}, expected {}", version, protocol::VERSION);
+                control.disconnect();
+            }
+        } else if message["method"] == protocol::JOB_EVENT {
+            match serde_json::from_value::<JobEvent>(message["params"].clone()) {
+                Ok(event) => events::publish(&app, event),
+                Err(e) => log::warn!("Invalid job event from server: {}", e),
+            }
+        } else if message["method"] == protocol::DOWNLOAD_REQUEST {
+            let started = serde_json::from_value::<DownloadRequest>(message["params"].clone())
+                .map_err(|e| e.to_string())
+                .and_then(|request| downloads::start(&app, request, None));
+            if let Err(e) = started {
+                log::warn!("Download requested by the server failed to start: {}", e);
+            }
+        } else if message["method"] == protocol::RECORDING_READY {
+            match serde_json::from_value::<RecordingReady>(message["params"].clone()) {
+                Ok(ready) => recordings::recording_ready(&app, ready),
+                Err(e) => log::warn!("Invalid recording from server: {}", e),
+            }
+        } else if message["method"] == protocol::HAR_READY {
+            match serde_json::from_value::<HarReady>(message["params"].clone()) {
+                Ok(ready) => har::har_ready(&app, ready),
+                Err(e) => log::warn!("Invalid HAR capture from server: {}", e),
+            }
+        } else if message["method"] == protocol::FAILURE_CAPTURED {
+            match serde_json::from_value::<FailureCaptured>(message["params"].clone()) {
+                Ok(captured) => failures::failure_captured(&app, captured),
+                Err(e) => log::warn!("Invalid failure capture from server: {}", e),
+            }
+        } else if message["method"] == protocol::TRACE_READY {
+            match serde_json::from_value::<TraceReady>(message["params"].clone()) {
+                Ok(ready) => traces::trace_ready(&app, ready),
+                Err(e) => log::warn!("Invalid trace from server: {}", e),
+            }
+        } else if message["method"] == protocol::LIVE_FRAME {
+            live_view::frame(&app, message["params"].clone());
+        } else if message["method"] == protocol::RECORD_ACTION {
+            recorder::action(&app, message["params"].clone());
+        } else if message["method"] == protocol::CREDENTIAL_REDEEM {
+            // Keychain reads may block on an unlock prompt
+            let (app, conn) = (app.clone(), conn.clone());
+            std::thread::spawn(move || conn.reply(&message["id"], credentials::redeem(&app, &message["params"])));
+        } else if message["method"] == protocol::CAPTCHA_SOLVE {
+            // Solving takes a while; other messages keep flowing meanwhile
+            let (app, conn) = (app.clone(), conn.clone());
+            std::thread::spawn(move || conn.reply(&message["id"], captcha::solve(&app, &message["params"])));
+        } else if message["method"] == protocol::INTERACTION_REQUEST {
+            // Waits for the user, possibly for minutes
+            let (app, conn) = (app.clone(), conn.clone());
+            std::thread::spawn(move || conn.reply(&message["id"], interactions::request(&app, &message["params"])));
+        } else if let Some(id) = message["id"].as_u64() {
+            let reply = match message.get("error") {
+                Some(error) => Err(error["message"].as_str().unwrap_or("Unknown error").to_string()),
+                None => Ok(message["result"].clone()),
+            };
+            if let Some(tx) = conn.pending.lock().unwrap().remove(&id) {
+                let _ = tx.send(reply);
+            }
+        }
+    }
+    log::info!("Control channel closed");
+    conn.pending.lock().unwrap().clear();
+}
+
+impl ControlChannel {
+    fn connection(&self) -> Option<Arc<Connection>> {
+        self.conn.lock().unwrap().clone()
+    }
+
+    fn attach(&self, app: &AppHandle, reader: Box<dyn Read + Send>, writer: Box<dyn Write + Send>) -> Arc<Connection> {
+        let conn = Arc::new(Connection { writer: Mutex::new(writer), pending: Pending::default(), next_id: AtomicU64::new(1) });
+        self.announced_ready.store(false, Ordering::Relaxed);
+
+        let (app, reader_conn) = (app.clone(), conn.clone());
+        std::thread::spawn(move || read_loop(app, reader, reader_conn));
+        conn
+    }
+
+    /// Takes over a freshly spawned server's stdin/stdout. There is no handshake: the
+    /// server is still starting, and checks in with a `ready` notification.
+    pub fn attach_stdio(&self, app: &AppHandle, child: &mut Child) {
+        let (Some(stdout), Some(stdin)) = (child.stdout.take(), child.stdin.take()) else {
+            log::warn!("Server was not spawned with piped stdio, control channel unavailable");
+            return;
+        };
+        let conn = self.attach(app, Box::new(stdout), Box::new(stdin));
+        *self.conn.lock().unwrap() = Some(conn);
+        log::info!("Control channel attached to server stdio (protocol {})", protocol::VERSION);
+    }
+
+    /// Connects to the server's socket and checks it speaks the same protocol version.
+    pub fn connect_socket(&self, app: &AppHandle, path: &Path) -> Result<(), String> {
+        let (reader, writer) = open(path).map_err(|e| format!("Failed to connect to {}: {}", path.display(), e))?;
+        let conn = self.attach(app, reader, writer);
+
+        let health: Health = serde_json::from_value(conn.call(protocol::HEALTH, serde_json::json!({}))?)
+            .map_err(|e| format!("Unexpected health reply: {}", e))?;
+        if health.protocol_version != protocol::VERSION {
+            return Err(format!(
+                "Server speaks control protocol {}, expected {}",
+                health.protocol_version,
+                protocol::VERSION
+            ));
+        }
+        log::info!("Control channel connected (server {}, protocol {})", health.version, protocol::VERSION);
+        *self.conn.lock().unwrap() = Some(conn);
+        Ok(())
+    }
+
+    pub fn disconnect(&self) {
+        self.conn.lock().unwrap().take();
+    }
+
+    pub fn announced_ready(&self) -> bool {
+        self.announced_ready.load(Ordering::Relaxed)
+    }
+
+    /// Asks the server to close its browser and exit. Returns whether it agreed.
+    pub fn request_shutdown(&self) -> bool {
+        let Some(conn) = self.connection() else {
+            return false;
+        };
+        match conn.call(protocol::SHUTDOWN, serde_json::json!({})) {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!("Graceful shutdown request failed: {}", e);
+                false
+            }
+        }
+    }
+
+    pub fn push_config(&self, config: Value) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::CONFIG_PUSH, config).map(|_| ())
+    }
+
+    pub fn snapshot_session(&self) -> Result<Value, String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::SESSION_SNAPSHOT, serde_json::json!({}))
+    }
+
+    pub fn restore_session(&self, state: Value) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::SESSION_RESTORE, state).map(|_| ())
+    }
+
+    /// Static checks of a prompt by the server; see `recipe.validate` in the schema.
+    pub fn validate_prompt(&self, prompt: &str) -> Result<Value, String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::RECIPE_VALIDATE, serde_json::json!({ "prompt": prompt }))
+    }
+
+    /// Per-site request counts and limits; see `domains.stats` in the schema.
+    pub fn domain_stats(&self) -> Result<Value, String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::DOMAINS_STATS, serde_json::json!({}))
+    }
+
+    /// Queues cookies for the browser; see `cookies.add` in the schema.
+    pub fn add_cookies(&self, cookies: Value) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::COOKIES_ADD, serde_json::json!({ "cookies": cookies })).map(|_| ())
+    }
+
+    /// The browser's cookies as of its last job.
+    pub fn get_cookies(&self) -> Result<Value, String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::COOKIES_GET, serde_json::json!({}))
+    }
+
+    /// Starts streaming the browser, or switches modes; see `live.start` in the schema.
+    pub fn live_start(&self, control: bool) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::LIVE_START, serde_json::json!({ "control": control })).map(|_| ())
+    }
+
+    pub fn live_stop(&self) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::LIVE_STOP, serde_json::json!({})).map(|_| ())
+    }
+
+    pub fn live_input(&self, event: Value) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::LIVE_INPUT, serde_json::json!({ "event": event })).map(|_| ())
+    }
+
+    /// Pauses a job run step by step before its next step; see `job.pause` in the schema.
+    pub fn job_pause(&self, job_id: &str) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::JOB_PAUSE, serde_json::json!({ "job_id": job_id })).map(|_| ())
+    }
+
+    pub fn job_step(&self, job_id: &str) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::JOB_STEP, serde_json::json!({ "job_id": job_id })).map(|_| ())
+    }
+
+    pub fn job_resume(&self, job_id: &str) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::JOB_RESUME, serde_json::json!({ "job_id": job_id })).map(|_| ())
+    }
+
+    /// Applies a recipe's new breakpoints to its running jobs.
+    pub fn job_breakpoints(&self, recipe_id: &str, steps: &[u32]) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        let params = serde_json::json!({ "recipe_id": recipe_id, "steps": steps });
+        conn.call(protocol::JOB_BREAKPOINTS, params).map(|_| ())
+    }
+
+    /// Opens a recording browser on `url`; see `record.start` in the schema.
+    pub fn record_start(&self, url: &str) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::RECORD_START, serde_json::json!({ "url": url })).map(|_| ())
+    }
+
+    pub fn record_pause(&self, paused: bool) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::RECORD_PAUSE, serde_json::json!({ "paused": paused })).map(|_| ())
+    }
+
+    /// Closes the recording browser and returns the actions recorded.
+    pub fn record_stop(&self) -> Result<Value, String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::RECORD_STOP, serde_json::json!({}))
+    }
+
+    pub fn set_log_level(&self, level: &str) -> Result<(), String> {
+        let conn = self.connection().ok_or("Control channel is not connected")?;
+        conn.call(protocol::LOG_LEVEL, serde_json::json!({ "level": level })).map(|_| ())
+    }
+}
+
+/// Turns the running server's verbose logging on or off without a restart.
+#[tauri::command]
+pub async fn set_server_verbose(app: AppHandle, enabled: bool) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<AppState>().control.push_config(serde_json::json!({ "verbose": enabled }))
+    })
+    .await
+    .map_err(|e| format!("Failed to update server config: {}", e))?
+}
+
+/// Changes the running server's log level (`debug`, `info`, `warning` or `error`).
+#[tauri::command]
+pub async fn set_server_log_level(app: AppHandle, level: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<AppState>().control.set_log_level(&level))
+        .await
+        .map_err(|e| format!("Failed to update server log level: {}", e))?
+}
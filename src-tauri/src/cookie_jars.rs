@@ -0,0 +1,477 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::history;
+use crate::{crypto, secrets, security, AppState};
+
+/// Keychain entry holding the key cookie jars are encrypted with.
+pub const KEY_SECRET: &str = "cookie-jar-key";
+const JARS_DIR: &str = "cookie_jars";
+const EXTENSION: &str = "jar";
+
+/// A cookie in the shape Playwright's `add_cookies` takes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// A leading `.` also sends it to subdomains.
+    pub domain: String,
+    pub path: String,
+    /// Unix seconds; -1 for a session cookie.
+    pub expires: f64,
+    pub http_only: bool,
+    pub secure: bool,
+    /// `Strict`, `Lax` or `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<String>,
+}
+
+impl Cookie {
+    fn same(&self, other: &Cookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+
+    /// Whether it is sent to `domain` or its subdomains.
+    fn belongs_to(&self, domain: &str) -> bool {
+        let host = self.domain.trim_start_matches('.').to_ascii_lowercase();
+        let domain = domain.trim().trim_start_matches('.').to_ascii_lowercase();
+        host == domain || host.ends_with(&format!(".{}", domain))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CookieJarInfo {
+    pub name: String,
+    pub cookies: usize,
+    /// Sites with cookies in the jar, sorted.
+    pub domains: Vec<String>,
+    pub updated_at: i64,
+}
+
+/// File formats cookies are exported in.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CookieFormat {
+    /// `cookies.txt`, as curl, wget and most browser extensions read it.
+    #[default]
+    Netscape,
+    /// A JSON array of cookies, as Playwright and Cookie-Editor read it.
+    Json,
+}
+
+/// A browser profile whose cookies can be imported.
+#[derive(Debug, Clone, Serialize)]
+pub struct BrowserProfile {
+    pub browser: String,
+    pub name: String,
+    /// The profile's cookie database, to pass to `import_cookies`.
+    pub path: PathBuf,
+}
+
+/// The key jars are sealed with, created on first use.
+fn jar_key() -> Result<[u8; crypto::KEY_LEN], String> {
+    if let Some(encoded) = secrets::get(KEY_SECRET)? {
+        let bytes = BASE64.decode(encoded).map_err(|_| "Cookie jar key in the keychain is damaged")?;
+        return bytes.try_into().map_err(|_| "Cookie jar key in the keychain is damaged".to_string());
+    }
+    let key = crypto::random_bytes::<{ crypto::KEY_LEN }>()?;
+    secrets::set(KEY_SECRET, &BASE64.encode(key))?;
+    log::info!("Created cookie jar encryption key");
+    Ok(key)
+}
+
+fn jars_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(dir.join(JARS_DIR))
+}
+
+fn jar_path(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ' '));
+    if !valid || name.trim() != name {
+        return Err(format!("Invalid cookie jar name '{}': use letters, digits, spaces, - and _", name));
+    }
+    Ok(jars_dir(app)?.join(format!("{}.{}", name, EXTENSION)))
+}
+
+fn read_jar(app: &AppHandle, name: &str) -> Result<Vec<Cookie>, String> {
+    let path = jar_path(app, name)?;
+    let sealed = match std::fs::read(&path) {
+        Ok(sealed) => sealed,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(format!("No cookie jar named '{}'", name)),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let plaintext = crypto::open(&jar_key()?, &sealed)?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Cookie jar '{}' is damaged: {}", name, e))
+}
+
+fn write_jar(app: &AppHandle, name: &str, cookies: &[Cookie]) -> Result<(), String> {
+    let path = jar_path(app, name)?;
+    let dir = jars_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let plaintext = serde_json::to_vec(cookies).map_err(|e| e.to_string())?;
+    let sealed = crypto::seal(&jar_key()?, &plaintext)?;
+    std::fs::write(&path, sealed).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn info(name: &str, cookies: &[Cookie], updated_at: i64) -> CookieJarInfo {
+    let mut domains: Vec<String> = cookies.iter().map(|c| c.domain.trim_start_matches('.').to_string()).collect();
+    domains.sort();
+    domains.dedup();
+    CookieJarInfo { name: name.to_string(), cookies: cookies.len(), domains, updated_at }
+}
+
+/// Adds `cookies` to a jar, creating it if needed; cookies already in it with the same
+/// name, domain and path are replaced.
+fn merge(app: &AppHandle, name: &str, cookies: Vec<Cookie>) -> Result<CookieJarInfo, String> {
+    let mut jar = match read_jar(app, name) {
+        Ok(jar) => jar,
+        Err(_) if !jar_path(app, name)?.exists() => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    jar.retain(|existing| !cookies.iter().any(|cookie| cookie.same(existing)));
+    jar.extend(cookies);
+    write_jar(app, name, &jar)?;
+    Ok(info(name, &jar, history::now_ms()))
+}
+
+/// `cookies.txt`: tab-separated domain, subdomains flag, path, secure, expiry, name and
+/// value, with HttpOnly cookies' lines prefixed `#HttpOnly_`.
+fn parse_netscape(text: &str) -> Vec<Cookie> {
+    text.lines()
+        .filter_map(|line| {
+            let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                Some(line) => (line, true),
+                None if line.starts_with('#') => return None,
+                None => (line, false),
+            };
+            let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+            let [domain, _, path, secure, expires, name, value] = fields[..] else {
+                return None;
+            };
+            let expires: f64 = expires.parse().unwrap_or(0.0);
+            Some(Cookie {
+                name: name.to_string(),
+                value: value.to_string(),
+                domain: domain.to_string(),
+                path: path.to_string(),
+                expires: if expires > 0.0 { expires } else { -1.0 },
+                http_only,
+                secure: secure.eq_ignore_ascii_case("TRUE"),
+                same_site: None,
+            })
+        })
+        .collect()
+}
+
+fn write_netscape(cookies: &[Cookie]) -> String {
+    let mut text = String::from("# Netscape HTTP Cookie File\n");
+    for cookie in cookies {
+        text.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            if cookie.http_only { "#HttpOnly_" } else { "" },
+            cookie.domain,
+            if cookie.domain.starts_with('.') { "TRUE" } else { "FALSE" },
+            cookie.path,
+            if cookie.secure { "TRUE" } else { "FALSE" },
+            if cookie.expires > 0.0 { cookie.expires as i64 } else { 0 },
+            cookie.name,
+            cookie.value,
+        ));
+    }
+    text
+}
+
+/// `Strict`, `Lax` or `None` from the spellings browsers and extensions use.
+fn same_site(value: &Value) -> Option<String> {
+    match value.as_str()?.to_ascii_lowercase().as_str() {
+        "strict" => Some("Strict".to_string()),
+        "lax" => Some("Lax".to_string()),
+        "none" | "no_restriction" => Some("None".to_string()),
+        _ => None,
+    }
+}
+
+/// A JSON array of cookies, or an object with a `cookies` array (Playwright's storage
+/// state). Both Playwright's field names and Cookie-Editor's are understood.
+fn parse_json(text: &str) -> Result<Vec<Cookie>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| format!("Not a cookie file: {}", e))?;
+    let entries = value.get("cookies").unwrap_or(&value).as_array().ok_or("Not a cookie file: expected a list")?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            let expires = entry["expires"].as_f64().or_else(|| entry["expirationDate"].as_f64());
+            let session = entry["session"].as_bool().unwrap_or(false);
+            Some(Cookie {
+                name: entry["name"].as_str()?.to_string(),
+                value: entry["value"].as_str().unwrap_or_default().to_string(),
+                domain: entry["domain"].as_str()?.to_string(),
+                path: entry["path"].as_str().unwrap_or("/").to_string(),
+                expires: expires.filter(|e| *e > 0.0 && !session).unwrap_or(-1.0),
+                http_only: entry["httpOnly"].as_bool().unwrap_or(false),
+                secure: entry["secure"].as_bool().unwrap_or(false),
+                same_site: same_site(&entry["sameSite"]),
+            })
+        })
+        .collect())
+}
+
+/// Firefox's `cookies.sqlite`. It is locked while Firefox runs, so a copy is read.
+fn read_firefox(path: &Path) -> Result<Vec<Cookie>, String> {
+    let dir = std::env::temp_dir().join(format!("cookie-import-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let read = || -> Result<Vec<Cookie>, String> {
+        let copy = dir.join("cookies.sqlite");
+        std::fs::copy(path, &copy).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        // Recent changes may still be in the write-ahead log
+        let _ = std::fs::copy(path.with_extension("sqlite-wal"), dir.join("cookies.sqlite-wal"));
+        let conn = Connection::open_with_flags(&copy, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut stmt = conn
+            .prepare("SELECT name, value, host, path, expiry, isSecure, isHttpOnly, sameSite FROM moz_cookies")
+            .map_err(|e| format!("{} is not a Firefox cookie database: {}", path.display(), e))?;
+        let rows = stmt.query_map([], |row| {
+            let expiry: i64 = row.get(4)?;
+            // Newer Firefox versions store milliseconds
+            let expiry = if expiry > 100_000_000_000 { expiry / 1000 } else { expiry };
+            Ok(Cookie {
+                name: row.get(0)?,
+                value: row.get(1)?,
+                domain: row.get(2)?,
+                path: row.get(3)?,
+                expires: if expiry > 0 { expiry as f64 } else { -1.0 },
+                secure: row.get::<_, i64>(5)? != 0,
+                http_only: row.get::<_, i64>(6)? != 0,
+                same_site: match row.get::<_, i64>(7)? {
+                    1 => Some("Lax".to_string()),
+                    2 => Some("Strict".to_string()),
+                    _ => None,
+                },
+            })
+        });
+        let cookies = rows.and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>());
+        cookies.map_err(|e| format!("Failed to read cookies from {}: {}", path.display(), e))
+    };
+    let cookies = read();
+    let _ = std::fs::remove_dir_all(&dir);
+    cookies
+}
+
+fn read_file(path: &Path) -> Result<Vec<Cookie>, String> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if extension == "sqlite" {
+        return read_firefox(path);
+    }
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if text.trim_start().starts_with(['[', '{']) {
+        parse_json(&text)
+    } else {
+        Ok(parse_netscape(&text))
+    }
+}
+
+/// Firefox profiles on this machine. Chromium-based browsers encrypt cookies with a key
+/// tied to the OS account, so their profiles can't be read; export a `cookies.txt` from
+/// them with an extension instead.
+fn firefox_profiles() -> Vec<BrowserProfile> {
+    let roots = [
+        dirs::data_dir().map(|dir| dir.join("Firefox").join("Profiles")),
+        dirs::data_dir().map(|dir| dir.join("Mozilla").join("Firefox").join("Profiles")),
+        dirs::home_dir().map(|dir| dir.join(".mozilla").join("firefox")),
+    ];
+    let mut profiles = Vec::new();
+    for root in roots.into_iter().flatten() {
+        let Ok(entries) = std::fs::read_dir(&root) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path().join("cookies.sqlite");
+            if !path.is_file() {
+                continue;
+            }
+            // Profile dirs are named `<random>.<profile name>`
+            let dir = entry.file_name().to_string_lossy().into_owned();
+            let name = dir.split_once('.').map_or(dir.as_str(), |(_, name)| name).to_string();
+            profiles.push(BrowserProfile { browser: "Firefox".to_string(), name, path });
+        }
+    }
+    profiles
+}
+
+fn ask_import_path(app: &AppHandle) -> Option<PathBuf> {
+    app.dialog()
+        .file()
+        .set_title("Import Cookies")
+        .add_filter("Cookies", &["txt", "json", "sqlite"])
+        .blocking_pick_file()
+        .and_then(|path| path.into_path().ok())
+}
+
+fn ask_export_path(app: &AppHandle, name: &str, format: CookieFormat) -> Option<PathBuf> {
+    let extension = match format {
+        CookieFormat::Netscape => "txt",
+        CookieFormat::Json => "json",
+    };
+    app.dialog()
+        .file()
+        .set_title("Export Cookies")
+        .set_file_name(format!("{}-cookies.{}", name, extension))
+        .add_filter(extension.to_uppercase(), &[extension])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+}
+
+#[tauri::command]
+pub fn list_cookie_jars(app: AppHandle) -> Result<Vec<CookieJarInfo>, String> {
+    let Ok(entries) = std::fs::read_dir(jars_dir(&app)?) else {
+        return Ok(Vec::new());
+    };
+    let mut jars = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().map_or(true, |e| e != EXTENSION) {
+            continue;
+        }
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let updated_at = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as i64);
+        match read_jar(&app, &name) {
+            Ok(cookies) => jars.push(info(&name, &cookies, updated_at)),
+            Err(e) => log::warn!("Skipping cookie jar {}: {}", name, e),
+        }
+    }
+    jars.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(jars)
+}
+
+#[tauri::command]
+pub fn list_browser_profiles() -> Vec<BrowserProfile> {
+    firefox_profiles()
+}
+
+/// Imports cookies into the named jar from a `cookies.txt`, a JSON cookie export or a
+/// Firefox `cookies.sqlite` (see `list_browser_profiles`), optionally only those of
+/// `domains` and their subdomains. Without a `path` a file picker is shown; returns
+/// `None` if it was cancelled.
+#[tauri::command]
+pub async fn import_cookies(
+    app: AppHandle,
+    jar: String,
+    path: Option<PathBuf>,
+    domains: Option<Vec<String>>,
+) -> Result<Option<CookieJarInfo>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jar_path(&app, &jar)?;
+        let Some(path) = path.or_else(|| ask_import_path(&app)) else {
+            return Ok(None);
+        };
+        let mut cookies = read_file(&path)?;
+        if let Some(domains) = domains.filter(|domains| !domains.is_empty()) {
+            cookies.retain(|cookie| domains.iter().any(|domain| cookie.belongs_to(domain)));
+        }
+        if cookies.is_empty() {
+            return Err(format!("No cookies to import from {}", path.display()));
+        }
+        let count = cookies.len();
+        let info = merge(&app, &jar, cookies)?;
+        log::info!("Imported {} cookies into cookie jar '{}'", count, jar);
+        Ok(Some(info))
+    })
+    .await
+    .map_err(|e| format!("Cookie import failed: {}", e))?
+}
+
+/// Writes a jar's cookies to a file, `cookies.txt` format by default, once the user has
+/// authenticated. Without a `path` a save dialog is shown; returns the file written, or
+/// `None` if it was cancelled.
+#[tauri::command]
+pub async fn export_cookies(
+    app: AppHandle,
+    jar: String,
+    path: Option<PathBuf>,
+    format: Option<CookieFormat>,
+) -> Result<Option<PathBuf>, String> {
+    let format = format.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        security::authenticate("export saved logins")?;
+        let cookies = read_jar(&app, &jar)?;
+        let Some(path) = path.or_else(|| ask_export_path(&app, &jar, format)) else {
+            return Ok(None);
+        };
+        let contents = match format {
+            CookieFormat::Netscape => write_netscape(&cookies),
+            CookieFormat::Json => serde_json::to_string_pretty(&cookies).map_err(|e| e.to_string())?,
+        };
+        std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        log::info!("Exported cookie jar '{}' to {}", jar, path.display());
+        Ok(Some(path))
+    })
+    .await
+    .map_err(|e| format!("Cookie export failed: {}", e))?
+}
+
+#[tauri::command]
+pub fn delete_cookie_jar(app: AppHandle, jar: String) -> Result<(), String> {
+    let path = jar_path(&app, &jar)?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => {
+            log::info!("Deleted cookie jar '{}'", jar);
+            Ok(())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(format!("No cookie jar named '{}'", jar)),
+        Err(e) => Err(format!("Failed to delete {}: {}", path.display(), e)),
+    }
+}
+
+/// Loads a jar's cookies into the automation browser, so automations start logged in.
+/// They are added before the next job runs and stay in the browser's profile.
+#[tauri::command]
+pub async fn apply_cookie_jar(app: AppHandle, jar: String) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let cookies = read_jar(&app, &jar)?;
+        let count = cookies.len();
+        app.state::<AppState>().control.add_cookies(serde_json::to_value(cookies).map_err(|e| e.to_string())?)?;
+        log::info!("Loaded {} cookies from cookie jar '{}' into the browser", count, jar);
+        Ok(count)
+    })
+    .await
+    .map_err(|e| format!("Loading cookies failed: {}", e))?
+}
+
+/// Saves the automation browser's cookies (as of its last job) into the named jar,
+/// optionally only those of `domains`, e.g. after logging in once by hand.
+#[tauri::command]
+pub async fn capture_cookie_jar(
+    app: AppHandle,
+    jar: String,
+    domains: Option<Vec<String>>,
+) -> Result<CookieJarInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jar_path(&app, &jar)?;
+        let result = app.state::<AppState>().control.get_cookies()?;
+        let mut cookies: Vec<Cookie> = serde_json::from_value(result.get("cookies").cloned().unwrap_or_default())
+            .map_err(|e| format!("Unexpected cookies from the server: {}", e))?;
+        if let Some(domains) = domains.filter(|domains| !domains.is_empty()) {
+            cookies.retain(|cookie| domains.iter().any(|domain| cookie.belongs_to(domain)));
+        }
+        if cookies.is_empty() {
+            return Err("The browser has no cookies to save".to_string());
+        }
+        let count = cookies.len();
+        let info = merge(&app, &jar, cookies)?;
+        log::info!("Saved {} browser cookies into cookie jar '{}'", count, jar);
+        Ok(info)
+    })
+    .await
+    .map_err(|e| format!("Saving cookies failed: {}", e))?
+}
@@ -0,0 +1,114 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::history::{self, CredentialAccess, History};
+use crate::{crypto, secrets, totp};
+
+/// How long a token stays redeemable: the longest a job may run, plus time waiting for
+/// the server to finish the job before it.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_LIMIT: usize = 100;
+
+/// Where a token's value comes from.
+pub enum Credential {
+    /// A secret variable's keychain entry.
+    Keychain(String),
+    /// A TOTP seed, whose current code is generated when the token is redeemed.
+    Totp(String),
+}
+
+/// What a token unlocks, and for which job.
+struct Grant {
+    job_id: String,
+    /// The placeholder's name.
+    name: String,
+    credential: Credential,
+    minted: Instant,
+}
+
+/// Unredeemed tokens. Kept in memory only, so a restart voids them all.
+static GRANTS: Mutex<Option<HashMap<String, Grant>>> = Mutex::new(None);
+
+fn audit(app: &AppHandle, job_id: &str, name: &str, outcome: &str) {
+    let entry = CredentialAccess {
+        at: history::now_ms(),
+        job_id: job_id.to_string(),
+        name: name.to_string(),
+        outcome: outcome.to_string(),
+    };
+    if let Err(e) = app.state::<History>().add_credential_access(&entry) {
+        log::warn!("Failed to record credential access by job {}: {}", job_id, e);
+    }
+}
+
+/// A token the server can trade for `credential` once, while it runs `job_id`. The token
+/// is all that travels with the job.
+pub fn mint(job_id: &str, name: &str, credential: Credential) -> Result<String, String> {
+    let token: String = crypto::random_bytes::<32>()?.iter().map(|b| format!("{:02x}", b)).collect();
+    let grant = Grant { job_id: job_id.to_string(), name: name.to_string(), credential, minted: Instant::now() };
+    GRANTS.lock().unwrap().get_or_insert_with(HashMap::new).insert(token.clone(), grant);
+    Ok(token)
+}
+
+/// Voids the tokens a finished job didn't use.
+pub fn revoke_job(job_id: &str) {
+    if let Some(grants) = GRANTS.lock().unwrap().as_mut() {
+        let before = grants.len();
+        grants.retain(|_, grant| grant.job_id != job_id && grant.minted.elapsed() < TOKEN_LIFETIME);
+        if grants.len() < before {
+            log::debug!("Voided {} unused credential tokens", before - grants.len());
+        }
+    }
+}
+
+/// Serves `credential.redeem`: the secret behind a token, exactly once, to the job it was
+/// minted for. Every attempt is audited, without the secret.
+pub fn redeem(app: &AppHandle, params: &Value) -> Result<Value, String> {
+    let token = params["token"].as_str().unwrap_or_default();
+    let job_id = params["job_id"].as_str().unwrap_or_default();
+    let grant = GRANTS.lock().unwrap().as_mut().and_then(|grants| grants.remove(token));
+    let Some(grant) = grant else {
+        log::warn!("Job {} tried to redeem an unknown or used credential token", job_id);
+        audit(app, job_id, "", "unknown_token");
+        return Err("Unknown or already used credential token".to_string());
+    };
+    let outcome = if grant.job_id != job_id {
+        Err("wrong_job")
+    } else if grant.minted.elapsed() >= TOKEN_LIFETIME {
+        Err("expired")
+    } else {
+        let value = match &grant.credential {
+            Credential::Keychain(key) => secrets::get(key),
+            Credential::Totp(seed) => totp::get_totp(seed),
+        };
+        match value {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err("missing"),
+            Err(e) => {
+                log::warn!("Keychain read for credential '{}' failed: {}", grant.name, e);
+                Err("keychain_error")
+            }
+        }
+    };
+    match outcome {
+        Ok(value) => {
+            log::info!("Served credential '{}' to job {}", grant.name, job_id);
+            audit(app, job_id, &grant.name, "served");
+            Ok(serde_json::json!({ "value": value }))
+        }
+        Err(reason) => {
+            log::warn!("Refused credential '{}' to job {}: {}", grant.name, job_id, reason);
+            audit(app, job_id, &grant.name, reason);
+            Err(format!("Credential '{}' is not available ({})", grant.name, reason.replace('_', " ")))
+        }
+    }
+}
+
+/// Credential redemptions and refusals, newest first.
+#[tauri::command]
+pub fn list_credential_access(app: AppHandle, limit: Option<usize>) -> Result<Vec<CredentialAccess>, String> {
+    app.state::<History>().credential_access(limit.unwrap_or(DEFAULT_LIMIT))
+}
@@ -0,0 +1,54 @@
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::num::NonZeroU32;
+
+/// AES-256-GCM key length.
+pub const KEY_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+/// PBKDF2-HMAC-SHA256 rounds for passphrase-derived keys (OWASP 2023 guidance).
+const PBKDF2_ITERATIONS: u32 = 600_000;
+
+pub fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| "System random generator unavailable".to_string())?;
+    Ok(bytes)
+}
+
+pub fn key_from_passphrase(passphrase: &str, salt: &[u8]) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).unwrap();
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    key
+}
+
+fn cipher(key: &[u8; KEY_LEN]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).expect("key has the AES-256 length"))
+}
+
+/// Encrypts and authenticates `plaintext`. The output is the random nonce followed by the
+/// ciphertext and tag.
+pub fn seal(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = random_bytes::<NONCE_LEN>()?;
+    let mut data = plaintext.to_vec();
+    cipher(key)
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&data);
+    Ok(sealed)
+}
+
+/// Reverses `seal`. Fails on a wrong key or tampered data.
+pub fn open(key: &[u8; KEY_LEN], sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let (nonce, data) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| "Invalid nonce".to_string())?;
+    let mut data = data.to_vec();
+    let plaintext = cipher(key)
+        .open_in_place(nonce, Aad::empty(), &mut data)
+        .map_err(|_| "Decryption failed: wrong key or damaged data".to_string())?;
+    Ok(plaintext.to_vec())
+}
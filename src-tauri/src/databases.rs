@@ -0,0 +1,709 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::events::{JobEvent, JobStatus};
+use crate::history::History;
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::secrets;
+use crate::settings::SettingsStore;
+use crate::sqlite_export::{self, ExportReport, Records, WriteMode};
+
+/// Keychain entry holding a target's login, followed by the target id.
+const CREDENTIALS_PREFIX: &str = "database-credentials:";
+
+/// Attempts per write before it counts as failed. Only lost connections are retried.
+const MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Engine {
+    Postgres,
+    Mysql,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsMode {
+    /// Plain TCP, e.g. for a database on the same machine.
+    Disable,
+    /// TLS with the server's certificate checked against the public roots and
+    /// `ca_certificate`.
+    #[default]
+    Require,
+}
+
+/// A table in a PostgreSQL or MySQL database that run results are written to. The login
+/// lives in the keychain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseTarget {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub engine: Engine,
+    pub host: String,
+    /// The engine's usual port if unset.
+    #[serde(default)]
+    pub port: Option<u16>,
+    pub database: String,
+    /// May name a schema, e.g. `analytics.prices`.
+    pub table: String,
+    #[serde(default)]
+    pub tls: TlsMode,
+    /// PEM file with the CA of a server whose certificate isn't publicly trusted.
+    #[serde(default)]
+    pub ca_certificate: Option<PathBuf>,
+    #[serde(default)]
+    pub mode: WriteMode,
+    /// Columns that identify a record in `upsert` mode; the table needs a unique index on
+    /// them, which is made when the shell creates the table.
+    #[serde(default)]
+    pub keys: Vec<String>,
+    /// Column names for result fields; fields not listed keep their own name.
+    #[serde(default)]
+    pub columns: BTreeMap<String, String>,
+    /// Rows sent per statement.
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Recipes and schedules whose successful runs are written to the table.
+    #[serde(default)]
+    pub recipes: Vec<String>,
+    #[serde(default)]
+    pub schedules: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_batch_size() -> usize {
+    500
+}
+
+impl DatabaseTarget {
+    fn receives(&self, job: &JobEvent) -> bool {
+        job.recipe_id.as_ref().is_some_and(|id| self.recipes.contains(id))
+            || job.schedule_id.as_ref().is_some_and(|id| self.schedules.contains(id))
+    }
+
+    /// Whether a login stored for `self` may be sent where `other` connects.
+    fn same_server(&self, other: &DatabaseTarget) -> bool {
+        self.engine == other.engine
+            && self.host == other.host
+            && self.port == other.port
+            && self.database == other.database
+            && self.tls == other.tls
+            && self.ca_certificate == other.ca_certificate
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DatabaseCredentials {
+    pub user: String,
+    pub password: String,
+}
+
+/// What `test_connection` found.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionInfo {
+    pub server_version: String,
+    pub table_exists: bool,
+    pub columns: Vec<String>,
+}
+
+/// Why a database call failed, and whether trying again could help.
+struct Failure {
+    message: String,
+    /// The connection was lost or never made, as opposed to the server refusing the SQL.
+    transient: bool,
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure { message, transient: false }
+    }
+}
+
+/// A connection to either engine, with the few statements the export needs.
+trait Database {
+    fn server_version(&mut self) -> Result<String, Failure>;
+
+    /// Declared columns of `table` and their types, empty if there is no such table.
+    fn columns(&mut self, table: &str) -> Result<Vec<(String, String)>, Failure>;
+
+    /// Creates `table` with the given columns, typed from their JSON values, and a unique
+    /// index on `keys` if there are any.
+    fn create_table(
+        &mut self,
+        table: &str,
+        columns: &[(String, Option<&'static str>)],
+        keys: &[String],
+    ) -> Result<(), Failure>;
+
+    /// Writes the rows in one transaction, `batch_size` rows per statement.
+    fn insert(
+        &mut self,
+        target: &DatabaseTarget,
+        columns: &[(String, String)],
+        rows: &[Vec<Value>],
+    ) -> Result<(), Failure>;
+}
+
+fn credentials_key(target_id: &str) -> String {
+    format!("{}{}", CREDENTIALS_PREFIX, target_id)
+}
+
+fn credentials(target: &DatabaseTarget) -> Result<DatabaseCredentials, String> {
+    let stored = secrets::get(&credentials_key(&target.id))?
+        .ok_or_else(|| format!("No login is stored for database target '{}'", target.name))?;
+    serde_json::from_str(&stored).map_err(|_| format!("Stored login of '{}' is damaged", target.name))
+}
+
+fn check(target: &DatabaseTarget) -> Result<(), String> {
+    if target.host.trim().is_empty() {
+        return Err(format!("Target '{}' has no host", target.name));
+    }
+    if target.database.trim().is_empty() || target.table.trim().is_empty() {
+        return Err(format!("Target '{}' needs a database and a table", target.name));
+    }
+    if target.mode == WriteMode::Upsert && target.keys.is_empty() {
+        return Err(format!("Target '{}' writes in upsert mode but has no key columns", target.name));
+    }
+    if target.batch_size == 0 {
+        return Err(format!("Target '{}' has a batch size of 0", target.name));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "databases")]
+mod engines {
+    use mysql::prelude::Queryable;
+    use postgres::types::ToSql;
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::CertificateDer;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{Database, DatabaseCredentials, DatabaseTarget, Engine, Failure, TlsMode};
+
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+    /// Most parameters a statement may have on either engine.
+    const MAX_PARAMS: usize = 65_535;
+
+    fn port(target: &DatabaseTarget) -> u16 {
+        target.port.unwrap_or(match target.engine {
+            Engine::Postgres => 5432,
+            Engine::Mysql => 3306,
+        })
+    }
+
+    impl From<postgres::Error> for Failure {
+        fn from(e: postgres::Error) -> Self {
+            Failure { transient: e.as_db_error().is_none(), message: e.to_string() }
+        }
+    }
+
+    impl From<mysql::Error> for Failure {
+        fn from(e: mysql::Error) -> Self {
+            use mysql::{DriverError, Error};
+            let transient = matches!(
+                e,
+                Error::IoError(_)
+                    | Error::CodecError(_)
+                    | Error::DriverError(DriverError::ConnectTimeout | DriverError::CouldNotConnect(_))
+            );
+            Failure { transient, message: e.to_string() }
+        }
+    }
+
+    /// Quotes a column name with the engine's quote mark.
+    fn quote(name: &str, mark: char) -> String {
+        format!("{}{}{}", mark, name.replace(mark, &format!("{}{}", mark, mark)), mark)
+    }
+
+    /// Quotes a possibly schema-qualified table name, e.g. `analytics.prices`.
+    fn quote_table(name: &str, mark: char) -> String {
+        name.split('.').map(|part| quote(part, mark)).collect::<Vec<_>>().join(".")
+    }
+
+    /// Rows per statement, kept under the engine's parameter limit.
+    fn chunk_size(target: &DatabaseTarget, columns: usize) -> usize {
+        target.batch_size.min(MAX_PARAMS / columns.max(1)).max(1)
+    }
+
+    fn tls_config(target: &DatabaseTarget) -> Result<rustls::ClientConfig, Failure> {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        if let Some(path) = &target.ca_certificate {
+            let certs = CertificateDer::pem_file_iter(path)
+                .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            for cert in certs {
+                roots.add(cert).map_err(|e| format!("Invalid CA certificate in {}: {}", path.display(), e))?;
+            }
+        }
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Ok(config)
+    }
+
+    pub(super) fn connect(
+        target: &DatabaseTarget,
+        creds: &DatabaseCredentials,
+    ) -> Result<Box<dyn Database>, Failure> {
+        match target.engine {
+            Engine::Postgres => {
+                let mut config = postgres::Config::new();
+                config
+                    .host(&target.host)
+                    .port(port(target))
+                    .dbname(&target.database)
+                    .user(&creds.user)
+                    .password(&creds.password)
+                    .application_name("Browser Automation")
+                    .connect_timeout(CONNECT_TIMEOUT);
+                let client = match target.tls {
+                    TlsMode::Disable => config.ssl_mode(postgres::config::SslMode::Disable).connect(postgres::NoTls)?,
+                    TlsMode::Require => {
+                        let tls = tokio_postgres_rustls::MakeRustlsConnect::new(tls_config(target)?);
+                        config.ssl_mode(postgres::config::SslMode::Require).connect(tls)?
+                    }
+                };
+                Ok(Box::new(Postgres(client)))
+            }
+            Engine::Mysql => {
+                let ssl = match target.tls {
+                    TlsMode::Disable => None,
+                    TlsMode::Require => {
+                        Some(mysql::SslOpts::default().with_root_cert_path(target.ca_certificate.clone()))
+                    }
+                };
+                let opts = mysql::OptsBuilder::new()
+                    .ip_or_hostname(Some(target.host.as_str()))
+                    .tcp_port(port(target))
+                    .db_name(Some(target.database.as_str()))
+                    .user(Some(creds.user.as_str()))
+                    .pass(Some(creds.password.as_str()))
+                    .ssl_opts(ssl)
+                    .tcp_connect_timeout(Some(CONNECT_TIMEOUT));
+                Ok(Box::new(Mysql(mysql::Conn::new(opts)?)))
+            }
+        }
+    }
+
+    /// A value as text, which PostgreSQL casts to the column's type.
+    fn text_value(value: &Value) -> Option<String> {
+        match value {
+            Value::Null => None,
+            Value::Bool(b) => Some(if *b { "1" } else { "0" }.to_string()),
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    fn mysql_value(value: &Value) -> mysql::Value {
+        match value {
+            Value::Null => mysql::Value::NULL,
+            Value::Bool(b) => mysql::Value::Int(*b as i64),
+            Value::Number(n) => match n.as_i64() {
+                Some(n) => mysql::Value::Int(n),
+                None => mysql::Value::Double(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => mysql::Value::Bytes(s.clone().into_bytes()),
+            other => mysql::Value::Bytes(other.to_string().into_bytes()),
+        }
+    }
+
+    struct Postgres(postgres::Client);
+
+    impl Database for Postgres {
+        fn server_version(&mut self) -> Result<String, Failure> {
+            Ok(self.0.query_one("SHOW server_version", &[])?.get(0))
+        }
+
+        fn columns(&mut self, table: &str) -> Result<Vec<(String, String)>, Failure> {
+            let rows = self.0.query(
+                "SELECT attname, format_type(atttypid, atttypmod) FROM pg_attribute
+                 WHERE attrelid = to_regclass($1::text) AND attnum > 0 AND NOT attisdropped ORDER BY attnum",
+                &[&quote_table(table, '"')],
+            )?;
+            Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+        }
+
+        fn create_table(
+            &mut self,
+            table: &str,
+            columns: &[(String, Option<&'static str>)],
+            keys: &[String],
+        ) -> Result<(), Failure> {
+            let definitions: Vec<String> = columns
+                .iter()
+                .map(|(column, kind)| {
+                    let kind = match *kind {
+                        Some("INTEGER") => "BIGINT",
+                        Some("REAL") => "DOUBLE PRECISION",
+                        _ => "TEXT",
+                    };
+                    format!("{} {}", quote(column, '"'), kind)
+                })
+                .collect();
+            let mut tx = self.0.transaction()?;
+            tx.batch_execute(&format!("CREATE TABLE {} ({})", quote_table(table, '"'), definitions.join(", ")))?;
+            if !keys.is_empty() {
+                let keys: Vec<String> = keys.iter().map(|key| quote(key, '"')).collect();
+                tx.batch_execute(&format!("CREATE UNIQUE INDEX ON {} ({})", quote_table(table, '"'), keys.join(", ")))?;
+            }
+            Ok(tx.commit()?)
+        }
+
+        fn insert(
+            &mut self,
+            target: &DatabaseTarget,
+            columns: &[(String, String)],
+            rows: &[Vec<Value>],
+        ) -> Result<(), Failure> {
+            let names: Vec<String> = columns.iter().map(|(column, _)| quote(column, '"')).collect();
+            let keys: Vec<String> = target.keys.iter().map(|key| quote(key, '"')).collect();
+            let conflict = if target.mode == super::WriteMode::Upsert {
+                let updates: Vec<String> = names
+                    .iter()
+                    .filter(|column| !keys.contains(column))
+                    .map(|column| format!("{} = EXCLUDED.{}", column, column))
+                    .collect();
+                format!(" ON CONFLICT ({}) DO UPDATE SET {}", keys.join(", "), updates.join(", "))
+            } else {
+                String::new()
+            };
+
+            let mut tx = self.0.transaction()?;
+            for chunk in rows.chunks(chunk_size(target, columns.len())) {
+                let mut tuples = Vec::with_capacity(chunk.len());
+                for index in 0..chunk.len() {
+                    let casts: Vec<String> = columns
+                        .iter()
+                        .enumerate()
+                        .map(|(col, (_, kind))| format!("CAST(${}::text AS {})", index * columns.len() + col + 1, kind))
+                        .collect();
+                    tuples.push(format!("({})", casts.join(", ")));
+                }
+                let sql = format!(
+                    "INSERT INTO {} ({}) VALUES {}{}",
+                    quote_table(&target.table, '"'),
+                    names.join(", "),
+                    tuples.join(", "),
+                    conflict
+                );
+                let values: Vec<Option<String>> = chunk.iter().flatten().map(text_value).collect();
+                let params: Vec<&(dyn ToSql + Sync)> =
+                    values.iter().map(|value| value as &(dyn ToSql + Sync)).collect();
+                tx.execute(&sql, &params)?;
+            }
+            Ok(tx.commit()?)
+        }
+    }
+
+    struct Mysql(mysql::Conn);
+
+    impl Database for Mysql {
+        fn server_version(&mut self) -> Result<String, Failure> {
+            let version: Option<String> = self.0.query_first("SELECT VERSION()")?;
+            Ok(version.unwrap_or_default())
+        }
+
+        fn columns(&mut self, table: &str) -> Result<Vec<(String, String)>, Failure> {
+            let (schema, name) = match table.split_once('.') {
+                Some((schema, name)) => (Some(schema), name),
+                None => (None, table),
+            };
+            Ok(self.0.exec(
+                "SELECT COLUMN_NAME, COLUMN_TYPE FROM information_schema.COLUMNS
+                 WHERE TABLE_SCHEMA = COALESCE(?, DATABASE()) AND TABLE_NAME = ? ORDER BY ORDINAL_POSITION",
+                (schema, name),
+            )?)
+        }
+
+        fn create_table(
+            &mut self,
+            table: &str,
+            columns: &[(String, Option<&'static str>)],
+            keys: &[String],
+        ) -> Result<(), Failure> {
+            let mut definitions: Vec<String> = columns
+                .iter()
+                .map(|(column, kind)| {
+                    let kind = match *kind {
+                        Some("INTEGER") => "BIGINT",
+                        Some("REAL") => "DOUBLE",
+                        // TEXT can't be part of an index without a prefix length
+                        _ if keys.contains(column) => "VARCHAR(255)",
+                        _ => "TEXT",
+                    };
+                    format!("{} {}", quote(column, '`'), kind)
+                })
+                .collect();
+            if !keys.is_empty() {
+                let keys: Vec<String> = keys.iter().map(|key| quote(key, '`')).collect();
+                definitions.push(format!("UNIQUE KEY ({})", keys.join(", ")));
+            }
+            Ok(self.0.query_drop(format!("CREATE TABLE {} ({})", quote_table(table, '`'), definitions.join(", ")))?)
+        }
+
+        fn insert(
+            &mut self,
+            target: &DatabaseTarget,
+            columns: &[(String, String)],
+            rows: &[Vec<Value>],
+        ) -> Result<(), Failure> {
+            let names: Vec<String> = columns.iter().map(|(column, _)| quote(column, '`')).collect();
+            let keys: Vec<String> = target.keys.iter().map(|key| quote(key, '`')).collect();
+            let conflict = if target.mode == super::WriteMode::Upsert {
+                let updates: Vec<String> = names
+                    .iter()
+                    .filter(|column| !keys.contains(column))
+                    .map(|column| format!("{} = VALUES({})", column, column))
+                    .collect();
+                format!(" ON DUPLICATE KEY UPDATE {}", updates.join(", "))
+            } else {
+                String::new()
+            };
+            let tuple = format!("({})", vec!["?"; columns.len()].join(", "));
+
+            let mut tx = self.0.start_transaction(mysql::TxOpts::default())?;
+            for chunk in rows.chunks(chunk_size(target, columns.len())) {
+                let sql = format!(
+                    "INSERT INTO {} ({}) VALUES {}{}",
+                    quote_table(&target.table, '`'),
+                    names.join(", "),
+                    vec![tuple.as_str(); chunk.len()].join(", "),
+                    conflict
+                );
+                let params: Vec<mysql::Value> = chunk.iter().flatten().map(mysql_value).collect();
+                tx.exec_drop(sql, params)?;
+            }
+            Ok(tx.commit()?)
+        }
+    }
+}
+
+#[cfg(feature = "databases")]
+use engines::connect;
+
+#[cfg(not(feature = "databases"))]
+fn connect(_target: &DatabaseTarget, _creds: &DatabaseCredentials) -> Result<Box<dyn Database>, Failure> {
+    Err("This build can't write to databases; it was built without the databases feature".to_string().into())
+}
+
+/// Writes the records, creating the table if it is missing. Fields the table has no column
+/// for are left out with a warning; the shell never alters a table it didn't make.
+fn write_records(db: &mut dyn Database, target: &DatabaseTarget, records: &Records) -> Result<ExportReport, Failure> {
+    let mut existing = db.columns(&target.table)?;
+    let created = existing.is_empty();
+    if created {
+        let columns: Vec<(String, Option<&'static str>)> = records
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                (column.clone(), sqlite_export::column_type(records.rows.iter().map(|row| &row[index])))
+            })
+            .collect();
+        let keys = if target.mode == WriteMode::Upsert { target.keys.as_slice() } else { &[] };
+        db.create_table(&target.table, &columns, keys)?;
+        existing = db.columns(&target.table)?;
+    }
+
+    let mut warnings = Vec::new();
+    let mut kept = Vec::new();
+    let mut columns = Vec::new();
+    for (index, column) in records.columns.iter().enumerate() {
+        match existing.iter().find(|(name, _)| name == column) {
+            Some(found) => {
+                kept.push(index);
+                columns.push(found.clone());
+            }
+            None => warnings.push(format!("Field '{}' has no column in {} and was left out", column, target.table)),
+        }
+    }
+    let keys: Vec<usize> = match target.mode {
+        WriteMode::Append => Vec::new(),
+        WriteMode::Upsert => target
+            .keys
+            .iter()
+            .map(|key| {
+                columns.iter().position(|(column, _)| column == key).ok_or_else(|| {
+                    format!("Key column '{}' is not in both the results of this run and {}", key, target.table)
+                })
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    // A key without a value never conflicts, so the row would pile up as a duplicate; and
+    // a statement may not update the same row twice, so the last of each record wins
+    let mut rows: Vec<Vec<Value>> = Vec::with_capacity(records.rows.len());
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut skipped = 0;
+    for row in &records.rows {
+        let row: Vec<Value> = kept.iter().map(|&index| row[index].clone()).collect();
+        if keys.iter().any(|&key| row[key].is_null()) {
+            skipped += 1;
+            continue;
+        }
+        if !keys.is_empty() {
+            let key: Vec<&Value> = keys.iter().map(|&key| &row[key]).collect();
+            let key = serde_json::to_string(&key).unwrap_or_default();
+            if let Some(&earlier) = positions.get(&key) {
+                rows[earlier] = row;
+                continue;
+            }
+            positions.insert(key, rows.len());
+        }
+        rows.push(row);
+    }
+    db.insert(target, &columns, &rows)?;
+
+    Ok(ExportReport {
+        target: target.name.clone(),
+        run_id: String::new(),
+        table: target.table.clone(),
+        created,
+        rows_written: rows.len(),
+        rows_skipped: skipped,
+        columns_added: Vec::new(),
+        warnings,
+    })
+}
+
+/// Writes a run's output to a target, retrying a few times if the connection drops.
+pub fn export_run(app: &AppHandle, target: &DatabaseTarget, run_id: &str) -> Result<ExportReport, String> {
+    check(target)?;
+    let creds = credentials(target)?;
+    let run = app
+        .state::<History>()
+        .get(run_id)?
+        .ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let output = run.output.ok_or_else(|| format!("Run '{}' has no results to export", run_id))?;
+    let records =
+        sqlite_export::records(&output, &target.columns, run_id, chrono::Utc::now().timestamp_millis());
+
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        if attempt > 1 {
+            std::thread::sleep(Duration::from_secs(2u64.pow(attempt - 1)));
+            log::info!("Retrying write to '{}' (attempt {}): {}", target.name, attempt, last_error);
+        }
+        match connect(target, &creds).and_then(|mut db| write_records(db.as_mut(), target, &records)) {
+            Ok(mut report) => {
+                report.run_id = run_id.to_string();
+                for warning in &report.warnings {
+                    log::warn!("Database target '{}': {}", target.name, warning);
+                }
+                let rows = report.rows_written;
+                log::info!("Wrote {} rows of run {} to {} on {}", rows, run_id, target.table, target.host);
+                return Ok(report);
+            }
+            Err(e) if e.transient => last_error = e.message,
+            Err(e) => return Err(format!("Write to '{}' failed: {}", target.name, e.message)),
+        }
+    }
+    Err(format!("Write to '{}' failed after {} attempts: {}", target.name, MAX_ATTEMPTS, last_error))
+}
+
+fn find_target(app: &AppHandle, target_id: &str) -> Result<DatabaseTarget, String> {
+    app.state::<SettingsStore>()
+        .get()
+        .database_targets
+        .into_iter()
+        .find(|target| target.id == target_id)
+        .ok_or_else(|| format!("No database target with id '{}'", target_id))
+}
+
+/// Built-in plugin that writes the results of successful runs to the databases they feed.
+struct DatabasePlugin;
+
+impl ShellPlugin for DatabasePlugin {
+    fn name(&self) -> &str {
+        "database-export"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        if job.status != Some(JobStatus::Success) || job.output.is_none() {
+            return;
+        }
+        let settings = ctx.app.state::<SettingsStore>().get();
+        for target in settings.database_targets.iter().filter(|target| target.enabled && target.receives(job)) {
+            if let Err(e) = export_run(ctx.app, target, &job.job_id) {
+                log::error!("Results of run {} not written to '{}': {}", job.job_id, target.name, e);
+            }
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(DatabasePlugin), "Writes run results to configured PostgreSQL and MySQL tables");
+}
+
+/// Stores the login for a database target in the keychain. `None` removes it.
+#[tauri::command]
+pub fn set_database_credentials(target_id: String, credentials: Option<DatabaseCredentials>) -> Result<(), String> {
+    let key = credentials_key(&target_id);
+    match credentials {
+        Some(credentials) => secrets::set(&key, &serde_json::to_string(&credentials).map_err(|e| e.to_string())?),
+        None => secrets::delete(&key),
+    }
+}
+
+/// Connects to a target and looks up its table, e.g. before saving a new or edited target.
+/// A new target, or one whose server changed, is tested with the login given here; the
+/// stored login is only sent to the server of the saved target.
+#[tauri::command]
+pub async fn test_connection(
+    app: AppHandle,
+    target: DatabaseTarget,
+    login: Option<DatabaseCredentials>,
+) -> Result<ConnectionInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        check(&target)?;
+        let creds = match login {
+            Some(login) => login,
+            None => {
+                let saved = find_target(&app, &target.id)
+                    .ok()
+                    .filter(|saved| saved.same_server(&target))
+                    .ok_or("Enter the login to test a new target or one whose server changed")?;
+                credentials(&saved)?
+            }
+        };
+        let test = || -> Result<ConnectionInfo, Failure> {
+            let mut db = connect(&target, &creds)?;
+            let columns = db.columns(&target.table)?;
+            Ok(ConnectionInfo {
+                server_version: db.server_version()?,
+                table_exists: !columns.is_empty(),
+                columns: columns.into_iter().map(|(column, _)| column).collect(),
+            })
+        };
+        test().map_err(|e| format!("Could not reach '{}': {}", target.name, e.message))
+    })
+    .await
+    .map_err(|e| format!("Connection test failed: {}", e))?
+}
+
+/// Writes a finished run's results to a configured database target.
+#[tauri::command]
+pub async fn export_run_to_database(app: AppHandle, target_id: String, run_id: String) -> Result<ExportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || export_run(&app, &find_target(&app, &target_id)?, &run_id))
+        .await
+        .map_err(|e| format!("Database export failed: {}", e))?
+}
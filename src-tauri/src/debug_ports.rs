@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::TcpListener;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+/// DevTools ports are handed out from here, so several browsers don't fight over 9222.
+const START_PORT: u16 = 9222;
+const END_PORT: u16 = 9322;
+const LIST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// DevTools port of each server's browser, by instance id (`main` for the primary one).
+static PORTS: Mutex<BTreeMap<String, u16>> = Mutex::new(BTreeMap::new());
+
+/// A page or worker a browser offers for inspection.
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugTarget {
+    pub instance_id: String,
+    pub port: u16,
+    pub id: String,
+    /// `page`, `iframe`, `service_worker`, ...
+    pub kind: String,
+    pub title: String,
+    pub url: String,
+    /// DevTools for this target, served by the browser itself.
+    pub inspector_url: Option<String>,
+}
+
+/// An entry of the browser's `/json/list`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CdpTarget {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    url: String,
+    devtools_frontend_url: Option<String>,
+}
+
+/// Picks a free DevTools port for the browser of server `instance_id` and tracks it until
+/// `release`. Handed to the server as `NOVA_DEBUG_PORT`.
+pub fn allocate(instance_id: &str) -> Result<u16, String> {
+    let mut ports = PORTS.lock().unwrap();
+    ports.remove(instance_id);
+    let port = (START_PORT..=END_PORT)
+        .filter(|port| !ports.values().any(|used| used == port))
+        .find(|port| TcpListener::bind(("127.0.0.1", *port)).is_ok())
+        .ok_or_else(|| format!("No free DevTools port between {} and {}", START_PORT, END_PORT))?;
+    ports.insert(instance_id.to_string(), port);
+    log::info!("DevTools port {} assigned to server '{}'", port, instance_id);
+    Ok(port)
+}
+
+pub fn release(instance_id: &str) {
+    PORTS.lock().unwrap().remove(instance_id);
+}
+
+fn list_targets(instance_id: &str, port: u16) -> Result<Vec<DebugTarget>, String> {
+    let targets: Vec<CdpTarget> = ureq::get(&format!("http://127.0.0.1:{}/json/list", port))
+        .timeout(LIST_TIMEOUT)
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    Ok(targets
+        .into_iter()
+        .map(|target| DebugTarget {
+            instance_id: instance_id.to_string(),
+            port,
+            // Relative to the browser's own DevTools server, except in some builds
+            inspector_url: target.devtools_frontend_url.map(|url| {
+                if url.starts_with('/') {
+                    format!("http://127.0.0.1:{}{}", port, url)
+                } else {
+                    url
+                }
+            }),
+            id: target.id,
+            kind: target.kind,
+            title: target.title,
+            url: target.url,
+        })
+        .collect())
+}
+
+/// What the servers' browsers offer for inspection. Servers whose browser hasn't started
+/// yet have none.
+#[tauri::command]
+pub async fn get_debug_targets() -> Result<Vec<DebugTarget>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let ports = PORTS.lock().unwrap().clone();
+        let mut targets = Vec::new();
+        for (instance_id, port) in ports {
+            match list_targets(&instance_id, port) {
+                Ok(found) => targets.extend(found),
+                Err(e) => log::debug!("No DevTools targets on port {} ({}): {}", port, instance_id, e),
+            }
+        }
+        targets
+    })
+    .await
+    .map_err(|e| format!("Listing DevTools targets failed: {}", e))
+}
+
+/// Opens DevTools for a target in the system browser.
+#[tauri::command]
+pub async fn open_in_devtools(app: AppHandle, instance_id: String, target_id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let port = PORTS.lock().unwrap().get(&instance_id).copied();
+        let port = port.ok_or_else(|| format!("No browser is running for server '{}'", instance_id))?;
+        let target = list_targets(&instance_id, port)?
+            .into_iter()
+            .find(|target| target.id == target_id)
+            .ok_or("That page is no longer open")?;
+        let url = target.inspector_url.ok_or("The browser offers no DevTools for that target")?;
+        app.opener().open_url(&url, None::<&str>).map_err(|e| format!("Failed to open DevTools: {}", e))?;
+        log::info!("Opened DevTools for '{}' on port {}", target.title, port);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Opening DevTools failed: {}", e))?
+}
@@ -0,0 +1,73 @@
+use tauri::{AppHandle, Manager};
+
+use crate::history::History;
+use crate::{recipes, AppState};
+
+/// The steps of a prompt: its non-empty lines, as the server splits it when a job runs
+/// step by step.
+pub fn steps(prompt: &str) -> Vec<&str> {
+    prompt.lines().map(str::trim).filter(|line| !line.is_empty()).collect()
+}
+
+/// A recipe's breakpoints. Jobs of a recipe with breakpoints run step by step, stopping
+/// before each breakpoint, and can be paused and stepped through from the shell.
+pub fn breakpoints(app: &AppHandle, recipe_id: &str) -> Vec<u32> {
+    app.state::<History>().breakpoints(recipe_id).unwrap_or_else(|e| {
+        log::warn!("Breakpoints of recipe {} not read: {}", recipe_id, e);
+        Vec::new()
+    })
+}
+
+/// Sets or clears a breakpoint before `step` (from 1) of a recipe, and returns the
+/// recipe's breakpoints. Running jobs of the recipe pick up the change.
+#[tauri::command]
+pub async fn set_breakpoint(app: AppHandle, recipe_id: String, step: u32, enabled: bool) -> Result<Vec<u32>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let recipe = recipes::load(&app, &recipe_id)?;
+        let count = steps(&recipe.task.prompt).len();
+        if step == 0 || step as usize > count {
+            return Err(format!("Recipe '{}' has no step {}; it has {}", recipe_id, step, count));
+        }
+        let history = app.state::<History>();
+        history.set_breakpoint(&recipe_id, step, enabled)?;
+        let breakpoints = history.breakpoints(&recipe_id)?;
+        let change = if enabled { "set on" } else { "cleared from" };
+        log::info!("Breakpoint {} step {} of recipe {}", change, step, recipe_id);
+        if let Err(e) = app.state::<AppState>().control.job_breakpoints(&recipe_id, &breakpoints) {
+            log::debug!("Breakpoints not sent to the server: {}", e);
+        }
+        Ok(breakpoints)
+    })
+    .await
+    .map_err(|e| format!("Setting the breakpoint failed: {}", e))?
+}
+
+#[tauri::command]
+pub fn list_breakpoints(app: AppHandle, recipe_id: String) -> Result<Vec<u32>, String> {
+    app.state::<History>().breakpoints(&recipe_id)
+}
+
+/// Pauses a job before its next step. Only jobs of recipes with breakpoints run step by
+/// step; others fail to pause.
+#[tauri::command]
+pub async fn pause_job(app: AppHandle, job_id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<AppState>().control.job_pause(&job_id))
+        .await
+        .map_err(|e| format!("Pausing the job failed: {}", e))?
+}
+
+/// Runs a paused job's next step and pauses it again.
+#[tauri::command]
+pub async fn step_job(app: AppHandle, job_id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<AppState>().control.job_step(&job_id))
+        .await
+        .map_err(|e| format!("Stepping the job failed: {}", e))?
+}
+
+/// Lets a paused job run on, until its next breakpoint.
+#[tauri::command]
+pub async fn resume_job(app: AppHandle, job_id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<AppState>().control.job_resume(&job_id))
+        .await
+        .map_err(|e| format!("Resuming the job failed: {}", e))?
+}
@@ -0,0 +1,211 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::packaging::{self, Packaging};
+use crate::{bundle, server};
+
+#[derive(Debug, Serialize)]
+pub struct SelfTestStep {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+    pub duration_ms: u128,
+}
+
+/// Machine-readable result of `run_self_test`, meant to be attached to support requests.
+#[derive(Debug, Serialize)]
+pub struct SelfTestReport {
+    pub app_version: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub packaging: Packaging,
+    pub passed: bool,
+    pub steps: Vec<SelfTestStep>,
+}
+
+impl SelfTestReport {
+    fn new() -> Self {
+        SelfTestReport {
+            app_version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            packaging: packaging::detect(),
+            passed: true,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Runs one step and records its outcome. Returns the step's value so later steps can use it.
+    fn step<T>(&mut self, name: &'static str, f: impl FnOnce() -> Result<(T, String), String>) -> Option<T> {
+        let started = Instant::now();
+        let result = f();
+        let duration_ms = started.elapsed().as_millis();
+
+        match result {
+            Ok((value, detail)) => {
+                log::info!("Self-test: {} passed ({})", name, detail);
+                self.steps.push(SelfTestStep { name, passed: true, detail, duration_ms });
+                Some(value)
+            }
+            Err(detail) => {
+                log::warn!("Self-test: {} failed ({})", name, detail);
+                self.passed = false;
+                self.steps.push(SelfTestStep { name, passed: false, detail, duration_ms });
+                None
+            }
+        }
+    }
+}
+
+fn verify_checksum(binary: &Path) -> Result<((), String), String> {
+    let actual = server::verify_binary(binary)?;
+    if server::expected_checksum(binary).is_some() {
+        Ok(((), format!("sha256 {}", actual)))
+    } else {
+        Ok(((), format!("sha256 {} (no checksum file in bundle, not verified)", actual)))
+    }
+}
+
+/// Random-enough token to confirm we are talking to the server we just spawned.
+fn instance_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("selftest-{:x}-{:x}", std::process::id(), nanos)
+}
+
+fn handshake(port: u16, token: &str) -> Result<((), String), String> {
+    let url = format!("{}/api/health", server::server_url(port));
+    let response = ureq::get(&url)
+        .timeout(Duration::from_secs(2))
+        .call()
+        .map_err(|e| format!("Health request failed: {}", e))?;
+    let body: serde_json::Value = response
+        .into_json()
+        .map_err(|e| format!("Health response was not JSON: {}", e))?;
+
+    if body["status"] != "ok" {
+        return Err(format!("Server reported unhealthy status: {}", body));
+    }
+    if body["instance_id"] != token {
+        return Err(format!("Identity mismatch: expected {}, got {}", token, body["instance_id"]));
+    }
+    Ok(((), format!("server version {}", body["version"].as_str().unwrap_or("unknown"))))
+}
+
+/// Runs a dry-run job, which takes the same path as any automation but skips the browser.
+fn dry_run_job(port: u16, token: &str) -> Result<((), String), String> {
+    let url = format!("{}/api/execute_automation", server::server_url(port));
+    let prompt = "self-test";
+    let payload = serde_json::json!({ "prompt": prompt, "job_id": token, "dry_run": true });
+    let body: serde_json::Value = match ureq::post(&url).timeout(Duration::from_secs(10)).send_json(payload) {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => {
+            response.into_json().map_err(|e| format!("Job response was not JSON: {}", e))?
+        }
+        Err(e) => return Err(format!("Job request failed: {}", e)),
+    };
+
+    if body["status"] != "success" {
+        return Err(format!("Job failed: {}", body["message"].as_str().unwrap_or("no message")));
+    }
+    if body["output"]["dry_run"] != true || body["output"]["prompt"] != prompt {
+        return Err(format!("Unexpected job result: {}", body["output"]));
+    }
+    Ok(((), "dry-run job completed".to_string()))
+}
+
+fn self_test(app: &AppHandle) -> SelfTestReport {
+    let mut report = SelfTestReport::new();
+
+    let binary = report.step("binary_present", || {
+        let server_bundle = bundle::prepare(app)?;
+        let binary = server_bundle.binary;
+        if binary.exists() {
+            let detail = format!("{} ({:?} layout)", binary.display(), server_bundle.layout);
+            Ok((binary, detail))
+        } else {
+            Err(format!("Server binary not found at {}", binary.display()))
+        }
+    });
+    let Some(binary) = binary else {
+        return report;
+    };
+
+    if report.step("binary_checksum", || verify_checksum(&binary)).is_none() {
+        return report;
+    }
+
+    let Some(port) = report.step("scratch_port", || {
+        let port = server::find_available_port().map_err(|e| e.to_string())?;
+        Ok((port, format!("port {}", port)))
+    }) else {
+        return report;
+    };
+
+    let token = instance_token();
+    let mut cmd = server::server_command(&binary, port, false);
+    cmd.env("NOVA_INSTANCE_ID", &token);
+    let Some(mut child) = report.step("spawn", || {
+        let child = cmd.spawn().map_err(|e| format!("Failed to start server: {}", e))?;
+        let detail = format!("PID {}", child.id());
+        Ok((child, detail))
+    }) else {
+        return report;
+    };
+
+    let ready = report.step("ready", || match server::wait_for_ready(port, 20) {
+        Some(attempts) => Ok(((), format!("ready after {} attempts", attempts))),
+        None => Err("Server did not become ready within 10 seconds".to_string()),
+    });
+    if ready.is_some() && report.step("handshake", || handshake(port, &token)).is_some() {
+        report.step("job", || dry_run_job(port, &token));
+    }
+
+    report.step("teardown", || {
+        server::stop_process(&mut child);
+        Ok(((), "scratch server stopped".to_string()))
+    });
+
+    report
+}
+
+/// Exercises the full stack against a throwaway server and returns a report for support.
+/// Environment the main server was last spawned with.
+static SERVER_ENV: Mutex<Option<BTreeMap<String, String>>> = Mutex::new(None);
+
+/// Remembers the environment of a server command about to be spawned. Values of
+/// variables that look like credentials are redacted.
+pub fn record_server_env(cmd: &Command) {
+    let env = cmd
+        .get_envs()
+        .filter_map(|(name, value)| Some((name.to_string_lossy().into_owned(), value?.to_string_lossy().into_owned())))
+        .map(|(name, value)| {
+            let upper = name.to_uppercase();
+            let secret = ["KEY", "TOKEN", "SECRET", "PASSWORD"].iter().any(|word| upper.contains(word));
+            (name, if secret { "<redacted>".to_string() } else { value })
+        })
+        .collect();
+    *SERVER_ENV.lock().unwrap() = Some(env);
+}
+
+/// The environment the running server was started with, for support.
+#[tauri::command]
+pub fn get_server_environment() -> Result<BTreeMap<String, String>, String> {
+    SERVER_ENV.lock().unwrap().clone().ok_or_else(|| "The server has not been started by the app".to_string())
+}
+
+#[tauri::command]
+pub async fn run_self_test(app: AppHandle) -> Result<SelfTestReport, String> {
+    log::info!("Running self-test...");
+    let report = tauri::async_runtime::spawn_blocking(move || self_test(&app))
+        .await
+        .map_err(|e| format!("Self-test task failed: {}", e))?;
+    log::info!("Self-test finished: {}", if report.passed { "passed" } else { "failed" });
+    Ok(report)
+}
@@ -0,0 +1,388 @@
+use chrono::{NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::events::{JobEvent, JobEventKind, JobStatus};
+use crate::history::{self, History, RunRecord};
+use crate::notifications::{self, Category, Notification, Urgency};
+use crate::settings::{Settings, SettingsStore};
+use crate::{email, integrations, recipes, reports, timetable};
+
+/// How often the shell checks whether the digest is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// History meta key holding the end of the period the last digest covered (Unix ms).
+const LAST_DIGEST_KEY: &str = "last_digest_until";
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// A digest after days without one covers at most this far back.
+const MAX_PERIOD_MS: i64 = 7 * DAY_MS;
+/// Failed runs and notable changes listed before the rest are just counted.
+const MAX_LISTED: usize = 10;
+/// Monitor changes and broken checks looked at per digest.
+const MAX_EVENTS: usize = 500;
+/// Lines of the digest shown in its desktop notification.
+const NOTIFIED_LINES: usize = 4;
+
+/// A daily summary of runs, generated by the shell.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestSettings {
+    pub enabled: bool,
+    /// `HH:MM`, local to `time_zone`, the digest goes out at. It covers the time since the
+    /// previous digest.
+    pub time: String,
+    /// IANA time zone; unset follows the machine's zone.
+    pub time_zone: Option<String>,
+    /// Show the digest as a desktop notification.
+    pub notify: bool,
+    /// Email the digest, to `email_recipients` or else the default recipients.
+    pub email: bool,
+    pub email_recipients: Vec<String>,
+    /// Name of a configured webhook to send the digest to.
+    pub webhook: Option<String>,
+    /// Send nothing for a period without runs.
+    pub skip_empty: bool,
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        DigestSettings {
+            enabled: false,
+            time: "08:00".to_string(),
+            time_zone: None,
+            notify: true,
+            email: false,
+            email_recipients: Vec::new(),
+            webhook: None,
+            skip_empty: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestRun {
+    pub run_id: String,
+    /// The schedule's or recipe's name, or the prompt.
+    pub name: String,
+    pub message: Option<String>,
+}
+
+/// Something worth a look that happened in the period.
+#[derive(Debug, Clone, Serialize)]
+pub struct NotableChange {
+    /// `started_failing`, `recovered`, `monitor_change` or `broken_check`.
+    pub kind: &'static str,
+    pub name: String,
+    pub detail: String,
+    pub run_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    /// The period covered (Unix ms).
+    pub since: i64,
+    pub until: i64,
+    pub runs: u32,
+    pub successes: u32,
+    pub failures: u32,
+    /// Newest first.
+    pub failed: Vec<DigestRun>,
+    pub changes: Vec<NotableChange>,
+}
+
+/// Names runs by their schedule or recipe, falling back to the prompt.
+struct Names<'a> {
+    app: &'a AppHandle,
+    settings: &'a Settings,
+    recipes: HashMap<String, String>,
+}
+
+impl Names<'_> {
+    fn of(&mut self, run: &RunRecord) -> String {
+        if let Some(schedule) = self.settings.schedules.iter().find(|s| Some(&s.id) == run.schedule_id.as_ref()) {
+            return schedule.name.clone();
+        }
+        if let Some(recipe_id) = run.recipe_id.as_deref() {
+            let app = self.app;
+            return self
+                .recipes
+                .entry(recipe_id.to_string())
+                .or_insert_with(|| {
+                    recipes::load(app, recipe_id)
+                        .ok()
+                        .and_then(|recipe| recipe.task.name)
+                        .unwrap_or_else(|| recipe_id.to_string())
+                })
+                .clone();
+        }
+        let prompt = run.prompt.lines().next().unwrap_or_default();
+        match prompt.char_indices().nth(60) {
+            Some((cut, _)) => format!("{}...", &prompt[..cut]),
+            None => prompt.to_string(),
+        }
+    }
+}
+
+/// Schedules and recipes whose latest run in the period failed after their last run before
+/// it succeeded, or the other way round.
+fn status_changes(
+    history: &History,
+    names: &mut Names,
+    runs: &[RunRecord],
+    since: i64,
+) -> Result<Vec<NotableChange>, String> {
+    let mut latest: Vec<&RunRecord> = Vec::new();
+    for run in runs.iter().filter(|run| run.schedule_id.is_some() || run.recipe_id.is_some()) {
+        let same = |other: &&RunRecord| match run.schedule_id {
+            Some(_) => other.schedule_id == run.schedule_id,
+            None => other.schedule_id.is_none() && other.recipe_id == run.recipe_id,
+        };
+        // Runs are newest first, so the first of each is its latest
+        if !latest.iter().any(same) {
+            latest.push(run);
+        }
+    }
+    let mut changes = Vec::new();
+    for run in latest {
+        let previous = history.previous_finished_run(run.schedule_id.as_deref(), run.recipe_id.as_deref(), since)?;
+        if !previous.is_some_and(|previous| previous.status != run.status) {
+            continue;
+        }
+        let (kind, detail) = if run.status == "success" {
+            ("recovered", "Succeeded again".to_string())
+        } else {
+            ("started_failing", run.message.clone().unwrap_or_else(|| "Failed".to_string()))
+        };
+        changes.push(NotableChange { kind, name: names.of(run), detail, run_id: run.id.clone() });
+    }
+    Ok(changes)
+}
+
+/// Summarizes the runs started in `since..until` and what changed in that time.
+pub fn generate(app: &AppHandle, since: i64, until: i64) -> Result<Digest, String> {
+    let history = app.state::<History>();
+    let settings = app.state::<SettingsStore>().get();
+    let mut names = Names { app, settings: &settings, recipes: HashMap::new() };
+    let runs = history.finished_runs(since, until)?;
+    let successes = runs.iter().filter(|run| run.status == "success").count() as u32;
+    let failed = runs
+        .iter()
+        .filter(|run| run.status != "success")
+        .map(|run| DigestRun { run_id: run.id.clone(), name: names.of(run), message: run.message.clone() })
+        .collect();
+
+    let mut changes = status_changes(&history, &mut names, &runs, since)?;
+    let in_period = |at: i64| (since..until).contains(&at);
+    for change in history.monitor_changes(None, true, MAX_EVENTS)?.into_iter().filter(|c| in_period(c.at)) {
+        let name = settings
+            .schedules
+            .iter()
+            .find(|s| s.id == change.schedule_id)
+            .map_or_else(|| change.schedule_id.clone(), |s| s.name.clone());
+        changes.push(NotableChange {
+            kind: "monitor_change",
+            name,
+            detail: format!("{} changes", change.changes.len()),
+            run_id: change.run_id,
+        });
+    }
+    for alert in history.alerts(None, MAX_EVENTS)?.into_iter().filter(|a| in_period(a.at)) {
+        let name = recipes::load(app, &alert.recipe_id)
+            .ok()
+            .and_then(|recipe| recipe.task.name)
+            .unwrap_or_else(|| alert.recipe_id.clone());
+        changes.push(NotableChange {
+            kind: "broken_check",
+            name,
+            detail: format!("{}: {}", alert.expression, alert.detail),
+            run_id: alert.run_id,
+        });
+    }
+
+    Ok(Digest {
+        since,
+        until,
+        runs: runs.len() as u32,
+        successes,
+        failures: runs.len() as u32 - successes,
+        failed,
+        changes,
+    })
+}
+
+impl Digest {
+    fn title(&self) -> String {
+        match self.failures {
+            0 => format!("Daily digest: {} runs, all succeeded", self.runs),
+            failures => format!("Daily digest: {} runs, {} failed", self.runs, failures),
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} succeeded, {} failed", self.successes, self.failures)];
+        let mut section = |heading: &str, items: Vec<String>| {
+            if items.is_empty() {
+                return;
+            }
+            lines.push(String::new());
+            lines.push(heading.to_string());
+            let total = items.len();
+            lines.extend(items.into_iter().take(MAX_LISTED).map(|item| format!("• {}", item)));
+            if total > MAX_LISTED {
+                lines.push(format!("and {} more", total - MAX_LISTED));
+            }
+        };
+        let changes = |kind: &str| -> Vec<String> {
+            self.changes
+                .iter()
+                .filter(|change| change.kind == kind)
+                .map(|change| format!("{}: {}", change.name, change.detail))
+                .collect()
+        };
+        section("Started failing:", changes("started_failing"));
+        section("Recovered:", changes("recovered"));
+        section("Changed:", changes("monitor_change"));
+        section("Checks broken:", changes("broken_check"));
+        let failed = self
+            .failed
+            .iter()
+            .map(|run| match run.message.as_deref().filter(|m| !m.is_empty()) {
+                Some(message) => format!("{}: {}", run.name, message.lines().next().unwrap_or_default()),
+                None => run.name.clone(),
+            })
+            .collect();
+        section("Failed runs:", failed);
+        lines
+    }
+
+    fn text(&self) -> String {
+        let period = format!("{} to {}", reports::format_time(self.since), reports::format_time(self.until));
+        format!("{}\n\n{}\n", period, self.lines().join("\n"))
+    }
+}
+
+/// Sends the digest everywhere the settings ask for; the errors of those that failed.
+fn deliver(app: &AppHandle, settings: &DigestSettings, digest: &Digest) -> Result<(), String> {
+    if !settings.notify && !settings.email && settings.webhook.is_none() {
+        return Err("The digest has nowhere to go; turn on its notification, email or webhook".to_string());
+    }
+    let mut errors = Vec::new();
+    if settings.notify {
+        let lines: Vec<String> = digest.lines().into_iter().filter(|line| !line.is_empty()).collect();
+        notifications::notify(
+            app,
+            Notification {
+                title: digest.title(),
+                body: lines[..lines.len().min(NOTIFIED_LINES)].join("\n"),
+                urgency: Urgency::Normal,
+                category: Category::Digest,
+            },
+        );
+    }
+    if settings.email {
+        if let Err(e) = email::send_text(app, &digest.title(), &digest.text(), &settings.email_recipients) {
+            errors.push(format!("Digest email not sent: {}", e));
+        }
+    }
+    if let Some(name) = settings.webhook.as_deref() {
+        let webhooks = app.state::<SettingsStore>().get().webhooks;
+        // Reported like a finished job, so the webhook's template applies unchanged
+        let event = JobEvent {
+            job_id: "digest".to_string(),
+            kind: JobEventKind::Finished,
+            prompt: digest.title(),
+            status: Some(if digest.failures > 0 { JobStatus::Error } else { JobStatus::Success }),
+            message: Some(digest.text()),
+            duration_ms: None,
+            schedule_id: None,
+            recipe_id: None,
+            output: None,
+            step: None,
+            steps: None,
+        };
+        match webhooks.iter().find(|w| w.name == name) {
+            Some(webhook) => {
+                if let Err(e) = integrations::send(webhook, &event) {
+                    errors.push(e);
+                }
+            }
+            None => errors.push(format!("The digest names a webhook '{}' that does not exist", name)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// When today's digest is due (Unix ms).
+fn due_today(settings: &DigestSettings) -> Result<i64, String> {
+    let time = NaiveTime::parse_from_str(settings.time.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid digest time '{}'", settings.time.trim()))?;
+    let zone = timetable::zone(settings.time_zone.as_deref())?;
+    let today = Utc::now().with_timezone(&zone).date_naive();
+    Ok(timetable::resolve(zone, today.and_time(time)).0.timestamp_millis())
+}
+
+/// Sends the digest if it is due and hasn't gone out yet today.
+fn scheduled_digest(app: &AppHandle) -> Result<(), String> {
+    let settings = app.state::<SettingsStore>().get().notifications.digest;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let due = due_today(&settings)?;
+    let history = app.state::<History>();
+    let last = history.meta(LAST_DIGEST_KEY)?.and_then(|at| at.parse::<i64>().ok());
+    if history::now_ms() < due || last.is_some_and(|last| last >= due) {
+        return Ok(());
+    }
+    // Marked first, so a digest that fails to deliver isn't retried every minute
+    history.set_meta(LAST_DIGEST_KEY, &due.to_string())?;
+    let since = last.map_or(due - DAY_MS, |last| last.max(due - MAX_PERIOD_MS));
+    let digest = generate(app, since, due)?;
+    if digest.runs == 0 && digest.changes.is_empty() && settings.skip_empty {
+        log::info!("No runs since the last digest, skipping it");
+        return Ok(());
+    }
+    deliver(app, &settings, &digest)?;
+    log::info!("Daily digest sent: {} runs, {} failed", digest.runs, digest.failures);
+    Ok(())
+}
+
+/// Sends the daily digest when it is turned on and due.
+pub fn setup(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        if let Err(e) = scheduled_digest(&app) {
+            log::error!("Daily digest failed: {}", e);
+        }
+    });
+}
+
+/// The digest of the last 24 hours, as it would be sent now.
+#[tauri::command]
+pub async fn preview_digest(app: AppHandle) -> Result<Digest, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let now = history::now_ms();
+        generate(&app, now - DAY_MS, now)
+    })
+    .await
+    .map_err(|e| format!("Generating the digest failed: {}", e))?
+}
+
+/// Sends the digest of the last 24 hours now, e.g. to check where it goes. The daily
+/// digest still goes out as usual.
+#[tauri::command]
+pub async fn send_digest(app: AppHandle) -> Result<Digest, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let now = history::now_ms();
+        let digest = generate(&app, now - DAY_MS, now)?;
+        deliver(&app, &app.state::<SettingsStore>().get().notifications.digest, &digest)?;
+        Ok(digest)
+    })
+    .await
+    .map_err(|e| format!("Sending the digest failed: {}", e))?
+}
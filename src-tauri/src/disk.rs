@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::blobs;
+use crate::history::{self, History};
+use crate::notifications::{self, Category, Notification, Urgency};
+use crate::settings::SettingsStore;
+use crate::AppState;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// When run artifacts are deleted, and how little free space counts as a problem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// Delete a run's artifacts this many days after it started. 0 keeps them forever.
+    pub max_age_days: u32,
+    /// Keep artifacts of only the most recent runs. 0 keeps all.
+    pub keep_last_runs: u32,
+    /// Below this much free space (MB) the user is warned and the retention policy runs.
+    pub low_space_mb: u64,
+    /// Below this much free space (MB) scheduled jobs pause and the server is told to
+    /// hold off on artifact-heavy work.
+    pub critical_space_mb: u64,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        RetentionSettings { max_age_days: 30, keep_last_runs: 500, low_space_mb: 2048, critical_space_mb: 512 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpaceLevel {
+    Ok,
+    Low,
+    Critical,
+}
+
+/// Sent as `disk-space-changed` when the level changes.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskSpace {
+    pub free_mb: u64,
+    pub total_mb: u64,
+    pub level: SpaceLevel,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PurgeReport {
+    pub runs_purged: usize,
+    pub bytes_freed: u64,
+}
+
+static LEVEL: Mutex<SpaceLevel> = Mutex::new(SpaceLevel::Ok);
+
+/// Free and total bytes on the volume holding `path`.
+#[cfg(unix)]
+fn space(path: &Path) -> Result<(u64, u64), String> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    let block = stat.f_frsize as u64;
+    Ok((stat.f_bavail as u64 * block, stat.f_blocks as u64 * block))
+}
+
+#[cfg(windows)]
+fn space(path: &Path) -> Result<(u64, u64), String> {
+    use windows::core::HSTRING;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+    let (mut free, mut total) = (0u64, 0u64);
+    unsafe { GetDiskFreeSpaceExW(&HSTRING::from(path), Some(&mut free), Some(&mut total), None) }
+        .map_err(|e| e.to_string())?;
+    Ok((free, total))
+}
+
+fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+fn disk_space(app: &AppHandle) -> Result<DiskSpace, String> {
+    let dir = data_dir(app)?;
+    let (free, total) = space(&dir).map_err(|e| format!("Failed to read free space of {}: {}", dir.display(), e))?;
+    let (free_mb, total_mb) = (free / (1024 * 1024), total / (1024 * 1024));
+    let retention = app.state::<SettingsStore>().get().retention;
+    let level = if free_mb < retention.critical_space_mb {
+        SpaceLevel::Critical
+    } else if free_mb < retention.low_space_mb {
+        SpaceLevel::Low
+    } else {
+        SpaceLevel::Ok
+    };
+    Ok(DiskSpace { free_mb, total_mb, level })
+}
+
+/// Whether free space is critically low; jobs that write artifacts should wait.
+pub fn is_critical() -> bool {
+    *LEVEL.lock().unwrap() == SpaceLevel::Critical
+}
+
+fn dir_size(path: &Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.file_type() {
+                    Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+                    _ => entry.metadata().map(|m| m.len()).unwrap_or(0),
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Deletes artifacts of runs that started more than `max_age_days` ago or fall outside
+/// the `keep_last` most recent runs (0 turns either rule off). Run history is kept.
+/// Artifact dirs with no history entry are judged by their modification time.
+pub fn purge(app: &AppHandle, max_age_days: u32, keep_last: u32) -> Result<PurgeReport, String> {
+    let runs_dir = data_dir(app)?.join("runs");
+    let Ok(entries) = std::fs::read_dir(&runs_dir) else {
+        return Ok(PurgeReport::default());
+    };
+    // Newest first
+    let known = app.state::<History>().started_at()?;
+    let cutoff = (max_age_days > 0).then(|| history::now_ms() - i64::from(max_age_days) * DAY_MS);
+
+    let mut report = PurgeReport::default();
+    for entry in entries.flatten().filter(|e| e.file_type().is_ok_and(|t| t.is_dir())) {
+        let id = entry.file_name().to_string_lossy().into_owned();
+        let started_at = match known.iter().position(|(run, _)| *run == id) {
+            Some(rank) if keep_last > 0 && rank >= keep_last as usize => None,
+            Some(rank) => Some(known[rank].1),
+            None => entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64),
+        };
+        let expired = match (started_at, cutoff) {
+            (None, _) => true,
+            (Some(started_at), Some(cutoff)) => started_at < cutoff,
+            (Some(_), None) => false,
+        };
+        if !expired {
+            continue;
+        }
+        let size = dir_size(&entry.path());
+        match std::fs::remove_dir_all(entry.path()) {
+            Ok(()) => {
+                report.runs_purged += 1;
+                report.bytes_freed += size;
+            }
+            Err(e) => log::warn!("Failed to delete artifacts of run {}: {}", id, e),
+        }
+    }
+    if report.runs_purged > 0 {
+        log::info!("Purged artifacts of {} runs ({} bytes)", report.runs_purged, report.bytes_freed);
+    }
+    Ok(report)
+}
+
+fn level_changed(app: &AppHandle, space: &DiskSpace) {
+    let critical = space.level == SpaceLevel::Critical;
+    if let Err(e) = app.state::<AppState>().control.push_config(serde_json::json!({ "disk_critical": critical })) {
+        log::debug!("Disk state not pushed to the server: {}", e);
+    }
+    let _ = app.emit("disk-space-changed", space);
+    if space.level == SpaceLevel::Ok {
+        log::info!("Free disk space back to {} MB", space.free_mb);
+        return;
+    }
+    log::warn!("Low disk space: {} MB free ({:?})", space.free_mb, space.level);
+    notifications::notify(
+        app,
+        Notification {
+            title: "Low disk space".to_string(),
+            body: if critical {
+                format!("Only {} MB free. Scheduled automations are paused until space is freed.", space.free_mb)
+            } else {
+                format!("Only {} MB free. Old run artifacts are being cleaned up.", space.free_mb)
+            },
+            urgency: if critical { Urgency::Critical } else { Urgency::Normal },
+            category: Category::System,
+        },
+    );
+}
+
+/// Checks free space periodically, runs the retention policy daily and whenever space
+/// runs low, and reports level changes.
+pub fn setup(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut last_purge: Option<SystemTime> = None;
+        loop {
+            match disk_space(&app) {
+                Ok(space) => {
+                    let retention = app.state::<SettingsStore>().get().retention;
+                    let daily = !last_purge.is_some_and(|t| t.elapsed().unwrap_or_default() < Duration::from_secs(86400));
+                    if daily || space.level != SpaceLevel::Ok {
+                        last_purge = Some(SystemTime::now());
+                        if let Err(e) = purge(&app, retention.max_age_days, retention.keep_last_runs) {
+                            log::warn!("Artifact retention failed: {}", e);
+                        }
+                        if let Err(e) = blobs::gc(&app) {
+                            log::warn!("Artifact blob cleanup failed: {}", e);
+                        }
+                    }
+                    // Judge the level after cleaning up
+                    let space = disk_space(&app).unwrap_or(space);
+                    let changed = std::mem::replace(&mut *LEVEL.lock().unwrap(), space.level) != space.level;
+                    if changed {
+                        level_changed(&app, &space);
+                    }
+                }
+                Err(e) => log::warn!("{}", e),
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+pub fn server_env() -> &'static str {
+    if is_critical() {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+#[tauri::command]
+pub async fn get_disk_space(app: AppHandle) -> Result<DiskSpace, String> {
+    tauri::async_runtime::spawn_blocking(move || disk_space(&app))
+        .await
+        .map_err(|e| format!("Failed to read disk space: {}", e))?
+}
+
+/// Deletes old run artifacts now. Rules not given come from the retention settings.
+#[tauri::command]
+pub async fn purge_artifacts(
+    app: AppHandle,
+    max_age_days: Option<u32>,
+    keep_last: Option<u32>,
+) -> Result<PurgeReport, String> {
+    let retention = app.state::<SettingsStore>().get().retention;
+    let (max_age_days, keep_last) =
+        (max_age_days.unwrap_or(retention.max_age_days), keep_last.unwrap_or(retention.keep_last_runs));
+    tauri::async_runtime::spawn_blocking(move || purge(&app, max_age_days, keep_last))
+        .await
+        .map_err(|e| format!("Failed to purge artifacts: {}", e))?
+}
@@ -0,0 +1,362 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::settings::SettingsStore;
+use crate::AppState;
+
+#[cfg(target_os = "linux")]
+use dbus::blocking::Connection;
+#[cfg(target_os = "linux")]
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "linux")]
+const PORTAL_SERVICE: &str = "org.freedesktop.portal.Desktop";
+#[cfg(target_os = "linux")]
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+#[cfg(target_os = "linux")]
+const SCREENSHOT_PORTAL: &str = "org.freedesktop.portal.Screenshot";
+#[cfg(target_os = "linux")]
+const SHORTCUTS_PORTAL: &str = "org.freedesktop.portal.GlobalShortcuts";
+/// The portal may ask the user to approve the capture first.
+#[cfg(target_os = "linux")]
+const PORTAL_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayServer {
+    X11,
+    Wayland,
+    /// An X11 window on a Wayland session; it can only see other X11 clients.
+    Xwayland,
+    Quartz,
+    Windows,
+    Unknown,
+}
+
+/// A rectangle in physical pixels on the virtual desktop.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A connected monitor, as `list_displays` reports it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Display {
+    /// The monitor's name where the OS gives one, otherwise its position in the list.
+    /// Names survive reconnecting and reordering; indexes do not.
+    pub id: String,
+    pub bounds: Bounds,
+    /// `bounds` minus taskbars, docks and menu bars.
+    pub work_area: Bounds,
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+/// How a desktop feature is reached on this display server.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// Direct OS or X11 APIs.
+    Native,
+    /// xdg-desktop-portal over D-Bus, the only route under Wayland.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    Portal,
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayCapabilities {
+    pub display_server: DisplayServer,
+    pub screenshot: Backend,
+    pub global_shortcuts: Backend,
+}
+
+pub fn display_server() -> DisplayServer {
+    if cfg!(target_os = "macos") {
+        return DisplayServer::Quartz;
+    }
+    if cfg!(windows) {
+        return DisplayServer::Windows;
+    }
+    let wayland_session = std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland");
+    // GDK picks the first backend listed in GDK_BACKEND
+    let forced_x11 = std::env::var("GDK_BACKEND").is_ok_and(|b| b.trim_start().starts_with("x11"));
+    match (wayland_session, forced_x11, std::env::var_os("DISPLAY").is_some()) {
+        (true, false, _) => DisplayServer::Wayland,
+        (true, true, _) => DisplayServer::Xwayland,
+        (false, _, true) => DisplayServer::X11,
+        (false, _, false) => DisplayServer::Unknown,
+    }
+}
+
+fn display_id(monitor: &Monitor, index: usize) -> String {
+    monitor.name().cloned().unwrap_or_else(|| format!("display-{}", index + 1))
+}
+
+fn to_display(monitor: &Monitor, index: usize, primary: Option<&Monitor>) -> Display {
+    let (position, size) = (monitor.position(), monitor.size());
+    let work_area = monitor.work_area();
+    Display {
+        id: display_id(monitor, index),
+        bounds: Bounds { x: position.x, y: position.y, width: size.width, height: size.height },
+        work_area: Bounds {
+            x: work_area.position.x,
+            y: work_area.position.y,
+            width: work_area.size.width,
+            height: work_area.size.height,
+        },
+        scale_factor: monitor.scale_factor(),
+        primary: primary.is_some_and(|p| p.position() == position && p.size() == size),
+    }
+}
+
+pub fn displays(app: &AppHandle) -> Result<Vec<Display>, String> {
+    let monitors = app.available_monitors().map_err(|e| format!("Failed to list displays: {}", e))?;
+    let primary = app.primary_monitor().ok().flatten();
+    Ok(monitors.iter().enumerate().map(|(i, m)| to_display(m, i, primary.as_ref())).collect())
+}
+
+fn find_display(app: &AppHandle, id: &str) -> Result<Display, String> {
+    displays(app)?
+        .into_iter()
+        .find(|display| display.id == id)
+        .ok_or_else(|| format!("No display '{}'", id))
+}
+
+/// Where the headed automation browser should open: the `browser_display` setting's work
+/// area as `x,y,width,height` in logical pixels, which is what Chromium's window flags
+/// take. Empty when no display is chosen or it is not connected.
+pub fn browser_bounds(app: &AppHandle) -> String {
+    let Some(id) = app.state::<SettingsStore>().get().browser_display else {
+        return String::new();
+    };
+    match find_display(app, &id) {
+        Ok(display) => {
+            let scale = display.scale_factor;
+            let area = display.work_area;
+            format!(
+                "{},{},{},{}",
+                (area.x as f64 / scale).round(),
+                (area.y as f64 / scale).round(),
+                (area.width as f64 / scale).round(),
+                (area.height as f64 / scale).round()
+            )
+        }
+        Err(e) => {
+            log::warn!("Browser display not applied: {}", e);
+            String::new()
+        }
+    }
+}
+
+/// Tells a running server where to open the browser after `browser_display` changed.
+pub fn apply_settings(app: &AppHandle) {
+    let bounds = browser_bounds(app);
+    if let Err(e) = app.state::<AppState>().control.push_config(serde_json::json!({ "browser_bounds": bounds })) {
+        log::debug!("Browser display not pushed to the server: {}", e);
+    }
+}
+
+/// Whether the desktop portal implements `interface` (it reports a version for it).
+#[cfg(target_os = "linux")]
+fn portal_has(interface: &str) -> bool {
+    use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+    let Ok(conn) = Connection::new_session() else {
+        return false;
+    };
+    let proxy = conn.with_proxy(PORTAL_SERVICE, PORTAL_PATH, Duration::from_secs(2));
+    proxy.get::<u32>(interface, "version").is_ok()
+}
+
+#[cfg(target_os = "linux")]
+pub fn capabilities() -> DisplayCapabilities {
+    let display_server = display_server();
+    let portal_or_nothing =
+        |interface| if portal_has(interface) { Backend::Portal } else { Backend::Unavailable };
+    let (screenshot, global_shortcuts) = match display_server {
+        DisplayServer::X11 => (Backend::Native, Backend::Native),
+        DisplayServer::Wayland | DisplayServer::Xwayland => {
+            (portal_or_nothing(SCREENSHOT_PORTAL), portal_or_nothing(SHORTCUTS_PORTAL))
+        }
+        _ => (Backend::Unavailable, Backend::Unavailable),
+    };
+    DisplayCapabilities { display_server, screenshot, global_shortcuts }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn capabilities() -> DisplayCapabilities {
+    DisplayCapabilities {
+        display_server: display_server(),
+        screenshot: Backend::Unavailable,
+        global_shortcuts: Backend::Native,
+    }
+}
+
+/// Grabs the X11 root window. GDK must be used from the UI thread.
+#[cfg(target_os = "linux")]
+fn capture_x11(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let path = path.to_path_buf();
+    app.run_on_main_thread(move || {
+        use gtk::gdk::prelude::WindowExtManual;
+        let root = gtk::gdk::Window::default_root_window();
+        let result = root
+            .pixbuf(0, 0, root.width(), root.height())
+            .ok_or_else(|| "Failed to read the screen".to_string())
+            .and_then(|pixbuf| pixbuf.savev(&path, "png", &[]).map_err(|e| e.to_string()));
+        let _ = tx.send(result);
+    })
+    .map_err(|e| e.to_string())?;
+    rx.recv().map_err(|_| "Screenshot was interrupted".to_string())?
+}
+
+/// Asks the desktop portal for a screenshot and moves the file it saves to `path`.
+#[cfg(target_os = "linux")]
+fn capture_portal(path: &Path) -> Result<(), String> {
+    use dbus::arg::{prop_cast, PropMap, Variant};
+    use dbus::message::MatchRule;
+    use std::sync::{Arc, Mutex};
+
+    let conn = Connection::new_session().map_err(|e| format!("Session bus unavailable: {}", e))?;
+    let token = format!("nova{}", uuid::Uuid::new_v4().simple());
+    let sender = conn.unique_name().trim_start_matches(':').replace('.', "_");
+    let request_path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+
+    // Subscribe before calling so a quick reply is not missed
+    let response: Arc<Mutex<Option<(u32, PropMap)>>> = Arc::default();
+    let rule = MatchRule::new_signal("org.freedesktop.portal.Request", "Response")
+        .with_path(dbus::Path::from(request_path));
+    let slot = response.clone();
+    conn.add_match(rule, move |reply: (u32, PropMap), _, _| {
+        *slot.lock().unwrap() = Some(reply);
+        false
+    })
+    .map_err(|e| e.to_string())?;
+
+    let mut options = PropMap::new();
+    options.insert("handle_token".to_string(), Variant(Box::new(token)));
+    options.insert("interactive".to_string(), Variant(Box::new(false)));
+    let proxy = conn.with_proxy(PORTAL_SERVICE, PORTAL_PATH, Duration::from_secs(5));
+    let _: (dbus::Path,) = proxy
+        .method_call(SCREENSHOT_PORTAL, "Screenshot", ("", options))
+        .map_err(|e| format!("Screenshot portal unavailable: {}", e))?;
+
+    let started = Instant::now();
+    let (code, results) = loop {
+        if let Some(reply) = response.lock().unwrap().take() {
+            break reply;
+        }
+        if started.elapsed() > PORTAL_TIMEOUT {
+            return Err("The screenshot portal did not answer".to_string());
+        }
+        conn.process(Duration::from_millis(250)).map_err(|e| e.to_string())?;
+    };
+    if code != 0 {
+        return Err("Screenshot was cancelled".to_string());
+    }
+    let source = prop_cast::<String>(&results, "uri")
+        .and_then(|uri| tauri::Url::parse(uri).ok())
+        .and_then(|url| url.to_file_path().ok())
+        .ok_or("The screenshot portal returned no file")?;
+    // The portal saves to the user's pictures dir; copy in case it is another filesystem
+    std::fs::copy(&source, path).map_err(|e| format!("Failed to save screenshot: {}", e))?;
+    let _ = std::fs::remove_file(&source);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn capture(app: &AppHandle, path: &Path) -> Result<(), String> {
+    match capabilities().screenshot {
+        Backend::Native => capture_x11(app, path),
+        Backend::Portal => capture_portal(path),
+        Backend::Unavailable => Err(format!(
+            "Screenshots are not available on this desktop ({:?} without a screenshot portal)",
+            display_server()
+        )),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn capture(_app: &AppHandle, _path: &Path) -> Result<(), String> {
+    Err("Screenshots are not supported on this platform yet".to_string())
+}
+
+fn ask_path(app: &AppHandle) -> Option<PathBuf> {
+    app.dialog()
+        .file()
+        .set_title("Save Screenshot")
+        .set_file_name("screenshot.png")
+        .add_filter("PNG", &["png"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+}
+
+/// Which display server the app runs on and how screenshots and global shortcuts can be
+/// reached there, so the UI can hide what would not work.
+#[tauri::command]
+pub async fn get_display_capabilities() -> Result<DisplayCapabilities, String> {
+    tauri::async_runtime::spawn_blocking(capabilities)
+        .await
+        .map_err(|e| format!("Failed to query display capabilities: {}", e))
+}
+
+/// Saves a screenshot of the screen as PNG. Without a `path` a save dialog is shown;
+/// returns the path written, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn capture_screenshot(app: AppHandle, path: Option<PathBuf>) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(path) = path.or_else(|| ask_path(&app)) else {
+            return Ok(None);
+        };
+        capture(&app, &path)?;
+        log::info!("Screenshot saved to {}", path.display());
+        Ok(Some(path))
+    })
+    .await
+    .map_err(|e| format!("Screenshot failed: {}", e))?
+}
+
+/// Connected monitors with their bounds, usable area, scale factor and which is primary.
+#[tauri::command]
+pub fn list_displays(app: AppHandle) -> Result<Vec<Display>, String> {
+    displays(&app)
+}
+
+/// Centers an app window (the main window unless `window` names another) on a display,
+/// keeping it maximized or fullscreen if it was.
+#[tauri::command]
+pub fn move_window_to_display(app: AppHandle, id: String, window: Option<String>) -> Result<(), String> {
+    let label = window.as_deref().unwrap_or("main");
+    let window = app.get_webview_window(label).ok_or_else(|| format!("No window '{}'", label))?;
+    let area = find_display(&app, &id)?.work_area;
+
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+    let maximized = window.is_maximized().unwrap_or(false);
+    // Maximized and fullscreen windows stay on their monitor until restored
+    if fullscreen {
+        window.set_fullscreen(false).map_err(|e| e.to_string())?;
+    }
+    if maximized {
+        window.unmaximize().map_err(|e| e.to_string())?;
+    }
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let x = area.x + (area.width.saturating_sub(size.width) / 2) as i32;
+    let y = area.y + (area.height.saturating_sub(size.height) / 2) as i32;
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| format!("Failed to move window: {}", e))?;
+    if maximized {
+        window.maximize().map_err(|e| e.to_string())?;
+    }
+    if fullscreen {
+        window.set_fullscreen(true).map_err(|e| e.to_string())?;
+    }
+    log::info!("Moved window '{}' to display '{}'", label, id);
+    Ok(())
+}
@@ -0,0 +1,327 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Url};
+
+use crate::access;
+use crate::history::{self, DomainOverride, History, PolicyAuditEntry};
+use crate::settings::SettingsStore;
+
+/// How long a fetched robots.txt is trusted.
+const ROBOTS_TTL: Duration = Duration::from_secs(60 * 60);
+const ROBOTS_TIMEOUT: Duration = Duration::from_secs(5);
+/// Larger robots.txt files are cut off here, as crawlers do.
+const ROBOTS_MAX_BYTES: u64 = 500 * 1024;
+/// The product token robots.txt groups are matched against.
+const USER_AGENT: &str = "BrowserAutomation";
+/// Hours an override lasts when none are given.
+const DEFAULT_OVERRIDE_HOURS: u32 = 24;
+const DEFAULT_LIMIT: usize = 100;
+/// Endings of words like `results.csv` that look like domains but are files.
+const FILE_EXTENSIONS: &[&str] = &[
+    "csv", "doc", "docx", "gif", "htm", "html", "jpeg", "jpg", "js", "json", "md", "pdf", "png", "py", "ts", "txt",
+    "xls", "xlsx", "xml", "zip",
+];
+
+/// Restricts which sites jobs may be sent to, for organizations that must show where their
+/// automations went. Checked by the shell before a job reaches the server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DomainPolicySettings {
+    pub enabled: bool,
+    /// Domains jobs may target, each including its subdomains.
+    pub allowed_domains: Vec<String>,
+    /// Also allow other sites whose robots.txt permits the page.
+    pub allow_by_robots: bool,
+}
+
+/// A robots.txt rule: whether it allows, and its path pattern.
+type Rule = (bool, String);
+
+/// A site's robots.txt rules for us, longest pattern first.
+#[derive(Debug, Clone)]
+enum Robots {
+    Rules(Vec<Rule>),
+    AllowAll,
+    DisallowAll,
+}
+
+/// Whether a robots.txt path pattern (with `*` and a trailing `$`) matches `path`.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return !anchored || rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    if anchored {
+        rest.ends_with(last)
+    } else {
+        rest.contains(last)
+    }
+}
+
+impl Robots {
+    /// Follows RFC 9309: the group naming our product token, else the `*` group; the
+    /// longest matching rule wins and `Allow` wins ties.
+    fn parse(text: &str) -> Robots {
+        let mut groups: Vec<(Vec<String>, Vec<Rule>)> = Vec::new();
+        let mut in_agents = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+            match key.as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push((Vec::new(), Vec::new()));
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.0.push(value.to_ascii_lowercase());
+                    }
+                    in_agents = true;
+                }
+                "allow" | "disallow" => {
+                    in_agents = false;
+                    // An empty Disallow allows everything, like no rule at all
+                    if let (Some(group), false) = (groups.last_mut(), value.is_empty()) {
+                        group.1.push((key == "allow", value.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        let token = USER_AGENT.to_ascii_lowercase();
+        let has_group = |name: &str| groups.iter().any(|(agents, _)| agents.iter().any(|agent| agent == name));
+        let agent = if has_group(&token) { token.as_str() } else { "*" };
+        let mut rules: Vec<Rule> = groups
+            .iter()
+            .filter(|(agents, _)| agents.iter().any(|name| name == agent))
+            .flat_map(|(_, rules)| rules.clone())
+            .collect();
+        rules.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(b.0.cmp(&a.0)));
+        Robots::Rules(rules)
+    }
+
+    fn allows(&self, path: &str) -> bool {
+        match self {
+            Robots::AllowAll => true,
+            Robots::DisallowAll => false,
+            Robots::Rules(rules) => {
+                rules.iter().find(|(_, pattern)| pattern_matches(pattern, path)).map_or(true, |(allow, _)| *allow)
+            }
+        }
+    }
+}
+
+fn robots_cache() -> &'static Mutex<HashMap<String, (Instant, Robots)>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (Instant, Robots)>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// A missing robots.txt (4xx) allows everything; one that can't be fetched (5xx, network
+/// errors) allows nothing, since we can't tell what the site wants.
+fn fetch_robots(origin: &str) -> Robots {
+    let agent = ureq::AgentBuilder::new().timeout(ROBOTS_TIMEOUT).user_agent(USER_AGENT).build();
+    match agent.get(&format!("{}/robots.txt", origin)).call() {
+        Ok(response) => {
+            let mut text = String::new();
+            let mut reader = std::io::Read::take(response.into_reader(), ROBOTS_MAX_BYTES);
+            if let Err(e) = std::io::Read::read_to_string(&mut reader, &mut text) {
+                log::warn!("Failed to read {}/robots.txt: {}", origin, e);
+                return Robots::DisallowAll;
+            }
+            Robots::parse(&text)
+        }
+        Err(ureq::Error::Status(code, _)) if code < 500 => Robots::AllowAll,
+        Err(e) => {
+            log::warn!("Failed to fetch {}/robots.txt: {}", origin, e);
+            Robots::DisallowAll
+        }
+    }
+}
+
+fn robots_allow(url: &Url) -> bool {
+    let origin = url.origin().ascii_serialization();
+    let cached = robots_cache()
+        .lock()
+        .unwrap()
+        .get(&origin)
+        .filter(|(fetched, _)| fetched.elapsed() < ROBOTS_TTL)
+        .map(|(_, robots)| robots.clone());
+    let robots = cached.unwrap_or_else(|| {
+        let robots = fetch_robots(&origin);
+        robots_cache().lock().unwrap().insert(origin, (Instant::now(), robots.clone()));
+        robots
+    });
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    robots.allows(&path)
+}
+
+/// A bare word like `amazon.com` or `shop.example.co.uk/deals` that names a site.
+fn is_bare_domain(word: &str) -> bool {
+    let host = word.split('/').next().unwrap_or_default();
+    let labels: Vec<&str> = host.split('.').collect();
+    let tld = labels.last().copied().unwrap_or_default();
+    labels.len() >= 2
+        && labels.iter().all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+        && !FILE_EXTENSIONS.contains(&tld.to_ascii_lowercase().as_str())
+}
+
+/// The web addresses a prompt names. Prompts are natural language, so only addresses
+/// written out (`https://…`, `www.…`, `amazon.com`) are recognized.
+pub fn target_urls(prompt: &str) -> Vec<Url> {
+    let trim = |c: char| matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | ')' | ']' | '}' | '>' | '"' | '\'' | '`');
+    prompt
+        .split_whitespace()
+        .map(|word| word.trim_start_matches(['(', '[', '{', '<', '"', '\'', '`']).trim_end_matches(trim))
+        .filter_map(|word| {
+            let lower = word.to_ascii_lowercase();
+            if lower.starts_with("http://") || lower.starts_with("https://") {
+                Url::parse(word).ok()
+            } else if lower.starts_with("www.") || is_bare_domain(word) {
+                Url::parse(&format!("https://{}", word)).ok()
+            } else {
+                None
+            }
+        })
+        .filter(|url| url.host_str().is_some())
+        .collect()
+}
+
+/// Lowercase, without a leading `.` or `www.`, so `www.example.com` covers the whole site.
+fn normalize(domain: &str) -> String {
+    let domain = domain.trim().trim_start_matches('.').to_ascii_lowercase();
+    domain.strip_prefix("www.").map(str::to_string).unwrap_or(domain)
+}
+
+fn covers(domain: &str, host: &str) -> bool {
+    let domain = normalize(domain);
+    !domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain)))
+}
+
+fn audit(app: &AppHandle, domain: &str, event: &str, detail: Option<String>) {
+    let entry = PolicyAuditEntry {
+        at: history::now_ms(),
+        domain: domain.to_string(),
+        event: event.to_string(),
+        detail,
+    };
+    if let Err(e) = app.state::<History>().add_policy_audit(&entry) {
+        log::warn!("Failed to record domain policy {} for {}: {}", event, domain, e);
+    }
+}
+
+/// The URL without its query and fragment, which may carry tokens.
+fn describe(url: &Url) -> String {
+    format!("{}{}", url.origin().ascii_serialization(), url.path())
+}
+
+/// Fails unless every site the prompt names is allowed by the domain policy: allowlisted,
+/// overridden, or (if enabled) permitted by its robots.txt. Refusals and uses of overrides
+/// are audited. Passes everything when the policy is off.
+pub fn check(app: &AppHandle, prompt: &str) -> Result<(), String> {
+    let policy = app.state::<SettingsStore>().get().domain_policy;
+    if !policy.enabled {
+        return Ok(());
+    }
+    for url in target_urls(prompt) {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        if policy.allowed_domains.iter().any(|domain| covers(domain, &host)) {
+            continue;
+        }
+        let overrides = app.state::<History>().domain_overrides(history::now_ms())?;
+        if let Some(granted) = overrides.iter().find(|o| covers(&o.domain, &host)) {
+            log::info!("Domain policy override for {} lets a job reach {}", granted.domain, host);
+            audit(app, &granted.domain, "override_used", Some(describe(&url)));
+            continue;
+        }
+        if policy.allow_by_robots && robots_allow(&url) {
+            continue;
+        }
+        log::warn!("Domain policy refused a job targeting {}", host);
+        audit(app, &host, "refused", Some(describe(&url)));
+        return Err(format!("Blocked by the domain policy: {} is not an allowed site", host));
+    }
+    Ok(())
+}
+
+/// Lets jobs target `domain` (and its subdomains) despite the domain policy for `hours`,
+/// 24 by default. The reason is required and kept in the audit log.
+#[tauri::command]
+pub fn override_domain_policy(
+    app: AppHandle,
+    domain: String,
+    reason: String,
+    hours: Option<u32>,
+) -> Result<DomainOverride, String> {
+    let domain = normalize(&domain);
+    if domain.is_empty() || domain.contains(['/', ':', ' ']) {
+        return Err(format!("Not a domain: {}", domain));
+    }
+    if reason.trim().is_empty() {
+        return Err("An override needs a reason".to_string());
+    }
+    let hours = hours.unwrap_or(DEFAULT_OVERRIDE_HOURS).max(1);
+    let now = history::now_ms();
+    let entry = DomainOverride {
+        domain,
+        reason: reason.trim().to_string(),
+        granted_by: access::name(&access::role(&app)),
+        granted_at: now,
+        expires_at: now + i64::from(hours) * 60 * 60 * 1000,
+    };
+    app.state::<History>().set_domain_override(&entry)?;
+    log::info!("Domain policy overridden for {} for {} hours", entry.domain, hours);
+    audit(
+        &app,
+        &entry.domain,
+        "override_granted",
+        Some(format!("{} for {} hours by {}: {}", entry.domain, hours, entry.granted_by, entry.reason)),
+    );
+    Ok(entry)
+}
+
+/// Ends an override before it expires.
+#[tauri::command]
+pub fn revoke_domain_override(app: AppHandle, domain: String) -> Result<(), String> {
+    let domain = normalize(&domain);
+    if !app.state::<History>().remove_domain_override(&domain)? {
+        return Err(format!("No override for {}", domain));
+    }
+    log::info!("Domain policy override for {} revoked", domain);
+    audit(&app, &domain, "override_revoked", Some(format!("by {}", access::name(&access::role(&app)))));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_domain_overrides(app: AppHandle) -> Result<Vec<DomainOverride>, String> {
+    app.state::<History>().domain_overrides(history::now_ms())
+}
+
+/// Refusals and overrides, newest first.
+#[tauri::command]
+pub fn list_policy_audit(app: AppHandle, limit: Option<usize>) -> Result<Vec<PolicyAuditEntry>, String> {
+    app.state::<History>().policy_audit(limit.unwrap_or(DEFAULT_LIMIT))
+}
@@ -0,0 +1,381 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::settings::SettingsStore;
+
+/// Progress events are sent at most this often per download.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// A transfer that stalls this long fails and can be resumed.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// Suffix of the file a download is written to until it completes.
+const PARTIAL_SUFFIX: &str = ".part";
+
+// What a running transfer should do next; checked between chunks
+const RUN: u8 = 0;
+const PAUSE: u8 = 1;
+const CANCEL: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    Downloading,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A download as the frontend sees it, sent as `download-progress` while it runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadInfo {
+    pub id: String,
+    pub url: String,
+    /// Final location; the data is in `<path>.part` until the download completes.
+    pub path: PathBuf,
+    pub received: u64,
+    /// From `Content-Length`, when the server sends one.
+    pub total: Option<u64>,
+    pub state: DownloadState,
+    pub error: Option<String>,
+    /// The job that produced the file, when the server asked for the download.
+    pub job_id: Option<String>,
+}
+
+/// A file the server or the webview wants saved, as sent in `download.request`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadRequest {
+    pub url: String,
+    #[serde(default)]
+    pub file_name: Option<String>,
+    #[serde(default)]
+    pub job_id: Option<String>,
+}
+
+struct Download {
+    info: DownloadInfo,
+    control: Arc<AtomicU8>,
+}
+
+#[derive(Default)]
+pub struct Downloads {
+    downloads: Mutex<HashMap<String, Download>>,
+}
+
+impl Downloads {
+    fn update(&self, id: &str, f: impl FnOnce(&mut DownloadInfo)) -> Option<DownloadInfo> {
+        let mut downloads = self.downloads.lock().unwrap();
+        let download = downloads.get_mut(id)?;
+        f(&mut download.info);
+        Some(download.info.clone())
+    }
+
+    fn info(&self, id: &str) -> Result<DownloadInfo, String> {
+        self.downloads
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|d| d.info.clone())
+            .ok_or_else(|| format!("No download '{}'", id))
+    }
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(PARTIAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Keeps only the last path component and drops characters Windows does not allow.
+fn sanitize_file_name(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let clean: String = name
+        .chars()
+        .map(|c| if c.is_control() || "<>:\"|?*".contains(c) { '_' } else { c })
+        .collect();
+    let clean = clean.trim().trim_matches('.').to_string();
+    if clean.is_empty() {
+        "download".to_string()
+    } else {
+        clean
+    }
+}
+
+fn file_name_from_url(url: &tauri::Url) -> String {
+    url.path_segments()
+        .and_then(|segments| segments.rev().find(|s| !s.is_empty()))
+        .map(percent_decode)
+        .unwrap_or_default()
+}
+
+/// Decodes `%XX` escapes in a URL path or query value, leaving invalid ones as they are.
+pub fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// `name`, or `name (1)`, `name (2)`… before the extension, whichever is not taken on
+/// disk or by another download in progress.
+pub fn unique_path(dir: &Path, name: &str, taken: &[PathBuf]) -> PathBuf {
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 => (&name[..dot], &name[dot..]),
+        _ => (name, ""),
+    };
+    (0..)
+        .map(|n| if n == 0 { dir.join(name) } else { dir.join(format!("{} ({}){}", stem, n, ext)) })
+        .find(|path| !path.exists() && !partial_path(path).exists() && !taken.contains(path))
+        .unwrap()
+}
+
+/// `directory`, else the download folder from the settings, else the user's Downloads.
+pub fn download_dir(app: &AppHandle, directory: Option<PathBuf>) -> Result<PathBuf, String> {
+    directory
+        .or_else(|| app.state::<SettingsStore>().get().download_dir)
+        .map(Ok)
+        .unwrap_or_else(|| app.path().download_dir().map_err(|e| format!("No Downloads folder: {}", e)))
+}
+
+fn emit(app: &AppHandle, info: &DownloadInfo) {
+    let _ = app.emit("download-progress", info);
+}
+
+/// Fetches into the `.part` file, resuming from what is already there, until done,
+/// paused or cancelled.
+fn transfer(app: &AppHandle, id: &str, control: &AtomicU8) -> Result<DownloadState, String> {
+    let downloads = app.state::<Downloads>();
+    let info = downloads.info(id)?;
+    let partial = partial_path(&info.path);
+    let offset = std::fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+
+    let agent = ureq::AgentBuilder::new().timeout_connect(CONNECT_TIMEOUT).timeout_read(READ_TIMEOUT).build();
+    let mut request = agent.get(&info.url);
+    if offset > 0 {
+        request = request.set("Range", &format!("bytes={}-", offset));
+    }
+    let response = request.call().map_err(|e| format!("Download failed: {}", e))?;
+    // A server that ignores the range sends the whole file again
+    let resumed = offset > 0 && response.status() == 206;
+    let length: Option<u64> = response.header("Content-Length").and_then(|l| l.parse().ok());
+    let mut received = if resumed { offset } else { 0 };
+    let total = length.map(|length| length + received);
+
+    let mut file: File = if resumed {
+        OpenOptions::new().append(true).open(&partial)
+    } else {
+        File::create(&partial)
+    }
+    .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+
+    if let Some(info) = downloads.update(id, |info| {
+        info.received = received;
+        info.total = total;
+    }) {
+        emit(app, &info);
+    }
+
+    let mut reader = response.into_reader();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut last_progress = Instant::now();
+    loop {
+        let stop = match control.load(Ordering::Relaxed) {
+            PAUSE => Some(DownloadState::Paused),
+            CANCEL => Some(DownloadState::Cancelled),
+            _ => None,
+        };
+        if let Some(state) = stop {
+            downloads.update(id, |info| info.received = received);
+            return Ok(state);
+        }
+        let read = reader.read(&mut buffer).map_err(|e| format!("Download interrupted: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read]).map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+        received += read as u64;
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            if let Some(info) = downloads.update(id, |info| info.received = received) {
+                emit(app, &info);
+            }
+        }
+    }
+    file.flush().map_err(|e| e.to_string())?;
+    drop(file);
+    if total.is_some_and(|total| received < total) {
+        return Err("Download ended early".to_string());
+    }
+    downloads.update(id, |info| info.received = received);
+    std::fs::rename(&partial, &info.path).map_err(|e| format!("Failed to save {}: {}", info.path.display(), e))?;
+    Ok(DownloadState::Completed)
+}
+
+fn run(app: &AppHandle, id: String, control: Arc<AtomicU8>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let result = transfer(&app, &id, &control);
+        let downloads = app.state::<Downloads>();
+        let info = downloads.update(&id, |info| match &result {
+            Ok(state) => info.state = *state,
+            Err(e) => {
+                info.state = DownloadState::Failed;
+                info.error = Some(e.clone());
+            }
+        });
+        let Some(info) = info else {
+            return;
+        };
+        match info.state {
+            DownloadState::Completed => log::info!("Downloaded {} to {}", info.url, info.path.display()),
+            DownloadState::Cancelled => {
+                let _ = std::fs::remove_file(partial_path(&info.path));
+                log::info!("Download {} cancelled", id);
+            }
+            DownloadState::Failed => log::warn!("Download {} failed: {}", id, info.error.as_deref().unwrap_or_default()),
+            _ => {}
+        }
+        emit(&app, &info);
+    });
+}
+
+/// Starts saving `request.url` into `directory`, or the download folder from the
+/// settings, or the user's Downloads folder.
+pub fn start(app: &AppHandle, request: DownloadRequest, directory: Option<PathBuf>) -> Result<DownloadInfo, String> {
+    let url = tauri::Url::parse(&request.url).map_err(|e| format!("Invalid download URL {}: {}", request.url, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported download URL: {}", request.url));
+    }
+    let dir = download_dir(app, directory)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let name = sanitize_file_name(&request.file_name.clone().unwrap_or_else(|| file_name_from_url(&url)));
+
+    let downloads = app.state::<Downloads>();
+    let info = {
+        let mut all = downloads.downloads.lock().unwrap();
+        let in_flight: Vec<PathBuf> = all
+            .values()
+            .filter(|d| matches!(d.info.state, DownloadState::Downloading | DownloadState::Paused))
+            .map(|d| d.info.path.clone())
+            .collect();
+        let info = DownloadInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: request.url,
+            path: unique_path(&dir, &name, &in_flight),
+            received: 0,
+            total: None,
+            state: DownloadState::Downloading,
+            error: None,
+            job_id: request.job_id,
+        };
+        let control = Arc::new(AtomicU8::new(RUN));
+        all.insert(info.id.clone(), Download { info: info.clone(), control: control.clone() });
+        run(app, info.id.clone(), control);
+        info
+    };
+    log::info!("Downloading {} to {}", info.url, info.path.display());
+    emit(app, &info);
+    Ok(info)
+}
+
+/// Asks a running download to stop with `signal`. It settles once its thread notices.
+fn signal(downloads: &Downloads, id: &str, signal: u8) -> Result<(), String> {
+    let all = downloads.downloads.lock().unwrap();
+    let download = all.get(id).ok_or_else(|| format!("No download '{}'", id))?;
+    download.control.store(signal, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Downloads a file produced by an automation or linked from a page.
+#[tauri::command]
+pub fn start_download(
+    app: AppHandle,
+    url: String,
+    file_name: Option<String>,
+    directory: Option<PathBuf>,
+) -> Result<DownloadInfo, String> {
+    start(&app, DownloadRequest { url, file_name, job_id: None }, directory)
+}
+
+#[tauri::command]
+pub fn list_downloads(downloads: State<'_, Downloads>) -> Vec<DownloadInfo> {
+    let mut list: Vec<DownloadInfo> = downloads.downloads.lock().unwrap().values().map(|d| d.info.clone()).collect();
+    list.sort_by(|a, b| a.path.cmp(&b.path));
+    list
+}
+
+#[tauri::command]
+pub fn pause_download(downloads: State<'_, Downloads>, id: String) -> Result<(), String> {
+    if downloads.info(&id)?.state != DownloadState::Downloading {
+        return Err("Only a running download can be paused".to_string());
+    }
+    signal(&downloads, &id, PAUSE)
+}
+
+/// Continues a paused or failed download from where it stopped, if the server supports
+/// range requests, otherwise from the start.
+#[tauri::command]
+pub fn resume_download(app: AppHandle, downloads: State<'_, Downloads>, id: String) -> Result<(), String> {
+    let control = {
+        let mut all = downloads.downloads.lock().unwrap();
+        let download = all.get_mut(&id).ok_or_else(|| format!("No download '{}'", id))?;
+        if !matches!(download.info.state, DownloadState::Paused | DownloadState::Failed) {
+            return Err("Only a paused or failed download can be resumed".to_string());
+        }
+        download.info.state = DownloadState::Downloading;
+        download.info.error = None;
+        // A fresh flag, so a pause still pending for the old thread cannot stop this one
+        download.control = Arc::new(AtomicU8::new(RUN));
+        download.control.clone()
+    };
+    run(&app, id, control);
+    Ok(())
+}
+
+/// Stops a download and deletes what was received.
+#[tauri::command]
+pub fn cancel_download(app: AppHandle, downloads: State<'_, Downloads>, id: String) -> Result<(), String> {
+    let info = downloads.info(&id)?;
+    match info.state {
+        DownloadState::Downloading => signal(&downloads, &id, CANCEL),
+        DownloadState::Paused | DownloadState::Failed => {
+            let _ = std::fs::remove_file(partial_path(&info.path));
+            if let Some(info) = downloads.update(&id, |info| info.state = DownloadState::Cancelled) {
+                emit(&app, &info);
+            }
+            Ok(())
+        }
+        _ => Err("The download has already ended".to_string()),
+    }
+}
+
+/// Shows a completed download in the file manager, selected.
+#[tauri::command]
+pub fn open_containing_folder(app: AppHandle, downloads: State<'_, Downloads>, id: String) -> Result<(), String> {
+    let info = downloads.info(&id)?;
+    let path = if info.state == DownloadState::Completed { info.path } else { partial_path(&info.path) };
+    app.opener()
+        .reveal_item_in_dir(&path)
+        .map_err(|e| format!("Failed to open folder: {}", e))
+}
@@ -0,0 +1,236 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::events::JobEvent;
+use crate::history::{self, History, RunRecord};
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::secrets;
+use crate::settings::SettingsStore;
+
+/// Keychain entry holding the SMTP password.
+pub const PASSWORD_KEY: &str = "smtp-password";
+/// Artifacts beyond this total size are listed in the report instead of attached.
+const MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (usually port 587).
+    StartTls,
+    /// TLS from the start (usually port 465).
+    Tls,
+    /// Unencrypted, for local relays only.
+    None,
+}
+
+/// SMTP server used for run reports. The password is kept in the keychain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailSettings {
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub security: SmtpSecurity,
+    /// Login name; leave empty for servers that don't require authentication.
+    pub username: String,
+    pub from: String,
+    /// Used when a report is sent without explicit recipients.
+    pub default_recipients: Vec<String>,
+}
+
+impl Default for EmailSettings {
+    fn default() -> Self {
+        EmailSettings {
+            smtp_host: String::new(),
+            smtp_port: 587,
+            security: SmtpSecurity::StartTls,
+            username: String::new(),
+            from: String::new(),
+            default_recipients: Vec::new(),
+        }
+    }
+}
+
+fn content_type_for(path: &Path) -> ContentType {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let mime = match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "txt" | "log" => "text/plain",
+        _ => "application/octet-stream",
+    };
+    ContentType::parse(mime).unwrap_or(ContentType::TEXT_PLAIN)
+}
+
+fn summary(run: &RunRecord) -> String {
+    let status = match run.status.as_str() {
+        "success" => "Succeeded",
+        "error" => "Failed",
+        _ => "Still running",
+    };
+    let mut body = format!("Automation run report\n\nTask: {}\nStatus: {}\nRun ID: {}\n", run.prompt, status, run.id);
+    if let Some(duration_ms) = run.duration_ms {
+        body.push_str(&format!("Duration: {:.1}s\n", duration_ms as f64 / 1000.0));
+    }
+    if let Some(message) = run.message.as_deref().filter(|m| !m.is_empty()) {
+        body.push_str(&format!("\n{}\n", message));
+    }
+    body
+}
+
+fn build_message(settings: &EmailSettings, run: &RunRecord, artifacts: &[std::path::PathBuf], recipients: &[String]) -> Result<Message, String> {
+    let from: Mailbox = settings.from.parse().map_err(|e| format!("Invalid sender address '{}': {}", settings.from, e))?;
+    let mut builder = Message::builder().from(from).subject(format!("Automation report: {}", run.prompt));
+    for recipient in recipients {
+        let mailbox: Mailbox = recipient.parse().map_err(|e| format!("Invalid recipient '{}': {}", recipient, e))?;
+        builder = builder.to(mailbox);
+    }
+
+    let mut body = summary(run);
+    let mut parts = Vec::new();
+    let mut total = 0;
+    for path in artifacts {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if total + size > MAX_ATTACHMENT_BYTES {
+            body.push_str(&format!("\nNot attached (too large): {}", name));
+            continue;
+        }
+        match std::fs::read(path) {
+            Ok(bytes) => {
+                total += size;
+                parts.push(Attachment::new(name).body(bytes, content_type_for(path)));
+            }
+            Err(e) => log::warn!("Skipping artifact {}: {}", path.display(), e),
+        }
+    }
+
+    let mut multipart = MultiPart::mixed().singlepart(SinglePart::plain(body));
+    for part in parts {
+        multipart = multipart.singlepart(part);
+    }
+    builder.multipart(multipart).map_err(|e| format!("Failed to build report email: {}", e))
+}
+
+fn transport(settings: &EmailSettings) -> Result<SmtpTransport, String> {
+    if settings.smtp_host.is_empty() {
+        return Err("No SMTP server configured".to_string());
+    }
+    let builder = match settings.security {
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&settings.smtp_host),
+        SmtpSecurity::Tls => SmtpTransport::relay(&settings.smtp_host),
+        SmtpSecurity::None => Ok(SmtpTransport::builder_dangerous(&settings.smtp_host)),
+    }
+    .map_err(|e| format!("Invalid SMTP server '{}': {}", settings.smtp_host, e))?;
+
+    let mut builder = builder.port(settings.smtp_port).timeout(Some(Duration::from_secs(30)));
+    if !settings.username.is_empty() {
+        let password = secrets::get(PASSWORD_KEY)?.unwrap_or_default();
+        builder = builder.credentials(Credentials::new(settings.username.clone(), password));
+    }
+    Ok(builder.build())
+}
+
+/// `recipients`, or the configured default recipients when there are none.
+fn resolve_recipients<'a>(settings: &'a EmailSettings, recipients: &'a [String]) -> Result<&'a [String], String> {
+    let recipients = if recipients.is_empty() { &settings.default_recipients[..] } else { recipients };
+    if recipients.is_empty() {
+        return Err("No recipients given and no default recipients configured".to_string());
+    }
+    Ok(recipients)
+}
+
+/// Emails a run's summary with its artifacts attached. Empty `recipients` falls back
+/// to the configured default recipients.
+pub fn send(app: &AppHandle, run_id: &str, recipients: &[String]) -> Result<(), String> {
+    let settings = app.state::<SettingsStore>().get().email;
+    let recipients = resolve_recipients(&settings, recipients)?;
+
+    let run = app
+        .state::<History>()
+        .get(run_id)?
+        .ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let message = build_message(&settings, &run, &history::artifacts(app, run_id), recipients)?;
+
+    transport(&settings)?
+        .send(&message)
+        .map_err(|e| format!("Failed to send report: {}", e))?;
+    log::info!("Report for run {} sent to {} recipient(s)", run_id, recipients.len());
+    Ok(())
+}
+
+/// Emails a plain-text message, e.g. the daily digest. Empty `recipients` falls back to
+/// the configured default recipients.
+pub fn send_text(app: &AppHandle, subject: &str, body: &str, recipients: &[String]) -> Result<(), String> {
+    let settings = app.state::<SettingsStore>().get().email;
+    let recipients = resolve_recipients(&settings, recipients)?;
+    let from: Mailbox = settings.from.parse().map_err(|e| format!("Invalid sender address '{}': {}", settings.from, e))?;
+    let mut builder = Message::builder().from(from).subject(subject);
+    for recipient in recipients {
+        let mailbox: Mailbox = recipient.parse().map_err(|e| format!("Invalid recipient '{}': {}", recipient, e))?;
+        builder = builder.to(mailbox);
+    }
+    let message = builder
+        .singlepart(SinglePart::plain(body.to_string()))
+        .map_err(|e| format!("Failed to build email: {}", e))?;
+    transport(&settings)?
+        .send(&message)
+        .map_err(|e| format!("Failed to send email: {}", e))?;
+    log::info!("'{}' sent to {} recipient(s)", subject, recipients.len());
+    Ok(())
+}
+
+/// Built-in plugin that emails a report after runs of schedules that opted in.
+struct EmailReportPlugin;
+
+impl ShellPlugin for EmailReportPlugin {
+    fn name(&self) -> &str {
+        "email-reports"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        let Some(schedule_id) = job.schedule_id.as_deref() else {
+            return;
+        };
+        let settings = ctx.app.state::<SettingsStore>().get();
+        let Some(schedule) = settings.schedules.iter().find(|s| s.id == schedule_id && s.email_report) else {
+            return;
+        };
+        if let Err(e) = send(ctx.app, &job.job_id, &schedule.report_recipients) {
+            log::error!("Report for schedule '{}' not sent: {}", schedule.name, e);
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(EmailReportPlugin), "Emails run reports for schedules that opt in");
+}
+
+/// Stores the SMTP password in the keychain. An empty password removes it.
+#[tauri::command]
+pub fn set_smtp_password(password: String) -> Result<(), String> {
+    if password.is_empty() {
+        secrets::delete(PASSWORD_KEY)
+    } else {
+        secrets::set(PASSWORD_KEY, &password)
+    }
+}
+
+#[tauri::command]
+pub async fn send_report(app: AppHandle, run_id: String, recipients: Vec<String>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || send(&app, &run_id, &recipients))
+        .await
+        .map_err(|e| format!("Sending report failed: {}", e))?
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::history::History;
+use crate::{blobs, history, notifications, plugins, power, reports, search, taskbar, thumbnails, transforms};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobEventKind {
+    Started,
+    Finished,
+    /// A job run step by step stopped before a step; see `debugger`.
+    Paused,
+    Resumed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Success,
+    Error,
+}
+
+/// A job lifecycle event reported by the server UI. Re-emitted to all windows as
+/// `job-event` and fanned out to shell subsystems.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobEvent {
+    pub job_id: String,
+    pub kind: JobEventKind,
+    pub prompt: String,
+    #[serde(default)]
+    pub status: Option<JobStatus>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// Set when the job was started by a schedule.
+    #[serde(default)]
+    pub schedule_id: Option<String>,
+    /// Set when the job runs a recipe from the store.
+    #[serde(default)]
+    pub recipe_id: Option<String>,
+    /// Structured result of a finished job (whatever the automation extracted).
+    #[serde(default)]
+    pub output: Option<serde_json::Value>,
+    /// The step a job paused or resumed at, from 1, when it runs step by step.
+    #[serde(default)]
+    pub step: Option<u32>,
+    #[serde(default)]
+    pub steps: Option<u32>,
+}
+
+/// How many recent events are remembered for de-duplication.
+const RECENT_EVENTS: usize = 64;
+
+/// Jobs can be reported twice, e.g. by the web UI bridge and by the server over the
+/// control channel. The first report of each (job, kind) wins. A job may pause and
+/// resume many times, so only starts and finishes are de-duplicated.
+static RECENT: Mutex<VecDeque<(String, JobEventKind)>> = Mutex::new(VecDeque::new());
+
+/// Jobs that have started but not finished.
+static RUNNING: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Updates the running set with `event` and returns how many jobs are running.
+fn track_running(event: &JobEvent) -> usize {
+    let mut running = RUNNING.lock().unwrap();
+    match event.kind {
+        JobEventKind::Started => running.insert(event.job_id.clone()),
+        JobEventKind::Finished => running.remove(&event.job_id),
+        JobEventKind::Paused | JobEventKind::Resumed => false,
+    };
+    running.len()
+}
+
+pub fn running_count() -> usize {
+    RUNNING.lock().unwrap().len()
+}
+
+fn is_duplicate(event: &JobEvent) -> bool {
+    if !matches!(event.kind, JobEventKind::Started | JobEventKind::Finished) {
+        return false;
+    }
+    let key = (event.job_id.clone(), event.kind);
+    let mut recent = RECENT.lock().unwrap();
+    if recent.contains(&key) {
+        return true;
+    }
+    if recent.len() == RECENT_EVENTS {
+        recent.pop_front();
+    }
+    recent.push_back(key);
+    false
+}
+
+/// The server reports a finish without the recipe or schedule the shell started the job
+/// for, so those are taken from the run's start. The output is then shaped as the recipe
+/// says; only the first finish of a job gets here, so that happens once.
+fn complete_finished(app: &AppHandle, event: &mut JobEvent) {
+    if event.recipe_id.is_none() || event.schedule_id.is_none() {
+        if let Ok(Some(run)) = app.state::<History>().get(&event.job_id) {
+            event.recipe_id = event.recipe_id.take().or(run.recipe_id);
+            event.schedule_id = event.schedule_id.take().or(run.schedule_id);
+        }
+    }
+    if let Some(output) = event.output.take() {
+        event.output = Some(transforms::recipe_output(app, &event.job_id, event.recipe_id.as_deref(), output));
+    }
+}
+
+pub fn publish(app: &AppHandle, mut event: JobEvent) {
+    if is_duplicate(&event) {
+        return;
+    }
+    if event.kind == JobEventKind::Finished {
+        complete_finished(app, &mut event);
+    }
+    log::info!("Job {} {:?} ({:?})", event.job_id, event.kind, event.status);
+    if let Err(e) = app.emit("job-event", &event) {
+        log::warn!("Failed to emit job event: {}", e);
+    }
+    history::record(app, &event);
+    let running = track_running(&event);
+    taskbar::jobs_changed(app, running);
+    power::jobs_changed(app, running);
+
+    if event.kind == JobEventKind::Finished {
+        plugins::job_finished(app, &event);
+        notifications::job_finished(app, &event);
+        blobs::run_finished(app, &event.job_id);
+        search::run_finished(app, &event.job_id);
+        thumbnails::run_finished(app, &event.job_id);
+        reports::run_finished(app, &event);
+    }
+}
+
+/// Entry point of the event bridge: the server's web UI reports job progress here.
+#[tauri::command]
+pub fn report_job_event(app: AppHandle, event: JobEvent) {
+    publish(&app, event);
+}
@@ -0,0 +1,204 @@
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::history::History;
+
+/// Emit a progress event every this many rows.
+const PROGRESS_EVERY: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Sent as `export-progress` while an export is written.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportProgress {
+    pub run_id: String,
+    pub rows_written: usize,
+    pub total_rows: usize,
+    pub done: bool,
+}
+
+/// A run's output flattened into a table.
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Flattens structured output: an array of objects becomes one row per object with the
+/// union of their keys as columns; a single object becomes one row; anything else ends
+/// up in a single `value` column. Nested values are kept as JSON text.
+pub fn to_table(output: &Value) -> Table {
+    let items: Vec<&Value> = match output {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+
+    if !items.iter().all(|item| item.is_object()) {
+        return Table {
+            columns: vec!["value".to_string()],
+            rows: items.into_iter().map(|item| vec![item.clone()]).collect(),
+        };
+    }
+
+    let mut columns: Vec<String> = Vec::new();
+    for item in &items {
+        for key in item.as_object().into_iter().flat_map(|object| object.keys()) {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    let rows = items
+        .iter()
+        .map(|item| columns.iter().map(|column| item.get(column).cloned().unwrap_or(Value::Null)).collect())
+        .collect();
+    Table { columns, rows }
+}
+
+pub fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+struct Progress<'a> {
+    app: &'a AppHandle,
+    run_id: &'a str,
+    total_rows: usize,
+}
+
+impl Progress<'_> {
+    fn report(&self, rows_written: usize) {
+        if rows_written % PROGRESS_EVERY == 0 {
+            self.emit(rows_written, false);
+        }
+    }
+
+    fn finish(&self) {
+        self.emit(self.total_rows, true);
+    }
+
+    fn emit(&self, rows_written: usize, done: bool) {
+        let progress = ExportProgress {
+            run_id: self.run_id.to_string(),
+            rows_written,
+            total_rows: self.total_rows,
+            done,
+        };
+        if let Err(e) = self.app.emit("export-progress", progress) {
+            log::warn!("Failed to emit export progress: {}", e);
+        }
+    }
+}
+
+fn write_csv(path: &Path, table: &Table, progress: &Progress) -> Result<(), String> {
+    let mut writer = csv::Writer::from_path(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    writer.write_record(&table.columns).map_err(|e| e.to_string())?;
+    for (index, row) in table.rows.iter().enumerate() {
+        writer.write_record(row.iter().map(cell_text)).map_err(|e| e.to_string())?;
+        progress.report(index + 1);
+    }
+    writer.flush().map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn write_xlsx(path: &Path, table: &Table, progress: &Progress) -> Result<(), String> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    let header = Format::new().set_bold();
+    for (col, name) in table.columns.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, name, &header).map_err(|e| e.to_string())?;
+    }
+    for (index, row) in table.rows.iter().enumerate() {
+        let row_num = index as u32 + 1;
+        for (col, value) in row.iter().enumerate() {
+            let col = col as u16;
+            let result = match value {
+                Value::Null => continue,
+                Value::Bool(b) => sheet.write_boolean(row_num, col, *b),
+                Value::Number(n) => match n.as_f64() {
+                    Some(n) => sheet.write_number(row_num, col, n),
+                    None => sheet.write_string(row_num, col, n.to_string()),
+                },
+                other => sheet.write_string(row_num, col, cell_text(other)),
+            };
+            result.map_err(|e| e.to_string())?;
+        }
+        progress.report(index + 1);
+    }
+    workbook.save(path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn write_json(path: &Path, output: &Value) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(output).map_err(|e| e.to_string())?;
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Asks where to save the export. `None` means the user cancelled.
+fn ask_path(app: &AppHandle, run_id: &str, format: ExportFormat) -> Option<PathBuf> {
+    let extension = format.extension();
+    app.dialog()
+        .file()
+        .set_title("Export Results")
+        .set_file_name(format!("run-{}.{}", run_id, extension))
+        .add_filter(extension.to_uppercase(), &[extension])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+}
+
+fn export(app: &AppHandle, run_id: &str, format: ExportFormat, path: Option<PathBuf>) -> Result<Option<PathBuf>, String> {
+    let run = app
+        .state::<History>()
+        .get(run_id)?
+        .ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let output = run.output.ok_or_else(|| format!("Run '{}' has no results to export", run_id))?;
+
+    let Some(path) = path.or_else(|| ask_path(app, run_id, format)) else {
+        return Ok(None);
+    };
+
+    log::info!("Exporting run {} as {:?} to {}", run_id, format, path.display());
+    let table = to_table(&output);
+    let progress = Progress { app, run_id, total_rows: table.rows.len() };
+    match format {
+        ExportFormat::Csv => write_csv(&path, &table, &progress)?,
+        ExportFormat::Xlsx => write_xlsx(&path, &table, &progress)?,
+        ExportFormat::Json => write_json(&path, &output)?,
+    }
+    progress.finish();
+    Ok(Some(path))
+}
+
+/// Exports a run's results. Without a `path` a save dialog is shown; returns the path
+/// written, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_run(
+    app: AppHandle,
+    run_id: String,
+    format: ExportFormat,
+    path: Option<PathBuf>,
+) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || export(&app, &run_id, format, path))
+        .await
+        .map_err(|e| format!("Export failed: {}", e))?
+}
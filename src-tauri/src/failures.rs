@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::artifact_protocol;
+use crate::history::{self, FailureCapture, History, RunRecord};
+
+/// Where a run's failure capture is kept, relative to its artifacts dir.
+const SCREENSHOT_PATH: &str = "failure/screenshot.png";
+const DOM_PATH: &str = "failure/dom.html";
+
+/// Sent by the server as `failure.captured` when a step of a job fails.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FailureCaptured {
+    pub job_id: String,
+    #[serde(default)]
+    pub step: Option<u32>,
+    pub error: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub screenshot: Option<PathBuf>,
+    #[serde(default)]
+    pub dom: Option<PathBuf>,
+}
+
+/// What the error view shows for a failed run.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureContext {
+    pub run: RunRecord,
+    /// `None` when the run didn't fail, or failed before a capture was taken (or with
+    /// captures turned off).
+    pub capture: Option<FailureCapture>,
+    /// Loadable by the webviews; see `artifact_protocol`.
+    pub screenshot_url: Option<String>,
+    pub dom_url: Option<String>,
+}
+
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    // The server's temp dir may be on another volume
+    std::fs::rename(from, to).or_else(|_| std::fs::copy(from, to).and_then(|_| std::fs::remove_file(from)))
+}
+
+/// Moves a captured file into the run's artifacts as `relative`; `None` if there was
+/// none or it couldn't be moved.
+fn keep(artifacts: &Path, from: Option<&Path>, relative: &str) -> Option<String> {
+    let from = from.filter(|path| path.is_file())?;
+    let target = artifacts.join(relative);
+    let moved = target
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| move_file(from, &target));
+    match moved {
+        Ok(()) => Some(relative.to_string()),
+        Err(e) => {
+            log::warn!("Failed to keep {}: {}", from.display(), e);
+            None
+        }
+    }
+}
+
+fn accept(app: &AppHandle, captured: &FailureCaptured) -> Result<FailureCapture, String> {
+    let artifacts = history::artifacts_dir(app, &captured.job_id)
+        .ok_or_else(|| format!("Invalid run id '{}'", captured.job_id))?;
+    let capture = FailureCapture {
+        run_id: captured.job_id.clone(),
+        step: captured.step,
+        error: captured.error.clone(),
+        url: captured.url.clone(),
+        screenshot: keep(&artifacts, captured.screenshot.as_deref(), SCREENSHOT_PATH),
+        dom: keep(&artifacts, captured.dom.as_deref(), DOM_PATH),
+        captured_at: history::now_ms(),
+    };
+    // The server's temp dir holding the files
+    if let Some(dir) = captured.screenshot.as_deref().or(captured.dom.as_deref()).and_then(Path::parent) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    app.state::<History>().add_failure_capture(&capture)?;
+    Ok(capture)
+}
+
+/// Takes in a failure capture the server reported, in the background, and emits
+/// `failure-captured` once it is indexed.
+pub fn failure_captured(app: &AppHandle, captured: FailureCaptured) {
+    let app = app.clone();
+    std::thread::spawn(move || match accept(&app, &captured) {
+        Ok(capture) => {
+            log::info!("Failure of run {} captured", capture.run_id);
+            let _ = app.emit("failure-captured", &capture);
+        }
+        Err(e) => log::warn!("Failed to keep the failure capture of run {}: {}", captured.job_id, e),
+    });
+}
+
+/// The run, the error it failed with and the screenshot and DOM snapshot taken then.
+#[tauri::command]
+pub fn get_failure_context(app: AppHandle, run_id: String) -> Result<FailureContext, String> {
+    let history = app.state::<History>();
+    let run = history.get(&run_id)?.ok_or_else(|| format!("Run '{}' does not exist", run_id))?;
+    let capture = history.failure_capture(&run_id)?;
+    let artifacts = history::artifacts_dir(&app, &run_id);
+    // Files purged by retention since are left out
+    let url = |path: Option<&String>| {
+        let path = path?;
+        artifacts.as_ref().filter(|dir| dir.join(path).is_file())?;
+        Some(artifact_protocol::url(&run_id, path))
+    };
+    Ok(FailureContext {
+        screenshot_url: capture.as_ref().and_then(|capture| url(capture.screenshot.as_ref())),
+        dom_url: capture.as_ref().and_then(|capture| url(capture.dom.as_ref())),
+        run,
+        capture,
+    })
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::settings::SettingsStore;
+use crate::AppState;
+
+/// Flags this build knows about and their defaults. Remote and local overrides may add
+/// flags that only the server reads.
+const DEFAULTS: &[(&str, bool)] = &[
+    // Manifest plugins loaded from the plugins dir
+    ("external_plugins", true),
+    // Extra workspace windows, each with its own server
+    ("workspaces", true),
+];
+
+/// JSON object of flag overrides fetched periodically. Builds without one use defaults
+/// and local overrides only.
+const REMOTE_URL: Option<&str> = option_env!("NOVA_FLAGS_URL");
+const FETCH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub type Flags = BTreeMap<String, bool>;
+
+/// Effective flags: baked-in defaults, overridden by the last remote fetch (cached in
+/// `flags.json` in the app data dir so it applies offline), overridden by the
+/// `feature_flags` setting.
+pub struct FeatureFlags {
+    remote: Mutex<Flags>,
+    effective: Mutex<Flags>,
+    cache: Option<PathBuf>,
+}
+
+impl FeatureFlags {
+    pub fn load(app: &AppHandle) -> Self {
+        let cache = app.path().app_data_dir().ok().map(|dir| dir.join("flags.json"));
+        let remote = cache
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        FeatureFlags { remote: Mutex::new(remote), effective: Mutex::new(Flags::new()), cache }
+    }
+
+    fn set_remote(&self, remote: Flags) {
+        if let Some(path) = &self.cache {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            let result = serde_json::to_string_pretty(&remote)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| std::fs::write(path, contents).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                log::warn!("Failed to cache feature flags: {}", e);
+            }
+        }
+        *self.remote.lock().unwrap() = remote;
+    }
+}
+
+/// Recomputes the effective flags and pushes changes to the frontend and the server.
+pub fn apply(app: &AppHandle) {
+    let state = app.state::<FeatureFlags>();
+    let mut flags: Flags = DEFAULTS.iter().map(|(name, on)| (name.to_string(), *on)).collect();
+    flags.extend(state.remote.lock().unwrap().clone());
+    flags.extend(app.state::<SettingsStore>().get().feature_flags);
+
+    let mut effective = state.effective.lock().unwrap();
+    if *effective == flags {
+        return;
+    }
+    *effective = flags.clone();
+    drop(effective);
+    log::info!("Feature flags: {:?}", flags);
+    if let Err(e) = app.state::<AppState>().control.push_config(serde_json::json!({ "feature_flags": flags })) {
+        log::debug!("Feature flags not pushed to the server: {}", e);
+    }
+    let _ = app.emit("feature-flags-changed", flags);
+}
+
+fn fetch_remote(url: &str) -> Result<Flags, String> {
+    ureq::get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| format!("invalid flags: {}", e))
+}
+
+/// Computes the flags and, when the build has a remote source, keeps them up to date.
+pub fn setup(app: &AppHandle) {
+    apply(app);
+    let Some(url) = REMOTE_URL else {
+        return;
+    };
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        match fetch_remote(url) {
+            Ok(remote) => {
+                app.state::<FeatureFlags>().set_remote(remote);
+                apply(&app);
+            }
+            Err(e) => log::warn!("Failed to fetch feature flags: {}", e),
+        }
+        std::thread::sleep(FETCH_INTERVAL);
+    });
+}
+
+pub fn enabled(app: &AppHandle, flag: &str) -> bool {
+    app.state::<FeatureFlags>().effective.lock().unwrap().get(flag).copied().unwrap_or(false)
+}
+
+/// Flags for a server about to be spawned, as JSON for its environment.
+pub fn server_env(app: &AppHandle) -> String {
+    serde_json::to_string(&*app.state::<FeatureFlags>().effective.lock().unwrap()).unwrap_or_default()
+}
+
+/// Whether a feature flag is on. Unknown flags are off.
+#[tauri::command]
+pub fn is_enabled(flags: State<'_, FeatureFlags>, flag: String) -> bool {
+    flags.effective.lock().unwrap().get(&flag).copied().unwrap_or(false)
+}
+
+#[tauri::command]
+pub fn get_feature_flags(flags: State<'_, FeatureFlags>) -> Flags {
+    flags.effective.lock().unwrap().clone()
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+use tauri::AppHandle;
+
+#[cfg(target_os = "macos")]
+use std::process::Command;
+#[cfg(target_os = "macos")]
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+#[cfg(target_os = "macos")]
+use tauri_plugin_opener::OpenerExt;
+
+#[cfg(target_os = "macos")]
+const QUARANTINE_ATTR: &str = "com.apple.quarantine";
+#[cfg(target_os = "macos")]
+const SECURITY_SETTINGS_URL: &str = "x-apple.systempreferences:com.apple.preference.security?General";
+
+#[cfg(target_os = "macos")]
+fn is_quarantined(path: &Path) -> bool {
+    Command::new("/usr/bin/xattr")
+        .args(["-p", QUARANTINE_ATTR])
+        .arg(path)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Checks the server binary's code signature before it is executed. A broken signature
+/// (modified files) is refused; an unsigned binary (local build) runs with a warning. An
+/// intact copy the app `extracted` itself has the quarantine flag it inherited from the
+/// download cleared, so Gatekeeper does not stop it on first launch.
+#[cfg(target_os = "macos")]
+pub fn prepare_binary(binary: &Path, extracted: bool) -> Result<(), String> {
+    let output = Command::new("/usr/bin/codesign")
+        .args(["--verify", "--strict"])
+        .arg(binary)
+        .output()
+        .map_err(|e| format!("Failed to run codesign: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.contains("not signed at all") {
+            return Err(format!("Server binary failed code signature verification: {}", stderr.trim()));
+        }
+        log::warn!("Server binary is not code signed; leaving quarantine to Gatekeeper");
+        return Ok(());
+    }
+    log::info!("Server binary code signature verified");
+
+    if extracted && is_quarantined(binary) {
+        let dir = binary.parent().unwrap_or(binary);
+        log::info!("Clearing quarantine from extracted server bundle {:?}", dir);
+        let status = Command::new("/usr/bin/xattr").args(["-dr", QUARANTINE_ATTR]).arg(dir).status();
+        if !status.is_ok_and(|status| status.success()) {
+            log::warn!("Failed to clear quarantine from {:?}", dir);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn prepare_binary(_binary: &Path, _extracted: bool) -> Result<(), String> {
+    Ok(())
+}
+
+/// If a failed spawn was Gatekeeper refusing the binary, tells the user how to allow it
+/// instead of leaving them with a generic error. Returns whether it was.
+#[cfg(target_os = "macos")]
+pub fn explain_spawn_error(app: &AppHandle, binary: &Path, error: &std::io::Error) -> bool {
+    if error.kind() != std::io::ErrorKind::PermissionDenied {
+        return false;
+    }
+    let rejected = Command::new("/usr/sbin/spctl")
+        .args(["--assess", "--type", "execute"])
+        .arg(binary)
+        .output()
+        .is_ok_and(|output| !output.status.success());
+    if !rejected && !is_quarantined(binary) {
+        return false;
+    }
+    log::error!("Gatekeeper blocked the server binary {:?}", binary);
+
+    let handle = app.clone();
+    app.dialog()
+        .message(
+            "macOS blocked the automation server from running.\n\n\
+             Open Privacy & Security in System Settings, choose \"Open Anyway\" for the \
+             server, then restart the app. If macOS says the app is damaged, download and \
+             reinstall it.",
+        )
+        .title("Server blocked by macOS")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Open Privacy & Security".to_string(),
+            "Close".to_string(),
+        ))
+        .show(move |open| {
+            if open {
+                if let Err(e) = handle.opener().open_url(SECURITY_SETTINGS_URL, None::<&str>) {
+                    log::error!("Failed to open security settings: {}", e);
+                }
+            }
+        });
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn explain_spawn_error(_app: &AppHandle, _binary: &Path, _error: &std::io::Error) -> bool {
+    false
+}
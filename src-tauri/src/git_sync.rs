@@ -0,0 +1,301 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::settings::{self, SettingsStore};
+use crate::{history, secrets};
+
+pub const TOKEN_KEY: &str = "sync-token";
+
+/// Settings shared through the repository. Machine-specific ones (folders, displays,
+/// the control API) stay local, as do sections marked sensitive.
+const SHARED_SECTIONS: &[&str] = &["schedules", "webhooks", "retention", "idle_threshold_minutes", "notify_jobs"];
+
+/// File in the repository holding the shared settings.
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Sync of the recipes folder with a git remote, so a team can share automations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    /// `https://` or ssh remote. HTTPS remotes use the token stored with
+    /// `set_sync_token`; ssh uses the user's own keys.
+    pub remote_url: String,
+    pub branch: String,
+    /// User name sent with the token, and the author of sync commits.
+    pub username: String,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        SyncSettings { enabled: false, remote_url: String::new(), branch: "main".to_string(), username: String::new() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    Disabled,
+    Idle,
+    Syncing,
+    /// Local and remote changes touch the same files; nothing was merged.
+    Conflict,
+    Error,
+}
+
+/// Sent as `sync-status-changed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncStatus {
+    pub state: SyncState,
+    /// Unix milliseconds of the last successful sync.
+    pub last_synced_at: Option<i64>,
+    pub message: Option<String>,
+    /// Files changed on both sides, when in conflict.
+    pub conflicts: Vec<String>,
+}
+
+static STATUS: Mutex<SyncStatus> =
+    Mutex::new(SyncStatus { state: SyncState::Disabled, last_synced_at: None, message: None, conflicts: Vec::new() });
+
+/// Held while syncing; a change made meanwhile sets `PENDING` for another round.
+static RUNNING: Mutex<()> = Mutex::new(());
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// How to settle a conflict reported by the previous sync.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    KeepLocal,
+    KeepRemote,
+}
+
+fn set_status(app: &AppHandle, update: impl FnOnce(&mut SyncStatus)) {
+    let status = {
+        let mut status = STATUS.lock().unwrap();
+        update(&mut status);
+        status.clone()
+    };
+    let _ = app.emit("sync-status-changed", status);
+}
+
+fn repo_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("recipes"))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+struct Git<'a> {
+    dir: &'a Path,
+    config: &'a SyncSettings,
+    token: Option<String>,
+}
+
+impl Git<'_> {
+    fn run(&self, args: &[&str]) -> Result<Output, String> {
+        let mut cmd = Command::new("git");
+        cmd.current_dir(self.dir).args(args).env("GIT_TERMINAL_PROMPT", "0");
+        let author = if self.config.username.is_empty() { "Browser Automation" } else { &self.config.username };
+        let mut config = vec![("user.name".to_string(), author.to_string())];
+        config.push(("user.email".to_string(), "sync@browserautomation.local".to_string()));
+        if let Some(token) = &self.token {
+            // Passed through the environment so the token never shows up in the process list
+            let credentials = BASE64.encode(format!("{}:{}", self.config.username, token));
+            config.push(("http.extraHeader".to_string(), format!("Authorization: Basic {}", credentials)));
+        }
+        cmd.env("GIT_CONFIG_COUNT", config.len().to_string());
+        for (index, (key, value)) in config.iter().enumerate() {
+            cmd.env(format!("GIT_CONFIG_KEY_{}", index), key).env(format!("GIT_CONFIG_VALUE_{}", index), value);
+        }
+        cmd.output().map_err(|e| format!("Failed to run git (is it installed?): {}", e))
+    }
+
+    /// Runs git and fails with its error output when it exits unsuccessfully.
+    fn check(&self, args: &[&str]) -> Result<String, String> {
+        let output = self.run(args)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("git {} failed: {}", args.first().unwrap_or(&""), stderr.trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// The shared settings sections, as stored in the repository.
+fn shared_settings(app: &AppHandle) -> Result<serde_json::Map<String, Value>, String> {
+    let (mut plain, _) = app.state::<SettingsStore>().get().split_sensitive()?;
+    plain.retain(|key, _| SHARED_SECTIONS.contains(&key.as_str()));
+    Ok(plain)
+}
+
+/// Applies shared settings that came from the remote on top of the local ones.
+fn import_settings(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return Ok(());
+    };
+    let Value::Object(shared) = serde_json::from_str(&contents).map_err(|e| format!("Invalid synced settings: {}", e))?
+    else {
+        return Err("Synced settings are not a JSON object".to_string());
+    };
+    if shared == shared_settings(app)? {
+        return Ok(());
+    }
+    let Value::Object(mut current) = serde_json::to_value(app.state::<SettingsStore>().get()).map_err(|e| e.to_string())?
+    else {
+        unreachable!("settings serialize to an object");
+    };
+    for (key, value) in shared.into_iter().filter(|(key, _)| SHARED_SECTIONS.contains(&key.as_str())) {
+        current.insert(key, value);
+    }
+    let merged = serde_json::from_value(Value::Object(current)).map_err(|e| format!("Invalid synced settings: {}", e))?;
+    log::info!("Applying settings from sync");
+    settings::apply(app, merged)
+}
+
+/// Commits local changes, merges the remote and pushes. Returns the conflicting files if
+/// the merge could not be done automatically.
+fn sync(app: &AppHandle, resolution: Option<Resolution>) -> Result<Vec<String>, String> {
+    let config = app.state::<SettingsStore>().get().sync;
+    if config.remote_url.is_empty() {
+        return Err("No sync remote is set".to_string());
+    }
+    let dir = repo_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let token = if config.remote_url.starts_with("https://") { secrets::get(TOKEN_KEY)? } else { None };
+    let git = Git { dir: &dir, config: &config, token };
+    let branch = config.branch.as_str();
+
+    if !dir.join(".git").exists() {
+        git.check(&["init", "-q"])?;
+        git.check(&["checkout", "-q", "-b", branch])?;
+        std::fs::write(dir.join(".gitignore"), ".history/\n*.tmp\n").map_err(|e| e.to_string())?;
+    }
+    if git.run(&["remote", "get-url", "origin"])?.status.success() {
+        git.check(&["remote", "set-url", "origin", &config.remote_url])?;
+    } else {
+        git.check(&["remote", "add", "origin", &config.remote_url])?;
+    }
+
+    let shared = serde_json::to_string_pretty(&shared_settings(app)?).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(SETTINGS_FILE), shared).map_err(|e| format!("Failed to write synced settings: {}", e))?;
+    git.check(&["add", "-A"])?;
+    if !git.check(&["status", "--porcelain"])?.trim().is_empty() {
+        git.check(&["commit", "-q", "-m", "Update recipes and settings"])?;
+    }
+
+    git.check(&["fetch", "-q", "origin"])?;
+    let remote = format!("origin/{}", branch);
+    if git.run(&["rev-parse", "--verify", "-q", &remote])?.status.success() {
+        let settings_before = git.run(&["rev-parse", "--verify", "-q", &format!("HEAD:{}", SETTINGS_FILE)])?.stdout;
+        let mut args = vec!["merge", "-q", "--no-edit", "--allow-unrelated-histories"];
+        match resolution {
+            Some(Resolution::KeepLocal) => args.extend(["-X", "ours"]),
+            Some(Resolution::KeepRemote) => args.extend(["-X", "theirs"]),
+            None => {}
+        }
+        args.push(&remote);
+        if !git.run(&args)?.status.success() {
+            let conflicts = git.check(&["diff", "--name-only", "--diff-filter=U"])?;
+            let _ = git.run(&["merge", "--abort"]);
+            let conflicts: Vec<String> = conflicts.lines().map(str::to_string).collect();
+            if conflicts.is_empty() {
+                return Err("Merging the remote changes failed".to_string());
+            }
+            return Ok(conflicts);
+        }
+        if git.run(&["rev-parse", "--verify", "-q", &format!("HEAD:{}", SETTINGS_FILE)])?.stdout != settings_before {
+            import_settings(app, &dir.join(SETTINGS_FILE))?;
+        }
+    }
+    git.check(&["push", "-q", "origin", &format!("HEAD:{}", branch)])?;
+    Ok(Vec::new())
+}
+
+fn run_sync(app: &AppHandle, resolution: Option<Resolution>) -> Result<SyncStatus, String> {
+    let _guard = RUNNING.lock().unwrap();
+    PENDING.store(false, Ordering::Relaxed);
+    set_status(app, |status| status.state = SyncState::Syncing);
+    let result = sync(app, resolution);
+    set_status(app, |status| match &result {
+        Ok(conflicts) if conflicts.is_empty() => {
+            status.state = SyncState::Idle;
+            status.last_synced_at = Some(history::now_ms());
+            status.message = None;
+            status.conflicts.clear();
+        }
+        Ok(conflicts) => {
+            status.state = SyncState::Conflict;
+            status.message = Some("Local and remote changes conflict".to_string());
+            status.conflicts = conflicts.clone();
+        }
+        Err(e) => {
+            status.state = SyncState::Error;
+            status.message = Some(e.clone());
+        }
+    });
+    match &result {
+        Ok(conflicts) if !conflicts.is_empty() => log::warn!("Sync conflict in {}", conflicts.join(", ")),
+        Err(e) => log::warn!("Sync failed: {}", e),
+        _ => {}
+    }
+    Ok(STATUS.lock().unwrap().clone())
+}
+
+/// Syncs in the background after recipes or settings change. Changes made during a sync
+/// are picked up by one more round right after it.
+pub fn changed(app: &AppHandle) {
+    let config = app.state::<SettingsStore>().get().sync;
+    if !config.enabled || STATUS.lock().unwrap().state == SyncState::Conflict {
+        return;
+    }
+    if PENDING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let _ = run_sync(&app, None);
+    });
+}
+
+/// Pulls what teammates changed while the app was closed.
+pub fn setup(app: &AppHandle) {
+    if app.state::<SettingsStore>().get().sync.enabled {
+        set_status(app, |status| status.state = SyncState::Idle);
+        changed(app);
+    }
+}
+
+/// Syncs now. After a conflict, `resolution` says which side wins for the conflicting
+/// changes; without it the conflict is reported again.
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, resolution: Option<Resolution>) -> Result<SyncStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || run_sync(&app, resolution))
+        .await
+        .map_err(|e| format!("Sync failed: {}", e))?
+}
+
+#[tauri::command]
+pub fn get_sync_status(app: AppHandle) -> SyncStatus {
+    let mut status = STATUS.lock().unwrap().clone();
+    if !app.state::<SettingsStore>().get().sync.enabled && status.state != SyncState::Syncing {
+        status.state = SyncState::Disabled;
+    }
+    status
+}
+
+/// Stores the access token for HTTPS remotes in the keychain. An empty token removes it.
+#[tauri::command]
+pub fn set_sync_token(token: String) -> Result<(), String> {
+    if token.is_empty() {
+        secrets::delete(TOKEN_KEY)
+    } else {
+        secrets::set(TOKEN_KEY, &token)
+    }
+}
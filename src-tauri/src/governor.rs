@@ -0,0 +1,124 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::AppState;
+
+/// Rough footprint of one automated Chromium with a few tabs.
+const MB_PER_BROWSER: u64 = 700;
+/// Memory left for the OS and the user's own apps.
+const RESERVED_MB: u64 = 1536;
+
+/// Limit set with `set_job_concurrency`, in place of the recommendation until reset.
+static OVERRIDE: Mutex<Option<usize>> = Mutex::new(None);
+
+/// Sent as `job-concurrency-changed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Concurrency {
+    /// Browsers allowed to run at once; each server runs one.
+    pub limit: usize,
+    /// What this machine can comfortably run right now.
+    pub recommended: usize,
+    /// `limit` was set by the user rather than recommended.
+    pub overridden: bool,
+    pub cpu_cores: usize,
+    pub available_memory_mb: Option<u64>,
+}
+
+#[cfg(target_os = "linux")]
+fn available_memory_mb() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024)
+}
+
+/// Free plus inactive pages, which macOS hands out without swapping.
+#[cfg(target_os = "macos")]
+fn available_memory_mb() -> Option<u64> {
+    let output = std::process::Command::new("/usr/bin/vm_stat").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let page_size: u64 = text.lines().next()?.split("page size of ").nth(1)?.split_whitespace().next()?.parse().ok()?;
+    let pages = |name: &str| -> u64 {
+        text.lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| line.rsplit(':').next())
+            .and_then(|value| value.trim().trim_end_matches('.').parse().ok())
+            .unwrap_or(0)
+    };
+    Some((pages("Pages free") + pages("Pages inactive")) * page_size / (1024 * 1024))
+}
+
+#[cfg(windows)]
+fn available_memory_mb() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+    let mut status = MEMORYSTATUSEX { dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32, ..Default::default() };
+    unsafe { GlobalMemoryStatusEx(&mut status) }.ok()?;
+    Some(status.ullAvailPhys / (1024 * 1024))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn available_memory_mb() -> Option<u64> {
+    None
+}
+
+fn cpu_cores() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// About one browser per two cores, fewer if free memory runs out first. Never below one.
+fn recommend(cpu_cores: usize, available_memory_mb: Option<u64>) -> usize {
+    let by_cpu = (cpu_cores / 2).max(1);
+    let by_memory = available_memory_mb
+        .map(|mb| (mb.saturating_sub(RESERVED_MB) / MB_PER_BROWSER) as usize)
+        .unwrap_or(by_cpu);
+    by_cpu.min(by_memory).max(1)
+}
+
+pub fn concurrency() -> Concurrency {
+    let cpu_cores = cpu_cores();
+    let available_memory_mb = available_memory_mb();
+    let recommended = recommend(cpu_cores, available_memory_mb);
+    let overridden = *OVERRIDE.lock().unwrap();
+    Concurrency {
+        limit: overridden.unwrap_or(recommended),
+        recommended,
+        overridden: overridden.is_some(),
+        cpu_cores,
+        available_memory_mb,
+    }
+}
+
+pub fn limit() -> usize {
+    concurrency().limit
+}
+
+/// The limit a server spawned now should start with, as `NOVA_MAX_CONCURRENCY`.
+pub fn server_env() -> String {
+    limit().to_string()
+}
+
+/// How many browsers may run at once and why. Recomputed on each call, so the
+/// recommendation follows free memory.
+#[tauri::command]
+pub fn get_job_concurrency() -> Concurrency {
+    concurrency()
+}
+
+/// Sets how many browsers may run at once; 0 goes back to the recommendation. Applies
+/// to the running server and to workspaces started from now on.
+#[tauri::command]
+pub fn set_job_concurrency(app: AppHandle, n: usize) -> Result<Concurrency, String> {
+    *OVERRIDE.lock().unwrap() = (n > 0).then_some(n);
+    let concurrency = concurrency();
+    log::info!("Job concurrency limit: {} (recommended {})", concurrency.limit, concurrency.recommended);
+    if let Err(e) = app
+        .state::<AppState>()
+        .control
+        .push_config(serde_json::json!({ "max_concurrency": concurrency.limit }))
+    {
+        log::debug!("Concurrency limit not pushed to the server: {}", e);
+    }
+    let _ = app.emit("job-concurrency-changed", &concurrency);
+    Ok(concurrency)
+}
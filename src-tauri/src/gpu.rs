@@ -0,0 +1,223 @@
+use serde::Serialize;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use crate::display::{self, DisplayServer};
+
+/// Detection runs external tools, and the GPU does not change while the app runs.
+static INFO: OnceLock<GpuInfo> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Adapter {
+    pub name: String,
+    pub vendor: Option<String>,
+    /// Kernel driver on Linux, vendor driver elsewhere.
+    pub driver: Option<String>,
+    pub driver_version: Option<String>,
+}
+
+/// How the server should launch the browser when nothing else asks for a mode.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrowserMode {
+    Headed,
+    /// No display to open a window on.
+    Headless,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuInfo {
+    pub adapters: Vec<Adapter>,
+    /// OpenGL renderer string, where the platform reports one.
+    pub renderer: Option<String>,
+    pub gl_version: Option<String>,
+    /// Only a software rasterizer is available, so WebGL is slow and may differ visually.
+    pub software_rendering: bool,
+    /// GL works without a display (a DRM render node, or any GPU on macOS and Windows),
+    /// so headless browsers still get hardware WebGL.
+    pub headless_gl: bool,
+    pub has_display: bool,
+    pub recommended_mode: BrowserMode,
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn is_software_renderer(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["llvmpipe", "softpipe", "swiftshader", "swrast", "microsoft basic"].iter().any(|s| name.contains(s))
+}
+
+#[cfg(target_os = "linux")]
+fn pci_vendor(id: &str) -> Option<&'static str> {
+    match id {
+        "0x10de" => Some("NVIDIA"),
+        "0x1002" => Some("AMD"),
+        "0x8086" => Some("Intel"),
+        "0x1af4" => Some("Red Hat (virtio)"),
+        "0x15ad" => Some("VMware"),
+        "0x1234" => Some("QEMU"),
+        _ => None,
+    }
+}
+
+/// DRM cards from sysfs, plus the GL renderer from `glxinfo` when it is installed.
+#[cfg(target_os = "linux")]
+fn detect() -> (Vec<Adapter>, Option<String>, Option<String>, bool) {
+    use std::path::Path;
+
+    let read = |path: &Path| std::fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let mut adapters = Vec::new();
+    if let Ok(entries) = std::fs::read_dir("/sys/class/drm") {
+        let mut cards: Vec<_> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            // card0-HDMI-A-1 and the like are connectors of a card
+            .filter(|path| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                name.starts_with("card") && !name.contains('-')
+            })
+            .collect();
+        cards.sort();
+        for card in cards {
+            let device = card.join("device");
+            let vendor_id = read(&device.join("vendor"));
+            let device_id = read(&device.join("device"));
+            let driver = std::fs::read_link(device.join("driver"))
+                .ok()
+                .and_then(|link| link.file_name().map(|n| n.to_string_lossy().into_owned()));
+            let driver_version = driver.as_ref().and_then(|d| read(&Path::new("/sys/module").join(d).join("version")));
+            let vendor = vendor_id.as_deref().and_then(pci_vendor).map(str::to_string).or(vendor_id.clone());
+            let ids = [vendor_id, device_id].into_iter().flatten().map(|id| id.trim_start_matches("0x").to_string());
+            adapters.push(Adapter {
+                name: format!("{} ({})", vendor.as_deref().unwrap_or("Unknown"), ids.collect::<Vec<_>>().join(":")),
+                vendor,
+                driver,
+                driver_version,
+            });
+        }
+    }
+
+    let glxinfo = run("glxinfo", &["-B"]).unwrap_or_default();
+    let field = |key: &str| {
+        glxinfo.lines().find_map(|line| line.trim().strip_prefix(key).map(|value| value.trim().to_string()))
+    };
+    let renderer = field("OpenGL renderer string:");
+    let gl_version = field("OpenGL version string:");
+
+    let render_node = std::fs::read_dir("/dev/dri")
+        .map(|entries| entries.flatten().any(|e| e.file_name().to_string_lossy().starts_with("renderD")))
+        .unwrap_or(false);
+    (adapters, renderer, gl_version, render_node)
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> (Vec<Adapter>, Option<String>, Option<String>, bool) {
+    let json = run("/usr/sbin/system_profiler", &["SPDisplaysDataType", "-json"])
+        .and_then(|out| serde_json::from_str::<serde_json::Value>(&out).ok())
+        .unwrap_or_default();
+    let text = |gpu: &serde_json::Value, key: &str| gpu.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let adapters: Vec<Adapter> = json["SPDisplaysDataType"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|gpu| Adapter {
+            name: text(gpu, "sppci_model").or_else(|| text(gpu, "_name")).unwrap_or_else(|| "Unknown".to_string()),
+            vendor: text(gpu, "spdisplays_vendor"),
+            driver: text(gpu, "spdisplays_mtlgpufamilysupport"),
+            driver_version: None,
+        })
+        .collect();
+    let renderer = adapters.first().map(|a| a.name.clone());
+    let headless_gl = !adapters.is_empty();
+    (adapters, renderer, None, headless_gl)
+}
+
+#[cfg(windows)]
+fn detect() -> (Vec<Adapter>, Option<String>, Option<String>, bool) {
+    let json = run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Get-CimInstance Win32_VideoController | Select-Object Name,AdapterCompatibility,InstalledDisplayDrivers,DriverVersion | ConvertTo-Json",
+        ],
+    )
+    .and_then(|out| serde_json::from_str::<serde_json::Value>(&out).ok())
+    .unwrap_or_default();
+    // ConvertTo-Json writes a bare object for a single adapter
+    let controllers = match json {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Null => Vec::new(),
+        item => vec![item],
+    };
+    let text = |gpu: &serde_json::Value, key: &str| gpu.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let adapters: Vec<Adapter> = controllers
+        .iter()
+        .map(|gpu| Adapter {
+            name: text(gpu, "Name").unwrap_or_else(|| "Unknown".to_string()),
+            vendor: text(gpu, "AdapterCompatibility"),
+            driver: text(gpu, "InstalledDisplayDrivers"),
+            driver_version: text(gpu, "DriverVersion"),
+        })
+        .collect();
+    let headless_gl = adapters.iter().any(|a| !is_software_renderer(&a.name));
+    (adapters, None, None, headless_gl)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn detect() -> (Vec<Adapter>, Option<String>, Option<String>, bool) {
+    (Vec::new(), None, None, false)
+}
+
+pub fn info() -> &'static GpuInfo {
+    INFO.get_or_init(|| {
+        let (adapters, renderer, gl_version, headless_gl) = detect();
+        let software_rendering = match &renderer {
+            Some(renderer) => is_software_renderer(renderer),
+            None => adapters.iter().all(|a| is_software_renderer(&a.name)),
+        };
+        let has_display = display::display_server() != DisplayServer::Unknown;
+        let info = GpuInfo {
+            adapters,
+            renderer,
+            gl_version,
+            software_rendering,
+            headless_gl,
+            has_display,
+            recommended_mode: if has_display { BrowserMode::Headed } else { BrowserMode::Headless },
+        };
+        log::info!(
+            "GPU: {} (software rendering: {}, headless GL: {})",
+            info.renderer.as_deref().or(info.adapters.first().map(|a| a.name.as_str())).unwrap_or("none"),
+            info.software_rendering,
+            info.headless_gl
+        );
+        info
+    })
+}
+
+/// The rendering hints the server reads from `NOVA_GPU` to pick a browser mode.
+pub fn server_env() -> String {
+    let info = info();
+    serde_json::json!({
+        "software_rendering": info.software_rendering,
+        "headless_gl": info.headless_gl,
+        "has_display": info.has_display,
+        "recommended_mode": info.recommended_mode,
+    })
+    .to_string()
+}
+
+/// The graphics adapters, GL renderer and what that means for headless browsing, to
+/// explain rendering differences between machines.
+#[tauri::command]
+pub async fn get_gpu_info() -> Result<GpuInfo, String> {
+    tauri::async_runtime::spawn_blocking(|| info().clone())
+        .await
+        .map_err(|e| format!("Failed to query GPU info: {}", e))
+}
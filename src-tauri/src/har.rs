@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::history::{self, HarCapture, History};
+use crate::settings::SettingsStore;
+use crate::AppState;
+
+/// Where a run's capture is kept, relative to its artifacts dir.
+const HAR_PATH: &str = "network/network.har";
+
+/// Network captures the server records for each job, as HAR, for debugging automations
+/// from their traffic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HarSettings {
+    pub enabled: bool,
+    /// Also keep captures of successful runs; by default only failed runs keep theirs.
+    pub keep_successful: bool,
+    /// Oldest captures are deleted once all of them together take more than this (MB).
+    /// 0 keeps all.
+    pub max_total_mb: u64,
+}
+
+impl Default for HarSettings {
+    fn default() -> Self {
+        HarSettings { enabled: true, keep_successful: false, max_total_mb: 500 }
+    }
+}
+
+/// Sent by the server as `har.ready` once a job's capture is written.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HarReady {
+    pub job_id: String,
+    pub path: PathBuf,
+}
+
+fn har_path(app: &AppHandle, run_id: &str) -> Option<PathBuf> {
+    history::artifacts_dir(app, run_id).map(|dir| dir.join(HAR_PATH))
+}
+
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    // The server's temp dir may be on another volume
+    std::fs::rename(from, to).or_else(|_| std::fs::copy(from, to).and_then(|_| std::fs::remove_file(from)))
+}
+
+/// Whether captures make it to the server, as `NOVA_HAR_CAPTURE`.
+pub fn server_env(app: &AppHandle) -> &'static str {
+    if app.state::<SettingsStore>().get().har.enabled {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+/// Tells a running server whether to capture.
+pub fn apply_settings(app: &AppHandle) {
+    let enabled = app.state::<SettingsStore>().get().har.enabled;
+    if let Err(e) = app.state::<AppState>().control.push_config(serde_json::json!({ "har_capture": enabled })) {
+        log::debug!("HAR capture setting not pushed to the server: {}", e);
+    }
+}
+
+/// Deletes the oldest captures until all of them fit in `max_total_mb`; returns how many
+/// were deleted.
+fn enforce_limit(app: &AppHandle, max_total_mb: u64) -> Result<usize, String> {
+    if max_total_mb == 0 {
+        return Ok(0);
+    }
+    let history = app.state::<History>();
+    let mut total: u64 = 0;
+    let mut deleted = 0;
+    // Newest first, so everything past the limit is older than what is kept
+    for capture in history.har_captures(None)? {
+        total += capture.size.max(0) as u64;
+        if total <= max_total_mb * 1024 * 1024 {
+            continue;
+        }
+        if let Some(path) = har_path(app, &capture.run_id) {
+            match std::fs::remove_file(&path) {
+                Ok(()) => deleted += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    log::warn!("Failed to delete HAR capture {}: {}", path.display(), e);
+                    continue;
+                }
+            }
+        }
+        history.remove_har_capture(&capture.run_id)?;
+    }
+    if deleted > 0 {
+        log::info!("Deleted {} HAR captures over the {} MB limit", deleted, max_total_mb);
+    }
+    Ok(deleted)
+}
+
+/// Moves a capture into its run's artifacts and indexes it, or deletes it if the run
+/// succeeded and only failed runs keep theirs. `None` means it was not kept.
+fn accept(app: &AppHandle, ready: &HarReady) -> Result<Option<HarCapture>, String> {
+    let settings = app.state::<SettingsStore>().get().har;
+    let target = har_path(app, &ready.job_id).ok_or_else(|| format!("Invalid run id '{}'", ready.job_id))?;
+    if !ready.path.is_file() {
+        return Err(format!("HAR capture {} does not exist", ready.path.display()));
+    }
+    let succeeded = app.state::<History>().get(&ready.job_id)?.is_some_and(|run| run.status == "success");
+    if succeeded && !settings.keep_successful {
+        let _ = std::fs::remove_file(&ready.path);
+        return Ok(None);
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    move_file(&ready.path, &target).map_err(|e| format!("Failed to move {}: {}", ready.path.display(), e))?;
+    let entries = std::fs::read(&target)
+        .ok()
+        .and_then(|data| serde_json::from_slice::<serde_json::Value>(&data).ok())
+        .and_then(|har| har["log"]["entries"].as_array().map(Vec::len))
+        .unwrap_or(0);
+    let capture = HarCapture {
+        run_id: ready.job_id.clone(),
+        size: std::fs::metadata(&target).map(|m| m.len() as i64).unwrap_or(0),
+        entries: entries as i64,
+        created_at: history::now_ms(),
+    };
+    app.state::<History>().add_har_capture(&capture)?;
+    enforce_limit(app, settings.max_total_mb)?;
+    Ok(Some(capture))
+}
+
+/// Takes in a capture the server reported, in the background.
+pub fn har_ready(app: &AppHandle, ready: HarReady) {
+    let app = app.clone();
+    std::thread::spawn(move || match accept(&app, &ready) {
+        Ok(Some(capture)) => {
+            log::info!("HAR capture of run {} added ({} requests)", capture.run_id, capture.entries)
+        }
+        Ok(None) => log::debug!("HAR capture of successful run {} not kept", ready.job_id),
+        Err(e) => log::warn!("Failed to add HAR capture of run {}: {}", ready.job_id, e),
+    });
+}
+
+/// HAR captures, newest first, optionally of one run. Entries whose file is gone (e.g.
+/// purged by retention) are dropped from the index.
+#[tauri::command]
+pub fn list_har_captures(app: AppHandle, run_id: Option<String>) -> Result<Vec<HarCapture>, String> {
+    let history = app.state::<History>();
+    let mut captures = Vec::new();
+    for capture in history.har_captures(run_id.as_deref())? {
+        if har_path(&app, &capture.run_id).is_some_and(|path| path.is_file()) {
+            captures.push(capture);
+        } else {
+            history.remove_har_capture(&capture.run_id)?;
+        }
+    }
+    Ok(captures)
+}
+
+fn ask_path(app: &AppHandle, run_id: &str) -> Option<PathBuf> {
+    app.dialog()
+        .file()
+        .set_title("Export Network Capture")
+        .set_file_name(format!("run-{}.har", run_id))
+        .add_filter("HAR", &["har"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+}
+
+/// Saves a run's network capture as a `.har` file, for browser dev tools or a HAR viewer.
+/// Without a `path` a save dialog is shown; returns the file written, or `None` if the
+/// dialog was cancelled.
+#[tauri::command]
+pub async fn export_har(app: AppHandle, run_id: String, path: Option<PathBuf>) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let source = har_path(&app, &run_id)
+            .filter(|path| path.is_file())
+            .ok_or_else(|| format!("Run {} has no network capture", run_id))?;
+        let Some(path) = path.or_else(|| ask_path(&app, &run_id)) else {
+            return Ok(None);
+        };
+        std::fs::copy(&source, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        log::info!("Exported HAR capture of run {} to {}", run_id, path.display());
+        Ok(Some(path))
+    })
+    .await
+    .map_err(|e| format!("Export failed: {}", e))?
+}
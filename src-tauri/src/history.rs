@@ -0,0 +1,1524 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+use crate::events::{JobEvent, JobEventKind, JobStatus};
+use crate::run_diff::OutputChange;
+
+/// Schema changes, applied in order. `PRAGMA user_version` records how many have run.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS runs (
+        id TEXT PRIMARY KEY,
+        prompt TEXT NOT NULL,
+        status TEXT NOT NULL,
+        message TEXT,
+        schedule_id TEXT,
+        started_at INTEGER NOT NULL,
+        finished_at INTEGER,
+        duration_ms INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS runs_started_at ON runs (started_at DESC);",
+    "ALTER TABLE runs ADD COLUMN output TEXT;",
+    "CREATE TABLE IF NOT EXISTS artifact_blobs (
+        run_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        hash TEXT NOT NULL,
+        PRIMARY KEY (run_id, name)
+    );
+    CREATE INDEX IF NOT EXISTS artifact_blobs_hash ON artifact_blobs (hash);",
+    "CREATE TABLE IF NOT EXISTS recordings (
+        run_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        duration_ms INTEGER,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (run_id, name)
+    );
+    CREATE INDEX IF NOT EXISTS recordings_created_at ON recordings (created_at DESC);",
+    "CREATE VIRTUAL TABLE IF NOT EXISTS run_text USING fts5(run_id UNINDEXED, source UNINDEXED, content);",
+    "CREATE TABLE IF NOT EXISTS queue (
+        id TEXT PRIMARY KEY,
+        prompt TEXT NOT NULL,
+        schedule_id TEXT,
+        priority INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        enqueued_at INTEGER NOT NULL,
+        started_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+    "CREATE TABLE IF NOT EXISTS schedule_audit (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        schedule_id TEXT NOT NULL,
+        at INTEGER NOT NULL,
+        event TEXT NOT NULL,
+        detail TEXT
+    );
+    CREATE INDEX IF NOT EXISTS schedule_audit_at ON schedule_audit (at DESC);",
+    "CREATE TABLE IF NOT EXISTS pipeline_steps (
+        run_id TEXT NOT NULL,
+        pipeline_id TEXT NOT NULL,
+        recipe_id TEXT NOT NULL,
+        after TEXT NOT NULL,
+        pass_artifacts INTEGER NOT NULL,
+        job_id TEXT,
+        status TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (run_id, recipe_id)
+    );
+    CREATE INDEX IF NOT EXISTS pipeline_steps_job_id ON pipeline_steps (job_id);",
+    "CREATE TABLE IF NOT EXISTS batches (
+        id TEXT PRIMARY KEY,
+        recipe_id TEXT NOT NULL,
+        prompt TEXT NOT NULL,
+        chunk_size INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS batch_rows (
+        batch_id TEXT NOT NULL,
+        row_index INTEGER NOT NULL,
+        inputs TEXT NOT NULL,
+        job_id TEXT,
+        status TEXT NOT NULL,
+        message TEXT,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (batch_id, row_index)
+    );
+    CREATE INDEX IF NOT EXISTS batch_rows_job_id ON batch_rows (job_id);",
+    "ALTER TABLE runs ADD COLUMN recipe_id TEXT;
+    ALTER TABLE queue ADD COLUMN recipe_id TEXT;
+    CREATE TABLE IF NOT EXISTS alerts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        run_id TEXT NOT NULL,
+        recipe_id TEXT NOT NULL,
+        expression TEXT NOT NULL,
+        detail TEXT NOT NULL,
+        at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS alerts_at ON alerts (at DESC);",
+    "CREATE TABLE IF NOT EXISTS access_audit (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at INTEGER NOT NULL,
+        command TEXT NOT NULL,
+        role TEXT NOT NULL,
+        required TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS access_audit_at ON access_audit (at DESC);",
+    "CREATE TABLE IF NOT EXISTS monitor_changes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        schedule_id TEXT NOT NULL,
+        run_id TEXT NOT NULL,
+        previous_run_id TEXT NOT NULL,
+        significant INTEGER NOT NULL,
+        changes TEXT NOT NULL,
+        at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS monitor_changes_schedule ON monitor_changes (schedule_id, at DESC);",
+    "CREATE TABLE IF NOT EXISTS domain_overrides (
+        domain TEXT PRIMARY KEY,
+        reason TEXT NOT NULL,
+        granted_by TEXT NOT NULL,
+        granted_at INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS policy_audit (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at INTEGER NOT NULL,
+        domain TEXT NOT NULL,
+        event TEXT NOT NULL,
+        detail TEXT
+    );
+    CREATE INDEX IF NOT EXISTS policy_audit_at ON policy_audit (at DESC);",
+    "CREATE TABLE IF NOT EXISTS har_captures (
+        run_id TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        entries INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS har_captures_created_at ON har_captures (created_at DESC);",
+    "CREATE TABLE IF NOT EXISTS credential_access (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at INTEGER NOT NULL,
+        job_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        outcome TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS credential_access_at ON credential_access (at DESC);",
+    "CREATE TABLE IF NOT EXISTS captcha_solves (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at INTEGER NOT NULL,
+        job_id TEXT NOT NULL,
+        provider TEXT NOT NULL,
+        captcha_type TEXT NOT NULL,
+        site TEXT NOT NULL,
+        outcome TEXT NOT NULL,
+        cost_usd REAL NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS captcha_solves_at ON captcha_solves (at DESC);
+    CREATE INDEX IF NOT EXISTS captcha_solves_job ON captcha_solves (job_id);",
+    "CREATE TABLE IF NOT EXISTS failure_captures (
+        run_id TEXT PRIMARY KEY,
+        step INTEGER,
+        error TEXT NOT NULL,
+        url TEXT,
+        screenshot TEXT,
+        dom TEXT,
+        captured_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE IF NOT EXISTS breakpoints (
+        recipe_id TEXT NOT NULL,
+        step INTEGER NOT NULL,
+        PRIMARY KEY (recipe_id, step)
+    );",
+];
+
+/// File name of the history DB in the app data dir.
+pub const DB_FILE: &str = "history.db";
+
+/// Applies pending migrations, each in its own transaction so a failing one leaves the
+/// schema at the previous version.
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Whether the DB at `path` exists and is on an older schema.
+pub fn needs_migration(path: &Path) -> Result<bool, String> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(|e| e.to_string())?;
+    Ok(version < MIGRATIONS.len())
+}
+
+/// Brings the DB at `path` to the current schema.
+pub fn migrate_file(path: &Path) -> Result<(), String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    migrate(&conn).map_err(|e| format!("Run history migration failed: {}", e))
+}
+
+/// A job as recorded in the history DB. Timestamps are unix milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    pub id: String,
+    pub prompt: String,
+    /// `running`, `success` or `error`.
+    pub status: String,
+    pub message: Option<String>,
+    pub schedule_id: Option<String>,
+    pub recipe_id: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub duration_ms: Option<i64>,
+    /// Structured result reported by the server, if any.
+    pub output: Option<serde_json::Value>,
+}
+
+impl RunRecord {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(RunRecord {
+            id: row.get("id")?,
+            prompt: row.get("prompt")?,
+            status: row.get("status")?,
+            message: row.get("message")?,
+            schedule_id: row.get("schedule_id")?,
+            recipe_id: row.get("recipe_id")?,
+            started_at: row.get("started_at")?,
+            finished_at: row.get("finished_at")?,
+            duration_ms: row.get("duration_ms")?,
+            output: row
+                .get::<_, Option<String>>("output")?
+                .and_then(|output| serde_json::from_str(&output).ok()),
+        })
+    }
+}
+
+/// A session recording kept with a run's artifacts, under `recordings/<name>`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Recording {
+    pub run_id: String,
+    pub name: String,
+    pub size: i64,
+    pub duration_ms: Option<i64>,
+    pub created_at: i64,
+}
+
+impl Recording {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Recording {
+            run_id: row.get("run_id")?,
+            name: row.get("name")?,
+            size: row.get("size")?,
+            duration_ms: row.get("duration_ms")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+/// A run's network traffic as HAR, kept with its artifacts as `network.har`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HarCapture {
+    pub run_id: String,
+    pub size: i64,
+    /// Requests captured.
+    pub entries: i64,
+    pub created_at: i64,
+}
+
+impl HarCapture {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(HarCapture {
+            run_id: row.get("run_id")?,
+            size: row.get("size")?,
+            entries: row.get("entries")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+/// The page as it was when a run's step failed, kept with its artifacts.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureCapture {
+    pub run_id: String,
+    /// From 1, when the run went step by step.
+    pub step: Option<u32>,
+    pub error: String,
+    pub url: Option<String>,
+    /// Paths relative to the run's artifacts dir; `None` when not captured.
+    pub screenshot: Option<String>,
+    pub dom: Option<String>,
+    pub captured_at: i64,
+}
+
+impl FailureCapture {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(FailureCapture {
+            run_id: row.get("run_id")?,
+            step: row.get("step")?,
+            error: row.get("error")?,
+            url: row.get("url")?,
+            screenshot: row.get("screenshot")?,
+            dom: row.get("dom")?,
+            captured_at: row.get("captured_at")?,
+        })
+    }
+}
+
+/// A job's attempt to redeem a credential token. Never holds the secret itself.
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialAccess {
+    pub at: i64,
+    pub job_id: String,
+    /// The secret variable the token stood for; empty when the token was unknown.
+    pub name: String,
+    /// `served`, or why it was refused, e.g. `expired`.
+    pub outcome: String,
+}
+
+impl CredentialAccess {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(CredentialAccess {
+            at: row.get("at")?,
+            job_id: row.get("job_id")?,
+            name: row.get("name")?,
+            outcome: row.get("outcome")?,
+        })
+    }
+}
+
+/// A captcha a job asked the configured solver for, and what it cost.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptchaSolve {
+    pub at: i64,
+    pub job_id: String,
+    pub provider: String,
+    /// e.g. `recaptcha_v2`.
+    pub captcha_type: String,
+    /// The page's origin.
+    pub site: String,
+    /// `solved`, or why not, e.g. `daily_limit`.
+    pub outcome: String,
+    pub cost_usd: f64,
+}
+
+impl CaptchaSolve {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(CaptchaSolve {
+            at: row.get("at")?,
+            job_id: row.get("job_id")?,
+            provider: row.get("provider")?,
+            captcha_type: row.get("captcha_type")?,
+            site: row.get("site")?,
+            outcome: row.get("outcome")?,
+            cost_usd: row.get("cost_usd")?,
+        })
+    }
+}
+
+/// A search hit: where in a run the query matched, best matches first.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchMatch {
+    pub run_id: String,
+    /// `prompt`, `message`, `output`, or the artifact's path relative to the run's dir.
+    pub source: String,
+    /// The matching text around the hit, with matched terms wrapped in `**`.
+    pub snippet: String,
+    /// Higher is more relevant.
+    pub score: f64,
+    pub prompt: String,
+    pub status: String,
+    pub started_at: i64,
+}
+
+/// Something the scheduler decided about a schedule other than running it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleAuditEntry {
+    pub schedule_id: String,
+    pub at: i64,
+    /// e.g. `suppressed`.
+    pub event: String,
+    pub detail: Option<String>,
+}
+
+impl ScheduleAuditEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(ScheduleAuditEntry {
+            schedule_id: row.get("schedule_id")?,
+            at: row.get("at")?,
+            event: row.get("event")?,
+            detail: row.get("detail")?,
+        })
+    }
+}
+
+/// A recipe assertion that a run's output broke.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub run_id: String,
+    pub recipe_id: String,
+    pub expression: String,
+    /// What broke it, e.g. `row 3: price is 120`.
+    pub detail: String,
+    pub at: i64,
+}
+
+impl Alert {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Alert {
+            run_id: row.get("run_id")?,
+            recipe_id: row.get("recipe_id")?,
+            expression: row.get("expression")?,
+            detail: row.get("detail")?,
+            at: row.get("at")?,
+        })
+    }
+}
+
+/// A command refused because the current role lacks the capability it needs.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessAuditEntry {
+    pub at: i64,
+    pub command: String,
+    pub role: String,
+    /// The capability the command needs, e.g. `administer`.
+    pub required: String,
+}
+
+impl AccessAuditEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(AccessAuditEntry {
+            at: row.get("at")?,
+            command: row.get("command")?,
+            role: row.get("role")?,
+            required: row.get("required")?,
+        })
+    }
+}
+
+/// How a monitored schedule's output differed from its previous successful run.
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorChange {
+    pub schedule_id: String,
+    pub run_id: String,
+    pub previous_run_id: String,
+    /// Enough changes counted under the monitor's sensitivity to alert.
+    pub significant: bool,
+    /// Every difference, including those the sensitivity settings ignore.
+    pub changes: Vec<OutputChange>,
+    pub at: i64,
+}
+
+impl MonitorChange {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let changes: String = row.get("changes")?;
+        Ok(MonitorChange {
+            schedule_id: row.get("schedule_id")?,
+            run_id: row.get("run_id")?,
+            previous_run_id: row.get("previous_run_id")?,
+            significant: row.get("significant")?,
+            changes: serde_json::from_str(&changes).unwrap_or_default(),
+            at: row.get("at")?,
+        })
+    }
+}
+
+/// Permission for jobs to target a domain the domain policy would refuse, until it expires.
+#[derive(Debug, Clone, Serialize)]
+pub struct DomainOverride {
+    pub domain: String,
+    pub reason: String,
+    /// The role that granted it.
+    pub granted_by: String,
+    pub granted_at: i64,
+    pub expires_at: i64,
+}
+
+impl DomainOverride {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(DomainOverride {
+            domain: row.get("domain")?,
+            reason: row.get("reason")?,
+            granted_by: row.get("granted_by")?,
+            granted_at: row.get("granted_at")?,
+            expires_at: row.get("expires_at")?,
+        })
+    }
+}
+
+/// Something the domain policy decided or someone changed about it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyAuditEntry {
+    pub at: i64,
+    pub domain: String,
+    /// e.g. `refused` or `override_used`.
+    pub event: String,
+    pub detail: Option<String>,
+}
+
+impl PolicyAuditEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(PolicyAuditEntry {
+            at: row.get("at")?,
+            domain: row.get("domain")?,
+            event: row.get("event")?,
+            detail: row.get("detail")?,
+        })
+    }
+}
+
+/// One step of a pipeline run. Steps keep their pipeline's dependencies as they were when
+/// the run started.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineStep {
+    pub run_id: String,
+    pub pipeline_id: String,
+    pub recipe_id: String,
+    /// Recipes of this run that must succeed first.
+    pub after: Vec<String>,
+    pub pass_artifacts: bool,
+    pub job_id: Option<String>,
+    /// `waiting`, `queued`, `success`, `error` or `skipped` (a dependency failed).
+    pub status: String,
+    pub started_at: i64,
+    pub updated_at: i64,
+}
+
+impl PipelineStep {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(PipelineStep {
+            run_id: row.get("run_id")?,
+            pipeline_id: row.get("pipeline_id")?,
+            recipe_id: row.get("recipe_id")?,
+            after: serde_json::from_str(&row.get::<_, String>("after")?).unwrap_or_default(),
+            pass_artifacts: row.get("pass_artifacts")?,
+            job_id: row.get("job_id")?,
+            status: row.get("status")?,
+            started_at: row.get("started_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+/// A recipe run once per row of a CSV file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Batch {
+    pub id: String,
+    pub recipe_id: String,
+    /// The recipe's prompt when the batch was created; rows fill in its placeholders.
+    pub prompt: String,
+    /// Most rows queued at once.
+    pub chunk_size: usize,
+    pub created_at: i64,
+}
+
+impl Batch {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Batch {
+            id: row.get("id")?,
+            recipe_id: row.get("recipe_id")?,
+            prompt: row.get("prompt")?,
+            chunk_size: row.get::<_, i64>("chunk_size")?.max(1) as usize,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchRow {
+    pub batch_id: String,
+    /// 0-based, not counting the header.
+    pub row_index: i64,
+    /// Variable values taken from the row.
+    pub inputs: BTreeMap<String, String>,
+    pub job_id: Option<String>,
+    /// `pending`, `queued`, `success`, `error` or `cancelled`.
+    pub status: String,
+    pub message: Option<String>,
+    pub updated_at: i64,
+}
+
+impl BatchRow {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(BatchRow {
+            batch_id: row.get("batch_id")?,
+            row_index: row.get("row_index")?,
+            inputs: serde_json::from_str(&row.get::<_, String>("inputs")?).unwrap_or_default(),
+            job_id: row.get("job_id")?,
+            status: row.get("status")?,
+            message: row.get("message")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+/// A job waiting in (or dispatched from) the shell's run queue.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedJob {
+    pub id: String,
+    pub prompt: String,
+    pub schedule_id: Option<String>,
+    pub recipe_id: Option<String>,
+    /// Higher runs sooner; only decides where a job is inserted, `reorder` can move it.
+    pub priority: i32,
+    pub enqueued_at: i64,
+    /// Set once the job was handed to the server.
+    pub started_at: Option<i64>,
+}
+
+impl QueuedJob {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(QueuedJob {
+            id: row.get("id")?,
+            prompt: row.get("prompt")?,
+            schedule_id: row.get("schedule_id")?,
+            recipe_id: row.get("recipe_id")?,
+            priority: row.get("priority")?,
+            enqueued_at: row.get("enqueued_at")?,
+            started_at: row.get("started_at")?,
+        })
+    }
+}
+
+/// Run history, kept in `history.db` in the app data dir. If the DB cannot be opened the
+/// app keeps working; runs just aren't recorded.
+pub struct History {
+    conn: Mutex<Option<Connection>>,
+}
+
+impl History {
+    pub fn open(app: &AppHandle) -> Self {
+        let conn = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|dir| {
+                std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+                let conn = Connection::open(dir.join(DB_FILE)).map_err(|e| e.to_string())?;
+                migrate(&conn).map_err(|e| e.to_string())?;
+                Ok(conn)
+            });
+        match conn {
+            Ok(conn) => History { conn: Mutex::new(Some(conn)) },
+            Err(e) => {
+                log::error!("Failed to open run history: {}", e);
+                History { conn: Mutex::new(None) }
+            }
+        }
+    }
+
+    /// Closes the database; later queries fail as if it never opened.
+    pub fn close(&self) {
+        self.conn.lock().unwrap().take();
+    }
+
+    /// Writes a consistent copy of the database to `path`, even while it is in use.
+    pub fn snapshot(&self, path: &Path) -> Result<(), String> {
+        let path = path.to_string_lossy().into_owned();
+        self.with_conn(|conn| conn.execute("VACUUM INTO ?1", params![path]).map(|_| ()))
+    }
+
+    fn with_conn<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+        let lock = self.conn.lock().unwrap();
+        let conn = lock.as_ref().ok_or("Run history is unavailable")?;
+        f(conn).map_err(|e| format!("Run history query failed: {}", e))
+    }
+
+    pub fn get(&self, run_id: &str) -> Result<Option<RunRecord>, String> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT * FROM runs WHERE id = ?1", params![run_id], RunRecord::from_row)
+                .optional()
+        })
+    }
+
+    pub fn list(&self, limit: u32) -> Result<Vec<RunRecord>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM runs ORDER BY started_at DESC LIMIT ?1")?;
+            let rows = stmt.query_map(params![limit], RunRecord::from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// Finished runs of a recipe started since `since` (ms), newest first, each with the
+    /// step it failed at, when known.
+    pub fn recipe_runs(
+        &self,
+        recipe_id: &str,
+        since: i64,
+        limit: u32,
+    ) -> Result<Vec<(RunRecord, Option<u32>)>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT runs.*, failure_captures.step AS failed_step FROM runs
+                 LEFT JOIN failure_captures ON failure_captures.run_id = runs.id
+                 WHERE runs.recipe_id = ?1 AND runs.status != 'running' AND runs.started_at >= ?2
+                 ORDER BY runs.started_at DESC LIMIT ?3",
+            )?;
+            let rows = stmt.query_map(params![recipe_id, since, limit], |row| {
+                Ok((RunRecord::from_row(row)?, row.get("failed_step")?))
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Finished runs started in `since..until` (ms), newest first.
+    pub fn finished_runs(&self, since: i64, until: i64) -> Result<Vec<RunRecord>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM runs WHERE status != 'running' AND started_at >= ?1 AND started_at < ?2
+                 ORDER BY started_at DESC",
+            )?;
+            let rows = stmt.query_map(params![since, until], RunRecord::from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// The last finished run of a schedule, or else of a recipe, started before `before` (ms).
+    pub fn previous_finished_run(
+        &self,
+        schedule_id: Option<&str>,
+        recipe_id: Option<&str>,
+        before: i64,
+    ) -> Result<Option<RunRecord>, String> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT * FROM runs WHERE status != 'running' AND started_at < ?3
+                 AND (schedule_id = ?1 OR (?1 IS NULL AND recipe_id = ?2))
+                 ORDER BY started_at DESC LIMIT 1",
+                params![schedule_id, recipe_id, before],
+                RunRecord::from_row,
+            )
+            .optional()
+        })
+    }
+
+    /// Records that artifact `name` of a run is stored as blob `hash`.
+    pub fn add_blob_ref(&self, run_id: &str, name: &str, hash: &str) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO artifact_blobs (run_id, name, hash) VALUES (?1, ?2, ?3)",
+                params![run_id, name, hash],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Every (run id, artifact name, blob hash) reference.
+    pub fn blob_refs(&self) -> Result<Vec<(String, String, String)>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT run_id, name, hash FROM artifact_blobs")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect()
+        })
+    }
+
+    pub fn remove_blob_ref(&self, run_id: &str, name: &str) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM artifact_blobs WHERE run_id = ?1 AND name = ?2", params![run_id, name])
+                .map(|_| ())
+        })
+    }
+
+    pub fn add_recording(&self, recording: &Recording) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO recordings (run_id, name, size, duration_ms, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![recording.run_id, recording.name, recording.size, recording.duration_ms, recording.created_at],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Indexed recordings, newest first, optionally only those of one run.
+    pub fn recordings(&self, run_id: Option<&str>) -> Result<Vec<Recording>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM recordings WHERE ?1 IS NULL OR run_id = ?1 ORDER BY created_at DESC",
+            )?;
+            let rows = stmt.query_map(params![run_id], Recording::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn add_schedule_audit(&self, entry: &ScheduleAuditEntry) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO schedule_audit (schedule_id, at, event, detail) VALUES (?1, ?2, ?3, ?4)",
+                params![entry.schedule_id, entry.at, entry.event, entry.detail],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Newest first, optionally only those of one schedule.
+    pub fn schedule_audit(&self, schedule_id: Option<&str>, limit: usize) -> Result<Vec<ScheduleAuditEntry>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM schedule_audit WHERE ?1 IS NULL OR schedule_id = ?1 ORDER BY at DESC, id DESC LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![schedule_id, limit as i64], ScheduleAuditEntry::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn add_access_audit(&self, entry: &AccessAuditEntry) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO access_audit (at, command, role, required) VALUES (?1, ?2, ?3, ?4)",
+                params![entry.at, entry.command, entry.role, entry.required],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Newest first.
+    pub fn access_audit(&self, limit: usize) -> Result<Vec<AccessAuditEntry>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM access_audit ORDER BY at DESC, id DESC LIMIT ?1")?;
+            let rows = stmt.query_map(params![limit as i64], AccessAuditEntry::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn add_monitor_change(&self, change: &MonitorChange) -> Result<(), String> {
+        let changes = serde_json::to_string(&change.changes).map_err(|e| e.to_string())?;
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO monitor_changes (schedule_id, run_id, previous_run_id, significant, changes, at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    change.schedule_id,
+                    change.run_id,
+                    change.previous_run_id,
+                    change.significant,
+                    changes,
+                    change.at
+                ],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Newest first, optionally only those of one schedule or those that alerted.
+    pub fn monitor_changes(
+        &self,
+        schedule_id: Option<&str>,
+        significant_only: bool,
+        limit: usize,
+    ) -> Result<Vec<MonitorChange>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM monitor_changes WHERE (?1 IS NULL OR schedule_id = ?1) AND (?2 = 0 OR significant = 1)
+                 ORDER BY at DESC, id DESC LIMIT ?3",
+            )?;
+            let rows = stmt.query_map(params![schedule_id, significant_only, limit as i64], MonitorChange::from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// Grants an override, replacing any earlier one for the same domain.
+    pub fn set_domain_override(&self, entry: &DomainOverride) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO domain_overrides (domain, reason, granted_by, granted_at, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![entry.domain, entry.reason, entry.granted_by, entry.granted_at, entry.expires_at],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// False if the domain had no override.
+    pub fn remove_domain_override(&self, domain: &str) -> Result<bool, String> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM domain_overrides WHERE domain = ?1", params![domain]).map(|n| n > 0)
+        })
+    }
+
+    /// Overrides that have not expired at `now`, soonest to expire first.
+    pub fn domain_overrides(&self, now: i64) -> Result<Vec<DomainOverride>, String> {
+        self.with_conn(|conn| {
+            let mut stmt =
+                conn.prepare("SELECT * FROM domain_overrides WHERE expires_at > ?1 ORDER BY expires_at, domain")?;
+            let rows = stmt.query_map(params![now], DomainOverride::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn add_policy_audit(&self, entry: &PolicyAuditEntry) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO policy_audit (at, domain, event, detail) VALUES (?1, ?2, ?3, ?4)",
+                params![entry.at, entry.domain, entry.event, entry.detail],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Newest first.
+    pub fn policy_audit(&self, limit: usize) -> Result<Vec<PolicyAuditEntry>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM policy_audit ORDER BY at DESC, id DESC LIMIT ?1")?;
+            let rows = stmt.query_map(params![limit as i64], PolicyAuditEntry::from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// The last successful run of a schedule that started before `run_id`.
+    pub fn previous_successful_run(&self, schedule_id: &str, run_id: &str) -> Result<Option<RunRecord>, String> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT * FROM runs WHERE schedule_id = ?1 AND id != ?2 AND status = 'success'
+                    AND started_at <= (SELECT started_at FROM runs WHERE id = ?2)
+                 ORDER BY started_at DESC LIMIT 1",
+                params![schedule_id, run_id],
+                RunRecord::from_row,
+            )
+            .optional()
+        })
+    }
+
+    /// The last successful run of a recipe, before `run_id` if given.
+    pub fn previous_successful_recipe_run(
+        &self,
+        recipe_id: &str,
+        run_id: Option<&str>,
+    ) -> Result<Option<RunRecord>, String> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT * FROM runs WHERE recipe_id = ?1 AND status = 'success'
+                    AND (?2 IS NULL OR (id != ?2 AND started_at <= (SELECT started_at FROM runs WHERE id = ?2)))
+                 ORDER BY started_at DESC LIMIT 1",
+                params![recipe_id, run_id],
+                RunRecord::from_row,
+            )
+            .optional()
+        })
+    }
+
+    pub fn add_alert(&self, alert: &Alert) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO alerts (run_id, recipe_id, expression, detail, at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![alert.run_id, alert.recipe_id, alert.expression, alert.detail, alert.at],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Newest first, optionally only those of one recipe.
+    pub fn alerts(&self, recipe_id: Option<&str>, limit: usize) -> Result<Vec<Alert>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT * FROM alerts WHERE ?1 IS NULL OR recipe_id = ?1 ORDER BY at DESC, id DESC LIMIT ?2")?;
+            let rows = stmt.query_map(params![recipe_id, limit as i64], Alert::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn add_pipeline_steps(&self, steps: &[PipelineStep]) -> Result<(), String> {
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for step in steps {
+                tx.execute(
+                    "INSERT INTO pipeline_steps
+                        (run_id, pipeline_id, recipe_id, after, pass_artifacts, job_id, status, started_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        step.run_id,
+                        step.pipeline_id,
+                        step.recipe_id,
+                        serde_json::to_string(&step.after).unwrap_or_default(),
+                        step.pass_artifacts,
+                        step.job_id,
+                        step.status,
+                        step.started_at,
+                        step.updated_at
+                    ],
+                )?;
+            }
+            tx.commit()
+        })
+    }
+
+    pub fn update_pipeline_step(
+        &self,
+        run_id: &str,
+        recipe_id: &str,
+        status: &str,
+        job_id: Option<&str>,
+    ) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "UPDATE pipeline_steps SET status = ?3, job_id = COALESCE(?4, job_id), updated_at = ?5
+                 WHERE run_id = ?1 AND recipe_id = ?2",
+                params![run_id, recipe_id, status, job_id, now_ms()],
+            )
+            .map(|_| ())
+        })
+    }
+
+    pub fn pipeline_steps(&self, run_id: &str) -> Result<Vec<PipelineStep>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM pipeline_steps WHERE run_id = ?1 ORDER BY rowid")?;
+            let rows = stmt.query_map(params![run_id], PipelineStep::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn pipeline_step_for_job(&self, job_id: &str) -> Result<Option<PipelineStep>, String> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT * FROM pipeline_steps WHERE job_id = ?1", params![job_id], PipelineStep::from_row)
+                .optional()
+        })
+    }
+
+    /// Ids of the latest pipeline runs, newest first.
+    pub fn pipeline_runs(&self, limit: usize) -> Result<Vec<String>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT run_id FROM pipeline_steps GROUP BY run_id ORDER BY MAX(started_at) DESC LIMIT ?1",
+            )?;
+            let rows = stmt.query_map(params![limit as i64], |row| row.get(0))?;
+            rows.collect()
+        })
+    }
+
+    pub fn add_batch(&self, batch: &Batch, rows: &[BTreeMap<String, String>]) -> Result<(), String> {
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT INTO batches (id, recipe_id, prompt, chunk_size, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![batch.id, batch.recipe_id, batch.prompt, batch.chunk_size as i64, batch.created_at],
+            )?;
+            let mut stmt = tx.prepare(
+                "INSERT INTO batch_rows (batch_id, row_index, inputs, status, updated_at) VALUES (?1, ?2, ?3, 'pending', ?4)",
+            )?;
+            for (index, inputs) in rows.iter().enumerate() {
+                let inputs = serde_json::to_string(inputs).unwrap_or_default();
+                stmt.execute(params![batch.id, index as i64, inputs, batch.created_at])?;
+            }
+            drop(stmt);
+            tx.commit()
+        })
+    }
+
+    pub fn batch(&self, id: &str) -> Result<Option<Batch>, String> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT * FROM batches WHERE id = ?1", params![id], Batch::from_row).optional()
+        })
+    }
+
+    /// Ids of batches with rows still to queue.
+    pub fn pending_batches(&self) -> Result<Vec<String>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT DISTINCT batch_id FROM batch_rows WHERE status = 'pending'")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect()
+        })
+    }
+
+    /// A batch's rows in order, optionally only those with `status`, at most `limit`.
+    pub fn batch_rows(&self, batch_id: &str, status: Option<&str>, limit: usize) -> Result<Vec<BatchRow>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM batch_rows WHERE batch_id = ?1 AND (?2 IS NULL OR status = ?2) ORDER BY row_index LIMIT ?3",
+            )?;
+            let rows = stmt.query_map(params![batch_id, status, limit as i64], BatchRow::from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// Row count per status.
+    pub fn batch_counts(&self, batch_id: &str) -> Result<BTreeMap<String, i64>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT status, COUNT(*) FROM batch_rows WHERE batch_id = ?1 GROUP BY status")?;
+            let rows = stmt.query_map(params![batch_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect()
+        })
+    }
+
+    pub fn batch_row_for_job(&self, job_id: &str) -> Result<Option<BatchRow>, String> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT * FROM batch_rows WHERE job_id = ?1", params![job_id], BatchRow::from_row).optional()
+        })
+    }
+
+    pub fn update_batch_row(
+        &self,
+        batch_id: &str,
+        row_index: i64,
+        status: &str,
+        job_id: Option<&str>,
+        message: Option<&str>,
+    ) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "UPDATE batch_rows SET status = ?3, job_id = COALESCE(?4, job_id), message = ?5, updated_at = ?6
+                 WHERE batch_id = ?1 AND row_index = ?2",
+                params![batch_id, row_index, status, job_id, message, now_ms()],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Marks rows not queued yet as cancelled; returns how many.
+    pub fn cancel_pending_batch_rows(&self, batch_id: &str) -> Result<usize, String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "UPDATE batch_rows SET status = 'cancelled', updated_at = ?2 WHERE batch_id = ?1 AND status = 'pending'",
+                params![batch_id, now_ms()],
+            )
+        })
+    }
+
+    pub fn remove_recording(&self, run_id: &str, name: &str) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM recordings WHERE run_id = ?1 AND name = ?2", params![run_id, name]).map(|_| ())
+        })
+    }
+
+    pub fn add_har_capture(&self, capture: &HarCapture) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO har_captures (run_id, size, entries, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![capture.run_id, capture.size, capture.entries, capture.created_at],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Indexed HAR captures, newest first, optionally only that of one run.
+    pub fn har_captures(&self, run_id: Option<&str>) -> Result<Vec<HarCapture>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM har_captures WHERE ?1 IS NULL OR run_id = ?1 ORDER BY created_at DESC, run_id",
+            )?;
+            let rows = stmt.query_map(params![run_id], HarCapture::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn remove_har_capture(&self, run_id: &str) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM har_captures WHERE run_id = ?1", params![run_id]).map(|_| ())
+        })
+    }
+
+    /// A run keeps the capture of its last failure.
+    pub fn add_failure_capture(&self, capture: &FailureCapture) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO failure_captures (run_id, step, error, url, screenshot, dom, captured_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    capture.run_id,
+                    capture.step,
+                    capture.error,
+                    capture.url,
+                    capture.screenshot,
+                    capture.dom,
+                    capture.captured_at
+                ],
+            )
+            .map(|_| ())
+        })
+    }
+
+    pub fn failure_capture(&self, run_id: &str) -> Result<Option<FailureCapture>, String> {
+        self.with_conn(|conn| {
+            let sql = "SELECT * FROM failure_captures WHERE run_id = ?1";
+            conn.query_row(sql, params![run_id], FailureCapture::from_row).optional()
+        })
+    }
+
+    pub fn add_credential_access(&self, entry: &CredentialAccess) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO credential_access (at, job_id, name, outcome) VALUES (?1, ?2, ?3, ?4)",
+                params![entry.at, entry.job_id, entry.name, entry.outcome],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Newest first.
+    pub fn credential_access(&self, limit: usize) -> Result<Vec<CredentialAccess>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM credential_access ORDER BY at DESC, id DESC LIMIT ?1")?;
+            let rows = stmt.query_map(params![limit as i64], CredentialAccess::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn add_captcha_solve(&self, solve: &CaptchaSolve) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO captcha_solves (at, job_id, provider, captcha_type, site, outcome, cost_usd)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    solve.at,
+                    solve.job_id,
+                    solve.provider,
+                    solve.captcha_type,
+                    solve.site,
+                    solve.outcome,
+                    solve.cost_usd
+                ],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Newest first.
+    pub fn captcha_solves(&self, limit: usize) -> Result<Vec<CaptchaSolve>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM captcha_solves ORDER BY at DESC, id DESC LIMIT ?1")?;
+            let rows = stmt.query_map(params![limit as i64], CaptchaSolve::from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// Captchas solved since `since` (ms) and what they cost in total.
+    pub fn captcha_spend_since(&self, since: i64) -> Result<(u32, f64), String> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(cost_usd), 0) FROM captcha_solves WHERE at >= ?1 AND outcome = 'solved'",
+                params![since],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+        })
+    }
+
+    /// Solver requests a job has made, whatever their outcome.
+    pub fn captcha_requests_for_job(&self, job_id: &str) -> Result<u32, String> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT COUNT(*) FROM captcha_solves WHERE job_id = ?1", params![job_id], |row| row.get(0))
+        })
+    }
+
+    pub fn set_breakpoint(&self, recipe_id: &str, step: u32, enabled: bool) -> Result<(), String> {
+        self.with_conn(|conn| {
+            let sql = if enabled {
+                "INSERT OR IGNORE INTO breakpoints (recipe_id, step) VALUES (?1, ?2)"
+            } else {
+                "DELETE FROM breakpoints WHERE recipe_id = ?1 AND step = ?2"
+            };
+            conn.execute(sql, params![recipe_id, step]).map(|_| ())
+        })
+    }
+
+    /// A recipe's breakpoints, in step order.
+    pub fn breakpoints(&self, recipe_id: &str) -> Result<Vec<u32>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT step FROM breakpoints WHERE recipe_id = ?1 ORDER BY step")?;
+            let rows = stmt.query_map(params![recipe_id], |row| row.get(0))?;
+            rows.collect()
+        })
+    }
+
+    pub fn clear_breakpoints(&self, recipe_id: &str) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM breakpoints WHERE recipe_id = ?1", params![recipe_id]).map(|_| ())
+        })
+    }
+
+    /// Replaces what the search index holds for a run with `texts` as (source, content).
+    pub fn index_run_text(&self, run_id: &str, texts: &[(String, String)]) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM run_text WHERE run_id = ?1", params![run_id])?;
+            let mut stmt = conn.prepare("INSERT INTO run_text (run_id, source, content) VALUES (?1, ?2, ?3)")?;
+            for (source, content) in texts {
+                stmt.execute(params![run_id, source, content])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Finished runs the search index has nothing for yet.
+    pub fn unindexed_runs(&self) -> Result<Vec<String>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id FROM runs WHERE status != 'running' AND id NOT IN (SELECT DISTINCT run_id FROM run_text)",
+            )?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect()
+        })
+    }
+
+    /// Runs `query`, an FTS5 match expression, against the search index.
+    pub fn search(&self, query: &str, limit: u32) -> Result<Vec<SearchMatch>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT run_text.run_id, run_text.source, snippet(run_text, 2, '**', '**', '…', 16), -bm25(run_text),
+                        runs.prompt, runs.status, runs.started_at
+                 FROM run_text JOIN runs ON runs.id = run_text.run_id
+                 WHERE run_text MATCH ?1 ORDER BY bm25(run_text) LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![query, limit], |row| {
+                Ok(SearchMatch {
+                    run_id: row.get(0)?,
+                    source: row.get(1)?,
+                    snippet: row.get(2)?,
+                    score: row.get(3)?,
+                    prompt: row.get(4)?,
+                    status: row.get(5)?,
+                    started_at: row.get(6)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Running jobs first, then waiting ones in the order they will be dispatched.
+    pub fn queue(&self) -> Result<Vec<QueuedJob>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM queue ORDER BY started_at IS NULL, started_at, position")?;
+            let rows = stmt.query_map([], QueuedJob::from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// Adds a job ahead of the waiting jobs with a lower priority.
+    pub fn enqueue(&self, job: &QueuedJob) -> Result<(), String> {
+        self.with_conn(|conn| {
+            let position: i64 = conn.query_row(
+                "SELECT COALESCE(
+                    (SELECT MIN(position) FROM queue WHERE started_at IS NULL AND priority < ?1),
+                    (SELECT MAX(position) + 1 FROM queue),
+                    0)",
+                params![job.priority],
+                |row| row.get(0),
+            )?;
+            conn.execute("UPDATE queue SET position = position + 1 WHERE position >= ?1", params![position])?;
+            conn.execute(
+                "INSERT INTO queue (id, prompt, schedule_id, recipe_id, priority, position, enqueued_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![job.id, job.prompt, job.schedule_id, job.recipe_id, job.priority, position, job.enqueued_at],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Puts the waiting jobs in the order of `ids`; jobs not listed go after them.
+    pub fn reorder_queue(&self, ids: &[String]) -> Result<(), String> {
+        self.with_conn(|conn| {
+            let offset = ids.len() as i64;
+            conn.execute("UPDATE queue SET position = position + ?1", params![offset])?;
+            for (position, id) in ids.iter().enumerate() {
+                conn.execute("UPDATE queue SET position = ?1 WHERE id = ?2", params![position as i64, id])?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn mark_dispatched(&self, id: &str, started_at: i64) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute("UPDATE queue SET started_at = ?1 WHERE id = ?2", params![started_at, id]).map(|_| ())
+        })
+    }
+
+    /// Removes a job that is still waiting. Returns false if it isn't waiting anymore.
+    pub fn remove_waiting(&self, id: &str) -> Result<bool, String> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM queue WHERE id = ?1 AND started_at IS NULL", params![id]).map(|n| n > 0)
+        })
+    }
+
+    pub fn remove_from_queue(&self, id: &str) -> Result<(), String> {
+        self.with_conn(|conn| conn.execute("DELETE FROM queue WHERE id = ?1", params![id]).map(|_| ()))
+    }
+
+    /// Puts jobs that were running when the app last quit back in line, ahead of the rest.
+    pub fn requeue_dispatched(&self) -> Result<usize, String> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "UPDATE queue SET started_at = NULL, position = position - (SELECT MAX(position) + 1 FROM queue)
+                 WHERE started_at IS NOT NULL",
+                [],
+            )
+        })
+    }
+
+    pub fn meta(&self, key: &str) -> Result<Option<String>, String> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| row.get(0)).optional()
+        })
+    }
+
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)", params![key, value]).map(|_| ())
+        })
+    }
+
+    /// Every run's id and start time, newest first.
+    pub fn started_at(&self) -> Result<Vec<(String, i64)>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT id, started_at FROM runs ORDER BY started_at DESC")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect()
+        })
+    }
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+/// `YYYYMMDDTHHMMSSZ` in UTC.
+pub fn compact_utc(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}{:02}{:02}T{:02}{:02}{:02}Z", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// Records a job event. A finished event for a run we never saw start still gets a row.
+pub fn record(app: &AppHandle, event: &JobEvent) {
+    let now = now_ms();
+    let result = app.state::<History>().with_conn(|conn| match event.kind {
+        JobEventKind::Started => conn.execute(
+            "INSERT OR REPLACE INTO runs (id, prompt, status, schedule_id, recipe_id, started_at)
+             VALUES (?1, ?2, 'running', ?3, ?4, ?5)",
+            params![event.job_id, event.prompt, event.schedule_id, event.recipe_id, now],
+        ),
+        JobEventKind::Finished => {
+            let status = match event.status {
+                Some(JobStatus::Error) => "error",
+                _ => "success",
+            };
+            let duration = event.duration_ms.map(|d| d as i64);
+            let started_at = now - duration.unwrap_or(0);
+            let output = event.output.as_ref().map(|output| output.to_string());
+            conn.execute(
+                "INSERT INTO runs
+                    (id, prompt, status, message, schedule_id, started_at, finished_at, duration_ms, output, recipe_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                 ON CONFLICT (id) DO UPDATE SET status = ?3, message = ?4, finished_at = ?7, duration_ms = ?8, output = ?9,
+                    recipe_id = COALESCE(recipe_id, ?10)",
+                params![
+                    event.job_id,
+                    event.prompt,
+                    status,
+                    event.message,
+                    event.schedule_id,
+                    started_at,
+                    now,
+                    duration,
+                    output,
+                    event.recipe_id
+                ],
+            )
+        }
+        JobEventKind::Paused | JobEventKind::Resumed => Ok(0),
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to record run {}: {}", event.job_id, e);
+    }
+}
+
+/// Where files produced by a run are kept: `runs/<run_id>` in the app data dir.
+pub fn artifacts_dir(app: &AppHandle, run_id: &str) -> Option<PathBuf> {
+    let safe = !run_id.is_empty() && run_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !safe {
+        return None;
+    }
+    app.path().app_data_dir().ok().map(|dir| dir.join("runs").join(run_id))
+}
+
+/// Files directly inside a run's artifacts dir, sorted by name.
+pub fn artifacts(app: &AppHandle, run_id: &str) -> Vec<PathBuf> {
+    let Some(entries) = artifacts_dir(app, run_id).and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file()).collect();
+    files.sort();
+    files
+}
+
+/// Every file under a run's artifacts `dir`, with its path relative to it, sorted.
+pub fn artifact_files(dir: &Path) -> Result<Vec<(PathBuf, String)>, String> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, String)>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                walk(root, &path, files)?;
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                // Names use forward slashes on every OS, as archive entries do
+                let name = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+                files.push((path, name));
+            }
+        }
+        Ok(())
+    }
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(files)
+}
+
+#[tauri::command]
+pub fn list_runs(history: State<'_, History>, limit: Option<u32>) -> Result<Vec<RunRecord>, String> {
+    history.list(limit.unwrap_or(50))
+}
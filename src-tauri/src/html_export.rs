@@ -0,0 +1,230 @@
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::export::{cell_text, to_table};
+use crate::history::{self, History, RunRecord};
+use crate::reports::{format_duration, format_time};
+use crate::thumbnails::is_screenshot;
+
+/// Where the run's artifacts are copied inside the exported folder.
+const ASSETS_DIR: &str = "assets";
+
+const STYLE: &str = r#"
+body {
+    font: 14px/1.5 -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif;
+    margin: 0; color: #1f2328; background: #f6f8fa;
+}
+main { max-width: 1100px; margin: 0 auto; padding: 32px 24px; }
+h1 { font-size: 24px; margin: 0 0 4px; }
+h2 { font-size: 18px; margin: 32px 0 12px; }
+.prompt { font-size: 16px; color: #59636e; margin: 0 0 16px; }
+.status { display: inline-block; padding: 2px 10px; border-radius: 12px; font-weight: 600; background: #ddf4ff; }
+.status.success { background: #dafbe1; color: #1a7f37; }
+.status.error { background: #ffebe9; color: #cf222e; }
+dl {
+    display: grid; grid-template-columns: 140px 1fr; gap: 6px 16px;
+    background: #fff; padding: 16px; border-radius: 8px;
+}
+dt { font-weight: 600; }
+dd { margin: 0; word-break: break-word; }
+.table-wrap { overflow-x: auto; background: #fff; border-radius: 8px; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 6px 10px; border-bottom: 1px solid #d1d9e0; vertical-align: top; }
+th { background: #f6f8fa; position: sticky; top: 0; }
+tr.hidden { display: none; }
+input[type=search] {
+    padding: 6px 10px; margin-bottom: 8px; width: 260px; border: 1px solid #d1d9e0; border-radius: 6px;
+}
+.shots { display: grid; grid-template-columns: repeat(auto-fill, minmax(240px, 1fr)); gap: 16px; }
+figure { margin: 0; background: #fff; border-radius: 8px; padding: 8px; }
+figure img { width: 100%; cursor: zoom-in; border-radius: 4px; }
+figcaption { font-size: 12px; color: #59636e; margin-top: 4px; word-break: break-all; }
+#viewer {
+    display: none; position: fixed; inset: 0; background: rgba(0, 0, 0, .85);
+    align-items: center; justify-content: center; cursor: zoom-out;
+}
+#viewer.open { display: flex; }
+#viewer img { max-width: 95vw; max-height: 95vh; }
+footer { color: #59636e; font-size: 12px; margin-top: 40px; }
+"#;
+
+const SCRIPT: &str = r#"
+const filter = document.getElementById('filter');
+if (filter) {
+    filter.addEventListener('input', () => {
+        const query = filter.value.toLowerCase();
+        document.querySelectorAll('#results tbody tr').forEach(row => {
+            row.classList.toggle('hidden', !row.textContent.toLowerCase().includes(query));
+        });
+    });
+}
+const viewer = document.getElementById('viewer');
+document.querySelectorAll('.shots img').forEach(img => {
+    img.addEventListener('click', () => {
+        viewer.querySelector('img').src = img.src;
+        viewer.classList.add('open');
+    });
+});
+viewer.addEventListener('click', () => viewer.classList.remove('open'));
+document.addEventListener('keydown', event => {
+    if (event.key === 'Escape') viewer.classList.remove('open');
+});
+"#;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A relative link to an artifact copied under `ASSETS_DIR`.
+fn asset_href(name: &str) -> String {
+    let mut href = format!("{}/", ASSETS_DIR);
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => href.push(byte as char),
+            _ => href.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    href
+}
+
+fn field(html: &mut String, label: &str, value: &str) {
+    let value = if value.is_empty() { "-" } else { value };
+    html.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", escape(label), escape(value)));
+}
+
+fn render(run: &RunRecord, artifacts: &[String]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    html.push_str(&format!("<title>Run Report: {}</title>\n", escape(&run.prompt)));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n<main>\n", STYLE));
+
+    html.push_str("<h1>Run Report</h1>\n");
+    html.push_str(&format!("<p class=\"prompt\">{}</p>\n", escape(&run.prompt)));
+    html.push_str(&format!("<span class=\"status {0}\">{0}</span>\n", escape(&run.status)));
+
+    html.push_str("<h2>Parameters</h2>\n<dl>\n");
+    field(&mut html, "Run", &run.id);
+    field(&mut html, "Prompt", &run.prompt);
+    field(&mut html, "Recipe", run.recipe_id.as_deref().unwrap_or_default());
+    field(&mut html, "Schedule", run.schedule_id.as_deref().unwrap_or_default());
+    field(&mut html, "Result", run.message.as_deref().unwrap_or_default());
+    field(&mut html, "Started", &format_time(run.started_at));
+    field(&mut html, "Finished", &run.finished_at.map(format_time).unwrap_or_default());
+    field(&mut html, "Duration", &run.duration_ms.map(format_duration).unwrap_or_default());
+    html.push_str("</dl>\n");
+
+    if let Some(output) = &run.output {
+        let table = to_table(output);
+        html.push_str("<h2>Extracted Data</h2>\n");
+        html.push_str("<input id=\"filter\" type=\"search\" placeholder=\"Filter rows\">\n");
+        html.push_str("<div class=\"table-wrap\"><table id=\"results\">\n<thead><tr>");
+        for column in &table.columns {
+            html.push_str(&format!("<th>{}</th>", escape(column)));
+        }
+        html.push_str("</tr></thead>\n<tbody>\n");
+        for row in &table.rows {
+            html.push_str("<tr>");
+            for value in row {
+                html.push_str(&format!("<td>{}</td>", escape(&cell_text(value))));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</tbody>\n</table></div>\n");
+    }
+
+    let (screenshots, files): (Vec<&String>, Vec<&String>) =
+        artifacts.iter().partition(|name| is_screenshot(Path::new(name.as_str())));
+    if !screenshots.is_empty() {
+        html.push_str("<h2>Screenshots</h2>\n<div class=\"shots\">\n");
+        for name in screenshots {
+            html.push_str(&format!(
+                "<figure><img src=\"{}\" alt=\"{1}\" loading=\"lazy\"><figcaption>{1}</figcaption></figure>\n",
+                escape(&asset_href(name)),
+                escape(name)
+            ));
+        }
+        html.push_str("</div>\n");
+    }
+    if !files.is_empty() {
+        html.push_str("<h2>Files</h2>\n<ul>\n");
+        for name in files {
+            html.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", escape(&asset_href(name)), escape(name)));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str(&format!(
+        "<footer>Exported from Browser Automation on {}</footer>\n",
+        escape(&format_time(history::now_ms()))
+    ));
+    html.push_str("</main>\n<div id=\"viewer\"><img alt=\"\"></div>\n");
+    html.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", SCRIPT));
+    html
+}
+
+/// Asks for the folder to export into; the export goes in a `run-<id>` folder inside it.
+/// `None` means the user cancelled.
+fn ask_path(app: &AppHandle, run_id: &str) -> Option<PathBuf> {
+    app.dialog()
+        .file()
+        .set_title("Export Run as HTML")
+        .blocking_pick_folder()
+        .and_then(|path| path.into_path().ok())
+        .map(|dir| dir.join(format!("run-{}", run_id)))
+}
+
+fn export(app: &AppHandle, run_id: &str, path: Option<PathBuf>) -> Result<Option<PathBuf>, String> {
+    let run = app
+        .state::<History>()
+        .get(run_id)?
+        .ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let Some(path) = path.or_else(|| ask_path(app, run_id)) else {
+        return Ok(None);
+    };
+    let occupied = std::fs::read_dir(&path).is_ok_and(|mut entries| entries.next().is_some());
+    if occupied {
+        return Err(format!("{} already exists and is not empty", path.display()));
+    }
+
+    log::info!("Exporting run {} as HTML to {}", run_id, path.display());
+    let assets = path.join(ASSETS_DIR);
+    let mut artifacts = Vec::new();
+    if let Some(dir) = history::artifacts_dir(app, run_id).filter(|dir| dir.is_dir()) {
+        for (file, name) in history::artifact_files(&dir)? {
+            let target = assets.join(&name);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            std::fs::copy(&file, &target).map_err(|e| format!("Failed to copy {}: {}", file.display(), e))?;
+            artifacts.push(name);
+        }
+    }
+    std::fs::create_dir_all(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let index = path.join("index.html");
+    std::fs::write(&index, render(&run, &artifacts))
+        .map_err(|e| format!("Failed to write {}: {}", index.display(), e))?;
+    Ok(Some(path))
+}
+
+/// Exports a run as a folder with an `index.html` and the run's artifacts, viewable in
+/// any browser without the app. Without a `path` a folder picker is shown; returns the
+/// folder written, or `None` if the picker was cancelled.
+#[tauri::command]
+pub async fn export_run_html(app: AppHandle, run_id: String, path: Option<PathBuf>) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || export(&app, &run_id, path))
+        .await
+        .map_err(|e| format!("Export failed: {}", e))?
+}
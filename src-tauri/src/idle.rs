@@ -0,0 +1,117 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::settings::SettingsStore;
+use crate::AppState;
+
+/// How often idle time is sampled. Transitions are reported at most this late.
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+static STATE: Mutex<IdleState> = Mutex::new(IdleState { idle: false, idle_secs: None });
+
+/// Sent as `user-idle-changed` when the user steps away or comes back.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct IdleState {
+    /// No input for at least `idle_threshold_minutes`.
+    pub idle: bool,
+    /// Seconds since the last keyboard or mouse input; `None` where it cannot be read.
+    pub idle_secs: Option<u64>,
+}
+
+/// Seconds since the last input, from the HID system's `HIDIdleTime` (nanoseconds).
+#[cfg(target_os = "macos")]
+fn idle_secs() -> Option<u64> {
+    let output = std::process::Command::new("/usr/sbin/ioreg").args(["-c", "IOHIDSystem", "-d", "4"]).output().ok()?;
+    String::from_utf8_lossy(&output.stdout).lines().find_map(|line| {
+        let (_, value) = line.split_once("\"HIDIdleTime\" = ")?;
+        value.trim().parse::<u64>().ok().map(|ns| ns / 1_000_000_000)
+    })
+}
+
+#[cfg(windows)]
+fn idle_secs() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO { cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+    unsafe {
+        if !GetLastInputInfo(&mut info).as_bool() {
+            return None;
+        }
+        // Both are 32-bit millisecond tick counts that wrap together
+        Some(u64::from(GetTickCount().wrapping_sub(info.dwTime)) / 1000)
+    }
+}
+
+/// Asks the desktop's session bus services, which work on X11 and Wayland alike: GNOME's
+/// idle monitor (milliseconds), then the freedesktop screensaver KDE and others implement
+/// (seconds).
+#[cfg(target_os = "linux")]
+fn idle_secs() -> Option<u64> {
+    let conn = dbus::blocking::Connection::new_session().ok()?;
+    let timeout = Duration::from_secs(2);
+    let mutter = conn.with_proxy("org.gnome.Mutter.IdleMonitor", "/org/gnome/Mutter/IdleMonitor/Core", timeout);
+    if let Ok((ms,)) = mutter.method_call::<(u64,), _, _, _>("org.gnome.Mutter.IdleMonitor", "GetIdletime", ()) {
+        return Some(ms / 1000);
+    }
+    let screensaver = conn.with_proxy("org.freedesktop.ScreenSaver", "/org/freedesktop/ScreenSaver", timeout);
+    screensaver
+        .method_call::<(u32,), _, _, _>("org.freedesktop.ScreenSaver", "GetSessionIdleTime", ())
+        .ok()
+        .map(|(secs,)| u64::from(secs))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn idle_secs() -> Option<u64> {
+    None
+}
+
+/// Whether the user is away. Jobs that wait for idle never run where idle time cannot be
+/// read, so that counts as active.
+pub fn is_idle() -> bool {
+    STATE.lock().unwrap().idle
+}
+
+fn push(app: &AppHandle, idle: bool) {
+    if let Err(e) = app.state::<AppState>().control.push_config(serde_json::json!({ "user_idle": idle })) {
+        log::debug!("Idle state not pushed to the server: {}", e);
+    }
+}
+
+/// Polls idle time and reports idle/active transitions to the frontend and the server.
+pub fn setup(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        let idle_secs = idle_secs();
+        let threshold = app.state::<SettingsStore>().get().idle_threshold_minutes.max(1) * 60;
+        let idle = idle_secs.is_some_and(|secs| secs >= threshold);
+        let changed = {
+            let mut state = STATE.lock().unwrap();
+            let changed = state.idle != idle;
+            *state = IdleState { idle, idle_secs };
+            changed
+        };
+        if changed {
+            log::info!("User is {}", if idle { "idle" } else { "active again" });
+            push(&app, idle);
+            let _ = app.emit("user-idle-changed", IdleState { idle, idle_secs });
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+/// The idle state as a server spawned now should start with.
+pub fn server_env() -> &'static str {
+    if is_idle() {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+#[tauri::command]
+pub fn get_idle_state() -> IdleState {
+    *STATE.lock().unwrap()
+}
@@ -0,0 +1,248 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder};
+
+use crate::{bundle, debug_ports, disk, display, flags, governor, gpu, har, idle, license, politeness, server, AppState};
+
+/// Window label prefix for workspace windows (matched by the default capability).
+const WINDOW_PREFIX: &str = "workspace-";
+
+struct Instance {
+    name: String,
+    port: u16,
+    data_dir: PathBuf,
+    process: Child,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceInfo {
+    pub id: String,
+    pub name: String,
+    pub port: u16,
+    pub pid: Option<u32>,
+    pub data_dir: Option<PathBuf>,
+    pub window_label: String,
+    pub running: bool,
+}
+
+/// Additional server instances for parallel workspaces. Each one has its own port, data
+/// dir and window. The primary server stays with the supervisor and is listed as "main".
+#[derive(Default)]
+pub struct ServerManager {
+    instances: Mutex<HashMap<String, Instance>>,
+}
+
+impl ServerManager {
+    fn ports_in_use(&self, app: &AppHandle) -> Vec<u16> {
+        let mut ports: Vec<u16> = self.instances.lock().unwrap().values().map(|i| i.port).collect();
+        ports.push(*app.state::<AppState>().server_port.lock().unwrap());
+        ports
+    }
+
+    fn stop(&self, id: &str) -> Option<()> {
+        let mut instance = self.instances.lock().unwrap().remove(id)?;
+        log::info!("Stopping workspace '{}' (PID: {})...", instance.name, instance.process.id());
+        server::stop_process(&mut instance.process);
+        debug_ports::release(id);
+        Some(())
+    }
+
+    /// Port of the workspace server behind a window, `None` for windows of the main one.
+    pub fn port_for_window(&self, label: &str) -> Option<u16> {
+        let instances = self.instances.lock().unwrap();
+        instances.iter().find(|(id, _)| window_label(id) == label).map(|(_, instance)| instance.port)
+    }
+
+    pub fn stop_all(&self) {
+        let ids: Vec<String> = self.instances.lock().unwrap().keys().cloned().collect();
+        for id in ids {
+            self.stop(&id);
+        }
+    }
+}
+
+impl Drop for ServerManager {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+/// Turns a workspace name into an id usable in paths and window labels.
+fn workspace_id(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+fn window_label(id: &str) -> String {
+    format!("{}{}", WINDOW_PREFIX, id)
+}
+
+/// Starts a workspace server and opens its window. Preparing the bundle may copy it, and
+/// windows must not be built on the main thread, so this runs on a blocking thread.
+fn spawn(app: &AppHandle, name: &str) -> Result<InstanceInfo, String> {
+    let manager = app.state::<ServerManager>();
+    if !flags::enabled(app, "workspaces") {
+        return Err("Workspaces are not available in this version".to_string());
+    }
+    let id = workspace_id(name);
+    if id.is_empty() || id == "main" {
+        return Err(format!("Invalid workspace name: {:?}", name));
+    }
+    if manager.instances.lock().unwrap().contains_key(&id) {
+        return Err(format!("Workspace '{}' is already running", name));
+    }
+    // Every workspace runs its own browser, alongside the main one
+    let limit = governor::limit();
+    if manager.instances.lock().unwrap().len() + 1 >= limit {
+        return Err(format!(
+            "This computer is set to run at most {} browser{} at once. Close a workspace or raise the limit first.",
+            limit,
+            if limit == 1 { "" } else { "s" }
+        ));
+    }
+
+    let port = server::find_port_excluding(&manager.ports_in_use(app)).map_err(|e| e.to_string())?;
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join("workspaces")
+        .join(&id);
+    std::fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create workspace dir: {}", e))?;
+
+    let binary = bundle::prepare(app)?.binary;
+    let mut cmd = server::server_command(&binary, port, server::verbose_requested());
+    cmd.env("NOVA_DATA_DIR", &data_dir)
+        .env("NOVA_ENTITLEMENTS", license::server_env(app))
+        .env("NOVA_FEATURE_FLAGS", flags::server_env(app))
+        .env("NOVA_GPU", gpu::server_env())
+        .env("NOVA_BROWSER_BOUNDS", display::browser_bounds(app))
+        .env("NOVA_USER_IDLE", idle::server_env())
+        .env("NOVA_MAX_CONCURRENCY", governor::server_env())
+        .env("NOVA_DISK_CRITICAL", disk::server_env())
+        .env("NOVA_DOMAIN_LIMITS", politeness::server_env(app))
+        .env("NOVA_HAR_CAPTURE", har::server_env(app))
+        .env("NOVA_DEBUG_PORT", debug_ports::allocate(&id)?.to_string())
+        .current_dir(&data_dir);
+
+    log::info!("Starting workspace '{}' on port {}...", name, port);
+    let process = cmd.spawn().map_err(|e| {
+        debug_ports::release(&id);
+        format!("Failed to start server: {}", e)
+    })?;
+    let pid = process.id();
+
+    let label = window_label(&id);
+    let window = WebviewWindowBuilder::new(app, &label, WebviewUrl::App("index.html".into()))
+        .title(format!("Browser Automation - {}", name.trim()))
+        .inner_size(450.0, 600.0)
+        .build();
+    let window = match window {
+        Ok(window) => window,
+        Err(e) => {
+            let mut process = process;
+            server::stop_process(&mut process);
+            debug_ports::release(&id);
+            return Err(format!("Failed to open workspace window: {}", e));
+        }
+    };
+
+    let mut instances = manager.instances.lock().unwrap();
+    // Another call may have started the same workspace meanwhile
+    if instances.contains_key(&id) {
+        drop(instances);
+        let mut process = process;
+        server::stop_process(&mut process);
+        let _ = window.destroy();
+        return Err(format!("Workspace '{}' is already running", name));
+    }
+    instances.insert(id.clone(), Instance { name: name.trim().to_string(), port, data_dir: data_dir.clone(), process });
+    drop(instances);
+
+    // The window shows the bundled UI, which talks to this server through the proxy
+    std::thread::spawn(move || match server::wait_for_ready(port, 20) {
+        Some(attempt) => {
+            log::info!("Workspace server on port {} ready after {} attempts", port, attempt);
+            if let Err(e) = window.emit_to(window.label(), "server-ready", port) {
+                log::error!("Failed to tell workspace window the server is ready: {}", e);
+            }
+        }
+        None => log::warn!("Workspace server on port {} did not become ready within 10 seconds", port),
+    });
+
+    Ok(InstanceInfo {
+        id,
+        name: name.trim().to_string(),
+        port,
+        pid: Some(pid),
+        data_dir: Some(data_dir),
+        window_label: label,
+        running: true,
+    })
+}
+
+#[tauri::command]
+pub async fn spawn_instance(app: AppHandle, name: String) -> Result<InstanceInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || spawn(&app, &name))
+        .await
+        .map_err(|e| format!("Failed to start workspace: {}", e))?
+}
+
+#[tauri::command]
+pub fn list_instances(app: AppHandle, manager: State<'_, ServerManager>) -> Vec<InstanceInfo> {
+    let app_state = app.state::<AppState>();
+    let main_pid = app_state.python_process.lock().unwrap().as_ref().map(|child| child.id());
+    let mut list = vec![InstanceInfo {
+        id: "main".to_string(),
+        name: "Main".to_string(),
+        port: *app_state.server_port.lock().unwrap(),
+        pid: main_pid,
+        data_dir: None,
+        window_label: "main".to_string(),
+        running: main_pid.is_some(),
+    }];
+
+    let mut instances = manager.instances.lock().unwrap();
+    let mut workspaces: Vec<InstanceInfo> = instances
+        .iter_mut()
+        .map(|(id, instance)| InstanceInfo {
+            id: id.clone(),
+            name: instance.name.clone(),
+            port: instance.port,
+            pid: Some(instance.process.id()),
+            data_dir: Some(instance.data_dir.clone()),
+            window_label: window_label(id),
+            running: matches!(instance.process.try_wait(), Ok(None)),
+        })
+        .collect();
+    workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+    list.extend(workspaces);
+    list
+}
+
+#[tauri::command]
+pub fn stop_instance(app: AppHandle, manager: State<'_, ServerManager>, id: String) -> Result<(), String> {
+    if id == "main" {
+        return Err("The main server cannot be stopped as a workspace".to_string());
+    }
+    manager.stop(&id).ok_or_else(|| format!("No running workspace '{}'", id))?;
+    if let Some(window) = app.get_webview_window(&window_label(&id)) {
+        let _ = window.destroy();
+    }
+    Ok(())
+}
+
+/// Stops a workspace's server when its window goes away.
+pub fn on_window_destroyed(app: &AppHandle, label: &str) {
+    if let Some(id) = label.strip_prefix(WINDOW_PREFIX) {
+        app.state::<ServerManager>().stop(id);
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::events::{JobEvent, JobEventKind, JobStatus};
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::settings::SettingsStore;
+
+const MAX_ATTEMPTS: u32 = 4;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookKind {
+    Slack,
+    Discord,
+    Generic,
+}
+
+/// An outgoing webhook fired when a job finishes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub name: String,
+    pub kind: WebhookKind,
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Only fire for failed jobs.
+    #[serde(default)]
+    pub failures_only: bool,
+    /// Request body with `{{job_id}}`, `{{prompt}}`, `{{status}}`, `{{message}}` and
+    /// `{{duration_ms}}` placeholders. Values are JSON-escaped, so the template can be a
+    /// JSON document. Defaults to a message suited to the webhook kind.
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_template(kind: WebhookKind) -> &'static str {
+    match kind {
+        WebhookKind::Slack => r#"{"text": "Automation {{status}}: {{prompt}}\n{{message}}"}"#,
+        WebhookKind::Discord => r#"{"content": "Automation {{status}}: {{prompt}}\n{{message}}"}"#,
+        WebhookKind::Generic => {
+            r#"{"job_id": "{{job_id}}", "prompt": "{{prompt}}", "status": "{{status}}", "message": "{{message}}", "duration_ms": {{duration_ms}}}"#
+        }
+    }
+}
+
+/// Escapes a value for use inside a JSON string literal.
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+pub fn render_template(template: &str, job: &JobEvent) -> String {
+    let status = match job.status {
+        Some(JobStatus::Success) => "succeeded",
+        Some(JobStatus::Error) => "failed",
+        None => "finished",
+    };
+    template
+        .replace("{{job_id}}", &json_escape(&job.job_id))
+        .replace("{{prompt}}", &json_escape(&job.prompt))
+        .replace("{{status}}", status)
+        .replace("{{message}}", &json_escape(job.message.as_deref().unwrap_or("")))
+        .replace("{{duration_ms}}", &job.duration_ms.unwrap_or(0).to_string())
+}
+
+/// Sends a webhook, retrying with exponential backoff on network errors, 429 and 5xx.
+pub fn send(webhook: &WebhookConfig, job: &JobEvent) -> Result<(), String> {
+    let template = webhook.template.as_deref().unwrap_or_else(|| default_template(webhook.kind));
+    let body = render_template(template, job);
+
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = ureq::post(&webhook.url)
+            .timeout(Duration::from_secs(10))
+            .set("Content-Type", "application/json")
+            .send_string(&body);
+
+        let retryable = match result {
+            Ok(_) => {
+                log::info!("Webhook '{}' delivered", webhook.name);
+                return Ok(());
+            }
+            Err(ureq::Error::Status(code, _)) if code == 429 || code >= 500 => format!("HTTP {}", code),
+            Err(ureq::Error::Status(code, _)) => return Err(format!("Webhook '{}' rejected: HTTP {}", webhook.name, code)),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt == MAX_ATTEMPTS {
+            return Err(format!("Webhook '{}' failed after {} attempts: {}", webhook.name, attempt, retryable));
+        }
+        log::warn!("Webhook '{}' attempt {} failed ({}), retrying in {}s", webhook.name, attempt, retryable, backoff.as_secs());
+        std::thread::sleep(backoff);
+        backoff *= 2;
+    }
+    unreachable!()
+}
+
+/// Built-in plugin that fires the configured webhooks.
+struct WebhookPlugin;
+
+impl ShellPlugin for WebhookPlugin {
+    fn name(&self) -> &str {
+        "webhooks"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        let webhooks = ctx.app.state::<SettingsStore>().get().webhooks;
+        let failed = job.status == Some(JobStatus::Error);
+        for webhook in webhooks.iter().filter(|w| w.enabled && (failed || !w.failures_only)) {
+            if let Err(e) = send(webhook, job) {
+                log::error!("{}", e);
+            }
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(WebhookPlugin), "Sends configured webhooks when jobs finish");
+}
+
+/// Sends a sample job-finished event to a webhook so users can check their setup.
+#[tauri::command]
+pub async fn test_webhook(webhook: WebhookConfig) -> Result<(), String> {
+    let sample = JobEvent {
+        job_id: "test".to_string(),
+        kind: JobEventKind::Finished,
+        prompt: "Test notification".to_string(),
+        status: Some(JobStatus::Success),
+        message: Some("This is a test message from Browser Automation.".to_string()),
+        duration_ms: Some(0),
+        schedule_id: None,
+        recipe_id: None,
+        output: None,
+        step: None,
+        steps: None,
+    };
+    tauri::async_runtime::spawn_blocking(move || send(&webhook, &sample))
+        .await
+        .map_err(|e| format!("Webhook test failed: {}", e))?
+}
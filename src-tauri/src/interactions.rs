@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::history;
+use crate::notifications::{self, Category, Notification, Urgency};
+use crate::standby;
+
+/// How long the user has to answer when the server doesn't say.
+const DEFAULT_TIMEOUT_SECS: u64 = 5 * 60;
+const MAX_TIMEOUT_SECS: u64 = 30 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InteractionKind {
+    /// Solve a captcha in the automation browser, then confirm.
+    Captcha,
+    /// Approve a sign-in on another device (e.g. a 2FA push), then confirm.
+    Approval,
+    /// Pick one of `options`.
+    Choice,
+    /// Type an answer, e.g. a code sent by text message.
+    Text,
+}
+
+/// What the server sends with `interaction.request`.
+#[derive(Debug, Clone, Deserialize)]
+struct InteractionRequest {
+    job_id: String,
+    kind: InteractionKind,
+    message: String,
+    #[serde(default)]
+    options: Vec<String>,
+    timeout_secs: Option<u64>,
+}
+
+/// A job waiting for the user, sent to the UI as `interaction-requested`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Interaction {
+    pub id: String,
+    pub job_id: String,
+    pub kind: InteractionKind,
+    pub message: String,
+    pub options: Vec<String>,
+    pub requested_at: i64,
+    /// After this (ms) the job stops waiting.
+    pub deadline: i64,
+}
+
+/// Open interactions and where their answer goes; `None` declines.
+static PENDING: Mutex<Vec<(Interaction, Sender<Option<String>>)>> = Mutex::new(Vec::new());
+
+fn take(id: &str) -> Option<(Interaction, Sender<Option<String>>)> {
+    let mut pending = PENDING.lock().unwrap();
+    let at = pending.iter().position(|(interaction, _)| interaction.id == id)?;
+    Some(pending.remove(at))
+}
+
+fn closed(app: &AppHandle, id: &str) {
+    let _ = app.emit("interaction-closed", id);
+}
+
+/// Serves `interaction.request`: shows what the job needs, brings the window forward and
+/// blocks until the user answers, declines, or the deadline passes.
+pub fn request(app: &AppHandle, params: &Value) -> Result<Value, String> {
+    let request: InteractionRequest =
+        serde_json::from_value(params.clone()).map_err(|e| format!("Invalid interaction request: {}", e))?;
+    if request.kind == InteractionKind::Choice && request.options.is_empty() {
+        return Err("A choice needs options".to_string());
+    }
+    let timeout_secs = request.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).clamp(1, MAX_TIMEOUT_SECS);
+    let now = history::now_ms();
+    let interaction = Interaction {
+        id: uuid::Uuid::new_v4().to_string(),
+        job_id: request.job_id,
+        kind: request.kind,
+        message: request.message,
+        options: request.options,
+        requested_at: now,
+        deadline: now + timeout_secs as i64 * 1000,
+    };
+    let (tx, rx) = mpsc::channel();
+    PENDING.lock().unwrap().push((interaction.clone(), tx));
+    log::info!("Job {} is waiting for the user ({:?})", interaction.job_id, interaction.kind);
+
+    let _ = app.emit("interaction-requested", &interaction);
+    standby::show_main_window(app);
+    notifications::notify(
+        app,
+        Notification {
+            title: "An automation needs your input".to_string(),
+            body: interaction.message.clone(),
+            urgency: Urgency::Critical,
+            category: Category::Interactions,
+        },
+    );
+
+    let answer = rx.recv_timeout(Duration::from_secs(timeout_secs));
+    match answer {
+        Ok(Some(answer)) => Ok(serde_json::json!({ "answer": answer })),
+        Ok(None) => Err("The user declined".to_string()),
+        Err(_) => {
+            take(&interaction.id);
+            closed(app, &interaction.id);
+            log::info!("Nobody answered job {} within {} seconds", interaction.job_id, timeout_secs);
+            Err(format!("No answer within {} seconds", timeout_secs))
+        }
+    }
+}
+
+/// Interactions jobs are waiting on, oldest first.
+#[tauri::command]
+pub fn list_interactions() -> Vec<Interaction> {
+    PENDING.lock().unwrap().iter().map(|(interaction, _)| interaction.clone()).collect()
+}
+
+/// Answers an interaction and lets its job continue; `None` declines it. A choice must be
+/// one of its options.
+#[tauri::command]
+pub fn respond_to_interaction(app: AppHandle, id: String, answer: Option<String>) -> Result<(), String> {
+    {
+        let pending = PENDING.lock().unwrap();
+        let (interaction, _) =
+            pending.iter().find(|(interaction, _)| interaction.id == id).ok_or("The job is no longer waiting")?;
+        if let (InteractionKind::Choice, Some(answer)) = (interaction.kind, &answer) {
+            if !interaction.options.contains(answer) {
+                return Err(format!("'{}' is not one of the options", answer));
+            }
+        }
+    }
+    let (interaction, tx) = take(&id).ok_or("The job is no longer waiting")?;
+    log::info!("User {} job {}'s request", if answer.is_some() { "answered" } else { "declined" }, interaction.job_id);
+    let _ = tx.send(answer);
+    closed(&app, &id);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::events::{self, JobEvent, JobEventKind, JobStatus};
+use crate::settings::SettingsStore;
+use crate::{credentials, debugger, domain_policy, server, variables};
+
+/// Longest a shell-started job may run before it is reported as failed.
+const JOB_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+pub fn new_job_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Runs a prompt on the server at `port` and reports it like a job started from the UI.
+/// Blocks until the job finishes. `${name}` variables are filled in only for the server;
+/// history and events keep the placeholders, so secrets don't end up there. Secret variables
+/// don't reach the server with the job at all, only one-time tokens for them. Jobs the domain
+/// policy refuses finish with an error without reaching the server.
+pub fn run(
+    app: &AppHandle,
+    port: u16,
+    job_id: String,
+    prompt: &str,
+    schedule_id: Option<String>,
+    recipe_id: Option<String>,
+) {
+    let resolved = variables::resolve_for_job(app, prompt, &job_id)
+        .and_then(|resolved| domain_policy::check(app, &resolved.0).map(|()| resolved));
+    let (resolved_prompt, tokens) = match &resolved {
+        Ok((resolved, tokens)) => (resolved.as_str(), Some(tokens)),
+        Err(_) => (prompt, None),
+    };
+    // With breakpoints the server runs the recipe step by step; see `debugger`
+    let breakpoints = recipe_id.as_deref().map(|id| debugger::breakpoints(app, id)).unwrap_or_default();
+    let body = serde_json::json!({
+        "prompt": resolved_prompt,
+        "job_id": job_id,
+        "schedule_id": schedule_id,
+        "recipe_id": recipe_id,
+        "credentials": tokens,
+        "breakpoints": breakpoints,
+        "capture_failures": app.state::<SettingsStore>().get().capture_failures,
+    });
+    let mut event = JobEvent {
+        job_id,
+        kind: JobEventKind::Started,
+        prompt: prompt.to_string(),
+        status: None,
+        message: None,
+        duration_ms: None,
+        schedule_id,
+        recipe_id,
+        output: None,
+        step: None,
+        steps: None,
+    };
+    events::publish(app, event.clone());
+
+    let started = Instant::now();
+    if let Err(e) = resolved {
+        credentials::revoke_job(&event.job_id);
+        event.kind = JobEventKind::Finished;
+        event.status = Some(JobStatus::Error);
+        event.message = Some(e);
+        event.duration_ms = Some(0);
+        events::publish(app, event);
+        return;
+    }
+    let result = ureq::post(&format!("{}/api/execute_automation", server::server_url(port)))
+        .timeout(JOB_TIMEOUT)
+        .send_json(body);
+    let (status, message, output) = match result {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => {
+            let body: serde_json::Value = response.into_json().unwrap_or_default();
+            let status = if body["status"] == "success" { JobStatus::Success } else { JobStatus::Error };
+            let output = Some(body["output"].clone()).filter(|output| !output.is_null());
+            (status, body["message"].as_str().map(str::to_string), output)
+        }
+        Err(e) => (JobStatus::Error, Some(e.to_string()), None),
+    };
+    credentials::revoke_job(&event.job_id);
+
+    event.kind = JobEventKind::Finished;
+    event.status = Some(status);
+    event.message = message;
+    event.output = output;
+    event.duration_ms = Some(started.elapsed().as_millis() as u64);
+    events::publish(app, event);
+}
@@ -0,0 +1,86 @@
+use std::sync::Mutex;
+use tauri::plugin::{Builder as PluginBuilder, TauriPlugin};
+use tauri::webview::PageLoadEvent;
+use tauri::{AppHandle, Manager, Url, Webview, Wry};
+
+use crate::launch::{is_kiosk, LaunchOptions};
+
+/// Blocks the context menu (and with it "Inspect Element") and the developer tools
+/// shortcuts while `window.__kioskMode` is set. Installed once per page; re-running it
+/// only updates the flag.
+const LOCKDOWN_SCRIPT: &str = r#"
+(function (enabled) {
+    window.__kioskMode = enabled;
+    if (window.__kioskInstalled) return;
+    window.__kioskInstalled = true;
+    document.addEventListener('contextmenu', (e) => {
+        if (window.__kioskMode) e.preventDefault();
+    }, true);
+    document.addEventListener('keydown', (e) => {
+        if (!window.__kioskMode) return;
+        const key = e.key.toLowerCase();
+        const modifier = e.ctrlKey || e.metaKey;
+        if (key === 'f12' || (modifier && (e.shiftKey || e.altKey) && ['i', 'j', 'c'].includes(key))) {
+            e.preventDefault();
+            e.stopPropagation();
+        }
+    }, true);
+})"#;
+
+/// Pages the app serves itself: the bundled UI and the local automation server.
+fn is_local(url: &Url) -> bool {
+    match url.scheme() {
+        "tauri" | "asset" | "about" | "data" | "blob" => true,
+        "http" | "https" => matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "tauri.localhost")),
+        _ => false,
+    }
+}
+
+fn lockdown(webview: &Webview, enabled: bool) {
+    if let Err(e) = webview.eval(format!("{}({});", LOCKDOWN_SCRIPT, enabled)) {
+        log::warn!("Failed to update kiosk lockdown in {}: {}", webview.label(), e);
+    }
+}
+
+/// Keeps kiosk windows on the app's own pages and re-applies the lockdown after every load.
+pub fn plugin() -> TauriPlugin<Wry> {
+    PluginBuilder::new("kiosk")
+        .on_navigation(|webview, url| {
+            if !is_kiosk(webview.app_handle()) || is_local(url) {
+                return true;
+            }
+            log::warn!("Kiosk mode: blocked navigation to {}", url);
+            false
+        })
+        .on_page_load(|webview, payload| {
+            if payload.event() == PageLoadEvent::Finished && is_kiosk(webview.app_handle()) {
+                lockdown(webview, true);
+            }
+        })
+        .build()
+}
+
+/// Puts the main window into (or out of) kiosk presentation: fullscreen (which also hides
+/// the custom titlebar) and with developer tools locked away.
+pub fn apply(app: &AppHandle) {
+    let enabled = is_kiosk(app);
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if let Err(e) = window.set_fullscreen(enabled) {
+        log::error!("Failed to {} kiosk mode: {}", if enabled { "enter" } else { "leave" }, e);
+    }
+    #[cfg(debug_assertions)]
+    if enabled {
+        window.close_devtools();
+    }
+    lockdown(window.as_ref(), enabled);
+}
+
+/// Turns kiosk mode on or off for the running app. Launch with `--kiosk` to start in it.
+#[tauri::command]
+pub fn set_kiosk_mode(app: AppHandle, enabled: bool) {
+    app.state::<Mutex<LaunchOptions>>().lock().unwrap().kiosk = enabled;
+    log::info!("Kiosk mode {}", if enabled { "enabled" } else { "disabled" });
+    apply(&app);
+}
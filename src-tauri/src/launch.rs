@@ -0,0 +1,79 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::settings::SettingsStore;
+use crate::{control_api, standby};
+
+/// Startup options taken from the command line.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LaunchOptions {
+    /// Skip optional subsystems (schedules, plugins, tray, custom config) and start the
+    /// server with conservative defaults so users can recover from a bad configuration.
+    pub safe_mode: bool,
+    /// Launched at login to pre-warm the server: start hidden in the tray.
+    pub standby: bool,
+    /// Presentation mode for wall displays: fullscreen, no window chrome or devtools,
+    /// no navigation away from the app, and the server is always restarted after a crash.
+    pub kiosk: bool,
+    /// Read-only mode for shared machines: runs and recipes can be reviewed, but nothing
+    /// runs jobs, changes settings or reads secrets; see `access::role`.
+    pub viewer: bool,
+    /// Task files to open, e.g. double-clicked `.nova` files, jump list entries or the
+    /// command line.
+    pub files: Vec<PathBuf>,
+    /// Undo the app's OS registrations and exit; see `uninstall::run`.
+    pub uninstall_cleanup: bool,
+    /// With `uninstall_cleanup`, delete the user's data as well.
+    pub remove_data: bool,
+}
+
+impl LaunchOptions {
+    pub fn from_args() -> Self {
+        let mut options = LaunchOptions::default();
+        for arg in std::env::args().skip(1) {
+            match arg.as_str() {
+                "--safe-mode" => options.safe_mode = true,
+                "--standby" => options.standby = true,
+                "--kiosk" => options.kiosk = true,
+                "--viewer" => options.viewer = true,
+                "--uninstall-cleanup" => options.uninstall_cleanup = true,
+                "--remove-data" => options.remove_data = true,
+                // Made absolute so they still resolve if handed to an already running app
+                _ if !arg.starts_with('-') => options.files.push(
+                    std::env::current_dir().map(|dir| dir.join(&arg)).unwrap_or_else(|_| PathBuf::from(&arg)),
+                ),
+                _ => {}
+            }
+        }
+        options
+    }
+}
+
+pub fn is_safe_mode(app: &AppHandle) -> bool {
+    app.state::<Mutex<LaunchOptions>>().lock().unwrap().safe_mode
+}
+
+pub fn is_kiosk(app: &AppHandle) -> bool {
+    app.state::<Mutex<LaunchOptions>>().lock().unwrap().kiosk
+}
+
+pub fn is_viewer(app: &AppHandle) -> bool {
+    app.state::<Mutex<LaunchOptions>>().lock().unwrap().viewer
+}
+
+/// Switches the running app into safe mode, e.g. after the user accepted the crash prompt.
+pub fn enable_safe_mode(app: &AppHandle) {
+    app.state::<Mutex<LaunchOptions>>().lock().unwrap().safe_mode = true;
+    if let Some(store) = app.try_state::<SettingsStore>() {
+        store.enter_safe_mode();
+    }
+    standby::apply_settings(app);
+    control_api::apply_settings(app);
+}
+
+#[tauri::command]
+pub fn get_launch_options(options: State<'_, Mutex<LaunchOptions>>) -> LaunchOptions {
+    options.lock().unwrap().clone()
+}
@@ -1,34 +1,130 @@
-use std::process::{Command, Child};
+mod access;
+mod alerts;
+mod analytics;
+mod antivirus;
+mod archive;
+mod artifact_protocol;
+mod backups;
+mod batches;
+mod blackouts;
+mod blobs;
+mod bundle;
+mod calendar;
+mod captcha;
+mod change_monitor;
+mod control_channel;
+mod cookie_jars;
+mod control_api;
+mod credentials;
+mod crypto;
+mod databases;
+mod debug_ports;
+mod debugger;
+mod diagnostics;
+mod digest;
+mod disk;
+mod display;
+mod domain_policy;
+mod downloads;
+mod email;
+mod events;
+mod export;
+mod failures;
+mod flags;
+mod gatekeeper;
+mod git_sync;
+mod governor;
+mod gpu;
+mod har;
+mod history;
+mod html_export;
+mod idle;
+mod instances;
+mod interactions;
+mod jobs;
+mod kiosk;
+mod integrations;
+mod launch;
+mod license;
+mod live_view;
+mod migrations;
+mod monitor;
+mod normalize;
+mod notifications;
+mod ocr;
+mod packaging;
+mod pipelines;
+mod plugins;
+mod politeness;
+mod power;
+mod print;
+mod profile;
+mod proxy;
+mod purge;
+mod queue;
+mod recent;
+mod recipe_bundles;
+mod recipe_validation;
+mod recipes;
+mod recorder;
+mod recordings;
+mod redaction;
+mod reports;
+mod recovery;
+mod run_diff;
+mod schedules;
+mod search;
+mod security;
+mod secrets;
+mod selector_picker;
+mod server;
+mod session;
+mod settings;
+mod sheets;
+mod single_instance;
+mod sqlite_export;
+mod standby;
+mod supervisor;
+mod taskbar;
+mod tasks;
+mod thumbnails;
+mod timetable;
+mod titlebar;
+mod totp;
+mod traces;
+mod transforms;
+mod uninstall;
+mod uploads;
+mod variables;
+mod webview_data;
+mod zoom;
+
+use launch::LaunchOptions;
+use settings::SettingsStore;
+use std::process::Child;
 use std::sync::{Arc, Mutex};
-use std::net::TcpListener;
+use std::time::Duration;
 use tauri::Manager;
 
 struct AppState {
     python_process: Arc<Mutex<Option<Child>>>,
     server_port: Arc<Mutex<u16>>,
+    control: control_channel::ControlChannel,
 }
 
 impl AppState {
     fn cleanup_server(&self) {
         let mut lock = self.python_process.lock().unwrap();
         if let Some(mut process) = lock.take() {
-            let pid = process.id();
-            log::info!("Cleanup: Stopping server (PID: {})...", pid);
-
-            // Kill child processes first
-            #[cfg(unix)]
-            {
-                let _ = std::process::Command::new("pkill")
-                    .arg("-P")
-                    .arg(pid.to_string())
-                    .output();
-                std::thread::sleep(std::time::Duration::from_millis(200));
+            log::info!("Cleanup: Stopping server (PID: {})...", process.id());
+            // Let the server close its browser cleanly before resorting to kill
+            if self.control.request_shutdown() && server::wait_for_exit(&mut process, Duration::from_secs(3)) {
+                log::info!("Cleanup: Server exited gracefully");
+            } else {
+                server::stop_process(&mut process);
+                log::info!("Cleanup: Server stopped");
             }
-
-            // Kill main process
-            let _ = process.kill();
-            let _ = process.wait();
-            log::info!("Cleanup: Server stopped");
+            self.control.disconnect();
         }
     }
 }
@@ -40,41 +136,24 @@ impl Drop for AppState {
     }
 }
 
-fn find_available_port() -> Result<u16, std::io::Error> {
-    // Try to find an available port in the range 5555-5655 (100 ports)
-    // This keeps ports predictable and avoids conflicts with other services
-    const START_PORT: u16 = 5555;
-    const END_PORT: u16 = 5655;
-
-    for port in START_PORT..=END_PORT {
-        match TcpListener::bind(("127.0.0.1", port)) {
-            Ok(listener) => {
-                // Port is available, drop the listener to free it
-                drop(listener);
-                log::info!("Found available port: {}", port);
-                return Ok(port);
-            }
-            Err(_) => {
-                // Port is in use, try next one
-                continue;
-            }
-        }
-    }
-
-    // If all ports in range are taken, return error
-    Err(std::io::Error::new(
-        std::io::ErrorKind::AddrInUse,
-        format!("No available ports found in range {}-{}", START_PORT, END_PORT)
-    ))
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   let app_state = AppState {
       python_process: Arc::new(Mutex::new(None)),
       server_port: Arc::new(Mutex::new(5555)), // Default, will be updated if we spawn server
+      control: control_channel::ControlChannel::default(),
   };
 
+  let launch_options = LaunchOptions::from_args();
+  if launch_options.uninstall_cleanup {
+      std::process::exit(uninstall::run(launch_options.remove_data));
+  }
+  // A second launch (e.g. double-clicking a task file) goes to the running app instead
+  if single_instance::forward(&launch_options.files, !launch_options.standby) {
+      return;
+  }
+  let primary_instance = single_instance::bind();
+
   // Register signal handlers for cleanup
   let cleanup_state = app_state.python_process.clone();
   ctrlc::set_handler(move || {
@@ -88,7 +167,212 @@ pub fn run() {
   }).expect("Error setting Ctrl-C handler");
 
   tauri::Builder::default()
+    .plugin(tauri_plugin_autostart::init(
+        tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+        Some(vec!["--standby"]),
+    ))
+    .plugin(tauri_plugin_dialog::init())
+    .plugin(tauri_plugin_opener::init())
+    .plugin(kiosk::plugin())
+    .plugin(security::plugin())
+    .plugin(titlebar::plugin())
+    .register_asynchronous_uri_scheme_protocol(artifact_protocol::SCHEME, artifact_protocol::handle)
     .manage(app_state)
+    .manage(supervisor::Supervisor::default())
+    .manage(instances::ServerManager::default())
+    .manage(plugins::PluginRegistry::default())
+    .manage(control_api::ControlApi::default())
+    .manage(license::License::default())
+    .manage(downloads::Downloads::default())
+    .manage(Mutex::new(launch_options))
+    .invoke_handler(access::guard(tauri::generate_handler![
+      access::get_role,
+      access::list_access_audit,
+      alerts::list_alerts,
+      alerts::test_assertion,
+      analytics::get_recipe_analytics,
+      archive::archive_run,
+      backups::create_backup,
+      backups::restore_backup,
+      batches::cancel_batch,
+      batches::enqueue_batch,
+      batches::get_batch_status,
+      batches::list_batch_rows,
+      blackouts::validate_blackout_window,
+      blobs::gc_artifacts,
+      bundle::get_server_layout,
+      calendar::export_schedules_ics,
+      captcha::get_captcha_usage,
+      captcha::list_captcha_solves,
+      captcha::set_captcha_api_key,
+      change_monitor::list_monitor_changes,
+      control_channel::set_server_log_level,
+      control_channel::set_server_verbose,
+      control_api::get_control_api_token,
+      control_api::reset_control_api_token,
+      cookie_jars::apply_cookie_jar,
+      cookie_jars::capture_cookie_jar,
+      cookie_jars::delete_cookie_jar,
+      cookie_jars::export_cookies,
+      cookie_jars::import_cookies,
+      cookie_jars::list_browser_profiles,
+      cookie_jars::list_cookie_jars,
+      credentials::list_credential_access,
+      databases::export_run_to_database,
+      databases::set_database_credentials,
+      databases::test_connection,
+      debug_ports::get_debug_targets,
+      debug_ports::open_in_devtools,
+      debugger::list_breakpoints,
+      debugger::pause_job,
+      debugger::resume_job,
+      debugger::set_breakpoint,
+      debugger::step_job,
+      diagnostics::get_server_environment,
+      diagnostics::run_self_test,
+      digest::preview_digest,
+      digest::send_digest,
+      disk::get_disk_space,
+      disk::purge_artifacts,
+      display::capture_screenshot,
+      display::get_display_capabilities,
+      display::list_displays,
+      display::move_window_to_display,
+      domain_policy::list_domain_overrides,
+      domain_policy::list_policy_audit,
+      domain_policy::override_domain_policy,
+      domain_policy::revoke_domain_override,
+      downloads::cancel_download,
+      downloads::list_downloads,
+      downloads::open_containing_folder,
+      downloads::pause_download,
+      downloads::resume_download,
+      downloads::start_download,
+      governor::get_job_concurrency,
+      governor::set_job_concurrency,
+      git_sync::get_sync_status,
+      git_sync::set_sync_token,
+      git_sync::sync_now,
+      gpu::get_gpu_info,
+      har::export_har,
+      har::list_har_captures,
+      idle::get_idle_state,
+      notifications::get_dnd_state,
+      email::send_report,
+      email::set_smtp_password,
+      events::report_job_event,
+      export::export_run,
+      failures::get_failure_context,
+      flags::get_feature_flags,
+      flags::is_enabled,
+      history::list_runs,
+      html_export::export_run_html,
+      instances::list_instances,
+      instances::spawn_instance,
+      instances::stop_instance,
+      integrations::test_webhook,
+      interactions::list_interactions,
+      interactions::respond_to_interaction,
+      kiosk::set_kiosk_mode,
+      launch::get_launch_options,
+      license::activate,
+      license::deactivate,
+      license::get_license_status,
+      live_view::open_live_view,
+      live_view::send_live_view_input,
+      live_view::set_live_view_control,
+      live_view::start_live_view,
+      monitor::toggle_monitor_window,
+      normalize::preview_normalization,
+      ocr::ocr_artifact,
+      pipelines::delete_pipeline,
+      pipelines::get_pipeline_status,
+      pipelines::save_pipeline,
+      pipelines::start_pipeline,
+      plugins::list_plugins,
+      plugins::run_plugin_command,
+      politeness::get_domain_stats,
+      power::get_power_state,
+      print::export_page_pdf,
+      print::print_current_page,
+      profile::export_profile,
+      profile::import_profile,
+      proxy::api_request,
+      purge::purge_all_user_data,
+      purge::request_purge_token,
+      queue::cancel_queued_job,
+      queue::enqueue_job,
+      queue::list_queue,
+      queue::pause_queue,
+      queue::reorder_queue,
+      queue::resume_queue,
+      recent::add_recent_task,
+      recent::clear_recent_tasks,
+      recent::list_recent_tasks,
+      recipe_bundles::install_recipe_from_url,
+      recipe_validation::get_recipe_diagnostics,
+      recipe_validation::validate_recipe,
+      recipes::delete_recipe,
+      recipes::duplicate_recipe,
+      recipes::get_recipe,
+      recipes::list_recipe_revisions,
+      recipes::list_recipes,
+      recipes::rename_recipe,
+      recipes::restore_recipe_revision,
+      recipes::save_recipe,
+      recorder::get_recording_status,
+      recorder::pause_recording,
+      recorder::start_recording,
+      recorder::stop_recording,
+      recordings::delete_recordings_older_than,
+      recordings::list_recordings,
+      redaction::redact_regions,
+      reports::generate_run_report,
+      run_diff::diff_runs,
+      schedules::get_upcoming_runs,
+      schedules::list_schedule_audit,
+      search::search_runs,
+      security::authenticate_user,
+      selector_picker::close_selector_picker,
+      selector_picker::open_selector_picker,
+      session::restore_session,
+      session::save_session,
+      settings::get_settings,
+      settings::update_settings,
+      sheets::append_run_to_sheet,
+      sheets::connect_google_account,
+      sheets::disconnect_google_account,
+      sheets::google_account_status,
+      sheets::set_google_oauth_client,
+      sqlite_export::export_run_to_sqlite,
+      sqlite_export::preview_sqlite_export,
+      supervisor::get_server_status,
+      supervisor::request_server_restart,
+      taskbar::set_badge,
+      taskbar::set_progress,
+      tasks::run_task_file,
+      thumbnails::get_thumbnail,
+      timetable::simulate_schedule,
+      titlebar::close_window,
+      titlebar::minimize_window,
+      titlebar::toggle_maximize_window,
+      totp::list_totp_seeds,
+      totp::set_totp_seed,
+      traces::get_run_trace,
+      transforms::preview_transform,
+      uploads::set_upload_credentials,
+      uploads::upload_artifacts,
+      variables::missing_variables,
+      variables::set_secret_variable,
+      variables::template_variables,
+      webview_data::clear_cookies,
+      webview_data::clear_webview_cache,
+      webview_data::get_webview_storage_usage,
+      zoom::reset_zoom,
+      zoom::set_zoom,
+      zoom::zoom_in,
+      zoom::zoom_out,
+    ]))
     .setup(|app| {
       // Enable logging for both debug and release modes
       // In release mode, logs are saved to:
@@ -112,114 +396,74 @@ pub fn run() {
 
       log::info!("Application starting...");
       log::info!("Version: {}", env!("CARGO_PKG_VERSION"));
+      log::info!("Packaging: {:?}", packaging::detect());
+      log::info!("Display server: {:?}", display::display_server());
+
+      let launch_options = app.state::<Mutex<LaunchOptions>>().lock().unwrap().clone();
+      if launch_options.safe_mode {
+          log::warn!("Safe mode: schedules, plugins, tray and custom config are disabled");
+      }
+      if launch_options.kiosk {
+          log::info!("Kiosk mode: starting fullscreen");
+          kiosk::apply(app.handle());
+      }
+      if launch_options.viewer {
+          log::info!("Viewer mode: jobs, settings changes and secrets are disabled");
+      }
+      if let Err(e) = migrations::run(app.handle()) {
+          log::error!("{}", e);
+      }
+      app.manage(SettingsStore::load(app.handle(), launch_options.safe_mode));
+      app.manage(history::History::open(app.handle()));
+      app.manage(zoom::ZoomLevels::load(app.handle()));
+      license::setup(app.handle());
+      app.manage(flags::FeatureFlags::load(app.handle()));
+      flags::setup(app.handle());
+      idle::setup(app.handle());
+      notifications::setup(app.handle());
+      settings::report_load_error(app.handle());
+      digest::setup(app.handle());
+      disk::setup(app.handle());
+      backups::setup(app.handle());
+      search::setup(app.handle());
+      git_sync::setup(app.handle());
+      integrations::register(app.handle());
+      email::register(app.handle());
+      uploads::register(app.handle());
+      sqlite_export::register(app.handle());
+      databases::register(app.handle());
+      sheets::register(app.handle());
+      schedules::register(app.handle());
+      pipelines::register(app.handle());
+      batches::register(app.handle());
+      alerts::register(app.handle());
+      change_monitor::register(app.handle());
+      plugins::load(app.handle(), launch_options.safe_mode);
+      standby::setup(app.handle(), launch_options.standby);
+      schedules::setup(app.handle(), launch_options.safe_mode || launch_options.viewer);
+      queue::setup(app.handle());
+      if !launch_options.viewer {
+          batches::setup(app.handle());
+      }
+      control_api::apply_settings(app.handle());
+      if let Some(primary) = primary_instance {
+          single_instance::listen(app.handle(), primary);
+      }
+      tasks::open_all(app.handle(), launch_options.files.clone());
 
       // Spawn Python Flask server (production mode only)
       if !cfg!(debug_assertions) {
-          log::info!("Starting Python Flask server...");
-
-          // Find an available port
-          let port = match find_available_port() {
-              Ok(p) => {
-                  log::info!("Found available port: {}", p);
-                  p
-              },
-              Err(e) => {
-                  log::error!("Failed to find available port: {}", e);
-                  return Err(Box::new(e).into());
-              }
-          };
-
-          // Store the port in app state
-          *app.state::<AppState>().server_port.lock().unwrap() = port;
-
-          // Production mode - Python and dependencies are bundled with the app
-          let resource_dir = match app.path().resource_dir() {
-              Ok(dir) => dir,
-              Err(e) => {
-                  log::error!("Failed to get resource dir: {}", e);
-                  return Err(Box::new(e).into());
-              }
-          };
-
-          // Resources specified in tauri.conf.json with ../ paths are placed in _up_ subdirectory
-          let resource_base = resource_dir.join("_up_");
-          let server_binary = resource_base.join("bundle-bin").join("server");
-
-          log::info!("Resource dir: {:?}", resource_dir);
-          log::info!("Server binary: {:?}", server_binary);
-
-          // Check if binary exists before trying to spawn
-          if !server_binary.exists() {
-              log::error!("Server binary not found at: {:?}", server_binary);
-              return Err("Server binary not found in bundle".into());
-          }
-
-          log::info!("Server binary found, starting server on port {}...", port);
-
-          // Check for VERBOSE environment variable to pass to server
-          let verbose_flag = std::env::var("VERBOSE")
-              .map(|v| v.to_lowercase() == "true" || v == "1")
-              .unwrap_or(false);
-
-          // Start server binary in its own process group so we can kill it and all children
-          let mut cmd = Command::new(&server_binary);
-
-          // On Unix, create a new process group for the server
-          #[cfg(unix)]
-          {
-              use std::os::unix::process::CommandExt;
-              cmd.process_group(0);
-          }
-
-          // Add port argument
-          cmd.arg("--port").arg(port.to_string());
-
-          // Add verbose flag if set
-          if verbose_flag {
-              log::info!("VERBOSE mode enabled - passing --verbose to server");
-              cmd.arg("--verbose");
-          }
-
-          log::info!("Spawning server process...");
-          let server_child = match cmd.spawn() {
-                  Ok(child) => {
-                      log::info!("Server process started successfully (PID: {})", child.id());
-                      child
-                  },
-                  Err(e) => {
-                      log::error!("Failed to start server: {}", e);
-                      return Err(Box::new(e).into());
-                  }
-              };
-
-          // Store the process handle
-          *app.state::<AppState>().python_process.lock().unwrap() = Some(server_child);
-
-          log::info!("Flask server starting on port {}...", port);
-
-          // Wait for server to be ready, then navigate the window to it
-          let window = app.get_webview_window("main").expect("Failed to get main window");
-          let server_url = format!("http://127.0.0.1:{}", port);
+          let offer_safe_mode = !launch_options.safe_mode && recovery::should_offer_safe_mode(app.handle());
+          // Start on a worker thread: the safe mode prompt and first-run bundle
+          // extraction must not block the main thread
+          let app_handle = app.handle().clone();
           std::thread::spawn(move || {
-              // Wait for server to start (up to 10 seconds)
-              for attempt in 1..=20 {
-                  std::thread::sleep(std::time::Duration::from_millis(500));
-
-                  // Check if server is responding
-                  let check_url = server_url.clone();
-                  if let Ok(response) = ureq::get(&check_url).timeout(std::time::Duration::from_millis(500)).call() {
-                      if response.status() == 200 {
-                          log::info!("Flask server is ready after {} attempts", attempt);
-                          // Navigate to the Flask server
-                          let nav_script = format!("window.location.href = '{}'", server_url);
-                          if let Err(e) = window.eval(&nav_script) {
-                              log::error!("Failed to navigate window: {}", e);
-                          }
-                          return;
-                      }
-                  }
+              let safe_mode = launch_options.safe_mode
+                  || (offer_safe_mode && recovery::prompt_safe_mode(&app_handle));
+              if safe_mode && !launch_options.safe_mode {
+                  launch::enable_safe_mode(&app_handle);
               }
-              log::warn!("Flask server did not become ready within 10 seconds");
+              supervisor::launch(&app_handle, safe_mode);
           });
       } else {
           log::info!("Development mode: Flask server should be started manually with 'npm run server'");
@@ -228,22 +472,48 @@ pub fn run() {
       log::info!("Setup complete!");
       Ok(())
     })
+    .on_page_load(|webview, payload| {
+      if payload.event() == tauri::webview::PageLoadEvent::Finished {
+          zoom::restore(webview);
+      }
+    })
     .on_window_event(|window, event| {
       // Clean up server process when window closes
       match event {
+          // Workspace windows stop their own server once destroyed
+          tauri::WindowEvent::CloseRequested { .. } if window.label() != "main" => {}
+          tauri::WindowEvent::CloseRequested { api, .. } if standby::should_hide_on_close(window.app_handle()) => {
+              // Warm standby: keep the server running and just hide the window
+              api.prevent_close();
+              let _ = window.hide();
+          }
           tauri::WindowEvent::CloseRequested { .. } => {
               log::info!("Window close requested - cleaning up server...");
+              session::save_before_stop(window.app_handle());
               let app_state: tauri::State<AppState> = window.state();
               app_state.cleanup_server();
               // Explicitly close the window to complete the close operation
               let _ = window.close();
           }
+          tauri::WindowEvent::Focused(true) => {
+              standby::mark_active(window.app_handle());
+          }
           tauri::WindowEvent::Destroyed => {
               log::info!("Window destroyed");
+              instances::on_window_destroyed(window.app_handle(), window.label());
           }
           _ => {}
       }
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app, event| match event {
+      tauri::RunEvent::Exit => plugins::shutdown(app),
+      // Task files opened from Finder or the Dock's recent items
+      #[cfg(target_os = "macos")]
+      tauri::RunEvent::Opened { urls } => {
+          tasks::open_all(app, urls.iter().filter_map(|url| url.to_file_path().ok()).collect());
+      }
+      _ => {}
+    });
 }
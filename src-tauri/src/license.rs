@@ -0,0 +1,337 @@
+use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD};
+use base64::Engine;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{control_api, history, secrets, AppState};
+
+/// Keychain entry holding the signed activation token.
+pub const TOKEN_KEY: &str = "license-token";
+/// Keychain entry holding this machine's activation id.
+pub const MACHINE_KEY: &str = "machine-id";
+
+/// Ed25519 key (base64) the license server signs tokens with. Builds without one do not
+/// use licensing and have every feature.
+const PUBLIC_KEY: Option<&str> = option_env!("NOVA_LICENSE_PUBLIC_KEY");
+const SERVER_URL: Option<&str> = option_env!("NOVA_LICENSE_SERVER");
+
+/// Tokens are re-issued online once they are this old; until they expire they keep
+/// working offline.
+const REFRESH_AFTER_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Premium features a license unlocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Entitlements {
+    /// Automations run on a timer by the shell.
+    pub scheduling: bool,
+    /// Serving the UI to other machines on the local network.
+    pub lan_mode: bool,
+}
+
+impl Entitlements {
+    fn all() -> Self {
+        Entitlements { scheduling: true, lan_mode: true }
+    }
+}
+
+/// Signed contents of an activation token.
+#[derive(Debug, Deserialize)]
+struct Claims {
+    license_id: String,
+    machine_id: String,
+    #[serde(default)]
+    licensee: Option<String>,
+    entitlements: Entitlements,
+    /// Unix milliseconds.
+    issued_at: i64,
+    /// Unix milliseconds; the token stops working offline after this.
+    expires_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseState {
+    /// This build does not use licensing.
+    Unmanaged,
+    Unlicensed,
+    Active,
+    /// Not refreshed online before the token expired.
+    Expired,
+    Invalid,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseStatus {
+    pub state: LicenseState,
+    pub entitlements: Entitlements,
+    pub machine_id: Option<String>,
+    pub license_id: Option<String>,
+    pub licensee: Option<String>,
+    /// Unix milliseconds.
+    pub refreshed_at: Option<i64>,
+    /// Unix milliseconds.
+    pub expires_at: Option<i64>,
+    pub message: Option<String>,
+}
+
+impl LicenseStatus {
+    fn new(state: LicenseState, machine_id: Option<String>) -> Self {
+        LicenseStatus {
+            state,
+            entitlements: if state == LicenseState::Unmanaged { Entitlements::all() } else { Entitlements::default() },
+            machine_id,
+            license_id: None,
+            licensee: None,
+            refreshed_at: None,
+            expires_at: None,
+            message: None,
+        }
+    }
+
+    fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// The license as last validated.
+pub struct License {
+    status: Mutex<LicenseStatus>,
+}
+
+impl Default for License {
+    fn default() -> Self {
+        License { status: Mutex::new(LicenseStatus::new(LicenseState::Unlicensed, None)) }
+    }
+}
+
+/// Stable id this installation activates as, created on first use.
+fn machine_id() -> Result<String, String> {
+    if let Some(id) = secrets::get(MACHINE_KEY)? {
+        return Ok(id);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    secrets::set(MACHINE_KEY, &id)?;
+    Ok(id)
+}
+
+/// Checks a `<claims>.<signature>` token (both base64url) against the baked-in key.
+fn verify(token: &str, public_key: &str, machine_id: &str) -> Result<Claims, String> {
+    let public_key = BASE64.decode(public_key).map_err(|_| "License public key is malformed")?;
+    let (claims, signature) = token.trim().split_once('.').ok_or("Activation token is malformed")?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| "Activation token is malformed")?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(claims.as_bytes(), &signature)
+        .map_err(|_| "Activation token signature is invalid")?;
+    let claims = URL_SAFE_NO_PAD.decode(claims).map_err(|_| "Activation token is malformed")?;
+    let claims: Claims = serde_json::from_slice(&claims).map_err(|e| format!("Activation token is malformed: {}", e))?;
+    if claims.machine_id != machine_id {
+        return Err("Activation token belongs to another machine".to_string());
+    }
+    Ok(claims)
+}
+
+/// Validates the stored token offline.
+fn evaluate() -> LicenseStatus {
+    let Some(public_key) = PUBLIC_KEY else {
+        return LicenseStatus::new(LicenseState::Unmanaged, None);
+    };
+    let machine_id = match machine_id() {
+        Ok(id) => id,
+        Err(e) => return LicenseStatus::new(LicenseState::Invalid, None).with_message(e),
+    };
+    let token = match secrets::get(TOKEN_KEY) {
+        Ok(Some(token)) => token,
+        Ok(None) => return LicenseStatus::new(LicenseState::Unlicensed, Some(machine_id)),
+        Err(e) => return LicenseStatus::new(LicenseState::Invalid, Some(machine_id)).with_message(e),
+    };
+    let claims = match verify(&token, public_key, &machine_id) {
+        Ok(claims) => claims,
+        Err(e) => return LicenseStatus::new(LicenseState::Invalid, Some(machine_id)).with_message(e),
+    };
+
+    let expired = history::now_ms() >= claims.expires_at;
+    let mut status = LicenseStatus::new(
+        if expired { LicenseState::Expired } else { LicenseState::Active },
+        Some(machine_id),
+    );
+    if !expired {
+        status.entitlements = claims.entitlements;
+    }
+    status.license_id = Some(claims.license_id);
+    status.licensee = claims.licensee;
+    status.refreshed_at = Some(claims.issued_at);
+    status.expires_at = Some(claims.expires_at);
+    status
+}
+
+/// Publishes a new status to the frontend and the running server.
+fn set_status(app: &AppHandle, status: LicenseStatus) {
+    let license = app.state::<License>();
+    let changed = {
+        let mut current = license.status.lock().unwrap();
+        let changed = current.state != status.state || current.entitlements != status.entitlements;
+        *current = status.clone();
+        changed
+    };
+    if changed {
+        log::info!("License {:?}, entitlements {:?}", status.state, status.entitlements);
+        if let Err(e) = app
+            .state::<AppState>()
+            .control
+            .push_config(serde_json::json!({ "entitlements": status.entitlements }))
+        {
+            log::debug!("Entitlements not pushed to the server: {}", e);
+        }
+        // Losing LAN mode closes the control API to other machines
+        control_api::apply_settings(app);
+    }
+    let _ = app.emit("license-changed", status);
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenReply {
+    /// Empty for calls that do not issue a token.
+    #[serde(default)]
+    token: String,
+}
+
+/// Error from the license server, split so a rejection can be told from being offline.
+enum RequestError {
+    Rejected(String),
+    Unreachable(String),
+}
+
+fn request(endpoint: &str, body: serde_json::Value) -> Result<String, RequestError> {
+    let server = SERVER_URL.ok_or_else(|| RequestError::Unreachable("No license server configured".to_string()))?;
+    let url = format!("{}/v1/{}", server.trim_end_matches('/'), endpoint);
+    match ureq::post(&url).timeout(REQUEST_TIMEOUT).send_json(body) {
+        Ok(response) => response
+            .into_json::<TokenReply>()
+            .map(|reply| reply.token)
+            .map_err(|e| RequestError::Unreachable(format!("Invalid reply from the license server: {}", e))),
+        Err(ureq::Error::Status(code, response)) if code < 500 => {
+            let reason = response.into_string().unwrap_or_default();
+            Err(RequestError::Rejected(if reason.trim().is_empty() { format!("HTTP {}", code) } else { reason }))
+        }
+        Err(e) => Err(RequestError::Unreachable(format!("License server unreachable: {}", e))),
+    }
+}
+
+/// Swaps the stored token for a fresh one. A revoked license is removed; being offline
+/// leaves the current token in place until it expires.
+fn refresh(app: &AppHandle) {
+    let Ok(Some(token)) = secrets::get(TOKEN_KEY) else {
+        return;
+    };
+    let Ok(machine_id) = machine_id() else {
+        return;
+    };
+    match request("refresh", serde_json::json!({ "token": token, "machine_id": machine_id })) {
+        Ok(token) => match secrets::set(TOKEN_KEY, &token) {
+            Ok(()) => log::info!("License refreshed"),
+            Err(e) => log::error!("Failed to store refreshed license: {}", e),
+        },
+        Err(RequestError::Rejected(reason)) => {
+            log::warn!("License was revoked: {}", reason);
+            if let Err(e) = secrets::delete(TOKEN_KEY) {
+                log::error!("{}", e);
+            }
+            set_status(app, evaluate().with_message(format!("License was revoked: {}", reason)));
+            return;
+        }
+        Err(RequestError::Unreachable(e)) => log::warn!("License refresh postponed: {}", e),
+    }
+    set_status(app, evaluate());
+}
+
+fn refresh_due(app: &AppHandle) -> bool {
+    let status = app.state::<License>().status.lock().unwrap().clone();
+    matches!(status.state, LicenseState::Active | LicenseState::Expired)
+        && status.refreshed_at.is_some_and(|at| history::now_ms() - at >= REFRESH_AFTER_MS)
+}
+
+/// Loads the license and keeps it refreshed in the background.
+pub fn setup(app: &AppHandle) {
+    set_status(app, evaluate());
+    if PUBLIC_KEY.is_none() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        if refresh_due(&app) {
+            refresh(&app);
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+pub fn entitlements(app: &AppHandle) -> Entitlements {
+    app.state::<License>().status.lock().unwrap().entitlements
+}
+
+/// Entitlements for a server about to be spawned, as JSON for its environment.
+pub fn server_env(app: &AppHandle) -> String {
+    serde_json::to_string(&entitlements(app)).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_license_status(license: State<'_, License>) -> LicenseStatus {
+    license.status.lock().unwrap().clone()
+}
+
+/// Activates this machine with a license key.
+#[tauri::command]
+pub async fn activate(app: AppHandle, key: String) -> Result<LicenseStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let public_key = PUBLIC_KEY.ok_or("This build does not use licensing")?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err("Enter a license key".to_string());
+        }
+        let machine_id = machine_id()?;
+        let body = serde_json::json!({
+            "key": key,
+            "machine_id": machine_id,
+            "app_version": env!("CARGO_PKG_VERSION"),
+        });
+        let token = request("activate", body).map_err(|e| match e {
+            RequestError::Rejected(reason) => format!("Activation refused: {}", reason),
+            RequestError::Unreachable(e) => e,
+        })?;
+        verify(&token, public_key, &machine_id)?;
+        secrets::set(TOKEN_KEY, &token)?;
+        let status = evaluate();
+        set_status(&app, status.clone());
+        Ok(status)
+    })
+    .await
+    .map_err(|e| format!("Activation failed: {}", e))?
+}
+
+/// Removes the license from this machine, releasing the seat when the server is reachable.
+#[tauri::command]
+pub async fn deactivate(app: AppHandle) -> Result<LicenseStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let (Some(token), Ok(machine_id)) = (secrets::get(TOKEN_KEY)?, machine_id()) {
+            match request("deactivate", serde_json::json!({ "token": token, "machine_id": machine_id })) {
+                Ok(_) => {}
+                Err(RequestError::Rejected(e)) | Err(RequestError::Unreachable(e)) => {
+                    log::warn!("Seat not released on the license server: {}", e)
+                }
+            }
+        }
+        secrets::delete(TOKEN_KEY)?;
+        let status = evaluate();
+        set_status(&app, status.clone());
+        Ok(status)
+    })
+    .await
+    .map_err(|e| format!("Deactivation failed: {}", e))?
+}
@@ -0,0 +1,73 @@
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::AppState;
+
+/// Label of the live view window.
+const VIEWER_LABEL: &str = "live-view";
+
+/// Relays a `live.frame` from the server to the viewer window.
+pub fn frame(app: &AppHandle, params: Value) {
+    if let Err(e) = app.emit_to(VIEWER_LABEL, "live-frame", params) {
+        log::debug!("Live frame not delivered to the viewer: {}", e);
+    }
+}
+
+/// Opens the live view, a window showing the automation browser as it runs, or brings it
+/// forward if it is already open. Streaming stops when the window closes.
+#[tauri::command]
+pub async fn open_live_view(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(VIEWER_LABEL) {
+        let _ = window.unminimize();
+        return window.set_focus().map_err(|e| format!("Failed to focus the live view: {}", e));
+    }
+    let window = WebviewWindowBuilder::new(&app, VIEWER_LABEL, WebviewUrl::App("viewer.html".into()))
+        .title("Live View")
+        .inner_size(1024.0, 700.0)
+        .min_inner_size(480.0, 320.0)
+        .build()
+        .map_err(|e| format!("Failed to open the live view: {}", e))?;
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            let app = handle.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = app.state::<AppState>().control.live_stop() {
+                    log::debug!("Live view not stopped on the server: {}", e);
+                }
+            });
+        }
+    });
+    log::info!("Live view opened");
+    Ok(())
+}
+
+/// Starts streaming the browser to the live view, read-only.
+#[tauri::command]
+pub async fn start_live_view(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<AppState>().control.live_start(false))
+        .await
+        .map_err(|e| format!("Live view failed: {}", e))?
+}
+
+/// Switches the live view between watching and taking control of the browser, in which
+/// the viewer's mouse and keyboard input goes to the page.
+#[tauri::command]
+pub async fn set_live_view_control(app: AppHandle, enabled: bool) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<AppState>().control.live_start(enabled)?;
+        log::info!("Live view {}", if enabled { "took control of the browser" } else { "is read-only" });
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Live view failed: {}", e))?
+}
+
+/// Replays a mouse, wheel or key event from the viewer in the browser; see `live.input`
+/// in the schema. Only while the live view has control.
+#[tauri::command]
+pub async fn send_live_view_input(app: AppHandle, event: Value) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || app.state::<AppState>().control.live_input(event))
+        .await
+        .map_err(|e| format!("Live view input failed: {}", e))?
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::{bundle, history};
+
+/// Records which layout the app data is in, kept in the app data dir.
+const LAYOUT_FILE: &str = "layout.json";
+/// Copies of what the pending migrations touch, in the app data dir while they run.
+const BACKUP_DIR: &str = ".migration-backup";
+/// The history DB and the journal SQLite may leave next to it.
+const DB_PATHS: &[(Root, &str)] = &[(Root::Data, history::DB_FILE), (Root::Data, "history.db-journal")];
+
+/// Changes to the app data or config layout, oldest first. Versions only ever grow; add a
+/// step at the end and list every path it creates, moves or rewrites so a failure can be
+/// rolled back. Schema changes to the history DB go in `history::MIGRATIONS` instead.
+const MIGRATIONS: &[Migration] = &[];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Root {
+    Data,
+    Config,
+}
+
+pub struct Dirs {
+    pub data: PathBuf,
+    pub config: PathBuf,
+}
+
+impl Dirs {
+    fn resolve(&self, root: Root, path: &str) -> PathBuf {
+        match root {
+            Root::Data => self.data.join(path),
+            Root::Config => self.config.join(path),
+        }
+    }
+}
+
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    /// Paths the step may change, relative to their root.
+    pub paths: &'static [(Root, &'static str)],
+    pub run: fn(&Dirs) -> Result<(), String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Layout {
+    version: u32,
+    /// App version that last wrote the layout, for support.
+    app_version: String,
+}
+
+fn latest() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+fn read_layout(dirs: &Dirs) -> Result<Option<Layout>, String> {
+    let path = dirs.data.join(LAYOUT_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("{} is damaged: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Written to a temp file and renamed, so the version never ends up half-written.
+fn write_layout(dirs: &Dirs, version: u32) -> Result<(), String> {
+    let layout = Layout { version, app_version: env!("CARGO_PKG_VERSION").to_string() };
+    let path = dirs.data.join(LAYOUT_FILE);
+    let temp = path.with_extension("json.tmp");
+    std::fs::create_dir_all(&dirs.data).map_err(|e| format!("Failed to create {}: {}", dirs.data.display(), e))?;
+    let json = serde_json::to_string_pretty(&layout).map_err(|e| e.to_string())?;
+    std::fs::write(&temp, json).map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+    std::fs::rename(&temp, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+fn copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        return bundle::copy_dir_all(from, to);
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(from, to).map(|_| ())
+}
+
+/// Where `path` is kept in the backup, by root so data and config can't collide.
+fn backup_path(dirs: &Dirs, root: Root, path: &str) -> PathBuf {
+    let root = if root == Root::Data { "data" } else { "config" };
+    dirs.data.join(BACKUP_DIR).join(root).join(path)
+}
+
+fn back_up(dirs: &Dirs, paths: &[(Root, &str)]) -> Result<(), String> {
+    let dir = dirs.data.join(BACKUP_DIR);
+    remove(&dir).map_err(|e| format!("Failed to clear {}: {}", dir.display(), e))?;
+    for (root, path) in paths {
+        let source = dirs.resolve(*root, path);
+        if source.exists() {
+            copy(&source, &backup_path(dirs, *root, path))
+                .map_err(|e| format!("Failed to back up {}: {}", source.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Puts every path back as it was. A path with no backup didn't exist, so it is removed.
+fn roll_back(dirs: &Dirs, paths: &[(Root, &str)]) -> Result<(), String> {
+    for (root, path) in paths {
+        let target = dirs.resolve(*root, path);
+        let backup = backup_path(dirs, *root, path);
+        remove(&target).map_err(|e| format!("Failed to remove {}: {}", target.display(), e))?;
+        if backup.exists() {
+            copy(&backup, &target).map_err(|e| format!("Failed to restore {}: {}", target.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+fn migrate(dirs: &Dirs) -> Result<(), String> {
+    let layout = read_layout(dirs)?;
+    let settings_exist = dirs.resolve(Root::Config, "settings.json").exists();
+    let db = dirs.data.join(history::DB_FILE);
+    if layout.is_none() && !settings_exist && !db.exists() {
+        // First run: nothing to upgrade
+        return write_layout(dirs, latest());
+    }
+    let current = layout.map_or(0, |layout| layout.version);
+    if current > latest() {
+        log::warn!("App data is from a newer version (layout {}, this version knows {})", current, latest());
+        return Ok(());
+    }
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|migration| migration.version > current).collect();
+    let schema_behind = history::needs_migration(&db)?;
+    if pending.is_empty() && !schema_behind {
+        return if current == latest() { Ok(()) } else { write_layout(dirs, latest()) };
+    }
+
+    let mut paths: Vec<(Root, &str)> = pending.iter().flat_map(|migration| migration.paths.iter().copied()).collect();
+    if schema_behind {
+        paths.extend_from_slice(DB_PATHS);
+    }
+    back_up(dirs, &paths)?;
+    let result = pending
+        .iter()
+        .try_for_each(|migration| {
+            log::info!("Migrating app data to layout {}: {}", migration.version, migration.description);
+            (migration.run)(dirs)
+                .map_err(|e| format!("Layout {} ({}) failed: {}", migration.version, migration.description, e))
+        })
+        .and_then(|_| if schema_behind { history::migrate_file(&db) } else { Ok(()) })
+        .and_then(|_| write_layout(dirs, latest()));
+    match result {
+        Ok(()) => {
+            let _ = remove(&dirs.data.join(BACKUP_DIR));
+            log::info!("App data migrated from layout {} to {}", current, latest());
+            Ok(())
+        }
+        Err(e) => {
+            let rolled_back = roll_back(dirs, &paths);
+            if let Err(rollback) = &rolled_back {
+                log::error!("Rolling back the migration failed, the backup is kept in {}: {}", BACKUP_DIR, rollback);
+            } else {
+                let _ = remove(&dirs.data.join(BACKUP_DIR));
+            }
+            Err(format!("App data could not be upgraded and was left as it was: {}", e))
+        }
+    }
+}
+
+/// Upgrades the app data and config layout and the history schema to this version's,
+/// before anything reads them. Everything a migration touches is backed up first and
+/// restored if any step fails, so the data stays usable by the previous version.
+pub fn run(app: &AppHandle) -> Result<(), String> {
+    let path = app.path();
+    let dirs = Dirs {
+        data: path.app_data_dir().map_err(|e| format!("Failed to get app data dir: {}", e))?,
+        config: path.app_config_dir().map_err(|e| format!("Failed to get app config dir: {}", e))?,
+    };
+    migrate(&dirs)
+}
@@ -0,0 +1,26 @@
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+/// Label of the compact job monitor window.
+const MONITOR_LABEL: &str = "monitor";
+
+/// Opens the monitor, a small always-on-top window showing live job progress from the
+/// `job-event` stream, or closes it if it is already open. Returns whether it is now open.
+#[tauri::command]
+pub async fn toggle_monitor_window(app: AppHandle) -> Result<bool, String> {
+    if let Some(window) = app.get_webview_window(MONITOR_LABEL) {
+        window.close().map_err(|e| format!("Failed to close monitor: {}", e))?;
+        return Ok(false);
+    }
+
+    WebviewWindowBuilder::new(&app, MONITOR_LABEL, WebviewUrl::App("monitor.html".into()))
+        .title("Job Monitor")
+        .inner_size(320.0, 150.0)
+        .min_inner_size(240.0, 110.0)
+        .always_on_top(true)
+        .visible_on_all_workspaces(true)
+        .skip_taskbar(true)
+        .build()
+        .map_err(|e| format!("Failed to open monitor: {}", e))?;
+    log::info!("Job monitor opened");
+    Ok(true)
+}
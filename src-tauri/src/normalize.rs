@@ -0,0 +1,526 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Locale of values when neither the rule nor the recipe names one.
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// What a scraped value is, and so what it becomes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueKind {
+    /// `1.234,5` in `de-DE` becomes `1234.5`.
+    Number,
+    /// `1.234,50 €` becomes `{"amount": 1234.5, "currency": "EUR"}`; the currency is `null`
+    /// when the value doesn't say.
+    Currency,
+    /// `12,5 %` becomes the fraction `0.125`.
+    Percent,
+    /// `5. März 2024` becomes `"2024-03-05"`.
+    Date,
+    /// `05.03.2024 14:30` becomes `"2024-03-05T14:30:00"`. Times are kept as shown; a time
+    /// zone in the value is dropped.
+    DateTime,
+}
+
+/// Converts a field of a recipe's output to a canonical form before the run is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeRule {
+    /// Field name, matched wherever it appears in the output (e.g. `price`).
+    pub field: String,
+    pub kind: ValueKind,
+    /// e.g. `de-DE`; unset uses the recipe's locale.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateOrder {
+    Dmy,
+    Mdy,
+    Ymd,
+}
+
+/// Month names and words for relative days in one language. Names match by prefix, so
+/// `Sept.` and `févr.` are found too.
+struct Words {
+    language: &'static str,
+    months: [&'static [&'static str]; 12],
+    /// e.g. `yesterday` and -1.
+    days: &'static [(&'static str, i64)],
+}
+
+const WORDS: &[Words] = &[
+    Words {
+        language: "en",
+        months: [
+            &["january"],
+            &["february"],
+            &["march"],
+            &["april"],
+            &["may"],
+            &["june"],
+            &["july"],
+            &["august"],
+            &["september"],
+            &["october"],
+            &["november"],
+            &["december"],
+        ],
+        days: &[("today", 0), ("yesterday", -1), ("tomorrow", 1)],
+    },
+    Words {
+        language: "de",
+        months: [
+            &["januar", "jänner"],
+            &["februar"],
+            &["märz", "mrz"],
+            &["april"],
+            &["mai"],
+            &["juni"],
+            &["juli"],
+            &["august"],
+            &["september"],
+            &["oktober"],
+            &["november"],
+            &["dezember"],
+        ],
+        days: &[("heute", 0), ("gestern", -1), ("morgen", 1)],
+    },
+    Words {
+        language: "fr",
+        months: [
+            &["janvier"],
+            &["février", "fevrier"],
+            &["mars"],
+            &["avril"],
+            &["mai"],
+            &["juin"],
+            &["juillet"],
+            &["août", "aout"],
+            &["septembre"],
+            &["octobre"],
+            &["novembre"],
+            &["décembre", "decembre"],
+        ],
+        days: &[("aujourd'hui", 0), ("hier", -1), ("demain", 1)],
+    },
+    Words {
+        language: "es",
+        months: [
+            &["enero"],
+            &["febrero"],
+            &["marzo"],
+            &["abril"],
+            &["mayo"],
+            &["junio"],
+            &["julio"],
+            &["agosto"],
+            &["septiembre", "setiembre"],
+            &["octubre"],
+            &["noviembre"],
+            &["diciembre"],
+        ],
+        days: &[("hoy", 0), ("ayer", -1), ("mañana", 1)],
+    },
+    Words {
+        language: "it",
+        months: [
+            &["gennaio"],
+            &["febbraio"],
+            &["marzo"],
+            &["aprile"],
+            &["maggio"],
+            &["giugno"],
+            &["luglio"],
+            &["agosto"],
+            &["settembre"],
+            &["ottobre"],
+            &["novembre"],
+            &["dicembre"],
+        ],
+        days: &[("oggi", 0), ("ieri", -1), ("domani", 1)],
+    },
+    Words {
+        language: "pt",
+        months: [
+            &["janeiro"],
+            &["fevereiro"],
+            &["março", "marco"],
+            &["abril"],
+            &["maio"],
+            &["junho"],
+            &["julho"],
+            &["agosto"],
+            &["setembro"],
+            &["outubro"],
+            &["novembro"],
+            &["dezembro"],
+        ],
+        days: &[("hoje", 0), ("ontem", -1), ("amanhã", 1)],
+    },
+    Words {
+        language: "nl",
+        months: [
+            &["januari"],
+            &["februari"],
+            &["maart", "mrt"],
+            &["april"],
+            &["mei"],
+            &["juni"],
+            &["juli"],
+            &["augustus"],
+            &["september"],
+            &["oktober"],
+            &["november"],
+            &["december"],
+        ],
+        days: &[("vandaag", 0), ("gisteren", -1), ("morgen", 1)],
+    },
+];
+
+/// Languages whose numbers use a decimal comma, unless their region says otherwise.
+const DECIMAL_COMMA: &[&str] = &[
+    "de", "fr", "es", "it", "pt", "nl", "sv", "da", "nb", "nn", "no", "fi", "pl", "cs", "sk", "ru", "uk", "tr", "el",
+    "hu", "ro", "id",
+];
+/// Languages that use a decimal point.
+const DECIMAL_POINT: &[&str] = &["en", "ja", "zh", "ko", "he", "th", "hi"];
+/// Places where a decimal-comma language uses a decimal point.
+const DECIMAL_POINT_REGIONS: &[(&str, &str)] =
+    &[("de", "CH"), ("de", "LI"), ("it", "CH"), ("es", "MX"), ("es", "US"), ("es", "PR")];
+/// Everything that can separate digit groups; the locale's decimal separator is left out.
+const GROUP_SEPARATORS: &[char] = &[',', '.', ' ', '\u{a0}', '\u{202f}', '\'', '’'];
+/// Symbols and local names of currencies, longest first so `R$` wins over `$`. `$`, `¥`
+/// and `kr` depend on the locale.
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("us$", "USD"),
+    ("ca$", "CAD"),
+    ("au$", "AUD"),
+    ("a$", "AUD"),
+    ("r$", "BRL"),
+    ("zł", "PLN"),
+    ("kč", "CZK"),
+    ("fr.", "CHF"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("₹", "INR"),
+    ("₩", "KRW"),
+    ("₽", "RUB"),
+    ("₺", "TRY"),
+    ("₪", "ILS"),
+];
+
+/// How a locale writes numbers and dates.
+struct Locale {
+    language: String,
+    region: Option<String>,
+    decimal: char,
+    order: DateOrder,
+}
+
+impl Locale {
+    /// Parses a BCP 47 tag like `de-DE` or `pt_BR`; a language alone (`fr`) is enough.
+    fn parse(tag: &str) -> Result<Locale, String> {
+        let mut parts = tag.trim().split(['-', '_']);
+        let language = parts.next().unwrap_or_default().to_lowercase();
+        // Skips a script such as `Hans` in `zh-Hans-CN`
+        let region = parts
+            .find(|part| {
+                (part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))
+                    || (part.len() == 3 && part.chars().all(|c| c.is_ascii_digit()))
+            })
+            .map(str::to_uppercase);
+        let decimal = if DECIMAL_POINT.contains(&language.as_str()) {
+            '.'
+        } else if DECIMAL_COMMA.contains(&language.as_str()) {
+            let place = (language.as_str(), region.as_deref().unwrap_or_default());
+            if DECIMAL_POINT_REGIONS.contains(&place) {
+                '.'
+            } else {
+                ','
+            }
+        } else {
+            return Err(format!("Unsupported locale '{}'", tag.trim()));
+        };
+        let order = match (language.as_str(), region.as_deref()) {
+            ("en", Some("US" | "PH") | None) => DateOrder::Mdy,
+            ("en", Some("CA")) | ("ja" | "zh" | "ko" | "hu" | "sv", _) => DateOrder::Ymd,
+            _ => DateOrder::Dmy,
+        };
+        Ok(Locale { language, region, decimal, order })
+    }
+
+    fn words(&self) -> impl Iterator<Item = &'static Words> + '_ {
+        // English words are understood everywhere, as many sites mix them in
+        WORDS.iter().filter(|words| words.language == self.language || words.language == "en")
+    }
+
+    /// The month a word names, from 1, if exactly one month starts with it.
+    fn month(&self, word: &str) -> Option<u32> {
+        let word = word.trim_end_matches('.');
+        if word.chars().count() < 3 {
+            return None;
+        }
+        let mut found = None;
+        for words in self.words() {
+            for (index, names) in words.months.iter().enumerate() {
+                if names.iter().any(|name| name.starts_with(word)) {
+                    if found.is_some_and(|month| month != index as u32 + 1) {
+                        return None;
+                    }
+                    found = Some(index as u32 + 1);
+                }
+            }
+        }
+        found
+    }
+
+    fn relative_day(&self, word: &str) -> Option<i64> {
+        self.words().flat_map(|words| words.days.iter()).find(|(name, _)| *name == word).map(|(_, offset)| *offset)
+    }
+
+    fn currency_for(&self, symbol: &str) -> Option<&'static str> {
+        let region = self.region.as_deref();
+        match symbol {
+            "$" => Some(match region {
+                Some("CA") => "CAD",
+                Some("AU") => "AUD",
+                Some("NZ") => "NZD",
+                Some("MX") => "MXN",
+                Some("SG") => "SGD",
+                Some("HK") => "HKD",
+                _ => "USD",
+            }),
+            "¥" => Some(if self.language == "zh" { "CNY" } else { "JPY" }),
+            "kr" | "kr." => match self.language.as_str() {
+                "sv" => Some("SEK"),
+                "da" => Some("DKK"),
+                "nb" | "nn" | "no" => Some("NOK"),
+                _ => None,
+            },
+            _ => CURRENCY_SYMBOLS.iter().find(|(known, _)| *known == symbol).map(|(_, code)| *code),
+        }
+    }
+}
+
+/// Parses a number written the locale's way: digit groups may be separated, and must be
+/// three digits long (two in India) so a stray decimal point isn't taken for a separator.
+/// Negative numbers start with a minus sign, end with one or are in parentheses.
+fn parse_number(text: &str, locale: &Locale) -> Result<f64, String> {
+    let invalid = || format!("'{}' is not a number in {}", text.trim(), locale.language);
+    let mut body = text.trim().replace('\u{2212}', "-");
+    let mut negative = false;
+    if body.starts_with('(') && body.ends_with(')') {
+        negative = true;
+        body = body[1..body.len() - 1].trim().to_string();
+    }
+    if let Some(rest) = body.strip_prefix('-').or_else(|| body.strip_suffix('-')) {
+        negative = !negative;
+        body = rest.trim().to_string();
+    }
+    body = body.trim_start_matches('+').to_string();
+    let (integer, fraction) = match body.split_once(locale.decimal) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (body.as_str(), None),
+    };
+    let groups: Vec<&str> = integer.split(|c| c != locale.decimal && GROUP_SEPARATORS.contains(&c)).collect();
+    let indian = locale.region.as_deref() == Some("IN");
+    let digits = |group: &str| group.chars().all(|c| c.is_ascii_digit());
+    let grouped = groups.iter().enumerate().all(|(index, group)| match index {
+        _ if !digits(group) => false,
+        0 => groups.len() == 1 || (1..=3).contains(&group.len()),
+        _ if index == groups.len() - 1 => group.len() == 3,
+        _ => group.len() == 3 || (indian && group.len() == 2),
+    });
+    let fraction = fraction.unwrap_or("0");
+    if !grouped || fraction.is_empty() || !digits(fraction) || (integer.is_empty() && fraction == "0") {
+        return Err(invalid());
+    }
+    let integer = groups.concat();
+    let value: f64 = format!("{}.{}", if integer.is_empty() { "0" } else { &integer }, fraction)
+        .parse()
+        .map_err(|_| invalid())?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Splits an amount from its currency, given as a symbol or an ISO code (`EUR 12,50`).
+fn parse_currency(text: &str, locale: &Locale) -> Result<Value, String> {
+    let lower = text.trim().to_lowercase();
+    let mut currency = None;
+    let mut amount = lower.clone();
+    let symbols = CURRENCY_SYMBOLS.iter().map(|(symbol, _)| *symbol).chain(["kr.", "kr", "$", "¥"]);
+    for symbol in symbols {
+        if let Some(position) = lower.find(symbol) {
+            currency = locale.currency_for(symbol);
+            amount = format!("{}{}", &lower[..position], &lower[position + symbol.len()..]);
+            break;
+        }
+    }
+    // Whatever letters are left must be an ISO code
+    let letters: String = amount.chars().filter(|c| c.is_alphabetic()).collect();
+    if !letters.is_empty() {
+        if currency.is_some() || letters.chars().count() != 3 || !letters.is_ascii() {
+            return Err(format!("'{}' is not an amount of money", text.trim()));
+        }
+        amount = amount.replace(&letters, "");
+        return Ok(json!({ "amount": parse_number(&amount, locale)?, "currency": letters.to_uppercase() }));
+    }
+    Ok(json!({ "amount": parse_number(&amount, locale)?, "currency": currency }))
+}
+
+/// Two-digit years are taken to be within 1969–2068, as `strptime` does.
+fn full_year(year: u32, digits: usize) -> i32 {
+    match (digits, year) {
+        (1..=2, 0..=68) => 2000 + year as i32,
+        (1..=2, _) => 1900 + year as i32,
+        _ => year as i32,
+    }
+}
+
+/// Takes a time like `14:30`, `2:30 pm` or `14:30:05` out of `text`.
+fn take_time(text: &mut String) -> Result<Option<NaiveTime>, String> {
+    let bytes = text.as_bytes();
+    let Some(colon) = (1..bytes.len().saturating_sub(1))
+        .find(|&i| bytes[i] == b':' && bytes[i - 1].is_ascii_digit() && bytes[i + 1].is_ascii_digit())
+    else {
+        return Ok(None);
+    };
+    let start = (0..colon).rev().take_while(|&i| bytes[i].is_ascii_digit()).last().unwrap_or(colon);
+    let mut end = colon + 1;
+    while end < bytes.len() && (bytes[end].is_ascii_digit() || (bytes[end] == b':' && end + 1 < bytes.len())) {
+        end += 1;
+    }
+    let clock = text[start..end].to_string();
+    let rest = text[end..].trim_start();
+    let meridiem = ["am", "a.m.", "pm", "p.m."].into_iter().find(|m| rest.starts_with(m));
+    let consumed = text.len() - rest.len() + meridiem.map_or(0, str::len);
+    let invalid = || format!("'{}' is not a time", clock);
+    let parts: Vec<u32> = clock.split(':').map(|part| part.parse().map_err(|_| invalid())).collect::<Result<_, _>>()?;
+    let (mut hour, minute, second) = (parts[0], parts[1], parts.get(2).copied().unwrap_or(0));
+    match meridiem {
+        Some(m) if !(1..=12).contains(&hour) => return Err(format!("'{} {}' is not a time", clock, m)),
+        Some(m) if m.starts_with('a') => hour %= 12,
+        Some(_) => hour = hour % 12 + 12,
+        None => {}
+    }
+    let time = NaiveTime::from_hms_opt(hour, minute, second).ok_or_else(invalid)?;
+    text.replace_range(start..consumed, " ");
+    Ok(Some(time))
+}
+
+/// Parses a date written as numbers in the locale's order (`05.03.2024`), with a month
+/// name (`March 5th, 2024`, `5 de marzo de 2024`), as ISO 8601, or as today, yesterday
+/// or tomorrow. A date without a year is taken to be in the current year.
+fn parse_date(text: &str, locale: &Locale) -> Result<(NaiveDate, Option<NaiveTime>), String> {
+    let invalid = || format!("'{}' is not a date in {}", text.trim(), locale.language);
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(text.trim()) {
+        return Ok((at.date_naive(), Some(at.time())));
+    }
+    let mut rest = text.trim().to_lowercase();
+    // `2024-03-05T14:30` has its time after a `T`
+    let digit = |c: char| c.is_ascii_digit();
+    let separator = rest
+        .char_indices()
+        .find(|&(i, c)| c == 't' && rest[..i].ends_with(digit) && rest[i + 1..].starts_with(digit));
+    if let Some((t, _)) = separator {
+        rest.replace_range(t..t + 1, " ");
+    }
+    let time = take_time(&mut rest)?;
+    let mut numbers: Vec<(u32, usize)> = Vec::new();
+    let mut month = None;
+    let mut relative = None;
+    // `5th` and `1er` are split into their number and suffix
+    let tokens = rest.split(|c: char| !(c.is_alphanumeric() || c == '\'')).flat_map(|token| {
+        let split = token.find(|c: char| !c.is_ascii_digit()).filter(|&i| i > 0).unwrap_or(token.len());
+        [&token[..split], &token[split..]]
+    });
+    for token in tokens.filter(|token| !token.is_empty()) {
+        if token.chars().all(|c| c.is_ascii_digit()) {
+            numbers.push((token.parse().map_err(|_| invalid())?, token.len()));
+        } else if let Some(found) = locale.month(token) {
+            month = month.or(Some(found));
+        } else if let Some(offset) = locale.relative_day(token) {
+            relative = Some(offset);
+        }
+        // Other words (weekdays, `of`, `de`, ordinal suffixes) are skipped
+    }
+    let date = match (month, numbers.as_slice(), relative) {
+        (None, [], Some(offset)) => Some(Local::now().date_naive() + TimeDelta::days(offset)),
+        (Some(month), [(day, 1..=2)], None) => NaiveDate::from_ymd_opt(Local::now().year(), month, *day),
+        (Some(month), [year @ (_, 4), (day, 1..=2)] | [(day, 1..=2), year], None) => {
+            NaiveDate::from_ymd_opt(full_year(year.0, year.1), month, *day)
+        }
+        (None, [(year, 4), (month, _), (day, _)], None) => NaiveDate::from_ymd_opt(*year as i32, *month, *day),
+        (None, [first, second, third], None) => {
+            let ((year, digits), month, day) = match locale.order {
+                DateOrder::Dmy => (*third, second.0, first.0),
+                DateOrder::Mdy => (*third, first.0, second.0),
+                DateOrder::Ymd => (*first, second.0, third.0),
+            };
+            NaiveDate::from_ymd_opt(full_year(year, digits), month, day)
+        }
+        _ => None,
+    };
+    date.map(|date| (date, time)).ok_or_else(invalid)
+}
+
+/// Converts a scraped value written in `locale` (a BCP 47 tag like `de-DE`) to the canonical
+/// form of its kind: numbers and percentages as JSON numbers, money as an amount with its
+/// ISO currency code, dates and times as ISO 8601 strings.
+pub fn normalize_value(value: &str, kind: ValueKind, locale: &str) -> Result<Value, String> {
+    let locale = Locale::parse(locale)?;
+    match kind {
+        ValueKind::Number => Ok(json!(parse_number(value, &locale)?)),
+        ValueKind::Currency => parse_currency(value, &locale),
+        ValueKind::Percent => {
+            let number = value.trim().trim_end_matches(['%', '\u{066a}']);
+            Ok(json!(parse_number(number, &locale)? / 100.0))
+        }
+        ValueKind::Date => parse_date(value, &locale).map(|(date, _)| json!(date.format("%Y-%m-%d").to_string())),
+        ValueKind::DateTime => parse_date(value, &locale).map(|(date, time)| {
+            let time = time.unwrap_or(NaiveTime::MIN);
+            json!(format!("{}T{}", date.format("%Y-%m-%d"), time.format("%H:%M:%S")))
+        }),
+    }
+}
+
+/// Errors if the tag names a locale values can't be normalized in.
+pub fn check_locale(tag: &str) -> Result<(), String> {
+    Locale::parse(tag).map(|_| ())
+}
+
+fn walk(value: &mut Value, rules: &[NormalizeRule], locale: &str, problems: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                let rule = rules.iter().find(|rule| &rule.field == name);
+                match (rule, &*field) {
+                    (Some(rule), Value::String(text)) => {
+                        match normalize_value(text, rule.kind, rule.locale.as_deref().unwrap_or(locale)) {
+                            Ok(normalized) => *field = normalized,
+                            Err(e) => problems.push(format!("{}: {}", name, e)),
+                        }
+                    }
+                    _ => walk(field, rules, locale, problems),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| walk(item, rules, locale, problems)),
+        _ => {}
+    }
+}
+
+/// Normalizes the fields of `output` the rules name, wherever they appear. Values that
+/// can't be normalized are left as they were; the returned problems say which and why.
+pub fn apply(output: &mut Value, rules: &[NormalizeRule], locale: Option<&str>) -> Vec<String> {
+    let mut problems = Vec::new();
+    walk(output, rules, locale.unwrap_or(DEFAULT_LOCALE), &mut problems);
+    problems
+}
+
+/// Normalizes one value as a recipe's rule would, to try out a kind and locale.
+#[tauri::command]
+pub fn preview_normalization(value: String, kind: ValueKind, locale: Option<String>) -> Result<Value, String> {
+    normalize_value(&value, kind, locale.as_deref().unwrap_or(DEFAULT_LOCALE))
+}
@@ -0,0 +1,380 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::blackouts::BlackoutWindow;
+use crate::digest::DigestSettings;
+use crate::events::{JobEvent, JobEventKind, JobStatus};
+use crate::settings::SettingsStore;
+
+/// How often the do-not-disturb state and quiet hours are checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Titles listed in a digest before it just counts the rest.
+const DIGEST_TITLES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Urgency {
+    /// Held back while do-not-disturb is on and summarized when it lifts.
+    Normal,
+    /// Delivered immediately, e.g. a failed job the user is waiting on.
+    Critical,
+}
+
+/// What a notification is about; each category can be turned off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// Jobs finishing while the window is in the background.
+    Jobs,
+    /// Schedules that failed after their retries.
+    Schedules,
+    /// Page monitors that saw changes.
+    Monitors,
+    /// Recipe checks a run's output broke.
+    Alerts,
+    /// Jobs waiting for the user's input.
+    Interactions,
+    /// The app itself, e.g. low disk space.
+    System,
+    /// The daily digest of runs.
+    Digest,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub urgency: Urgency,
+    pub category: Category,
+}
+
+/// Times of day notifications are held back, like do-not-disturb but set in the app.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuietHours {
+    pub enabled: bool,
+    /// `HH:MM`, local to `time_zone`. A range ending before it starts crosses midnight.
+    pub start_time: String,
+    pub end_time: String,
+    /// e.g. `sat`, `sun`; empty means every day.
+    pub weekdays: Vec<String>,
+    /// IANA time zone; unset follows the machine's zone.
+    pub time_zone: Option<String>,
+    /// Critical notifications, e.g. a job waiting for input, still get through.
+    pub allow_critical: bool,
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        QuietHours {
+            enabled: false,
+            start_time: "22:00".to_string(),
+            end_time: "07:00".to_string(),
+            weekdays: Vec::new(),
+            time_zone: None,
+            allow_critical: true,
+        }
+    }
+}
+
+impl QuietHours {
+    /// The same conditions as a blackout window, which already knows how to match them.
+    fn window(&self) -> BlackoutWindow {
+        BlackoutWindow {
+            name: "Quiet hours".to_string(),
+            enabled: self.enabled,
+            dates: Vec::new(),
+            weekdays: self.weekdays.clone(),
+            start_time: Some(self.start_time.clone()),
+            end_time: Some(self.end_time.clone()),
+            time_zone: self.time_zone.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Categories turned on or off; those not listed are on.
+    pub categories: BTreeMap<Category, bool>,
+    pub quiet_hours: QuietHours,
+    pub digest: DigestSettings,
+}
+
+impl NotificationSettings {
+    pub fn enabled(&self, category: Category) -> bool {
+        self.categories.get(&category).copied().unwrap_or(true)
+    }
+}
+
+struct Queue {
+    dnd: bool,
+    quiet: bool,
+    held: Vec<Notification>,
+}
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue { dnd: false, quiet: false, held: Vec::new() });
+
+/// Sent as `dnd-changed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DndState {
+    /// The OS focus / do-not-disturb mode is on.
+    pub active: bool,
+    /// It is within the configured quiet hours.
+    pub quiet_hours: bool,
+    /// Notifications waiting for the digest.
+    pub queued: usize,
+}
+
+/// GNOME and KDE both expose the freedesktop `Inhibited` property while notifications
+/// are paused; older GNOME only has the banner setting.
+#[cfg(target_os = "linux")]
+fn dnd_active() -> bool {
+    use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+    if let Ok(conn) = dbus::blocking::Connection::new_session() {
+        let proxy = conn.with_proxy(
+            "org.freedesktop.Notifications",
+            "/org/freedesktop/Notifications",
+            Duration::from_secs(2),
+        );
+        if let Ok(inhibited) = proxy.get::<bool>("org.freedesktop.Notifications", "Inhibited") {
+            return inhibited;
+        }
+    }
+    std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .is_ok_and(|output| output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "false")
+}
+
+/// Focus modes record an assertion while active (macOS 12+); earlier versions keep a
+/// Do Not Disturb flag in the notification center preferences.
+#[cfg(target_os = "macos")]
+fn dnd_active() -> bool {
+    let assertions = std::env::var_os("HOME")
+        .map(|home| std::path::PathBuf::from(home).join("Library/DoNotDisturb/DB/Assertions.json"))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok());
+    if let Some(assertions) = assertions {
+        let records = |d: &serde_json::Value| d["storeAssertionRecords"].as_array().is_some_and(|r| !r.is_empty());
+        return assertions["data"].as_array().is_some_and(|data| data.iter().any(records));
+    }
+    std::process::Command::new("defaults")
+        .args(["-currentHost", "read", "com.apple.notificationcenterui", "doNotDisturb"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+/// Quiet hours, presentation mode and fullscreen apps all mean notifications are unwelcome.
+#[cfg(windows)]
+fn dnd_active() -> bool {
+    use windows::Win32::UI::Shell::{SHQueryUserNotificationState, QUNS_ACCEPTS_NOTIFICATIONS};
+    unsafe { SHQueryUserNotificationState() }.is_ok_and(|state| state != QUNS_ACCEPTS_NOTIFICATIONS)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn dnd_active() -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn show(_app: &AppHandle, notification: &Notification) -> Result<(), String> {
+    use dbus::arg::{PropMap, Variant};
+    let conn = dbus::blocking::Connection::new_session().map_err(|e| e.to_string())?;
+    let proxy = conn.with_proxy(
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        Duration::from_secs(5),
+    );
+    let mut hints = PropMap::new();
+    let urgency: u8 = if notification.urgency == Urgency::Critical { 2 } else { 1 };
+    hints.insert("urgency".to_string(), Variant(Box::new(urgency)));
+    let _: (u32,) = proxy
+        .method_call(
+            "org.freedesktop.Notifications",
+            "Notify",
+            (
+                "Browser Automation",
+                0u32,
+                "",
+                notification.title.as_str(),
+                notification.body.as_str(),
+                Vec::<String>::new(),
+                hints,
+                -1i32,
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn show(_app: &AppHandle, notification: &Notification) -> Result<(), String> {
+    // Text is passed as arguments so it needs no AppleScript escaping
+    let status = std::process::Command::new("/usr/bin/osascript")
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e",
+            "end run",
+            &notification.title,
+            &notification.body,
+        ])
+        .status()
+        .map_err(|e| e.to_string())?;
+    status.success().then_some(()).ok_or_else(|| "osascript failed".to_string())
+}
+
+#[cfg(windows)]
+fn show(app: &AppHandle, notification: &Notification) -> Result<(), String> {
+    const SCRIPT: &str = "\
+        [Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null;\
+        $t = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02);\
+        $x = $t.GetElementsByTagName('text');\
+        $x.Item(0).AppendChild($t.CreateTextNode($env:TOAST_TITLE)) > $null;\
+        $x.Item(1).AppendChild($t.CreateTextNode($env:TOAST_BODY)) > $null;\
+        [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:TOAST_APP_ID).Show([Windows.UI.Notifications.ToastNotification]::new($t))";
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("TOAST_TITLE", &notification.title)
+        .env("TOAST_BODY", &notification.body)
+        .env("TOAST_APP_ID", &app.config().identifier)
+        .status()
+        .map_err(|e| e.to_string())?;
+    status.success().then_some(()).ok_or_else(|| "Toast notification failed".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn show(_app: &AppHandle, _notification: &Notification) -> Result<(), String> {
+    Err("Notifications are not supported on this platform".to_string())
+}
+
+fn deliver(app: &AppHandle, notification: Notification) {
+    let app = app.clone();
+    // Delivery runs external tools or waits on D-Bus; never hold up the caller
+    std::thread::spawn(move || {
+        if let Err(e) = show(&app, &notification) {
+            log::warn!("Failed to show notification '{}': {}", notification.title, e);
+        }
+    });
+}
+
+fn state() -> DndState {
+    let queue = QUEUE.lock().unwrap();
+    DndState { active: queue.dnd, quiet_hours: queue.quiet, queued: queue.held.len() }
+}
+
+/// Whether it is within the quiet hours. Quiet hours that can't be parsed are logged and
+/// treated as off.
+fn quiet_now(quiet_hours: &QuietHours) -> bool {
+    quiet_hours.window().matches(chrono::Utc::now()).unwrap_or_else(|e| {
+        log::warn!("Ignoring quiet hours: {}", e);
+        false
+    })
+}
+
+/// Shows a desktop notification unless its category is turned off, or holds it for the
+/// digest while do-not-disturb is on (unless it is critical or the user turned
+/// `respect_do_not_disturb` off) or during quiet hours (unless it is critical and they
+/// allow that).
+pub fn notify(app: &AppHandle, notification: Notification) {
+    let settings = app.state::<SettingsStore>().get();
+    if !settings.notifications.enabled(notification.category) {
+        log::debug!("{:?} notifications are off, dropping '{}'", notification.category, notification.title);
+        return;
+    }
+    {
+        let mut queue = QUEUE.lock().unwrap();
+        let normal = notification.urgency == Urgency::Normal;
+        let held_by_dnd = queue.dnd && settings.respect_do_not_disturb && normal;
+        let held_by_quiet = queue.quiet && (normal || !settings.notifications.quiet_hours.allow_critical);
+        if held_by_dnd || held_by_quiet {
+            log::debug!("Notifications are paused, holding '{}'", notification.title);
+            queue.held.push(notification);
+            drop(queue);
+            let _ = app.emit("dnd-changed", state());
+            return;
+        }
+    }
+    deliver(app, notification);
+}
+
+/// One notification summarizing everything held back while do-not-disturb or quiet hours
+/// were on.
+fn digest(held: Vec<Notification>) -> Option<Notification> {
+    if held.len() <= 1 {
+        return held.into_iter().next();
+    }
+    let mut lines: Vec<String> = held.iter().take(DIGEST_TITLES).map(|n| format!("• {}", n.title)).collect();
+    if held.len() > DIGEST_TITLES {
+        lines.push(format!("and {} more", held.len() - DIGEST_TITLES));
+    }
+    Some(Notification {
+        title: format!("{} notifications held back", held.len()),
+        body: lines.join("\n"),
+        urgency: Urgency::Normal,
+        category: Category::System,
+    })
+}
+
+/// Polls the do-not-disturb state and quiet hours and delivers the digest when both lift.
+pub fn setup(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        let active = dnd_active();
+        let quiet = quiet_now(&app.state::<SettingsStore>().get().notifications.quiet_hours);
+        let lifted = {
+            let mut queue = QUEUE.lock().unwrap();
+            if queue.dnd == active && queue.quiet == quiet {
+                None
+            } else {
+                if queue.dnd != active {
+                    log::info!("Do not disturb {}", if active { "on" } else { "off" });
+                }
+                if queue.quiet != quiet {
+                    log::info!("Quiet hours {}", if quiet { "started" } else { "ended" });
+                }
+                queue.dnd = active;
+                queue.quiet = quiet;
+                Some(if active || quiet { Vec::new() } else { std::mem::take(&mut queue.held) })
+            }
+        };
+        if let Some(held) = lifted {
+            if let Some(digest) = digest(held) {
+                deliver(&app, digest);
+            }
+            let _ = app.emit("dnd-changed", state());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+/// Notifies about a finished job if the main window is not in front. Failures are critical.
+pub fn job_finished(app: &AppHandle, event: &JobEvent) {
+    if event.kind != JobEventKind::Finished || !app.state::<SettingsStore>().get().notify_jobs {
+        return;
+    }
+    let focused = app.get_webview_window("main").is_some_and(|w| w.is_focused().unwrap_or(false));
+    if focused {
+        return;
+    }
+    let failed = event.status == Some(JobStatus::Error);
+    notify(
+        app,
+        Notification {
+            title: if failed { "Automation failed" } else { "Automation finished" }.to_string(),
+            body: event.message.clone().unwrap_or_else(|| event.prompt.clone()),
+            urgency: if failed { Urgency::Critical } else { Urgency::Normal },
+            category: Category::Jobs,
+        },
+    );
+}
+
+#[tauri::command]
+pub fn get_dnd_state() -> DndState {
+    state()
+}
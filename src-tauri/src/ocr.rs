@@ -0,0 +1,62 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::thumbnails::resolve_screenshot;
+
+/// Tesseract language used when none is given.
+const DEFAULT_LANG: &str = "eng";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrResult {
+    pub text: String,
+    /// Tesseract's mean word confidence, 0-100.
+    pub confidence: i32,
+    pub lang: String,
+}
+
+/// Tesseract language codes such as `eng` or `deu+fra`. Anything else is refused before
+/// it reaches Tesseract, which would look it up as a path.
+fn is_valid_lang(lang: &str) -> bool {
+    !lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '+')
+}
+
+/// Recognizes the text in an image with libtesseract, which needs the traineddata for
+/// `lang` installed where it looks by default (or in `TESSDATA_PREFIX`).
+#[cfg(feature = "ocr")]
+fn recognize(path: &std::path::Path, lang: &str) -> Result<(String, i32), String> {
+    fn run(path: &str, lang: &str) -> Result<(String, i32), tesseract::TesseractError> {
+        let mut tesseract = tesseract::Tesseract::new(None, Some(lang))?.set_image(path)?.recognize()?;
+        let text = tesseract.get_text()?;
+        Ok((text, tesseract.mean_text_conf()))
+    }
+    let path = path.to_str().ok_or_else(|| format!("Unsupported path: {}", path.display()))?;
+    run(path, lang).map_err(|e| format!("OCR failed: {}", e))
+}
+
+#[cfg(not(feature = "ocr"))]
+fn recognize(_path: &std::path::Path, _lang: &str) -> Result<(String, i32), String> {
+    Err("This build can't read text from screenshots; it was built without the ocr feature".to_string())
+}
+
+/// Reads the text out of a screenshot, for pages whose text the server could not take
+/// from the DOM (canvases, images, PDFs in the viewer). `artifact_id` is `<run_id>/<path>`
+/// within the run's artifacts; `lang` is a Tesseract language code, `eng` by default.
+#[tauri::command]
+pub async fn ocr_artifact(app: AppHandle, artifact_id: String, lang: Option<String>) -> Result<OcrResult, String> {
+    let lang = lang.unwrap_or_else(|| DEFAULT_LANG.to_string());
+    if !is_valid_lang(&lang) {
+        return Err(format!("Invalid OCR language: {}", lang));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let (_, _, path) = resolve_screenshot(&app, &artifact_id)?;
+        let (text, confidence) = recognize(&path, &lang)?;
+        log::info!("Read {} characters from {} ({}% confidence)", text.len(), artifact_id, confidence);
+        Ok(OcrResult {
+            text: text.trim().to_string(),
+            confidence,
+            lang,
+        })
+    })
+    .await
+    .map_err(|e| format!("OCR failed: {}", e))?
+}
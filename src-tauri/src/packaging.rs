@@ -0,0 +1,94 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// How the app was installed. The Linux sandboxes move resources, restrict writable and
+/// shared dirs, and leak their own environment into child processes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Packaging {
+    Native,
+    /// Runs from a read-only squashfs mounted at `$APPDIR`, at a new path on every launch.
+    AppImage,
+    /// Sandboxed with the app under `/app` and data under `~/.var/app/<id>`.
+    Flatpak,
+    /// Confined with the app under `$SNAP`.
+    Snap,
+}
+
+#[cfg(target_os = "linux")]
+pub fn detect() -> Packaging {
+    if Path::new("/.flatpak-info").exists() || std::env::var_os("FLATPAK_ID").is_some() {
+        Packaging::Flatpak
+    } else if std::env::var_os("SNAP").is_some() && std::env::var_os("SNAP_NAME").is_some() {
+        Packaging::Snap
+    } else if std::env::var_os("APPIMAGE").is_some() && std::env::var_os("APPDIR").is_some() {
+        Packaging::AppImage
+    } else {
+        Packaging::Native
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn detect() -> Packaging {
+    Packaging::Native
+}
+
+/// Where the bundled server may be, most likely first. Packagers that rebuild the app
+/// for a sandbox do not always keep the `_up_` dir Tauri puts `../` resources in.
+pub fn bundle_dir_candidates(resource_dir: &Path) -> Vec<PathBuf> {
+    let mut candidates = vec![resource_dir.join("_up_").join("bundle-bin"), resource_dir.join("bundle-bin")];
+    let root = match detect() {
+        Packaging::Native => None,
+        Packaging::AppImage => std::env::var_os("APPDIR").map(|dir| PathBuf::from(dir).join("usr")),
+        Packaging::Flatpak => Some(PathBuf::from("/app")),
+        Packaging::Snap => std::env::var_os("SNAP").map(|dir| PathBuf::from(dir).join("usr")),
+    };
+    if let (Some(root), Some(name)) = (root, resource_dir.file_name()) {
+        let lib = root.join("lib").join(name);
+        candidates.push(lib.join("_up_").join("bundle-bin"));
+        candidates.push(lib.join("bundle-bin"));
+    }
+    candidates
+}
+
+/// Directory for sockets and lock files shared by every instance of the app in this
+/// session. Flatpak gives each sandbox its own `/tmp`, so it uses the per-app runtime dir.
+pub fn runtime_dir() -> PathBuf {
+    if detect() == Packaging::Flatpak {
+        if let (Some(runtime), Some(id)) = (std::env::var_os("XDG_RUNTIME_DIR"), std::env::var_os("FLATPAK_ID")) {
+            let dir = PathBuf::from(runtime).join("app").join(id);
+            if dir.is_dir() {
+                return dir;
+            }
+        }
+    }
+    std::env::temp_dir()
+}
+
+/// The server's browser profile (cookies and sign-ins of automated sites). The server
+/// keeps it at the same place on every OS.
+pub fn browser_profile_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join("Library/Application Support/BrowserAutomation/user_data_dir"))
+}
+
+/// Directories the sandbox keeps its own tools in, searched before the system `PATH`.
+pub fn extra_path() -> Vec<PathBuf> {
+    match detect() {
+        Packaging::Flatpak => vec![PathBuf::from("/app/bin")],
+        Packaging::Snap => std::env::var_os("SNAP")
+            .map(|snap| vec![PathBuf::from(&snap).join("usr/bin"), PathBuf::from(snap).join("bin")])
+            .unwrap_or_default(),
+        // The AppImage mount only lives as long as the app; the server runs from the
+        // extracted copy and must not depend on it
+        Packaging::AppImage | Packaging::Native => Vec::new(),
+    }
+}
+
+/// Sandbox variables the server needs to keep working inside the same sandbox.
+pub fn inherited_env() -> &'static [&'static str] {
+    match detect() {
+        Packaging::Flatpak => &["FLATPAK_ID", "XDG_DATA_HOME", "XDG_CONFIG_HOME", "XDG_CACHE_HOME"],
+        Packaging::Snap => &["SNAP", "SNAP_NAME", "SNAP_REVISION", "SNAP_USER_DATA", "SNAP_USER_COMMON", "SNAP_DATA"],
+        Packaging::AppImage | Packaging::Native => &[],
+    }
+}
@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::events::{JobEvent, JobStatus};
+use crate::history::{self, History, PipelineStep};
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::settings::{self, SettingsStore};
+use crate::{queue, recipes};
+
+/// Longest previous output passed on in a prompt.
+const MAX_PASSED_OUTPUT: usize = 4000;
+
+/// Recipes run in dependency order: each step starts once the steps it is `after` have
+/// all succeeded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pipeline {
+    pub id: String,
+    pub name: String,
+    pub steps: Vec<PipelineStepConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStepConfig {
+    pub recipe_id: String,
+    /// Recipe ids of earlier steps.
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Tell the step where its dependencies' artifacts are, and what they output.
+    #[serde(default)]
+    pub pass_artifacts: bool,
+}
+
+/// Sent as `pipeline-changed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineRun {
+    pub run_id: String,
+    pub pipeline_id: String,
+    /// `running`, `success` or `error`.
+    pub status: String,
+    pub steps: Vec<PipelineStep>,
+}
+
+/// Serializes step updates, so two steps finishing together can't both start a dependent.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Checks the steps name distinct recipes, depend only on each other, and have no cycles.
+fn validate(pipeline: &Pipeline) -> Result<(), String> {
+    if pipeline.steps.is_empty() {
+        return Err(format!("Pipeline '{}' has no steps", pipeline.name));
+    }
+    let mut dependencies: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for step in &pipeline.steps {
+        let after = step.after.iter().map(String::as_str).collect();
+        if dependencies.insert(step.recipe_id.as_str(), after).is_some() {
+            return Err(format!("Recipe '{}' is in the pipeline twice", step.recipe_id));
+        }
+    }
+    for (recipe, after) in &dependencies {
+        if let Some(unknown) = after.iter().find(|dependency| !dependencies.contains_key(*dependency)) {
+            return Err(format!("'{}' runs after '{}', which is not a step of the pipeline", recipe, unknown));
+        }
+    }
+
+    // Kahn's algorithm: whatever can't be ordered is on a cycle
+    let mut done: BTreeSet<&str> = BTreeSet::new();
+    while done.len() < dependencies.len() {
+        let ready: Vec<&str> = dependencies
+            .iter()
+            .filter(|(recipe, after)| !done.contains(*recipe) && after.iter().all(|after| done.contains(after)))
+            .map(|(recipe, _)| *recipe)
+            .collect();
+        if ready.is_empty() {
+            let cycle: Vec<&str> = dependencies.keys().copied().filter(|recipe| !done.contains(recipe)).collect();
+            return Err(format!("The pipeline has a dependency cycle between {}", cycle.join(", ")));
+        }
+        done.extend(ready);
+    }
+    Ok(())
+}
+
+fn run_status(steps: &[PipelineStep]) -> &'static str {
+    if steps.iter().any(|step| step.status == "error" || step.status == "skipped") {
+        if steps.iter().any(|step| step.status == "queued") {
+            "running"
+        } else {
+            "error"
+        }
+    } else if steps.iter().all(|step| step.status == "success") {
+        "success"
+    } else {
+        "running"
+    }
+}
+
+fn load_run(history: &History, run_id: &str) -> Result<PipelineRun, String> {
+    let steps = history.pipeline_steps(run_id)?;
+    let first = steps.first().ok_or_else(|| format!("No pipeline run '{}'", run_id))?;
+    Ok(PipelineRun {
+        run_id: run_id.to_string(),
+        pipeline_id: first.pipeline_id.clone(),
+        status: run_status(&steps).to_string(),
+        steps,
+    })
+}
+
+fn changed(app: &AppHandle, run_id: &str) {
+    match load_run(&app.state::<History>(), run_id) {
+        Ok(run) => {
+            let _ = app.emit("pipeline-changed", run);
+        }
+        Err(e) => log::warn!("Failed to read pipeline run {}: {}", run_id, e),
+    }
+}
+
+/// The step's prompt, followed by where its dependencies' artifacts are and what they
+/// output when it asks for them.
+fn step_prompt(app: &AppHandle, step: &PipelineStep, steps: &[PipelineStep]) -> Result<String, String> {
+    let mut prompt = recipes::load(app, &step.recipe_id)?.task.prompt;
+    if !step.pass_artifacts {
+        return Ok(prompt);
+    }
+    for dependency in steps.iter().filter(|s| step.after.contains(&s.recipe_id)) {
+        let Some(job_id) = dependency.job_id.as_deref() else {
+            continue;
+        };
+        prompt.push_str(&format!("\n\nResults of the previous step '{}':", dependency.recipe_id));
+        let files = history::artifacts(app, job_id);
+        if !files.is_empty() {
+            prompt.push_str("\nFiles:");
+            for file in files {
+                prompt.push_str(&format!("\n- {}", file.display()));
+            }
+        }
+        let output = app.state::<History>().get(job_id)?.and_then(|run| run.output);
+        if let Some(output) = output {
+            let output = output.to_string();
+            let end = output.char_indices().nth(MAX_PASSED_OUTPUT).map(|(i, _)| i).unwrap_or(output.len());
+            prompt.push_str(&format!("\nOutput: {}", &output[..end]));
+        }
+    }
+    Ok(prompt)
+}
+
+/// Queues every waiting step whose dependencies have all succeeded, and skips those
+/// whose dependencies failed. Call with `LOCK` held.
+fn advance(app: &AppHandle, run_id: &str) -> Result<(), String> {
+    let history = app.state::<History>();
+    loop {
+        let steps = history.pipeline_steps(run_id)?;
+        let status_of = |recipe: &str| steps.iter().find(|s| s.recipe_id == recipe).map(|s| s.status.as_str());
+        let mut progressed = false;
+        for step in steps.iter().filter(|step| step.status == "waiting") {
+            let statuses: Vec<Option<&str>> = step.after.iter().map(|recipe| status_of(recipe)).collect();
+            if statuses.iter().any(|status| matches!(status, Some("error" | "skipped"))) {
+                history.update_pipeline_step(run_id, &step.recipe_id, "skipped", None)?;
+                progressed = true;
+            } else if statuses.iter().all(|status| *status == Some("success")) {
+                let queued = step_prompt(app, step, &steps)
+                    .and_then(|prompt| queue::enqueue(app, prompt, 0, None, Some(step.recipe_id.clone())));
+                match queued {
+                    Ok(job) => history.update_pipeline_step(run_id, &step.recipe_id, "queued", Some(&job.id))?,
+                    Err(e) => {
+                        log::error!("Pipeline step '{}' could not be queued: {}", step.recipe_id, e);
+                        history.update_pipeline_step(run_id, &step.recipe_id, "error", None)?;
+                    }
+                }
+                progressed = true;
+            }
+        }
+        // Skipping or failing a step can settle steps after it
+        if !progressed {
+            return Ok(());
+        }
+    }
+}
+
+fn start(app: &AppHandle, pipeline_id: &str) -> Result<PipelineRun, String> {
+    let settings = app.state::<SettingsStore>().get();
+    let pipeline = settings
+        .pipelines
+        .iter()
+        .find(|p| p.id == pipeline_id)
+        .ok_or_else(|| format!("No pipeline '{}'", pipeline_id))?;
+    validate(pipeline)?;
+
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let now = history::now_ms();
+    let steps: Vec<PipelineStep> = pipeline
+        .steps
+        .iter()
+        .map(|step| PipelineStep {
+            run_id: run_id.clone(),
+            pipeline_id: pipeline.id.clone(),
+            recipe_id: step.recipe_id.clone(),
+            after: step.after.clone(),
+            pass_artifacts: step.pass_artifacts,
+            job_id: None,
+            status: "waiting".to_string(),
+            started_at: now,
+            updated_at: now,
+        })
+        .collect();
+    let _lock = LOCK.lock().unwrap();
+    app.state::<History>().add_pipeline_steps(&steps)?;
+    log::info!("Starting pipeline '{}' as run {}", pipeline.name, run_id);
+    advance(app, &run_id)?;
+    changed(app, &run_id);
+    load_run(&app.state::<History>(), &run_id)
+}
+
+/// Built-in plugin that moves pipelines along as their jobs finish.
+struct PipelinePlugin;
+
+impl ShellPlugin for PipelinePlugin {
+    fn name(&self) -> &str {
+        "pipelines"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        let _lock = LOCK.lock().unwrap();
+        let history = ctx.app.state::<History>();
+        let step = match history.pipeline_step_for_job(&job.job_id) {
+            Ok(Some(step)) => step,
+            Ok(None) => return,
+            Err(e) => {
+                log::warn!("Failed to look up the pipeline of job {}: {}", job.job_id, e);
+                return;
+            }
+        };
+        let status = if job.status == Some(JobStatus::Success) { "success" } else { "error" };
+        let result = history
+            .update_pipeline_step(&step.run_id, &step.recipe_id, status, None)
+            .and_then(|_| advance(ctx.app, &step.run_id));
+        if let Err(e) = result {
+            log::error!("Pipeline run {} could not continue: {}", step.run_id, e);
+        }
+        changed(ctx.app, &step.run_id);
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(PipelinePlugin), "Starts pipeline steps once the steps before them succeed");
+}
+
+/// Adds or replaces a pipeline after checking its dependencies.
+#[tauri::command]
+pub fn save_pipeline(app: AppHandle, pipeline: Pipeline) -> Result<(), String> {
+    validate(&pipeline)?;
+    let mut settings = app.state::<SettingsStore>().get();
+    match settings.pipelines.iter_mut().find(|p| p.id == pipeline.id) {
+        Some(existing) => *existing = pipeline,
+        None => settings.pipelines.push(pipeline),
+    }
+    settings::apply(&app, settings)
+}
+
+#[tauri::command]
+pub fn delete_pipeline(app: AppHandle, id: String) -> Result<(), String> {
+    let mut settings = app.state::<SettingsStore>().get();
+    settings.pipelines.retain(|p| p.id != id);
+    settings::apply(&app, settings)
+}
+
+/// Queues the pipeline's first steps; the rest follow as their dependencies succeed.
+#[tauri::command]
+pub async fn start_pipeline(app: AppHandle, id: String) -> Result<PipelineRun, String> {
+    tauri::async_runtime::spawn_blocking(move || start(&app, &id))
+        .await
+        .map_err(|e| format!("Starting the pipeline failed: {}", e))?
+}
+
+/// One pipeline run, or the latest runs when `run_id` is omitted.
+#[tauri::command]
+pub fn get_pipeline_status(app: AppHandle, run_id: Option<String>) -> Result<Vec<PipelineRun>, String> {
+    let history = app.state::<History>();
+    let run_ids = match run_id {
+        Some(run_id) => vec![run_id],
+        None => history.pipeline_runs(20)?,
+    };
+    run_ids.iter().map(|run_id| load_run(&history, run_id)).collect()
+}
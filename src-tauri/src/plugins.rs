@@ -0,0 +1,341 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::events::JobEvent;
+use crate::{flags, server};
+
+const MANIFEST_FILE: &str = "plugin.json";
+/// Longest a plugin's command or HTTP call may take; hooks that hang are given up on, so
+/// one can't keep the app from exiting.
+const ACTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a plugin hook gets to see.
+pub struct PluginContext<'a> {
+    pub app: &'a AppHandle,
+    /// Base URL of the main server, if it is running.
+    pub server_url: Option<String>,
+}
+
+/// A shell extension. Hooks run on a background thread, so they may block.
+pub trait ShellPlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn on_server_ready(&self, _ctx: &PluginContext) {}
+
+    fn on_job_finished(&self, _ctx: &PluginContext, _job: &JobEvent) {}
+
+    fn on_shutdown(&self, _ctx: &PluginContext) {}
+
+    /// Custom commands the plugin exposes through `run_plugin_command`.
+    fn commands(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn run_command(&self, _ctx: &PluginContext, command: &str, _payload: serde_json::Value) -> Result<(), String> {
+        Err(format!("Unknown command '{}'", command))
+    }
+}
+
+/// Something a declarative plugin does when a hook or command fires.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginAction {
+    /// Runs a program (relative paths resolve against the plugin dir). The hook payload
+    /// is passed as JSON in `NOVA_PLUGIN_PAYLOAD`.
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Sends the hook payload as JSON to a URL.
+    Http {
+        url: String,
+        #[serde(default = "default_http_method")]
+        method: String,
+    },
+    /// Re-emits the payload to the frontend under the given event name.
+    Emit { event: String },
+}
+
+fn default_http_method() -> String {
+    "POST".to_string()
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PluginHooks {
+    #[serde(default)]
+    pub on_server_ready: Vec<PluginAction>,
+    #[serde(default)]
+    pub on_job_finished: Vec<PluginAction>,
+    #[serde(default)]
+    pub on_shutdown: Vec<PluginAction>,
+}
+
+/// Contents of `plugins/<name>/plugin.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub hooks: PluginHooks,
+    #[serde(default)]
+    pub commands: HashMap<String, Vec<PluginAction>>,
+}
+
+/// A plugin defined entirely by its manifest.
+pub struct ManifestPlugin {
+    manifest: PluginManifest,
+    dir: PathBuf,
+}
+
+impl ManifestPlugin {
+    fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST_FILE);
+        let contents = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let manifest: PluginManifest =
+            serde_json::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(ManifestPlugin { manifest, dir: dir.to_path_buf() })
+    }
+
+    fn run_actions(&self, ctx: &PluginContext, hook: &str, actions: &[PluginAction], payload: serde_json::Value) {
+        let payload = serde_json::json!({
+            "plugin": self.manifest.name,
+            "hook": hook,
+            "server_url": ctx.server_url,
+            "data": payload,
+        });
+        for action in actions {
+            if let Err(e) = self.run_action(ctx, action, &payload) {
+                log::error!("Plugin '{}' {} action failed: {}", self.manifest.name, hook, e);
+            }
+        }
+    }
+
+    fn run_action(&self, ctx: &PluginContext, action: &PluginAction, payload: &serde_json::Value) -> Result<(), String> {
+        match action {
+            PluginAction::Command { program, args } => {
+                let program_path = self.dir.join(program);
+                let program = if program_path.exists() { program_path } else { PathBuf::from(program) };
+                let mut child = Command::new(&program)
+                    .args(args)
+                    .current_dir(&self.dir)
+                    .env("NOVA_PLUGIN_PAYLOAD", payload.to_string())
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+                if !server::wait_for_exit(&mut child, ACTION_TIMEOUT) {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    let seconds = ACTION_TIMEOUT.as_secs();
+                    return Err(format!("{} did not finish within {} seconds and was stopped", program.display(), seconds));
+                }
+                let status = child.wait().map_err(|e| e.to_string())?;
+                if !status.success() {
+                    return Err(format!("{} exited with {}", program.display(), status));
+                }
+                Ok(())
+            }
+            PluginAction::Http { url, method } => {
+                ureq::request(method, url)
+                    .timeout(ACTION_TIMEOUT)
+                    .send_json(payload.clone())
+                    .map_err(|e| format!("{} {} failed: {}", method, url, e))?;
+                Ok(())
+            }
+            PluginAction::Emit { event } => ctx.app.emit(event, payload).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+impl ShellPlugin for ManifestPlugin {
+    fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn on_server_ready(&self, ctx: &PluginContext) {
+        self.run_actions(ctx, "on_server_ready", &self.manifest.hooks.on_server_ready, serde_json::Value::Null);
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        let job = serde_json::to_value(job).unwrap_or_default();
+        self.run_actions(ctx, "on_job_finished", &self.manifest.hooks.on_job_finished, job);
+    }
+
+    fn on_shutdown(&self, ctx: &PluginContext) {
+        self.run_actions(ctx, "on_shutdown", &self.manifest.hooks.on_shutdown, serde_json::Value::Null);
+    }
+
+    fn commands(&self) -> Vec<String> {
+        let mut commands: Vec<String> = self.manifest.commands.keys().cloned().collect();
+        commands.sort();
+        commands
+    }
+
+    fn run_command(&self, ctx: &PluginContext, command: &str, payload: serde_json::Value) -> Result<(), String> {
+        let actions = self
+            .manifest
+            .commands
+            .get(command)
+            .ok_or_else(|| format!("Unknown command '{}'", command))?;
+        self.run_actions(ctx, command, actions, payload);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub builtin: bool,
+    pub commands: Vec<String>,
+}
+
+/// All registered plugins: built-in ones registered in code plus manifests from the plugins dir.
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: Mutex<Vec<Arc<dyn ShellPlugin>>>,
+    info: Mutex<Vec<PluginInfo>>,
+}
+
+impl PluginRegistry {
+    /// Registers a plugin implemented in the shell itself.
+    pub fn register(&self, plugin: Arc<dyn ShellPlugin>, description: &str) {
+        log::info!("Plugin registered: {}", plugin.name());
+        self.info.lock().unwrap().push(PluginInfo {
+            name: plugin.name().to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            description: description.to_string(),
+            builtin: true,
+            commands: plugin.commands(),
+        });
+        self.plugins.lock().unwrap().push(plugin);
+    }
+
+    fn register_manifest(&self, plugin: ManifestPlugin) {
+        log::info!("Plugin loaded: {} {}", plugin.manifest.name, plugin.manifest.version);
+        self.info.lock().unwrap().push(PluginInfo {
+            name: plugin.manifest.name.clone(),
+            version: plugin.manifest.version.clone(),
+            description: plugin.manifest.description.clone(),
+            builtin: false,
+            commands: plugin.commands(),
+        });
+        self.plugins.lock().unwrap().push(Arc::new(plugin));
+    }
+
+    fn snapshot(&self) -> Vec<Arc<dyn ShellPlugin>> {
+        self.plugins.lock().unwrap().clone()
+    }
+}
+
+pub fn plugins_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join("plugins"))
+}
+
+/// Loads manifest plugins from the plugins dir. Safe mode skips plugins entirely.
+pub fn load(app: &AppHandle, safe_mode: bool) {
+    let registry = app.state::<PluginRegistry>();
+    if safe_mode {
+        log::info!("Safe mode: plugins disabled");
+        return;
+    }
+    if !flags::enabled(app, "external_plugins") {
+        log::info!("External plugins are turned off by a feature flag");
+        return;
+    }
+    let Some(dir) = plugins_dir(app) else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.join(MANIFEST_FILE).exists() {
+            continue;
+        }
+        match ManifestPlugin::load(&path) {
+            Ok(plugin) => registry.register_manifest(plugin),
+            Err(e) => log::error!("Failed to load plugin: {}", e),
+        }
+    }
+}
+
+fn context(app: &AppHandle) -> PluginContext<'_> {
+    let app_state = app.state::<crate::AppState>();
+    let running = app_state.python_process.lock().unwrap().is_some();
+    let port = *app_state.server_port.lock().unwrap();
+    PluginContext { app, server_url: running.then(|| server::server_url(port)) }
+}
+
+/// Runs `hook` for every plugin on a background thread.
+fn dispatch(app: &AppHandle, hook: impl Fn(&dyn ShellPlugin, &PluginContext) + Send + 'static) {
+    let plugins = app.state::<PluginRegistry>().snapshot();
+    if plugins.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let ctx = context(&app);
+        for plugin in plugins {
+            hook(plugin.as_ref(), &ctx);
+        }
+    });
+}
+
+pub fn server_ready(app: &AppHandle) {
+    dispatch(app, |plugin, ctx| plugin.on_server_ready(ctx));
+}
+
+pub fn job_finished(app: &AppHandle, job: &JobEvent) {
+    let job = job.clone();
+    dispatch(app, move |plugin, ctx| plugin.on_job_finished(ctx, &job));
+}
+
+/// Runs shutdown hooks and waits for them, since the process is about to exit. Each plugin
+/// action is given up on after `ACTION_TIMEOUT`.
+pub fn shutdown(app: &AppHandle) {
+    let ctx = context(app);
+    for plugin in app.state::<PluginRegistry>().snapshot() {
+        plugin.on_shutdown(&ctx);
+    }
+}
+
+#[tauri::command]
+pub fn list_plugins(registry: State<'_, PluginRegistry>) -> Vec<PluginInfo> {
+    registry.info.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub async fn run_plugin_command(
+    app: AppHandle,
+    plugin: String,
+    command: String,
+    payload: Option<serde_json::Value>,
+) -> Result<(), String> {
+    let target = app
+        .state::<PluginRegistry>()
+        .snapshot()
+        .into_iter()
+        .find(|p| p.name() == plugin)
+        .ok_or_else(|| format!("No plugin named '{}'", plugin))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let ctx = context(&app);
+        target.run_command(&ctx, &command, payload.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("Plugin command failed: {}", e))?
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SettingsStore;
+use crate::AppState;
+
+/// Limits on how hard automations may hit a site. Unset fields don't limit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DomainLimits {
+    /// Page loads per minute, spread out evenly.
+    pub requests_per_minute: Option<u32>,
+    /// Page loads of the site in flight at once.
+    pub max_concurrent: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DomainLimit {
+    /// Applies to this domain and its subdomains, e.g. `example.com`.
+    pub domain: String,
+    #[serde(flatten)]
+    pub limits: DomainLimits,
+}
+
+/// Rate limits and concurrency caps the server applies to the sites automations visit,
+/// so they stay polite and don't get the user banned.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolitenessSettings {
+    /// Limits for every site without its own entry.
+    pub default: DomainLimits,
+    /// Per-site limits; the longest matching domain wins.
+    pub domains: Vec<DomainLimit>,
+}
+
+/// How the server has treated one site since it started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainStats {
+    pub domain: String,
+    pub requests: u64,
+    /// Page loads held back by a limit.
+    pub throttled: u64,
+    /// Total time page loads were held back.
+    pub waited_ms: u64,
+    pub in_flight: u64,
+    pub limits: DomainLimits,
+}
+
+fn config(app: &AppHandle) -> Value {
+    serde_json::to_value(app.state::<SettingsStore>().get().politeness).unwrap_or_default()
+}
+
+/// The limits a server spawned now should start with, as `NOVA_DOMAIN_LIMITS`.
+pub fn server_env(app: &AppHandle) -> String {
+    config(app).to_string()
+}
+
+/// Tells a running server about changed limits.
+pub fn apply_settings(app: &AppHandle) {
+    let limits = config(app);
+    if let Err(e) = app.state::<AppState>().control.push_config(serde_json::json!({ "domain_limits": limits })) {
+        log::debug!("Domain limits not pushed to the server: {}", e);
+    }
+}
+
+/// Requests, throttling and limits per site the running server has visited, busiest first.
+#[tauri::command]
+pub async fn get_domain_stats(app: AppHandle) -> Result<Vec<DomainStats>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = app.state::<AppState>().control.domain_stats()?;
+        let mut stats: Vec<DomainStats> = serde_json::from_value(result.get("domains").cloned().unwrap_or_default())
+            .map_err(|e| format!("Unexpected domain stats from the server: {}", e))?;
+        stats.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.domain.cmp(&b.domain)));
+        Ok(stats)
+    })
+    .await
+    .map_err(|e| format!("Failed to get domain stats: {}", e))?
+}
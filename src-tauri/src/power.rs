@@ -0,0 +1,158 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::events;
+use crate::settings::SettingsStore;
+
+/// Shown by the OS where it lists what keeps the computer awake.
+const REASON: &str = "Browser automation jobs are running";
+
+/// Held while jobs run; dropping it lets the system sleep again.
+static ASSERTION: Mutex<Option<Assertion>> = Mutex::new(None);
+
+/// An `NSProcessInfo` activity: no App Nap, no idle system sleep.
+#[cfg(target_os = "macos")]
+struct Assertion(
+    objc2::rc::Retained<objc2::runtime::ProtocolObject<dyn objc2_foundation::NSObjectProtocol>>,
+);
+
+// The activity token is only handed back to NSProcessInfo, which is thread safe
+#[cfg(target_os = "macos")]
+unsafe impl Send for Assertion {}
+
+#[cfg(target_os = "macos")]
+fn acquire() -> Result<Assertion, String> {
+    use objc2_foundation::{NSActivityOptions, NSProcessInfo, NSString};
+    let activity = NSProcessInfo::processInfo()
+        .beginActivityWithOptions_reason(NSActivityOptions::UserInitiated, &NSString::from_str(REASON));
+    Ok(Assertion(activity))
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for Assertion {
+    fn drop(&mut self) {
+        unsafe { objc2_foundation::NSProcessInfo::processInfo().endActivity(&self.0) };
+    }
+}
+
+/// A Windows power request keeping the system out of sleep.
+#[cfg(windows)]
+struct Assertion(windows::Win32::Foundation::HANDLE);
+
+#[cfg(windows)]
+unsafe impl Send for Assertion {}
+
+#[cfg(windows)]
+fn acquire() -> Result<Assertion, String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Power::{PowerCreateRequest, PowerRequestSystemRequired, PowerSetRequest};
+    use windows::Win32::System::Threading::{POWER_REQUEST_CONTEXT_SIMPLE_STRING, REASON_CONTEXT, REASON_CONTEXT_0};
+
+    let mut reason: Vec<u16> = REASON.encode_utf16().chain(Some(0)).collect();
+    let context = REASON_CONTEXT {
+        // POWER_REQUEST_CONTEXT_VERSION
+        Version: 0,
+        Flags: POWER_REQUEST_CONTEXT_SIMPLE_STRING,
+        Reason: REASON_CONTEXT_0 { SimpleReasonString: PWSTR(reason.as_mut_ptr()) },
+    };
+    unsafe {
+        let handle = PowerCreateRequest(&context).map_err(|e| format!("PowerCreateRequest failed: {}", e))?;
+        if let Err(e) = PowerSetRequest(handle, PowerRequestSystemRequired) {
+            let _ = CloseHandle(handle);
+            return Err(format!("PowerSetRequest failed: {}", e));
+        }
+        Ok(Assertion(handle))
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Assertion {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Power::{PowerClearRequest, PowerRequestSystemRequired};
+        unsafe {
+            let _ = PowerClearRequest(self.0, PowerRequestSystemRequired);
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// A logind inhibitor lock; it is released when the file descriptor is closed.
+#[cfg(target_os = "linux")]
+struct Assertion(#[allow(dead_code)] dbus::arg::OwnedFd);
+
+#[cfg(target_os = "linux")]
+fn acquire() -> Result<Assertion, String> {
+    let conn = dbus::blocking::Connection::new_system().map_err(|e| format!("System bus unavailable: {}", e))?;
+    let proxy = conn.with_proxy(
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        std::time::Duration::from_secs(5),
+    );
+    let (fd,): (dbus::arg::OwnedFd,) = proxy
+        .method_call(
+            "org.freedesktop.login1.Manager",
+            "Inhibit",
+            ("sleep:idle", "Browser Automation", REASON, "block"),
+        )
+        .map_err(|e| format!("logind refused the inhibitor: {}", e))?;
+    Ok(Assertion(fd))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+struct Assertion;
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+fn acquire() -> Result<Assertion, String> {
+    Err("Sleep prevention is not supported on this platform".to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerState {
+    /// Sleep (and App Nap on macOS) is held off because jobs are running.
+    pub keeping_awake: bool,
+}
+
+fn state() -> PowerState {
+    PowerState { keeping_awake: ASSERTION.lock().unwrap().is_some() }
+}
+
+fn update(app: &AppHandle, running: usize) {
+    let wanted = running > 0 && app.state::<SettingsStore>().get().prevent_sleep;
+    {
+        let mut assertion = ASSERTION.lock().unwrap();
+        if assertion.is_some() == wanted {
+            return;
+        }
+        if wanted {
+            match acquire() {
+                Ok(acquired) => *assertion = Some(acquired),
+                Err(e) => {
+                    log::warn!("Cannot keep the system awake: {}", e);
+                    return;
+                }
+            }
+        } else {
+            *assertion = None;
+        }
+    }
+    log::info!("{}", if wanted { "Keeping the system awake while jobs run" } else { "System may sleep again" });
+    let _ = app.emit("power-state-changed", state());
+}
+
+/// Takes a power assertion while any job runs and releases it when all are done.
+pub fn jobs_changed(app: &AppHandle, running: usize) {
+    update(app, running);
+}
+
+/// Applies a change of the `prevent_sleep` setting to jobs already running.
+pub fn apply_settings(app: &AppHandle) {
+    update(app, events::running_count());
+}
+
+#[tauri::command]
+pub fn get_power_state() -> PowerState {
+    state()
+}
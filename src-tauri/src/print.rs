@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::webview::PlatformWebview;
+use tauri::WebviewWindow;
+use tauri_plugin_dialog::DialogExt;
+
+/// Long reports can take a while to lay out, but a stuck render should not hang the caller.
+const PDF_TIMEOUT: Duration = Duration::from_secs(60);
+
+type Done = mpsc::Sender<Result<(), String>>;
+
+/// Starts rendering the page to a PDF at `path`. Runs on the main thread; the outcome is
+/// sent on `done` once the platform webview has finished writing.
+#[cfg(target_os = "linux")]
+fn start_pdf(webview: PlatformWebview, path: &Path, done: Done) {
+    use webkit2gtk::{PrintOperation, PrintOperationExt};
+
+    let uri = match gtk::glib::filename_to_uri(path, None) {
+        Ok(uri) => uri,
+        Err(e) => {
+            let _ = done.send(Err(format!("Invalid PDF path: {}", e)));
+            return;
+        }
+    };
+    let settings = gtk::PrintSettings::new();
+    settings.set_printer("Print to File");
+    settings.set(gtk::PRINT_SETTINGS_OUTPUT_FILE_FORMAT, Some("pdf"));
+    settings.set(gtk::PRINT_SETTINGS_OUTPUT_URI, Some(&uri));
+
+    let operation = PrintOperation::new(&webview.inner());
+    operation.set_print_settings(&settings);
+    let failed = done.clone();
+    operation.connect_failed(move |_, error| {
+        let _ = failed.send(Err(error.to_string()));
+    });
+    // Also emitted after a failure; only the first outcome is read
+    operation.connect_finished(move |_| {
+        let _ = done.send(Ok(()));
+    });
+    operation.print();
+}
+
+#[cfg(windows)]
+fn start_pdf(webview: PlatformWebview, path: &Path, done: Done) {
+    use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2_7;
+    use webview2_com::PrintToPdfCompletedHandler;
+    use windows::core::{Interface, HSTRING, PCWSTR};
+
+    let path = HSTRING::from(path);
+    let completed = done.clone();
+    let handler = PrintToPdfCompletedHandler::create(Box::new(move |result, written| {
+        let _ = completed.send(match result {
+            Err(e) => Err(e.message()),
+            Ok(()) if !written => Err("The page could not be saved as PDF".to_string()),
+            Ok(()) => Ok(()),
+        });
+        Ok(())
+    }));
+    let started = unsafe {
+        webview
+            .controller()
+            .CoreWebView2()
+            .and_then(|webview| webview.cast::<ICoreWebView2_7>())
+            .and_then(|webview| webview.PrintToPdf(PCWSTR(path.as_ptr()), None, &handler))
+    };
+    if let Err(e) = started {
+        let _ = done.send(Err(format!("PDF export is not supported by this WebView2 runtime: {}", e)));
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn start_pdf(webview: PlatformWebview, path: &Path, done: Done) {
+    use block2::RcBlock;
+    use objc2_foundation::{NSData, NSError, NSString};
+    use objc2_web_kit::WKWebView;
+
+    let path = NSString::from_str(&path.to_string_lossy());
+    let handler = RcBlock::new(move |data: *mut NSData, error: *mut NSError| {
+        let result = match unsafe { (data.as_ref(), error.as_ref()) } {
+            (Some(data), _) if data.writeToFile_atomically(&path, true) => Ok(()),
+            (Some(_), _) => Err(format!("Failed to write {}", path)),
+            (None, Some(error)) => Err(error.localizedDescription().to_string()),
+            (None, None) => Err("The page could not be saved as PDF".to_string()),
+        };
+        let _ = done.send(result);
+    });
+    unsafe {
+        let webview: &WKWebView = &*webview.inner().cast();
+        webview.createPDFWithConfiguration_completionHandler(None, &handler);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+fn start_pdf(_webview: PlatformWebview, _path: &Path, done: Done) {
+    let _ = done.send(Err("PDF export is not supported on this platform".to_string()));
+}
+
+fn write_pdf(window: &WebviewWindow, path: &Path) -> Result<(), String> {
+    let (done, finished) = mpsc::channel();
+    let target = path.to_path_buf();
+    window
+        .with_webview(move |webview| start_pdf(webview, &target, done))
+        .map_err(|e| format!("Failed to reach the webview: {}", e))?;
+    finished
+        .recv_timeout(PDF_TIMEOUT)
+        .map_err(|_| "Timed out waiting for the PDF".to_string())?
+}
+
+fn ask_path(window: &WebviewWindow) -> Option<PathBuf> {
+    window
+        .dialog()
+        .file()
+        .set_title("Export as PDF")
+        .set_file_name("report.pdf")
+        .add_filter("PDF", &["pdf"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+}
+
+/// Opens the system print dialog for the calling window's page.
+#[tauri::command]
+pub fn print_current_page(window: WebviewWindow) -> Result<(), String> {
+    window.print().map_err(|e| format!("Failed to print: {}", e))
+}
+
+/// Saves the calling window's page as a PDF. Without a `path` a save dialog is shown;
+/// returns the path written, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_page_pdf(window: WebviewWindow, path: Option<PathBuf>) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(path) = path.or_else(|| ask_path(&window)) else {
+            return Ok(None);
+        };
+        if !path.is_absolute() {
+            return Err(format!("PDF path must be absolute: {}", path.display()));
+        }
+        write_pdf(&window, &path)?;
+        log::info!("Saved {} as PDF to {}", window.label(), path.display());
+        Ok(Some(path))
+    })
+    .await
+    .map_err(|e| format!("PDF export failed: {}", e))?
+}
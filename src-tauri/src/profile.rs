@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::settings::{self, Sections, Settings, SettingsStore};
+use crate::{crypto, email, history, secrets, security};
+
+/// Newest profile archive format this version reads.
+const FORMAT_VERSION: u32 = 1;
+const EXTENSION: &str = "baprofile";
+
+const MANIFEST: &str = "manifest.json";
+const SETTINGS: &str = "settings.json";
+/// Keychain secrets and sensitive settings sections, sealed with a key derived from the
+/// export passphrase.
+const CREDENTIALS: &str = "credentials.enc";
+
+/// Keychain entries that make sense on another machine. Machine-bound ones such as the
+/// control API token are left behind.
+const PORTABLE_SECRETS: &[&str] = &[email::PASSWORD_KEY];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format: u32,
+    app_version: String,
+    /// Unix milliseconds.
+    created_at: i64,
+    includes_credentials: bool,
+}
+
+/// Contents of the sealed part of the archive.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SealedPayload {
+    #[serde(default)]
+    secrets: BTreeMap<String, String>,
+    #[serde(default)]
+    sections: Sections,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub schedules: usize,
+    pub webhooks: usize,
+    pub credentials_imported: usize,
+    /// The archive has credentials but no passphrase was given to unlock them.
+    pub credentials_skipped: bool,
+}
+
+fn seal_payload(passphrase: &str, sections: Sections) -> Result<Vec<u8>, String> {
+    let mut payload = SealedPayload { sections, ..Default::default() };
+    for key in PORTABLE_SECRETS {
+        if let Some(value) = secrets::get(key)? {
+            payload.secrets.insert(key.to_string(), value);
+        }
+    }
+    let plaintext = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+    let salt = crypto::random_bytes::<{ crypto::SALT_LEN }>()?;
+    let mut sealed = salt.to_vec();
+    sealed.extend(crypto::seal(&crypto::key_from_passphrase(passphrase, &salt), &plaintext)?);
+    Ok(sealed)
+}
+
+fn open_payload(passphrase: &str, sealed: &[u8]) -> Result<SealedPayload, String> {
+    if sealed.len() < crypto::SALT_LEN {
+        return Err("Credentials in the profile are damaged".to_string());
+    }
+    let (salt, sealed) = sealed.split_at(crypto::SALT_LEN);
+    let plaintext = crypto::open(&crypto::key_from_passphrase(passphrase, salt), sealed)
+        .map_err(|_| "Wrong passphrase for the profile credentials".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Invalid credentials in profile: {}", e))
+}
+
+fn write_archive(path: &Path, settings: &Sections, credentials: Option<Vec<u8>>) -> Result<(), String> {
+    let manifest = Manifest {
+        format: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: history::now_ms(),
+        includes_credentials: credentials.is_some(),
+    };
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let mut add = |name: &str, contents: &[u8]| -> Result<(), String> {
+        zip.start_file(name, SimpleFileOptions::default()).map_err(|e| e.to_string())?;
+        zip.write_all(contents).map_err(|e| e.to_string())
+    };
+    add(MANIFEST, &serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?)?;
+    add(SETTINGS, &serde_json::to_vec_pretty(settings).map_err(|e| e.to_string())?)?;
+    if let Some(credentials) = credentials {
+        add(CREDENTIALS, &credentials)?;
+    }
+    zip.finish().map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {} from profile: {}", name, e)),
+    };
+    let mut contents = Vec::new();
+    entry.read_to_end(&mut contents).map_err(|e| format!("Failed to read {} from profile: {}", name, e))?;
+    Ok(Some(contents))
+}
+
+fn export(app: &AppHandle, path: &Path, passphrase: Option<&str>) -> Result<(), String> {
+    // Sensitive sections only leave the machine encrypted, so without a passphrase they
+    // are not exported at all
+    let (settings, sensitive) = app.state::<SettingsStore>().get().split_sensitive()?;
+    let credentials = passphrase
+        .filter(|p| !p.is_empty())
+        .map(|passphrase| seal_payload(passphrase, sensitive))
+        .transpose()?;
+    write_archive(path, &settings, credentials)?;
+    log::info!("Profile exported to {}", path.display());
+    Ok(())
+}
+
+fn import(app: &AppHandle, path: &Path, passphrase: Option<&str>) -> Result<ImportSummary, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("{} is not a profile archive: {}", path.display(), e))?;
+
+    let manifest: Manifest = read_entry(&mut archive, MANIFEST)?
+        .ok_or("Profile has no manifest")
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|_| "Profile manifest is invalid"))?;
+    if manifest.format > FORMAT_VERSION {
+        return Err(format!("Profile was exported by a newer version ({}), please update", manifest.app_version));
+    }
+    let mut settings: Sections = read_entry(&mut archive, SETTINGS)?
+        .ok_or("Profile has no settings")
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|_| "Profile settings are invalid"))?;
+
+    // Unlock credentials before changing anything so a wrong passphrase leaves no trace
+    let sealed = read_entry(&mut archive, CREDENTIALS)?;
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    let payload = match (&sealed, passphrase) {
+        (Some(sealed), Some(passphrase)) => open_payload(passphrase, sealed)?,
+        _ => SealedPayload::default(),
+    };
+    settings.extend(payload.sections);
+    let settings: Settings =
+        serde_json::from_value(settings.into()).map_err(|e| format!("Profile settings are invalid: {}", e))?;
+
+    let summary = ImportSummary {
+        schedules: settings.schedules.len(),
+        webhooks: settings.webhooks.len(),
+        credentials_imported: payload.secrets.len(),
+        credentials_skipped: sealed.is_some() && passphrase.is_none(),
+    };
+    settings::apply(app, settings)?;
+    for (key, value) in payload.secrets.iter().filter(|(key, _)| PORTABLE_SECRETS.contains(&key.as_str())) {
+        secrets::set(key, value)?;
+    }
+    log::info!(
+        "Profile imported from {} (exported by version {})",
+        path.display(),
+        manifest.app_version
+    );
+    Ok(summary)
+}
+
+fn ask_path(app: &AppHandle, save: bool) -> Option<PathBuf> {
+    let dialog = app
+        .dialog()
+        .file()
+        .add_filter("Browser Automation Profile", &[EXTENSION]);
+    let path = if save {
+        dialog.set_title("Export Profile").set_file_name(format!("profile.{}", EXTENSION)).blocking_save_file()
+    } else {
+        dialog.set_title("Import Profile").blocking_pick_file()
+    };
+    path.and_then(|path| path.into_path().ok())
+}
+
+/// Writes settings and schedules to a portable archive. With a `passphrase`, keychain
+/// credentials and sensitive settings sections are included, encrypted with it, after the
+/// user confirms who they are. Without a `path` a save dialog is shown; returns the path
+/// written, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_profile(
+    app: AppHandle,
+    path: Option<PathBuf>,
+    passphrase: Option<String>,
+) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if passphrase.is_some() {
+            security::authenticate("export your saved credentials")?;
+        }
+        let Some(path) = path.or_else(|| ask_path(&app, true)) else {
+            return Ok(None);
+        };
+        export(&app, &path, passphrase.as_deref())?;
+        Ok(Some(path))
+    })
+    .await
+    .map_err(|e| format!("Profile export failed: {}", e))?
+}
+
+/// Replaces the current settings with those from a profile archive. Credentials are
+/// imported only when the export `passphrase` is given. Returns `None` if the dialog was
+/// cancelled.
+#[tauri::command]
+pub async fn import_profile(
+    app: AppHandle,
+    path: Option<PathBuf>,
+    passphrase: Option<String>,
+) -> Result<Option<ImportSummary>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(path) = path.or_else(|| ask_path(&app, false)) else {
+            return Ok(None);
+        };
+        import(&app, &path, passphrase.as_deref()).map(Some)
+    })
+    .await
+    .map_err(|e| format!("Profile import failed: {}", e))?
+}
@@ -0,0 +1,130 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Webview};
+
+use crate::access::{self, Capability};
+use crate::instances::ServerManager;
+use crate::{domain_policy, server, AppState};
+
+/// How long a request keeps being retried while the server restarts.
+const RETRY_WINDOW: Duration = Duration::from_secs(20);
+const RETRY_INTERVAL: Duration = Duration::from_millis(500);
+/// Automation requests block until the job is done, so reads may take a long time.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: String,
+}
+
+impl From<ureq::Response> for ProxyResponse {
+    fn from(response: ureq::Response) -> Self {
+        let status = response.status();
+        let content_type = response.content_type().to_string();
+        let body = response.into_string().unwrap_or_default();
+        ProxyResponse { status, content_type, body }
+    }
+}
+
+fn idempotent(method: &str) -> bool {
+    matches!(method, "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS")
+}
+
+/// The port of the server behind `window`: its workspace's, or wherever the main server is
+/// right now.
+fn port_for(app: &AppHandle, window: &str) -> u16 {
+    app.state::<ServerManager>()
+        .port_for_window(window)
+        .unwrap_or_else(|| *app.state::<AppState>().server_port.lock().unwrap())
+}
+
+/// Sends a request to the server behind `window`. Requests that never reached the server
+/// are retried, as are idempotent ones that failed mid-way or hit a 502/503, until
+/// `RETRY_WINDOW` runs out. The port is looked up again on every attempt, so a restart
+/// onto a new port is followed transparently.
+fn forward(
+    app: &AppHandle,
+    window: &str,
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+) -> Result<ProxyResponse, String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(2))
+        .timeout(REQUEST_TIMEOUT)
+        .build();
+    let started = Instant::now();
+
+    loop {
+        let port = port_for(app, window);
+        let request = agent
+            .request(method, &format!("{}{}", server::server_url(port), path))
+            .set("Content-Type", "application/json");
+        let outcome = match body {
+            Some(body) => request.send_string(body),
+            None => request.call(),
+        };
+
+        let retry = match &outcome {
+            Ok(_) => false,
+            Err(ureq::Error::Status(code, _)) => idempotent(method) && (*code == 502 || *code == 503),
+            Err(ureq::Error::Transport(t)) => t.kind() == ureq::ErrorKind::ConnectionFailed || idempotent(method),
+        };
+        if !retry || started.elapsed() > RETRY_WINDOW {
+            return match outcome {
+                Ok(response) | Err(ureq::Error::Status(_, response)) => Ok(response.into()),
+                Err(e) => Err(format!("{} {} failed: {}", method, path, e)),
+            };
+        }
+
+        log::debug!("{} {} on port {} failed, retrying", method, path, port);
+        std::thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+/// `path` without its query, fragment or trailing slash, e.g. `/api/execute_automation`
+/// for `/api/execute_automation/?wait=1`.
+fn endpoint(path: &str) -> &str {
+    let end = path.find(['?', '#']).unwrap_or(path.len());
+    path[..end].trim_end_matches('/')
+}
+
+/// Frontend entry point for server API calls; the UI is bundled with the app and reaches
+/// the server only through here. `path` is relative to the server root, e.g.
+/// `/api/execute_automation`. Workspace windows reach their own server. Anything that may
+/// start a job, i.e. the automation endpoint or any write carrying a prompt, is checked
+/// against the domain policy first.
+#[tauri::command]
+pub async fn api_request(
+    app: AppHandle,
+    webview: Webview,
+    method: String,
+    path: String,
+    body: Option<String>,
+) -> Result<ProxyResponse, String> {
+    let method = method.to_uppercase();
+    if !path.starts_with('/') {
+        return Err(format!("Invalid API path: {}", path));
+    }
+    // Anything but a read may start a job or change data
+    let write = !matches!(method.as_str(), "GET" | "HEAD" | "OPTIONS");
+    if write {
+        access::check(&app, Capability::Operate, &format!("{} {}", method, path))?;
+    }
+    let window = webview.label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        if write {
+            let request: serde_json::Value =
+                body.as_deref().and_then(|body| serde_json::from_str(body).ok()).unwrap_or_default();
+            let prompt = request["prompt"].as_str();
+            if prompt.is_some() || endpoint(&path) == "/api/execute_automation" {
+                domain_policy::check(&app, prompt.unwrap_or_default())?;
+            }
+        }
+        forward(&app, &window, &method, &path, body.as_deref())
+    })
+    .await
+    .map_err(|e| format!("API request failed: {}", e))?
+}
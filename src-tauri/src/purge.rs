@@ -0,0 +1,159 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::history::History;
+use crate::instances::ServerManager;
+use crate::settings::SettingsStore;
+use crate::{
+    captcha, control_api, cookie_jars, email, git_sync, license, packaging, secrets, security, totp, uploads, variables,
+    webview_data, AppState,
+};
+
+/// How long a confirmation token from `request_purge_token` stays valid.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(120);
+/// Keychain entries the app writes under fixed names; secret variables come on top.
+pub const KEYCHAIN_KEYS: &[&str] = &[
+    email::PASSWORD_KEY,
+    git_sync::TOKEN_KEY,
+    uploads::CREDENTIALS_KEY,
+    license::TOKEN_KEY,
+    license::MACHINE_KEY,
+    control_api::TOKEN_KEY,
+    cookie_jars::KEY_SECRET,
+    captcha::API_KEY_SECRET,
+];
+
+/// The outstanding confirmation token and when it was issued. Used once.
+static PENDING: Mutex<Option<(String, Instant)>> = Mutex::new(None);
+
+/// Overwrites a file with zeros before removing it, so its contents don't linger in free
+/// space. Best effort: SSDs and copy-on-write filesystems may keep old blocks anyway.
+fn shred_file(path: &Path) -> std::io::Result<()> {
+    let mut remaining = std::fs::metadata(path)?.len();
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let zeros = [0u8; 64 * 1024];
+    while remaining > 0 {
+        let chunk = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()?;
+    drop(file);
+    std::fs::remove_file(path)
+}
+
+/// Shreds everything under `path` except `keep`, collecting what couldn't be removed.
+pub fn shred(path: &Path, keep: &[PathBuf], failed: &mut Vec<String>) {
+    if keep.iter().any(|kept| kept == path) {
+        return;
+    }
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return;
+    };
+    let result = if metadata.is_dir() {
+        for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+            shred(&entry.path(), keep, failed);
+        }
+        // Still holds kept entries, or whatever failed
+        match std::fs::remove_dir(path) {
+            Err(_) if keep.iter().any(|kept| kept.starts_with(path)) => Ok(()),
+            result => result,
+        }
+    } else if metadata.is_file() {
+        shred_file(path)
+    } else {
+        // Symlinks are removed, never followed
+        std::fs::remove_file(path)
+    };
+    if let Err(e) = result {
+        failed.push(format!("{}: {}", path.display(), e));
+    }
+}
+
+/// Every directory the app or its server keeps user data in.
+fn data_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let path = app.path();
+    let mut dirs: Vec<PathBuf> = [
+        path.app_data_dir(),
+        path.app_local_data_dir(),
+        path.app_config_dir(),
+        path.app_cache_dir(),
+        path.app_log_dir(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    dirs.extend(packaging::browser_profile_dir());
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+fn purge(app: &AppHandle) -> Result<(), String> {
+    log::warn!("Purging all user data");
+    app.state::<ServerManager>().stop_all();
+    app.state::<AppState>().cleanup_server();
+
+    // The webview holds its storage open; it is cleared through the webview instead
+    for webview in app.webview_windows().into_values() {
+        if let Err(e) = webview.clear_all_browsing_data() {
+            log::warn!("Failed to clear webview data: {}", e);
+        }
+    }
+    let mut failed = Vec::new();
+    let settings = app.state::<SettingsStore>().get();
+    let mut keys = variables::secret_keys(&settings.variables);
+    keys.extend(totp::seed_keys(&settings.totp_seeds));
+    for key in KEYCHAIN_KEYS.iter().copied().chain(keys.iter().map(String::as_str)) {
+        if let Err(e) = secrets::delete(key) {
+            failed.push(e);
+        }
+    }
+
+    app.state::<History>().close();
+    let keep = webview_data::webview_data_paths(app);
+    for dir in data_dirs(app) {
+        shred(&dir, &keep, &mut failed);
+    }
+    if !failed.is_empty() {
+        log::error!("Purge left {} items behind", failed.len());
+        return Err(format!("Some data could not be removed:\n{}", failed.join("\n")));
+    }
+    Ok(())
+}
+
+/// Issues the token `purge_all_user_data` must be called with, so a purge always takes
+/// two deliberate steps.
+#[tauri::command]
+pub fn request_purge_token() -> String {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    *PENDING.lock().unwrap() = Some((token.clone(), Instant::now()));
+    token
+}
+
+/// Stops the server and deletes everything the app stores: settings, recipes, run history
+/// and artifacts, browser and webview profiles, logs and keychain entries. Files are
+/// overwritten before removal. The app then restarts as on first run. The user confirms
+/// who they are with the OS first.
+#[tauri::command]
+pub async fn purge_all_user_data(app: AppHandle, confirm_token: String) -> Result<(), String> {
+    let pending = PENDING.lock().unwrap().take();
+    match pending {
+        Some((token, issued)) if token == confirm_token && issued.elapsed() < TOKEN_LIFETIME => {}
+        Some(_) => return Err("The confirmation has expired or does not match; request a new one".to_string()),
+        None => return Err("Request a confirmation token first".to_string()),
+    }
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        security::authenticate("delete all of your data")?;
+        purge(&handle)
+    })
+        .await
+        .map_err(|e| format!("Purge failed: {}", e))??;
+    log::info!("All user data purged, restarting");
+    app.restart()
+}
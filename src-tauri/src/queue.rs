@@ -0,0 +1,175 @@
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::history::{self, History, QueuedJob};
+use crate::{access, events, governor, jobs, launch, server, AppState};
+
+/// How often the dispatcher looks for a free slot.
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+const PAUSED_KEY: &str = "queue_paused";
+
+/// Sent as `queue-changed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueState {
+    pub paused: bool,
+    pub jobs: Vec<QueuedJob>,
+}
+
+fn is_paused(history: &History) -> bool {
+    history.meta(PAUSED_KEY).ok().flatten().is_some_and(|value| value == "1")
+}
+
+fn state(history: &History) -> Result<QueueState, String> {
+    Ok(QueueState { paused: is_paused(history), jobs: history.queue()? })
+}
+
+fn changed(app: &AppHandle) {
+    match state(&app.state::<History>()) {
+        Ok(state) => {
+            let _ = app.emit("queue-changed", state);
+        }
+        Err(e) => log::warn!("Failed to read the run queue: {}", e),
+    }
+}
+
+/// Hands the next waiting job to the server, if it is up and below the concurrency limit.
+fn dispatch_next(app: &AppHandle) -> Result<(), String> {
+    // Jobs queued before a viewer mode launch wait for a normal one
+    if launch::is_viewer(app) {
+        return Ok(());
+    }
+    let history = app.state::<History>();
+    if is_paused(&history) || events::running_count() >= governor::limit() {
+        return Ok(());
+    }
+    let Some(job) = history.queue()?.into_iter().find(|job| job.started_at.is_none()) else {
+        return Ok(());
+    };
+    let port = *app.state::<AppState>().server_port.lock().unwrap();
+    if !server::is_ready(port) {
+        return Ok(());
+    }
+    history.mark_dispatched(&job.id, history::now_ms())?;
+    log::info!("Dispatching queued job {}", job.id);
+    changed(app);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        jobs::run(&app, port, job.id.clone(), &job.prompt, job.schedule_id, job.recipe_id);
+        if let Err(e) = app.state::<History>().remove_from_queue(&job.id) {
+            log::warn!("Failed to remove job {} from the queue: {}", job.id, e);
+        }
+        changed(&app);
+    });
+    Ok(())
+}
+
+/// Starts the dispatcher. Jobs still marked running belonged to a server that is gone,
+/// so they wait their turn again.
+pub fn setup(app: &AppHandle) {
+    match app.state::<History>().requeue_dispatched() {
+        Ok(0) => {}
+        Ok(n) => log::info!("Requeued {} jobs interrupted by the last exit", n),
+        Err(e) => {
+            log::warn!("Run queue unavailable: {}", e);
+            return;
+        }
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK_INTERVAL);
+        if let Err(e) = dispatch_next(&app) {
+            log::warn!("Run queue dispatch failed: {}", e);
+        }
+    });
+}
+
+/// Queues a prompt to run once the server has a free slot. Higher `priority` jobs go
+/// ahead of lower ones already waiting. `recipe_id` names the recipe the prompt came from.
+pub fn enqueue(
+    app: &AppHandle,
+    prompt: String,
+    priority: i32,
+    schedule_id: Option<String>,
+    recipe_id: Option<String>,
+) -> Result<QueuedJob, String> {
+    if launch::is_viewer(app) {
+        return Err(access::refusal("running jobs"));
+    }
+    if prompt.trim().is_empty() {
+        return Err("Cannot queue an empty prompt".to_string());
+    }
+    let job = QueuedJob {
+        id: jobs::new_job_id(),
+        prompt,
+        schedule_id,
+        recipe_id,
+        priority,
+        enqueued_at: history::now_ms(),
+        started_at: None,
+    };
+    app.state::<History>().enqueue(&job)?;
+    changed(app);
+    Ok(job)
+}
+
+#[tauri::command]
+pub fn enqueue_job(
+    app: AppHandle,
+    prompt: String,
+    priority: Option<i32>,
+    schedule_id: Option<String>,
+    recipe_id: Option<String>,
+) -> Result<QueuedJob, String> {
+    enqueue(&app, prompt, priority.unwrap_or(0), schedule_id, recipe_id)
+}
+
+#[tauri::command]
+pub fn list_queue(history: State<'_, History>) -> Result<QueueState, String> {
+    state(&history)
+}
+
+/// Stops dispatching new jobs; running ones finish. Stays paused across restarts.
+#[tauri::command]
+pub fn pause_queue(app: AppHandle) -> Result<(), String> {
+    app.state::<History>().set_meta(PAUSED_KEY, "1")?;
+    log::info!("Run queue paused");
+    changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_queue(app: AppHandle) -> Result<(), String> {
+    app.state::<History>().set_meta(PAUSED_KEY, "0")?;
+    log::info!("Run queue resumed");
+    changed(&app);
+    Ok(())
+}
+
+/// Sets the order of waiting jobs; any not in `ids` keep their order after them.
+#[tauri::command]
+pub fn reorder_queue(app: AppHandle, ids: Vec<String>) -> Result<(), String> {
+    app.state::<History>().reorder_queue(&ids)?;
+    changed(&app);
+    Ok(())
+}
+
+/// Removes a job that has not started yet; false if it is not waiting.
+pub fn cancel(app: &AppHandle, id: &str) -> Result<bool, String> {
+    if !app.state::<History>().remove_waiting(id)? {
+        return Ok(false);
+    }
+    log::info!("Queued job {} cancelled", id);
+    changed(app);
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn cancel_queued_job(app: AppHandle, id: String) -> Result<(), String> {
+    if !cancel(&app, &id)? {
+        return Err(format!("Job {} is not waiting in the queue", id));
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::{history, tasks};
+
+/// Entries kept in the app's own recent list.
+const MAX_RECENT: usize = 10;
+
+/// Serializes read-modify-write of `recent-tasks.json`.
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentTask {
+    pub path: PathBuf,
+    pub name: String,
+    /// Unix milliseconds.
+    pub opened_at: i64,
+}
+
+fn recent_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join("recent-tasks.json"))
+}
+
+fn load(app: &AppHandle) -> Vec<RecentTask> {
+    recent_path(app)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(app: &AppHandle, entries: &[RecentTask]) -> Result<(), String> {
+    let path = recent_path(app).ok_or("Recent tasks location is unavailable")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to save recent tasks: {}", e))
+}
+
+/// Adds the file to the OS recent documents: the Dock menu on macOS, the taskbar jump
+/// list on Windows and the desktop's recent files on Linux.
+#[cfg(target_os = "macos")]
+fn note_with_os(path: &Path) {
+    use objc2_app_kit::NSDocumentController;
+    use objc2_foundation::{MainThreadMarker, NSString, NSURL};
+
+    let Some(mtm) = MainThreadMarker::new() else {
+        return;
+    };
+    let url = NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy()));
+    NSDocumentController::sharedDocumentController(mtm).noteNewRecentDocumentURL(&url);
+}
+
+#[cfg(windows)]
+fn note_with_os(path: &Path) {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::UI::Shell::{SHAddToRecentDocs, SHARD_PATHW};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    unsafe { SHAddToRecentDocs(SHARD_PATHW.0 as u32, Some(wide.as_ptr().cast())) };
+}
+
+#[cfg(target_os = "linux")]
+fn note_with_os(path: &Path) {
+    use gtk::prelude::RecentManagerExt;
+
+    let Some(manager) = gtk::RecentManager::default() else {
+        return;
+    };
+    match gtk::glib::filename_to_uri(path, None) {
+        Ok(uri) => {
+            manager.add_item(&uri);
+        }
+        Err(e) => log::debug!("Not adding {} to recent files: {}", path.display(), e),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+fn note_with_os(_path: &Path) {}
+
+/// Removes the app's entries from the OS recent documents.
+#[cfg(target_os = "macos")]
+fn clear_with_os(_entries: &[RecentTask]) {
+    use objc2_app_kit::NSDocumentController;
+    use objc2_foundation::MainThreadMarker;
+
+    if let Some(mtm) = MainThreadMarker::new() {
+        unsafe { NSDocumentController::sharedDocumentController(mtm).clearRecentDocuments(None) };
+    }
+}
+
+#[cfg(windows)]
+fn clear_with_os(_entries: &[RecentTask]) {
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::{ApplicationDestinations, IApplicationDestinations};
+
+    let result = unsafe {
+        CoCreateInstance::<_, IApplicationDestinations>(&ApplicationDestinations, None, CLSCTX_INPROC_SERVER)
+            .and_then(|destinations| destinations.RemoveAllDestinations())
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to clear the jump list: {}", e);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn clear_with_os(entries: &[RecentTask]) {
+    use gtk::prelude::RecentManagerExt;
+
+    let Some(manager) = gtk::RecentManager::default() else {
+        return;
+    };
+    for entry in entries {
+        if let Ok(uri) = gtk::glib::filename_to_uri(&entry.path, None) {
+            let _ = manager.remove_item(&uri);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+fn clear_with_os(_entries: &[RecentTask]) {}
+
+/// Records a task file as the most recently used one.
+pub fn add(app: &AppHandle, path: &Path, name: &str) -> Result<(), String> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    {
+        let _guard = LOCK.lock().unwrap();
+        let mut entries = load(app);
+        entries.retain(|entry| entry.path != path);
+        entries.insert(0, RecentTask { path: path.clone(), name: name.to_string(), opened_at: history::now_ms() });
+        entries.truncate(MAX_RECENT);
+        save(app, &entries)?;
+    }
+    // The OS APIs all expect the UI thread
+    app.run_on_main_thread(move || note_with_os(&path)).map_err(|e| e.to_string())
+}
+
+pub fn clear(app: &AppHandle) -> Result<(), String> {
+    let entries = {
+        let _guard = LOCK.lock().unwrap();
+        let entries = load(app);
+        save(app, &[])?;
+        entries
+    };
+    app.run_on_main_thread(move || clear_with_os(&entries)).map_err(|e| e.to_string())
+}
+
+/// Recently opened task files, newest first. Files that no longer exist are left out.
+#[tauri::command]
+pub fn list_recent_tasks(app: AppHandle) -> Vec<RecentTask> {
+    load(&app).into_iter().filter(|entry| entry.path.exists()).collect()
+}
+
+#[tauri::command]
+pub fn add_recent_task(app: AppHandle, path: PathBuf) -> Result<(), String> {
+    let task = tasks::load(&path)?;
+    add(&app, &path, &task.display_name(&path))
+}
+
+#[tauri::command]
+pub fn clear_recent_tasks(app: AppHandle) -> Result<(), String> {
+    clear(&app)?;
+    log::info!("Recent tasks cleared");
+    Ok(())
+}
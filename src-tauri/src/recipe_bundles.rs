@@ -0,0 +1,167 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::{Cursor, Read};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use zip::ZipArchive;
+
+use crate::recipes::{self, Recipe};
+use crate::settings::SettingsStore;
+use crate::tasks::{self, TaskFile};
+
+/// Bundles are a handful of small JSON files; anything bigger is refused.
+const MAX_BUNDLE_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_RECIPES: usize = 100;
+/// Newest bundle format this version understands.
+const FORMAT_VERSION: u32 = 1;
+
+/// `manifest.json` of a recipe bundle: a zip holding it and the `.nova` files it lists.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    format: u32,
+    name: String,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    recipes: Vec<String>,
+}
+
+fn fetch(agent: &ureq::Agent, url: &str) -> Result<Option<Vec<u8>>, String> {
+    let response = match agent.get(url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(format!("Failed to download {}: {}", url, e)),
+    };
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_BUNDLE_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if bytes.len() as u64 > MAX_BUNDLE_SIZE {
+        return Err(format!("{} is too large to be a recipe bundle", url));
+    }
+    Ok(Some(bytes))
+}
+
+/// Accepts the bundle if `<url>.sig` is a valid Ed25519 signature by a trusted key, or if
+/// it matches the SHA-256 checksum the user got alongside the link.
+fn verify(
+    agent: &ureq::Agent,
+    url: &str,
+    bundle: &[u8],
+    sha256: Option<&str>,
+    trusted: &[String],
+) -> Result<(), String> {
+    if let Some(expected) = sha256 {
+        let actual = format!("{:x}", Sha256::digest(bundle));
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err("The recipe bundle does not match its checksum".to_string());
+        }
+        return Ok(());
+    }
+    let signature = fetch(agent, &format!("{}.sig", url))?
+        .ok_or("The recipe bundle is not signed; install it with its checksum instead")?;
+    let signature = BASE64
+        .decode(String::from_utf8_lossy(&signature).trim())
+        .map_err(|_| "The recipe bundle's signature is malformed")?;
+    let signed_by_trusted = trusted.iter().any(|key| {
+        BASE64
+            .decode(key)
+            .is_ok_and(|key| UnparsedPublicKey::new(&ED25519, key).verify(bundle, &signature).is_ok())
+    });
+    if !signed_by_trusted {
+        return Err("The recipe bundle is not signed by a trusted publisher".to_string());
+    }
+    Ok(())
+}
+
+/// Reads and checks everything in the bundle before anything is installed: the manifest
+/// must be well-formed, list only plain `.nova` names, and account for every entry.
+fn read_bundle(bundle: &[u8]) -> Result<(Manifest, Vec<TaskFile>), String> {
+    let mut zip = ZipArchive::new(Cursor::new(bundle)).map_err(|e| format!("Not a recipe bundle: {}", e))?;
+    let mut read_entry = |name: &str| -> Result<String, String> {
+        let entry = zip.by_name(name).map_err(|_| format!("The recipe bundle has no {}", name))?;
+        let mut contents = String::new();
+        entry
+            .take(MAX_BUNDLE_SIZE)
+            .read_to_string(&mut contents)
+            .map_err(|e| format!("Failed to read {} from the bundle: {}", name, e))?;
+        Ok(contents)
+    };
+
+    let manifest: Manifest = serde_json::from_str(&read_entry("manifest.json")?)
+        .map_err(|e| format!("The bundle's manifest is invalid: {}", e))?;
+    if manifest.format > FORMAT_VERSION {
+        return Err(format!("The recipe bundle needs a newer version of the app (format {})", manifest.format));
+    }
+    if manifest.name.trim().is_empty() || manifest.recipes.is_empty() || manifest.recipes.len() > MAX_RECIPES {
+        return Err(format!("The bundle's manifest must name itself and list 1 to {} recipes", MAX_RECIPES));
+    }
+    for file in &manifest.recipes {
+        let plain = file.ends_with(&format!(".{}", tasks::EXTENSION))
+            && !file.starts_with('.')
+            && !file.contains(['/', '\\', ':'])
+            && !file.contains("..");
+        if !plain {
+            return Err(format!("The bundle lists an invalid recipe file '{}'", file));
+        }
+    }
+
+    let tasks = manifest
+        .recipes
+        .iter()
+        .map(|file| tasks::parse(&read_entry(file)?, file))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let unexpected =
+        zip.file_names().find(|name| *name != "manifest.json" && !manifest.recipes.iter().any(|f| f == name));
+    if let Some(name) = unexpected {
+        return Err(format!("The recipe bundle contains an unexpected file '{}'", name));
+    }
+    Ok((manifest, tasks))
+}
+
+fn install(app: &AppHandle, url: &str, sha256: Option<&str>) -> Result<Vec<Recipe>, String> {
+    if !url.starts_with("https://") {
+        return Err("Recipe bundles can only be installed over HTTPS".to_string());
+    }
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(15))
+        .timeout_read(Duration::from_secs(30))
+        .build();
+    let bundle = fetch(&agent, url)?.ok_or_else(|| format!("{} was not found", url))?;
+    let trusted = app.state::<SettingsStore>().get().trusted_recipe_keys;
+    verify(&agent, url, &bundle, sha256, &trusted)?;
+    let (manifest, tasks) = read_bundle(&bundle)?;
+
+    log::info!(
+        "Installing recipe bundle '{}' by {} ({} recipes) from {}",
+        manifest.name,
+        manifest.author.as_deref().unwrap_or("unknown author"),
+        tasks.len(),
+        url
+    );
+    if let Some(description) = &manifest.description {
+        log::debug!("Bundle description: {}", description);
+    }
+    tasks.into_iter().map(|task| recipes::save(app, None, task, None)).collect()
+}
+
+/// Downloads a shared recipe bundle, checks its signature (or the given SHA-256
+/// checksum) and contents, and adds its recipes to the store.
+#[tauri::command]
+pub async fn install_recipe_from_url(
+    app: AppHandle,
+    url: String,
+    sha256: Option<String>,
+) -> Result<Vec<Recipe>, String> {
+    tauri::async_runtime::spawn_blocking(move || install(&app, &url, sha256.as_deref()))
+        .await
+        .map_err(|e| format!("Installing the recipe bundle failed: {}", e))?
+}
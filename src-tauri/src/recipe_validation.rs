@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::{history, recipes, variables, AppState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in a recipe's prompt, placed by character offset so the UI can
+/// underline it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// e.g. `invalid_selector`, `missing_variable`.
+    pub code: String,
+    pub message: String,
+    pub offset: usize,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecipeValidation {
+    pub id: String,
+    /// Revision of the recipe that was checked.
+    pub revision: String,
+    /// Unix milliseconds.
+    pub validated_at: i64,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Last result per recipe id.
+static CACHE: Mutex<BTreeMap<String, RecipeValidation>> = Mutex::new(BTreeMap::new());
+
+/// `${name}` placeholders the active variable profile can't fill.
+fn missing_variables(app: &AppHandle, prompt: &str) -> Result<Vec<Diagnostic>, String> {
+    let mut diagnostics = Vec::new();
+    for name in variables::missing_variables(app.clone(), prompt.to_string())? {
+        let placeholder = format!("${{{}}}", name);
+        let offset = prompt.find(&placeholder).map(|byte| prompt[..byte].chars().count()).unwrap_or(0);
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code: "missing_variable".to_string(),
+            message: format!("No value for {} in the active profile", placeholder),
+            offset,
+            length: placeholder.chars().count(),
+        });
+    }
+    Ok(diagnostics)
+}
+
+fn validate(app: &AppHandle, id: &str) -> Result<RecipeValidation, String> {
+    let recipe = recipes::load(app, id)?;
+    let prompt = &recipe.task.prompt;
+    let mut diagnostics = missing_variables(app, prompt)?;
+    match app.state::<AppState>().control.validate_prompt(prompt) {
+        Ok(result) => {
+            let checked: Vec<Diagnostic> = serde_json::from_value(result["diagnostics"].clone())
+                .map_err(|e| format!("Invalid diagnostics from the server: {}", e))?;
+            diagnostics.extend(checked);
+        }
+        Err(e) => diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            code: "server_unavailable".to_string(),
+            message: format!("Only variables were checked; the server could not check the prompt: {}", e),
+            offset: 0,
+            length: 0,
+        }),
+    }
+    diagnostics.sort_by_key(|d| d.offset);
+
+    let validation =
+        RecipeValidation { id: id.to_string(), revision: recipe.revision, validated_at: history::now_ms(), diagnostics };
+    CACHE.lock().unwrap().insert(id.to_string(), validation.clone());
+    Ok(validation)
+}
+
+/// Checks a recipe without running it: the server looks at the prompt (selectors, URLs)
+/// and the shell at its variables. The result is cached for `get_recipe_diagnostics`.
+#[tauri::command]
+pub async fn validate_recipe(app: AppHandle, id: String) -> Result<RecipeValidation, String> {
+    tauri::async_runtime::spawn_blocking(move || validate(&app, &id))
+        .await
+        .map_err(|e| format!("Validation failed: {}", e))?
+}
+
+/// The cached result for a recipe, if it was validated since it last changed.
+#[tauri::command]
+pub fn get_recipe_diagnostics(app: AppHandle, id: String) -> Result<Option<RecipeValidation>, String> {
+    let revision = recipes::load(&app, &id)?.revision;
+    Ok(CACHE.lock().unwrap().get(&id).filter(|v| v.revision == revision).cloned())
+}
@@ -0,0 +1,266 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager};
+
+use crate::tasks::{self, TaskFile};
+use crate::{alerts, git_sync, history, normalize, server, transforms};
+
+/// Earlier revisions kept per recipe.
+const MAX_REVISIONS: usize = 20;
+
+/// Serializes changes to the recipe store, so a check for conflicts and the write that
+/// follows it can't interleave with another save.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// A saved automation, stored as `recipes/<id>.nova` in the app data dir.
+#[derive(Debug, Clone, Serialize)]
+pub struct Recipe {
+    pub id: String,
+    #[serde(flatten)]
+    pub task: TaskFile,
+    /// Hash of the saved file. Passing it back when saving makes the save fail instead
+    /// of overwriting changes made elsewhere in the meantime.
+    pub revision: String,
+    /// Unix milliseconds.
+    pub modified_at: i64,
+}
+
+/// An earlier version of a recipe, kept in `recipes/.history/<id>/<saved_at>.nova`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecipeRevision {
+    /// Unix milliseconds; identifies the revision.
+    pub saved_at: i64,
+    #[serde(flatten)]
+    pub task: TaskFile,
+}
+
+fn recipes_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("recipes"))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+fn recipe_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid recipe id '{}'", id));
+    }
+    Ok(dir.join(format!("{}.{}", id, tasks::EXTENSION)))
+}
+
+fn revisions_dir(dir: &Path, id: &str) -> PathBuf {
+    dir.join(".history").join(id)
+}
+
+/// A free id made from the recipe's name, e.g. "Check prices" becomes `check-prices`.
+fn new_id(dir: &Path, name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() { "recipe".to_string() } else { slug };
+    (1..)
+        .map(|n| if n == 1 { slug.clone() } else { format!("{}-{}", slug, n) })
+        .find(|id| recipe_path(dir, id).is_ok_and(|path| !path.exists()) && !revisions_dir(dir, id).exists())
+        .unwrap()
+}
+
+/// The saved recipe `id`.
+pub fn load(app: &AppHandle, id: &str) -> Result<Recipe, String> {
+    read(&recipes_dir(app)?, id)
+}
+
+fn read(dir: &Path, id: &str) -> Result<Recipe, String> {
+    let path = recipe_path(dir, id)?;
+    if !path.is_file() {
+        return Err(format!("Recipe '{}' does not exist", id));
+    }
+    let task = tasks::load(&path)?;
+    let revision = server::sha256_file(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let modified_at = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    Ok(Recipe { id: id.to_string(), task, revision, modified_at })
+}
+
+/// Writes next to `path` and renames over it, so a crash never leaves half a recipe.
+fn write_atomic(path: &Path, task: &TaskFile) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(task).map_err(|e| e.to_string())?;
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, contents)
+        .and_then(|_| std::fs::rename(&temp, path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&temp);
+            format!("Failed to save {}: {}", path.display(), e)
+        })
+}
+
+/// Keeps the file at `path` as a revision, then drops the oldest beyond `MAX_REVISIONS`.
+fn archive_revision(dir: &Path, id: &str, path: &Path) -> Result<(), String> {
+    let revisions = revisions_dir(dir, id);
+    std::fs::create_dir_all(&revisions).map_err(|e| format!("Failed to create {}: {}", revisions.display(), e))?;
+    let target = revisions.join(format!("{}.{}", history::now_ms(), tasks::EXTENSION));
+    std::fs::copy(path, &target).map_err(|e| format!("Failed to keep revision of '{}': {}", id, e))?;
+    for (_, old) in revision_files(dir, id).into_iter().skip(MAX_REVISIONS) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// A recipe's revision files with their timestamps, newest first.
+fn revision_files(dir: &Path, id: &str) -> Vec<(i64, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(revisions_dir(dir, id)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(i64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let saved_at = path.file_stem()?.to_str()?.parse().ok()?;
+            Some((saved_at, path))
+        })
+        .collect();
+    files.sort_by_key(|(saved_at, _)| std::cmp::Reverse(*saved_at));
+    files
+}
+
+/// Creates (no `id`) or updates a recipe. With `base_revision`, fails if the recipe
+/// changed since that revision was read.
+pub fn save(
+    app: &AppHandle,
+    id: Option<String>,
+    task: TaskFile,
+    base_revision: Option<String>,
+) -> Result<Recipe, String> {
+    if task.prompt.trim().is_empty() {
+        return Err("A recipe needs a prompt".to_string());
+    }
+    for assertion in &task.assertions {
+        alerts::parse(&assertion.expression)?;
+    }
+    for locale in task.locale.iter().chain(task.normalize.iter().filter_map(|rule| rule.locale.as_ref())) {
+        normalize::check_locale(locale)?;
+    }
+    transforms::check(&task.transforms)?;
+    let dir = recipes_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let _guard = LOCK.lock().unwrap();
+    let id = id.unwrap_or_else(|| new_id(&dir, task.name.as_deref().unwrap_or_default()));
+    let path = recipe_path(&dir, &id)?;
+
+    if path.exists() {
+        let current = server::sha256_file(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if base_revision.is_some_and(|base| base != current) {
+            return Err(format!("Recipe '{}' was changed elsewhere; reload it before saving", id));
+        }
+        archive_revision(&dir, &id, &path)?;
+    } else if base_revision.is_some() {
+        return Err(format!("Recipe '{}' was deleted elsewhere", id));
+    }
+    write_atomic(&path, &task)?;
+    git_sync::changed(app);
+    read(&dir, &id)
+}
+
+/// Every recipe, sorted by name. Unreadable files are skipped.
+#[tauri::command]
+pub fn list_recipes(app: AppHandle) -> Result<Vec<Recipe>, String> {
+    let dir = recipes_dir(&app)?;
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut recipes: Vec<Recipe> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == tasks::EXTENSION))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_string_lossy().into_owned();
+            read(&dir, &id).map_err(|e| log::warn!("Skipping recipe {}: {}", path.display(), e)).ok()
+        })
+        .collect();
+    recipes.sort_by_key(|recipe| recipe.task.display_name(Path::new(&recipe.id)).to_lowercase());
+    Ok(recipes)
+}
+
+#[tauri::command]
+pub fn get_recipe(app: AppHandle, id: String) -> Result<Recipe, String> {
+    load(&app, &id)
+}
+
+/// Creates a recipe (no `id`) or updates one, keeping the previous version as a revision.
+#[tauri::command]
+pub fn save_recipe(
+    app: AppHandle,
+    id: Option<String>,
+    task: TaskFile,
+    base_revision: Option<String>,
+) -> Result<Recipe, String> {
+    save(&app, id, task, base_revision)
+}
+
+/// Renames a recipe. Its id stays the same, so links to it keep working.
+#[tauri::command]
+pub fn rename_recipe(app: AppHandle, id: String, name: String, base_revision: Option<String>) -> Result<Recipe, String> {
+    if name.trim().is_empty() {
+        return Err("A recipe needs a name".to_string());
+    }
+    let mut task = read(&recipes_dir(&app)?, &id)?.task;
+    task.name = Some(name);
+    save(&app, Some(id), task, base_revision)
+}
+
+#[tauri::command]
+pub fn duplicate_recipe(app: AppHandle, id: String) -> Result<Recipe, String> {
+    let mut task = read(&recipes_dir(&app)?, &id)?.task;
+    let name = task.display_name(Path::new(&id));
+    task.name = Some(format!("{} (copy)", name));
+    save(&app, None, task, None)
+}
+
+/// Deletes a recipe and its revisions.
+#[tauri::command]
+pub fn delete_recipe(app: AppHandle, id: String) -> Result<(), String> {
+    let dir = recipes_dir(&app)?;
+    let path = recipe_path(&dir, &id)?;
+    let _guard = LOCK.lock().unwrap();
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete recipe '{}': {}", id, e))?;
+    let _ = std::fs::remove_dir_all(revisions_dir(&dir, &id));
+    if let Err(e) = app.state::<history::History>().clear_breakpoints(&id) {
+        log::warn!("Breakpoints of recipe {} not cleared: {}", id, e);
+    }
+    log::info!("Recipe {} deleted", id);
+    git_sync::changed(&app);
+    Ok(())
+}
+
+/// Earlier versions of a recipe, newest first.
+#[tauri::command]
+pub fn list_recipe_revisions(app: AppHandle, id: String) -> Result<Vec<RecipeRevision>, String> {
+    let dir = recipes_dir(&app)?;
+    recipe_path(&dir, &id)?;
+    Ok(revision_files(&dir, &id)
+        .into_iter()
+        .filter_map(|(saved_at, path)| tasks::load(&path).ok().map(|task| RecipeRevision { saved_at, task }))
+        .collect())
+}
+
+/// Makes an earlier version current again. The version it replaces is kept as a revision.
+#[tauri::command]
+pub fn restore_recipe_revision(app: AppHandle, id: String, saved_at: i64) -> Result<Recipe, String> {
+    let dir = recipes_dir(&app)?;
+    recipe_path(&dir, &id)?;
+    let (_, path) = revision_files(&dir, &id)
+        .into_iter()
+        .find(|(time, _)| *time == saved_at)
+        .ok_or_else(|| format!("Recipe '{}' has no revision from {}", id, saved_at))?;
+    let task = tasks::load(&path)?;
+    save(&app, Some(id), task, None)
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::recipes::{self, Recipe};
+use crate::tasks::TaskFile;
+use crate::{history, AppState};
+
+/// A navigation this soon after a click or key press is taken to be caused by it.
+const CAUSED_NAVIGATION_MS: i64 = 3000;
+/// Clicks on the same element this close together are one click.
+const REPEAT_CLICK_MS: i64 = 500;
+/// Typed values with a password field's are replaced by this variable.
+const PASSWORD_VARIABLE: &str = "${password}";
+
+/// The recording in progress, if any.
+static SESSION: Mutex<Option<RecorderStatus>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecorderStatus {
+    pub recording: bool,
+    pub paused: bool,
+    pub url: Option<String>,
+    /// Unix milliseconds.
+    pub started_at: Option<i64>,
+    /// Actions recorded so far, before cleanup.
+    pub actions: usize,
+}
+
+/// Something the user did in the recording browser; see `record.action` in the schema.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedAction {
+    /// `navigate`, `click`, `fill`, `select` or `press`.
+    pub kind: String,
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// What the page calls the element: its label, text or placeholder.
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Typed into a password field; the value was not recorded.
+    #[serde(default)]
+    pub sensitive: bool,
+    pub url: String,
+    /// Unix milliseconds.
+    pub at: i64,
+}
+
+fn status() -> RecorderStatus {
+    SESSION.lock().unwrap().clone().unwrap_or_default()
+}
+
+fn emit_status(app: &AppHandle) {
+    let _ = app.emit("recorder-status", status());
+}
+
+/// Relays a `record.action` from the server to the app's windows as `recorder-action`.
+pub fn action(app: &AppHandle, params: Value) {
+    let action = match serde_json::from_value::<RecordedAction>(params["action"].clone()) {
+        Ok(action) => action,
+        Err(e) => {
+            log::warn!("Invalid recorded action from server: {}", e);
+            return;
+        }
+    };
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        session.actions += 1;
+    }
+    let _ = app.emit("recorder-action", &action);
+}
+
+/// Tidies up a recording: drops navigations that a click or key press caused (replaying
+/// the click navigates again), repeated clicks, and clicks into a field that is then
+/// typed into, and keeps only the last of several edits of a field in a row.
+fn clean(actions: Vec<RecordedAction>) -> Vec<RecordedAction> {
+    let mut steps: Vec<RecordedAction> = Vec::new();
+    for action in actions {
+        let same_element =
+            |previous: &RecordedAction| previous.selector.is_some() && previous.selector == action.selector;
+        match (steps.last(), action.kind.as_str()) {
+            (Some(previous), "navigate") if previous.url == action.url && previous.kind == "navigate" => continue,
+            (Some(previous), "navigate")
+                if matches!(previous.kind.as_str(), "click" | "press" | "select")
+                    && action.at - previous.at <= CAUSED_NAVIGATION_MS =>
+            {
+                continue
+            }
+            (Some(previous), "click")
+                if previous.kind == "click" && same_element(previous) && action.at - previous.at <= REPEAT_CLICK_MS =>
+            {
+                continue
+            }
+            (Some(previous), "fill")
+                if matches!(previous.kind.as_str(), "click" | "fill") && same_element(previous) =>
+            {
+                steps.pop();
+            }
+            _ => {}
+        }
+        steps.push(action);
+    }
+    steps
+}
+
+/// How a step refers to its element: by label, with the selector in backticks as a hint.
+fn target(action: &RecordedAction, fallback: &str) -> String {
+    let label = action.label.as_deref().map(|label| format!("\"{}\"", label));
+    // Backticked selectors are checked as CSS, which XPath isn't
+    let selector = action
+        .selector
+        .as_deref()
+        .filter(|selector| !selector.starts_with('/') && !selector.contains('`'))
+        .map(|selector| format!("`{}`", selector));
+    match (label, selector) {
+        (Some(label), Some(selector)) => format!("{} ({})", label, selector),
+        (Some(label), None) => label,
+        (None, Some(selector)) => format!("the {} {}", fallback, selector),
+        (None, None) => format!("the {}", fallback),
+    }
+}
+
+/// One line of the recipe's prompt for each step.
+fn prompt(steps: &[RecordedAction]) -> String {
+    // Recorded text is literal, even where it looks like a placeholder
+    let literal = |value: &Option<String>| value.as_deref().unwrap_or_default().replace("${", "$${");
+    steps
+        .iter()
+        .filter_map(|step| {
+            let line = match step.kind.as_str() {
+                "navigate" => format!("Go to {}", step.url),
+                "click" => format!("Click {}", target(step, "element")),
+                "fill" if step.sensitive => format!("Type {} into {}", PASSWORD_VARIABLE, target(step, "field")),
+                "fill" => format!("Type \"{}\" into {}", literal(&step.value), target(step, "field")),
+                "select" => format!("Select \"{}\" in {}", literal(&step.value), target(step, "list")),
+                "press" => format!("Press {} in {}", literal(&step.value), target(step, "field")),
+                _ => return None,
+            };
+            Some(format!("{}.", line))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn draft_name(steps: &[RecordedAction]) -> String {
+    let host = steps
+        .first()
+        .and_then(|step| tauri::Url::parse(&step.url).ok())
+        .and_then(|url| url.host_str().map(|host| host.trim_start_matches("www.").to_string()));
+    match host {
+        Some(host) => format!("Recorded on {}", host),
+        None => "Recorded steps".to_string(),
+    }
+}
+
+/// Opens a browser on `url` whose actions are recorded until `stop_recording`.
+#[tauri::command]
+pub async fn start_recording(app: AppHandle, url: String) -> Result<RecorderStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if SESSION.lock().unwrap().is_some() {
+            return Err("A recording is already in progress".to_string());
+        }
+        app.state::<AppState>().control.record_start(&url)?;
+        *SESSION.lock().unwrap() = Some(RecorderStatus {
+            recording: true,
+            paused: false,
+            url: Some(url.clone()),
+            started_at: Some(history::now_ms()),
+            actions: 0,
+        });
+        log::info!("Recording started on {}", url);
+        emit_status(&app);
+        Ok(status())
+    })
+    .await
+    .map_err(|e| format!("Recording failed: {}", e))?
+}
+
+/// Stops or resumes recording; the browser stays open either way.
+#[tauri::command]
+pub async fn pause_recording(app: AppHandle, paused: bool) -> Result<RecorderStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if SESSION.lock().unwrap().is_none() {
+            return Err("Nothing is being recorded".to_string());
+        }
+        app.state::<AppState>().control.record_pause(paused)?;
+        if let Some(session) = SESSION.lock().unwrap().as_mut() {
+            session.paused = paused;
+        }
+        emit_status(&app);
+        Ok(status())
+    })
+    .await
+    .map_err(|e| format!("Recording failed: {}", e))?
+}
+
+/// Closes the recording browser and saves what was recorded, tidied up, as a draft
+/// recipe. Without `save`, the recording is discarded.
+#[tauri::command]
+pub async fn stop_recording(app: AppHandle, name: Option<String>, save: bool) -> Result<Option<Recipe>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if SESSION.lock().unwrap().take().is_none() {
+            return Err("Nothing is being recorded".to_string());
+        }
+        emit_status(&app);
+        let result = app.state::<AppState>().control.record_stop()?;
+        let actions: Vec<RecordedAction> = serde_json::from_value(result["actions"].clone())
+            .map_err(|e| format!("Invalid recording from server: {}", e))?;
+        if !save {
+            log::info!("Recording discarded ({} actions)", actions.len());
+            return Ok(None);
+        }
+        let recorded = actions.len();
+        let steps = clean(actions);
+        if steps.is_empty() {
+            return Err("Nothing was recorded".to_string());
+        }
+        let name = name.filter(|name| !name.trim().is_empty()).unwrap_or_else(|| draft_name(&steps));
+        let mut task = TaskFile::new(Some(name), prompt(&steps));
+        task.draft = true;
+        let recipe = recipes::save(&app, None, task, None)?;
+        log::info!("Recording saved as draft recipe '{}': {} of {} actions kept", recipe.id, steps.len(), recorded);
+        Ok(Some(recipe))
+    })
+    .await
+    .map_err(|e| format!("Recording failed: {}", e))?
+}
+
+#[tauri::command]
+pub fn get_recording_status() -> RecorderStatus {
+    status()
+}
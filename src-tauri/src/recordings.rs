@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::artifact_protocol;
+use crate::downloads;
+use crate::history::{self, History, Recording};
+
+/// Containers every platform's webview plays; other recordings are remuxed to mp4.
+const PLAYABLE: &[&str] = &["mp4", "webm"];
+
+/// Sent by the server as `recording.ready` once a session recording is written.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecordingReady {
+    pub job_id: String,
+    pub path: PathBuf,
+}
+
+/// A recording as listed to the UI, with the URL its video element plays.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingEntry {
+    #[serde(flatten)]
+    pub recording: Recording,
+    pub url: String,
+}
+
+impl From<Recording> for RecordingEntry {
+    fn from(recording: Recording) -> Self {
+        let url = artifact_protocol::url(&recording.run_id, &format!("recordings/{}", recording.name));
+        RecordingEntry { recording, url }
+    }
+}
+
+fn recording_path(app: &AppHandle, recording: &Recording) -> Option<PathBuf> {
+    history::artifacts_dir(app, &recording.run_id).map(|dir| dir.join("recordings").join(&recording.name))
+}
+
+fn ffmpeg(source: &Path, target: &Path, codec_args: &[&str]) -> bool {
+    Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(source)
+        .args(codec_args)
+        // Index up front so playback and seeking start before the whole file is read
+        .args(["-movflags", "+faststart"])
+        .arg(target)
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Converts `source` to mp4 with ffmpeg, if it is installed. Copying the streams is
+/// tried first; they are only re-encoded when mp4 can't hold them.
+fn remux(source: &Path, target: &Path) -> bool {
+    ffmpeg(source, target, &["-c", "copy"])
+        || ffmpeg(source, target, &["-c:v", "libx264", "-preset", "veryfast", "-pix_fmt", "yuv420p", "-c:a", "aac"])
+}
+
+fn probe_duration_ms(path: &Path) -> Option<i64> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .ok()?;
+    let seconds: f64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some((seconds * 1000.0) as i64)
+}
+
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    // The server's temp dir may be on another volume
+    std::fs::rename(from, to).or_else(|_| std::fs::copy(from, to).and_then(|_| std::fs::remove_file(from)))
+}
+
+/// Moves a recording into its run's artifacts, remuxes it if the webview can't play it,
+/// and indexes it.
+pub fn accept(app: &AppHandle, ready: &RecordingReady) -> Result<Recording, String> {
+    let dir = history::artifacts_dir(app, &ready.job_id)
+        .ok_or_else(|| format!("Invalid run id '{}'", ready.job_id))?
+        .join("recordings");
+    if !ready.path.is_file() {
+        return Err(format!("Recording {} does not exist", ready.path.display()));
+    }
+    let name = ready.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let mut path = downloads::unique_path(&dir, &name, &[]);
+    move_file(&ready.path, &path).map_err(|e| format!("Failed to move {}: {}", ready.path.display(), e))?;
+
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if !PLAYABLE.contains(&extension.as_str()) {
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let mp4 = downloads::unique_path(&dir, &format!("{}.mp4", stem), &[]);
+        if remux(&path, &mp4) {
+            let _ = std::fs::remove_file(&path);
+            path = mp4;
+        } else {
+            let _ = std::fs::remove_file(&mp4);
+            log::warn!("Could not convert recording {} to mp4; it may not play in the app", path.display());
+        }
+    }
+
+    let recording = Recording {
+        run_id: ready.job_id.clone(),
+        name: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+        size: std::fs::metadata(&path).map(|m| m.len() as i64).unwrap_or(0),
+        duration_ms: probe_duration_ms(&path),
+        created_at: history::now_ms(),
+    };
+    app.state::<History>().add_recording(&recording)?;
+    Ok(recording)
+}
+
+/// Takes in a recording the server reported, in the background, and emits
+/// `recording-added` when it is ready to play.
+pub fn recording_ready(app: &AppHandle, ready: RecordingReady) {
+    let app = app.clone();
+    std::thread::spawn(move || match accept(&app, &ready) {
+        Ok(recording) => {
+            log::info!("Recording {} of run {} added", recording.name, recording.run_id);
+            let _ = app.emit("recording-added", RecordingEntry::from(recording));
+        }
+        Err(e) => log::warn!("Failed to add recording of run {}: {}", ready.job_id, e),
+    });
+}
+
+/// Recordings, newest first, optionally of one run. Entries whose file is gone (e.g.
+/// purged by retention) are dropped from the index.
+#[tauri::command]
+pub fn list_recordings(
+    app: AppHandle,
+    history: State<'_, History>,
+    run_id: Option<String>,
+) -> Result<Vec<RecordingEntry>, String> {
+    let mut entries = Vec::new();
+    for recording in history.recordings(run_id.as_deref())? {
+        if recording_path(&app, &recording).is_some_and(|path| path.is_file()) {
+            entries.push(RecordingEntry::from(recording));
+        } else {
+            history.remove_recording(&recording.run_id, &recording.name)?;
+        }
+    }
+    Ok(entries)
+}
+
+fn delete_older_than(app: &AppHandle, days: u32) -> Result<usize, String> {
+    let cutoff = history::now_ms() - i64::from(days) * 24 * 60 * 60 * 1000;
+    let history = app.state::<History>();
+    let mut deleted = 0;
+    for recording in history.recordings(None)?.into_iter().filter(|r| r.created_at < cutoff) {
+        if let Some(path) = recording_path(app, &recording) {
+            match std::fs::remove_file(&path) {
+                Ok(()) => deleted += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    log::warn!("Failed to delete recording {}: {}", path.display(), e);
+                    continue;
+                }
+            }
+        }
+        history.remove_recording(&recording.run_id, &recording.name)?;
+    }
+    log::info!("Deleted {} recordings older than {} days", deleted, days);
+    Ok(deleted)
+}
+
+/// Deletes recordings made more than `days` days ago; returns how many were deleted.
+#[tauri::command]
+pub async fn delete_recordings_older_than(app: AppHandle, days: u32) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || delete_older_than(&app, days))
+        .await
+        .map_err(|e| format!("Deleting recordings failed: {}", e))?
+}
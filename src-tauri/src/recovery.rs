@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+/// Consecutive crash-loops after which we offer to start in safe mode.
+const SAFE_MODE_PROMPT_THRESHOLD: u32 = 2;
+
+/// Crash bookkeeping that has to survive app restarts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecoveryState {
+    pub consecutive_crash_loops: u32,
+}
+
+fn state_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join("recovery.json"))
+}
+
+pub fn load(app: &AppHandle) -> RecoveryState {
+    state_path(app)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(app: &AppHandle, state: &RecoveryState) {
+    let Some(path) = state_path(app) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    match serde_json::to_string_pretty(state) {
+        Ok(contents) => {
+            if let Err(e) = std::fs::write(&path, contents) {
+                log::error!("Failed to save recovery state: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to serialize recovery state: {}", e),
+    }
+}
+
+/// Records that the server failed to stay up, returning the new consecutive count.
+pub fn record_crash_loop(app: &AppHandle) -> u32 {
+    let mut state = load(app);
+    state.consecutive_crash_loops += 1;
+    log::warn!("Server crash-loop recorded ({} consecutive)", state.consecutive_crash_loops);
+    save(app, &state);
+    state.consecutive_crash_loops
+}
+
+pub fn record_successful_start(app: &AppHandle) {
+    let state = load(app);
+    if state.consecutive_crash_loops > 0 {
+        save(app, &RecoveryState::default());
+    }
+}
+
+pub fn should_offer_safe_mode(app: &AppHandle) -> bool {
+    load(app).consecutive_crash_loops >= SAFE_MODE_PROMPT_THRESHOLD
+}
+
+/// Asks the user whether to start in safe mode. Blocks, so never call on the main thread.
+pub fn prompt_safe_mode(app: &AppHandle) -> bool {
+    let crash_loops = load(app).consecutive_crash_loops;
+    let safe_mode = app
+        .dialog()
+        .message(format!(
+            "The automation server failed to start {} times in a row.\n\n\
+             Safe mode disables schedules, plugins, the tray icon and custom settings \
+             so you can fix the configuration.",
+            crash_loops
+        ))
+        .title("Start in safe mode?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Start in Safe Mode".to_string(),
+            "Start Normally".to_string(),
+        ))
+        .blocking_show();
+    log::info!("Safe mode prompt answered: {}", if safe_mode { "safe mode" } else { "normal" });
+    safe_mode
+}
@@ -0,0 +1,83 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::run_diff::read_png;
+use crate::thumbnails::{resolve_screenshot, write_png};
+
+/// Colour redaction boxes are filled with.
+const FILL: [u8; 4] = [0, 0, 0, 255];
+/// Inserted before the extension of redacted copies.
+const SUFFIX: &str = "redacted";
+
+/// A region of a screenshot, in its pixels from the top left.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// `<stem>.redacted.png` next to `source`, or `<stem>.redacted-2.png` and so on when
+/// earlier redactions took that name.
+fn redacted_path(source: &Path) -> PathBuf {
+    let stem = source.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    (1..)
+        .map(|n| match n {
+            1 => source.with_file_name(format!("{}.{}.png", stem, SUFFIX)),
+            n => source.with_file_name(format!("{}.{}-{}.png", stem, SUFFIX, n)),
+        })
+        .find(|path| !path.exists())
+        .expect("some name is free")
+}
+
+/// Writes a copy of a screenshot with `rects` filled in, keeping the original. Returns the
+/// new artifact's path.
+fn redact(source: &Path, rects: &[Rect]) -> Result<PathBuf, String> {
+    let (width, height, mut pixels) = read_png(source)?;
+    let mut covered = 0;
+    for rect in rects {
+        let (left, top) = (rect.x.min(width), rect.y.min(height));
+        let right = rect.x.saturating_add(rect.width).min(width);
+        let bottom = rect.y.saturating_add(rect.height).min(height);
+        if left >= right || top >= bottom {
+            continue;
+        }
+        for y in top..bottom {
+            let row = y as usize * width as usize;
+            pixels[row + left as usize..row + right as usize].fill(FILL);
+        }
+        covered += 1;
+    }
+    if covered == 0 {
+        return Err("None of the regions are inside the screenshot".to_string());
+    }
+    let rgba: Vec<u8> = pixels.into_iter().flatten().collect();
+    let path = redacted_path(source);
+    write_png(&path, width, height, &rgba)?;
+    Ok(path)
+}
+
+/// Draws redaction boxes over a screenshot so it can be shared without what they cover.
+/// The boxed copy is saved as a new artifact of the same run, next to the original, which
+/// is left as it was. Returns the new artifact's id.
+#[tauri::command]
+pub async fn redact_regions(app: AppHandle, artifact_id: String, rects: Vec<Rect>) -> Result<String, String> {
+    if rects.is_empty() {
+        return Err("No regions to redact".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let (run_id, name, source) = resolve_screenshot(&app, &artifact_id)?;
+        let path = redact(&source, &rects)?;
+        let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let redacted = match name.rsplit_once('/') {
+            Some((dir, _)) => format!("{}/{}/{}", run_id, dir, file_name),
+            None => format!("{}/{}", run_id, file_name),
+        };
+        log::info!("Redacted {} region(s) of {} into {}", rects.len(), artifact_id, redacted);
+        Ok(redacted)
+    })
+    .await
+    .map_err(|e| format!("Redaction failed: {}", e))?
+}
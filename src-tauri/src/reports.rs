@@ -0,0 +1,406 @@
+use chrono::{Local, TimeZone};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::events::JobEvent;
+use crate::export::{cell_text, to_table};
+use crate::history::{self, History, RunRecord};
+use crate::run_diff::read_png;
+use crate::settings::SettingsStore;
+use crate::thumbnails::{downscale, fit, is_screenshot};
+
+/// A4 in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const BODY_SIZE: f32 = 10.0;
+/// Helvetica's average glyph width as a share of the font size, for wrapping text.
+const CHAR_WIDTH: f32 = 0.5;
+/// Screenshots are embedded at most this wide in pixels, which keeps reports small while
+/// staying sharp at page width.
+const MAX_IMAGE_PIXELS: u32 = 1600;
+/// Screenshots and table rows past these are left out, with a note saying how many.
+const MAX_SCREENSHOTS: usize = 40;
+const MAX_TABLE_ROWS: usize = 200;
+/// Name of the report within the run's artifacts.
+const FILE_NAME: &str = "report.pdf";
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+/// What a report covers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportTemplate {
+    /// Title page, parameters, timings, screenshots and extracted tables.
+    #[default]
+    Full,
+    /// Everything but the screenshots, for reports that get mailed around.
+    Summary,
+}
+
+/// Reports written automatically when runs finish.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportSettings {
+    /// Write a report for every run a schedule started.
+    pub scheduled_runs: bool,
+    pub template: ReportTemplate,
+}
+
+/// An image ready to embed: flate-compressed RGB.
+struct Image {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+/// Lays out text and images top to bottom, starting a new page when one is full.
+struct Layout {
+    pages: Vec<Content>,
+    /// Images by page, each placed by the page's content.
+    images: Vec<Vec<Image>>,
+    /// Distance from the bottom of the page to the top of the next line.
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        let mut layout = Layout { pages: Vec::new(), images: Vec::new(), y: 0.0 };
+        layout.new_page();
+        layout
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Content::new());
+        self.images.push(Vec::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn content(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("layout always has a page")
+    }
+
+    /// Makes room for `height` more points, on a new page if this one is too full.
+    fn reserve(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn text(&mut self, text: &str, font: Name, size: f32, x: f32) {
+        let leading = size * 1.3;
+        let columns = ((PAGE_WIDTH - MARGIN - x) / (size * CHAR_WIDTH)) as usize;
+        for line in wrap(text, columns) {
+            self.reserve(leading);
+            self.y -= leading;
+            let y = self.y + size * 0.3;
+            self.content().begin_text().set_font(font, size).next_line(x, y).show(Str(&latin1(&line))).end_text();
+        }
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.space(BODY_SIZE);
+        self.reserve(BODY_SIZE * 4.0);
+        self.text(text, BOLD, 14.0, MARGIN);
+        self.space(BODY_SIZE * 0.5);
+    }
+
+    /// `label: value`, with the value wrapped in a column of its own.
+    fn field(&mut self, label: &str, value: &str) {
+        self.reserve(BODY_SIZE * 1.3);
+        let top = self.y;
+        self.text(label, BOLD, BODY_SIZE, MARGIN);
+        let below_label = self.y;
+        self.y = top;
+        self.text(if value.is_empty() { "-" } else { value }, REGULAR, BODY_SIZE, MARGIN + 110.0);
+        self.y = self.y.min(below_label);
+    }
+
+    /// Places an image at page width (or its own, if smaller) with a caption above it.
+    fn image(&mut self, caption: &str, image: Image) {
+        let usable_width = PAGE_WIDTH - 2.0 * MARGIN;
+        let usable_height = PAGE_HEIGHT - 2.0 * MARGIN - BODY_SIZE * 3.0;
+        let scale = (usable_width / image.width as f32).min(usable_height / image.height as f32).min(1.0);
+        let (width, height) = (image.width as f32 * scale, image.height as f32 * scale);
+        self.reserve(height + BODY_SIZE * 3.0);
+        self.text(caption, BOLD, BODY_SIZE, MARGIN);
+        self.space(BODY_SIZE * 0.5);
+        let page = self.pages.len() - 1;
+        let name = format!("Im{}", self.images[page].len());
+        self.images[page].push(image);
+        let y = self.y - height;
+        self.content()
+            .save_state()
+            .transform([width, 0.0, 0.0, height, MARGIN, y])
+            .x_object(Name(name.as_bytes()))
+            .restore_state();
+        self.y = y - BODY_SIZE;
+    }
+
+    /// A table with evenly wide columns, cells cut to fit.
+    fn table(&mut self, columns: &[String], rows: &[Vec<String>]) {
+        let width = (PAGE_WIDTH - 2.0 * MARGIN) / columns.len().max(1) as f32;
+        let size = 8.0;
+        let fits = ((width - 4.0) / (size * CHAR_WIDTH)) as usize;
+        let row_height = size * 1.6;
+        for (index, cells) in std::iter::once(columns).chain(rows.iter().map(Vec::as_slice)).enumerate() {
+            self.reserve(row_height);
+            self.y -= row_height;
+            let y = self.y + size * 0.5;
+            let font = if index == 0 { BOLD } else { REGULAR };
+            for (column, cell) in cells.iter().enumerate() {
+                let x = MARGIN + column as f32 * width;
+                let cell = truncate(cell, fits);
+                self.content().begin_text().set_font(font, size).next_line(x, y).show(Str(&latin1(&cell))).end_text();
+            }
+            let line_y = self.y;
+            self.content()
+                .set_stroke_gray(0.8)
+                .set_line_width(0.5)
+                .move_to(MARGIN, line_y)
+                .line_to(PAGE_WIDTH - MARGIN, line_y)
+                .stroke();
+        }
+    }
+
+    /// Writes the laid out pages as a PDF.
+    fn finish(self, title: &str) -> Vec<u8> {
+        let mut pdf = Pdf::new();
+        let mut next = 1;
+        let mut alloc = || {
+            next += 1;
+            Ref::new(next - 1)
+        };
+        let (catalog, tree, regular, bold, info) = (alloc(), alloc(), alloc(), alloc(), alloc());
+        pdf.catalog(catalog).pages(tree);
+        pdf.document_info(info)
+            .title(TextStr(title))
+            .creator(TextStr("Browser Automation"))
+            .producer(TextStr("Browser Automation"));
+        pdf.type1_font(regular).base_font(Name(b"Helvetica")).encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.type1_font(bold).base_font(Name(b"Helvetica-Bold")).encoding_predefined(Name(b"WinAnsiEncoding"));
+
+        let mut page_ids = Vec::new();
+        for (content, images) in self.pages.into_iter().zip(self.images) {
+            let (page_id, content_id) = (alloc(), alloc());
+            let image_ids: Vec<Ref> = images.iter().map(|_| alloc()).collect();
+            let mut page = pdf.page(page_id);
+            page.parent(tree).media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT)).contents(content_id);
+            let mut resources = page.resources();
+            resources.fonts().pair(REGULAR, regular).pair(BOLD, bold);
+            let mut x_objects = resources.x_objects();
+            for (index, id) in image_ids.iter().enumerate() {
+                x_objects.pair(Name(format!("Im{}", index).as_bytes()), *id);
+            }
+            x_objects.finish();
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+            for (image, id) in images.iter().zip(image_ids) {
+                let mut xobject = pdf.image_xobject(id, &image.data);
+                xobject.filter(Filter::FlateDecode);
+                xobject.width(image.width as i32).height(image.height as i32);
+                xobject.color_space().device_rgb();
+                xobject.bits_per_component(8);
+            }
+            page_ids.push(page_id);
+        }
+        let count = page_ids.len() as i32;
+        pdf.pages(tree).kids(page_ids).count(count);
+        pdf.finish()
+    }
+}
+
+/// Splits `text` into lines of at most `columns` characters, at spaces where it can.
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let mut word = word.to_string();
+            while word.chars().count() > columns {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                let split = word.char_indices().nth(columns).map_or(word.len(), |(at, _)| at);
+                lines.push(word[..split].to_string());
+                word = word[split..].to_string();
+            }
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > columns {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+fn truncate(text: &str, columns: usize) -> String {
+    let text = text.replace(['\r', '\n'], " ");
+    if text.chars().count() <= columns {
+        return text;
+    }
+    let mut cut: String = text.chars().take(columns.saturating_sub(3)).collect();
+    cut.push_str("...");
+    cut
+}
+
+/// Text in the standard fonts' encoding; characters outside Latin-1 become `?`.
+fn latin1(text: &str) -> Vec<u8> {
+    text.chars().map(|c| if (c as u32) < 256 && !c.is_control() { c as u8 } else { b'?' }).collect()
+}
+
+pub fn format_time(ms: i64) -> String {
+    Local.timestamp_millis_opt(ms).single().map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default()
+}
+
+pub fn format_duration(ms: i64) -> String {
+    let seconds = ms / 1000;
+    match seconds {
+        0..=59 => format!("{}.{}s", seconds, (ms % 1000) / 100),
+        60..=3599 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {}m", seconds / 3600, (seconds % 3600) / 60),
+    }
+}
+
+/// Decodes a screenshot, scales it down to `MAX_IMAGE_PIXELS` and flattens it onto white.
+fn load_image(path: &Path) -> Result<Image, String> {
+    let (width, height, pixels) = read_png(path)?;
+    if width == 0 || height == 0 {
+        return Err(format!("{} is empty", path.display()));
+    }
+    let (target_width, target_height) = fit(width, height, MAX_IMAGE_PIXELS);
+    let rgba = downscale(width, height, &pixels, (target_width, target_height));
+    let rgb: Vec<u8> = rgba
+        .chunks_exact(4)
+        .flat_map(|p| {
+            let alpha = p[3] as u16;
+            let over_white = move |c: u8| ((c as u16 * alpha + 255 * (255 - alpha)) / 255) as u8;
+            [over_white(p[0]), over_white(p[1]), over_white(p[2])]
+        })
+        .collect();
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&rgb).map_err(|e| e.to_string())?;
+    let data = encoder.finish().map_err(|e| e.to_string())?;
+    Ok(Image { width: target_width, height: target_height, data })
+}
+
+fn render(run: &RunRecord, screenshots: &[(PathBuf, String)], template: ReportTemplate) -> Vec<u8> {
+    let mut layout = Layout::new();
+    layout.space(PAGE_HEIGHT / 4.0);
+    layout.text("Run Report", BOLD, 28.0, MARGIN);
+    layout.space(BODY_SIZE);
+    layout.text(&run.prompt, REGULAR, 14.0, MARGIN);
+    layout.space(BODY_SIZE * 2.0);
+    layout.field("Status", &run.status);
+    layout.field("Run", &run.id);
+    layout.field("Generated", &format_time(history::now_ms()));
+    layout.new_page();
+
+    layout.heading("Parameters");
+    layout.field("Prompt", &run.prompt);
+    layout.field("Recipe", run.recipe_id.as_deref().unwrap_or_default());
+    layout.field("Schedule", run.schedule_id.as_deref().unwrap_or_default());
+    layout.field("Result", run.message.as_deref().unwrap_or_default());
+
+    layout.heading("Timings");
+    layout.field("Started", &format_time(run.started_at));
+    layout.field("Finished", &run.finished_at.map(format_time).unwrap_or_default());
+    layout.field("Duration", &run.duration_ms.map(format_duration).unwrap_or_default());
+
+    if let Some(output) = &run.output {
+        let table = to_table(output);
+        let rows: Vec<Vec<String>> =
+            table.rows.iter().take(MAX_TABLE_ROWS).map(|row| row.iter().map(cell_text).collect()).collect();
+        layout.heading("Extracted Data");
+        layout.table(&table.columns, &rows);
+        if table.rows.len() > MAX_TABLE_ROWS {
+            let more = table.rows.len() - MAX_TABLE_ROWS;
+            layout.space(BODY_SIZE * 0.5);
+            layout.text(&format!("{} more rows in the exported results", more), REGULAR, BODY_SIZE, MARGIN);
+        }
+    }
+
+    if template == ReportTemplate::Full && !screenshots.is_empty() {
+        layout.new_page();
+        layout.heading("Screenshots");
+        for (path, name) in screenshots.iter().take(MAX_SCREENSHOTS) {
+            match load_image(path) {
+                Ok(image) => layout.image(name, image),
+                Err(e) => log::warn!("Screenshot {} left out of the report of run {}: {}", name, run.id, e),
+            }
+        }
+        if screenshots.len() > MAX_SCREENSHOTS {
+            let more = screenshots.len() - MAX_SCREENSHOTS;
+            layout.text(&format!("{} more screenshots in the run's artifacts", more), REGULAR, BODY_SIZE, MARGIN);
+        }
+    }
+    layout.finish(&format!("Run Report: {}", run.prompt))
+}
+
+/// Writes the report of a run into its artifacts, replacing an earlier one.
+fn generate(app: &AppHandle, run_id: &str, template: ReportTemplate) -> Result<PathBuf, String> {
+    let run = app.state::<History>().get(run_id)?.ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let dir = history::artifacts_dir(app, run_id).ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let screenshots: Vec<(PathBuf, String)> = match dir.is_dir() {
+        true => history::artifact_files(&dir)?
+            .into_iter()
+            .filter(|(path, _)| is_screenshot(path))
+            .collect(),
+        false => Vec::new(),
+    };
+    let pdf = render(&run, &screenshots, template);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(FILE_NAME);
+    std::fs::write(&path, pdf).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    log::info!("Wrote {:?} report of run {} to {}", template, run_id, path.display());
+    Ok(path)
+}
+
+/// Writes the report of a finished run in the background when the settings ask for one.
+pub fn run_finished(app: &AppHandle, event: &JobEvent) {
+    let settings = app.state::<SettingsStore>().get().reports;
+    if !settings.scheduled_runs || event.schedule_id.is_none() {
+        return;
+    }
+    let (app, run_id) = (app.clone(), event.job_id.clone());
+    std::thread::spawn(move || {
+        if let Err(e) = generate(&app, &run_id, settings.template) {
+            log::warn!("Failed to write the report of run {}: {}", run_id, e);
+        }
+    });
+}
+
+/// Renders a PDF report of a run and saves it with the run's artifacts as `report.pdf`.
+/// Returns the report's artifact id (`<run_id>/report.pdf`).
+#[tauri::command]
+pub async fn generate_run_report(
+    app: AppHandle,
+    run_id: String,
+    template: Option<ReportTemplate>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        generate(&app, &run_id, template.unwrap_or_default())?;
+        Ok(format!("{}/{}", run_id, FILE_NAME))
+    })
+    .await
+    .map_err(|e| format!("Report failed: {}", e))?
+}
@@ -0,0 +1,289 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::history::{self, History};
+
+/// Reports are kept with the later run, under `diffs/<earlier run id>.json`.
+const DIFFS_DIR: &str = "diffs";
+/// Changes listed in full; the counts always cover all of them.
+const MAX_LISTED: usize = 200;
+/// Channel difference below which pixels count as unchanged (compression noise).
+const PIXEL_TOLERANCE: u8 = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A difference in the runs' structured output, at a JSON pointer path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Rows of a CSV artifact present in only one run. Rows are compared whole, header aside.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableDiff {
+    pub name: String,
+    pub header_changed: bool,
+    pub added: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+    pub added_rows: Vec<Vec<String>>,
+    pub removed_rows: Vec<Vec<String>>,
+}
+
+/// How much of a screenshot changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageDiff {
+    pub name: String,
+    /// Set when the sizes differ, in which case pixels are not compared.
+    pub size_changed: Option<((u32, u32), (u32, u32))>,
+    pub changed_pixels: u64,
+    /// Share of all pixels, 0.0 to 1.0.
+    pub changed_ratio: f64,
+    /// Bounding box of the changes as (x, y, width, height).
+    pub changed_region: Option<(u32, u32, u32, u32)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffReport {
+    pub run_a: String,
+    pub run_b: String,
+    pub created_at: i64,
+    /// False when the runs had different prompts, so differences may be expected.
+    pub prompts_match: bool,
+    pub identical: bool,
+    pub output_changes: usize,
+    pub output: Vec<OutputChange>,
+    pub tables: Vec<TableDiff>,
+    pub images: Vec<ImageDiff>,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    /// Where the report was stored, relative to run B's artifacts.
+    pub artifact: String,
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+pub fn diff_values(path: String, a: Option<&Value>, b: Option<&Value>, changes: &mut Vec<OutputChange>) {
+    match (a, b) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                diff_values(format!("{}/{}", path, escape_pointer(key)), a.get(key), b.get(key), changes);
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for index in 0..a.len().max(b.len()) {
+                diff_values(format!("{}/{}", path, index), a.get(index), b.get(index), changes);
+            }
+        }
+        (a, b) if a == b => {}
+        (a, b) => changes.push(OutputChange {
+            path,
+            kind: match (a, b) {
+                (None, _) => ChangeKind::Added,
+                (_, None) => ChangeKind::Removed,
+                _ => ChangeKind::Changed,
+            },
+            before: a.cloned(),
+            after: b.cloned(),
+        }),
+    }
+}
+
+fn read_csv(path: &Path) -> Result<(Vec<String>, Vec<Vec<String>>), String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let header = reader.headers().map_err(|e| e.to_string())?.iter().map(str::to_string).collect();
+    let rows = reader
+        .records()
+        .map(|record| record.map(|r| r.iter().map(str::to_string).collect()).map_err(|e| e.to_string()))
+        .collect::<Result<_, _>>()?;
+    Ok((header, rows))
+}
+
+fn diff_tables(name: &str, a: &Path, b: &Path) -> Result<TableDiff, String> {
+    let (header_a, rows_a) = read_csv(a)?;
+    let (header_b, rows_b) = read_csv(b)?;
+    // Multisets, so a duplicated row counts as added
+    let mut remaining: BTreeMap<&Vec<String>, usize> = BTreeMap::new();
+    for row in &rows_a {
+        *remaining.entry(row).or_default() += 1;
+    }
+    let mut added_rows = Vec::new();
+    let mut unchanged = 0;
+    for row in &rows_b {
+        match remaining.get_mut(row) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                unchanged += 1;
+            }
+            _ => added_rows.push(row.clone()),
+        }
+    }
+    let mut removed_rows: Vec<Vec<String>> = Vec::new();
+    for (row, count) in remaining {
+        removed_rows.extend(std::iter::repeat(row.clone()).take(count));
+    }
+    let (added, removed) = (added_rows.len(), removed_rows.len());
+    added_rows.truncate(MAX_LISTED);
+    removed_rows.truncate(MAX_LISTED);
+    Ok(TableDiff {
+        name: name.to_string(),
+        header_changed: header_a != header_b,
+        added,
+        removed,
+        unchanged,
+        added_rows,
+        removed_rows,
+    })
+}
+
+/// Decodes a PNG to RGBA pixels.
+pub fn read_png(path: &Path) -> Result<(u32, u32, Vec<[u8; 4]>), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let channels = frame.color_type.samples();
+    let pixels = buffer[..frame.buffer_size()]
+        .chunks_exact(channels)
+        .map(|p| match p {
+            [gray] => [*gray, *gray, *gray, 255],
+            [gray, alpha] => [*gray, *gray, *gray, *alpha],
+            [r, g, b] => [*r, *g, *b, 255],
+            [r, g, b, a] => [*r, *g, *b, *a],
+            _ => [0, 0, 0, 0],
+        })
+        .collect();
+    Ok((frame.width, frame.height, pixels))
+}
+
+fn diff_images(name: &str, a: &Path, b: &Path) -> Result<ImageDiff, String> {
+    let (width_a, height_a, pixels_a) = read_png(a)?;
+    let (width, height, pixels_b) = read_png(b)?;
+    let mut diff = ImageDiff {
+        name: name.to_string(),
+        size_changed: None,
+        changed_pixels: 0,
+        changed_ratio: 0.0,
+        changed_region: None,
+    };
+    if (width_a, height_a) != (width, height) {
+        diff.size_changed = Some(((width_a, height_a), (width, height)));
+        return Ok(diff);
+    }
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    for (index, (a, b)) in pixels_a.iter().zip(&pixels_b).enumerate() {
+        if a.iter().zip(b).all(|(a, b)| a.abs_diff(*b) < PIXEL_TOLERANCE) {
+            continue;
+        }
+        diff.changed_pixels += 1;
+        let (x, y) = ((index as u32) % width, (index as u32) / width);
+        (left, top, right, bottom) = (left.min(x), top.min(y), right.max(x), bottom.max(y));
+    }
+    if diff.changed_pixels > 0 {
+        diff.changed_ratio = diff.changed_pixels as f64 / (width as f64 * height as f64);
+        diff.changed_region = Some((left, top, right - left + 1, bottom - top + 1));
+    }
+    Ok(diff)
+}
+
+/// A run's artifacts by relative name, leaving out earlier diff reports.
+fn artifacts(app: &AppHandle, run_id: &str) -> Result<BTreeMap<String, PathBuf>, String> {
+    let Some(dir) = history::artifacts_dir(app, run_id).filter(|dir| dir.is_dir()) else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(history::artifact_files(&dir)?
+        .into_iter()
+        .filter(|(_, name)| !name.starts_with(&format!("{}/", DIFFS_DIR)))
+        .map(|(path, name)| (name, path))
+        .collect())
+}
+
+fn diff(app: &AppHandle, run_a: &str, run_b: &str) -> Result<DiffReport, String> {
+    let history = app.state::<History>();
+    let a = history.get(run_a)?.ok_or_else(|| format!("No run with id '{}'", run_a))?;
+    let b = history.get(run_b)?.ok_or_else(|| format!("No run with id '{}'", run_b))?;
+
+    let mut output = Vec::new();
+    diff_values(String::new(), a.output.as_ref(), b.output.as_ref(), &mut output);
+    let output_changes = output.len();
+    output.truncate(MAX_LISTED);
+
+    let (files_a, files_b) = (artifacts(app, run_a)?, artifacts(app, run_b)?);
+    let (mut tables, mut images) = (Vec::new(), Vec::new());
+    for (name, path_a) in &files_a {
+        let Some(path_b) = files_b.get(name) else {
+            continue;
+        };
+        let extension = Path::new(name).extension().map(|e| e.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("csv") => tables.push(diff_tables(name, path_a, path_b)?),
+            Some("png") => match diff_images(name, path_a, path_b) {
+                Ok(diff) => images.push(diff),
+                Err(e) => log::warn!("Screenshot {} not compared: {}", name, e),
+            },
+            _ => {}
+        }
+    }
+    let only_in_a: Vec<String> = files_a.keys().filter(|name| !files_b.contains_key(*name)).cloned().collect();
+    let only_in_b: Vec<String> = files_b.keys().filter(|name| !files_a.contains_key(*name)).cloned().collect();
+
+    let identical = output_changes == 0
+        && only_in_a.is_empty()
+        && only_in_b.is_empty()
+        && tables.iter().all(|t| !t.header_changed && t.added == 0 && t.removed == 0)
+        && images.iter().all(|i| i.size_changed.is_none() && i.changed_pixels == 0);
+    let artifact = format!("{}/{}.json", DIFFS_DIR, run_a);
+    let report = DiffReport {
+        run_a: run_a.to_string(),
+        run_b: run_b.to_string(),
+        created_at: history::now_ms(),
+        prompts_match: a.prompt == b.prompt,
+        identical,
+        output_changes,
+        output,
+        tables,
+        images,
+        only_in_a,
+        only_in_b,
+        artifact: artifact.clone(),
+    };
+
+    let dir = history::artifacts_dir(app, run_b).ok_or_else(|| format!("Invalid run id '{}'", run_b))?;
+    let path = dir.join(&artifact);
+    std::fs::create_dir_all(dir.join(DIFFS_DIR)).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    log::info!("Compared runs {} and {}: {} output changes", run_a, run_b, output_changes);
+    Ok(report)
+}
+
+/// Compares two runs (typically of the same recipe): their structured output, the rows
+/// of CSV artifacts and the pixels of PNG screenshots with the same names. The report is
+/// also saved as an artifact of `run_b`.
+#[tauri::command]
+pub async fn diff_runs(app: AppHandle, run_a: String, run_b: String) -> Result<DiffReport, String> {
+    tauri::async_runtime::spawn_blocking(move || diff(&app, &run_a, &run_b))
+        .await
+        .map_err(|e| format!("Comparing runs failed: {}", e))?
+}
@@ -0,0 +1,398 @@
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::change_monitor::MonitorSettings;
+use crate::events::{JobEvent, JobStatus};
+use crate::history::{self, History, ScheduleAuditEntry};
+use crate::notifications::{self, Category, Notification, Urgency};
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::settings::SettingsStore;
+use crate::timetable::Timetable;
+use crate::{blackouts, disk, idle, integrations, jobs, launch, license, queue, recipes, server, standby, AppState};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// An automation run repeatedly by the shell on the main server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    pub prompt: String,
+    pub every_minutes: u64,
+    /// Cron expression (five fields, or six with seconds) run on `time_zone`'s wall
+    /// clock instead of every `every_minutes`.
+    #[serde(default)]
+    pub cron: Option<String>,
+    /// IANA time zone of `cron`, e.g. `Europe/Berlin`; unset follows the machine's zone.
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Email a report after each run.
+    #[serde(default)]
+    pub email_report: bool,
+    /// Report recipients; empty uses the default recipients from the email settings.
+    #[serde(default)]
+    pub report_recipients: Vec<String>,
+    /// A heavy job: once due, wait until the user is away before running it.
+    #[serde(default)]
+    pub only_when_idle: bool,
+    /// Upload the run's artifacts to the configured bucket afterwards.
+    #[serde(default)]
+    pub upload_results: bool,
+    /// Runs during blackout windows too.
+    #[serde(default)]
+    pub ignore_blackouts: bool,
+    /// Runs again after a failure.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// What to do once a run has failed for good.
+    #[serde(default)]
+    pub on_failure: FailureHooks,
+    /// Compare each run's output with the previous one and alert on changes.
+    #[serde(default)]
+    pub monitor: MonitorSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Runs in total, counting the first; 1 means no retries.
+    pub max_attempts: u32,
+    /// Wait before the first retry; each further one waits `backoff_factor` times longer.
+    pub backoff_seconds: u64,
+    pub backoff_factor: f64,
+    /// Retry only when the error message contains one of these (case-insensitive);
+    /// empty retries any failure.
+    pub retry_on: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { max_attempts: 1, backoff_seconds: 60, backoff_factor: 2.0, retry_on: Vec::new() }
+    }
+}
+
+impl RetryPolicy {
+    fn applies_to(&self, message: Option<&str>) -> bool {
+        let message = message.unwrap_or_default().to_lowercase();
+        self.retry_on.is_empty() || self.retry_on.iter().any(|pattern| message.contains(&pattern.to_lowercase()))
+    }
+
+    /// Wait before attempt `attempt + 1`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self.backoff_factor.max(1.0).powi(attempt.saturating_sub(1) as i32);
+        Duration::from_secs_f64((self.backoff_seconds as f64 * factor).min(86400.0))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FailureHooks {
+    /// Desktop notification, even while the window is focused.
+    pub notify: bool,
+    /// Name of a configured webhook to send, whether or not it is enabled for all jobs.
+    pub webhook: Option<String>,
+    /// Recipe id queued in place of the failed run.
+    pub fallback_recipe: Option<String>,
+}
+
+/// What to record once a due schedule has run.
+enum Due {
+    Interval,
+    /// The wall-clock time the run was for.
+    Cron(NaiveDateTime),
+}
+
+impl Schedule {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.every_minutes.max(1) * 60)
+    }
+
+    pub fn timetable(&self) -> Result<Option<Timetable>, String> {
+        match self.cron.as_deref().filter(|cron| !cron.trim().is_empty()) {
+            Some(cron) => Timetable::new(cron, self.time_zone.as_deref()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Wall-clock time of the last cron run, or of when the schedule was first seen.
+    fn last_wall(&self, timetable: &Timetable) -> NaiveDateTime {
+        *LAST_WALL.lock().unwrap().entry(self.id.clone()).or_insert_with(|| timetable.now_wall())
+    }
+
+    fn due(&self) -> Result<Option<Due>, String> {
+        match self.timetable()? {
+            Some(timetable) => {
+                let run = timetable.due(self.last_wall(&timetable), Utc::now());
+                Ok(run.map(|run| Due::Cron(run.wall)))
+            }
+            None => {
+                let last = *LAST_RUN.lock().unwrap().entry(self.id.clone()).or_insert_with(Instant::now);
+                Ok((last.elapsed() >= self.interval()).then_some(Due::Interval))
+            }
+        }
+    }
+
+    fn mark_run(&self, due: Due) {
+        match due {
+            Due::Interval => {
+                LAST_RUN.lock().unwrap().insert(self.id.clone(), Instant::now());
+            }
+            Due::Cron(wall) => {
+                LAST_WALL.lock().unwrap().insert(self.id.clone(), wall);
+            }
+        }
+    }
+
+    /// When the scheduler will next start this schedule, and the `count - 1` times after,
+    /// ignoring idle and server waits. Empty if its cron expression or zone is invalid.
+    pub fn upcoming_runs(&self, count: usize) -> Vec<SystemTime> {
+        match self.timetable() {
+            Ok(Some(timetable)) => {
+                timetable.runs_after(self.last_wall(&timetable)).take(count).map(|run| run.at.into()).collect()
+            }
+            Ok(None) => {
+                let now = Instant::now();
+                let last = LAST_RUN.lock().unwrap().get(&self.id).copied().unwrap_or(now);
+                let next = SystemTime::now() + (last + self.interval()).saturating_duration_since(now);
+                (0..count as u32).map(|n| next + self.interval() * n).collect()
+            }
+            Err(e) => {
+                log::debug!("Schedule '{}' has no upcoming runs: {}", self.name, e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// A future run of a schedule, for countdowns.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpcomingRun {
+    pub schedule_id: String,
+    pub name: String,
+    /// Unix milliseconds.
+    pub at: i64,
+    pub in_seconds: u64,
+}
+
+/// The next `limit` runs across all enabled schedules, soonest first, leaving out those a
+/// blackout window suppresses. Nothing runs in safe or viewer mode or without the
+/// scheduling entitlement, so then there are none.
+pub fn upcoming(app: &AppHandle, limit: usize) -> Vec<UpcomingRun> {
+    if launch::is_safe_mode(app) || launch::is_viewer(app) || !license::entitlements(app).scheduling {
+        return Vec::new();
+    }
+    let now = SystemTime::now();
+    let settings = app.state::<SettingsStore>().get();
+    let mut runs = Vec::new();
+    for schedule in settings.schedules.iter().filter(|s| s.enabled) {
+        let suppressed =
+            |at: SystemTime| !schedule.ignore_blackouts && blackouts::covering(&settings.blackouts, at.into()).is_some();
+        // A schedule can't take more than `limit` places in the first `limit` runs
+        for at in schedule.upcoming_runs(limit).into_iter().filter(|&at| !suppressed(at)) {
+            let in_seconds = at.duration_since(now).unwrap_or_default().as_secs();
+            let at_ms = at.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0);
+            runs.push(UpcomingRun {
+                schedule_id: schedule.id.clone(),
+                name: schedule.name.clone(),
+                at: at_ms,
+                in_seconds,
+            });
+        }
+    }
+    runs.sort_by_key(|run| run.at);
+    runs.truncate(limit);
+    runs
+}
+
+#[tauri::command]
+pub fn get_upcoming_runs(app: AppHandle, limit: Option<usize>) -> Vec<UpcomingRun> {
+    upcoming(&app, limit.unwrap_or(10).min(500))
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// When each schedule last ran. A schedule's first run is one interval after the app
+/// starts (or the schedule is added), not immediately, so it is counted from then.
+static LAST_RUN: Mutex<BTreeMap<String, Instant>> = Mutex::new(BTreeMap::new());
+
+/// The same for cron schedules, as the wall-clock time the last run was for. Comparing
+/// wall-clock times keeps a DST change or a move to another zone from repeating a run.
+static LAST_WALL: Mutex<BTreeMap<String, NaiveDateTime>> = Mutex::new(BTreeMap::new());
+
+/// Attempt number of queued retries, by job id. First runs are not listed.
+static ATTEMPTS: Mutex<BTreeMap<String, u32>> = Mutex::new(BTreeMap::new());
+
+fn audit(app: &AppHandle, schedule_id: &str, event: &str, detail: Option<String>) {
+    let entry = ScheduleAuditEntry {
+        schedule_id: schedule_id.to_string(),
+        at: history::now_ms(),
+        event: event.to_string(),
+        detail,
+    };
+    if let Err(e) = app.state::<History>().add_schedule_audit(&entry) {
+        log::warn!("Failed to record a schedule audit entry: {}", e);
+    }
+}
+
+/// Starts the scheduler loop. `disabled` (safe or viewer mode) leaves schedules off.
+pub fn setup(app: &AppHandle, disabled: bool) {
+    if disabled {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(TICK_INTERVAL);
+            standby::update_tooltip(&app);
+            // Without the entitlement schedules stay saved but do not run
+            if !license::entitlements(&app).scheduling {
+                continue;
+            }
+            // Unattended runs would fill the disk with artifacts; they resume once space is freed
+            if disk::is_critical() {
+                continue;
+            }
+            let schedules = app.state::<SettingsStore>().get().schedules;
+            for schedule in schedules.iter().filter(|s| s.enabled) {
+                let due = match schedule.due() {
+                    Ok(Some(due)) => due,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        log::warn!("Schedule '{}' cannot run: {}", schedule.name, e);
+                        continue;
+                    }
+                };
+                // Skipped rather than delayed: a holiday should not end with every schedule at once
+                if let Some(window) = blackouts::active(&app, Utc::now()).filter(|_| !schedule.ignore_blackouts) {
+                    log::info!("Schedule '{}' suppressed by blackout window '{}'", schedule.name, window.name);
+                    schedule.mark_run(due);
+                    audit(&app, &schedule.id, "suppressed", Some(format!("Blackout window '{}'", window.name)));
+                    continue;
+                }
+                // Stays due, so it runs on the first tick after the user steps away
+                if schedule.only_when_idle && !idle::is_idle() {
+                    log::debug!("Schedule '{}' is due, waiting for the user to be idle", schedule.name);
+                    continue;
+                }
+                let port = *app.state::<AppState>().server_port.lock().unwrap();
+                if !server::is_ready(port) {
+                    log::warn!("Schedule '{}' is due but the server is not ready", schedule.name);
+                    continue;
+                }
+                schedule.mark_run(due);
+                standby::update_tooltip(&app);
+                let job_id = jobs::new_job_id();
+                log::info!("Running schedule '{}' as job {}", schedule.name, job_id);
+                // A job can take many minutes; other schedules keep their times meanwhile
+                let (app, prompt, schedule_id) = (app.clone(), schedule.prompt.clone(), schedule.id.clone());
+                std::thread::spawn(move || jobs::run(&app, port, job_id, &prompt, Some(schedule_id), None));
+            }
+        }
+    });
+}
+
+fn run_failure_hooks(app: &AppHandle, schedule: &Schedule, job: &JobEvent, attempts: u32) {
+    let hooks = &schedule.on_failure;
+    let reason = job.message.as_deref().unwrap_or("no error message");
+    if hooks.notify {
+        notifications::notify(
+            app,
+            Notification {
+                title: format!("Schedule '{}' failed", schedule.name),
+                body: format!("Failed after {} attempts: {}", attempts, reason),
+                urgency: Urgency::Critical,
+                category: Category::Schedules,
+            },
+        );
+    }
+    if let Some(name) = hooks.webhook.as_deref() {
+        let webhooks = app.state::<SettingsStore>().get().webhooks;
+        match webhooks.iter().find(|w| w.name == name) {
+            Some(webhook) => {
+                if let Err(e) = integrations::send(webhook, job) {
+                    log::warn!("Failure webhook '{}' of schedule '{}' failed: {}", name, schedule.name, e);
+                }
+            }
+            None => log::warn!("Schedule '{}' names a webhook '{}' that does not exist", schedule.name, name),
+        }
+    }
+    if let Some(recipe) = hooks.fallback_recipe.as_deref() {
+        let queued = recipes::load(app, recipe)
+            .and_then(|loaded| queue::enqueue(app, loaded.task.prompt, 0, None, Some(recipe.to_string())));
+        match queued {
+            Ok(fallback) => log::info!("Queued fallback recipe '{}' as job {}", recipe, fallback.id),
+            Err(e) => log::error!("Fallback recipe '{}' of schedule '{}' not queued: {}", recipe, schedule.name, e),
+        }
+    }
+}
+
+/// Built-in plugin that retries failed scheduled runs per the schedule's policy, then
+/// runs its failure hooks once no attempts are left.
+struct RetryPlugin;
+
+impl ShellPlugin for RetryPlugin {
+    fn name(&self) -> &str {
+        "schedule-retries"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        let attempt = ATTEMPTS.lock().unwrap().remove(&job.job_id).unwrap_or(1);
+        if job.status != Some(JobStatus::Error) {
+            return;
+        }
+        let Some(schedule_id) = job.schedule_id.as_deref() else {
+            return;
+        };
+        let settings = ctx.app.state::<SettingsStore>().get();
+        let Some(schedule) = settings.schedules.into_iter().find(|s| s.id == schedule_id) else {
+            return;
+        };
+        if attempt >= schedule.retry.max_attempts || !schedule.retry.applies_to(job.message.as_deref()) {
+            run_failure_hooks(ctx.app, &schedule, job, attempt);
+            return;
+        }
+
+        let delay = schedule.retry.backoff(attempt);
+        log::info!(
+            "Schedule '{}' failed (attempt {} of {}), retrying in {}s",
+            schedule.name,
+            attempt,
+            schedule.retry.max_attempts,
+            delay.as_secs()
+        );
+        let app = ctx.app.clone();
+        let (prompt, recipe_id) = (job.prompt.clone(), job.recipe_id.clone());
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            match queue::enqueue(&app, prompt, 0, Some(schedule.id.clone()), recipe_id) {
+                Ok(retry) => {
+                    ATTEMPTS.lock().unwrap().insert(retry.id, attempt + 1);
+                }
+                Err(e) => log::error!("Retry of schedule '{}' not queued: {}", schedule.name, e),
+            }
+        });
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(RetryPlugin), "Retries failed scheduled runs and runs their failure hooks");
+}
+
+/// What the scheduler did instead of running schedules, newest first.
+#[tauri::command]
+pub fn list_schedule_audit(
+    app: AppHandle,
+    schedule_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ScheduleAuditEntry>, String> {
+    app.state::<History>().schedule_audit(schedule_id.as_deref(), limit.unwrap_or(100))
+}
@@ -0,0 +1,96 @@
+use std::io::Read;
+use tauri::{AppHandle, Manager, State};
+
+use crate::history::{self, History, SearchMatch};
+
+/// Artifacts indexed for search, by extension.
+const TEXT_EXTENSIONS: &[&str] = &["txt", "log", "json", "jsonl", "csv", "md", "html", "htm", "xml", "yaml", "yml"];
+
+/// Only the start of larger text artifacts is indexed.
+const MAX_INDEXED_BYTES: u64 = 2 * 1024 * 1024;
+
+fn read_text(path: &std::path::Path) -> Option<String> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path).ok()?.take(MAX_INDEXED_BYTES).read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// (Re)indexes a run's prompt, result and text artifacts.
+pub fn index_run(app: &AppHandle, run_id: &str) -> Result<(), String> {
+    let history = app.state::<History>();
+    let Some(run) = history.get(run_id)? else {
+        return Ok(());
+    };
+    let mut texts = vec![("prompt".to_string(), run.prompt)];
+    if let Some(message) = run.message {
+        texts.push(("message".to_string(), message));
+    }
+    if let Some(output) = run.output {
+        let output = output.as_str().map(str::to_string).unwrap_or_else(|| output.to_string());
+        texts.push(("output".to_string(), output));
+    }
+    if let Some(dir) = history::artifacts_dir(app, run_id).filter(|dir| dir.is_dir()) {
+        for (path, name) in history::artifact_files(&dir)? {
+            let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+            if !TEXT_EXTENSIONS.contains(&extension.as_str()) {
+                continue;
+            }
+            if let Some(content) = read_text(&path) {
+                texts.push((name, content));
+            }
+        }
+    }
+    history.index_run_text(run_id, &texts)
+}
+
+/// Indexes a finished run in the background.
+pub fn run_finished(app: &AppHandle, run_id: &str) {
+    let (app, run_id) = (app.clone(), run_id.to_string());
+    std::thread::spawn(move || {
+        if let Err(e) = index_run(&app, &run_id) {
+            log::warn!("Failed to index run {} for search: {}", run_id, e);
+        }
+    });
+}
+
+/// Indexes runs recorded before search existed, or while indexing failed.
+pub fn setup(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let runs = match app.state::<History>().unindexed_runs() {
+            Ok(runs) => runs,
+            Err(e) => {
+                log::warn!("Failed to find runs to index: {}", e);
+                return;
+            }
+        };
+        if !runs.is_empty() {
+            log::info!("Indexing {} runs for search", runs.len());
+        }
+        for run_id in runs {
+            if let Err(e) = index_run(&app, &run_id) {
+                log::warn!("Failed to index run {} for search: {}", run_id, e);
+            }
+        }
+    });
+}
+
+/// Turns what the user typed into an FTS5 query matching runs that contain every word,
+/// so punctuation in pasted error messages isn't read as query syntax.
+fn to_fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Finds runs whose prompt, result or text artifacts contain `query`, best matches first.
+#[tauri::command]
+pub fn search_runs(history: State<'_, History>, query: String, limit: Option<u32>) -> Result<Vec<SearchMatch>, String> {
+    let query = to_fts_query(&query);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    history.search(&query, limit.unwrap_or(50))
+}
@@ -0,0 +1,28 @@
+/// Credentials kept in the OS keychain (Keychain, Credential Manager, Secret Service)
+/// rather than in the settings file.
+const SERVICE: &str = "com.browserautomation.desktop";
+
+fn entry(key: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, key).map_err(|e| format!("Keychain unavailable: {}", e))
+}
+
+pub fn get(key: &str) -> Result<Option<String>, String> {
+    match entry(key)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read '{}' from the keychain: {}", key, e)),
+    }
+}
+
+pub fn set(key: &str, value: &str) -> Result<(), String> {
+    entry(key)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store '{}' in the keychain: {}", key, e))
+}
+
+pub fn delete(key: &str) -> Result<(), String> {
+    match entry(key)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to remove '{}' from the keychain: {}", key, e)),
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::http::HeaderMap;
+use tauri::plugin::{Builder as PluginBuilder, TauriPlugin};
+use tauri::Wry;
+
+use crate::control_api;
+
+/// How long a successful authentication covers further sensitive actions, so one flow
+/// (say, confirming and then purging) prompts only once.
+const GRACE_PERIOD: Duration = Duration::from_secs(2 * 60);
+
+/// Header privileged commands must carry the session nonce in.
+const NONCE_HEADER: &str = "x-session-nonce";
+/// Adds the nonce to every IPC call from the app's own, bundled pages. Anything the local
+/// server renders may hold content from automated sites and never gets it. The nonce only
+/// lives in this closure, so scripts on other pages can't read it from a global.
+const NONCE_SCRIPT: &str = r#"
+(function (nonce, header) {
+    const trusted = location.protocol === 'tauri:' || location.hostname === 'tauri.localhost';
+    const internals = window.__TAURI_INTERNALS__;
+    if (!trusted || !internals) return;
+    const invoke = internals.invoke;
+    internals.invoke = (cmd, args, options) => {
+        const headers = Object.assign({}, options && options.headers, { [header]: nonce });
+        return invoke(cmd, args, Object.assign({}, options, { headers }));
+    };
+})"#;
+
+/// When the user last authenticated.
+static LAST_AUTHENTICATED: Mutex<Option<Instant>> = Mutex::new(None);
+/// Random per launch, handed only to the app's own pages.
+static SESSION_NONCE: OnceLock<String> = OnceLock::new();
+
+fn session_nonce() -> &'static str {
+    SESSION_NONCE.get_or_init(|| format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()))
+}
+
+/// Whether an IPC call came from one of the app's own pages, judged by the nonce it
+/// carries. See `access::guard` for which commands need it.
+pub fn has_session_nonce(headers: &HeaderMap) -> bool {
+    let given = headers.get(NONCE_HEADER).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    control_api::token_matches(given, session_nonce())
+}
+
+/// Injects the session nonce into the app's own pages as they load.
+pub fn plugin() -> TauriPlugin<Wry> {
+    let script = format!("{}('{}', '{}');", NONCE_SCRIPT, session_nonce(), NONCE_HEADER);
+    PluginBuilder::new("session-nonce").js_init_script(script).build()
+}
+
+/// Asks for Touch ID or the login password via Local Authentication.
+#[cfg(target_os = "macos")]
+fn prompt(reason: &str) -> Result<(), String> {
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send};
+    use objc2_foundation::{NSError, NSString};
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+    /// `LAPolicyDeviceOwnerAuthentication`: biometrics, falling back to the password.
+    const DEVICE_OWNER_AUTHENTICATION: isize = 2;
+
+    let (done, finished) = std::sync::mpsc::channel();
+    let reason = NSString::from_str(reason);
+    let reply = RcBlock::new(move |success: Bool, error: *mut NSError| {
+        let result = match unsafe { error.as_ref() } {
+            _ if success.as_bool() => Ok(()),
+            Some(error) => Err(error.localizedDescription().to_string()),
+            None => Err("Authentication failed".to_string()),
+        };
+        let _ = done.send(result);
+    });
+    unsafe {
+        let context: Retained<AnyObject> = msg_send![class!(LAContext), new];
+        let _: () = msg_send![
+            &context,
+            evaluatePolicy: DEVICE_OWNER_AUTHENTICATION,
+            localizedReason: &*reason,
+            reply: &*reply
+        ];
+    }
+    finished.recv().map_err(|_| "Authentication was interrupted".to_string())?
+}
+
+/// Asks for Windows Hello (face, fingerprint or PIN).
+#[cfg(windows)]
+fn prompt(reason: &str) -> Result<(), String> {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{
+        UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+
+    let availability = UserConsentVerifier::CheckAvailabilityAsync()
+        .and_then(|operation| operation.get())
+        .map_err(|e| format!("Windows Hello is unavailable: {}", e))?;
+    if availability != UserConsentVerifierAvailability::Available {
+        return Err("Set up Windows Hello to confirm this action".to_string());
+    }
+    let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+        .and_then(|operation| operation.get())
+        .map_err(|e| format!("Windows Hello failed: {}", e))?;
+    match result {
+        UserConsentVerificationResult::Verified => Ok(()),
+        UserConsentVerificationResult::Canceled => Err("Authentication was cancelled".to_string()),
+        _ => Err("Authentication failed".to_string()),
+    }
+}
+
+/// Asks the desktop's polkit agent for the user's password. There is no action of our
+/// own to check, so this borrows the one `pkexec` uses, which always needs authentication.
+#[cfg(target_os = "linux")]
+fn prompt(reason: &str) -> Result<(), String> {
+    log::info!("Requesting authentication: {}", reason);
+    let status = std::process::Command::new("pkcheck")
+        .args(["--action-id", "org.freedesktop.policykit.exec", "--allow-user-interaction", "--process"])
+        .arg(std::process::id().to_string())
+        .stdout(std::process::Stdio::null())
+        .status()
+        .map_err(|e| format!("polkit is not available to confirm this action: {}", e))?;
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(3) => Err("Authentication was cancelled".to_string()),
+        _ => Err("Authentication failed".to_string()),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+fn prompt(_reason: &str) -> Result<(), String> {
+    Err("Confirming your identity is not supported on this platform".to_string())
+}
+
+/// Makes the user confirm who they are with the OS (Touch ID, Windows Hello, polkit)
+/// unless they did within `GRACE_PERIOD`. `reason` is shown in the prompt where the OS
+/// allows. Blocks until the prompt is answered, so keep it off the main thread.
+pub fn authenticate(reason: &str) -> Result<(), String> {
+    let recent = LAST_AUTHENTICATED.lock().unwrap().is_some_and(|at| at.elapsed() < GRACE_PERIOD);
+    if recent {
+        return Ok(());
+    }
+    prompt(reason).inspect_err(|e| log::warn!("Authentication for '{}' refused: {}", reason, e))?;
+    *LAST_AUTHENTICATED.lock().unwrap() = Some(Instant::now());
+    Ok(())
+}
+
+/// Lets the frontend confirm the user before a sensitive step of its own. Commands that
+/// reveal secrets or weaken security also check by themselves.
+#[tauri::command]
+pub async fn authenticate_user(reason: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || authenticate(&reason))
+        .await
+        .map_err(|e| format!("Authentication failed: {}", e))?
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Url, WebviewUrl, WebviewWindowBuilder};
+
+/// Label of the selector picker window.
+const PICKER_LABEL: &str = "selector-picker";
+/// The injected script reports a pick by navigating here; the navigation is cancelled.
+const PICK_HOST: &str = "selector-picker.invalid";
+
+/// Highlights the element under the pointer and, on click, works out selectors for it and
+/// reports them by navigating to `PICK_HOST`. Escape pauses picking so the page can be
+/// used normally, e.g. to log in or open a menu first.
+const PICKER_SCRIPT: &str = r#"
+(function () {
+    if (window !== window.top || window.__selectorPicker) return;
+    window.__selectorPicker = true;
+    let picking = true;
+    let box, banner;
+
+    const unique = (selector) => {
+        try { return document.querySelectorAll(selector).length === 1; } catch (e) { return false; }
+    };
+    const quote = (value) => JSON.stringify(value);
+    // Ids and classes with long digit runs or hashes are usually generated per build or load
+    const generated = /\d{3,}|[0-9a-f]{8,}|^[a-z]{1,3}-[A-Za-z0-9]{5,}$/;
+    const stable = (value) => value && value.length < 60 && !generated.test(value);
+
+    const sameTag = (node) => node.parentElement
+        ? [...node.parentElement.children].filter(c => c.tagName === node.tagName)
+        : [node];
+
+    function cssPath(el) {
+        const parts = [];
+        const root = document.documentElement;
+        for (let node = el; node && node.nodeType === 1 && node !== root; node = node.parentElement) {
+            let part = node.tagName.toLowerCase();
+            if (node.id && stable(node.id)) {
+                parts.unshift(`#${CSS.escape(node.id)}`);
+                break;
+            }
+            const siblings = sameTag(node);
+            if (siblings.length > 1) part += `:nth-of-type(${siblings.indexOf(node) + 1})`;
+            parts.unshift(part);
+            // Stop as soon as the path is unique, to keep it short
+            if (unique(parts.join(' > '))) break;
+        }
+        return parts.join(' > ');
+    }
+
+    function xpath(el) {
+        const parts = [];
+        for (let node = el; node && node.nodeType === 1; node = node.parentElement) {
+            if (node.id && stable(node.id)) {
+                parts.unshift(`//*[@id=${quote(node.id)}]`);
+                return parts.join('/');
+            }
+            const siblings = sameTag(node);
+            parts.unshift(`${node.tagName.toLowerCase()}[${siblings.indexOf(node) + 1}]`);
+        }
+        return '/' + parts.join('/');
+    }
+
+    function selectors(el) {
+        const found = [];
+        const add = (kind, value, isUnique) => found.push({ kind, value, unique: isUnique });
+        for (const attr of ['data-testid', 'data-test', 'data-qa', 'data-cy']) {
+            const value = el.getAttribute(attr);
+            if (value) add('css', `[${attr}=${quote(value)}]`, unique(`[${attr}=${quote(value)}]`));
+        }
+        if (el.id && stable(el.id)) add('css', `#${CSS.escape(el.id)}`, unique(`#${CSS.escape(el.id)}`));
+        const tag = el.tagName.toLowerCase();
+        for (const attr of ['name', 'aria-label', 'placeholder', 'title', 'alt']) {
+            const value = el.getAttribute(attr);
+            if (value) {
+                const selector = `${tag}[${attr}=${quote(value)}]`;
+                add('css', selector, unique(selector));
+            }
+        }
+        const text = (el.innerText || '').trim().replace(/\s+/g, ' ');
+        if (text && text.length <= 50) {
+            const role = el.getAttribute('role') || ({ a: 'link', button: 'button' })[tag];
+            if (role) add('role', `role=${role}[name=${quote(text)}]`, false);
+            add('text', `text=${quote(text)}`, false);
+        }
+        const path = cssPath(el);
+        add('css', path, unique(path));
+        add('xpath', xpath(el), true);
+        // Unique ones first, keeping the order of preference within each group
+        return found.filter(s => s.unique).concat(found.filter(s => !s.unique));
+    }
+
+    function overlay() {
+        box = document.createElement('div');
+        box.style.cssText = 'position:fixed;pointer-events:none;z-index:2147483647;border:2px solid #f59e0b;' +
+            'background:rgba(245,158,11,0.15);border-radius:2px;display:none';
+        banner = document.createElement('div');
+        banner.style.cssText = 'position:fixed;bottom:12px;left:50%;transform:translateX(-50%);z-index:2147483647;' +
+            'background:#1a1a1a;color:#fff;font:13px -apple-system,BlinkMacSystemFont,sans-serif;padding:6px 12px;' +
+            'border-radius:6px;pointer-events:none;opacity:0.9';
+        document.documentElement.append(box, banner);
+        update();
+    }
+
+    function update() {
+        banner.textContent = picking
+            ? 'Click an element to pick it · Esc to use the page'
+            : 'Using the page · Esc to pick again';
+        if (!picking) box.style.display = 'none';
+    }
+
+    document.addEventListener('mousemove', (e) => {
+        if (!picking || !box) return;
+        const rect = e.target.getBoundingClientRect();
+        Object.assign(box.style, {
+            display: 'block', left: `${rect.left}px`, top: `${rect.top}px`,
+            width: `${rect.width}px`, height: `${rect.height}px`,
+        });
+    }, true);
+
+    for (const type of ['mousedown', 'mouseup', 'click', 'auxclick', 'dblclick']) {
+        document.addEventListener(type, (e) => {
+            if (!picking) return;
+            e.preventDefault();
+            e.stopImmediatePropagation();
+            if (type !== 'click') return;
+            const el = e.target;
+            const pick = {
+                url: location.href,
+                tag: el.tagName.toLowerCase(),
+                text: (el.innerText || el.value || '').trim().slice(0, 200),
+                selectors: selectors(el),
+            };
+            location.href = 'https://selector-picker.invalid/pick?data=' + encodeURIComponent(JSON.stringify(pick));
+        }, true);
+    }
+
+    document.addEventListener('keydown', (e) => {
+        if (e.key !== 'Escape') return;
+        picking = !picking;
+        update();
+        e.preventDefault();
+    }, true);
+
+    if (document.readyState === 'loading') {
+        document.addEventListener('DOMContentLoaded', overlay);
+    } else {
+        overlay();
+    }
+})();
+"#;
+
+/// One way to find the picked element, best first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickedSelector {
+    /// `css`, `xpath`, or a Playwright `role` or `text` selector.
+    pub kind: String,
+    pub value: String,
+    /// Matched only this element on the page when it was picked.
+    pub unique: bool,
+}
+
+/// Sent to the app's windows as `selector-picked`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickedElement {
+    pub url: String,
+    pub tag: String,
+    pub text: String,
+    pub selectors: Vec<PickedSelector>,
+}
+
+/// The element a navigation to `PICK_HOST` reports, if that is what it is.
+fn picked(url: &Url) -> Option<PickedElement> {
+    if url.host_str() != Some(PICK_HOST) {
+        return None;
+    }
+    let data = url.query_pairs().find(|(name, _)| name == "data")?.1;
+    match serde_json::from_str(&data) {
+        Ok(element) => Some(element),
+        Err(e) => {
+            log::warn!("Ignoring a malformed pick: {}", e);
+            None
+        }
+    }
+}
+
+/// Opens the selector picker on `url`, or points the open one there. Each element the
+/// user clicks is sent to the app's windows as `selector-picked`, with selectors for it.
+#[tauri::command]
+pub async fn open_selector_picker(app: AppHandle, url: String) -> Result<(), String> {
+    let url = Url::parse(&url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("The picker only opens web pages, not {}", url.scheme()));
+    }
+    if let Some(window) = app.get_webview_window(PICKER_LABEL) {
+        window.navigate(url).map_err(|e| format!("Failed to load the page: {}", e))?;
+        return window.set_focus().map_err(|e| format!("Failed to focus the picker: {}", e));
+    }
+    let handle = app.clone();
+    WebviewWindowBuilder::new(&app, PICKER_LABEL, WebviewUrl::External(url.clone()))
+        .title("Pick an Element")
+        .inner_size(1100.0, 800.0)
+        .initialization_script(PICKER_SCRIPT)
+        .on_navigation(move |url| {
+            let Some(element) = picked(url) else {
+                return true;
+            };
+            log::info!("Picked <{}> on {}", element.tag, element.url);
+            let _ = handle.emit("selector-picked", &element);
+            false
+        })
+        .build()
+        .map_err(|e| format!("Failed to open the selector picker: {}", e))?;
+    log::info!("Selector picker opened on {}", url);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn close_selector_picker(app: AppHandle) -> Result<(), String> {
+    match app.get_webview_window(PICKER_LABEL) {
+        Some(window) => window.close().map_err(|e| format!("Failed to close the selector picker: {}", e)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,248 @@
+use sha2::{Digest, Sha256};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::time::Duration;
+
+use crate::packaging;
+
+// Ports handed out to server instances. Kept in sync with the port scan in ui/index.html.
+pub const START_PORT: u16 = 5555;
+pub const END_PORT: u16 = 5655;
+
+pub fn find_available_port() -> Result<u16, std::io::Error> {
+    find_port_excluding(&[])
+}
+
+/// Like `find_available_port`, but skips ports already handed to servers that may not
+/// have bound them yet.
+pub fn find_port_excluding(exclude: &[u16]) -> Result<u16, std::io::Error> {
+    // Try to find an available port in the range 5555-5655 (100 ports)
+    // This keeps ports predictable and avoids conflicts with other services
+    for port in (START_PORT..=END_PORT).filter(|port| !exclude.contains(port)) {
+        match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(listener) => {
+                // Port is available, drop the listener to free it
+                drop(listener);
+                log::info!("Found available port: {}", port);
+                return Ok(port);
+            }
+            Err(_) => {
+                // Port is in use, try next one
+                continue;
+            }
+        }
+    }
+
+    // If all ports in range are taken, return error
+    Err(std::io::Error::new(
+        std::io::ErrorKind::AddrInUse,
+        format!("No available ports found in range {}-{}", START_PORT, END_PORT)
+    ))
+}
+
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Checksum recorded by the bundle script in `server.sha256`, if the bundle has one.
+pub fn expected_checksum(binary: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(binary.with_file_name("server.sha256")).ok()?;
+    // shasum output format: "<hex>  <filename>"
+    contents.split_whitespace().next().map(|hex| hex.to_lowercase())
+}
+
+/// Checks the server binary exists and matches its recorded checksum.
+/// Returns the actual checksum on success.
+pub fn verify_binary(binary: &Path) -> Result<String, String> {
+    if !binary.exists() {
+        return Err(format!("Server binary not found at {}", binary.display()));
+    }
+    let actual = sha256_file(binary).map_err(|e| format!("Failed to hash server binary: {}", e))?;
+    match expected_checksum(binary) {
+        Some(expected) if expected != actual => {
+            Err(format!("Checksum mismatch: expected {}, got {}", expected, actual))
+        }
+        _ => Ok(actual),
+    }
+}
+
+/// Whether VERBOSE is set in our environment and should be forwarded to the server.
+pub fn verbose_requested() -> bool {
+    std::env::var("VERBOSE")
+        .map(|v| v.to_lowercase() == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// Variables passed through from the shell's environment when set. Everything else the
+/// user's shell exported (pyenv, conda, proxies, `PYTHONPATH`...) is dropped so it cannot
+/// break the bundled runtime; `NOVA_*` variables always pass.
+const INHERITED_ENV: &[&str] = &[
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "TZ",
+    "TMPDIR",
+    // The browser needs the desktop session on Linux
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XDG_RUNTIME_DIR",
+    "DBUS_SESSION_BUS_ADDRESS",
+    "ELEVENLABS_AGENT_ID",
+];
+
+/// Windows system variables the Python runtime and browser cannot start without.
+#[cfg(windows)]
+const INHERITED_ENV_WINDOWS: &[&str] = &[
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "USERNAME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+    "PROGRAMFILES",
+    "PROGRAMFILES(X86)",
+    "TEMP",
+    "TMP",
+    "COMPUTERNAME",
+    "NUMBER_OF_PROCESSORS",
+    "PROCESSOR_ARCHITECTURE",
+];
+
+#[cfg(windows)]
+fn system_path() -> String {
+    let root = std::env::var("SYSTEMROOT").unwrap_or_else(|_| r"C:\Windows".to_string());
+    format!(r"{root}\System32;{root};{root}\System32\Wbem", root = root)
+}
+
+#[cfg(not(windows))]
+fn system_path() -> String {
+    "/usr/local/bin:/usr/bin:/bin:/usr/sbin:/sbin".to_string()
+}
+
+/// Replaces the inherited environment with a curated one. `NOVA_INHERIT_ENV=1` keeps
+/// the full environment for troubleshooting.
+fn sandbox_env(cmd: &mut Command) {
+    if std::env::var("NOVA_INHERIT_ENV").is_ok_and(|v| v == "1") {
+        log::warn!("NOVA_INHERIT_ENV set - server inherits the full environment");
+        return;
+    }
+    // Linux sandboxes keep their own tools ahead of the system ones
+    let mut path: Vec<PathBuf> = packaging::extra_path();
+    path.extend(std::env::split_paths(&system_path()));
+    cmd.env_clear().env("PATH", std::env::join_paths(path).unwrap_or_else(|_| system_path().into()));
+    #[cfg(windows)]
+    let names = INHERITED_ENV.iter().chain(INHERITED_ENV_WINDOWS);
+    #[cfg(not(windows))]
+    let names = INHERITED_ENV.iter();
+    let names = names.chain(packaging::inherited_env());
+    for name in names {
+        if let Some(value) = std::env::var_os(name) {
+            cmd.env(name, value);
+        }
+    }
+    for (name, value) in std::env::vars_os() {
+        if name.to_string_lossy().starts_with("NOVA_") {
+            cmd.env(name, value);
+        }
+    }
+}
+
+/// Builds the command used to launch a server binary on `port`, with a sandboxed
+/// environment.
+///
+/// Callers can add extra environment before spawning.
+pub fn server_command(binary: &Path, port: u16, verbose: bool) -> Command {
+    // Start server binary in its own process group so we can kill it and all children
+    let mut cmd = Command::new(binary);
+    sandbox_env(&mut cmd);
+
+    // On Unix, create a new process group for the server
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    // Add port argument
+    cmd.arg("--port").arg(port.to_string());
+
+    // Add verbose flag if set
+    if verbose {
+        log::info!("VERBOSE mode enabled - passing --verbose to server");
+        cmd.arg("--verbose");
+    }
+
+    cmd
+}
+
+pub fn server_url(port: u16) -> String {
+    format!("http://127.0.0.1:{}", port)
+}
+
+/// Whether something is accepting connections on the server port yet.
+pub fn port_open(port: u16) -> bool {
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    TcpStream::connect_timeout(&addr, Duration::from_millis(200)).is_ok()
+}
+
+/// Whether the server answers its health check.
+pub fn is_ready(port: u16) -> bool {
+    match ureq::get(&format!("{}/api/health", server_url(port))).timeout(Duration::from_millis(500)).call() {
+        Ok(response) => response.status() == 200,
+        Err(_) => false,
+    }
+}
+
+/// Polls the server until its health check answers 200, returning the number of attempts it took.
+pub fn wait_for_ready(port: u16, max_attempts: u32) -> Option<u32> {
+    for attempt in 1..=max_attempts {
+        std::thread::sleep(Duration::from_millis(500));
+
+        if is_ready(port) {
+            return Some(attempt);
+        }
+    }
+    None
+}
+
+/// Waits up to `timeout` for a process to exit on its own.
+pub fn wait_for_exit(process: &mut Child, timeout: Duration) -> bool {
+    let started = std::time::Instant::now();
+    while started.elapsed() < timeout {
+        if let Ok(Some(_)) = process.try_wait() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    false
+}
+
+/// Stops a server process and any children it spawned (browsers, drivers).
+pub fn stop_process(process: &mut Child) {
+    let pid = process.id();
+
+    // Kill child processes first
+    #[cfg(unix)]
+    {
+        let _ = Command::new("pkill")
+            .arg("-P")
+            .arg(pid.to_string())
+            .output();
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    // Kill main process
+    let _ = process.kill();
+    let _ = process.wait();
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::AppState;
+
+/// A server session snapshot, kept in `session.json` in the app data dir until the next
+/// server picks it up.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedSession {
+    /// App version that took the snapshot, for the logs after an update.
+    app_version: String,
+    state: serde_json::Value,
+}
+
+fn session_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join("session.json"))
+}
+
+/// Asks the running server for its session state and saves it.
+pub fn save(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>().control.snapshot_session()?;
+    let path = session_path(app).ok_or("Session location is unavailable")?;
+    let saved = SavedSession { app_version: env!("CARGO_PKG_VERSION").to_string(), state };
+    let contents = serde_json::to_string_pretty(&saved).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to save session: {}", e))?;
+    log::info!("Session saved to {}", path.display());
+    Ok(())
+}
+
+/// Hands a saved session to the running server. Returns false if there was nothing to
+/// restore. The snapshot is consumed so it is only resumed once.
+pub fn restore(app: &AppHandle) -> Result<bool, String> {
+    let Some(path) = session_path(app).filter(|path| path.exists()) else {
+        return Ok(false);
+    };
+    let contents = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read session: {}", e))?;
+    let _ = std::fs::remove_file(&path);
+    let saved: SavedSession = serde_json::from_str(&contents).map_err(|e| format!("Invalid session file: {}", e))?;
+
+    app.state::<AppState>().control.restore_session(saved.state)?;
+    log::info!("Session restored (saved by version {})", saved.app_version);
+    Ok(true)
+}
+
+/// Saves the session before a deliberate stop. Failures are logged, never fatal.
+pub fn save_before_stop(app: &AppHandle) {
+    if let Err(e) = save(app) {
+        log::warn!("Session not saved: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn save_session(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || save(&app))
+        .await
+        .map_err(|e| format!("Saving session failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn restore_session(app: AppHandle) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || restore(&app))
+        .await
+        .map_err(|e| format!("Restoring session failed: {}", e))?
+}
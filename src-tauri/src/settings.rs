@@ -0,0 +1,361 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::access::Role;
+use crate::backups::BackupSettings;
+use crate::blackouts::BlackoutWindow;
+use crate::captcha::CaptchaSettings;
+use crate::control_api::{self, ControlApiSettings};
+use crate::databases::DatabaseTarget;
+use crate::disk::RetentionSettings;
+use crate::domain_policy::DomainPolicySettings;
+use crate::email::EmailSettings;
+use crate::git_sync::{self, SyncSettings};
+use crate::har::HarSettings;
+use crate::integrations::WebhookConfig;
+use crate::notifications::{self, Category, Notification, NotificationSettings, Urgency};
+use crate::pipelines::Pipeline;
+use crate::politeness::PolitenessSettings;
+use crate::reports::ReportSettings;
+use crate::schedules::Schedule;
+use crate::sheets::SheetTarget;
+use crate::sqlite_export::SqliteTarget;
+use crate::uploads::UploadSettings;
+use crate::variables::VariableSettings;
+use crate::{crypto, display, flags, har, license, politeness, power, secrets, security, standby};
+
+/// Keychain entry holding the key for sensitive settings sections.
+const KEY_SECRET: &str = "settings-key";
+/// Field of the settings file holding the sealed sensitive sections.
+const ENCRYPTED_FIELD: &str = "encrypted_sections";
+
+/// Top-level settings sections by name.
+pub type Sections = Map<String, Value>;
+
+/// Shell settings, persisted as JSON in the app config dir.
+///
+/// Unknown or missing fields fall back to their defaults so older files keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Keep a server running in the background (started at login, app lives in the tray)
+    /// so opening the window connects instantly.
+    pub warm_standby: bool,
+    /// Restart a standby server that has sat unused for this many minutes, releasing
+    /// whatever memory the browser session accumulated. 0 disables recycling.
+    pub standby_recycle_minutes: u64,
+    /// Outgoing webhooks fired when jobs finish.
+    pub webhooks: Vec<WebhookConfig>,
+    /// Automations the shell runs on a timer.
+    pub schedules: Vec<Schedule>,
+    /// Periods in which schedules don't run unless they opt out.
+    pub blackouts: Vec<BlackoutWindow>,
+    /// Recipes chained by dependencies.
+    pub pipelines: Vec<Pipeline>,
+    /// SMTP server for run reports.
+    pub email: EmailSettings,
+    /// Token-protected localhost API for scripts and other apps.
+    pub control_api: ControlApiSettings,
+    /// Top-level sections (e.g. `webhooks`, `email`) stored encrypted with a key from the
+    /// OS keychain instead of as plain JSON.
+    pub sensitive_sections: Vec<String>,
+    /// Local feature flag overrides; these win over defaults and remote values.
+    pub feature_flags: BTreeMap<String, bool>,
+    /// Keep the computer from sleeping (and, on macOS, the app from napping) while jobs run.
+    pub prevent_sleep: bool,
+    /// Display (an id from `list_displays`) the headed automation browser opens on.
+    /// `None` leaves placement to the browser.
+    pub browser_display: Option<String>,
+    /// Minutes without keyboard or mouse input after which the user counts as away, and
+    /// schedules marked `only_when_idle` may run.
+    pub idle_threshold_minutes: u64,
+    /// Desktop notification when a job finishes while the window is in the background.
+    pub notify_jobs: bool,
+    /// Hold non-critical notifications while the OS focus / do-not-disturb mode is on
+    /// and show a digest when it ends.
+    pub respect_do_not_disturb: bool,
+    /// Which notifications are shown, quiet hours, and the daily digest of runs.
+    pub notifications: NotificationSettings,
+    /// Cleanup of old run artifacts and the free-space thresholds that trigger it.
+    pub retention: RetentionSettings,
+    /// Automatic backups of settings, recipes and history.
+    pub backups: BackupSettings,
+    /// Network captures of runs, kept for debugging.
+    pub har: HarSettings,
+    /// Have every failed job step capture a screenshot and DOM snapshot of the page,
+    /// kept with the run's artifacts.
+    pub capture_failures: bool,
+    /// Rate limits and concurrency caps per site.
+    pub politeness: PolitenessSettings,
+    /// Which sites jobs may be sent to.
+    pub domain_policy: DomainPolicySettings,
+    /// The captcha-solving service jobs may use, and its spending limits.
+    pub captcha: CaptchaSettings,
+    /// PDF reports written automatically when runs finish.
+    pub reports: ReportSettings,
+    /// Where downloaded files are saved; `None` uses the user's Downloads folder.
+    pub download_dir: Option<PathBuf>,
+    /// Sharing recipes and common settings through a git remote.
+    pub sync: SyncSettings,
+    /// S3-compatible bucket run artifacts are uploaded to.
+    pub uploads: UploadSettings,
+    /// Local SQLite tables run results are written to.
+    pub sqlite_targets: Vec<SqliteTarget>,
+    /// PostgreSQL and MySQL tables run results are written to.
+    pub database_targets: Vec<DatabaseTarget>,
+    /// Google Sheets tabs run results are appended to.
+    pub sheet_targets: Vec<SheetTarget>,
+    /// Ed25519 public keys (base64) of publishers whose signed recipe bundles install
+    /// without a checksum.
+    pub trusted_recipe_keys: Vec<String>,
+    /// `${name}` values for prompts, per environment profile.
+    pub variables: VariableSettings,
+    /// Names of the TOTP seeds kept in the keychain, for `${totp.<name>}`.
+    pub totp_seeds: Vec<String>,
+    /// What whoever uses the app with these settings may do; see `access`. Only an admin
+    /// can change it, so a lowered role is raised again by editing the settings file.
+    pub role: Role,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            warm_standby: false,
+            standby_recycle_minutes: 240,
+            webhooks: Vec::new(),
+            schedules: Vec::new(),
+            blackouts: Vec::new(),
+            pipelines: Vec::new(),
+            email: EmailSettings::default(),
+            control_api: ControlApiSettings::default(),
+            sensitive_sections: Vec::new(),
+            feature_flags: BTreeMap::new(),
+            prevent_sleep: true,
+            browser_display: None,
+            idle_threshold_minutes: 5,
+            notify_jobs: true,
+            respect_do_not_disturb: true,
+            notifications: NotificationSettings::default(),
+            retention: RetentionSettings::default(),
+            backups: BackupSettings::default(),
+            har: HarSettings::default(),
+            capture_failures: true,
+            politeness: PolitenessSettings::default(),
+            domain_policy: DomainPolicySettings::default(),
+            captcha: CaptchaSettings::default(),
+            reports: ReportSettings::default(),
+            download_dir: None,
+            sync: SyncSettings::default(),
+            uploads: UploadSettings::default(),
+            sqlite_targets: Vec::new(),
+            database_targets: Vec::new(),
+            sheet_targets: Vec::new(),
+            trusted_recipe_keys: Vec::new(),
+            variables: VariableSettings::default(),
+            totp_seeds: Vec::new(),
+            role: Role::default(),
+        }
+    }
+}
+
+/// The key for sensitive sections, created on first use.
+fn section_key(create: bool) -> Result<Option<[u8; crypto::KEY_LEN]>, String> {
+    if let Some(encoded) = secrets::get(KEY_SECRET)? {
+        let bytes = BASE64.decode(encoded).map_err(|_| "Settings key in the keychain is damaged")?;
+        return bytes.try_into().map(Some).map_err(|_| "Settings key in the keychain is damaged".to_string());
+    }
+    if !create {
+        return Ok(None);
+    }
+    let key = crypto::random_bytes::<{ crypto::KEY_LEN }>()?;
+    secrets::set(KEY_SECRET, &BASE64.encode(key))?;
+    log::info!("Created settings encryption key");
+    Ok(Some(key))
+}
+
+impl Settings {
+    /// Splits the settings into the plain JSON object and the sections marked sensitive.
+    pub fn split_sensitive(&self) -> Result<(Sections, Sections), String> {
+        let Value::Object(mut plain) = serde_json::to_value(self).map_err(|e| e.to_string())? else {
+            unreachable!("settings serialize to an object");
+        };
+        let mut sensitive = Sections::new();
+        for section in &self.sensitive_sections {
+            if section == "sensitive_sections" {
+                return Err("The list of sensitive sections cannot itself be encrypted".to_string());
+            }
+            let value = plain.remove(section).ok_or_else(|| format!("Unknown settings section '{}'", section))?;
+            sensitive.insert(section.clone(), value);
+        }
+        Ok((plain, sensitive))
+    }
+
+    /// Serializes for disk, sealing sensitive sections so they never hit the file in plain text.
+    fn to_file_contents(&self) -> Result<String, String> {
+        let (mut plain, sensitive) = self.split_sensitive()?;
+        if !sensitive.is_empty() {
+            let key = section_key(true)?.ok_or("Settings key unavailable")?;
+            let sealed = crypto::seal(&key, &serde_json::to_vec(&sensitive).map_err(|e| e.to_string())?)?;
+            plain.insert(ENCRYPTED_FIELD.to_string(), Value::String(BASE64.encode(sealed)));
+        }
+        serde_json::to_string_pretty(&plain).map_err(|e| format!("Failed to serialize settings: {}", e))
+    }
+
+    /// Parses a settings file, decrypting sealed sections. Fails if they cannot be decrypted
+    /// (e.g. the keychain is locked or its entry is gone) rather than defaulting them.
+    pub fn from_file_contents(contents: &str) -> Result<Settings, String> {
+        let Value::Object(mut object) = serde_json::from_str(contents).map_err(|e| e.to_string())? else {
+            return Err("settings file is not a JSON object".to_string());
+        };
+        if let Some(sealed) = object.remove(ENCRYPTED_FIELD) {
+            let sections = sealed
+                .as_str()
+                .ok_or("sealed sections are not a string".to_string())
+                .and_then(|sealed| BASE64.decode(sealed).map_err(|e| e.to_string()))
+                .and_then(|sealed| {
+                    let key = section_key(false)?.ok_or("the settings key is missing from the keychain")?;
+                    crypto::open(&key, &sealed)
+                })
+                .and_then(|plain| serde_json::from_slice::<Sections>(&plain).map_err(|e| e.to_string()))
+                .map_err(|e| format!("sensitive settings could not be decrypted: {}", e))?;
+            object.extend(sections);
+        }
+        serde_json::from_value(Value::Object(object)).map_err(|e| e.to_string())
+    }
+}
+
+pub struct SettingsStore {
+    settings: Mutex<Settings>,
+    path: Option<PathBuf>,
+    /// In safe mode custom config is ignored and nothing is written back.
+    safe_mode: Mutex<bool>,
+    /// Why the file on disk could not be read; saving is refused so it is never overwritten with defaults.
+    load_error: Option<String>,
+}
+
+impl SettingsStore {
+    pub fn load(app: &AppHandle, safe_mode: bool) -> Self {
+        let path = app.path().app_config_dir().ok().map(|dir| dir.join("settings.json"));
+
+        let mut load_error = None;
+        let settings = if safe_mode {
+            Settings::default()
+        } else {
+            path.as_ref()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .and_then(|contents| match Settings::from_file_contents(&contents) {
+                    Ok(settings) => Some(settings),
+                    Err(e) => {
+                        log::error!("Failed to load settings, using defaults until restart: {}", e);
+                        load_error = Some(e);
+                        None
+                    }
+                })
+                .unwrap_or_default()
+        };
+
+        SettingsStore {
+            settings: Mutex::new(settings),
+            path,
+            safe_mode: Mutex::new(safe_mode),
+            load_error,
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn update(&self, settings: Settings) -> Result<(), String> {
+        if *self.safe_mode.lock().unwrap() {
+            return Err("Settings cannot be changed in safe mode".to_string());
+        }
+        if let Some(e) = &self.load_error {
+            return Err(format!(
+                "Settings cannot be changed because the saved settings could not be loaded ({}). \
+                 Fix this (e.g. unlock the keychain) and restart the app.",
+                e
+            ));
+        }
+        let path = self.path.as_ref().ok_or("Settings location is unavailable")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+        }
+        let contents = settings.to_file_contents()?;
+        std::fs::write(path, contents).map_err(|e| format!("Failed to save settings: {}", e))?;
+
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    /// Whether the settings held are stand-in defaults rather than the user's, i.e. in safe
+    /// mode or after the file failed to load.
+    pub fn on_defaults(&self) -> bool {
+        *self.safe_mode.lock().unwrap() || self.load_error.is_some()
+    }
+
+    /// Drops custom settings for the rest of this run (the file on disk is kept).
+    pub fn enter_safe_mode(&self) {
+        *self.safe_mode.lock().unwrap() = true;
+        *self.settings.lock().unwrap() = Settings::default();
+    }
+}
+
+/// Tells the user their saved settings were not loaded, so running on defaults is not a surprise.
+pub fn report_load_error(app: &AppHandle) {
+    let Some(e) = app.state::<SettingsStore>().load_error.clone() else {
+        return;
+    };
+    notifications::notify(
+        app,
+        Notification {
+            title: "Settings could not be loaded".to_string(),
+            body: format!("Running with default settings; changes won't be saved until this is fixed: {}", e),
+            urgency: Urgency::Critical,
+            category: Category::System,
+        },
+    );
+}
+
+#[tauri::command]
+pub fn get_settings(store: State<'_, SettingsStore>) -> Settings {
+    store.get()
+}
+
+/// Saves new settings and brings the running subsystems in line with them. Turning on LAN
+/// mode for the control API needs the user to confirm who they are, whichever way the
+/// settings arrive.
+pub fn apply(app: &AppHandle, settings: Settings) -> Result<(), String> {
+    let current = app.state::<SettingsStore>().get().control_api;
+    if settings.control_api.lan && !current.lan && !license::entitlements(app).lan_mode {
+        return Err("LAN mode for the control API is not included in your license".to_string());
+    }
+    if settings.control_api.exposed() && !current.exposed() {
+        security::authenticate("let other computers on the network control automations")?;
+    }
+    app.state::<SettingsStore>().update(settings)?;
+    log::info!("Settings updated");
+    standby::apply_settings(app);
+    control_api::apply_settings(app);
+    flags::apply(app);
+    power::apply_settings(app);
+    display::apply_settings(app);
+    politeness::apply_settings(app);
+    har::apply_settings(app);
+    git_sync::changed(app);
+    Ok(())
+}
+
+/// Saves and applies new settings.
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, settings: Settings) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || apply(&app, settings))
+    .await
+    .map_err(|e| format!("Failed to update settings: {}", e))?
+}
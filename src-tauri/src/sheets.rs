@@ -0,0 +1,490 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tiny_http::{Header, Response, Server};
+
+use crate::downloads::percent_decode;
+use crate::events::{JobEvent, JobStatus};
+use crate::history::History;
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::settings::SettingsStore;
+use crate::{crypto, export, secrets};
+
+/// Keychain entry holding the OAuth client the user registered with Google.
+const CLIENT_KEY: &str = "google-oauth-client";
+/// Keychain entry holding the account's tokens.
+const TOKENS_KEY: &str = "google-oauth-tokens";
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+const SHEETS_URL: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+const SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+
+/// How long the browser sign-in may take.
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(300);
+/// Refresh the access token when it has less than this left.
+const EXPIRY_MARGIN_MS: i64 = 60_000;
+/// Attempts per request before it counts as failed.
+const MAX_ATTEMPTS: u32 = 3;
+/// Rows sent per append request.
+const ROWS_PER_REQUEST: usize = 1000;
+
+/// Held while the tokens are refreshed, so runs finishing together refresh once.
+static TOKENS: Mutex<()> = Mutex::new(());
+
+/// A tab of a Google Sheets spreadsheet that run results are appended to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetTarget {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// The id in the spreadsheet's URL, `docs.google.com/spreadsheets/d/<id>/edit`.
+    pub spreadsheet_id: String,
+    /// The tab's name.
+    pub sheet: String,
+    /// Keep the first row as column headings, adding new fields to its end, and line the
+    /// values up under them.
+    #[serde(default = "default_true")]
+    pub header: bool,
+    /// Column headings for result fields; fields not listed keep their own name.
+    #[serde(default)]
+    pub columns: BTreeMap<String, String>,
+    /// Recipes and schedules whose successful runs are appended.
+    #[serde(default)]
+    pub recipes: Vec<String>,
+    #[serde(default)]
+    pub schedules: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl SheetTarget {
+    fn receives(&self, job: &JobEvent) -> bool {
+        job.recipe_id.as_ref().is_some_and(|id| self.recipes.contains(id))
+            || job.schedule_id.as_ref().is_some_and(|id| self.schedules.contains(id))
+    }
+}
+
+/// A desktop-app OAuth client from the Google Cloud console.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Tokens {
+    access_token: String,
+    refresh_token: String,
+    /// Unix milliseconds.
+    expires_at: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenReply {
+    access_token: String,
+    expires_in: i64,
+    /// Only sent on sign-in, or when Google rotates it.
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoogleAccountStatus {
+    pub client_configured: bool,
+    pub connected: bool,
+}
+
+/// What appending a run to a sheet did.
+#[derive(Debug, Clone, Serialize)]
+pub struct SheetAppend {
+    pub target: String,
+    pub run_id: String,
+    pub rows_appended: usize,
+    pub rows_total: usize,
+    /// Fields added to the end of the heading row.
+    pub columns_added: Vec<String>,
+    /// Why appending stopped part way; the rows before it are in the sheet.
+    pub error: Option<String>,
+}
+
+/// Percent-encodes a URL query value or path segment.
+fn encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn client() -> Result<OAuthClient, String> {
+    let stored = secrets::get(CLIENT_KEY)?.ok_or("No Google OAuth client is set up")?;
+    serde_json::from_str(&stored).map_err(|_| "Stored Google OAuth client is damaged".to_string())
+}
+
+fn store_tokens(reply: TokenReply, refresh_token: String) -> Result<Tokens, String> {
+    let tokens = Tokens {
+        access_token: reply.access_token,
+        refresh_token: reply.refresh_token.unwrap_or(refresh_token),
+        expires_at: chrono::Utc::now().timestamp_millis() + reply.expires_in * 1000,
+    };
+    secrets::set(TOKENS_KEY, &serde_json::to_string(&tokens).map_err(|e| e.to_string())?)?;
+    Ok(tokens)
+}
+
+/// Posts a form to the token endpoint. `Err(None)` means Google refused the grant.
+fn token_request(form: &[(&str, &str)]) -> Result<TokenReply, Option<String>> {
+    match ureq::post(TOKEN_URL).timeout(Duration::from_secs(30)).send_form(form) {
+        Ok(response) => response.into_json().map_err(|e| Some(format!("Invalid reply from Google: {}", e))),
+        Err(ureq::Error::Status(400 | 401, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            log::warn!("Google refused the token request: {}", body.trim());
+            Err(None)
+        }
+        Err(e) => Err(Some(format!("Google sign-in unreachable: {}", e))),
+    }
+}
+
+/// A current access token, refreshed first if it is about to expire. A refresh token
+/// Google no longer accepts is dropped, so the account shows as disconnected.
+fn access_token(force_refresh: bool) -> Result<String, String> {
+    let _guard = TOKENS.lock().unwrap();
+    let stored = secrets::get(TOKENS_KEY)?.ok_or("No Google account is connected")?;
+    let tokens: Tokens = serde_json::from_str(&stored).map_err(|_| "Stored Google tokens are damaged")?;
+    if !force_refresh && tokens.expires_at - EXPIRY_MARGIN_MS > chrono::Utc::now().timestamp_millis() {
+        return Ok(tokens.access_token);
+    }
+    let client = client()?;
+    let form = [
+        ("grant_type", "refresh_token"),
+        ("refresh_token", tokens.refresh_token.as_str()),
+        ("client_id", client.client_id.as_str()),
+        ("client_secret", client.client_secret.as_str()),
+    ];
+    match token_request(&form) {
+        Ok(reply) => {
+            log::debug!("Google access token refreshed");
+            Ok(store_tokens(reply, tokens.refresh_token)?.access_token)
+        }
+        Err(None) => {
+            secrets::delete(TOKENS_KEY)?;
+            Err("Google no longer accepts the app's access; connect the account again".to_string())
+        }
+        Err(Some(e)) => Err(e),
+    }
+}
+
+/// Calls the Sheets API, retrying throttling, and refreshing the token once if Google turns
+/// it down. Server errors and timeouts are retried only for reads and overwrites: an append
+/// that failed mid-way may have gone through, and sending it again would duplicate its rows.
+fn call(method: &str, path: &str, body: Option<&Value>) -> Result<Value, String> {
+    let idempotent = method != "POST";
+    let url = format!("{}/{}", SHEETS_URL, path);
+    let mut token = access_token(false)?;
+    let mut refreshed = false;
+    let mut last_error = String::new();
+    let mut attempt = 0;
+    while attempt < MAX_ATTEMPTS {
+        attempt += 1;
+        if attempt > 1 {
+            std::thread::sleep(Duration::from_secs(2u64.pow(attempt - 1)));
+            log::info!("Retrying Sheets request (attempt {}): {}", attempt, last_error);
+        }
+        let request = ureq::request(method, &url)
+            .timeout(Duration::from_secs(60))
+            .set("Authorization", &format!("Bearer {}", token));
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        match result {
+            Ok(response) => return response.into_json().map_err(|e| format!("Invalid reply from Sheets: {}", e)),
+            Err(ureq::Error::Status(401, _)) if !refreshed => {
+                token = access_token(true)?;
+                refreshed = true;
+                attempt -= 1;
+            }
+            Err(ureq::Error::Status(code, response)) if code < 500 && code != 429 => {
+                let reply: Value = response.into_json().unwrap_or_default();
+                let message = reply["error"]["message"].as_str().map_or(format!("HTTP {}", code), str::to_string);
+                return Err(format!("Sheets refused the request: {}", message));
+            }
+            Err(ureq::Error::Status(429, _)) => last_error = "HTTP 429".to_string(),
+            Err(ureq::Error::Transport(t)) if idempotent || t.kind() == ureq::ErrorKind::ConnectionFailed => {
+                last_error = t.to_string()
+            }
+            Err(e) => return Err(format!("Sheets request failed and may have been applied: {}", e)),
+        }
+    }
+    Err(format!("Sheets request failed after {} attempts: {}", MAX_ATTEMPTS, last_error))
+}
+
+/// An A1 range on `sheet`, e.g. `'Prices'!1:1`, encoded for the URL.
+fn range(sheet: &str, cells: &str) -> String {
+    encode(&format!("'{}'!{}", sheet.replace('\'', "''"), cells))
+}
+
+fn cell(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::String(String::new()),
+        Value::Bool(_) | Value::Number(_) | Value::String(_) => value.clone(),
+        other => Value::String(other.to_string()),
+    }
+}
+
+/// Appends a run's results to a sheet, below whatever is there.
+pub fn append_run(app: &AppHandle, target: &SheetTarget, run_id: &str) -> Result<SheetAppend, String> {
+    if target.spreadsheet_id.trim().is_empty() || target.sheet.trim().is_empty() {
+        return Err(format!("Sheet target '{}' needs a spreadsheet and a tab", target.name));
+    }
+    let run = app
+        .state::<History>()
+        .get(run_id)?
+        .ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let output = run.output.ok_or_else(|| format!("Run '{}' has no results to export", run_id))?;
+    let table = export::to_table(&output);
+    let columns: Vec<String> =
+        table.columns.iter().map(|column| target.columns.get(column).unwrap_or(column).clone()).collect();
+    let spreadsheet = encode(&target.spreadsheet_id);
+
+    let mut columns_added = Vec::new();
+    let order: Vec<Option<usize>> = if target.header {
+        let reply = call("GET", &format!("{}/values/{}", spreadsheet, range(&target.sheet, "1:1")), None)?;
+        let mut header: Vec<String> = reply["values"][0]
+            .as_array()
+            .map(|cells| cells.iter().map(|cell| cell.as_str().map_or(cell.to_string(), str::to_string)).collect())
+            .unwrap_or_default();
+        for column in &columns {
+            if !header.contains(column) {
+                header.push(column.clone());
+                columns_added.push(column.clone());
+            }
+        }
+        if !columns_added.is_empty() {
+            let path = format!("{}/values/{}?valueInputOption=RAW", spreadsheet, range(&target.sheet, "1:1"));
+            call("PUT", &path, Some(&json!({ "values": [header] })))?;
+        }
+        header.iter().map(|heading| columns.iter().position(|column| column == heading)).collect()
+    } else {
+        (0..columns.len()).map(Some).collect()
+    };
+
+    // RAW, so scraped text starting with `=` stays text instead of becoming a formula
+    let path = format!(
+        "{}/values/{}:append?valueInputOption=RAW&insertDataOption=INSERT_ROWS",
+        spreadsheet,
+        range(&target.sheet, "A1")
+    );
+    let rows: Vec<Vec<Value>> = table
+        .rows
+        .iter()
+        .map(|row| order.iter().map(|index| index.map_or(Value::String(String::new()), |i| cell(&row[i]))).collect())
+        .collect();
+    let mut rows_appended = 0;
+    let mut error = None;
+    for chunk in rows.chunks(ROWS_PER_REQUEST) {
+        match call("POST", &path, Some(&json!({ "values": chunk }))) {
+            Ok(_) => rows_appended += chunk.len(),
+            Err(e) if rows_appended == 0 => return Err(e),
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+    log::info!("Appended {} of {} rows of run {} to sheet '{}'", rows_appended, rows.len(), run_id, target.name);
+    Ok(SheetAppend {
+        target: target.name.clone(),
+        run_id: run_id.to_string(),
+        rows_appended,
+        rows_total: rows.len(),
+        columns_added,
+        error,
+    })
+}
+
+/// Waits for Google to send the browser back to the loopback address with a code.
+fn receive_code(server: &Server, state: &str) -> Result<String, String> {
+    let deadline = Instant::now() + SIGN_IN_TIMEOUT;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let request = server
+            .recv_timeout(remaining)
+            .map_err(|e| e.to_string())?
+            .ok_or("Google sign-in timed out")?;
+        let query = request.url().split_once('?').map_or("", |(_, query)| query).to_string();
+        let params: BTreeMap<String, String> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.to_string(), percent_decode(&value.replace('+', " "))))
+            .collect();
+        // Browsers also ask for a favicon; only the redirect carries a state
+        if params.get("state").map(String::as_str) != Some(state) {
+            let _ = request.respond(Response::empty(404));
+            continue;
+        }
+        let (page, result) = match (params.get("code"), params.get("error")) {
+            (Some(code), _) => ("You're signed in. You can close this tab and go back to the app.", Ok(code.clone())),
+            (None, error) => (
+                "Sign-in didn't finish. You can close this tab.",
+                Err(format!("Google sign-in failed: {}", error.map_or("no code", String::as_str))),
+            ),
+        };
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"text/plain; charset=utf-8"[..]).unwrap();
+        let _ = request.respond(Response::from_string(page).with_header(header));
+        return result;
+    }
+}
+
+/// Signs in through the browser with PKCE and a loopback redirect, as Google asks of
+/// desktop apps, and keeps the tokens in the keychain.
+fn sign_in(app: &AppHandle) -> Result<(), String> {
+    let client = client()?;
+    let verifier = URL_SAFE_NO_PAD.encode(crypto::random_bytes::<32>()?);
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let state = URL_SAFE_NO_PAD.encode(crypto::random_bytes::<16>()?);
+
+    let server = Server::http("127.0.0.1:0").map_err(|e| format!("Failed to listen for the sign-in: {}", e))?;
+    let port = server.server_addr().to_ip().map(|addr| addr.port()).ok_or("Sign-in listener has no port")?;
+    let redirect_uri = format!("http://127.0.0.1:{}", port);
+    let url = format!(
+        "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&code_challenge={}\
+         &code_challenge_method=S256&state={}&access_type=offline&prompt=consent",
+        AUTH_URL,
+        encode(&client.client_id),
+        encode(&redirect_uri),
+        encode(SCOPE),
+        challenge,
+        state
+    );
+    app.opener().open_url(&url, None::<&str>).map_err(|e| format!("Failed to open the browser: {}", e))?;
+
+    let code = receive_code(&server, &state)?;
+    let form = [
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("client_id", client.client_id.as_str()),
+        ("client_secret", client.client_secret.as_str()),
+        ("code_verifier", verifier.as_str()),
+    ];
+    let reply = token_request(&form).map_err(|e| e.unwrap_or_else(|| "Google refused the sign-in".to_string()))?;
+    let refresh_token = reply.refresh_token.clone().ok_or("Google sent no refresh token")?;
+    let _guard = TOKENS.lock().unwrap();
+    store_tokens(reply, refresh_token)?;
+    log::info!("Google account connected");
+    Ok(())
+}
+
+fn find_target(app: &AppHandle, target_id: &str) -> Result<SheetTarget, String> {
+    app.state::<SettingsStore>()
+        .get()
+        .sheet_targets
+        .into_iter()
+        .find(|target| target.id == target_id)
+        .ok_or_else(|| format!("No sheet target with id '{}'", target_id))
+}
+
+/// Built-in plugin that appends the results of successful runs to the sheets they feed.
+struct SheetsPlugin;
+
+impl ShellPlugin for SheetsPlugin {
+    fn name(&self) -> &str {
+        "google-sheets"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        if job.status != Some(JobStatus::Success) || job.output.is_none() {
+            return;
+        }
+        let settings = ctx.app.state::<SettingsStore>().get();
+        for target in settings.sheet_targets.iter().filter(|target| target.enabled && target.receives(job)) {
+            match append_run(ctx.app, target, &job.job_id) {
+                Ok(SheetAppend { rows_appended, rows_total, error: Some(e), .. }) => log::error!(
+                    "Only {} of {} rows of run {} appended to '{}': {}",
+                    rows_appended,
+                    rows_total,
+                    job.job_id,
+                    target.name,
+                    e
+                ),
+                Ok(_) => {}
+                Err(e) => log::error!("Results of run {} not appended to '{}': {}", job.job_id, target.name, e),
+            }
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(SheetsPlugin), "Appends run results to configured Google Sheets");
+}
+
+/// Stores the OAuth client in the keychain. `None` removes it.
+#[tauri::command]
+pub fn set_google_oauth_client(client: Option<OAuthClient>) -> Result<(), String> {
+    match client {
+        Some(client) => secrets::set(CLIENT_KEY, &serde_json::to_string(&client).map_err(|e| e.to_string())?),
+        None => secrets::delete(CLIENT_KEY),
+    }
+}
+
+#[tauri::command]
+pub fn google_account_status() -> Result<GoogleAccountStatus, String> {
+    Ok(GoogleAccountStatus {
+        client_configured: secrets::get(CLIENT_KEY)?.is_some(),
+        connected: secrets::get(TOKENS_KEY)?.is_some(),
+    })
+}
+
+/// Opens Google's sign-in in the browser and waits for the user to allow access to their
+/// spreadsheets.
+#[tauri::command]
+pub async fn connect_google_account(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || sign_in(&app))
+        .await
+        .map_err(|e| format!("Google sign-in failed: {}", e))?
+}
+
+/// Revokes the app's access and forgets the tokens. They are forgotten even if Google
+/// can't be reached.
+#[tauri::command]
+pub async fn disconnect_google_account() -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let _guard = TOKENS.lock().unwrap();
+        let stored = secrets::get(TOKENS_KEY)?;
+        if let Some(tokens) = stored.and_then(|stored| serde_json::from_str::<Tokens>(&stored).ok()) {
+            let form = [("token", tokens.refresh_token.as_str())];
+            if let Err(e) = ureq::post(REVOKE_URL).timeout(Duration::from_secs(30)).send_form(&form) {
+                log::warn!("Failed to revoke Google access: {}", e);
+            }
+        }
+        secrets::delete(TOKENS_KEY)?;
+        log::info!("Google account disconnected");
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Failed to disconnect the Google account: {}", e))?
+}
+
+/// Appends a finished run's results to a configured sheet.
+#[tauri::command]
+pub async fn append_run_to_sheet(app: AppHandle, target_id: String, run_id: String) -> Result<SheetAppend, String> {
+    tauri::async_runtime::spawn_blocking(move || append_run(&app, &find_target(&app, &target_id)?, &run_id))
+        .await
+        .map_err(|e| format!("Sheet export failed: {}", e))?
+}
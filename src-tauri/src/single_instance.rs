@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::{packaging, tasks};
+
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Written to the instance file by the running app so later launches can find it.
+#[derive(Debug, Serialize, Deserialize)]
+struct InstanceInfo {
+    port: u16,
+    token: String,
+}
+
+/// What a second launch hands to the running app.
+#[derive(Debug, Serialize, Deserialize)]
+struct Handoff {
+    token: String,
+    files: Vec<PathBuf>,
+    /// Bring the main window forward. Login-time standby launches leave it alone.
+    focus: bool,
+}
+
+/// The listener of the first running app, waiting for later launches.
+pub struct Primary {
+    listener: TcpListener,
+    token: String,
+}
+
+/// Per-user file naming the running app's handoff port. The temp dir is per user on
+/// macOS and Windows but shared on Linux, hence the user name; Flatpak gets a runtime
+/// dir shared by its instances instead.
+fn instance_file() -> PathBuf {
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default();
+    packaging::runtime_dir().join(format!("com.browserautomation.desktop-{}.instance", user))
+}
+
+/// Passes `files` to an already running copy of the app. Returns true if it took them, in
+/// which case this process should exit.
+pub fn forward(files: &[PathBuf], focus: bool) -> bool {
+    let Some(info) = std::fs::read_to_string(instance_file())
+        .ok()
+        .and_then(|contents| serde_json::from_str::<InstanceInfo>(&contents).ok())
+    else {
+        return false;
+    };
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, info.port));
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, HANDOFF_TIMEOUT) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(HANDOFF_TIMEOUT));
+
+    let handoff = Handoff { token: info.token, files: files.to_vec(), focus };
+    let Ok(message) = serde_json::to_string(&handoff) else {
+        return false;
+    };
+    if writeln!(stream, "{}", message).is_err() {
+        return false;
+    }
+    // A stale file may point at a port some other program now owns, so wait for the ack
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).is_ok() && reply.trim() == "ok"
+}
+
+fn write_instance_file(info: &InstanceInfo) -> std::io::Result<()> {
+    let path = instance_file();
+    let contents = serde_json::to_string(info)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = std::fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(&path)?;
+        file.write_all(contents.as_bytes())
+    }
+    #[cfg(not(unix))]
+    std::fs::write(&path, contents)
+}
+
+/// Makes this process the one later launches hand over to.
+pub fn bind() -> Option<Primary> {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            // Later launches will open their own window; not worth refusing to start
+            log::warn!("Single instance handoff unavailable: {}", e);
+            return None;
+        }
+    };
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let info = InstanceInfo { port: listener.local_addr().map(|addr| addr.port()).unwrap_or(0), token: token.clone() };
+    if let Err(e) = write_instance_file(&info) {
+        log::warn!("Failed to write instance file: {}", e);
+    }
+    Some(Primary { listener, token })
+}
+
+fn handle(app: &AppHandle, stream: TcpStream, token: &str) {
+    let _ = stream.set_read_timeout(Some(HANDOFF_TIMEOUT));
+    let mut line = String::new();
+    let mut reader = BufReader::new(&stream);
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+    let Ok(handoff) = serde_json::from_str::<Handoff>(&line) else {
+        return;
+    };
+    if handoff.token != token {
+        log::warn!("Rejected a launch handoff with a wrong token");
+        return;
+    }
+    let _ = (&stream).write_all(b"ok\n");
+
+    log::info!("Another launch was handed over ({} file(s))", handoff.files.len());
+    if let Some(window) = app.get_webview_window("main").filter(|_| handoff.focus) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    tasks::open_all(app, handoff.files);
+}
+
+/// Accepts handoffs from later launches: the main window is brought forward and any task
+/// files they were started with are opened here.
+pub fn listen(app: &AppHandle, primary: Primary) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for stream in primary.listener.incoming().flatten() {
+            handle(&app, stream, &primary.token);
+        }
+    });
+}
@@ -0,0 +1,417 @@
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::events::{JobEvent, JobStatus};
+use crate::export;
+use crate::history::History;
+use crate::notifications::{self, Category, Notification, Urgency};
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::settings::SettingsStore;
+
+/// Columns added to every row, naming the run that wrote it and when.
+const RUN_COLUMNS: [&str; 2] = ["_run_id", "_written_at"];
+
+/// How long a write waits for someone else reading or writing the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How rows are written to a target table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// Every run adds its rows.
+    #[default]
+    Append,
+    /// Rows replace those with the same `keys`, so the table holds the latest of each record.
+    Upsert,
+}
+
+/// A table in a local SQLite database that run results are written to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqliteTarget {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// The database file, created if missing.
+    pub path: PathBuf,
+    pub table: String,
+    #[serde(default)]
+    pub mode: WriteMode,
+    /// Columns that identify a record in `upsert` mode.
+    #[serde(default)]
+    pub keys: Vec<String>,
+    /// Column names for result fields; fields not listed keep their own name.
+    #[serde(default)]
+    pub columns: BTreeMap<String, String>,
+    /// Recipes and schedules whose successful runs are written to the table.
+    #[serde(default)]
+    pub recipes: Vec<String>,
+    #[serde(default)]
+    pub schedules: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl SqliteTarget {
+    fn receives(&self, job: &JobEvent) -> bool {
+        job.recipe_id.as_ref().is_some_and(|id| self.recipes.contains(id))
+            || job.schedule_id.as_ref().is_some_and(|id| self.schedules.contains(id))
+    }
+}
+
+/// What writing a run to a target did.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportReport {
+    pub target: String,
+    pub run_id: String,
+    pub table: String,
+    /// The table did not exist and was created.
+    pub created: bool,
+    pub rows_written: usize,
+    /// Rows left out in `upsert` mode because a key had no value.
+    pub rows_skipped: usize,
+    /// Columns added for fields the table did not have yet.
+    pub columns_added: Vec<String>,
+    /// Schema drift worth a look: new fields, missing fields and type changes.
+    pub warnings: Vec<String>,
+}
+
+/// A run's output as rows to write, columns already renamed.
+pub struct Records {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Flattens a run's output like the CSV export does, renames columns and adds the run
+/// columns.
+pub fn records(output: &Value, renames: &BTreeMap<String, String>, run_id: &str, written_at: i64) -> Records {
+    let table = export::to_table(output);
+    let mut columns: Vec<String> =
+        table.columns.iter().map(|column| renames.get(column).unwrap_or(column).clone()).collect();
+    columns.extend(RUN_COLUMNS.iter().map(|column| column.to_string()));
+    let rows = table
+        .rows
+        .into_iter()
+        .map(|mut row| {
+            row.push(Value::String(run_id.to_string()));
+            row.push(Value::from(written_at));
+            row
+        })
+        .collect();
+    Records { columns, rows }
+}
+
+/// The SQL type a JSON value is stored as; `None` for null.
+pub fn value_type(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) => Some("INTEGER"),
+        Value::Number(n) if n.is_i64() || n.is_u64() => Some("INTEGER"),
+        Value::Number(_) => Some("REAL"),
+        _ => Some("TEXT"),
+    }
+}
+
+/// The type of a column from all its values: integers mixed with decimals are `REAL`,
+/// anything else mixed is `TEXT`. `None` if every value is null.
+pub fn column_type<'a>(values: impl Iterator<Item = &'a Value>) -> Option<&'static str> {
+    values.filter_map(value_type).reduce(|a, b| match (a, b) {
+        _ if a == b => a,
+        ("INTEGER", "REAL") | ("REAL", "INTEGER") => "REAL",
+        _ => "TEXT",
+    })
+}
+
+/// Whether values of type `found` fit a column declared as `declared`.
+fn fits(declared: &str, found: &str) -> bool {
+    let declared = declared.to_ascii_uppercase();
+    declared.is_empty() || declared == found || (declared == "REAL" && found == "INTEGER")
+}
+
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(n) => SqlValue::Integer(n),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// Whether an `upsert` row is missing a key. NULLs never conflict, so such a row would
+/// pile up as a duplicate instead of replacing its record; it is skipped.
+fn lacks_key(target: &SqliteTarget, columns: &[String], row: &[Value]) -> bool {
+    target.mode == WriteMode::Upsert
+        && columns.iter().zip(row).any(|(column, value)| value.is_null() && target.keys.contains(column))
+}
+
+/// Quotes a table or column name for use in SQL.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn check(target: &SqliteTarget) -> Result<(), String> {
+    if target.path.as_os_str().is_empty() {
+        return Err(format!("Target '{}' has no database file", target.name));
+    }
+    if target.table.trim().is_empty() {
+        return Err(format!("Target '{}' has no table", target.name));
+    }
+    if target.mode == WriteMode::Upsert && target.keys.is_empty() {
+        return Err(format!("Target '{}' writes in upsert mode but has no key columns", target.name));
+    }
+    Ok(())
+}
+
+/// How the table has to change to take a set of records.
+struct Plan {
+    created: bool,
+    /// Columns to add, with their type if known.
+    added: Vec<(String, Option<&'static str>)>,
+    warnings: Vec<String>,
+}
+
+/// Declared columns of `table` and their types, empty if there is no such table.
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote(table))).map_err(|e| e.to_string())?;
+    let columns = stmt
+        .query_map([], |row| Ok((row.get::<_, String>("name")?, row.get::<_, String>("type")?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(columns)
+}
+
+fn plan(target: &SqliteTarget, existing: &[(String, String)], records: &Records) -> Result<Plan, String> {
+    let types: Vec<Option<&'static str>> = (0..records.columns.len())
+        .map(|index| column_type(records.rows.iter().map(|row| &row[index])))
+        .collect();
+    let keys = if target.mode == WriteMode::Upsert { target.keys.as_slice() } else { &[] };
+    if let Some(key) = keys.iter().find(|key| !records.columns.contains(key)) {
+        return Err(format!("Key column '{}' is not in the results of this run", key));
+    }
+
+    let mut plan = Plan { created: existing.is_empty(), added: Vec::new(), warnings: Vec::new() };
+    for (column, found) in records.columns.iter().zip(&types) {
+        match existing.iter().find(|(name, _)| name == column) {
+            None => {
+                if !plan.created {
+                    let kind = found.unwrap_or("untyped");
+                    plan.warnings.push(format!("New field '{}' ({}) added as a column", column, kind));
+                }
+                plan.added.push((column.clone(), *found));
+            }
+            Some((_, declared)) => {
+                if let Some(found) = found.filter(|found| !fits(declared, found)) {
+                    let warning = format!("Column '{}' is {} but this run has {} values", column, declared, found);
+                    plan.warnings.push(warning);
+                }
+            }
+        }
+    }
+    for (column, _) in existing {
+        if !records.columns.contains(column) {
+            plan.warnings.push(format!("Column '{}' is missing from this run's results", column));
+        }
+    }
+    Ok(plan)
+}
+
+fn open(target: &SqliteTarget) -> Result<Connection, String> {
+    if let Some(dir) = target.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let conn =
+        Connection::open(&target.path).map_err(|e| format!("Failed to open {}: {}", target.path.display(), e))?;
+    conn.busy_timeout(BUSY_TIMEOUT).map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn write(conn: &mut Connection, target: &SqliteTarget, records: &Records, plan: &Plan) -> Result<usize, String> {
+    let table = quote(&target.table);
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let definitions: Vec<String> = plan
+        .added
+        .iter()
+        .map(|(column, kind)| match kind {
+            Some(kind) => format!("{} {}", quote(column), kind),
+            None => quote(column),
+        })
+        .collect();
+    if plan.created {
+        tx.execute(&format!("CREATE TABLE {} ({})", table, definitions.join(", ")), [])
+            .map_err(|e| format!("Failed to create table {}: {}", target.table, e))?;
+    } else {
+        for definition in &definitions {
+            tx.execute(&format!("ALTER TABLE {} ADD COLUMN {}", table, definition), [])
+                .map_err(|e| format!("Failed to add column to {}: {}", target.table, e))?;
+        }
+    }
+
+    let keys: Vec<String> = target.keys.iter().map(|key| quote(key)).collect();
+    let columns: Vec<String> = records.columns.iter().map(|column| quote(column)).collect();
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let mut sql = format!("INSERT INTO {} ({}) VALUES ({})", table, columns.join(", "), placeholders);
+    if target.mode == WriteMode::Upsert {
+        // ON CONFLICT needs a unique index over exactly these columns
+        let index = quote(&format!("{}_upsert_{}", target.table, target.keys.join("_")));
+        tx.execute(&format!("CREATE UNIQUE INDEX IF NOT EXISTS {} ON {} ({})", index, table, keys.join(", ")), [])
+            .map_err(|e| format!("Failed to index {} by its keys: {}", target.table, e))?;
+        let updates: Vec<String> = columns
+            .iter()
+            .filter(|column| !keys.contains(column))
+            .map(|column| format!("{} = excluded.{}", column, column))
+            .collect();
+        sql.push_str(&format!(" ON CONFLICT ({}) DO UPDATE SET {}", keys.join(", "), updates.join(", ")));
+    }
+
+    let mut written = 0;
+    {
+        let mut stmt = tx.prepare(&sql).map_err(|e| e.to_string())?;
+        for row in records.rows.iter().filter(|row| !lacks_key(target, &records.columns, row)) {
+            stmt.execute(params_from_iter(row.iter().map(sql_value)))
+                .map_err(|e| format!("Failed to write to {}: {}", target.table, e))?;
+            written += 1;
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to write to {}: {}", target.table, e))?;
+    Ok(written)
+}
+
+fn run_records(app: &AppHandle, target: &SqliteTarget, run_id: &str, written_at: i64) -> Result<Records, String> {
+    check(target)?;
+    let run = app
+        .state::<History>()
+        .get(run_id)?
+        .ok_or_else(|| format!("No run with id '{}'", run_id))?;
+    let output = run.output.ok_or_else(|| format!("Run '{}' has no results to export", run_id))?;
+    Ok(records(&output, &target.columns, run_id, written_at))
+}
+
+fn report(target: &SqliteTarget, run_id: &str, plan: Plan, rows_written: usize, rows_skipped: usize) -> ExportReport {
+    ExportReport {
+        target: target.name.clone(),
+        run_id: run_id.to_string(),
+        table: target.table.clone(),
+        created: plan.created,
+        rows_written,
+        rows_skipped,
+        columns_added: plan.added.into_iter().map(|(column, _)| column).collect(),
+        warnings: plan.warnings,
+    }
+}
+
+/// Writes a run's output to a target, creating the table or adding columns as needed.
+pub fn export_run(app: &AppHandle, target: &SqliteTarget, run_id: &str) -> Result<ExportReport, String> {
+    let records = run_records(app, target, run_id, chrono::Utc::now().timestamp_millis())?;
+
+    let mut conn = open(target)?;
+    let existing = table_columns(&conn, &target.table)?;
+    let plan = plan(target, &existing, &records)?;
+    let rows_written = write(&mut conn, target, &records, &plan)?;
+
+    for warning in &plan.warnings {
+        log::warn!("SQLite target '{}', table {}: {}", target.name, target.table, warning);
+    }
+    log::info!("Wrote {} rows of run {} to {} ({})", rows_written, run_id, target.table, target.path.display());
+    Ok(report(target, run_id, plan, rows_written, records.rows.len() - rows_written))
+}
+
+/// What writing a run to a target would change, without writing anything.
+pub fn preview_run(app: &AppHandle, target: &SqliteTarget, run_id: &str) -> Result<ExportReport, String> {
+    let records = run_records(app, target, run_id, 0)?;
+    let existing = if target.path.is_file() {
+        let conn = Connection::open_with_flags(&target.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("Failed to open {}: {}", target.path.display(), e))?;
+        table_columns(&conn, &target.table)?
+    } else {
+        Vec::new()
+    };
+    let plan = plan(target, &existing, &records)?;
+    let rows_skipped = records.rows.iter().filter(|row| lacks_key(target, &records.columns, row)).count();
+    Ok(report(target, run_id, plan, records.rows.len() - rows_skipped, rows_skipped))
+}
+
+fn find_target(app: &AppHandle, target_id: &str) -> Result<SqliteTarget, String> {
+    app.state::<SettingsStore>()
+        .get()
+        .sqlite_targets
+        .into_iter()
+        .find(|target| target.id == target_id)
+        .ok_or_else(|| format!("No SQLite target with id '{}'", target_id))
+}
+
+/// Built-in plugin that writes the results of successful runs to the targets they feed.
+struct SqliteExportPlugin;
+
+impl ShellPlugin for SqliteExportPlugin {
+    fn name(&self) -> &str {
+        "sqlite-export"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        if job.status != Some(JobStatus::Success) || job.output.is_none() {
+            return;
+        }
+        let settings = ctx.app.state::<SettingsStore>().get();
+        for target in settings.sqlite_targets.iter().filter(|target| target.enabled && target.receives(job)) {
+            match export_run(ctx.app, target, &job.job_id) {
+                Ok(report) if !report.warnings.is_empty() => {
+                    let more = match report.warnings.len() {
+                        1 => String::new(),
+                        n => format!(" (and {} more changes)", n - 1),
+                    };
+                    notifications::notify(
+                        ctx.app,
+                        Notification {
+                            title: format!("Table {} changed shape", report.table),
+                            body: format!("{}{}", report.warnings[0], more),
+                            urgency: Urgency::Normal,
+                            category: Category::Jobs,
+                        },
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("Results of run {} not written to '{}': {}", job.job_id, target.name, e),
+            }
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(SqliteExportPlugin), "Writes run results to configured SQLite tables");
+}
+
+/// Writes a finished run's results to a configured target.
+#[tauri::command]
+pub async fn export_run_to_sqlite(app: AppHandle, target_id: String, run_id: String) -> Result<ExportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || export_run(&app, &find_target(&app, &target_id)?, &run_id))
+        .await
+        .map_err(|e| format!("SQLite export failed: {}", e))?
+}
+
+/// Shows what writing a run to `target` would do to its table, e.g. before saving a new
+/// or edited target.
+#[tauri::command]
+pub async fn preview_sqlite_export(
+    app: AppHandle,
+    target: SqliteTarget,
+    run_id: String,
+) -> Result<ExportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || preview_run(&app, &target, &run_id))
+        .await
+        .map_err(|e| format!("SQLite export preview failed: {}", e))?
+}
@@ -0,0 +1,202 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::settings::SettingsStore;
+use crate::{launch, schedules, server, supervisor, AppState};
+
+const TRAY_ID: &str = "main";
+const TOOLTIP: &str = "Browser Automation";
+const RECYCLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// When the user last had the window in front of them, used to recycle an idle standby server.
+pub struct Standby {
+    last_active: Mutex<Instant>,
+}
+
+impl Default for Standby {
+    fn default() -> Self {
+        Standby { last_active: Mutex::new(Instant::now()) }
+    }
+}
+
+/// Warm standby applies unless it is turned off or we are in safe mode (no tray, no autostart changes).
+pub fn is_active(app: &AppHandle) -> bool {
+    !launch::is_safe_mode(app) && app.state::<SettingsStore>().get().warm_standby
+}
+
+pub fn setup(app: &AppHandle, launched_in_standby: bool) {
+    app.manage(Standby::default());
+    apply_settings(app);
+
+    if launched_in_standby && is_active(app) {
+        log::info!("Started in standby - keeping the window hidden");
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.hide();
+        }
+    }
+
+    // Development builds do not own the server, so there is nothing to recycle
+    if !cfg!(debug_assertions) {
+        let app_handle = app.clone();
+        std::thread::spawn(move || recycle_loop(app_handle));
+    }
+}
+
+/// Brings autostart registration and the tray icon in line with the current settings.
+pub fn apply_settings(app: &AppHandle) {
+    let active = is_active(app);
+
+    if !launch::is_safe_mode(app) {
+        sync_autostart(app, active);
+    }
+
+    if active {
+        if let Err(e) = ensure_tray(app) {
+            log::error!("Failed to create tray icon: {}", e);
+        }
+    } else if app.remove_tray_by_id(TRAY_ID).is_some() {
+        log::info!("Tray icon removed");
+    }
+    update_tooltip(app);
+}
+
+fn countdown(seconds: u64) -> String {
+    match seconds / 60 {
+        0 => "in under a minute".to_string(),
+        minutes @ 1..=59 => format!("in {} min", minutes),
+        minutes => format!("in {} h {} min", minutes / 60, minutes % 60),
+    }
+}
+
+/// Shows the soonest scheduled run in the tray tooltip. Called when schedules change
+/// and on every scheduler tick, which keeps the countdown to within a minute.
+pub fn update_tooltip(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let tooltip = match schedules::upcoming(app, 1).first() {
+        Some(next) => format!("{} - next: {} {}", TOOLTIP, next.name, countdown(next.in_seconds)),
+        None => TOOLTIP.to_string(),
+    };
+    if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+        log::debug!("Failed to update the tray tooltip: {}", e);
+    }
+}
+
+fn sync_autostart(app: &AppHandle, enabled: bool) {
+    let autolaunch = app.autolaunch();
+    if autolaunch.is_enabled().ok() == Some(enabled) {
+        return;
+    }
+    let result = if enabled { autolaunch.enable() } else { autolaunch.disable() };
+    match result {
+        Ok(()) => log::info!("Launch at login {}", if enabled { "enabled" } else { "disabled" }),
+        Err(e) => log::error!("Failed to update launch at login: {}", e),
+    }
+}
+
+fn ensure_tray(app: &AppHandle) -> tauri::Result<()> {
+    if app.tray_by_id(TRAY_ID).is_some() {
+        return Ok(());
+    }
+
+    let open_item = MenuItem::with_id(app, "open", "Open Browser Automation", true, None::<&str>)?;
+    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&open_item, &quit_item])?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(TOOLTIP)
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "open" => show_main_window(app),
+            "quit" => quit(app),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    #[cfg(target_os = "linux")]
+    {
+        builder = builder.temp_dir_path(crate::uninstall::tray_icon_dir());
+    }
+    builder.build(app)?;
+    log::info!("Tray icon created");
+    Ok(())
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    mark_active(app);
+}
+
+pub fn mark_active(app: &AppHandle) {
+    if let Some(standby) = app.try_state::<Standby>() {
+        *standby.last_active.lock().unwrap() = Instant::now();
+    }
+}
+
+fn quit(app: &AppHandle) {
+    log::info!("Quit requested from tray - cleaning up server...");
+    app.state::<AppState>().cleanup_server();
+    app.exit(0);
+}
+
+/// With warm standby the window only hides on close, keeping the server running.
+pub fn should_hide_on_close(app: &AppHandle) -> bool {
+    is_active(app)
+}
+
+fn recycle_loop(app: AppHandle) {
+    loop {
+        std::thread::sleep(RECYCLE_CHECK_INTERVAL);
+
+        let recycle_minutes = app.state::<SettingsStore>().get().standby_recycle_minutes;
+        if !is_active(&app) || recycle_minutes == 0 {
+            continue;
+        }
+
+        let window_visible = app
+            .get_webview_window("main")
+            .and_then(|window| window.is_visible().ok())
+            .unwrap_or(true);
+        if window_visible {
+            mark_active(&app);
+            continue;
+        }
+
+        let idle = app.state::<Standby>().last_active.lock().unwrap().elapsed();
+        if idle < Duration::from_secs(recycle_minutes * 60) {
+            continue;
+        }
+
+        // The server is single-threaded: if it does not answer it is busy running a job
+        // (or down, which the supervisor handles), so leave it alone
+        let port = *app.state::<AppState>().server_port.lock().unwrap();
+        if !server::is_ready(port) {
+            continue;
+        }
+
+        log::info!("Recycling standby server after {} minutes idle", idle.as_secs() / 60);
+        supervisor::restart_server(&app);
+        mark_active(&app);
+    }
+}
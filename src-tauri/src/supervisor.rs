@@ -0,0 +1,533 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, ChildStderr, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::control_channel::Transport;
+use crate::{
+    antivirus, bundle, debug_ports, diagnostics, disk, display, flags, gatekeeper, governor, gpu, har, idle, launch,
+    license, plugins, politeness, recovery, server, session, AppState,
+};
+
+// Crashes within CRASH_WINDOW before we stop auto-restarting and ask the user.
+const MAX_CRASHES: usize = 3;
+const CRASH_WINDOW: Duration = Duration::from_secs(60);
+// Longest wait between restarts in kiosk mode, which never gives up.
+const KIOSK_MAX_BACKOFF_SECS: usize = 30;
+
+// Startup waits: the server normally answers within READY_TIMEOUT, but a first-run
+// self-extraction can keep it from opening its socket for much longer.
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_UNPACK_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Lines of server stderr kept for the crash diagnostic.
+const STDERR_TAIL_LINES: usize = 40;
+const DIALOG_STDERR_LINES: usize = 12;
+
+const SAFE_MODE_LABEL: &str = "Start in Safe Mode";
+const REINSTALL_LABEL: &str = "Reinstall Server";
+const OPEN_LOGS_LABEL: &str = "Open Log Folder";
+
+/// Crash history and recent stderr output of the supervised server.
+#[derive(Default)]
+pub struct Supervisor {
+    crashes: Mutex<VecDeque<Instant>>,
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl Supervisor {
+    /// Records a crash and returns how many happened within the crash window.
+    fn record_crash(&self) -> usize {
+        let now = Instant::now();
+        let mut crashes = self.crashes.lock().unwrap();
+        crashes.push_back(now);
+        while crashes.front().is_some_and(|t| now.duration_since(*t) > CRASH_WINDOW) {
+            crashes.pop_front();
+        }
+        crashes.len()
+    }
+
+    fn reset(&self) {
+        self.crashes.lock().unwrap().clear();
+    }
+
+    pub fn stderr_tail(&self) -> Vec<String> {
+        self.stderr_tail.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Starts the server, reporting a failure to the frontend instead of returning it.
+/// Blocks while a first-run bundle extraction happens, so call it off the main thread.
+pub fn launch(app: &AppHandle, safe_mode: bool) {
+    if let Err(e) = start_server(app, safe_mode) {
+        log::error!("Failed to start server: {}", e);
+        let port = *app.state::<AppState>().server_port.lock().unwrap();
+        emit_progress(app, StartupPhase::Failed, port, Duration::ZERO);
+    }
+}
+
+/// Stops the running server (if any) and starts a fresh one.
+pub fn restart_server(app: &AppHandle) {
+    session::save_before_stop(app);
+    app.state::<AppState>().cleanup_server();
+    launch(app, launch::is_safe_mode(app));
+}
+
+/// Spawns the server, retrying after a pause while antivirus software blocks the binary.
+fn spawn_with_retry(app: &AppHandle, cmd: &mut Command, binary: &Path) -> std::io::Result<Child> {
+    let mut attempt = 1;
+    loop {
+        match cmd.spawn() {
+            Err(e) if antivirus::is_blocked(&e) => {
+                antivirus::report(app, binary, &e, attempt);
+                if attempt > antivirus::RETRIES {
+                    return Err(e);
+                }
+                std::thread::sleep(antivirus::RETRY_DELAY);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Spawns the bundled server, supervises it, and navigates the main window to it once it answers.
+pub fn start_server(app: &AppHandle, safe_mode: bool) -> Result<(), Box<dyn std::error::Error>> {
+    log::info!("Starting Python Flask server...");
+
+    // Find an available port
+    let port = match server::find_available_port() {
+        Ok(p) => {
+            log::info!("Found available port: {}", p);
+            p
+        },
+        Err(e) => {
+            log::error!("Failed to find available port: {}", e);
+            return Err(Box::new(e));
+        }
+    };
+
+    // Store the port in app state
+    *app.state::<AppState>().server_port.lock().unwrap() = port;
+
+    // Production mode - Python and dependencies are bundled with the app
+    let server_bundle = match bundle::prepare(app) {
+        Ok(server_bundle) => server_bundle,
+        Err(e) => {
+            log::error!("{}", e);
+            return Err(e.into());
+        }
+    };
+    let server_binary = server_bundle.binary;
+
+    log::info!("Server binary: {:?} ({:?} layout)", server_binary, server_bundle.layout);
+
+    // Check if binary exists before trying to spawn
+    if !server_binary.exists() {
+        log::error!("Server binary not found at: {:?}", server_binary);
+        return Err("Server binary not found in bundle".into());
+    }
+
+    let extracted = server_bundle.layout == bundle::BundleLayout::Extracted;
+    if let Err(e) = gatekeeper::prepare_binary(&server_binary, extracted) {
+        log::error!("{}", e);
+        return Err(e.into());
+    }
+
+    log::info!("Server binary found, starting server on port {}...", port);
+
+    // Check for VERBOSE environment variable to pass to server.
+    // Safe mode ignores VERBOSE; the server then skips saved config other than the API key,
+    // network capture and GPU workarounds, and runs one browser at a time
+    let verbose = !safe_mode && server::verbose_requested();
+    let mut cmd = server::server_command(&server_binary, port, verbose);
+    if safe_mode {
+        cmd.env("NOVA_SAFE_MODE", "1");
+    }
+    cmd.env("NOVA_ENTITLEMENTS", license::server_env(app));
+    cmd.env("NOVA_FEATURE_FLAGS", flags::server_env(app));
+    cmd.env("NOVA_GPU", gpu::server_env());
+    cmd.env("NOVA_BROWSER_BOUNDS", display::browser_bounds(app));
+    cmd.env("NOVA_USER_IDLE", idle::server_env());
+    cmd.env("NOVA_MAX_CONCURRENCY", governor::server_env());
+    cmd.env("NOVA_DISK_CRITICAL", disk::server_env());
+    cmd.env("NOVA_DOMAIN_LIMITS", politeness::server_env(app));
+    cmd.env("NOVA_HAR_CAPTURE", har::server_env(app));
+    match debug_ports::allocate("main") {
+        Ok(debug_port) => {
+            cmd.env("NOVA_DEBUG_PORT", debug_port.to_string());
+        }
+        Err(e) => log::warn!("{}; the browser uses its default DevTools port", e),
+    }
+    let transport = Transport::for_port(port);
+    transport.configure(&mut cmd);
+    // Keep stderr so a crash diagnostic can show what the server said last
+    cmd.stderr(Stdio::piped());
+
+    diagnostics::record_server_env(&cmd);
+
+    log::info!("Spawning server process...");
+    let mut server_child = match spawn_with_retry(app, &mut cmd, &server_binary) {
+        Ok(child) => {
+            log::info!("Server process started successfully (PID: {})", child.id());
+            child
+        },
+        Err(e) => {
+            log::error!("Failed to start server: {}", e);
+            gatekeeper::explain_spawn_error(app, &server_binary, &e);
+            return Err(Box::new(e));
+        }
+    };
+
+    if let Transport::Stdio = transport {
+        app.state::<AppState>().control.attach_stdio(app, &mut server_child);
+    }
+
+    if let Some(stderr) = server_child.stderr.take() {
+        let tail = app.state::<Supervisor>().stderr_tail.clone();
+        tail.lock().unwrap().clear();
+        std::thread::spawn(move || capture_stderr(stderr, tail));
+    }
+
+    // Store the process handle
+    let pid = server_child.id();
+    *app.state::<AppState>().python_process.lock().unwrap() = Some(server_child);
+
+    let monitor_handle = app.clone();
+    std::thread::spawn(move || monitor(monitor_handle, safe_mode));
+
+    log::info!("Flask server starting on port {}...", port);
+
+    // Wait for server to be ready, then tell the UI, which is bundled and already showing
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        match wait_for_startup(&app_handle, pid, port) {
+            StartupPhase::Ready => {
+                recovery::record_successful_start(&app_handle);
+                if let Transport::Socket(path) = transport {
+                    if let Err(e) = app_handle.state::<AppState>().control.connect_socket(&app_handle, &path) {
+                        log::warn!("Control channel unavailable, using HTTP only: {}", e);
+                    }
+                }
+                if let Err(e) = session::restore(&app_handle) {
+                    log::warn!("Session not restored: {}", e);
+                }
+                plugins::server_ready(&app_handle);
+                if let Err(e) = app_handle.emit_to("main", "server-ready", port) {
+                    log::error!("Failed to tell the window the server is ready: {}", e);
+                }
+            }
+            StartupPhase::TimedOut => {
+                recovery::record_crash_loop(&app_handle);
+            }
+            // The monitor thread handles a server that exits during startup
+            _ => {}
+        }
+    });
+    Ok(())
+}
+
+/// Phases reported to the frontend while the server is starting.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPhase {
+    /// Process is running but has not opened its socket yet. PyInstaller onefile
+    /// binaries spend this time unpacking themselves, which can take many seconds.
+    Unpacking,
+    /// Socket is open but the app is not answering requests yet.
+    Listening,
+    Ready,
+    Exited,
+    TimedOut,
+    /// The server could not be launched at all.
+    Failed,
+}
+
+/// Payload of the `server-startup-progress` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupProgress {
+    pub phase: StartupPhase,
+    pub port: u16,
+    pub elapsed_ms: u128,
+    pub message: &'static str,
+}
+
+fn process_alive(app: &AppHandle, pid: u32) -> bool {
+    let app_state = app.state::<AppState>();
+    let lock = app_state.python_process.lock().unwrap();
+    lock.as_ref().is_some_and(|child| child.id() == pid)
+}
+
+/// Waits for the server to answer, emitting `server-startup-progress` heartbeats.
+///
+/// The timeout is adaptive: as long as the process is alive but has not opened its socket
+/// (self-extraction on first run) we keep waiting up to `MAX_UNPACK_TIMEOUT`. Once the socket is
+/// open the server gets `READY_TIMEOUT` to start answering.
+fn wait_for_startup(app: &AppHandle, pid: u32, port: u16) -> StartupPhase {
+    let started = Instant::now();
+    let mut listening_since: Option<Instant> = None;
+    let mut extended = false;
+
+    let phase = loop {
+        std::thread::sleep(STARTUP_POLL_INTERVAL);
+        let elapsed = started.elapsed();
+
+        if !process_alive(app, pid) {
+            log::warn!("Server process exited during startup");
+            break StartupPhase::Exited;
+        }
+
+        let phase = if server::port_open(port) {
+            if server::is_ready(port) {
+                log::info!("Flask server is ready after {:.1}s", elapsed.as_secs_f32());
+                break StartupPhase::Ready;
+            }
+            StartupPhase::Listening
+        } else if app.state::<AppState>().control.announced_ready() {
+            // Stdio channel: the server is configured and opening its port
+            StartupPhase::Listening
+        } else {
+            StartupPhase::Unpacking
+        };
+
+        if phase == StartupPhase::Listening {
+            let since = *listening_since.get_or_insert_with(Instant::now);
+            if since.elapsed() > READY_TIMEOUT {
+                log::warn!("Flask server opened its port but did not answer within {}s", READY_TIMEOUT.as_secs());
+                break StartupPhase::TimedOut;
+            }
+        } else if elapsed > MAX_UNPACK_TIMEOUT {
+            log::warn!("Flask server did not open its port within {}s", MAX_UNPACK_TIMEOUT.as_secs());
+            break StartupPhase::TimedOut;
+        } else if elapsed > READY_TIMEOUT && !extended {
+            log::info!("Server is still unpacking after {}s - extending startup timeout", READY_TIMEOUT.as_secs());
+            extended = true;
+        }
+
+        emit_progress(app, phase, port, elapsed);
+    };
+
+    emit_progress(app, phase, port, started.elapsed());
+    phase
+}
+
+fn emit_progress(app: &AppHandle, phase: StartupPhase, port: u16, elapsed: Duration) {
+    let message = match phase {
+        StartupPhase::Unpacking if elapsed > READY_TIMEOUT => {
+            "Preparing the automation engine for first use, this can take a minute"
+        }
+        StartupPhase::Unpacking => "Starting automation engine",
+        StartupPhase::Listening => "Connecting to server",
+        StartupPhase::Ready => "Server ready",
+        StartupPhase::Exited => "Server stopped unexpectedly",
+        StartupPhase::TimedOut => "Server did not start in time",
+        StartupPhase::Failed => "Server could not be started",
+    };
+    let progress = StartupProgress { phase, port, elapsed_ms: elapsed.as_millis(), message };
+    if let Err(e) = app.emit("server-startup-progress", progress) {
+        log::debug!("Failed to emit startup progress: {}", e);
+    }
+}
+
+fn capture_stderr(stderr: ChildStderr, tail: Arc<Mutex<VecDeque<String>>>) {
+    for line in BufReader::new(stderr).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        log::debug!("[server] {}", line);
+        let mut tail = tail.lock().unwrap();
+        if tail.len() == STDERR_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+}
+
+/// Watches the server process until it exits. A process taken out of `AppState` by
+/// `cleanup_server` is an intentional stop; anything else is treated as a crash.
+fn monitor(app: AppHandle, safe_mode: bool) {
+    let status = loop {
+        std::thread::sleep(Duration::from_secs(1));
+
+        let app_state = app.state::<AppState>();
+        let mut lock = app_state.python_process.lock().unwrap();
+        let Some(child) = lock.as_mut() else {
+            return;
+        };
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                lock.take();
+                break status;
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to poll server process: {}", e),
+        }
+    };
+
+    handle_crash(&app, status, safe_mode);
+}
+
+fn handle_crash(app: &AppHandle, status: ExitStatus, safe_mode: bool) {
+    log::error!("Server exited unexpectedly ({})", status);
+
+    let supervisor = app.state::<Supervisor>();
+    let crashes = supervisor.record_crash();
+    // Nobody is at a kiosk to answer the dialog, so keep trying with a capped backoff
+    if launch::is_kiosk(app) {
+        let backoff = Duration::from_secs(crashes.min(KIOSK_MAX_BACKOFF_SECS) as u64);
+        log::warn!("Kiosk mode: restarting server in {}s (crash {})", backoff.as_secs(), crashes);
+        std::thread::sleep(backoff);
+        launch(app, safe_mode);
+        return;
+    }
+    if crashes >= MAX_CRASHES {
+        log::error!(
+            "Server crashed {} times within {} seconds - no longer restarting automatically",
+            crashes,
+            CRASH_WINDOW.as_secs()
+        );
+        recovery::record_crash_loop(app);
+        show_crash_loop_dialog(app);
+        return;
+    }
+
+    // Back off a little more after each crash so a failing dependency has time to recover
+    let backoff = Duration::from_secs(crashes as u64);
+    log::warn!("Restarting server in {}s (crash {} of {})", backoff.as_secs(), crashes, MAX_CRASHES);
+    std::thread::sleep(backoff);
+    launch(app, safe_mode);
+}
+
+fn show_crash_loop_dialog(app: &AppHandle) {
+    let tail = app.state::<Supervisor>().stderr_tail();
+    let last_lines = tail[tail.len().saturating_sub(DIALOG_STDERR_LINES)..].join("\n");
+
+    let result = app
+        .dialog()
+        .message(format!(
+            "The automation server keeps stopping unexpectedly.\n\nLast output:\n{}",
+            if last_lines.is_empty() { "(no output)" } else { &last_lines }
+        ))
+        .title("Automation server stopped")
+        .kind(MessageDialogKind::Error)
+        .buttons(MessageDialogButtons::YesNoCancelCustom(
+            SAFE_MODE_LABEL.to_string(),
+            REINSTALL_LABEL.to_string(),
+            OPEN_LOGS_LABEL.to_string(),
+        ))
+        .blocking_show_with_result();
+
+    // Custom buttons come back as either the standard variant or their label depending on platform
+    match result {
+        MessageDialogResult::Yes => restart_in_safe_mode(app),
+        MessageDialogResult::No => reinstall_server(app),
+        MessageDialogResult::Custom(label) if label == SAFE_MODE_LABEL => restart_in_safe_mode(app),
+        MessageDialogResult::Custom(label) if label == REINSTALL_LABEL => reinstall_server(app),
+        _ => open_log_folder(app),
+    }
+}
+
+fn restart_in_safe_mode(app: &AppHandle) {
+    log::info!("Restarting server in safe mode");
+    launch::enable_safe_mode(app);
+    app.state::<Supervisor>().reset();
+    launch(app, true);
+}
+
+/// Discards the extracted server copy, re-verifies the bundled one and restarts from it
+/// with a clean crash history.
+fn reinstall_server(app: &AppHandle) {
+    log::info!("Reinstalling server bundle");
+    if let Err(e) = bundle::remove_extracted(app) {
+        log::warn!("{}", e);
+    }
+    let verified = bundle::resource_bundle_dir(app)
+        .and_then(|dir| server::verify_binary(&dir.join("server")));
+    if let Err(e) = verified {
+        log::error!("Server bundle is damaged: {}", e);
+        app.dialog()
+            .message(format!(
+                "The automation server files are damaged and cannot be repaired automatically.\n\n{}\n\n\
+                 Please reinstall the application.",
+                e
+            ))
+            .title("Reinstall required")
+            .kind(MessageDialogKind::Error)
+            .blocking_show();
+        return;
+    }
+
+    app.state::<Supervisor>().reset();
+    launch(app, launch::is_safe_mode(app));
+}
+
+fn open_log_folder(app: &AppHandle) {
+    match app.path().app_log_dir() {
+        Ok(dir) => {
+            if let Err(e) = app.opener().open_path(dir.to_string_lossy(), None::<&str>) {
+                log::error!("Failed to open log folder: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to resolve log folder: {}", e),
+    }
+}
+
+/// Coarse server state for status indicators.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerState {
+    Running,
+    /// The process is up but not answering yet.
+    Starting,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerStatus {
+    pub state: ServerState,
+    pub port: u16,
+}
+
+fn server_status(app: &AppHandle) -> ServerStatus {
+    let app_state = app.state::<AppState>();
+    let port = *app_state.server_port.lock().unwrap();
+    let spawned = app_state.python_process.lock().unwrap().is_some();
+    // Ask the port first: development builds talk to a server they did not spawn
+    let state = if server::is_ready(port) {
+        ServerState::Running
+    } else if spawned {
+        ServerState::Starting
+    } else {
+        ServerState::Stopped
+    };
+    ServerStatus { state, port }
+}
+
+#[tauri::command]
+pub async fn get_server_status(app: AppHandle) -> Result<ServerStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || server_status(&app))
+        .await
+        .map_err(|e| format!("Failed to check server: {}", e))
+}
+
+/// Restarts the server on demand, with a clean crash history.
+#[tauri::command]
+pub async fn request_server_restart(app: AppHandle) -> Result<(), String> {
+    if cfg!(debug_assertions) {
+        return Err("The server is started manually in development mode".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        log::info!("Server restart requested");
+        app.state::<Supervisor>().reset();
+        restart_server(&app);
+    })
+    .await
+    .map_err(|e| format!("Failed to restart server: {}", e))
+}
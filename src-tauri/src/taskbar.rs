@@ -0,0 +1,70 @@
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager};
+
+fn main_window(app: &AppHandle) -> Result<tauri::WebviewWindow, String> {
+    app.get_webview_window("main").ok_or_else(|| "Main window is not open".to_string())
+}
+
+fn show_progress(app: &AppHandle, status: ProgressBarStatus, progress: Option<u64>) -> Result<(), String> {
+    main_window(app)?
+        .set_progress_bar(ProgressBarState { status: Some(status), progress })
+        .map_err(|e| format!("Failed to set progress: {}", e))
+}
+
+/// A small red dot, used on Windows where taskbar buttons cannot show a number.
+#[cfg(windows)]
+fn badge_dot() -> tauri::image::Image<'static> {
+    const SIZE: u32 = 16;
+    let center = (SIZE as f32 - 1.0) / 2.0;
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let inside = (x as f32 - center).hypot(y as f32 - center) <= center;
+            rgba.extend_from_slice(if inside { &[0xef, 0x44, 0x44, 0xff] } else { &[0, 0, 0, 0] });
+        }
+    }
+    tauri::image::Image::new_owned(rgba, SIZE, SIZE)
+}
+
+fn show_badge(app: &AppHandle, count: Option<i64>) -> Result<(), String> {
+    let window = main_window(app)?;
+    let count = count.filter(|count| *count > 0);
+    #[cfg(windows)]
+    let result = window.set_overlay_icon(count.map(|_| badge_dot()));
+    #[cfg(not(windows))]
+    let result = window.set_badge_count(count);
+    result.map_err(|e| format!("Failed to set badge: {}", e))
+}
+
+/// Mirrors job activity on the Dock icon / taskbar button: indeterminate progress and a
+/// badge with the number of `running` jobs, cleared once all of them have finished.
+pub fn jobs_changed(app: &AppHandle, running: usize) {
+    // The server does not report how far along a job is, so progress is indeterminate
+    let result = if running > 0 {
+        show_progress(app, ProgressBarStatus::Indeterminate, None).and_then(|_| show_badge(app, Some(running as i64)))
+    } else {
+        show_progress(app, ProgressBarStatus::None, None).and_then(|_| show_badge(app, None))
+    };
+    if let Err(e) = result {
+        log::debug!("Taskbar update skipped: {}", e);
+    }
+}
+
+/// Sets Dock/taskbar progress. `fraction` runs from 0.0 to 1.0; omit it to clear.
+#[tauri::command]
+pub fn set_progress(app: AppHandle, fraction: Option<f64>) -> Result<(), String> {
+    match fraction {
+        Some(fraction) => {
+            let percent = (fraction.clamp(0.0, 1.0) * 100.0).round() as u64;
+            show_progress(&app, ProgressBarStatus::Normal, Some(percent))
+        }
+        None => show_progress(&app, ProgressBarStatus::None, None),
+    }
+}
+
+/// Sets the Dock/taskbar badge. Omit `count` or pass 0 to clear it. Windows shows a dot
+/// instead of the number.
+#[tauri::command]
+pub fn set_badge(app: AppHandle, count: Option<i64>) -> Result<(), String> {
+    show_badge(&app, count)
+}
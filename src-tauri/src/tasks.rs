@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::alerts::Assertion;
+use crate::normalize::NormalizeRule;
+use crate::transforms::Transform;
+use crate::{access, jobs, launch, recent, server, AppState};
+
+/// How long an opened task waits for the server, e.g. when it launched the app.
+const SERVER_WAIT: Duration = Duration::from_secs(120);
+
+/// Extension of task files, registered with the OS as the app's document type.
+pub const EXTENSION: &str = "nova";
+/// Newest task file format this version understands.
+const FORMAT_VERSION: u32 = 1;
+/// Task files are a prompt and a few fields; anything bigger is not one.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+fn default_version() -> u32 {
+    FORMAT_VERSION
+}
+
+/// An automation saved to a `.nova` file (JSON).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskFile {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// Display name; the file name is used when missing.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub prompt: String,
+    /// Checked against the output of each successful run; see `alerts`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<Assertion>,
+    /// Not reviewed yet, e.g. just recorded.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
+    /// Locale the site writes numbers and dates in, e.g. `de-DE`; see `normalize`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Output fields converted to canonical numbers, amounts and dates before the run is
+    /// stored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub normalize: Vec<NormalizeRule>,
+    /// Steps run over the normalized output, in order; see `transforms`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<Transform>,
+}
+
+impl TaskFile {
+    pub fn new(name: Option<String>, prompt: String) -> Self {
+        TaskFile {
+            version: FORMAT_VERSION,
+            name,
+            prompt,
+            assertions: Vec::new(),
+            draft: false,
+            locale: None,
+            normalize: Vec::new(),
+            transforms: Vec::new(),
+        }
+    }
+
+    pub fn display_name(&self, path: &Path) -> String {
+        self.name
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .or_else(|| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "Task".to_string())
+    }
+}
+
+/// Reads and validates a task file.
+pub fn load(path: &Path) -> Result<TaskFile, String> {
+    if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(EXTENSION)) {
+        return Err(format!("{} is not a .{} task file", path.display(), EXTENSION));
+    }
+    let size = std::fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(format!("{} is too large to be a task file", path.display()));
+    }
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse(&contents, &path.display().to_string())
+}
+
+/// Validates the contents of a task file; `source` names it in errors.
+pub fn parse(contents: &str, source: &str) -> Result<TaskFile, String> {
+    if contents.len() as u64 > MAX_FILE_SIZE {
+        return Err(format!("{} is too large to be a task file", source));
+    }
+    let task: TaskFile =
+        serde_json::from_str(contents).map_err(|e| format!("{} is not a valid task file: {}", source, e))?;
+    if task.version > FORMAT_VERSION {
+        return Err(format!(
+            "{} was saved by a newer version of the app (format {}), please update",
+            source, task.version
+        ));
+    }
+    if task.prompt.trim().is_empty() {
+        return Err(format!("{} has an empty prompt", source));
+    }
+    Ok(task)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TaskOpened {
+    path: PathBuf,
+    task: TaskFile,
+}
+
+fn wait_for_server(app: &AppHandle) -> Option<u16> {
+    let started = Instant::now();
+    while started.elapsed() < SERVER_WAIT {
+        let port = *app.state::<AppState>().server_port.lock().unwrap();
+        if server::is_ready(port) {
+            return Some(port);
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+    None
+}
+
+/// Opens a task file: it becomes the most recent task, the frontend is told via
+/// `task-opened`, and the task runs on the main server once that is up. Blocks until the
+/// job has finished.
+pub fn open(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let task = load(path)?;
+    let name = task.display_name(path);
+    log::info!("Opening task '{}' from {}", name, path.display());
+    if let Err(e) = recent::add(app, path, &name) {
+        log::warn!("Failed to record recent task: {}", e);
+    }
+    let _ = app.emit("task-opened", TaskOpened { path: path.to_path_buf(), task: task.clone() });
+    if launch::is_viewer(app) {
+        return Err(access::refusal("running tasks"));
+    }
+
+    let port = wait_for_server(app).ok_or("The automation server is not running")?;
+    jobs::run(app, port, jobs::new_job_id(), &task.prompt, None, None);
+    Ok(())
+}
+
+/// Opens task files handed to the app by the OS (Dock recent items, jump list entries,
+/// command line) in the background.
+pub fn open_all(app: &AppHandle, paths: Vec<PathBuf>) {
+    for path in paths {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = open(&app, &path) {
+                log::error!("Failed to open task: {}", e);
+                app.dialog()
+                    .message(e)
+                    .title("Could not open task")
+                    .kind(MessageDialogKind::Error)
+                    .blocking_show();
+            }
+        });
+    }
+}
+
+/// Runs a task file, as if it had been opened from the OS.
+#[tauri::command]
+pub async fn run_task_file(app: AppHandle, path: PathBuf) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || open(&app, &path))
+        .await
+        .map_err(|e| format!("Running task failed: {}", e))?
+}
@@ -0,0 +1,213 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::history;
+use crate::run_diff::read_png;
+
+/// Sizes thumbnails are made in, in pixels along the longer side. Other requested sizes
+/// get the next one up, so the cache holds a few files per screenshot at most.
+const SIZES: &[u32] = &[128, 256, 512];
+/// Made for every screenshot as soon as its run finishes.
+const DEFAULT_SIZE: u32 = 256;
+/// Pixels along the longer side of the image the placeholder is worked out from.
+const BLURHASH_SOURCE_SIZE: u32 = 32;
+/// Placeholder detail: enough to hint at a page's layout, short enough to inline.
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Thumbnail {
+    /// `data:image/png;base64,...`, ready for an `<img>`.
+    pub data_url: String,
+    pub width: u32,
+    pub height: u32,
+    /// Blurred placeholder to show while the thumbnail loads.
+    pub blurhash: String,
+}
+
+/// Cached thumbnails in the app cache dir: `thumbnails/<run_id>/<hash of the name>-<size>.png`,
+/// plus `<hash of the name>.blurhash`. Purges clear it with the rest of the cache.
+fn cache_dir(app: &AppHandle, run_id: &str) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("thumbnails").join(run_id))
+        .map_err(|e| format!("Failed to get app cache dir: {}", e))
+}
+
+fn cache_key(name: &str) -> String {
+    format!("{:x}", Sha256::digest(name.as_bytes()))[..32].to_string()
+}
+
+/// The screenshot an artifact id (`<run_id>/<path>`, as in artifact URLs) points at, as
+/// its run id, name and path. Anything that could step outside the run's artifacts dir
+/// is refused.
+pub fn resolve_screenshot(app: &AppHandle, artifact_id: &str) -> Result<(String, String, PathBuf), String> {
+    let invalid = || format!("Unknown artifact: {}", artifact_id);
+    let (run_id, name) = artifact_id.split_once('/').ok_or_else(invalid)?;
+    if !Path::new(name).components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(invalid());
+    }
+    let path = history::artifacts_dir(app, run_id).map(|dir| dir.join(name)).filter(|path| path.is_file());
+    let path = path.ok_or_else(invalid)?;
+    if !is_screenshot(&path) {
+        return Err(format!("Not a screenshot: {}", artifact_id));
+    }
+    Ok((run_id.to_string(), name.to_string(), path))
+}
+
+pub fn is_screenshot(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("png"))
+}
+
+/// Whether `cached` was made from the current `source`; a newer screenshot under the
+/// same name replaces it.
+fn is_fresh(cached: &Path, source: &Path) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    matches!((modified(cached), modified(source)), (Some(cached), Some(source)) if cached >= source)
+}
+
+/// The size of `(width, height)` scaled down to fit `size` along its longer side.
+pub fn fit(width: u32, height: u32, size: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= size {
+        return (width, height);
+    }
+    let scale = |side: u32| ((side as u64 * size as u64 / longest as u64) as u32).max(1);
+    (scale(width), scale(height))
+}
+
+/// Downscales RGBA pixels by averaging the source pixels each target pixel covers.
+pub fn downscale(width: u32, height: u32, pixels: &[[u8; 4]], target: (u32, u32)) -> Vec<u8> {
+    let (target_width, target_height) = target;
+    let span = |index: u32, from: u32, to: u32| {
+        let start = index as u64 * from as u64 / to as u64;
+        let end = ((index as u64 + 1) * from as u64 / to as u64).max(start + 1);
+        start as usize..end as usize
+    };
+    let mut output = Vec::with_capacity(target_width as usize * target_height as usize * 4);
+    for y in 0..target_height {
+        let rows = span(y, height, target_height);
+        for x in 0..target_width {
+            let columns = span(x, width, target_width);
+            let mut sum = [0u64; 4];
+            let mut count = 0u64;
+            for row in rows.clone() {
+                for pixel in &pixels[row * width as usize + columns.start..row * width as usize + columns.end] {
+                    for (total, channel) in sum.iter_mut().zip(pixel) {
+                        *total += *channel as u64;
+                    }
+                    count += 1;
+                }
+            }
+            output.extend(sum.iter().map(|total| (total / count) as u8));
+        }
+    }
+    output
+}
+
+pub fn write_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgba))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Makes (or reuses) the thumbnail of `source` at `size` and its placeholder. Returns the
+/// thumbnail's path and the placeholder.
+fn generate(app: &AppHandle, run_id: &str, name: &str, source: &Path, size: u32) -> Result<(PathBuf, String), String> {
+    let dir = cache_dir(app, run_id)?;
+    let key = cache_key(name);
+    let thumbnail = dir.join(format!("{}-{}.png", key, size));
+    let blurhash_path = dir.join(format!("{}.blurhash", key));
+    let cached_blurhash = std::fs::read_to_string(&blurhash_path).ok().filter(|_| is_fresh(&blurhash_path, source));
+    if let Some(blurhash) = cached_blurhash.clone().filter(|_| is_fresh(&thumbnail, source)) {
+        return Ok((thumbnail, blurhash));
+    }
+
+    let (width, height, pixels) = read_png(source)?;
+    if width == 0 || height == 0 {
+        return Err(format!("{} is empty", source.display()));
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let (thumb_width, thumb_height) = fit(width, height, size);
+    let rgba = downscale(width, height, &pixels, (thumb_width, thumb_height));
+    write_png(&thumbnail, thumb_width, thumb_height, &rgba)?;
+
+    let blurhash = match cached_blurhash {
+        Some(blurhash) => blurhash,
+        None => {
+            let (tiny_width, tiny_height) = fit(width, height, BLURHASH_SOURCE_SIZE);
+            let tiny = downscale(width, height, &pixels, (tiny_width, tiny_height));
+            let (x, y) = BLURHASH_COMPONENTS;
+            let blurhash = blurhash::encode(x, y, tiny_width, tiny_height, &tiny)
+                .map_err(|e| format!("Failed to make a placeholder for {}: {:?}", source.display(), e))?;
+            std::fs::write(&blurhash_path, &blurhash)
+                .map_err(|e| format!("Failed to write {}: {}", blurhash_path.display(), e))?;
+            blurhash
+        }
+    };
+    Ok((thumbnail, blurhash))
+}
+
+/// Makes default-size thumbnails for every screenshot a run left.
+fn generate_run(app: &AppHandle, run_id: &str) -> Result<usize, String> {
+    let Some(dir) = history::artifacts_dir(app, run_id).filter(|dir| dir.is_dir()) else {
+        return Ok(0);
+    };
+    let mut made = 0;
+    for (path, name) in history::artifact_files(&dir)? {
+        if !is_screenshot(&path) {
+            continue;
+        }
+        match generate(app, run_id, &name, &path, DEFAULT_SIZE) {
+            Ok(_) => made += 1,
+            Err(e) => log::warn!("No thumbnail for {} of run {}: {}", name, run_id, e),
+        }
+    }
+    Ok(made)
+}
+
+/// Makes thumbnails for a finished run's screenshots in the background, so run history
+/// has them ready when it opens.
+pub fn run_finished(app: &AppHandle, run_id: &str) {
+    let (app, run_id) = (app.clone(), run_id.to_string());
+    std::thread::spawn(move || match generate_run(&app, &run_id) {
+        Ok(0) => {}
+        Ok(made) => log::info!("Made {} thumbnails for run {}", made, run_id),
+        Err(e) => log::warn!("Failed to make thumbnails for run {}: {}", run_id, e),
+    });
+}
+
+/// A downscaled screenshot for run history. `artifact_id` is `<run_id>/<path>` within the
+/// run's artifacts; `size` is the longest side wanted in pixels, rounded up to a cached size.
+#[tauri::command]
+pub async fn get_thumbnail(app: AppHandle, artifact_id: String, size: Option<u32>) -> Result<Thumbnail, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let wanted = size.unwrap_or(DEFAULT_SIZE);
+        let size = SIZES.iter().copied().find(|s| *s >= wanted).unwrap_or(SIZES[SIZES.len() - 1]);
+        let (run_id, name, source) = resolve_screenshot(&app, &artifact_id)?;
+        let (path, blurhash) = generate(&app, &run_id, &name, &source, size)?;
+        let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let info = png::Decoder::new(bytes.as_slice())
+            .read_info()
+            .map(|reader| (reader.info().width, reader.info().height))
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Ok(Thumbnail {
+            data_url: format!("data:image/png;base64,{}", BASE64.encode(&bytes)),
+            width: info.0,
+            height: info.1,
+            blurhash,
+        })
+    })
+    .await
+    .map_err(|e| format!("Thumbnail failed: {}", e))?
+}
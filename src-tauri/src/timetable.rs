@@ -0,0 +1,180 @@
+use chrono::{DateTime, LocalResult, NaiveDateTime, TimeDelta, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+/// Longest a clock change can skip; DST gaps are an hour, a zone moving across the date line a day.
+const MAX_GAP_MINUTES: i64 = 25 * 60;
+const MAX_SIMULATED_RUNS: usize = 1000;
+/// Standard cron's weekday numbers, Sunday being both 0 and 7.
+const WEEKDAYS: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+/// Why a run happens at a different time than its expression says.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Adjustment {
+    /// The clocks skipped the time (spring forward), so the run happens when they land.
+    Shifted,
+    /// The time happened twice (fall back); the run happens the first time only.
+    Repeated,
+}
+
+/// One run of a timetable: the wall-clock time it is for and the instant it happens.
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub wall: NaiveDateTime,
+    pub at: DateTime<Utc>,
+    pub adjustment: Option<Adjustment>,
+}
+
+/// A cron expression evaluated on a time zone's wall clock, so a 09:00 run stays at 09:00
+/// local time across DST changes.
+pub struct Timetable {
+    cron: cron::Schedule,
+    zone: Tz,
+}
+
+/// The zone named by `name` (IANA, e.g. `Europe/Berlin`), or the machine's zone.
+pub fn zone(name: Option<&str>) -> Result<Tz, String> {
+    let name = match name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => name.to_string(),
+        None => iana_time_zone::get_timezone().map_err(|e| format!("Could not read the system time zone: {}", e))?,
+    };
+    Tz::from_str(&name).map_err(|_| format!("Unknown time zone '{}'", name))
+}
+
+/// When the clocks show `wall`. Times inside a DST gap happen when the clocks land, and
+/// repeated times the first time round.
+pub fn resolve(zone: Tz, wall: NaiveDateTime) -> (DateTime<Utc>, Option<Adjustment>) {
+    match zone.from_local_datetime(&wall) {
+        LocalResult::Single(at) => (at.with_timezone(&Utc), None),
+        LocalResult::Ambiguous(first, _) => (first.with_timezone(&Utc), Some(Adjustment::Repeated)),
+        LocalResult::None => {
+            let start = wall.with_second(0).unwrap_or(wall);
+            for minutes in 1..=MAX_GAP_MINUTES {
+                if let Some(at) = zone.from_local_datetime(&(start + TimeDelta::minutes(minutes))).earliest() {
+                    return (at.with_timezone(&Utc), Some(Adjustment::Shifted));
+                }
+            }
+            (Utc.from_utc_datetime(&wall), Some(Adjustment::Shifted))
+        }
+    }
+}
+
+/// Standard cron numbers weekdays from 0 (or 7) for Sunday; the parser counts from 1, so
+/// numbers become names. A range ending on 7 is spelled out day by day, as `FRI-SUN` would
+/// run backwards for the parser. Steps after `/` stay numbers. Six-field expressions are
+/// the parser's own and keep its numbering, 1 for Sunday to 7 for Saturday.
+fn weekday_names(field: &str) -> String {
+    let items: Vec<String> = field
+        .split(',')
+        .map(|item| {
+            let (range, step) = item.split_once('/').unwrap_or((item, "1"));
+            let start = range.strip_suffix("-7").and_then(|start| start.parse::<usize>().ok());
+            match (start, step.parse::<usize>()) {
+                (Some(start @ 0..=7), Ok(step @ 1..)) => {
+                    let days: BTreeSet<usize> = (start..=7).step_by(step).map(|day| day % 7).collect();
+                    days.iter().map(|&day| WEEKDAYS[day]).collect::<Vec<_>>().join(",")
+                }
+                _ => name_numbers(item),
+            }
+        })
+        .collect();
+    items.join(",")
+}
+
+/// Replaces the weekday numbers in one item of the field with their names.
+fn name_numbers(item: &str) -> String {
+    let mut named = String::new();
+    let mut chars = item.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_digit() {
+            named.push(c);
+            continue;
+        }
+        let mut number = c.to_string();
+        while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+            number.push(digit);
+        }
+        let name = number.parse::<usize>().ok().and_then(|n| WEEKDAYS.get(n)).filter(|_| !named.ends_with('/'));
+        named.push_str(name.copied().unwrap_or(&number));
+    }
+    named
+}
+
+impl Timetable {
+    /// Accepts standard five-field cron, or six fields with seconds first in the parser's own
+    /// form, where weekdays run from 1 for Sunday to 7 for Saturday. `zone_name`
+    /// `None` follows the machine's time zone, read again on each call.
+    pub fn new(expression: &str, zone_name: Option<&str>) -> Result<Self, String> {
+        let expression = expression.trim();
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let full = match fields.as_slice() {
+            [minute, hour, day, month, weekday] => {
+                format!("0 {} {} {} {} {}", minute, hour, day, month, weekday_names(weekday))
+            }
+            _ => expression.to_string(),
+        };
+        let cron = cron::Schedule::from_str(&full)
+            .map_err(|e| format!("Invalid cron expression '{}': {}", expression, e))?;
+        Ok(Timetable { cron, zone: zone(zone_name)? })
+    }
+
+    /// What the clocks in the timetable's zone show now.
+    pub fn now_wall(&self) -> NaiveDateTime {
+        Utc::now().with_timezone(&self.zone).naive_local()
+    }
+
+    /// Runs for wall-clock times after `after`, in order. Each wall-clock time runs once,
+    /// and a run shifted onto another's instant is dropped.
+    pub fn runs_after(&self, after: NaiveDateTime) -> impl Iterator<Item = Run> + '_ {
+        let mut last: Option<DateTime<Utc>> = None;
+        // Evaluated as UTC so every wall-clock time exists exactly once
+        self.cron.after(&Utc.from_utc_datetime(&after)).filter_map(move |time| {
+            let wall = time.naive_utc();
+            let (at, adjustment) = resolve(self.zone, wall);
+            if last.is_some_and(|last| at <= last) {
+                return None;
+            }
+            last = Some(at);
+            Some(Run { wall, at, adjustment })
+        })
+    }
+
+    /// The latest run due by `now` after `after`. Runs missed while the machine slept, or
+    /// jumped over by a time-zone change, are folded into it rather than run one by one.
+    pub fn due(&self, after: NaiveDateTime, now: DateTime<Utc>) -> Option<Run> {
+        self.runs_after(after).take_while(|run| run.at <= now).last()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedRun {
+    /// Unix milliseconds.
+    pub at: i64,
+    /// Wall-clock time in the schedule's zone, e.g. `2026-03-29 03:00:00 CEST`.
+    pub local_time: String,
+    pub adjustment: Option<Adjustment>,
+}
+
+/// Lists when a cron schedule would run between `from` and `to` (Unix milliseconds) in
+/// time zone `tz` (the machine's when omitted), including DST adjustments.
+#[tauri::command]
+pub fn simulate_schedule(cron: String, tz: Option<String>, from: i64, to: i64) -> Result<Vec<SimulatedRun>, String> {
+    let timetable = Timetable::new(&cron, tz.as_deref())?;
+    let from = Utc.timestamp_millis_opt(from).single().ok_or("Invalid start time")?;
+    let to = Utc.timestamp_millis_opt(to).single().ok_or("Invalid end time")?;
+    let start = from.with_timezone(&timetable.zone).naive_local() - TimeDelta::seconds(1);
+    Ok(timetable
+        .runs_after(start)
+        .skip_while(|run| run.at < from)
+        .take_while(|run| run.at <= to)
+        .take(MAX_SIMULATED_RUNS)
+        .map(|run| SimulatedRun {
+            at: run.at.timestamp_millis(),
+            local_time: run.at.with_timezone(&timetable.zone).format("%Y-%m-%d %H:%M:%S %Z").to_string(),
+            adjustment: run.adjustment,
+        })
+        .collect())
+}
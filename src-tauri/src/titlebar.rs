@@ -0,0 +1,114 @@
+use tauri::plugin::{Builder as PluginBuilder, TauriPlugin};
+use tauri::{WebviewWindow, Wry};
+
+/// Draws the main window's titlebar: a drag region with the app name, a server status
+/// dot with a restart button, a marker while the computer is kept awake for jobs, and
+/// window buttons (traffic lights on the left on macOS,
+/// on the right elsewhere). The window has no native decorations, so everything here is
+/// page content injected into every page the main window loads.
+const TITLEBAR_SCRIPT: &str = r#"
+(function () {
+    if (window.__TAURI_INTERNALS__?.metadata?.currentWindow?.label !== 'main' || window.self !== window.top) return;
+    const HEIGHT = 32;
+    const mac = navigator.userAgent.includes('Mac');
+    const invoke = (cmd) => window.__TAURI_INTERNALS__.invoke(cmd);
+
+    function build() {
+        if (document.getElementById('__app-titlebar')) return;
+        const style = document.createElement('style');
+        style.textContent = `
+            html { padding-top: ${HEIGHT}px !important; box-sizing: border-box; }
+            html.__titlebar-hidden { padding-top: 0 !important; }
+            html.__titlebar-hidden #__app-titlebar { display: none; }
+            #__app-titlebar { position: fixed; top: 0; left: 0; right: 0; height: ${HEIGHT}px; z-index: 2147483647;
+                display: flex; align-items: center; gap: 8px; padding: 0 10px; box-sizing: border-box;
+                background: #111; color: #ddd; border-bottom: 1px solid #2a2a2a; user-select: none;
+                font: 12px -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif;
+                flex-direction: ${mac ? 'row' : 'row-reverse'}; }
+            #__app-titlebar .tb-title { flex: 1; text-align: center; pointer-events: none; }
+            #__app-titlebar .tb-group { display: flex; align-items: center; gap: 6px; }
+            #__app-titlebar button { border: none; background: none; color: inherit; cursor: pointer; padding: 0; font: inherit; }
+            #__app-titlebar .tb-light { width: 12px; height: 12px; border-radius: 50%; }
+            #__app-titlebar .tb-win { width: 28px; height: 24px; border-radius: 4px; }
+            #__app-titlebar .tb-win:hover { background: #333; }
+            #__app-titlebar .tb-win.tb-close:hover { background: #e81123; color: #fff; }
+            #__app-titlebar .tb-status { display: flex; align-items: center; gap: 6px; color: #999; }
+            #__app-titlebar .tb-dot { width: 8px; height: 8px; border-radius: 50%; background: #555; }
+            #__app-titlebar .tb-dot.running { background: #22c55e; }
+            #__app-titlebar .tb-dot.starting { background: #eab308; }
+            #__app-titlebar .tb-dot.stopped { background: #ef4444; }
+            #__app-titlebar .tb-restart:hover { color: #fff; }
+            #__app-titlebar .tb-awake[hidden] { display: none; }
+        `;
+        document.head.appendChild(style);
+
+        const bar = document.createElement('div');
+        bar.id = '__app-titlebar';
+        bar.setAttribute('data-tauri-drag-region', '');
+        const buttons = mac
+            ? `<button class="tb-light" style="background:#ff5f57" data-cmd="close_window" title="Close"></button>
+               <button class="tb-light" style="background:#febc2e" data-cmd="minimize_window" title="Minimize"></button>
+               <button class="tb-light" style="background:#28c840" data-cmd="toggle_maximize_window" title="Zoom"></button>`
+            : `<button class="tb-win tb-close" data-cmd="close_window" title="Close">&#x2715;</button>
+               <button class="tb-win" data-cmd="toggle_maximize_window" title="Maximize">&#x25A1;</button>
+               <button class="tb-win" data-cmd="minimize_window" title="Minimize">&#x2014;</button>`;
+        bar.innerHTML = `
+            <div class="tb-group">${buttons}</div>
+            <div class="tb-title" data-tauri-drag-region>Browser Automation</div>
+            <div class="tb-group tb-status">
+                <span class="tb-awake" title="Keeping the computer awake while jobs run" hidden>&#x2615;</span>
+                <span class="tb-dot"></span><span class="tb-label">Server</span>
+                <button class="tb-restart" data-cmd="request_server_restart" title="Restart server">&#x21bb;</button>
+            </div>`;
+        bar.addEventListener('click', (e) => {
+            const cmd = e.target.closest('button')?.dataset.cmd;
+            if (cmd) invoke(cmd).catch(err => console.warn('[TITLEBAR]', cmd, err));
+        });
+        document.body.appendChild(bar);
+        refresh();
+        setInterval(refresh, 3000);
+    }
+
+    const LABELS = { running: 'Server running', starting: 'Server starting', stopped: 'Server stopped' };
+    async function refresh() {
+        try {
+            const fullscreen = await window.__TAURI_INTERNALS__.invoke('plugin:window|is_fullscreen', { label: 'main' });
+            document.documentElement.classList.toggle('__titlebar-hidden', fullscreen);
+            const status = await invoke('get_server_status');
+            document.querySelector('#__app-titlebar .tb-dot').className = `tb-dot ${status.state}`;
+            document.querySelector('#__app-titlebar .tb-label').textContent = LABELS[status.state];
+            const power = await invoke('get_power_state');
+            document.querySelector('#__app-titlebar .tb-awake').hidden = !power.keeping_awake;
+        } catch (err) {
+            console.warn('[TITLEBAR] Status update failed:', err);
+        }
+    }
+
+    if (document.readyState === 'loading') {
+        document.addEventListener('DOMContentLoaded', build);
+    } else {
+        build();
+    }
+})();
+"#;
+
+pub fn plugin() -> TauriPlugin<Wry> {
+    PluginBuilder::new("titlebar").js_init_script(TITLEBAR_SCRIPT.to_string()).build()
+}
+
+#[tauri::command]
+pub fn minimize_window(window: WebviewWindow) -> Result<(), String> {
+    window.minimize().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn toggle_maximize_window(window: WebviewWindow) -> Result<(), String> {
+    let maximized = window.is_maximized().map_err(|e| e.to_string())?;
+    if maximized { window.unmaximize() } else { window.maximize() }.map_err(|e| e.to_string())
+}
+
+/// Goes through the regular close handling, so warm standby and server cleanup still apply.
+#[tauri::command]
+pub fn close_window(window: WebviewWindow) -> Result<(), String> {
+    window.close().map_err(|e| e.to_string())
+}
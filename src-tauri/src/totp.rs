@@ -0,0 +1,180 @@
+use ring::hmac;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Url};
+
+use crate::secrets;
+use crate::settings::{self, SettingsStore};
+
+/// Prompts ask for a current code as `${totp.<name>}`.
+pub const PLACEHOLDER_PREFIX: &str = "totp.";
+/// A code with less time than this left is skipped for the next one, so it doesn't expire
+/// while the automation types it.
+const MIN_REMAINING: Duration = Duration::from_secs(8);
+
+/// A TOTP seed, as an `otpauth://` URI would describe it.
+struct Seed {
+    key: Vec<u8>,
+    algorithm: hmac::Algorithm,
+    digits: u32,
+    period: u64,
+}
+
+fn seed_key(name: &str) -> String {
+    format!("totp:{}", name)
+}
+
+/// Keychain entries of every stored seed.
+pub fn seed_keys(names: &[String]) -> Vec<String> {
+    names.iter().map(|name| seed_key(name)).collect()
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// RFC 4648 base32, as authenticator apps show it: any case, spaces and padding ignored.
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '-' && *c != '=') {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes).filter(|bytes| !bytes.is_empty())
+}
+
+impl Seed {
+    /// A base32 secret, or an `otpauth://totp/...` URI (what setup QR codes contain).
+    fn parse(text: &str) -> Result<Seed, String> {
+        let text = text.trim();
+        let mut seed = Seed { key: Vec::new(), algorithm: hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, digits: 6, period: 30 };
+        if !text.to_ascii_lowercase().starts_with("otpauth://") {
+            seed.key = base32_decode(text).ok_or("The TOTP secret is not valid base32")?;
+            return Ok(seed);
+        }
+        let url = Url::parse(text).map_err(|e| format!("Invalid otpauth URI: {}", e))?;
+        if url.host_str() != Some("totp") {
+            return Err("Only time-based (totp) otpauth URIs are supported".to_string());
+        }
+        for (name, value) in url.query_pairs() {
+            match name.as_ref() {
+                "secret" => seed.key = base32_decode(&value).ok_or("The TOTP secret is not valid base32")?,
+                "algorithm" => {
+                    seed.algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+                        "SHA256" => hmac::HMAC_SHA256,
+                        "SHA512" => hmac::HMAC_SHA512,
+                        other => return Err(format!("Unsupported TOTP algorithm {}", other)),
+                    }
+                }
+                "digits" => seed.digits = value.parse().ok().filter(|d| (6..=8).contains(d)).ok_or("Invalid digits")?,
+                "period" => seed.period = value.parse().ok().filter(|p| *p > 0).ok_or("Invalid period")?,
+                _ => {}
+            }
+        }
+        if seed.key.is_empty() {
+            return Err("The otpauth URI has no secret".to_string());
+        }
+        Ok(seed)
+    }
+
+    /// Normalized, so the keychain holds one format.
+    fn to_uri(&self, name: &str) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+        let mut secret = String::new();
+        for chunk in self.key.chunks(5) {
+            let mut block = [0u8; 5];
+            block[..chunk.len()].copy_from_slice(chunk);
+            let value = block.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+            for i in 0..(chunk.len() * 8).div_ceil(5) {
+                secret.push(ALPHABET[(value >> (35 - i * 5)) as usize & 31] as char);
+            }
+        }
+        let algorithm = if self.algorithm == hmac::HMAC_SHA256 {
+            "SHA256"
+        } else if self.algorithm == hmac::HMAC_SHA512 {
+            "SHA512"
+        } else {
+            "SHA1"
+        };
+        format!(
+            "otpauth://totp/{}?secret={}&algorithm={}&digits={}&period={}",
+            name, secret, algorithm, self.digits, self.period
+        )
+    }
+
+    /// RFC 6238: HOTP (RFC 4226) over the number of periods since the epoch.
+    fn code(&self, unix_secs: u64) -> String {
+        let counter = unix_secs / self.period;
+        let tag = hmac::sign(&hmac::Key::new(self.algorithm, &self.key), &counter.to_be_bytes());
+        let tag = tag.as_ref();
+        let offset = (tag[tag.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([tag[offset], tag[offset + 1], tag[offset + 2], tag[offset + 3]]) & 0x7fff_ffff;
+        format!("{:0width$}", binary % 10u32.pow(self.digits), width = self.digits as usize)
+    }
+}
+
+fn unix_now() -> Duration {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+/// Whether a seed named `name` is stored.
+pub fn has_seed(name: &str) -> Result<bool, String> {
+    Ok(valid_name(name) && secrets::get(&seed_key(name))?.is_some())
+}
+
+/// The current code for the seed `name`, or `None` without one. Waits for the next code
+/// when the current one is about to expire.
+pub fn get_totp(name: &str) -> Result<Option<String>, String> {
+    if !valid_name(name) {
+        return Ok(None);
+    }
+    let Some(stored) = secrets::get(&seed_key(name))? else {
+        return Ok(None);
+    };
+    let seed = Seed::parse(&stored)?;
+    let now = unix_now();
+    let period = Duration::from_secs(seed.period);
+    let elapsed = Duration::from_secs(now.as_secs() % seed.period) + Duration::from_nanos(now.subsec_nanos().into());
+    let remaining = period - elapsed;
+    if remaining < MIN_REMAINING && period > MIN_REMAINING {
+        std::thread::sleep(remaining);
+    }
+    Ok(Some(seed.code(unix_now().as_secs())))
+}
+
+/// Stores a TOTP seed in the keychain under `name`, from its base32 secret or the
+/// `otpauth://` URI of its setup QR code. An empty seed removes it.
+#[tauri::command]
+pub fn set_totp_seed(app: AppHandle, name: String, seed: String) -> Result<(), String> {
+    if !valid_name(&name) {
+        return Err(format!("Invalid TOTP name '{}'", name));
+    }
+    let mut settings = app.state::<SettingsStore>().get();
+    if seed.trim().is_empty() {
+        secrets::delete(&seed_key(&name))?;
+        settings.totp_seeds.retain(|seed| *seed != name);
+    } else {
+        secrets::set(&seed_key(&name), &Seed::parse(&seed)?.to_uri(&name))?;
+        if !settings.totp_seeds.contains(&name) {
+            settings.totp_seeds.push(name);
+        }
+    }
+    settings::apply(&app, settings)
+}
+
+/// Names of the stored seeds, for `${totp.<name>}`. Seeds themselves are never returned.
+#[tauri::command]
+pub fn list_totp_seeds(app: AppHandle) -> Vec<String> {
+    app.state::<SettingsStore>().get().totp_seeds
+}
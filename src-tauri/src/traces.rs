@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use crate::history;
+
+/// Where a run's trace is kept, relative to its artifacts dir.
+const TRACE_PATH: &str = "trace.json";
+
+/// A timed part of a run. Times are milliseconds from the start of the run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Span {
+    pub id: u32,
+    pub parent_id: Option<u32>,
+    pub name: String,
+    /// What the span is, for colouring: `step`, `act`, `browser`, `captcha`, `pause`,
+    /// `credentials` or `capture`.
+    pub kind: String,
+    pub start_ms: f64,
+    pub duration_ms: f64,
+    /// Nesting level, 0 at the top; the row of a flame graph.
+    #[serde(default)]
+    pub depth: u32,
+    /// `ok` or `error`.
+    pub status: String,
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+}
+
+/// A run's spans, normalized: ordered by start, parents before their children, every
+/// span ended and within its parent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunTrace {
+    pub run_id: String,
+    /// Unix milliseconds.
+    pub started_at: i64,
+    pub duration_ms: f64,
+    pub status: String,
+    pub spans: Vec<Span>,
+}
+
+/// A span as the server sends it; unfinished spans have no duration.
+#[derive(Debug, Deserialize)]
+struct RawSpan {
+    id: u32,
+    parent_id: Option<u32>,
+    name: String,
+    kind: String,
+    start_ms: f64,
+    duration_ms: Option<f64>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    attributes: Map<String, Value>,
+}
+
+/// Sent by the server as `trace.ready` when a job finishes.
+#[derive(Debug, Deserialize)]
+pub struct TraceReady {
+    pub job_id: String,
+    pub started_at: i64,
+    pub duration_ms: f64,
+    pub status: String,
+    spans: Vec<RawSpan>,
+}
+
+fn normalize(ready: TraceReady) -> RunTrace {
+    let end = ready.duration_ms.max(0.0);
+    let mut raw = ready.spans;
+    raw.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms).then(a.id.cmp(&b.id)));
+    // Start and end of each span kept so far, for clamping its children
+    let mut bounds: HashMap<u32, (f64, f64, u32)> = HashMap::new();
+    let mut spans = Vec::with_capacity(raw.len());
+    for span in raw {
+        let parent = span.parent_id.and_then(|id| bounds.get(&id).map(|bounds| (id, *bounds)));
+        let (min, max, depth) = match parent {
+            Some((_, (start, finish, depth))) => (start, finish, depth + 1),
+            None => (0.0, end, 0),
+        };
+        let start = span.start_ms.clamp(min, max);
+        let finish = span.duration_ms.map_or(max, |duration| start + duration.max(0.0)).min(max);
+        bounds.insert(span.id, (start, finish, depth));
+        spans.push(Span {
+            id: span.id,
+            parent_id: parent.map(|(id, _)| id),
+            name: span.name,
+            kind: span.kind,
+            start_ms: start,
+            duration_ms: finish - start,
+            depth,
+            status: span.status.unwrap_or_else(|| "ok".to_string()),
+            attributes: span.attributes,
+        });
+    }
+    RunTrace { run_id: ready.job_id, started_at: ready.started_at, duration_ms: end, status: ready.status, spans }
+}
+
+fn store(app: &AppHandle, trace: &RunTrace) -> Result<(), String> {
+    let dir = history::artifacts_dir(app, &trace.run_id).ok_or_else(|| format!("Invalid run id '{}'", trace.run_id))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let contents = serde_json::to_vec(trace).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(TRACE_PATH), contents).map_err(|e| format!("Failed to save the trace: {}", e))
+}
+
+/// Stores a finished job's trace with its run, in the background.
+pub fn trace_ready(app: &AppHandle, ready: TraceReady) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let trace = normalize(ready);
+        match store(&app, &trace) {
+            Ok(()) => log::debug!("Trace of run {} stored ({} spans)", trace.run_id, trace.spans.len()),
+            Err(e) => log::warn!("Failed to store the trace of run {}: {}", trace.run_id, e),
+        }
+    });
+}
+
+/// A run's timing spans, for its timeline. Only runs the server traced have one.
+#[tauri::command]
+pub fn get_run_trace(app: AppHandle, run_id: String) -> Result<RunTrace, String> {
+    let path = history::artifacts_dir(&app, &run_id)
+        .ok_or_else(|| format!("Invalid run id '{}'", run_id))?
+        .join(TRACE_PATH);
+    let contents = match std::fs::read(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(format!("Run '{}' has no trace", run_id)),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    serde_json::from_slice(&contents).map_err(|e| format!("Invalid trace of run '{}': {}", run_id, e))
+}
@@ -0,0 +1,406 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tauri::{AppHandle, Manager};
+
+use crate::history::{self, History};
+use crate::normalize::{self, ValueKind};
+use crate::recipes;
+use crate::tasks::TaskFile;
+
+/// Where the output of a run is kept as the server reported it, relative to the run's
+/// artifacts dir, when its recipe post-processes it.
+const RAW_OUTPUT_PATH: &str = "raw/output.json";
+/// Problems named in the log for one run; the rest are counted.
+const LOGGED_PROBLEMS: usize = 5;
+/// Field a `join` marks records with.
+const CHANGE_FIELD: &str = "_change";
+/// Field holding the previous values of a changed record's fields.
+const PREVIOUS_FIELD: &str = "_previous";
+
+/// What a `join` does with the previous successful run's records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinMode {
+    /// Marks each record `new`, `changed` or `unchanged` in `_change`; changed records get
+    /// the fields' previous values in `_previous`.
+    #[default]
+    Annotate,
+    /// Keeps only the records that weren't there before.
+    NewOnly,
+    /// Adds the previous records that are gone now, so the output keeps everything seen.
+    Accumulate,
+}
+
+/// One step of a recipe's post-processing. The output's records are the items of an
+/// array, or the output itself when it is an object; records that aren't objects pass
+/// through unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    /// Renames fields, `from` → `to`. A `from` starting with `/` is a JSON pointer into the
+    /// record (`/details/price`), which moves the nested value up. With `drop_unmapped`,
+    /// fields not named are removed.
+    Map {
+        fields: BTreeMap<String, String>,
+        #[serde(default)]
+        drop_unmapped: bool,
+    },
+    /// Pulls part of a text field out with a regular expression: its first capture group,
+    /// or the whole match. Named groups each become a field of that name instead. Records
+    /// it doesn't match get `null`. With `kind`, the value is then normalized.
+    Extract {
+        field: String,
+        pattern: String,
+        /// Field the value goes to; the source field when unset.
+        #[serde(default)]
+        into: Option<String>,
+        #[serde(default)]
+        kind: Option<ValueKind>,
+        /// Unset uses the recipe's locale.
+        #[serde(default)]
+        locale: Option<String>,
+    },
+    /// Keeps the first record for each combination of `keys`; whole records are compared
+    /// when there are none.
+    Dedupe {
+        #[serde(default)]
+        keys: Vec<String>,
+    },
+    /// Matches records with the previous successful run's of the same recipe, on `keys`.
+    Join {
+        keys: Vec<String>,
+        /// Fields compared to tell a changed record; all of them when empty.
+        #[serde(default)]
+        fields: Vec<String>,
+        #[serde(default)]
+        mode: JoinMode,
+    },
+}
+
+/// What the transforms need besides the output.
+pub struct Context<'a> {
+    pub locale: Option<&'a str>,
+    /// The previous successful run's output, for `join`.
+    pub previous: Option<&'a Value>,
+}
+
+/// The result of running a pipeline, with what happened along the way.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransformPreview {
+    pub input: Value,
+    pub output: Value,
+    /// Records left after each transform, in order.
+    pub records: Vec<usize>,
+    /// Values that couldn't be converted and were left as they were.
+    pub problems: Vec<String>,
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))
+}
+
+/// Errors if a transform can't run, e.g. its pattern doesn't compile.
+pub fn check(transforms: &[Transform]) -> Result<(), String> {
+    for transform in transforms {
+        match transform {
+            Transform::Extract { pattern, locale, .. } => {
+                compile(pattern)?;
+                if let Some(locale) = locale {
+                    normalize::check_locale(locale)?;
+                }
+            }
+            Transform::Join { keys, .. } if keys.is_empty() => {
+                return Err("A join needs at least one key field".to_string());
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Splits an output into its records; `true` if it was an array.
+fn records(output: Value) -> (Vec<Value>, bool) {
+    match output {
+        Value::Array(items) => (items, true),
+        other => (vec![other], false),
+    }
+}
+
+fn objects(items: &mut [Value]) -> impl Iterator<Item = &mut Map<String, Value>> {
+    items.iter_mut().filter_map(Value::as_object_mut)
+}
+
+/// Identifies a record by its `keys`, or by all of it.
+fn key(record: &Value, keys: &[String]) -> String {
+    if keys.is_empty() {
+        return record.to_string();
+    }
+    let values: Vec<&Value> = keys.iter().map(|key| record.get(key).unwrap_or(&Value::Null)).collect();
+    serde_json::to_string(&values).unwrap_or_default()
+}
+
+fn map(record: &mut Map<String, Value>, fields: &BTreeMap<String, String>, drop_unmapped: bool) {
+    let source = Value::Object(std::mem::take(record));
+    let mut mapped = Map::new();
+    for (from, to) in fields {
+        let value = if from.starts_with('/') { source.pointer(from) } else { source.get(from) };
+        mapped.insert(to.clone(), value.cloned().unwrap_or(Value::Null));
+    }
+    let Value::Object(source) = source else {
+        unreachable!("records are objects");
+    };
+    if !drop_unmapped {
+        for (name, value) in source {
+            if !fields.contains_key(&name) && !mapped.contains_key(&name) {
+                mapped.insert(name, value);
+            }
+        }
+    }
+    *record = mapped;
+}
+
+struct Extraction<'a> {
+    regex: Regex,
+    field: &'a str,
+    into: &'a str,
+    kind: Option<ValueKind>,
+    locale: &'a str,
+}
+
+impl Extraction<'_> {
+    fn convert(&self, text: &str, field: &str, problems: &mut Vec<String>) -> Value {
+        let Some(kind) = self.kind else {
+            return Value::String(text.to_string());
+        };
+        normalize::normalize_value(text, kind, self.locale).unwrap_or_else(|e| {
+            problems.push(format!("{}: {}", field, e));
+            Value::String(text.to_string())
+        })
+    }
+
+    fn apply(&self, record: &mut Map<String, Value>, problems: &mut Vec<String>) {
+        let Some(Value::String(text)) = record.get(self.field) else {
+            return;
+        };
+        let captures = self.regex.captures(text);
+        let named: Vec<&str> = self.regex.capture_names().flatten().collect();
+        let mut values = Vec::new();
+        if named.is_empty() {
+            let found = captures.and_then(|captures| captures.get(1).or_else(|| captures.get(0)));
+            let value = found.map_or(Value::Null, |found| self.convert(found.as_str(), self.into, problems));
+            values.push((self.into.to_string(), value));
+        } else {
+            for name in named {
+                let found = captures.as_ref().and_then(|captures| captures.name(name));
+                let value = found.map_or(Value::Null, |found| self.convert(found.as_str(), name, problems));
+                values.push((name.to_string(), value));
+            }
+        }
+        record.extend(values);
+    }
+}
+
+fn join(records: Vec<Value>, previous: &[Value], keys: &[String], fields: &[String], mode: JoinMode) -> Vec<Value> {
+    let before: HashMap<String, &Value> = previous.iter().map(|record| (key(record, keys), record)).collect();
+    match mode {
+        JoinMode::NewOnly => records.into_iter().filter(|record| !before.contains_key(&key(record, keys))).collect(),
+        JoinMode::Accumulate => {
+            let now: HashSet<String> = records.iter().map(|record| key(record, keys)).collect();
+            let gone = previous.iter().filter(|record| !now.contains(&key(record, keys))).cloned();
+            records.into_iter().chain(gone).collect()
+        }
+        JoinMode::Annotate => records
+            .into_iter()
+            .map(|mut record| {
+                let found = before.get(&key(&record, keys)).and_then(|previous| previous.as_object());
+                let Some(fields_now) = record.as_object_mut() else {
+                    return record;
+                };
+                let Some(previous) = found else {
+                    fields_now.insert(CHANGE_FIELD.to_string(), "new".into());
+                    return record;
+                };
+                // The previous run's records carry annotations of their own
+                let compared = |name: &String| {
+                    name != CHANGE_FIELD && name != PREVIOUS_FIELD && (fields.is_empty() || fields.contains(name))
+                };
+                let names: Vec<&String> =
+                    fields_now.keys().chain(previous.keys()).filter(|name| compared(name)).collect();
+                let mut changed = Map::new();
+                for name in names {
+                    let old = previous.get(name).unwrap_or(&Value::Null);
+                    if fields_now.get(name).unwrap_or(&Value::Null) != old && !changed.contains_key(name) {
+                        changed.insert(name.clone(), old.clone());
+                    }
+                }
+                let change = if changed.is_empty() { "unchanged" } else { "changed" };
+                fields_now.insert(CHANGE_FIELD.to_string(), change.into());
+                if !changed.is_empty() {
+                    fields_now.insert(PREVIOUS_FIELD.to_string(), Value::Object(changed));
+                }
+                record
+            })
+            .collect(),
+    }
+}
+
+/// Runs the transforms over the output in order. Values that can't be converted are left
+/// as they were and named in the preview's problems.
+pub fn run(output: Value, transforms: &[Transform], context: &Context) -> Result<TransformPreview, String> {
+    let input = output.clone();
+    let (mut items, array) = records(output);
+    let mut counts = Vec::with_capacity(transforms.len());
+    let mut problems = Vec::new();
+    for transform in transforms {
+        match transform {
+            Transform::Map { fields, drop_unmapped } => {
+                objects(&mut items).for_each(|record| map(record, fields, *drop_unmapped));
+            }
+            Transform::Extract { field, pattern, into, kind, locale } => {
+                let extraction = Extraction {
+                    regex: compile(pattern)?,
+                    field,
+                    into: into.as_deref().unwrap_or(field),
+                    kind: *kind,
+                    locale: locale.as_deref().or(context.locale).unwrap_or(normalize::DEFAULT_LOCALE),
+                };
+                objects(&mut items).for_each(|record| extraction.apply(record, &mut problems));
+            }
+            Transform::Dedupe { keys } => {
+                let mut seen = HashSet::new();
+                items.retain(|record| seen.insert(key(record, keys)));
+            }
+            Transform::Join { keys, fields, mode } => {
+                let previous = context.previous.cloned().map(|previous| records(previous).0).unwrap_or_default();
+                items = join(items, &previous, keys, fields, *mode);
+            }
+        }
+        counts.push(items.len());
+    }
+    let output = match (array, items.len()) {
+        (false, 1) => items.pop().unwrap_or_default(),
+        _ => Value::Array(items),
+    };
+    Ok(TransformPreview { input, output, records: counts, problems })
+}
+
+/// The last successful output of the recipe, before `run_id` if given, for joins.
+fn previous_output(app: &AppHandle, recipe_id: &str, run_id: Option<&str>) -> Option<Value> {
+    match app.state::<History>().previous_successful_recipe_run(recipe_id, run_id) {
+        Ok(previous) => previous.and_then(|run| run.output),
+        Err(e) => {
+            log::warn!("Previous output of recipe '{}' unavailable: {}", recipe_id, e);
+            None
+        }
+    }
+}
+
+fn keep_raw(app: &AppHandle, run_id: &str, output: &Value) -> Result<(), String> {
+    let path = history::artifacts_dir(app, run_id)
+        .ok_or_else(|| format!("Invalid run id '{}'", run_id))?
+        .join(RAW_OUTPUT_PATH);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents = serde_json::to_vec(output).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Normalizes the output with the recipe's rules, then runs its transforms.
+fn process(
+    app: &AppHandle,
+    task: &TaskFile,
+    recipe_id: &str,
+    run_id: Option<&str>,
+    mut output: Value,
+) -> Result<TransformPreview, String> {
+    let input = output.clone();
+    let mut problems = normalize::apply(&mut output, &task.normalize, task.locale.as_deref());
+    let previous = task
+        .transforms
+        .iter()
+        .any(|transform| matches!(transform, Transform::Join { .. }))
+        .then(|| previous_output(app, recipe_id, run_id))
+        .flatten();
+    let context = Context { locale: task.locale.as_deref(), previous: previous.as_ref() };
+    let mut preview = run(output, &task.transforms, &context)?;
+    problems.append(&mut preview.problems);
+    Ok(TransformPreview { input, problems, ..preview })
+}
+
+/// A recipe run's output as it is stored: normalized and transformed as the recipe says.
+/// The server's output is kept with the run's artifacts, so the recipe's pipeline can be
+/// tried on it again. If the pipeline fails the output is stored as it came.
+pub fn recipe_output(app: &AppHandle, run_id: &str, recipe_id: Option<&str>, output: Value) -> Value {
+    let Some(recipe_id) = recipe_id else {
+        return output;
+    };
+    let Ok(recipe) = recipes::load(app, recipe_id) else {
+        return output;
+    };
+    if recipe.task.normalize.is_empty() && recipe.task.transforms.is_empty() {
+        return output;
+    }
+    if let Err(e) = keep_raw(app, run_id, &output) {
+        log::warn!("Raw output of run {} not kept: {}", run_id, e);
+    }
+    match process(app, &recipe.task, recipe_id, Some(run_id), output.clone()) {
+        Ok(processed) => {
+            let problems = &processed.problems;
+            if !problems.is_empty() {
+                let mut named = problems.iter().take(LOGGED_PROBLEMS).cloned().collect::<Vec<_>>().join("; ");
+                if problems.len() > LOGGED_PROBLEMS {
+                    named.push_str(&format!(" and {} more", problems.len() - LOGGED_PROBLEMS));
+                }
+                log::warn!("Run {}: {} values left as scraped: {}", run_id, problems.len(), named);
+            }
+            processed.output
+        }
+        Err(e) => {
+            log::error!("Post-processing run {} failed, storing its output as it came: {}", run_id, e);
+            output
+        }
+    }
+}
+
+/// Runs a recipe's normalization and `transforms` over the output of one of its runs (as
+/// the server reported it), or over `output` when given, without storing anything. For
+/// trying transforms out while writing them.
+#[tauri::command]
+pub async fn preview_transform(
+    app: AppHandle,
+    recipe_id: String,
+    transforms: Vec<Transform>,
+    run_id: Option<String>,
+    output: Option<Value>,
+) -> Result<TransformPreview, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        check(&transforms)?;
+        let mut task = recipes::load(&app, &recipe_id)?.task;
+        task.transforms = transforms;
+        let output = match (output, run_id.as_deref()) {
+            (Some(output), _) => output,
+            (None, Some(run_id)) => {
+                let raw = history::artifacts_dir(&app, run_id)
+                    .map(|dir| dir.join(RAW_OUTPUT_PATH))
+                    .and_then(|path| std::fs::read(path).ok())
+                    .and_then(|contents| serde_json::from_slice(&contents).ok());
+                match raw {
+                    Some(raw) => raw,
+                    None => app
+                        .state::<History>()
+                        .get(run_id)?
+                        .ok_or_else(|| format!("Run '{}' does not exist", run_id))?
+                        .output
+                        .ok_or_else(|| format!("Run '{}' has no output", run_id))?,
+                }
+            }
+            (None, None) => return Err("Give a run or an output to try the transforms on".to_string()),
+        };
+        process(&app, &task, &recipe_id, run_id.as_deref(), output)
+    })
+    .await
+    .map_err(|e| format!("Previewing the transforms failed: {}", e))?
+}
@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+use crate::settings::Settings;
+use crate::{packaging, purge, secrets, totp, variables};
+
+/// Matches `identifier` in tauri.conf.json; Tauri names the app's directories after it.
+pub const IDENTIFIER: &str = "com.browserautomation.desktop";
+/// Matches `productName`; the autostart entry is registered under it.
+const PRODUCT_NAME: &str = "BrowserAutomation";
+/// MIME type of task files on Linux.
+const TASK_MIME_TYPE: &str = "application/x-browser-automation-task";
+
+type Step = (&'static str, fn() -> Result<(), String>);
+
+/// Where the Linux tray icon is written. Tray icons otherwise go to a directory shared
+/// with other apps, which an uninstall could not tidy up.
+pub fn tray_icon_dir() -> PathBuf {
+    packaging::runtime_dir().join(format!("{}-tray", IDENTIFIER))
+}
+
+/// The directories Tauri resolves for the app, worked out without a running app.
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = [dirs::data_dir(), dirs::data_local_dir(), dirs::config_dir(), dirs::cache_dir()]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(IDENTIFIER))
+        .collect();
+    #[cfg(target_os = "macos")]
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join("Library/Logs").join(IDENTIFIER));
+        dirs.push(home.join("Library/WebKit").join(IDENTIFIER));
+    }
+    dirs.extend(packaging::browser_profile_dir());
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+fn remove_autostart() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let autolaunch = auto_launch::AutoLaunchBuilder::new()
+        .set_app_name(PRODUCT_NAME)
+        .set_app_path(&exe.display().to_string())
+        .set_use_launch_agent(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    if autolaunch.is_enabled().unwrap_or(true) {
+        autolaunch.disable().map_err(|e| format!("Failed to remove launch at login: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Drops the app's document types (and any URL schemes) from Launch Services.
+#[cfg(target_os = "macos")]
+fn remove_file_associations() -> Result<(), String> {
+    const LSREGISTER: &str = "/System/Library/Frameworks/CoreServices.framework/Frameworks/\
+                              LaunchServices.framework/Support/lsregister";
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let exe = exe.to_string_lossy();
+    let Some((bundle, _)) = exe.split_once(".app/") else {
+        return Ok(());
+    };
+    let status = std::process::Command::new(LSREGISTER)
+        .args(["-u", &format!("{}.app", bundle)])
+        .status()
+        .map_err(|e| format!("Failed to run lsregister: {}", e))?;
+    if !status.success() {
+        return Err(format!("lsregister failed: {}", status));
+    }
+    Ok(())
+}
+
+/// Removes the per-user `.nova` association; the installer owns the machine-wide one.
+#[cfg(target_os = "windows")]
+fn remove_file_associations() -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    use windows::Win32::UI::Shell::{SHChangeNotify, SHCNE_ASSOCCHANGED, SHCNF_IDLIST};
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let exe_name = exe.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let keys = [
+        format!(r"HKCU\Software\Classes\.{}", crate::tasks::EXTENSION),
+        format!(r"HKCU\Software\Classes\Applications\{}", exe_name),
+    ];
+    for key in keys {
+        // Fails when the key isn't there, which is fine
+        let _ = std::process::Command::new("reg")
+            .args(["delete", &key, "/f"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .status();
+    }
+    unsafe { SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, None, None) };
+    Ok(())
+}
+
+/// Removes the user's default-application choices for task files; the package manager
+/// owns the desktop entry and MIME type themselves.
+#[cfg(target_os = "linux")]
+fn remove_file_associations() -> Result<(), String> {
+    let lists = [dirs::config_dir(), dirs::data_dir().map(|dir| dir.join("applications"))];
+    for path in lists.into_iter().flatten().map(|dir| dir.join("mimeapps.list")) {
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let kept: Vec<&str> =
+            contents.lines().filter(|line| !line.starts_with(&format!("{}=", TASK_MIME_TYPE))).collect();
+        if kept.len() < contents.lines().count() {
+            std::fs::write(&path, kept.join("\n") + "\n")
+                .map_err(|e| format!("Failed to update {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn remove_file_associations() -> Result<(), String> {
+    Ok(())
+}
+
+/// The tray icon files and the running-instance marker left in temp dirs.
+fn remove_runtime_files() -> Result<(), String> {
+    let dir = tray_icon_dir();
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove {}: {}", dir.display(), e))?;
+    }
+    let prefix = format!("{}-", IDENTIFIER);
+    for entry in std::fs::read_dir(packaging::runtime_dir()).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(&prefix) && name.ends_with(".instance") {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Keychain entries and every data directory, as `purge_all_user_data` would.
+fn remove_data() -> Result<(), String> {
+    let settings = dirs::config_dir()
+        .and_then(|dir| std::fs::read_to_string(dir.join(IDENTIFIER).join("settings.json")).ok())
+        .and_then(|contents| Settings::from_file_contents(&contents).ok())
+        .unwrap_or_default();
+    let mut variable_keys = variables::secret_keys(&settings.variables);
+    variable_keys.extend(totp::seed_keys(&settings.totp_seeds));
+    let mut failed: Vec<String> = purge::KEYCHAIN_KEYS
+        .iter()
+        .copied()
+        .chain(variable_keys.iter().map(String::as_str))
+        .filter_map(|key| secrets::delete(key).err())
+        .collect();
+    for dir in data_dirs() {
+        purge::shred(&dir, &[], &mut failed);
+    }
+    if !failed.is_empty() {
+        return Err(format!("Some data could not be removed:\n{}", failed.join("\n")));
+    }
+    Ok(())
+}
+
+/// `--uninstall-cleanup`, run by uninstallers or by hand: undoes what the app registered
+/// with the OS (launch at login, file associations, tray and instance files) and, with
+/// `--remove-data`, deletes the user's data too. Runs without a window and returns the
+/// process exit code.
+pub fn run(with_data: bool) -> i32 {
+    let mut steps: Vec<Step> = vec![
+        ("launch at login", remove_autostart),
+        ("file associations", remove_file_associations),
+        ("runtime files", remove_runtime_files),
+    ];
+    if with_data {
+        steps.push(("user data", remove_data));
+    }
+    let mut code = 0;
+    for (name, step) in steps {
+        if let Err(e) = step() {
+            eprintln!("Cleanup of {} failed: {}", name, e);
+            code = 1;
+        }
+    }
+    code
+}
@@ -0,0 +1,291 @@
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::events::JobEvent;
+use crate::plugins::{PluginContext, PluginRegistry, ShellPlugin};
+use crate::settings::SettingsStore;
+use crate::{artifact_protocol, history, secrets};
+
+pub const CREDENTIALS_KEY: &str = "upload-credentials";
+
+/// Attempts per file before the upload counts as failed.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Emit a progress event every this many bytes.
+const PROGRESS_EVERY: u64 = 1024 * 1024;
+
+/// Where artifacts are uploaded. The endpoint and keys live in the keychain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadSettings {
+    pub bucket: String,
+    /// Prepended to `<run_id>/<file>` to form each object key.
+    pub prefix: String,
+    /// Address the bucket as `<endpoint>/<bucket>` rather than `<bucket>.<endpoint>`, as
+    /// most self-hosted S3-compatible servers expect.
+    pub path_style: bool,
+}
+
+impl Default for UploadSettings {
+    fn default() -> Self {
+        UploadSettings { bucket: String::new(), prefix: "runs/".to_string(), path_style: true }
+    }
+}
+
+/// Access to an S3-compatible service, kept in the keychain as JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadCredentials {
+    /// e.g. `https://s3.eu-central-1.amazonaws.com` or a MinIO server.
+    pub endpoint: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+/// Sent as `upload-progress` while a run's artifacts are uploaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgress {
+    pub run_id: String,
+    /// Artifact being uploaded, relative to the run's dir.
+    pub file: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+fn credentials() -> Result<UploadCredentials, String> {
+    let json = secrets::get(CREDENTIALS_KEY)?.ok_or("No upload credentials are set")?;
+    serde_json::from_str(&json).map_err(|e| format!("Stored upload credentials are damaged: {}", e))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data.as_bytes()).as_ref().to_vec()
+}
+
+/// SigV4 URI encoding of a path; `/` is kept.
+fn uri_encode(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// The URL and headers of a signed `PUT` of `key`. The body is sent unsigned so large
+/// files can be streamed instead of hashed up front.
+fn sign_put(
+    creds: &UploadCredentials,
+    settings: &UploadSettings,
+    key: &str,
+) -> Result<(String, Vec<(String, String)>), String> {
+    let endpoint = creds.endpoint.trim_end_matches('/');
+    let (scheme, host) =
+        endpoint.split_once("://").ok_or_else(|| format!("Invalid upload endpoint '{}'", endpoint))?;
+    let (host, path) = if settings.path_style {
+        (host.to_string(), uri_encode(&format!("/{}/{}", settings.bucket, key)))
+    } else {
+        (format!("{}.{}", settings.bucket, host), uri_encode(&format!("/{}", key)))
+    };
+
+    let date_time = history::compact_utc(SystemTime::now());
+    let date = &date_time[..8];
+    let payload = "UNSIGNED-PAYLOAD";
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        path, host, payload, date_time, signed_headers, payload
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, creds.region);
+    let canonical_hash = hex(&Sha256::digest(canonical.as_bytes()));
+    let to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", date_time, scope, canonical_hash);
+    let date_key = hmac_sha256(format!("AWS4{}", creds.secret_access_key).as_bytes(), date);
+    let signing_key =
+        [creds.region.as_str(), "s3", "aws4_request"].iter().fold(date_key, |key, part| hmac_sha256(&key, part));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        creds.access_key_id,
+        scope,
+        signed_headers,
+        hex(&hmac_sha256(&signing_key, &to_sign))
+    );
+    let headers = vec![
+        ("x-amz-content-sha256".to_string(), payload.to_string()),
+        ("x-amz-date".to_string(), date_time),
+        ("Authorization".to_string(), authorization),
+    ];
+    Ok((format!("{}://{}{}", scheme, host, path), headers))
+}
+
+/// A file being uploaded, reporting progress as it is read.
+struct Progress<'a> {
+    inner: File,
+    app: &'a AppHandle,
+    info: UploadProgress,
+    last_emitted: u64,
+}
+
+impl Read for Progress<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.info.bytes_sent += read as u64;
+        if self.info.bytes_sent - self.last_emitted >= PROGRESS_EVERY {
+            self.last_emitted = self.info.bytes_sent;
+            let _ = self.app.emit("upload-progress", &self.info);
+        }
+        Ok(read)
+    }
+}
+
+fn put_file(
+    app: &AppHandle,
+    creds: &UploadCredentials,
+    settings: &UploadSettings,
+    run_id: &str,
+    path: &Path,
+    name: &str,
+) -> Result<(), String> {
+    let key = format!("{}{}/{}", settings.prefix, run_id, name);
+    let total_bytes = std::fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(15))
+        .timeout_read(Duration::from_secs(60))
+        .build();
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        if attempt > 1 {
+            std::thread::sleep(Duration::from_secs(2u64.pow(attempt - 1)));
+            log::info!("Retrying upload of {} (attempt {}): {}", name, attempt, last_error);
+        }
+        let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let (url, headers) = sign_put(creds, settings, &key)?;
+        let mut request = agent
+            .put(&url)
+            .set("Content-Length", &total_bytes.to_string())
+            .set("Content-Type", artifact_protocol::content_type(path));
+        for (header, value) in &headers {
+            request = request.set(header, value);
+        }
+        let info = UploadProgress {
+            run_id: run_id.to_string(),
+            file: name.to_string(),
+            bytes_sent: 0,
+            total_bytes,
+            done: false,
+            error: None,
+        };
+        match request.send(Progress { inner: file, app, info, last_emitted: 0 }) {
+            Ok(_) => return Ok(()),
+            // Only server-side trouble and throttling are worth another try
+            Err(ureq::Error::Status(code, response)) if code < 500 && code != 429 => {
+                let body = response.into_string().unwrap_or_default();
+                return Err(format!("Upload of {} rejected ({}): {}", name, code, body.trim()));
+            }
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(format!("Upload of {} failed after {} attempts: {}", name, MAX_ATTEMPTS, last_error))
+}
+
+/// Uploads a run's artifacts, or just those named in `files`, and returns how many were
+/// uploaded. Stops at the first file that cannot be uploaded.
+pub fn upload_run(app: &AppHandle, run_id: &str, files: Option<&[String]>) -> Result<usize, String> {
+    let settings = app.state::<SettingsStore>().get().uploads;
+    if settings.bucket.is_empty() {
+        return Err("No upload bucket is set".to_string());
+    }
+    let creds = credentials()?;
+    let dir = history::artifacts_dir(app, run_id).ok_or_else(|| format!("Invalid run id '{}'", run_id))?;
+    if !dir.is_dir() {
+        return Err(format!("Run '{}' has no artifacts", run_id));
+    }
+    let mut artifacts = history::artifact_files(&dir)?;
+    if let Some(files) = files {
+        if let Some(missing) = files.iter().find(|file| !artifacts.iter().any(|(_, name)| name == *file)) {
+            return Err(format!("Run '{}' has no artifact '{}'", run_id, missing));
+        }
+        artifacts.retain(|(_, name)| files.contains(name));
+    }
+
+    for (path, name) in &artifacts {
+        let result = put_file(app, &creds, &settings, run_id, path, name);
+        let total_bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let info = UploadProgress {
+            run_id: run_id.to_string(),
+            file: name.clone(),
+            bytes_sent: if result.is_ok() { total_bytes } else { 0 },
+            total_bytes,
+            done: true,
+            error: result.as_ref().err().cloned(),
+        };
+        let _ = app.emit("upload-progress", info);
+        result?;
+    }
+    log::info!("Uploaded {} artifacts of run {} to bucket {}", artifacts.len(), run_id, settings.bucket);
+    Ok(artifacts.len())
+}
+
+/// Built-in plugin that uploads the artifacts of schedules that opted in.
+struct UploadPlugin;
+
+impl ShellPlugin for UploadPlugin {
+    fn name(&self) -> &str {
+        "artifact-uploads"
+    }
+
+    fn on_job_finished(&self, ctx: &PluginContext, job: &JobEvent) {
+        let Some(schedule_id) = job.schedule_id.as_deref() else {
+            return;
+        };
+        let settings = ctx.app.state::<SettingsStore>().get();
+        let Some(schedule) = settings.schedules.iter().find(|s| s.id == schedule_id && s.upload_results) else {
+            return;
+        };
+        let has_artifacts = history::artifacts_dir(ctx.app, &job.job_id).is_some_and(|dir| dir.is_dir());
+        if !has_artifacts {
+            return;
+        }
+        if let Err(e) = upload_run(ctx.app, &job.job_id, None) {
+            log::error!("Results of schedule '{}' not uploaded: {}", schedule.name, e);
+        }
+    }
+}
+
+pub fn register(app: &AppHandle) {
+    app.state::<PluginRegistry>()
+        .register(Arc::new(UploadPlugin), "Uploads run artifacts for schedules that opt in");
+}
+
+/// Stores the endpoint and keys in the keychain. `None` removes them.
+#[tauri::command]
+pub fn set_upload_credentials(credentials: Option<UploadCredentials>) -> Result<(), String> {
+    match credentials {
+        Some(credentials) => {
+            secrets::set(CREDENTIALS_KEY, &serde_json::to_string(&credentials).map_err(|e| e.to_string())?)
+        }
+        None => secrets::delete(CREDENTIALS_KEY),
+    }
+}
+
+/// Uploads a run's artifacts (all, or the relative paths in `files`) to the configured
+/// bucket. Progress is reported as `upload-progress` events.
+#[tauri::command]
+pub async fn upload_artifacts(app: AppHandle, run_id: String, files: Option<Vec<String>>) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || upload_run(&app, &run_id, files.as_deref()))
+        .await
+        .map_err(|e| format!("Upload failed: {}", e))?
+}
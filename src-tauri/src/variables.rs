@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Manager};
+
+use crate::credentials::{self, Credential};
+use crate::{secrets, totp};
+use crate::settings::{self, SettingsStore};
+
+/// Profile whose variables apply when the active profile doesn't set them.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Values for `${name}` placeholders in prompts and recipes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VariableProfile {
+    pub values: BTreeMap<String, String>,
+    /// Variables whose values are kept in the keychain, never in settings or recipes.
+    pub secrets: Vec<String>,
+}
+
+/// Variable sets per environment (e.g. `staging`, `production`). The active profile
+/// overrides the `default` one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VariableSettings {
+    pub active_profile: String,
+    pub profiles: BTreeMap<String, VariableProfile>,
+}
+
+impl Default for VariableSettings {
+    fn default() -> Self {
+        VariableSettings { active_profile: DEFAULT_PROFILE.to_string(), profiles: BTreeMap::new() }
+    }
+}
+
+fn secret_key(profile: &str, name: &str) -> String {
+    format!("variable:{}:{}", profile, name)
+}
+
+/// Keychain entries of every secret variable in `variables`.
+pub fn secret_keys(variables: &VariableSettings) -> Vec<String> {
+    let profiles = variables.profiles.iter();
+    profiles.flat_map(|(profile, values)| values.secrets.iter().map(|name| secret_key(profile, name))).collect()
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// A placeholder or literal text in a template. `$${name}` is a literal `${name}`.
+enum Part<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+    /// The `${name}` of a `$${name}`.
+    Escaped(&'a str),
+}
+
+fn parse(template: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        if rest[..start].ends_with('$') {
+            parts.push(Part::Text(&rest[..start - 1]));
+            parts.push(Part::Escaped(&rest[start..=end]));
+        } else {
+            let name = rest[start + 2..end].trim();
+            if valid_name(name) {
+                parts.push(Part::Text(&rest[..start]));
+                parts.push(Part::Variable(name));
+            } else {
+                parts.push(Part::Text(&rest[..=end]));
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    parts.push(Part::Text(rest));
+    parts
+}
+
+/// Where a variable's value comes from.
+enum Source {
+    Plain(String),
+    /// Kept out of prompts until a job's server asks for it.
+    Secret(Credential),
+}
+
+fn find(variables: &VariableSettings, name: &str) -> Result<Option<Source>, String> {
+    if let Some(seed) = name.strip_prefix(totp::PLACEHOLDER_PREFIX) {
+        return Ok(totp::has_seed(seed)?.then(|| Source::Secret(Credential::Totp(seed.to_string()))));
+    }
+    for profile in [variables.active_profile.as_str(), DEFAULT_PROFILE] {
+        let Some(set) = variables.profiles.get(profile) else {
+            continue;
+        };
+        if set.secrets.iter().any(|secret| secret == name) {
+            let key = secret_key(profile, name);
+            if secrets::get(&key)?.is_some() {
+                return Ok(Some(Source::Secret(Credential::Keychain(key))));
+            }
+        } else if let Some(value) = set.values.get(name) {
+            return Ok(Some(Source::Plain(value.clone())));
+        }
+    }
+    Ok(None)
+}
+
+fn lookup(variables: &VariableSettings, name: &str) -> Result<Option<String>, String> {
+    match find(variables, name)? {
+        Some(Source::Plain(value)) => Ok(Some(value)),
+        Some(Source::Secret(Credential::Keychain(key))) => secrets::get(&key),
+        Some(Source::Secret(Credential::Totp(seed))) => totp::get_totp(&seed),
+        None => Ok(None),
+    }
+}
+
+fn missing_error(variables: &VariableSettings, missing: &[&str]) -> String {
+    format!(
+        "No value for {} in profile '{}'",
+        missing.iter().map(|name| format!("${{{}}}", name)).collect::<Vec<_>>().join(", "),
+        variables.active_profile
+    )
+}
+
+/// Replaces `${name}` placeholders with the active profile's values, falling back to the
+/// default profile, and `${totp.<name>}` with a current TOTP code. Fails naming every variable without a value.
+pub fn resolve(app: &AppHandle, template: &str) -> Result<String, String> {
+    let variables = app.state::<SettingsStore>().get().variables;
+    let mut resolved = String::with_capacity(template.len());
+    let mut missing: Vec<&str> = Vec::new();
+    for part in parse(template) {
+        match part {
+            Part::Text(text) | Part::Escaped(text) => resolved.push_str(text),
+            Part::Variable(name) => match lookup(&variables, name)? {
+                Some(value) => resolved.push_str(&value),
+                None if !missing.contains(&name) => missing.push(name),
+                None => {}
+            },
+        }
+    }
+    if !missing.is_empty() {
+        return Err(missing_error(&variables, &missing));
+    }
+    Ok(resolved)
+}
+
+/// Like `resolve`, but for a job the server runs: secret variables and TOTP codes stay
+/// placeholders, each with a one-time token (by name) the server redeems over the control
+/// channel for the value. Without secrets the template is resolved fully and no tokens are
+/// minted.
+pub fn resolve_for_job(
+    app: &AppHandle,
+    template: &str,
+    job_id: &str,
+) -> Result<(String, BTreeMap<String, String>), String> {
+    let variables = app.state::<SettingsStore>().get().variables;
+    let mut plain = BTreeMap::new();
+    let mut secret = BTreeMap::new();
+    let mut missing: Vec<&str> = Vec::new();
+    for part in parse(template) {
+        let Part::Variable(name) = part else {
+            continue;
+        };
+        match find(&variables, name)? {
+            Some(Source::Plain(value)) => {
+                plain.insert(name.to_string(), value);
+            }
+            Some(Source::Secret(credential)) => {
+                secret.insert(name.to_string(), credential);
+            }
+            None if !missing.contains(&name) => missing.push(name),
+            None => {}
+        }
+    }
+    if !missing.is_empty() {
+        return Err(missing_error(&variables, &missing));
+    }
+    if secret.is_empty() {
+        return resolve(app, template).map(|resolved| (resolved, BTreeMap::new()));
+    }
+    let mut tokens = BTreeMap::new();
+    for (name, credential) in secret {
+        let token = credentials::mint(job_id, &name, credential)?;
+        tokens.insert(name, token);
+    }
+    Ok((substitute(template, &plain), tokens))
+}
+
+/// Fills in the placeholders `values` has, leaving the rest (and escapes) for `resolve`.
+/// Values are inserted literally, even if they contain placeholders.
+pub fn substitute(template: &str, values: &BTreeMap<String, String>) -> String {
+    let mut substituted = String::with_capacity(template.len());
+    for part in parse(template) {
+        match part {
+            Part::Text(text) => substituted.push_str(text),
+            Part::Escaped(text) => {
+                substituted.push('$');
+                substituted.push_str(text);
+            }
+            Part::Variable(name) => match values.get(name) {
+                Some(value) => substituted.push_str(&value.replace("${", "$${")),
+                None => substituted.push_str(&format!("${{{}}}", name)),
+            },
+        }
+    }
+    substituted
+}
+
+/// The variables a template uses, in order, without resolving them.
+#[tauri::command]
+pub fn template_variables(template: String) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for part in parse(&template) {
+        if let Part::Variable(name) = part {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Variables the active profile (with the default one) has no value for.
+#[tauri::command]
+pub fn missing_variables(app: AppHandle, template: String) -> Result<Vec<String>, String> {
+    let variables = app.state::<SettingsStore>().get().variables;
+    let mut missing = Vec::new();
+    for name in template_variables(template) {
+        if lookup(&variables, &name)?.is_none() {
+            missing.push(name);
+        }
+    }
+    Ok(missing)
+}
+
+/// Stores a secret variable's value in the keychain and marks it secret in the profile,
+/// dropping any plain value. An empty value removes the secret.
+#[tauri::command]
+pub fn set_secret_variable(app: AppHandle, profile: String, name: String, value: String) -> Result<(), String> {
+    if !valid_name(&name) || profile.trim().is_empty() {
+        return Err(format!("Invalid variable '{}' in profile '{}'", name, profile));
+    }
+    let mut settings = app.state::<SettingsStore>().get();
+    let set = settings.variables.profiles.entry(profile.clone()).or_default();
+    set.values.remove(&name);
+    if value.is_empty() {
+        secrets::delete(&secret_key(&profile, &name))?;
+        set.secrets.retain(|secret| *secret != name);
+    } else {
+        secrets::set(&secret_key(&profile, &name), &value)?;
+        if !set.secrets.contains(&name) {
+            set.secrets.push(name);
+        }
+    }
+    settings::apply(&app, settings)
+}
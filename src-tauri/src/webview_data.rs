@@ -0,0 +1,132 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// WebKitGTK keeps its data in named entries directly inside the app's local data dir,
+/// next to our own files, so only these are counted as webview storage.
+#[cfg(target_os = "linux")]
+const WEBKIT_ENTRIES: &[&str] = &[
+    "cookies",
+    "localstorage",
+    "databases",
+    "storage",
+    "mediakeys",
+    "hsts-storage",
+    "itp",
+    "serviceworkers",
+    "deviceidhashsalts",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageLocation {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsage {
+    pub total_bytes: u64,
+    pub cookie_count: usize,
+    pub locations: Vec<StorageLocation>,
+}
+
+/// Where the platform webview keeps cache, cookies and site storage for this app.
+pub fn webview_data_paths(app: &AppHandle) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    #[cfg(target_os = "windows")]
+    if let Ok(dir) = app.path().app_local_data_dir() {
+        paths.push(dir.join("EBWebView"));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(dir) = app.path().app_local_data_dir() {
+            paths.extend(WEBKIT_ENTRIES.iter().map(|entry| dir.join(entry)));
+        }
+        if let Ok(dir) = app.path().app_cache_dir() {
+            paths.push(dir);
+        }
+    }
+    #[cfg(target_os = "macos")]
+    if let Ok(home) = app.path().home_dir() {
+        let identifier = &app.config().identifier;
+        paths.push(home.join("Library/WebKit").join(identifier));
+        paths.push(home.join("Library/Caches").join(identifier).join("WebKit"));
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    let _ = app;
+    paths
+}
+
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| disk_usage(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+fn webviews(app: &AppHandle) -> Vec<tauri::WebviewWindow> {
+    app.webview_windows().into_values().collect()
+}
+
+/// Deletes every cookie from all app webviews. Returns how many were removed.
+#[tauri::command]
+pub fn clear_cookies(app: AppHandle) -> Result<usize, String> {
+    let mut removed = 0;
+    for webview in webviews(&app) {
+        let cookies = webview.cookies().map_err(|e| format!("Failed to read cookies: {}", e))?;
+        for cookie in cookies {
+            webview.delete_cookie(cookie).map_err(|e| format!("Failed to delete cookie: {}", e))?;
+            removed += 1;
+        }
+    }
+    log::info!("Cleared {} webview cookies", removed);
+    Ok(removed)
+}
+
+/// Clears the webview cache and site storage (localStorage, IndexedDB) while keeping
+/// cookies, so a broken frontend state can be reset without signing out of sites.
+/// The platform webviews only offer clearing everything at once, so cookies are saved
+/// first and put back afterwards.
+#[tauri::command]
+pub fn clear_webview_cache(app: AppHandle) -> Result<(), String> {
+    for webview in webviews(&app) {
+        let cookies = webview.cookies().map_err(|e| format!("Failed to read cookies: {}", e))?;
+        webview
+            .clear_all_browsing_data()
+            .map_err(|e| format!("Failed to clear webview data: {}", e))?;
+        for cookie in cookies {
+            if let Err(e) = webview.set_cookie(cookie) {
+                log::warn!("Failed to restore cookie: {}", e);
+            }
+        }
+    }
+    log::info!("Cleared webview cache and storage");
+    Ok(())
+}
+
+/// Disk space used by the webview's cache, cookies and site storage.
+#[tauri::command]
+pub async fn get_webview_storage_usage(app: AppHandle) -> Result<StorageUsage, String> {
+    let cookie_count = webviews(&app)
+        .first()
+        .map(|webview| webview.cookies().map(|cookies| cookies.len()))
+        .transpose()
+        .map_err(|e| format!("Failed to read cookies: {}", e))?
+        .unwrap_or(0);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let locations: Vec<StorageLocation> = webview_data_paths(&app)
+            .into_iter()
+            .filter(|path| path.exists())
+            .map(|path| StorageLocation { bytes: disk_usage(&path), path })
+            .collect();
+        StorageUsage { total_bytes: locations.iter().map(|l| l.bytes).sum(), cookie_count, locations }
+    })
+    .await
+    .map_err(|e| format!("Failed to measure webview storage: {}", e))
+}
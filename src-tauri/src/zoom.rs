@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+/// Zoom steps used by zoom in/out, the same ladder browsers use.
+const STEPS: &[f64] = &[0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0];
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+
+/// Zoom level per window label, kept in `zoom.json` in the app config dir so each
+/// window opens at the level it was left at.
+pub struct ZoomLevels {
+    levels: Mutex<BTreeMap<String, f64>>,
+    path: Option<PathBuf>,
+}
+
+impl ZoomLevels {
+    pub fn load(app: &AppHandle) -> Self {
+        let path = app.path().app_config_dir().ok().map(|dir| dir.join("zoom.json"));
+        let levels = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        ZoomLevels { levels: Mutex::new(levels), path }
+    }
+
+    fn get(&self, label: &str) -> f64 {
+        self.levels.lock().unwrap().get(label).copied().unwrap_or(1.0)
+    }
+
+    fn set(&self, label: &str, level: f64) {
+        let mut levels = self.levels.lock().unwrap();
+        if level == 1.0 {
+            levels.remove(label);
+        } else {
+            levels.insert(label.to_string(), level);
+        }
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string_pretty(&*levels)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| std::fs::write(path, contents).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Failed to save zoom level: {}", e);
+        }
+    }
+}
+
+/// Re-applies the saved zoom level after a page load in any window.
+pub fn restore(webview: &tauri::Webview) {
+    let level = webview.app_handle().state::<ZoomLevels>().get(webview.label());
+    if level != 1.0 {
+        if let Err(e) = webview.set_zoom(level) {
+            log::warn!("Failed to restore zoom for {}: {}", webview.label(), e);
+        }
+    }
+}
+
+fn apply(window: &WebviewWindow, level: f64) -> Result<f64, String> {
+    if !level.is_finite() {
+        return Err(format!("Invalid zoom level: {}", level));
+    }
+    let level = level.clamp(MIN_ZOOM, MAX_ZOOM);
+    window.set_zoom(level).map_err(|e| format!("Failed to set zoom: {}", e))?;
+    window.state::<ZoomLevels>().set(window.label(), level);
+    Ok(level)
+}
+
+fn current(window: &WebviewWindow) -> f64 {
+    window.state::<ZoomLevels>().get(window.label())
+}
+
+/// Sets the calling window's zoom (1.0 = 100%). Returns the level actually applied.
+#[tauri::command]
+pub fn set_zoom(window: WebviewWindow, level: f64) -> Result<f64, String> {
+    apply(&window, level)
+}
+
+#[tauri::command]
+pub fn zoom_in(window: WebviewWindow) -> Result<f64, String> {
+    let current = current(&window);
+    let next = STEPS.iter().copied().find(|step| *step > current + 0.001).unwrap_or(MAX_ZOOM);
+    apply(&window, next)
+}
+
+#[tauri::command]
+pub fn zoom_out(window: WebviewWindow) -> Result<f64, String> {
+    let current = current(&window);
+    let next = STEPS.iter().rev().copied().find(|step| *step < current - 0.001).unwrap_or(MIN_ZOOM);
+    apply(&window, next)
+}
+
+#[tauri::command]
+pub fn reset_zoom(window: WebviewWindow) -> Result<f64, String> {
+    apply(&window, 1.0)
+}
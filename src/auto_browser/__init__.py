@@ -1,3 +1,9 @@
 """Browser Automation UI"""
 
+import os
+
 __version__ = "0.1.3"
+
+# The desktop shell started the server in safe mode: no saved configuration beyond the API
+# key, no optional capture or GPU workarounds, and one browser at a time
+SAFE_MODE = os.getenv('NOVA_SAFE_MODE') == '1'
@@ -0,0 +1,98 @@
+"""
+Captcha solving through the desktop shell.
+
+The shell holds the solver's API key, applies the spending limits and records every
+attempt; this side only finds a captcha on the page, asks the shell for a token with
+`captcha.solve`, and fills the token in where the page's widget would have put it.
+"""
+
+from urllib.parse import urlsplit
+
+from . import control_channel, interactions
+
+# The shell gives up on a solve after 150 seconds
+SOLVE_TIMEOUT = 180
+
+# Finds a reCAPTCHA v2, hCaptcha or Turnstile widget and its site key
+_DETECT = """() => {
+    const widget = (selector, type) => {
+        const el = document.querySelector(selector);
+        return el && el.dataset.sitekey ? {type, key: el.dataset.sitekey} : null;
+    };
+    const frame = (pattern, type) => {
+        const el = [...document.querySelectorAll('iframe')].find(f => pattern.test(f.src));
+        if (!el) return null;
+        const params = new URL(el.src).searchParams;
+        const key = params.get('k') || params.get('sitekey');
+        return key ? {type, key} : null;
+    };
+    return widget('.g-recaptcha[data-sitekey]', 'recaptcha_v2')
+        || widget('.h-captcha[data-sitekey]', 'hcaptcha')
+        || widget('.cf-turnstile[data-sitekey]', 'turnstile')
+        || frame(/recaptcha\\/api2\\/anchor/, 'recaptcha_v2')
+        || frame(/hcaptcha\\.com/, 'hcaptcha');
+}"""
+
+# Puts the token in the widget's response fields and calls the page's callback, if any
+_FILL = """([type, token]) => {
+    const names = {
+        recaptcha_v2: ['g-recaptcha-response'],
+        hcaptcha: ['h-captcha-response', 'g-recaptcha-response'],
+        turnstile: ['cf-turnstile-response'],
+    }[type];
+    for (const name of names) {
+        document.querySelectorAll(`[name="${name}"], #${name}`).forEach(el => { el.value = token; });
+    }
+    const widget = document.querySelector('.g-recaptcha, .h-captcha, .cf-turnstile');
+    const callback = widget && widget.dataset.callback;
+    if (callback && typeof window[callback] === 'function') {
+        window[callback](token);
+    }
+}"""
+
+
+def detect(page):
+    """The captcha on the page as {'type', 'key'}, or None."""
+    try:
+        return page.evaluate(_DETECT)
+    except Exception as e:
+        print(f"[CAPTCHA] Detection failed: {e}")
+        return None
+
+
+def _ask_user(page, job_id):
+    """Have the user solve it in the browser window, when no solver could."""
+    site = urlsplit(page.url).hostname or 'the page'
+    message = f"Solve the captcha on {site} in the automation browser, then confirm."
+    answer = interactions.ask(job_id, 'captcha', message)
+    if answer is None:
+        return False
+    print("[CAPTCHA] Solved by the user")
+    return True
+
+
+def solve_if_present(page, job_id, can_ask_user=False):
+    """
+    Solve a captcha on the page through the shell, if there is one and a shell is
+    connected: by the configured solver, or else by the user when the browser is visible
+    (`can_ask_user`). Must run on the browser's thread. Returns whether one was solved.
+    """
+    if not job_id or not control_channel.is_connected():
+        return False
+    found = detect(page)
+    if not found:
+        return False
+    print(f"[CAPTCHA] Found a {found['type']} captcha, asking the desktop app to solve it")
+    try:
+        reply = control_channel.call('captcha.solve', {
+            'job_id': job_id,
+            'captcha_type': found['type'],
+            'website_url': page.url,
+            'website_key': found['key'],
+        }, timeout=SOLVE_TIMEOUT)
+        page.evaluate(_FILL, [found['type'], reply['token']])
+    except Exception as e:
+        print(f"[CAPTCHA] Not solved: {e}")
+        return _ask_user(page, job_id) if can_ask_user else False
+    print("[CAPTCHA] Solved")
+    return True
@@ -16,7 +16,9 @@ class ConfigManager:
     """Manages application configuration and API keys"""
 
     # macOS standard location for app config
-    CONFIG_DIR = Path.home() / "Library" / "Application Support" / "BrowserAutomation"
+    APP_CONFIG_DIR = Path.home() / "Library" / "Application Support" / "BrowserAutomation"
+    # Workspace servers get their own data dir from the desktop shell
+    CONFIG_DIR = Path(os.environ['NOVA_DATA_DIR']) if os.getenv('NOVA_DATA_DIR') else APP_CONFIG_DIR
     CONFIG_FILE = CONFIG_DIR / "config.json"
 
     @classmethod
@@ -30,19 +32,28 @@ class ConfigManager:
         """Check if configuration file exists"""
         return cls.CONFIG_FILE.exists()
 
-    @classmethod
-    def load_config(cls) -> Dict[str, str]:
-        """Load configuration from file"""
-        if not cls.config_exists():
+    @staticmethod
+    def _read(path: Path) -> Dict[str, str]:
+        if not path.exists():
             return {}
 
         try:
-            with open(cls.CONFIG_FILE, 'r') as f:
+            with open(path, 'r') as f:
                 return json.load(f)
         except Exception as e:
             print(f"Error loading config: {e}")
             return {}
 
+    @classmethod
+    def load_config(cls) -> Dict[str, str]:
+        """Load configuration from file. A workspace starts from the app's config (e.g. the
+        API key) and keeps its own changes on top."""
+        config = {}
+        if cls.CONFIG_DIR != cls.APP_CONFIG_DIR:
+            config.update(cls._read(cls.APP_CONFIG_DIR / "config.json"))
+        config.update(cls._read(cls.CONFIG_FILE))
+        return config
+
     @classmethod
     def save_config(cls, config: Dict[str, str]) -> bool:
         """Save configuration to file"""
@@ -0,0 +1,207 @@
+"""
+Control channel between the desktop shell and this server.
+
+Newline-delimited JSON-RPC 2.0, either over a Unix socket (the server listens, the
+shell connects once HTTP is up) or over stdin/stdout (available from the moment the
+process starts, and on platforms without Unix sockets). The message contract lives in
+control_protocol.json, which the shell also compiles against, so both sides agree on
+method names and the protocol version.
+"""
+
+import json
+import os
+import socket
+import sys
+import itertools
+import threading
+from pathlib import Path
+
+SCHEMA = json.loads((Path(__file__).parent / 'control_protocol.json').read_text())
+PROTOCOL_VERSION = SCHEMA['version']
+
+_client = None
+_client_lock = threading.Lock()
+
+# Requests this server made to the shell, by id: [event, reply]
+_pending = {}
+_pending_lock = threading.Lock()
+_next_id = itertools.count(1)
+
+
+class _Client:
+    """One connected shell: a line reader and a line writer."""
+
+    def __init__(self, reader, writer):
+        self.reader = reader
+        self.writer = writer
+
+    def send(self, message):
+        with _client_lock:
+            self.writer.write(json.dumps(message) + '\n')
+            self.writer.flush()
+
+
+def _methods(direction):
+    return {name for name, spec in SCHEMA['methods'].items() if spec['direction'] == direction}
+
+
+def _check_handlers(handlers):
+    expected = _methods('shell_to_server')
+    if set(handlers) != expected:
+        raise ValueError(f'Control handlers {sorted(handlers)} do not match schema methods {sorted(expected)}')
+
+
+def _dispatch(handlers, request):
+    method = request.get('method')
+    if method not in handlers:
+        return {'code': -32601, 'message': f'Method not found: {method}'}, None
+    try:
+        return None, handlers[method](request.get('params') or {})
+    except Exception as e:
+        return {'code': -32000, 'message': str(e)}, None
+
+
+def _serve_client(client, handlers):
+    global _client
+    with _client_lock:
+        _client = client
+    try:
+        for line in client.reader:
+            if not line.strip():
+                continue
+            try:
+                request = json.loads(line)
+            except ValueError:
+                client.send({'jsonrpc': '2.0', 'id': None, 'error': {'code': -32700, 'message': 'Parse error'}})
+                continue
+            if 'method' not in request:
+                _resolve(request)
+                continue
+            error, result = _dispatch(handlers, request)
+            if 'id' not in request:
+                continue
+            response = {'jsonrpc': '2.0', 'id': request['id']}
+            if error:
+                response['error'] = error
+            else:
+                response['result'] = result if result is not None else {}
+            client.send(response)
+    except (OSError, ValueError):
+        pass
+    finally:
+        with _client_lock:
+            if _client is client:
+                _client = None
+        with _pending_lock:
+            for waiter in _pending.values():
+                waiter[0].set()
+
+
+def _resolve(reply):
+    """Hand the shell's reply to the call() waiting for it."""
+    with _pending_lock:
+        waiter = _pending.get(reply.get('id'))
+    if waiter is None:
+        return
+    waiter[1] = reply
+    waiter[0].set()
+
+
+def serve(path, handlers):
+    """
+    Listen on the Unix socket `path` in a background thread, one shell connection at a
+    time. `handlers` must implement exactly the shell_to_server methods of the schema.
+    """
+    _check_handlers(handlers)
+    if not hasattr(socket, 'AF_UNIX'):
+        print('[CONTROL] Unix sockets are not available, control channel disabled')
+        return
+
+    if os.path.exists(path):
+        os.unlink(path)
+    listener = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+    listener.bind(path)
+    os.chmod(path, 0o600)
+    listener.listen(1)
+    print(f'[CONTROL] Listening on {path} (protocol {PROTOCOL_VERSION})')
+
+    def accept_loop():
+        while True:
+            conn, _ = listener.accept()
+            with conn:
+                client = _Client(conn.makefile('r', encoding='utf-8'), conn.makefile('w', encoding='utf-8'))
+                _serve_client(client, handlers)
+
+    threading.Thread(target=accept_loop, daemon=True, name='control-channel').start()
+
+
+def serve_stdio(handlers):
+    """
+    Speak the protocol over stdin/stdout. Everything else written to stdout (prints,
+    subprocess output) is redirected to stderr so it cannot corrupt the channel. When
+    stdin closes the shell is gone, so the server shuts itself down.
+    """
+    _check_handlers(handlers)
+    writer = os.fdopen(os.dup(sys.stdout.fileno()), 'w', encoding='utf-8')
+    sys.stdout.flush()
+    os.dup2(sys.stderr.fileno(), sys.stdout.fileno())
+    client = _Client(sys.stdin, writer)
+    print(f'[CONTROL] Using stdio (protocol {PROTOCOL_VERSION})')
+
+    def read_loop():
+        _serve_client(client, handlers)
+        print('[CONTROL] Shell closed stdin, shutting down')
+        handlers['shutdown']({})
+
+    threading.Thread(target=read_loop, daemon=True, name='control-channel').start()
+
+
+def is_connected():
+    """Whether a shell is attached to receive notifications."""
+    with _client_lock:
+        return _client is not None
+
+
+def notify(method, params):
+    """Send a server_to_shell notification. Does nothing when no shell is connected."""
+    if method not in _methods('server_to_shell'):
+        raise ValueError(f'Unknown notification: {method}')
+    with _client_lock:
+        client = _client
+    if client is None:
+        return
+    try:
+        client.send({'jsonrpc': '2.0', 'method': method, 'params': params})
+    except (OSError, ValueError) as e:
+        print(f'[CONTROL] Failed to send {method}: {e}')
+
+
+def call(method, params, timeout=10):
+    """
+    Make a server_to_shell request and wait for the shell's result. Raises RuntimeError
+    when no shell is connected, it doesn't answer in time, or it answers with an error.
+    """
+    if method not in _methods('server_to_shell'):
+        raise ValueError(f'Unknown request: {method}')
+    with _client_lock:
+        client = _client
+    if client is None:
+        raise RuntimeError('The desktop shell is not connected')
+    request_id = f'server-{next(_next_id)}'
+    waiter = [threading.Event(), None]
+    with _pending_lock:
+        _pending[request_id] = waiter
+    try:
+        client.send({'jsonrpc': '2.0', 'id': request_id, 'method': method, 'params': params})
+        waiter[0].wait(timeout)
+    except (OSError, ValueError) as e:
+        raise RuntimeError(f'Failed to send {method}: {e}') from e
+    finally:
+        with _pending_lock:
+            _pending.pop(request_id, None)
+    reply = waiter[1]
+    if reply is None:
+        raise RuntimeError(f'No reply to {method} from the desktop shell')
+    if 'error' in reply:
+        raise RuntimeError(reply['error'].get('message', 'Unknown error'))
+    return reply.get('result') or {}
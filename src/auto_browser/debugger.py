@@ -0,0 +1,100 @@
+"""
+Step-through debugging of running jobs.
+
+A job started with breakpoints runs its prompt one step at a time, a step being a
+non-empty line, instead of in a single act() call. Before each step it checks whether
+to stop: at a breakpoint, when the shell asked it to pause, or after a single step.
+While stopped it waits, and the shell is told with a `paused` job event; `resumed`
+follows when it continues. Jobs without breakpoints run in one go and can't be paused.
+"""
+
+import threading
+
+from . import control_channel, run_trace
+
+_sessions = {}
+_sessions_lock = threading.Lock()
+
+
+def steps(prompt):
+    """The steps of a prompt: its non-empty lines. The shell counts them the same way."""
+    return [line.strip() for line in prompt.splitlines() if line.strip()]
+
+
+class Session:
+    """Debugging state of one running job."""
+
+    def __init__(self, job, breakpoints):
+        self.job = job
+        self.breakpoints = {int(step) for step in breakpoints}
+        self.paused_at = None
+        self._pause_requested = False
+        self._condition = threading.Condition()
+
+    def _event(self, kind, step, total, message):
+        control_channel.notify('job.event', dict(self.job, kind=kind, step=step, steps=total, message=message))
+
+    def before_step(self, step, total, text):
+        """Wait here if the job should stop before `step` (1-based)."""
+        with self._condition:
+            if step not in self.breakpoints and not self._pause_requested:
+                return
+            where = 'at a breakpoint ' if step in self.breakpoints else ''
+            self._pause_requested = False
+            self.paused_at = step
+            print(f"[DEBUG] Job {self.job['job_id']} paused {where}before step {step}/{total}")
+            self._event('paused', step, total, f'Paused {where}before step {step}: {text}')
+            with run_trace.span('Paused', 'pause', breakpoint=step in self.breakpoints):
+                while self.paused_at is not None:
+                    # A job nobody can resume would hang until it times out
+                    if not control_channel.is_connected():
+                        print("[DEBUG] Shell disconnected, resuming")
+                        self.paused_at = None
+                        break
+                    self._condition.wait(1)
+        self._event('resumed', step, total, f'Running step {step}: {text}')
+
+    def set_breakpoints(self, breakpoints):
+        with self._condition:
+            self.breakpoints = {int(step) for step in breakpoints}
+
+    def pause(self):
+        with self._condition:
+            if self.paused_at is None:
+                self._pause_requested = True
+
+    def resume(self, single_step=False):
+        """Continue a paused job; with `single_step`, it stops again before the next step.
+        A running job asked to single-step stops before its next step."""
+        with self._condition:
+            self._pause_requested = single_step
+            self.paused_at = None
+            self._condition.notify_all()
+
+
+def begin(job, breakpoints):
+    session = Session(job, breakpoints)
+    with _sessions_lock:
+        _sessions[job['job_id']] = session
+    return session
+
+
+def end(job_id):
+    with _sessions_lock:
+        _sessions.pop(job_id, None)
+
+
+def session(job_id):
+    with _sessions_lock:
+        found = _sessions.get(job_id)
+    if found is None:
+        raise ValueError(f'Job {job_id} is not running step by step; give its recipe a breakpoint first')
+    return found
+
+
+def set_breakpoints(recipe_id, breakpoints):
+    """New breakpoints for a recipe, applied to its jobs that are running."""
+    with _sessions_lock:
+        running = [s for s in _sessions.values() if s.job.get('recipe_id') == recipe_id]
+    for found in running:
+        found.set_breakpoints(breakpoints)
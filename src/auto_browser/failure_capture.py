@@ -0,0 +1,40 @@
+"""
+Screenshot and DOM snapshot of the page at the moment a job step fails.
+
+The desktop shell asks for them with each job it sends (capture_failures), following its
+setting. They are written to a temp dir and handed over with `failure.captured`; the shell
+moves them into the run's artifacts and keeps them with the error for its error view.
+"""
+
+import os
+import tempfile
+
+from . import control_channel
+
+SCREENSHOT_TIMEOUT_MS = 10000
+
+
+def capture(page, job_id, error, step=None):
+    """Capture `page` for the failed `step` (from 1; None when the job ran in one go).
+    Best effort: whatever can't be captured is left out. Returns False without a shell."""
+    if not control_channel.is_connected():
+        return False
+    directory = tempfile.mkdtemp(prefix='nova-failure-')
+    captured = {'job_id': job_id, 'step': step, 'error': str(error), 'url': None, 'screenshot': None, 'dom': None}
+    try:
+        captured['url'] = page.url
+        path = os.path.join(directory, 'screenshot.png')
+        page.screenshot(path=path, timeout=SCREENSHOT_TIMEOUT_MS)
+        captured['screenshot'] = path
+    except Exception as e:
+        print(f"[FAILURE] Screenshot not taken: {e}")
+    try:
+        path = os.path.join(directory, 'dom.html')
+        with open(path, 'w', encoding='utf-8') as f:
+            f.write(page.content())
+        captured['dom'] = path
+    except Exception as e:
+        print(f"[FAILURE] DOM snapshot not taken: {e}")
+    control_channel.notify('failure.captured', captured)
+    print(f"[FAILURE] Captured the page of job {job_id} at {captured['url']}")
+    return True
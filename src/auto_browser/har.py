@@ -0,0 +1,152 @@
+"""
+Per-job network capture in HAR 1.2 format, handed to the desktop shell when the job ends.
+
+The browser context outlives jobs, so requests are recorded from Playwright events only
+while a job runs. Bodies aren't captured, and credentials in headers are replaced, so a
+capture can be shared to debug a failed automation.
+"""
+
+import json
+import os
+import tempfile
+import threading
+from datetime import datetime, timezone
+from urllib.parse import parse_qsl, urlsplit
+
+from . import SAFE_MODE
+
+# Requests beyond this many in one job are dropped, to bound memory
+MAX_ENTRIES = 5000
+
+REDACTED_HEADERS = {'authorization', 'proxy-authorization', 'cookie', 'set-cookie'}
+
+
+def _headers(headers):
+    return [
+        {'name': name, 'value': '[redacted]' if name.lower() in REDACTED_HEADERS else value}
+        for name, value in (headers or {}).items()
+    ]
+
+
+def _span(timing, start, end):
+    """Milliseconds between two Playwright timing marks, or -1 when either is missing."""
+    if timing.get(start, -1) < 0 or timing.get(end, -1) < 0:
+        return -1
+    return max(0, timing[end] - timing[start])
+
+
+def _entry(request, response, failure=None):
+    timing = request.timing or {}
+    started = datetime.fromtimestamp(timing.get('startTime', 0) / 1000, tz=timezone.utc)
+    timings = {
+        'blocked': -1,
+        'dns': _span(timing, 'domainLookupStart', 'domainLookupEnd'),
+        'connect': _span(timing, 'connectStart', 'connectEnd'),
+        'ssl': _span(timing, 'secureConnectionStart', 'connectEnd'),
+        'send': 0,
+        'wait': _span(timing, 'requestStart', 'responseStart'),
+        'receive': _span(timing, 'responseStart', 'responseEnd'),
+    }
+    post_data = request.post_data
+    entry = {
+        'startedDateTime': started.isoformat(),
+        'time': max(0, timing.get('responseEnd', 0)),
+        'request': {
+            'method': request.method,
+            'url': request.url,
+            'httpVersion': '',
+            'cookies': [],
+            'headers': _headers(request.headers),
+            'queryString': [{'name': k, 'value': v} for k, v in parse_qsl(urlsplit(request.url).query)],
+            'headersSize': -1,
+            'bodySize': len(post_data.encode()) if post_data else 0,
+        },
+        'response': {
+            'status': response.status if response else 0,
+            'statusText': response.status_text if response else '',
+            'httpVersion': '',
+            'cookies': [],
+            'headers': _headers(response.headers if response else {}),
+            'content': {'size': -1, 'mimeType': (response.headers.get('content-type', '') if response else '')},
+            'redirectURL': (response.headers.get('location', '') if response else ''),
+            'headersSize': -1,
+            'bodySize': -1,
+        },
+        'cache': {},
+        'timings': timings,
+        '_resourceType': request.resource_type,
+    }
+    if post_data:
+        entry['request']['postData'] = {
+            'mimeType': request.headers.get('content-type', ''),
+            'text': post_data,
+        }
+    if failure:
+        entry['_failure'] = failure
+    return entry
+
+
+class HarRecorder:
+    def __init__(self, enabled=False):
+        self.enabled = enabled
+        self._lock = threading.Lock()
+        self._job_id = None
+        self._entries = []
+        self._responses = {}
+
+    def attach(self, context):
+        """Listen to a Playwright (sync API) browser context for the rest of its life."""
+        context.on('response', self._on_response)
+        context.on('requestfinished', self._on_finished)
+        context.on('requestfailed', self._on_failed)
+
+    def start(self, job_id):
+        with self._lock:
+            self._job_id = job_id if self.enabled else None
+            self._entries = []
+            self._responses = {}
+
+    def _on_response(self, response):
+        with self._lock:
+            if self._job_id:
+                self._responses[response.request] = response
+
+    def _record(self, request, failure=None):
+        with self._lock:
+            if not self._job_id:
+                return
+            response = self._responses.pop(request, None)
+            if len(self._entries) < MAX_ENTRIES:
+                try:
+                    self._entries.append(_entry(request, response, failure))
+                except Exception as e:
+                    print(f"[HAR] Skipped a request: {e}")
+
+    def _on_finished(self, request):
+        self._record(request)
+
+    def _on_failed(self, request):
+        self._record(request, request.failure)
+
+    def stop(self):
+        """End the job's capture and write it to a temporary file; returns its path, or
+        None when nothing was captured."""
+        from . import __version__
+        with self._lock:
+            job_id, entries = self._job_id, self._entries
+            self._job_id, self._entries, self._responses = None, [], {}
+        if not job_id or not entries:
+            return None
+        har = {'log': {
+            'version': '1.2',
+            'creator': {'name': 'Browser Automation', 'version': __version__},
+            'pages': [],
+            'entries': entries,
+        }}
+        fd, path = tempfile.mkstemp(prefix=f'{job_id}-', suffix='.har')
+        with os.fdopen(fd, 'w', encoding='utf-8') as f:
+            json.dump(har, f)
+        return path
+
+
+recorder = HarRecorder(enabled=os.getenv('NOVA_HAR_CAPTURE') == '1' and not SAFE_MODE)
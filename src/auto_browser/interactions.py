@@ -0,0 +1,39 @@
+"""
+Asking the user for help with a job through the desktop shell.
+
+The shell shows the request in its window, brings it forward and notifies the user;
+the job waits here until they answer, decline, or the deadline passes.
+"""
+
+from . import control_channel
+
+KINDS = ('captcha', 'approval', 'choice', 'text')
+
+# Seconds a job waits for the user when not told otherwise
+DEFAULT_TIMEOUT = 5 * 60
+
+
+def ask(job_id, kind, message, options=None, timeout=DEFAULT_TIMEOUT):
+    """
+    Ask the user and wait. Returns their answer (for captcha and approval just a
+    confirmation), or None when they decline, don't answer in time, or no shell is
+    connected.
+    """
+    if kind not in KINDS:
+        raise ValueError(f'Unknown interaction kind: {kind}')
+    if not job_id or not control_channel.is_connected():
+        return None
+    print(f"[INTERACTION] Waiting for the user ({kind}): {message}")
+    try:
+        reply = control_channel.call('interaction.request', {
+            'job_id': job_id,
+            'kind': kind,
+            'message': message,
+            'options': list(options or []),
+            'timeout_secs': int(timeout),
+        }, timeout=timeout + 10)
+    except RuntimeError as e:
+        print(f"[INTERACTION] No answer: {e}")
+        return None
+    print("[INTERACTION] The user answered")
+    return reply.get('answer')
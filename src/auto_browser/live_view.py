@@ -0,0 +1,140 @@
+"""
+Live view of the automation browser, streamed to the desktop shell's viewer window.
+
+Playwright's sync API may only be used from the thread that started it, and that thread
+is busy while a job runs. The live view therefore runs its own Playwright on its own
+thread, attached to the browser through its DevTools port, and streams the active tab
+with a CDP screencast as `live.frame` notifications. In control mode the mouse and
+keyboard events the viewer relays are replayed into the page.
+"""
+
+import queue
+import threading
+
+from . import control_channel
+
+# Frames are scaled down to fit, and JPEG-compressed, to keep the channel responsive
+MAX_WIDTH = 1280
+MAX_HEIGHT = 800
+QUALITY = 60
+
+MOUSE_ACTIONS = {'mousePressed', 'mouseReleased', 'mouseMoved'}
+KEY_ACTIONS = {'keyDown', 'keyUp', 'char'}
+BUTTONS = {'none', 'left', 'middle', 'right'}
+
+
+def _input_command(event):
+    """The CDP command replaying a viewer event, or ValueError for one we don't accept."""
+    kind = event.get('type')
+    if kind == 'mouse' and event.get('action') in MOUSE_ACTIONS:
+        return 'Input.dispatchMouseEvent', {
+            'type': event['action'],
+            'x': float(event['x']),
+            'y': float(event['y']),
+            'button': event.get('button') if event.get('button') in BUTTONS else 'none',
+            'clickCount': int(event.get('click_count', 0)),
+        }
+    if kind == 'wheel':
+        return 'Input.dispatchMouseEvent', {
+            'type': 'mouseWheel',
+            'x': float(event['x']),
+            'y': float(event['y']),
+            'deltaX': float(event.get('delta_x', 0)),
+            'deltaY': float(event.get('delta_y', 0)),
+        }
+    if kind == 'key' and event.get('action') in KEY_ACTIONS:
+        params = {'type': event['action'], 'key': str(event.get('key', '')), 'code': str(event.get('code', ''))}
+        if event.get('text'):
+            params['text'] = str(event['text'])
+        return 'Input.dispatchKeyEvent', params
+    raise ValueError(f'Unsupported input event: {kind}')
+
+
+class LiveView:
+    def __init__(self):
+        self._lock = threading.Lock()
+        self._thread = None
+        self._stop = threading.Event()
+        self._inputs = queue.Queue()
+        self.control = False
+
+    def running(self):
+        return self._thread is not None and self._thread.is_alive()
+
+    def start(self, control=False):
+        """Start streaming, or switch between read-only and control mode."""
+        with self._lock:
+            self.control = bool(control)
+            if self.running():
+                return
+            self._stop.clear()
+            self._inputs = queue.Queue()
+            self._thread = threading.Thread(target=self._run, daemon=True, name='live-view')
+            self._thread.start()
+        print(f"[LIVE] Streaming the browser ({'control' if self.control else 'read-only'})")
+
+    def stop(self):
+        self._stop.set()
+        self.control = False
+
+    def send_input(self, event):
+        if not self.control:
+            raise ValueError('The live view is read-only')
+        if not self.running():
+            raise RuntimeError('The live view is not running')
+        self._inputs.put(_input_command(event))
+
+    def _run(self):
+        from playwright.sync_api import sync_playwright
+        from .main import DEBUGGING_PORT
+        try:
+            # Leaving the block drops the connection; the browser itself keeps running
+            with sync_playwright() as playwright:
+                browser = playwright.chromium.connect_over_cdp(f'http://127.0.0.1:{DEBUGGING_PORT}')
+                self._stream(browser)
+        except Exception as e:
+            print(f"[LIVE] Streaming stopped: {e}")
+        print("[LIVE] Stopped streaming the browser")
+
+    @staticmethod
+    def _active_page(browser):
+        pages = [page for context in browser.contexts for page in context.pages if not page.is_closed()]
+        return pages[-1] if pages else None
+
+    def _on_frame(self, session, frame):
+        session.send('Page.screencastFrameAck', {'sessionId': frame['sessionId']})
+        metadata = frame.get('metadata', {})
+        control_channel.notify('live.frame', {
+            'data': frame['data'],
+            'width': int(metadata.get('deviceWidth', 0)),
+            'height': int(metadata.get('deviceHeight', 0)),
+        })
+
+    def _stream(self, browser):
+        page, session = None, None
+        while not self._stop.is_set() and control_channel.is_connected():
+            # Follow the newest tab, which is where the automation usually is
+            active = self._active_page(browser)
+            if active is None:
+                self._stop.wait(0.5)
+                continue
+            if active != page:
+                page = active
+                session = page.context.new_cdp_session(page)
+                session.on('Page.screencastFrame', lambda frame, s=session: self._on_frame(s, frame))
+                session.send('Page.startScreencast', {
+                    'format': 'jpeg', 'quality': QUALITY, 'maxWidth': MAX_WIDTH, 'maxHeight': MAX_HEIGHT,
+                })
+            try:
+                method, params = self._inputs.get_nowait()
+                session.send(method, params)
+            except queue.Empty:
+                # Lets Playwright deliver the screencast's frames
+                page.wait_for_timeout(30)
+            except Exception as e:
+                print(f"[LIVE] Input not delivered: {e}")
+        if session is not None and page is not None and not page.is_closed():
+            session.send('Page.stopScreencast')
+
+
+viewer = LiveView()
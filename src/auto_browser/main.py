@@ -1,9 +1,14 @@
 """Main entry point for Browser Automation UI"""
 
 import os
-from pathlib import Path
 from nova_act import NovaAct
 
+from .config_manager import ConfigManager
+
+# DevTools port of the automation browser, which the live view attaches to; the desktop
+# shell assigns one per server so several browsers can run side by side
+DEBUGGING_PORT = int(os.getenv("NOVA_DEBUG_PORT") or 9222)
+
 
 class BrowserUI:
     """Simple UI for browser automation"""
@@ -29,10 +34,18 @@ class BrowserUI:
             )
 
         # Enable browser debugging (for development)
-        os.environ["NOVA_ACT_BROWSER_ARGS"] = "--remote-debugging-port=9222"
-
-        # Set up persistent user data directory for stateful browser sessions
-        user_data_dir = Path.home() / "Library" / "Application Support" / "BrowserAutomation" / "user_data_dir"
+        browser_args = [f"--remote-debugging-port={DEBUGGING_PORT}"]
+
+        # Place the window on the display chosen in the desktop app ("x,y,width,height")
+        bounds = os.getenv("NOVA_BROWSER_BOUNDS", "").split(",")
+        if len(bounds) == 4 and not self.headless:
+            x, y, width, height = bounds
+            browser_args += [f"--window-position={x},{y}", f"--window-size={width},{height}"]
+        os.environ["NOVA_ACT_BROWSER_ARGS"] = " ".join(browser_args)
+
+        # Set up persistent user data directory for stateful browser sessions, one per
+        # workspace so their browsers don't fight over the profile lock
+        user_data_dir = ConfigManager.CONFIG_DIR / "user_data_dir"
         user_data_dir.mkdir(parents=True, exist_ok=True)
 
         # Internal: Initialize Nova Act agent with statefulness
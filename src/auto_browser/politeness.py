@@ -0,0 +1,153 @@
+"""
+Per-site rate limits and concurrency caps, so automations stay polite and don't get banned.
+
+The shell owns the limits: they arrive in NOVA_DOMAIN_LIMITS at spawn and through
+config.push afterwards, shaped like {"default": limits, "domains": [{"domain": ..., **limits}]}
+where limits are requests_per_minute and max_concurrent (null for no limit). A domain's
+limits also cover its subdomains and the longest match wins; matched sites share one budget.
+
+Only page loads (navigation requests) are limited. Loads over the rate are held until their
+slot; loads over the concurrency cap wait until one of the site's loads finishes.
+"""
+
+import json
+import os
+import threading
+import time
+from urllib.parse import urlparse
+
+
+def _limit(value):
+    """A positive integer limit, or None for no limit."""
+    try:
+        value = int(value)
+    except (TypeError, ValueError):
+        return None
+    return value if value > 0 else None
+
+
+def _limits(raw):
+    raw = raw if isinstance(raw, dict) else {}
+    return {
+        'requests_per_minute': _limit(raw.get('requests_per_minute')),
+        'max_concurrent': _limit(raw.get('max_concurrent')),
+    }
+
+
+class DomainLimiter:
+    def __init__(self):
+        self._lock = threading.Lock()
+        self._default = _limits(None)
+        self._domains = {}
+        self._stats = {}
+        self._next_slot = {}
+        self._queued = {}
+        # Requests let through, mapped to the site they count against
+        self._in_flight = {}
+
+    def configure(self, config):
+        config = config if isinstance(config, dict) else {}
+        domains = {}
+        for entry in config.get('domains') or []:
+            if isinstance(entry, dict) and entry.get('domain'):
+                domain = str(entry['domain']).strip().lower().lstrip('.')
+                domains[domain] = _limits(entry)
+        with self._lock:
+            self._default = _limits(config.get('default'))
+            self._domains = domains
+        print(f"[POLITENESS] Default limits {self._default}, {len(domains)} site(s) with their own")
+
+    def _site(self, url):
+        """The site a URL counts against and its limits."""
+        host = (urlparse(url).hostname or '').lower()
+        matches = [d for d in self._domains if host == d or host.endswith('.' + d)]
+        if matches:
+            domain = max(matches, key=len)
+            return domain, self._domains[domain]
+        return host, self._default
+
+    def _entry(self, site, limits):
+        stats = self._stats.setdefault(site, {
+            'domain': site, 'requests': 0, 'throttled': 0, 'waited_ms': 0, 'in_flight': 0,
+        })
+        stats['limits'] = limits
+        return stats
+
+    def attach(self, context):
+        """Start limiting page loads in a Playwright (sync API) browser context."""
+        context.route('**/*', self._on_route)
+        context.on('requestfinished', self._on_done)
+        context.on('requestfailed', self._on_done)
+
+    def _on_route(self, route, request):
+        if not request.is_navigation_request():
+            route.continue_()
+            return
+        with self._lock:
+            site, limits = self._site(request.url)
+            stats = self._entry(site, limits)
+            stats['requests'] += 1
+            wait = 0.0
+            rate = limits['requests_per_minute']
+            if rate:
+                now = time.monotonic()
+                slot = max(now, self._next_slot.get(site, now))
+                self._next_slot[site] = slot + 60.0 / rate
+                wait = slot - now
+            if wait > 0:
+                stats['throttled'] += 1
+                stats['waited_ms'] += int(wait * 1000)
+        # The page can't go anywhere until this load is let through, so holding it here is fine
+        if wait > 0:
+            time.sleep(wait)
+        with self._lock:
+            cap = limits['max_concurrent']
+            if cap and stats['in_flight'] >= cap:
+                if wait <= 0:
+                    stats['throttled'] += 1
+                self._queued.setdefault(site, []).append((route, request, time.monotonic()))
+                return
+            self._start(site, stats, request)
+        route.continue_()
+
+    def _start(self, site, stats, request):
+        stats['in_flight'] += 1
+        self._in_flight[request] = site
+
+    def _on_done(self, request):
+        with self._lock:
+            site = self._in_flight.pop(request, None)
+            if site is None:
+                return
+            stats = self._stats[site]
+            stats['in_flight'] = max(0, stats['in_flight'] - 1)
+            queue = self._queued.get(site)
+            if not queue:
+                return
+            route, queued, queued_at = queue.pop(0)
+            stats['waited_ms'] += int((time.monotonic() - queued_at) * 1000)
+            self._start(site, stats, queued)
+        try:
+            route.continue_()
+        except Exception as e:
+            # The page may have moved on or closed while the load waited
+            print(f"[POLITENESS] Could not release a held page load: {e}")
+            self._on_done(queued)
+
+    def stats(self):
+        with self._lock:
+            return [dict(entry) for entry in self._stats.values()]
+
+
+def _load():
+    limiter = DomainLimiter()
+    raw = os.getenv('NOVA_DOMAIN_LIMITS')
+    if raw:
+        try:
+            limiter.configure(json.loads(raw))
+        except ValueError:
+            print(f"[POLITENESS] Ignoring malformed NOVA_DOMAIN_LIMITS: {raw}")
+    return limiter
+
+
+limiter = _load()
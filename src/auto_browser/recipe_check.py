@@ -0,0 +1,88 @@
+"""
+Static checks of an automation prompt, run without a browser.
+
+Prompts are natural language, so only what can be judged from the text is checked:
+CSS selectors quoted in backticks must parse, URLs must be well formed, and the prompt
+must fit what a single act() call handles well. Each finding is a diagnostic dict with
+severity ('error' or 'warning'), code, message, and the character offset and length of
+the text it refers to.
+"""
+
+import re
+from urllib.parse import urlparse
+
+# Longer prompts tend to be several tasks in one; splitting them works better
+MAX_PROMPT_CHARS = 2000
+
+_BACKTICKED = re.compile(r'`([^`\n]+)`')
+_URL = re.compile(r'\b(?:https?:)?//\S+|\bhttps?:\S*|\bwww\.\S+', re.IGNORECASE)
+_SELECTOR_START = re.compile(r'^(?:[#.\[*]|[a-zA-Z][\w-]*(?:[#.\[:]|\s*[>+~]))')
+_PAIRS = {'[': ']', '(': ')'}
+
+
+def _diagnostic(severity, code, message, offset=0, length=0):
+    return {'severity': severity, 'code': code, 'message': message, 'offset': offset, 'length': length}
+
+
+def _selector_error(selector):
+    """Why a CSS selector can't parse, or None. Checks brackets, quotes and combinators."""
+    stack = []
+    quote = None
+    for char in selector:
+        if quote:
+            if char == quote:
+                quote = None
+        elif char in '"\'':
+            quote = char
+        elif char in _PAIRS:
+            stack.append(_PAIRS[char])
+        elif char in ')]':
+            if not stack or stack.pop() != char:
+                return f"unexpected '{char}'"
+    if quote:
+        return 'unterminated string'
+    if stack:
+        return f"missing '{stack[-1]}'"
+    stripped = selector.strip()
+    if stripped[-1] in '>+~,':
+        return f"ends with '{stripped[-1]}'"
+    if re.search(r'[>+~]\s*[>+~]|,\s*,', stripped):
+        return 'two combinators in a row'
+    if re.search(r'[#.](?![\w-])', stripped.replace('\\.', '')):
+        return "'#' or '.' without a name"
+    return None
+
+
+def validate(prompt):
+    """Diagnostics for a prompt; an empty list means nothing was found."""
+    diagnostics = []
+    if not prompt.strip():
+        return [_diagnostic('error', 'empty_prompt', 'The prompt is empty')]
+    if len(prompt) > MAX_PROMPT_CHARS:
+        diagnostics.append(_diagnostic(
+            'warning', 'long_prompt',
+            f'The prompt is {len(prompt)} characters; consider splitting it into steps',
+            MAX_PROMPT_CHARS, len(prompt) - MAX_PROMPT_CHARS,
+        ))
+
+    for match in _BACKTICKED.finditer(prompt):
+        candidate = match.group(1)
+        if not _SELECTOR_START.match(candidate.strip()):
+            continue
+        error = _selector_error(candidate)
+        if error:
+            diagnostics.append(_diagnostic(
+                'error', 'invalid_selector', f'Selector `{candidate}` is invalid: {error}',
+                match.start(1), len(candidate),
+            ))
+
+    for match in _URL.finditer(prompt):
+        url = match.group(0).rstrip('.,;:!?)\'"')
+        target = url if '://' in url else 'https://' + url.lstrip('/')
+        parsed = urlparse(target)
+        if parsed.scheme not in ('http', 'https') or ('.' not in parsed.netloc and parsed.hostname != 'localhost'):
+            diagnostics.append(_diagnostic(
+                'warning', 'invalid_url', f'{url} does not look like a web address',
+                match.start(), len(url),
+            ))
+    return diagnostics
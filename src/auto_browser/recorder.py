@@ -0,0 +1,197 @@
+"""
+Recording of what the user does in a browser, for the desktop shell to turn into a recipe.
+
+The recorder opens a browser of its own, so jobs in the automation browser keep running
+meanwhile, and instruments its pages with a script that reports clicks, typing,
+selections and key presses. Navigations are recorded from Playwright's side. Each action
+goes to the shell as a `record.action` notification as it happens, and record.stop
+returns them all; tidying them up into steps is left to the shell. Like the live view,
+the recorder needs a Playwright of its own, on its own thread.
+"""
+
+import threading
+import time
+
+from . import control_channel
+
+# How long record.start waits to report a browser that fails to launch
+LAUNCH_WAIT = 4
+STOP_WAIT = 3
+
+ACTION_KINDS = {'navigate', 'click', 'fill', 'select', 'press'}
+ACTION_FIELDS = ('kind', 'selector', 'label', 'value', 'sensitive', 'url', 'at')
+
+# Reports actions through the __recordAction binding. Typing is recorded once per field
+# edit (on change, or before Enter), not per key; password values are never sent.
+SCRIPT = r"""
+(() => {
+    if (window !== window.top || window.__recorderInstalled) return;
+    window.__recorderInstalled = true;
+    const CLICKABLE = 'a, button, input, select, textarea, label, summary, [role=button], [role=link], [onclick]';
+    const TYPED = /^(text|email|password|search|tel|url|number|date|time|datetime-local|month|week)$/;
+    const generated = /\d{3,}|[0-9a-f]{8,}/;
+    const recorded = new WeakMap();
+
+    const unique = (selector) => {
+        try { return document.querySelectorAll(selector).length === 1; } catch (e) { return false; }
+    };
+    const isTyped = (el) => el.tagName === 'TEXTAREA' || (el.tagName === 'INPUT' && TYPED.test(el.type || 'text'));
+    const clean = (text) => (text || '').trim().replace(/\s+/g, ' ').slice(0, 60) || null;
+
+    function selector(el) {
+        const tag = el.tagName.toLowerCase();
+        for (const attr of ['data-testid', 'data-test', 'data-qa', 'data-cy', 'id', 'name', 'aria-label', 'placeholder']) {
+            const value = el.getAttribute(attr);
+            if (!value || (attr === 'id' && generated.test(value))) continue;
+            const candidate = attr === 'id' ? `#${CSS.escape(value)}` : `${tag}[${attr}=${JSON.stringify(value)}]`;
+            if (unique(candidate)) return candidate;
+        }
+        const parts = [];
+        for (let node = el; node && node !== document.documentElement; node = node.parentElement) {
+            const same = node.parentElement
+                ? [...node.parentElement.children].filter(c => c.tagName === node.tagName)
+                : [node];
+            const nth = same.length > 1 ? `:nth-of-type(${same.indexOf(node) + 1})` : '';
+            parts.unshift(node.tagName.toLowerCase() + nth);
+            if (unique(parts.join(' > '))) break;
+        }
+        return parts.join(' > ');
+    }
+
+    function label(el) {
+        const field = ['INPUT', 'SELECT', 'TEXTAREA'].includes(el.tagName);
+        const labelled = field && el.labels && el.labels.length ? el.labels[0].innerText : null;
+        return clean(el.getAttribute('aria-label') || labelled || el.getAttribute('placeholder')
+            || (field ? el.getAttribute('name') : el.innerText) || el.getAttribute('title')
+            || el.getAttribute('alt') || (el.type === 'submit' ? el.value : null));
+    }
+
+    function send(kind, el, extra) {
+        window.__recordAction(Object.assign({
+            kind, selector: selector(el), label: label(el), url: location.href, at: Date.now(),
+        }, extra));
+    }
+
+    function recordFill(el) {
+        if (recorded.get(el) === el.value) return;
+        recorded.set(el, el.value);
+        if (el.type === 'password') {
+            send('fill', el, { value: null, sensitive: true });
+        } else {
+            send('fill', el, { value: el.value });
+        }
+    }
+
+    document.addEventListener('click', (e) => {
+        const el = e.target.closest(CLICKABLE) || e.target;
+        // Choosing an option is recorded when the selection changes
+        if (el.tagName === 'SELECT' || el.tagName === 'OPTION') return;
+        send('click', el, {});
+    }, true);
+
+    document.addEventListener('change', (e) => {
+        const el = e.target;
+        if (el.tagName === 'SELECT') {
+            send('select', el, { value: clean(el.selectedOptions[0] && el.selectedOptions[0].text) });
+        } else if (isTyped(el)) {
+            recordFill(el);
+        }
+    }, true);
+
+    document.addEventListener('keydown', (e) => {
+        if (e.key !== 'Enter' && e.key !== 'Escape') return;
+        const el = e.target;
+        if (isTyped(el)) recordFill(el);
+        send('press', el, { value: e.key });
+    }, true);
+})();
+"""
+
+
+class Recorder:
+    def __init__(self):
+        self._lock = threading.Lock()
+        self._thread = None
+        self._stop = threading.Event()
+        self._ready = threading.Event()
+        self._error = None
+        self._actions = []
+        self.paused = False
+
+    def running(self):
+        return self._thread is not None and self._thread.is_alive()
+
+    def start(self, url):
+        """Open a recording browser on `url`. Raises if it fails to launch right away."""
+        with self._lock:
+            if self.running():
+                raise RuntimeError('Already recording')
+            self._stop.clear()
+            self._ready.clear()
+            self._error = None
+            self._actions = []
+            self.paused = False
+            self._thread = threading.Thread(target=self._run, args=(url,), daemon=True, name='recorder')
+            self._thread.start()
+        self._ready.wait(LAUNCH_WAIT)
+        if self._error:
+            raise RuntimeError(f'The recording browser did not start: {self._error}')
+        print(f"[RECORD] Recording on {url}")
+
+    def pause(self, paused):
+        self.paused = bool(paused)
+        print(f"[RECORD] Recording {'paused' if self.paused else 'resumed'}")
+
+    def stop(self):
+        """Close the recording browser; returns what was recorded."""
+        self._stop.set()
+        if self.running():
+            self._thread.join(STOP_WAIT)
+        actions, self._actions = self._actions, []
+        print(f"[RECORD] Stopped recording, {len(actions)} action(s)")
+        return actions
+
+    def _record(self, action):
+        if self.paused or self._stop.is_set() or not isinstance(action, dict):
+            return
+        if action.get('kind') not in ACTION_KINDS:
+            return
+        action = {field: action.get(field) for field in ACTION_FIELDS}
+        action['sensitive'] = bool(action['sensitive'])
+        if action['sensitive']:
+            action['value'] = None
+        self._actions.append(action)
+        control_channel.notify('record.action', {'action': action})
+
+    def _watch(self, page):
+        def navigated(frame):
+            if frame == page.main_frame and frame.url.startswith(('http:', 'https:')):
+                self._record({'kind': 'navigate', 'url': frame.url, 'at': int(time.time() * 1000)})
+        page.on('framenavigated', navigated)
+
+    def _run(self, url):
+        from playwright.sync_api import sync_playwright
+        try:
+            with sync_playwright() as playwright:
+                browser = playwright.chromium.launch(headless=False)
+                try:
+                    context = browser.new_context(no_viewport=True)
+                    context.expose_binding('__recordAction', lambda source, action: self._record(action))
+                    context.add_init_script(SCRIPT)
+                    context.on('page', self._watch)
+                    page = context.new_page()
+                    page.goto(url)
+                    self._ready.set()
+                    # Until stopped, or the user closes the window
+                    while not self._stop.is_set() and context.pages:
+                        context.pages[0].wait_for_timeout(100)
+                finally:
+                    browser.close()
+        except Exception as e:
+            self._error = str(e)
+            print(f"[RECORD] Recording browser stopped: {e}")
+        finally:
+            self._ready.set()
+
+
+recorder = Recorder()
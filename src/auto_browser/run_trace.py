@@ -0,0 +1,77 @@
+"""
+Timing spans of a job, for the run timeline in the desktop shell.
+
+A trace is started for each job on the thread running it. Code on that thread opens
+spans with `span()`; spans opened inside another are its children. When the job ends
+the trace goes to the shell with `trace.ready`, which stores it with the run. Outside a
+job, spans record nothing.
+"""
+
+import contextlib
+import threading
+import time
+
+from . import control_channel
+
+_local = threading.local()
+
+
+class Trace:
+    def __init__(self, job_id):
+        self.job_id = job_id
+        self.started = time.monotonic()
+        self.started_at = int(time.time() * 1000)
+        self.spans = []
+        self.open = []
+
+    def elapsed_ms(self):
+        return round((time.monotonic() - self.started) * 1000, 1)
+
+
+def start(job_id):
+    _local.trace = Trace(job_id)
+
+
+@contextlib.contextmanager
+def span(name, kind, **attributes):
+    """Time the block as a span of the current job's trace. `kind` groups spans for
+    display: job steps, act calls, browser start-up, captchas, pauses and the like."""
+    trace = getattr(_local, 'trace', None)
+    if trace is None:
+        yield None
+        return
+    entry = {
+        'id': len(trace.spans) + 1,
+        'parent_id': trace.open[-1]['id'] if trace.open else None,
+        'name': name,
+        'kind': kind,
+        'start_ms': trace.elapsed_ms(),
+        'duration_ms': None,
+        'status': 'ok',
+        'attributes': {key: value for key, value in attributes.items() if value is not None},
+    }
+    trace.spans.append(entry)
+    trace.open.append(entry)
+    try:
+        yield entry
+    except BaseException:
+        entry['status'] = 'error'
+        raise
+    finally:
+        entry['duration_ms'] = round(trace.elapsed_ms() - entry['start_ms'], 1)
+        trace.open.pop()
+
+
+def finish(job_id, status):
+    """End the current trace and hand it to the shell."""
+    trace = getattr(_local, 'trace', None)
+    _local.trace = None
+    if trace is None or trace.job_id != job_id or not control_channel.is_connected():
+        return
+    control_channel.notify('trace.ready', {
+        'job_id': job_id,
+        'started_at': trace.started_at,
+        'duration_ms': trace.elapsed_ms(),
+        'status': status,
+        'spans': trace.spans,
+    })
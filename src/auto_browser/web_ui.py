@@ -3,11 +3,22 @@ Web UI for browser automation with ElevenLabs voice interface.
 Serves the widget and handles client tool calls.
 """
 
-from flask import Flask, render_template_string, request, jsonify
+from flask import Flask, request, jsonify, send_from_directory
 from flask_cors import CORS
+import json
 import os
+import re
 import threading
+import time
+import uuid
 from datetime import datetime
+from pathlib import Path
+from types import SimpleNamespace
+
+from . import (
+    SAFE_MODE, captcha, control_channel, debugger, failure_capture, har, live_view, politeness, recipe_check, recorder,
+    run_trace,
+)
 
 app = Flask(__name__)
 CORS(app)  # Enable CORS for client tool calls
@@ -25,6 +36,12 @@ class AutomationServer:
         self.starting_page = None
         self.headless = False
         self.verbose = os.getenv('VERBOSE', '').lower() in ('true', '1', 'yes')
+        self.current_url = None
+        self.last_prompt = None
+        # Cookies the shell sent, added on the browser's own thread before the next command
+        self.pending_cookies = []
+        # The browser's cookies as of the last command, for the shell to save
+        self.cookies = []
 
     def configure(self, api_key, starting_page="https://google.com", headless=False):
         """Configure automation settings (doesn't start browser yet)"""
@@ -33,6 +50,12 @@ class AutomationServer:
                 print("\n⚠️  Automation already configured, skipping...")
                 return
 
+            if not headless and gpu_hints.get('recommended_mode') == 'headless':
+                print("[GPU] No display available, starting the browser headless")
+                headless = True
+            if gpu_hints.get('software_rendering'):
+                print("[GPU] Only software rendering is available; WebGL pages may render differently")
+
             self.api_key = api_key
             self.starting_page = starting_page
             self.headless = headless
@@ -78,6 +101,14 @@ class AutomationServer:
             if self.verbose:
                 print(f"[VERBOSE] Starting browser with page: {self.starting_page}")
             self.browser.start(starting_page=self.starting_page)
+            try:
+                politeness.limiter.attach(self.browser.agent.page.context)
+            except Exception as e:
+                print(f"[POLITENESS] Site limits not applied to this browser: {e}")
+            try:
+                har.recorder.attach(self.browser.agent.page.context)
+            except Exception as e:
+                print(f"[HAR] Network capture not available in this browser: {e}")
             self.is_ready = True
 
             print(f"\n{'='*80}")
@@ -112,37 +143,89 @@ class AutomationServer:
 
             raise
 
-    def execute_prompt(self, prompt):
-        """Execute automation prompt - thread-safe with lazy initialization"""
+    def execute_prompt(self, prompt, shown_prompt=None, job_id=None, capture_failure=False, step=None, dry_run=False):
+        """Execute automation prompt - thread-safe with lazy initialization.
+        `shown_prompt` is logged and remembered instead, when the prompt holds secrets.
+        With a `job_id`, captchas in the way are solved through the desktop app. With
+        `capture_failure`, a failure captures the page as `step` of the job. A `dry_run`
+        goes through everything but the browser and completes at once."""
+        shown_prompt = shown_prompt or prompt
         with self.lock:
             if self.verbose:
                 print(f"[VERBOSE] Lock acquired for prompt execution")
                 print(f"[VERBOSE] Browser ready state: {self.is_ready}")
 
+            if dry_run:
+                print(f"\n[AUTOMATION] Dry run: {shown_prompt}")
+                return SimpleNamespace(response=None, parsed_response={'dry_run': True, 'prompt': shown_prompt})
+
             # Initialize browser on first use (lazy)
             if not self.is_ready:
                 if self.verbose:
                     print("[VERBOSE] Browser not ready, initializing...")
-                self._initialize_if_needed()
+                with run_trace.span('Start the browser', 'browser'):
+                    self._initialize_if_needed()
+
+            self._add_pending_cookies()
 
             # Execute automation - this may take a while
-            print(f"\n[AUTOMATION] Executing: {prompt}")
+            print(f"\n[AUTOMATION] Executing: {shown_prompt}")
             if self.verbose:
                 print(f"[VERBOSE] Calling browser.agent.act() with prompt length: {len(prompt)}")
 
             try:
-                self.browser.agent.act(prompt)
+                self.last_prompt = shown_prompt
+                can_ask_user = not self.headless
+                with run_trace.span('Check for a captcha', 'captcha'):
+                    captcha.solve_if_present(self.browser.agent.page, job_id, can_ask_user)
+                try:
+                    with run_trace.span('Act', 'act', prompt=shown_prompt):
+                        result = self.browser.agent.act(prompt)
+                except Exception:
+                    # A captcha that stopped the automation: solve it and try once more
+                    with run_trace.span('Check for a captcha', 'captcha'):
+                        solved = captcha.solve_if_present(self.browser.agent.page, job_id, can_ask_user)
+                    if not solved:
+                        raise
+                    with run_trace.span('Act again', 'act', prompt=shown_prompt):
+                        result = self.browser.agent.act(prompt)
                 if self.verbose:
                     print(f"[VERBOSE] browser.agent.act() completed successfully")
                 print(f"[AUTOMATION] Completed\n")
+                # Read here, on the browser's own thread, for session snapshots
+                try:
+                    self.current_url = self.browser.agent.page.url
+                    self.cookies = self.browser.agent.page.context.cookies()
+                except Exception:
+                    pass
+                return result
             except Exception as e:
                 print(f"\n[AUTOMATION ERROR] Failed to execute: {e}")
+                if capture_failure and job_id and self.browser is not None:
+                    with run_trace.span('Capture the failure', 'capture'):
+                        failure_capture.capture(self.browser.agent.page, job_id, e, step)
                 if self.verbose:
                     print("[VERBOSE] Full error traceback:")
                     import traceback
                     traceback.print_exc()
                 raise
 
+    def add_cookies(self, cookies):
+        """Queue cookies for the browser; Playwright may only be used from its own thread."""
+        self.pending_cookies.extend(c for c in cookies if isinstance(c, dict))
+        print(f"[COOKIES] {len(self.pending_cookies)} cookies waiting for the next command")
+
+    def _add_pending_cookies(self):
+        # Called with the lock held, on the browser's thread
+        cookies, self.pending_cookies = self.pending_cookies, []
+        if not cookies:
+            return
+        try:
+            self.browser.agent.page.context.add_cookies(cookies)
+            print(f"[COOKIES] Added {len(cookies)} cookies to the browser")
+        except Exception as e:
+            print(f"[COOKIES] Failed to add cookies: {e}")
+
     def close_browser(self):
         """Close the browser and clean up resources (callable from API)"""
         with self.lock:
@@ -173,6 +256,24 @@ class AutomationServer:
         """Clean shutdown of browser session (alias for close_browser for backward compatibility)"""
         self.close_browser()
 
+    def snapshot(self):
+        """Session state the desktop shell keeps across restarts. Doesn't take the lock,
+        so it answers even while a command is running."""
+        return {
+            'current_url': self.current_url,
+            'headless': self.headless,
+            'last_prompt': self.last_prompt,
+        }
+
+    def restore(self, state):
+        """Resume a snapshot: the browser reopens where it was when it next starts"""
+        with self.lock:
+            if state.get('current_url') and not self.is_ready:
+                self.starting_page = state['current_url']
+                self.current_url = state['current_url']
+            self.last_prompt = state.get('last_prompt')
+        print(f"[SESSION] Restored session (page: {state.get('current_url') or 'default'})")
+
     def is_busy(self):
         """Check if currently executing a command"""
         return self.lock.locked()
@@ -181,368 +282,87 @@ class AutomationServer:
 # Global automation server instance
 automation_server = AutomationServer()
 
-# HTML template with ElevenLabs widget
-HTML_TEMPLATE = """
-<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Browser Automation - Voice Interface</title>
-    <style>
-        body {
-            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
-            max-width: {% if expanded_ui %}1200px{% else %}600px{% endif %};
-            margin: 0 auto;
-            padding: 20px;
-            background: #f5f5f5;
-        }
 
-        /* Title header styling */
-        .app-title {
-            text-align: center;
-            font-size: 24px;
-            font-weight: 600;
-            color: #333;
-            margin: 15px 0 25px 0;
-            padding: 0;
-        }
+def _load_entitlements():
+    """Premium features the shell's license unlocks. A server started without the shell
+    is not licensed and has everything."""
+    entitlements = {'scheduling': True, 'lan_mode': True}
+    raw = os.getenv('NOVA_ENTITLEMENTS')
+    if raw:
+        try:
+            entitlements = {key: bool(json.loads(raw).get(key)) for key in entitlements}
+        except (ValueError, AttributeError):
+            print(f"[LICENSE] Ignoring malformed NOVA_ENTITLEMENTS: {raw}")
+    return entitlements
 
-        /* Hide ElevenLabs branding footer with gradient overlay */
-        .branding-overlay {
-            position: fixed;
-            bottom: 0;
-            left: 0;
-            right: 0;
-            height: 32px;
-            background: linear-gradient(to bottom, rgba(245,245,245,0) 0%, rgba(245,245,245,0.9) 25%, rgba(245,245,245,1) 50%);
-            z-index: 9999;
-            pointer-events: none;
-        }
 
-        {% if expanded_ui %}
-        .header {
-            background: white;
-            padding: 30px;
-            border-radius: 10px;
-            box-shadow: 0 2px 10px rgba(0,0,0,0.1);
-            margin-bottom: 20px;
-        }
+entitlements = _load_entitlements()
 
-        h1 {
-            margin: 0 0 10px 0;
-            color: #333;
-        }
 
-        .subtitle {
-            color: #666;
-            margin: 0;
-        }
+def _load_feature_flags():
+    """Feature flags from the shell; unknown flags are off."""
+    raw = os.getenv('NOVA_FEATURE_FLAGS')
+    if not raw:
+        return {}
+    try:
+        return {str(name): bool(on) for name, on in json.loads(raw).items()}
+    except (ValueError, AttributeError):
+        print(f"[FLAGS] Ignoring malformed NOVA_FEATURE_FLAGS: {raw}")
+        return {}
 
-        .console {
-            background: #1e1e1e;
-            color: #d4d4d4;
-            padding: 20px;
-            border-radius: 10px;
-            font-family: 'Courier New', monospace;
-            font-size: 14px;
-            max-height: 400px;
-            overflow-y: auto;
-            box-shadow: 0 2px 10px rgba(0,0,0,0.1);
-            margin-bottom: 20px;
-        }
 
-        .log-entry {
-            margin: 5px 0;
-            padding: 5px 0;
-            border-bottom: 1px solid #333;
-        }
+feature_flags = _load_feature_flags()
 
-        .log-time {
-            color: #858585;
-            margin-right: 10px;
-        }
 
-        .log-event {
-            color: #4ec9b0;
-            font-weight: bold;
-        }
+def is_enabled(flag):
+    return feature_flags.get(flag, False)
 
-        .log-data {
-            color: #ce9178;
-            margin-top: 5px;
-            padding-left: 20px;
-            white-space: pre-wrap;
-        }
 
-        .clear-btn {
-            background: #007acc;
-            color: white;
-            border: none;
-            padding: 10px 20px;
-            border-radius: 5px;
-            cursor: pointer;
-            margin-bottom: 20px;
-        }
+def _load_gpu_hints():
+    """What the shell found out about the GPU and display, so the browser is launched in
+    a mode that can actually render here."""
+    raw = os.getenv('NOVA_GPU')
+    if not raw or SAFE_MODE:
+        return {}
+    try:
+        hints = json.loads(raw)
+        return hints if isinstance(hints, dict) else {}
+    except ValueError:
+        print(f"[GPU] Ignoring malformed NOVA_GPU: {raw}")
+        return {}
 
-        .clear-btn:hover {
-            background: #005a9e;
-        }
 
-        .status {
-            background: #2d2d30;
-            color: #cccccc;
-            padding: 10px 20px;
-            border-radius: 5px;
-            margin-bottom: 20px;
-        }
+gpu_hints = _load_gpu_hints()
 
-        .status-indicator {
-            display: inline-block;
-            width: 10px;
-            height: 10px;
-            border-radius: 50%;
-            background: #4ec9b0;
-            margin-right: 10px;
-            animation: pulse 2s infinite;
-        }
+# Whether the user has stepped away, as the shell last reported; heavy work can wait for it
+user_idle = os.getenv('NOVA_USER_IDLE') == '1'
 
-        @keyframes pulse {
-            0%, 100% { opacity: 1; }
-            50% { opacity: 0.5; }
-        }
-        {% endif %}
-    </style>
-</head>
-<body>
-    {% if expanded_ui %}
-    <div class="header">
-        <h1>Browser Automation</h1>
-        <p class="subtitle">Voice-controlled automation interface</p>
-    </div>
-
-    <div class="status">
-        <span class="status-indicator"></span>
-        Connected to automation backend
-    </div>
-
-    <div class="console" id="console">
-        <div class="log-entry">
-            <span class="log-time">Ready</span>
-            <span class="log-event">Waiting for voice commands...</span>
-        </div>
-    </div>
-
-    <button class="clear-btn" onclick="clearConsole()">Clear Console</button>
-    {% endif %}
-
-    {% if not expanded_ui %}
-    <!-- Title for minimal UI mode -->
-    <h1 class="app-title">Web Automation Agent</h1>
-    {% endif %}
-
-    <!-- Widget wrapper to enable branding hiding -->
-    <div class="widget-wrapper">
-        <!-- Voice Conversational AI Widget -->
-        <elevenlabs-convai agent-id="{{ agent_id }}"></elevenlabs-convai>
-    </div>
-
-    <!-- Overlay to hide branding footer -->
-    <div class="branding-overlay"></div>
-
-    <script src="https://unpkg.com/@elevenlabs/convai-widget-embed" async type="text/javascript"></script>
-
-    <script>
-        {% if expanded_ui %}
-        const consoleEl = document.getElementById('console');
-
-        function log(event, data) {
-            const time = new Date().toLocaleTimeString();
-            const entry = document.createElement('div');
-            entry.className = 'log-entry';
-
-            let html = `<span class="log-time">[${time}]</span><span class="log-event">${event}</span>`;
-
-            if (data) {
-                const dataStr = typeof data === 'object' ? JSON.stringify(data, null, 2) : data;
-                html += `<div class="log-data">${dataStr}</div>`;
-            }
-
-            entry.innerHTML = html;
-            consoleEl.appendChild(entry);
-            consoleEl.scrollTop = consoleEl.scrollHeight;
-        }
 
-        function clearConsole() {
-            consoleEl.innerHTML = '<div class="log-entry"><span class="log-time">Ready</span><span class="log-event">Waiting for voice commands...</span></div>';
-        }
-        {% else %}
-        // Minimal UI (default) - stub functions for widget compatibility
-        function log(event, data) {
-            console.log('[LOG]', event, data);
-        }
-        {% endif %}
-
-        // Register client tools when DOM is ready
-        document.addEventListener('DOMContentLoaded', () => {
-            console.log('[WIDGET] DOM loaded, searching for widget element...');
-            const widget = document.querySelector('elevenlabs-convai');
-
-            if (widget) {
-                console.log('[WIDGET] Widget element found:', widget);
-                log('Widget Element Found', { agentId: '{{ agent_id }}' });
-
-                // Listen to all widget events for debugging
-                widget.addEventListener('elevenlabs-convai:status', (event) => {
-                    console.log('[WIDGET STATUS]', event.detail);
-                    {% if expanded_ui %}
-                    log('Widget Status Change', event.detail);
-                    {% endif %}
-                });
-
-                widget.addEventListener('elevenlabs-convai:error', (event) => {
-                    console.error('[WIDGET ERROR]', event.detail);
-                    {% if expanded_ui %}
-                    log('Widget Error', event.detail);
-                    {% endif %}
-                });
-
-                widget.addEventListener('elevenlabs-convai:message', (event) => {
-                    console.log('[WIDGET MESSAGE]', event.detail);
-                    {% if expanded_ui %}
-                    log('Widget Message', { message: event.detail });
-                    {% endif %}
-                });
-
-                // Register client tools when the widget initiates a call
-                widget.addEventListener('elevenlabs-convai:call', (event) => {
-                    console.log('[WIDGET] Call initiated, registering client tools...');
-                    {% if expanded_ui %}
-                    log('Registering Client Tools', null);
-                    {% endif %}
-
-                    // Register the send_prompt_to_automation tool
-                    event.detail.config.clientTools = {
-                        send_prompt_to_automation: async ({ prompt }) => {
-                            {% if expanded_ui %}
-                            log('Tool Called: send_prompt_to_automation', { prompt });
-                            {% endif %}
-
-                            try {
-                                // Send prompt to Python backend (use current origin for dynamic port support)
-                                const apiUrl = `${window.location.origin}/api/execute_automation`;
-                                const response = await fetch(apiUrl, {
-                                    method: 'POST',
-                                    headers: {
-                                        'Content-Type': 'application/json',
-                                    },
-                                    body: JSON.stringify({ prompt })
-                                });
-
-                                const result = await response.json();
-                                {% if expanded_ui %}
-                                log('Backend Response', result);
-                                {% endif %}
-
-                                // Log what we're returning to voice agent
-                                console.log('[VOICE] Returning to agent:', result.message);
-
-                                return result.message || 'Command received';
-                            } catch (error) {
-                                {% if expanded_ui %}
-                                log('Error', { message: error.message });
-                                {% endif %}
-                                return `Error: ${error.message}`;
-                            }
-                        }
-                    };
-
-                    {% if expanded_ui %}
-                    log('Client Tools Registered', {
-                        tools: ['send_prompt_to_automation']
-                    });
-                    {% endif %}
-                });
-
-                // Listen for call end event to close browser
-                widget.addEventListener('elevenlabs-convai:status', async (event) => {
-                    const status = event.detail?.status;
-                    console.log('[WIDGET] Status update:', status);
-
-                    // When call ends (widget goes back to idle), close the browser
-                    if (status === 'idle' || status === 'disconnected') {
-                        console.log('[WIDGET] Call ended, closing browser...');
-                        {% if expanded_ui %}
-                        log('Call Ended - Closing Browser', { status });
-                        {% endif %}
-
-                        try {
-                            const apiUrl = `${window.location.origin}/api/close_browser`;
-                            const response = await fetch(apiUrl, {
-                                method: 'POST',
-                                headers: {
-                                    'Content-Type': 'application/json',
-                                }
-                            });
-
-                            const result = await response.json();
-                            console.log('[BROWSER] Close response:', result);
-                            {% if expanded_ui %}
-                            log('Browser Close Result', result);
-                            {% endif %}
-                        } catch (error) {
-                            console.error('[BROWSER] Error closing browser:', error);
-                            {% if expanded_ui %}
-                            log('Browser Close Error', { message: error.message });
-                            {% endif %}
-                        }
-                    }
-                });
-            } else {
-                console.error('[WIDGET] Widget element NOT FOUND in DOM!');
-                {% if expanded_ui %}
-                log('Widget Error', { error: 'Widget element not found' });
-                {% endif %}
-            }
-        });
-
-        // Check if widget script loaded
-        window.addEventListener('load', () => {
-            console.log('[WIDGET] Page fully loaded');
-            const scripts = document.querySelectorAll('script');
-            const widgetScript = Array.from(scripts).find(s => s.src.includes('convai-widget-embed'));
-            if (widgetScript) {
-                console.log('[WIDGET] Widget script found:', widgetScript.src);
-            } else {
-                console.error('[WIDGET] Widget script NOT FOUND!');
-            }
-        });
-    </script>
-</body>
-</html>
-"""
+def _load_max_concurrency():
+    """Browsers the shell allows at once on this machine; None when run without it."""
+    if SAFE_MODE:
+        return 1
+    try:
+        return max(1, int(os.environ['NOVA_MAX_CONCURRENCY']))
+    except (KeyError, ValueError):
+        return None
 
 
-@app.route('/')
-def index():
-    """Serve the main UI with ElevenLabs widget."""
-    print(f"\n[DEBUG] / route hit, is_configured: {automation_server.is_configured}")
+max_concurrency = _load_max_concurrency()
 
-    # Check if automation is configured
-    if not automation_server.is_configured:
-        # Redirect to setup if not configured
-        print("[DEBUG] Redirecting to /setup")
-        from flask import redirect, url_for
-        return redirect(url_for('setup'))
+# Free space on the shell's data volume is critically low; skip optional files like screenshots
+disk_critical = os.getenv('NOVA_DISK_CRITICAL') == '1'
 
-    print("[DEBUG] Serving main UI")
+# The control UI ships with the desktop app as bundled assets; when the server runs on its
+# own (e.g. `npm run server` from a checkout) it serves the same pages from there.
+UI_DIR = Path(__file__).resolve().parents[2] / 'ui'
 
-    agent_id = os.getenv('ELEVENLABS_AGENT_ID', 'agent_0901kckpgzzgecfb8dsh31a3h45w')
-    expanded_ui = getattr(app, 'expanded_ui', False)
-    print(f"\n[UI] Serving page with ElevenLabs Agent ID: {agent_id}")
-    print(f"[UI] Expanded UI mode: {expanded_ui}\n")
 
-    response = app.make_response(render_template_string(HTML_TEMPLATE, agent_id=agent_id, expanded_ui=expanded_ui))
+def _serve_ui_page(name):
+    """Serve one of the bundled UI pages, for use in a regular browser."""
+    if not (UI_DIR / name).is_file():
+        return 'The UI is bundled with the desktop app and not available here.', 404
+    response = send_from_directory(UI_DIR, name)
 
     # Add permissive CSP to allow WebSocket connections, AudioWorklets, and external scripts
     # AudioWorklets require blob: URLs and worker-src directive
@@ -556,21 +376,96 @@ def index():
         "media-src * data: blob:; "
         "font-src * data: blob:;"
     )
-
     return response
 
 
+@app.route('/')
+def index():
+    """Serve the main UI with ElevenLabs widget when running without the desktop app."""
+    if not automation_server.is_configured:
+        from flask import redirect, url_for
+        return redirect(url_for('setup'))
+    return _serve_ui_page('app.html')
+
+
+@app.route('/api/ui_config')
+def ui_config():
+    """What the control UI needs to render: the voice agent and the layout."""
+    return jsonify({
+        'configured': automation_server.is_configured,
+        'agent_id': os.getenv('ELEVENLABS_AGENT_ID', 'agent_0901kckpgzzgecfb8dsh31a3h45w'),
+        'expanded_ui': getattr(app, 'expanded_ui', False),
+    })
+
+
+@app.route('/api/health')
+def health():
+    """
+    Liveness and identity check used by the desktop shell.
+    Echoes back the instance ID the shell passed in so it can confirm it is
+    talking to the process it spawned.
+    """
+    from . import __version__
+    return jsonify({
+        'status': 'ok',
+        'version': __version__,
+        'instance_id': os.getenv('NOVA_INSTANCE_ID'),
+        'pid': os.getpid(),
+        'configured': automation_server.is_configured,
+        'entitlements': entitlements,
+        'feature_flags': feature_flags,
+        'gpu': gpu_hints,
+        'user_idle': user_idle,
+        'max_concurrency': max_concurrency,
+        'disk_critical': disk_critical,
+        'safe_mode': SAFE_MODE,
+        'domain_stats': politeness.limiter.stats(),
+    })
+
+
+# `${name}` placeholders, or `$${name}` for a literal `${name}`; names as the shell allows them
+PLACEHOLDER = re.compile(r'(\$?)\$\{\s*([A-Za-z0-9_.-]+)\s*\}')
+
+
+def fill_credentials(prompt, tokens, job_id):
+    """
+    Replace the secret placeholders the shell left in a job's prompt with their values,
+    trading each one-time token for its secret over the control channel. The result must
+    never be logged or stored.
+    """
+    values = {}
+
+    def replace(match):
+        escape, name = match.groups()
+        if escape:
+            return match.group(0)[1:]
+        if name not in tokens:
+            return match.group(0)
+        if name not in values:
+            try:
+                reply = control_channel.call('credential.redeem', {'token': tokens[name], 'job_id': job_id})
+            except RuntimeError as e:
+                # Not a RuntimeError, which would be reported as the browser not being ready
+                raise ValueError(f"Credential '{name}' is not available: {e}") from e
+            values[name] = reply['value']
+        return values[name]
+
+    return PLACEHOLDER.sub(replace, prompt)
+
+
 @app.route('/api/execute_automation', methods=['POST'])
 def execute_automation():
     """
     Endpoint called by the ElevenLabs client tool.
     Receives the automation prompt and executes it via Nova Act.
     """
+    job = None
     try:
         data = request.get_json()
         if automation_server.verbose:
             print(f"[VERBOSE] /api/execute_automation called")
-            print(f"[VERBOSE] Request data: {data}")
+            shown = {k: v for k, v in (data or {}).items() if k != 'credentials'}
+            print(f"[VERBOSE] Request data: {shown}")
             print(f"[VERBOSE] Request headers: {dict(request.headers)}")
 
         if data is None:
@@ -587,6 +482,18 @@ def execute_automation():
                 'message': 'No prompt provided'
             }), 400
 
+        job = {
+            'job_id': data.get('job_id') or str(uuid.uuid4()),
+            'prompt': prompt,
+            'schedule_id': data.get('schedule_id'),
+            'recipe_id': data.get('recipe_id'),
+        }
+        started_at = time.monotonic()
+        control_channel.notify('job.event', dict(job, kind='started'))
+        run_trace.start(job['job_id'])
+        if control_channel.is_connected():
+            har.recorder.start(job['job_id'])
+
         # Log receipt
         timestamp = datetime.now().strftime('%Y-%m-%d %H:%M:%S')
         print(f"\n{'='*80}")
@@ -599,9 +506,30 @@ def execute_automation():
             print(f"[VERBOSE] Browser ready: {automation_server.is_ready}")
         print(f"{'='*80}\n")
 
+        # Secrets are fetched only now, and only ever held in memory
+        credentials = data.get('credentials') or {}
+        filled = prompt
+        if credentials:
+            with run_trace.span('Fetch credentials', 'credentials', count=len(credentials)):
+                filled = fill_credentials(prompt, credentials, job['job_id'])
+
         # Execute automation via Nova Act
         print("Executing automation...")
-        automation_server.execute_prompt(prompt)
+        breakpoints = data.get('breakpoints') or []
+        capture_failures = bool(data.get('capture_failures'))
+        # A job without the browser, e.g. the shell's self-test checking that jobs run
+        dry_run = bool(data.get('dry_run'))
+        if breakpoints and not dry_run:
+            result = _execute_steps(job, filled, breakpoints, capture_failures)
+        else:
+            result = automation_server.execute_prompt(
+                filled, shown_prompt=prompt, job_id=job['job_id'], capture_failure=capture_failures, dry_run=dry_run,
+            )
+
+        # Structured output, when the automation extracted any
+        output = getattr(result, 'parsed_response', None)
+        if output is None:
+            output = getattr(result, 'response', None)
 
         # Format detailed response
         response_message = (
@@ -615,11 +543,14 @@ def execute_automation():
         print(response_message)
         print(f"{'='*80}\n")
 
+        _report_finished(job, started_at, 'success', response_message, output)
+
         return jsonify({
             'status': 'success',
             'message': response_message,
             'timestamp': timestamp,
-            'prompt': prompt
+            'prompt': prompt,
+            'output': output
         })
 
     except RuntimeError as e:
@@ -629,6 +560,9 @@ def execute_automation():
         print(f"ERROR: {error_msg}")
         print(f"{'='*80}\n")
 
+        if job:
+            _report_finished(job, started_at, 'error', error_msg)
+
         return jsonify({
             'status': 'error',
             'message': error_msg,
@@ -645,6 +579,9 @@ def execute_automation():
         import traceback
         traceback.print_exc()
 
+        if job:
+            _report_finished(job, started_at, 'error', error_msg)
+
         return jsonify({
             'status': 'error',
             'message': error_msg,
@@ -652,6 +589,239 @@ def execute_automation():
         }), 500
 
 
+def _execute_steps(job, filled, breakpoints, capture_failures=False):
+    """Run a job one step at a time so it can stop at breakpoints; see debugger. Returns
+    the last step's result."""
+    steps = debugger.steps(filled)
+    # Secrets are filled into `steps`; logs and events get the steps as written
+    shown = debugger.steps(job['prompt'])
+    if len(shown) != len(steps):
+        shown = [f'step {number}' for number in range(1, len(steps) + 1)]
+    session = debugger.begin(job, breakpoints)
+    result = None
+    try:
+        for number, (step, shown_step) in enumerate(zip(steps, shown), 1):
+            with run_trace.span(f'Step {number}', 'step', text=shown_step):
+                session.before_step(number, len(steps), shown_step)
+                result = automation_server.execute_prompt(
+                    step, shown_prompt=shown_step, job_id=job['job_id'], capture_failure=capture_failures, step=number,
+                )
+    finally:
+        debugger.end(job['job_id'])
+    return result
+
+
+def _report_finished(job, started_at, status, message, output=None):
+    control_channel.notify('job.event', dict(
+        job,
+        kind='finished',
+        status=status,
+        message=message,
+        duration_ms=int((time.monotonic() - started_at) * 1000),
+        output=output,
+    ))
+    run_trace.finish(job['job_id'], status)
+    report_har(job['job_id'])
+
+
+def request_download(url, file_name=None, job_id=None):
+    """Hand a file an automation produced or found to the desktop shell, which saves it
+    to the user's download folder and shows progress. Returns False without a shell."""
+    if not control_channel.is_connected():
+        return False
+    control_channel.notify('download.request', {'url': url, 'file_name': file_name, 'job_id': job_id})
+    return True
+
+
+def report_recording(job_id, path):
+    """Hand a finished session recording to the desktop shell, which keeps it with the
+    run's artifacts for playback. The shell takes ownership of the file. Returns False
+    without a shell."""
+    if not control_channel.is_connected():
+        return False
+    control_channel.notify('recording.ready', {'job_id': job_id, 'path': os.path.abspath(path)})
+    return True
+
+
+def report_har(job_id):
+    """Hand the job's network capture to the desktop shell, which keeps it with the run's
+    artifacts. The shell takes ownership of the file."""
+    path = har.recorder.stop()
+    if path:
+        control_channel.notify('har.ready', {'job_id': job_id, 'path': path})
+
+
+def control_health(params):
+    """Control channel handshake; mirrors /api/health."""
+    from . import __version__
+    return {
+        'protocol_version': control_channel.PROTOCOL_VERSION,
+        'status': 'ok',
+        'version': __version__,
+        'instance_id': os.getenv('NOVA_INSTANCE_ID'),
+        'pid': os.getpid(),
+        'configured': automation_server.is_configured,
+    }
+
+
+def control_shutdown(params):
+    """Close the browser, then exit once the reply has gone out."""
+    def stop():
+        automation_server.shutdown()
+        os._exit(0)
+    threading.Timer(0.2, stop).start()
+    return {}
+
+
+def control_config_push(params):
+    global user_idle, max_concurrency, disk_critical
+    if 'verbose' in params:
+        automation_server.verbose = bool(params['verbose'])
+        print(f"[CONTROL] Verbose logging {'enabled' if automation_server.verbose else 'disabled'}")
+    if isinstance(params.get('entitlements'), dict):
+        for key in entitlements:
+            entitlements[key] = bool(params['entitlements'].get(key))
+        print(f"[CONTROL] Entitlements: {entitlements}")
+    if isinstance(params.get('feature_flags'), dict):
+        feature_flags.clear()
+        feature_flags.update({str(name): bool(on) for name, on in params['feature_flags'].items()})
+        print(f"[CONTROL] Feature flags: {feature_flags}")
+    if 'browser_bounds' in params:
+        # Read when the browser next starts
+        os.environ['NOVA_BROWSER_BOUNDS'] = str(params['browser_bounds'] or '')
+        print(f"[CONTROL] Browser bounds: {os.environ['NOVA_BROWSER_BOUNDS'] or 'default'}")
+    if 'user_idle' in params:
+        user_idle = bool(params['user_idle'])
+        print(f"[CONTROL] User is {'idle' if user_idle else 'active'}")
+    if 'max_concurrency' in params and not SAFE_MODE:
+        max_concurrency = max(1, int(params['max_concurrency']))
+        print(f"[CONTROL] Max concurrent browsers: {max_concurrency}")
+    if 'disk_critical' in params:
+        disk_critical = bool(params['disk_critical'])
+        print(f"[CONTROL] Disk space {'critically low' if disk_critical else 'ok'}")
+    if 'domain_limits' in params:
+        politeness.limiter.configure(params['domain_limits'])
+    if 'har_capture' in params and not SAFE_MODE:
+        har.recorder.enabled = bool(params['har_capture'])
+        print(f"[CONTROL] Network capture {'enabled' if har.recorder.enabled else 'disabled'}")
+    return {}
+
+
+def control_log_level(params):
+    import logging
+    level = str(params.get('level', '')).upper()
+    if level not in ('DEBUG', 'INFO', 'WARNING', 'ERROR'):
+        raise ValueError(f"Unknown log level: {params.get('level')}")
+    logging.getLogger().setLevel(level)
+    automation_server.verbose = level == 'DEBUG'
+    print(f"[CONTROL] Log level set to {level}")
+    return {}
+
+
+def control_session_snapshot(params):
+    return automation_server.snapshot()
+
+
+def control_session_restore(params):
+    automation_server.restore(params)
+    return {}
+
+
+def control_recipe_validate(params):
+    return {'diagnostics': recipe_check.validate(str(params.get('prompt', '')))}
+
+
+def control_cookies_add(params):
+    automation_server.add_cookies(params.get('cookies') or [])
+    return {}
+
+
+def control_cookies_get(params):
+    return {'cookies': automation_server.cookies}
+
+
+def control_domains_stats(params):
+    return {'domains': politeness.limiter.stats()}
+
+
+def control_live_start(params):
+    if not automation_server.is_ready:
+        raise RuntimeError('The browser is not running')
+    live_view.viewer.start(bool(params.get('control')))
+    return {}
+
+
+def control_live_stop(params):
+    live_view.viewer.stop()
+    return {}
+
+
+def control_live_input(params):
+    live_view.viewer.send_input(params.get('event') or {})
+    return {}
+
+
+def control_job_pause(params):
+    debugger.session(str(params.get('job_id'))).pause()
+    return {}
+
+
+def control_job_step(params):
+    debugger.session(str(params.get('job_id'))).resume(single_step=True)
+    return {}
+
+
+def control_job_resume(params):
+    debugger.session(str(params.get('job_id'))).resume()
+    return {}
+
+
+def control_job_breakpoints(params):
+    debugger.set_breakpoints(params.get('recipe_id'), params.get('steps') or [])
+    return {}
+
+
+def control_record_start(params):
+    url = str(params.get('url') or '')
+    if not url.startswith(('http://', 'https://')):
+        raise ValueError(f'Recording needs a web page to start on, not {url!r}')
+    recorder.recorder.start(url)
+    return {}
+
+
+def control_record_pause(params):
+    recorder.recorder.pause(bool(params.get('paused')))
+    return {}
+
+
+def control_record_stop(params):
+    return {'actions': recorder.recorder.stop()}
+
+
+CONTROL_HANDLERS = {
+    'health': control_health,
+    'shutdown': control_shutdown,
+    'config.push': control_config_push,
+    'log.level': control_log_level,
+    'session.snapshot': control_session_snapshot,
+    'session.restore': control_session_restore,
+    'recipe.validate': control_recipe_validate,
+    'domains.stats': control_domains_stats,
+    'cookies.add': control_cookies_add,
+    'cookies.get': control_cookies_get,
+    'live.start': control_live_start,
+    'live.stop': control_live_stop,
+    'live.input': control_live_input,
+    'job.pause': control_job_pause,
+    'job.step': control_job_step,
+    'job.resume': control_job_resume,
+    'job.breakpoints': control_job_breakpoints,
+    'record.start': control_record_start,
+    'record.pause': control_record_pause,
+    'record.stop': control_record_stop,
+}
+
+
 @app.route('/api/close_browser', methods=['POST'])
 def close_browser_endpoint():
     """
@@ -712,161 +882,7 @@ def close_browser_endpoint():
 @app.route('/setup')
 def setup():
     """Show API key setup page"""
-    SETUP_TEMPLATE = """
-<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Setup - Browser Automation</title>
-    <style>
-        body {
-            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
-            max-width: 500px;
-            margin: 50px auto;
-            padding: 20px;
-            background: #f5f5f5;
-        }
-        .setup-card {
-            background: white;
-            padding: 40px;
-            border-radius: 10px;
-            box-shadow: 0 2px 10px rgba(0,0,0,0.1);
-        }
-        h1 {
-            margin: 0 0 10px 0;
-            color: #333;
-            font-size: 24px;
-        }
-        .subtitle {
-            color: #666;
-            margin: 0 0 30px 0;
-            font-size: 14px;
-        }
-        label {
-            display: block;
-            margin: 20px 0 8px 0;
-            color: #333;
-            font-weight: 500;
-        }
-        input {
-            width: 100%;
-            padding: 12px;
-            border: 1px solid #ddd;
-            border-radius: 5px;
-            font-size: 14px;
-            box-sizing: border-box;
-        }
-        input:focus {
-            outline: none;
-            border-color: #007acc;
-        }
-        .help-text {
-            font-size: 12px;
-            color: #666;
-            margin-top: 5px;
-        }
-        button {
-            width: 100%;
-            padding: 12px;
-            background: #007acc;
-            color: white;
-            border: none;
-            border-radius: 5px;
-            font-size: 16px;
-            font-weight: 500;
-            cursor: pointer;
-            margin-top: 30px;
-        }
-        button:hover {
-            background: #005a9e;
-        }
-        button:disabled {
-            background: #ccc;
-            cursor: not-allowed;
-        }
-        .error {
-            color: #d32f2f;
-            font-size: 14px;
-            margin-top: 10px;
-            display: none;
-        }
-        .success {
-            color: #388e3c;
-            font-size: 14px;
-            margin-top: 10px;
-            display: none;
-        }
-    </style>
-</head>
-<body>
-    <div class="setup-card">
-        <h1>Welcome to Browser Automation</h1>
-        <p class="subtitle">Configure your API keys to get started</p>
-
-        <form id="setupForm">
-            <label for="apiKey">Main API Key *</label>
-            <input type="password" id="apiKey" name="apiKey" required placeholder="Enter your main API key">
-            <div class="help-text">Required for browser automation</div>
-
-            <label for="agentId">Voice Agent ID (Optional)</label>
-            <input type="text" id="agentId" name="agentId" placeholder="agent_xxxxxxxxxxxxxxxxx">
-            <div class="help-text">Optional: For voice control features</div>
-
-            <button type="submit" id="submitBtn">Save and Continue</button>
-
-            <div class="error" id="error"></div>
-            <div class="success" id="success"></div>
-        </form>
-    </div>
-
-    <script>
-        document.getElementById('setupForm').addEventListener('submit', async (e) => {
-            e.preventDefault();
-
-            const submitBtn = document.getElementById('submitBtn');
-            const errorDiv = document.getElementById('error');
-            const successDiv = document.getElementById('success');
-
-            submitBtn.disabled = true;
-            submitBtn.textContent = 'Saving...';
-            errorDiv.style.display = 'none';
-            successDiv.style.display = 'none';
-
-            try {
-                const response = await fetch('/api/save_config', {
-                    method: 'POST',
-                    headers: { 'Content-Type': 'application/json' },
-                    body: JSON.stringify({
-                        api_key: document.getElementById('apiKey').value,
-                        agent_id: document.getElementById('agentId').value
-                    })
-                });
-
-                const result = await response.json();
-
-                if (response.ok) {
-                    successDiv.textContent = 'Configuration saved! Redirecting...';
-                    successDiv.style.display = 'block';
-                    setTimeout(() => window.location.href = '/', 1000);
-                } else {
-                    errorDiv.textContent = result.message || 'Failed to save configuration';
-                    errorDiv.style.display = 'block';
-                    submitBtn.disabled = false;
-                    submitBtn.textContent = 'Save and Continue';
-                }
-            } catch (error) {
-                errorDiv.textContent = 'Error: ' + error.message;
-                errorDiv.style.display = 'block';
-                submitBtn.disabled = false;
-                submitBtn.textContent = 'Save and Continue';
-            }
-        });
-    </script>
-</body>
-</html>
-    """
-    return render_template_string(SETUP_TEMPLATE)
+    return _serve_ui_page('setup.html')
 
 
 @app.route('/api/save_config', methods=['POST'])